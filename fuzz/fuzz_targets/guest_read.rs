@@ -0,0 +1,92 @@
+//! throws arbitrary module memories at the reads in
+//! `src/runtime/wasm/memory.rs`
+//!
+//! the first 12 bytes pick where to read and how much, the rest is the
+//! module's memory. every read has to either fail or give back exactly the
+//! bytes that are there, without panicking
+
+#![no_main]
+
+#[path = "../../src/runtime/wasm/memory.rs"]
+#[allow(dead_code)]
+mod memory;
+
+use libfuzzer_sys::fuzz_target;
+use memory::{FromBytes, GuestReadError};
+
+/// shaped like the structs the host reads, with padding after `tag`
+#[repr(C)]
+#[derive(Debug)]
+struct Raw {
+    tag: u8,
+    ptr: u32,
+    len: u32,
+    size: f32,
+    value: u64,
+}
+
+unsafe impl FromBytes for Raw {}
+
+fuzz_target!(|input: &[u8]| {
+    let Some((header, memory)) = input.split_first_chunk::<12>() else {
+        return;
+    };
+
+    let word = |at: usize| u32::from_le_bytes(header[at..at + 4].try_into().unwrap());
+    let ptr = word(0);
+    let index = word(4);
+    let len = word(8);
+
+    // what's really there, `None` when it's out of bounds
+    let expected = |offset: usize, len: usize| -> Option<&[u8]> {
+        memory.get(offset..offset.checked_add(len)?)
+    };
+
+    let bytes = memory::guest_bytes(memory, ptr as usize, len as usize);
+    assert_eq!(bytes.ok(), expected(ptr as usize, len as usize));
+
+    match memory::guest_str(memory, ptr as usize, len as usize) {
+        Ok(string) => assert_eq!(
+            Some(string.as_bytes()),
+            expected(ptr as usize, len as usize)
+        ),
+        Err(GuestReadError::NotUtf8 { .. }) => {
+            let bytes = expected(ptr as usize, len as usize).unwrap();
+            assert!(std::str::from_utf8(bytes).is_err());
+        }
+        Err(GuestReadError::OutOfBounds { .. }) => {
+            assert!(expected(ptr as usize, len as usize).is_none());
+        }
+    }
+
+    match memory::guest_read::<Raw>(memory, ptr as usize) {
+        Ok(raw) => {
+            let bytes = expected(ptr as usize, size_of::<Raw>()).unwrap();
+            assert_eq!(raw.tag, bytes[0]);
+            assert_eq!(raw.ptr.to_ne_bytes(), bytes[4..8]);
+            assert_eq!(raw.len.to_ne_bytes(), bytes[8..12]);
+            assert_eq!(raw.size.to_bits().to_ne_bytes(), bytes[12..16]);
+            assert_eq!(raw.value.to_ne_bytes(), bytes[16..24]);
+        }
+        Err(_) => assert!(expected(ptr as usize, size_of::<Raw>()).is_none()),
+    }
+
+    let offset = (ptr as usize).checked_add(index as usize * 4);
+    match memory::guest_read_at::<[u8; 4]>(memory, ptr, index) {
+        Ok(word) => assert_eq!(
+            Some(&word[..]),
+            offset.and_then(|offset| expected(offset, 4))
+        ),
+        Err(_) => assert!(offset.and_then(|offset| expected(offset, 4)).is_none()),
+    }
+
+    match memory::guest_read_slice::<[u8; 4]>(memory, ptr, len) {
+        Ok(words) => {
+            assert_eq!(
+                Some(words.concat().as_slice()),
+                expected(ptr as usize, len as usize * 4)
+            )
+        }
+        Err(_) => assert!(expected(ptr as usize, len as usize * 4).is_none()),
+    }
+});
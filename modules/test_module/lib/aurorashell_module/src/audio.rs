@@ -0,0 +1,634 @@
+//! audio devices as the shell last saw them, for modules with a `PulseAudio`
+//! register
+//!
+//! `summary()` is cheap and enough to show the default sink and source. the
+//! name, description and profiles of a device are only read when asked for,
+//! so keep what `sink()`, `source()` or `card()` gave and read it again when
+//! the summary's `revision` changes
+//!
+//! example:
+//! ```
+//! let summary = audio::summary()?;
+//! let name = summary.default_sink.and_then(audio::sink).map(|sink| sink.description);
+//! ```
+
+use crate::bytes::{read_f32, read_u16, read_u32, string, take};
+
+unsafe extern "C" {
+    /// host function to get the size of the summary in bytes, 0 if the shell
+    /// hasn't heard from the audio service yet
+    fn audio_summary_size() -> u32;
+    /// host function to copy the summary to `ptr`, returns how many bytes
+    /// were written or 0 if it didn't fit in `len`
+    fn read_audio_summary(ptr: u32, len: u32) -> u32;
+    /// host function to get the size of a device in bytes, 0 if there's none
+    /// at `index`
+    fn audio_detail_size(kind: u32, index: u32) -> u32;
+    /// host function to copy a device to `ptr`, returns how many bytes were
+    /// written or 0 if it didn't fit in `len`
+    fn read_audio_detail(kind: u32, index: u32, ptr: u32, len: u32) -> u32;
+    /// host function to ask the audio service for something, with the text
+    /// at `ptr` for the action. returns 0 if it was sent
+    fn audio_request(action: u32, ptr: u32, len: u32) -> u32;
+}
+
+/// written by the host instead of an index when there's no device
+const NO_INDEX: u16 = u16::MAX;
+
+/// the name of the sink `combine_sinks` makes, to find it with `sink()`
+pub const COMBINED_SINK_NAME: &str = "aurorashell_combined";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestError {
+    /// the shell doesn't know the action or can't take what was given to it,
+    /// like a sink name with spaces
+    Unsupported,
+    /// what was given to the action is too long
+    TooLong,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub sinks: u16,
+    pub sources: u16,
+    pub cards: u16,
+    /// the index to give `sink()`
+    pub default_sink: Option<u16>,
+    /// the index to give `source()`
+    pub default_source: Option<u16>,
+    /// false when there's no sound server, show a disabled state
+    pub available: bool,
+    pub default_sink_muted: bool,
+    pub default_source_muted: bool,
+    /// something is recording from a source, like a call, for showing that
+    /// the microphone is in use
+    pub recording: bool,
+    /// whether the noise suppression filter is the default source, `None`
+    /// when there's no filter (like pipewire's `filter-chain` with rnnoise)
+    pub noise_suppression: Option<bool>,
+    /// there's an equalizer to read with `equalizer()`
+    pub equalizer: bool,
+    /// between 0.0 - 100.0 unless the device is over amplified
+    pub default_sink_volume: f32,
+    pub default_source_volume: f32,
+    /// changes with every update from the shell
+    pub revision: u32,
+    /// how many applications are playing, read them with `sink_input()`
+    pub sink_inputs: u16,
+    /// how many applications are recording, read them with
+    /// `source_output()`
+    pub source_outputs: u16,
+}
+
+/// a sink (output) or source (input)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    pub name: String,
+    pub description: String,
+    /// between 0.0 - 100.0 unless the device is over amplified
+    pub volume: f32,
+    pub muted: bool,
+    /// the index to give `card()`
+    pub card: Option<u32>,
+    /// the outputs or inputs the device can switch between, like speakers
+    /// and headphones, empty with older shells
+    pub ports: Vec<Port>,
+    /// the index into `ports`
+    pub active_port: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Port {
+    /// the name to give `set_sink_port` or `set_source_port`
+    pub name: String,
+    pub description: String,
+    /// whether something is plugged into it, ports that can't tell count as
+    /// plugged in
+    pub available: bool,
+}
+
+/// an application playing to a sink or recording from a source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stream {
+    /// the sound server's id of the stream, to give `set_sink_input_volume`
+    /// and the others
+    pub id: u32,
+    pub app_name: String,
+    /// the application's icon, for `Image::icon`
+    pub icon: Option<String>,
+    /// between 0.0 - 100.0 unless the stream is over amplified
+    pub volume: f32,
+    pub muted: bool,
+    /// the index to give `sink()` or `source()`
+    pub device: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub name: String,
+    pub profiles: Vec<Profile>,
+    /// the index into `profiles`
+    pub selected_profile: Option<u16>,
+    /// the ports of all the card's sinks and sources
+    pub ports: Vec<Port>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub description: String,
+}
+
+/// a pipewire `filter-chain` or easyeffects
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equalizer {
+    pub name: String,
+    /// whether `set_eq_band` works, easyeffects' bands can only be changed
+    /// by loading a preset
+    pub settable: bool,
+    pub bands: Vec<EqBand>,
+    /// the names to give `load_eq_preset`
+    pub presets: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EqBand {
+    pub name: String,
+    /// the center frequency in Hz
+    pub frequency: Option<f32>,
+    /// in dB, between -24.0 - 24.0
+    pub gain: f32,
+}
+
+/// what changed, given to modules as `ServiceEvent::Audio` when they're
+/// made with a service event function (see `create_module!`). the devices
+/// are read with `summary()`, `sink()` and the others as usual, they're up to
+/// date by the time the event is handled
+#[derive(Debug, Clone, PartialEq)]
+pub enum AudioEvent {
+    SinksChanged {
+        count: u16,
+    },
+    DefaultSinkChanged {
+        name: Option<String>,
+    },
+    SourcesChanged {
+        count: u16,
+    },
+    DefaultSourceChanged {
+        name: Option<String>,
+    },
+    CardsChanged {
+        count: u16,
+    },
+    SinkProfileChanged {
+        profile: Option<String>,
+    },
+    SourceProfileChanged {
+        profile: Option<String>,
+    },
+    /// something started or stopped recording from a source
+    RecordingChanged {
+        recording: bool,
+    },
+    NoiseSuppressionChanged {
+        available: bool,
+        enabled: bool,
+    },
+    EqualizerChanged {
+        available: bool,
+    },
+    /// an application started or stopped playing, or one of its streams
+    /// changed
+    SinkInputsChanged {
+        count: u16,
+    },
+    /// an application started or stopped recording, or one of its streams
+    /// changed
+    SourceOutputsChanged {
+        count: u16,
+    },
+    /// the default sink or source switched ports, like headphones being
+    /// plugged in
+    ActivePortChanged {
+        sink_port: Option<String>,
+        source_port: Option<String>,
+    },
+    /// the peaks of the default sink and source between 0.0 - 1.0, smoothed
+    /// so they fall back slowly. only sent to modules with an `AudioMeter`
+    /// register
+    LevelsChanged {
+        sink: f32,
+        source: f32,
+    },
+    /// there's no sound server, show a disabled state until `Available`
+    Unavailable {
+        reason: String,
+    },
+    Available,
+}
+
+/// the last summary the shell gave the module, `None` before the audio
+/// service first updates
+pub fn summary() -> Option<Summary> {
+    let size = unsafe { audio_summary_size() };
+    if size == 0 {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = vec![0; size as usize];
+    let written = unsafe { read_audio_summary(bytes.as_mut_ptr() as u32, size) };
+    if written == 0 {
+        return None;
+    }
+    bytes.truncate(written as usize);
+
+    return parse_summary(&bytes);
+}
+
+pub fn sink(index: u16) -> Option<Device> {
+    parse_device(&detail(0, index as u32)?)
+}
+
+pub fn source(index: u16) -> Option<Device> {
+    parse_device(&detail(1, index as u32)?)
+}
+
+/// the index is the sink or source's `card`
+pub fn card(index: u32) -> Option<Card> {
+    parse_card(&detail(2, index)?)
+}
+
+/// the equalizer the shell found, see `Summary::equalizer`
+pub fn equalizer() -> Option<Equalizer> {
+    parse_equalizer(&detail(3, 0)?)
+}
+
+/// an application playing to a sink, for a mixer with a slider for each
+pub fn sink_input(index: u16) -> Option<Stream> {
+    parse_stream(&detail(4, index as u32)?)
+}
+
+/// an application recording from a source
+pub fn source_output(index: u16) -> Option<Stream> {
+    parse_stream(&detail(5, index as u32)?)
+}
+
+/// sets the volume of an application's stream (its `id`) in percent,
+/// between 0.0 - 100.0
+pub fn set_sink_input_volume(id: u32, volume: f32) -> Result<(), RequestError> {
+    request(8, &format!("{id}\n{volume}"))
+}
+
+pub fn set_sink_input_muted(id: u32, muted: bool) -> Result<(), RequestError> {
+    request(9, &format!("{id}\n{}", muted as u8))
+}
+
+/// moves an application's stream (its `id`) to the sink named `sink` (its
+/// `name`)
+pub fn move_sink_input(id: u32, sink: &str) -> Result<(), RequestError> {
+    request(10, &format!("{id}\n{sink}"))
+}
+
+/// switches the sink named `sink` to its port named `port`, like from the
+/// speakers to the headphones
+pub fn set_sink_port(sink: &str, port: &str) -> Result<(), RequestError> {
+    request(11, &format!("{sink}\n{port}"))
+}
+
+/// switches the source named `source` to its port named `port`
+pub fn set_source_port(source: &str, port: &str) -> Result<(), RequestError> {
+    request(12, &format!("{source}\n{port}"))
+}
+
+/// raises the default sink's volume by `delta_percent`, or lowers it when
+/// it's negative, for volume keys. it stays between 0.0 - 100.0
+pub fn adjust_volume(delta_percent: f32) -> Result<(), RequestError> {
+    request(13, &delta_percent.to_string())
+}
+
+/// mutes the default sink, or unmutes it when it's muted
+pub fn toggle_mute() -> Result<(), RequestError> {
+    request(14, "")
+}
+
+/// makes a sink playing to the sinks named in `members` (their `name`) at
+/// once, or to every sink when it's empty, for "play to all outputs". it
+/// shows up in the sinks once the sound server made it and can be made the
+/// default sink like any other. an older one is replaced
+pub fn combine_sinks(members: &[&str]) -> Result<(), RequestError> {
+    request(0, &members.join("\n"))
+}
+
+/// removes the sink made by `combine_sinks`
+pub fn remove_combined_sink() -> Result<(), RequestError> {
+    request(1, "")
+}
+
+/// plays the default source through the default sink so the user can hear
+/// their microphone, `latency_msec` later (200ms when `None`, at most 2s).
+/// keeps going until `stop_mic_test`, a test that's already running is
+/// replaced
+pub fn start_mic_test(latency_msec: Option<u32>) -> Result<(), RequestError> {
+    match latency_msec {
+        Some(latency_msec) => request(2, &latency_msec.to_string()),
+        None => request(2, ""),
+    }
+}
+
+/// stops the test started by `start_mic_test`
+pub fn stop_mic_test() -> Result<(), RequestError> {
+    request(3, "")
+}
+
+/// makes the noise suppression filter the default source, or goes back to
+/// the source from before it. nothing happens without a filter, see
+/// `Summary::noise_suppression`
+pub fn set_noise_suppression(enabled: bool) -> Result<(), RequestError> {
+    match enabled {
+        true => request(4, ""),
+        false => request(5, ""),
+    }
+}
+
+/// sets the gain in dB of the band at `index` in `Equalizer::bands`
+pub fn set_eq_band(index: u16, gain: f32) -> Result<(), RequestError> {
+    request(6, &format!("{index}\n{gain}"))
+}
+
+/// loads one of `Equalizer::presets`
+pub fn load_eq_preset(name: &str) -> Result<(), RequestError> {
+    request(7, name)
+}
+
+fn request(action: u32, text: &str) -> Result<(), RequestError> {
+    match unsafe { audio_request(action, text.as_ptr() as u32, text.len() as u32) } {
+        0 => Ok(()),
+        1 => Err(RequestError::Unsupported),
+        _ => Err(RequestError::TooLong),
+    }
+}
+
+fn detail(kind: u32, index: u32) -> Option<Vec<u8>> {
+    let size = unsafe { audio_detail_size(kind, index) };
+    if size == 0 {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = vec![0; size as usize];
+    let written = unsafe { read_audio_detail(kind, index, bytes.as_mut_ptr() as u32, size) };
+    if written == 0 {
+        return None;
+    }
+    bytes.truncate(written as usize);
+
+    return Some(bytes);
+}
+
+/// reads the summary written by the host, `None` if it's cut short
+///
+/// u16s for the amount of sinks, sources and cards, u16 indexes of the
+/// default sink and source, a u8 of flags and a byte of padding, f32 volumes
+/// of the default sink and source, the u32 revision, then u16s for the
+/// amount of applications playing and recording, which older shells don't
+/// send
+fn parse_summary(bytes: &[u8]) -> Option<Summary> {
+    let mut cursor = 0;
+    let mut next = |len: usize| take(bytes, &mut cursor, len);
+
+    let sinks = u16::from_le_bytes(next(2)?.try_into().ok()?);
+    let sources = u16::from_le_bytes(next(2)?.try_into().ok()?);
+    let cards = u16::from_le_bytes(next(2)?.try_into().ok()?);
+    let default_sink = u16::from_le_bytes(next(2)?.try_into().ok()?);
+    let default_source = u16::from_le_bytes(next(2)?.try_into().ok()?);
+    let [flags, _] = next(2)?.try_into().ok()?;
+    let default_sink_volume = f32::from_le_bytes(next(4)?.try_into().ok()?);
+    let default_source_volume = f32::from_le_bytes(next(4)?.try_into().ok()?);
+    let revision = u32::from_le_bytes(next(4)?.try_into().ok()?);
+    let mut streams = || -> u16 {
+        next(2)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map_or(0, u16::from_le_bytes)
+    };
+    let sink_inputs = streams();
+    let source_outputs = streams();
+
+    return Some(Summary {
+        sinks,
+        sources,
+        cards,
+        default_sink: (default_sink != NO_INDEX).then_some(default_sink),
+        default_source: (default_source != NO_INDEX).then_some(default_source),
+        available: flags & 1 != 0,
+        default_sink_muted: flags & (1 << 1) != 0,
+        default_source_muted: flags & (1 << 2) != 0,
+        recording: flags & (1 << 3) != 0,
+        noise_suppression: (flags & (1 << 4) != 0).then_some(flags & (1 << 5) != 0),
+        equalizer: flags & (1 << 6) != 0,
+        default_sink_volume,
+        default_source_volume,
+        revision,
+        sink_inputs,
+        source_outputs,
+    });
+}
+
+/// the name and description, the f32 volume, a u8 for being muted, the u32
+/// index of the card, then the ports, which older shells don't send
+fn parse_device(bytes: &[u8]) -> Option<Device> {
+    let mut cursor = 0;
+
+    let name = string(bytes, &mut cursor)?;
+    let description = string(bytes, &mut cursor)?;
+    let volume = read_f32(bytes, &mut cursor)?;
+    let muted = take(bytes, &mut cursor, 1)?[0] != 0;
+    let card = read_u32(bytes, &mut cursor)?;
+    let (ports, active_port) = parse_ports(bytes, &mut cursor).unwrap_or_default();
+
+    return Some(Device {
+        name,
+        description,
+        volume,
+        muted,
+        card: (card != u32::MAX).then_some(card),
+        ports,
+        active_port,
+    });
+}
+
+/// the u32 id, the application's name and icon (empty for none), the f32
+/// volume, a u8 for being muted and the u32 index of the sink or source
+fn parse_stream(bytes: &[u8]) -> Option<Stream> {
+    let mut cursor = 0;
+
+    let id = read_u32(bytes, &mut cursor)?;
+    let app_name = string(bytes, &mut cursor)?;
+    let icon = string(bytes, &mut cursor)?;
+    let volume = read_f32(bytes, &mut cursor)?;
+    let muted = take(bytes, &mut cursor, 1)?[0] != 0;
+    let device = read_u32(bytes, &mut cursor)?;
+
+    return Some(Stream {
+        id,
+        app_name,
+        icon: (!icon.is_empty()).then_some(icon),
+        volume,
+        muted,
+        device: u16::try_from(device).ok(),
+    });
+}
+
+/// the name, a u16 amount of profiles, the u16 index of the selected one,
+/// the name and description of each profile, then the ports, which older
+/// shells don't send
+fn parse_card(bytes: &[u8]) -> Option<Card> {
+    let mut cursor = 0;
+
+    let name = string(bytes, &mut cursor)?;
+    let count = read_u16(bytes, &mut cursor)?;
+    let selected = read_u16(bytes, &mut cursor)?;
+
+    let mut profiles = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        profiles.push(Profile {
+            name: string(bytes, &mut cursor)?,
+            description: string(bytes, &mut cursor)?,
+        });
+    }
+
+    let (ports, _) = parse_ports(bytes, &mut cursor).unwrap_or_default();
+
+    return Some(Card {
+        name,
+        profiles,
+        selected_profile: (selected != NO_INDEX).then_some(selected),
+        ports,
+    });
+}
+
+/// a u16 amount of ports, the u16 index of the active one, then the name,
+/// description and a u8 for being plugged in of each port
+fn parse_ports(bytes: &[u8], cursor: &mut usize) -> Option<(Vec<Port>, Option<u16>)> {
+    let count = read_u16(bytes, cursor)?;
+    let active = read_u16(bytes, cursor)?;
+
+    let mut ports = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        ports.push(Port {
+            name: string(bytes, cursor)?,
+            description: string(bytes, cursor)?,
+            available: take(bytes, cursor, 1)?[0] != 0,
+        });
+    }
+
+    return Some((ports, (active != NO_INDEX).then_some(active)));
+}
+
+/// the name, a u8 for the bands being settable, a u16 amount of bands and a
+/// u16 amount of presets, then the name, f32 frequency and f32 gain of each
+/// band and the name of each preset
+fn parse_equalizer(bytes: &[u8]) -> Option<Equalizer> {
+    let mut cursor = 0;
+
+    let name = string(bytes, &mut cursor)?;
+    let settable = take(bytes, &mut cursor, 1)?[0] != 0;
+    let band_count = read_u16(bytes, &mut cursor)?;
+    let preset_count = read_u16(bytes, &mut cursor)?;
+
+    let mut bands = Vec::with_capacity(band_count as usize);
+    for _ in 0..band_count {
+        let name = string(bytes, &mut cursor)?;
+        let frequency = read_f32(bytes, &mut cursor)?;
+        let gain = read_f32(bytes, &mut cursor)?;
+
+        bands.push(EqBand {
+            name,
+            // the host writes 0.0 for a band without a frequency
+            frequency: (frequency != 0.0).then_some(frequency),
+            gain,
+        });
+    }
+
+    let mut presets = Vec::with_capacity(preset_count as usize);
+    for _ in 0..preset_count {
+        presets.push(string(bytes, &mut cursor)?);
+    }
+
+    return Some(Equalizer {
+        name,
+        settable,
+        bands,
+        presets,
+    });
+}
+
+/// reads an event from the shell, `None` for a kind this version doesn't
+/// know
+///
+/// a u8 for the kind of event, then a u16 count for the lists of devices, a
+/// name (empty for none) for the defaults and profiles, u8 flags for the
+/// rest or the reason the service is unavailable
+pub(crate) fn parse_event(bytes: &[u8]) -> Option<AudioEvent> {
+    let mut cursor = 0;
+
+    let kind = take(bytes, &mut cursor, 1)?[0];
+    let mut count = || -> Option<u16> { read_u16(bytes, &mut cursor) };
+
+    let event = match kind {
+        0 => AudioEvent::SinksChanged { count: count()? },
+        2 => AudioEvent::SourcesChanged { count: count()? },
+        4 => AudioEvent::CardsChanged { count: count()? },
+        1 | 3 | 5 | 6 => {
+            let name = string(bytes, &mut cursor)?;
+            let name = (!name.is_empty()).then_some(name);
+
+            match kind {
+                1 => AudioEvent::DefaultSinkChanged { name },
+                3 => AudioEvent::DefaultSourceChanged { name },
+                5 => AudioEvent::SinkProfileChanged { profile: name },
+                _ => AudioEvent::SourceProfileChanged { profile: name },
+            }
+        }
+        7 => AudioEvent::RecordingChanged {
+            recording: take(bytes, &mut cursor, 1)?[0] != 0,
+        },
+        8 => {
+            let [available, enabled] = take(bytes, &mut cursor, 2)?.try_into().ok()?;
+            AudioEvent::NoiseSuppressionChanged {
+                available: available != 0,
+                enabled: enabled != 0,
+            }
+        }
+        9 => AudioEvent::EqualizerChanged {
+            available: take(bytes, &mut cursor, 1)?[0] != 0,
+        },
+        10 => AudioEvent::Unavailable {
+            reason: string(bytes, &mut cursor)?,
+        },
+        11 => AudioEvent::Available,
+        12 => AudioEvent::SinkInputsChanged { count: count()? },
+        13 => AudioEvent::SourceOutputsChanged { count: count()? },
+        14 => {
+            let mut port = || -> Option<Option<String>> {
+                let name = string(bytes, &mut cursor)?;
+                Some((!name.is_empty()).then_some(name))
+            };
+
+            AudioEvent::ActivePortChanged {
+                sink_port: port()?,
+                source_port: port()?,
+            }
+        }
+        15 => {
+            let mut level = || -> Option<f32> { read_f32(bytes, &mut cursor) };
+
+            AudioEvent::LevelsChanged {
+                sink: level()?,
+                source: level()?,
+            }
+        }
+        _ => return None,
+    };
+
+    return Some(event);
+}
@@ -0,0 +1,86 @@
+//! the brightness of the screen's backlight, given to modules with a
+//! `Brightness` register as `ServiceEvent::Brightness` when they're made
+//! with a service event function (see `create_module!`)
+//!
+//! example:
+//! ```
+//! fn service_event(event: &ServiceEvent) -> u32 {
+//!     match event {
+//!         ServiceEvent::Brightness(BrightnessEvent::BrightnessChanged { .. }) => {
+//!             Message::Brightness(event.clone()).into()
+//!         }
+//!         _ => 0,
+//!     }
+//! }
+//! ```
+
+use crate::bytes::{read_u16, read_u32, string, take};
+
+/// what changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrightnessEvent {
+    /// the brightness of a backlight changed, or one was added or removed,
+    /// with every backlight sorted by name
+    BrightnessChanged {
+        backlights: Vec<Backlight>,
+    },
+    /// there's no backlight to control or logind can't be reached, show a
+    /// disabled slider until `Available`
+    Unavailable {
+        reason: String,
+    },
+    Available,
+}
+
+/// a device in `/sys/class/backlight`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backlight {
+    /// the name of the device, like `intel_backlight`
+    pub name: String,
+    /// from 0 to `max_brightness`, in the device's own steps
+    pub brightness: u32,
+    pub max_brightness: u32,
+}
+
+impl Backlight {
+    /// the brightness from 0 to 100
+    pub fn percent(&self) -> u8 {
+        if self.max_brightness == 0 {
+            return 0;
+        }
+
+        return (self.brightness as u64 * 100 / self.max_brightness as u64).min(100) as u8;
+    }
+}
+
+/// reads an event from the shell, `None` for a kind this version doesn't
+/// know
+///
+/// a u8 for the kind of event, then a u16 for the amount of backlights
+/// followed by each one's name, brightness and max brightness, or the reason
+/// the service is unavailable
+pub(crate) fn parse_event(bytes: &[u8]) -> Option<BrightnessEvent> {
+    let mut cursor = 0;
+
+    let event = match take(bytes, &mut cursor, 1)?[0] {
+        0 => {
+            let count = read_u16(bytes, &mut cursor)?;
+            let mut backlights = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                backlights.push(Backlight {
+                    name: string(bytes, &mut cursor)?,
+                    brightness: read_u32(bytes, &mut cursor)?,
+                    max_brightness: read_u32(bytes, &mut cursor)?,
+                });
+            }
+            BrightnessEvent::BrightnessChanged { backlights }
+        }
+        1 => BrightnessEvent::Unavailable {
+            reason: string(bytes, &mut cursor)?,
+        },
+        2 => BrightnessEvent::Available,
+        _ => return None,
+    };
+
+    return Some(event);
+}
@@ -0,0 +1,34 @@
+//! reads what the shell copies into the module's memory, numbers are little
+//! endian and strings are a u16 length followed by that many bytes of utf-8
+//!
+//! every read takes the bytes and a cursor into them, moves the cursor past
+//! what was read and returns `None` when the bytes are cut short
+
+/// the next `len` bytes after `cursor`, moving it past them
+pub(crate) fn take<'a>(bytes: &'a [u8], cursor: &mut usize, len: usize) -> Option<&'a [u8]> {
+    let taken = bytes.get(*cursor..cursor.checked_add(len)?)?;
+    *cursor += len;
+    Some(taken)
+}
+
+/// a u16 length followed by that many bytes of utf-8
+pub(crate) fn string(bytes: &[u8], cursor: &mut usize) -> Option<String> {
+    let len = read_u16(bytes, cursor)?;
+    Some(String::from_utf8_lossy(take(bytes, cursor, len as usize)?).into_owned())
+}
+
+pub(crate) fn read_u16(bytes: &[u8], cursor: &mut usize) -> Option<u16> {
+    Some(u16::from_le_bytes(take(bytes, cursor, 2)?.try_into().ok()?))
+}
+
+pub(crate) fn read_u32(bytes: &[u8], cursor: &mut usize) -> Option<u32> {
+    Some(u32::from_le_bytes(take(bytes, cursor, 4)?.try_into().ok()?))
+}
+
+pub(crate) fn read_f32(bytes: &[u8], cursor: &mut usize) -> Option<f32> {
+    Some(f32::from_le_bytes(take(bytes, cursor, 4)?.try_into().ok()?))
+}
+
+pub(crate) fn read_u64(bytes: &[u8], cursor: &mut usize) -> Option<u64> {
+    Some(u64::from_le_bytes(take(bytes, cursor, 8)?.try_into().ok()?))
+}
@@ -0,0 +1,31 @@
+//! puts text on the clipboard, like the emoji picked from a picker
+//!
+//! the text is set once the module is done with the message it's handling,
+//! so setting it more than once in a message only leaves the last text
+//!
+//! example:
+//! ```
+//! clipboard::set("🦊")?;
+//! ```
+
+unsafe extern "C" {
+    /// host function to put the text at `ptr` on the clipboard, returns 0 if
+    /// it will be
+    fn set_clipboard(ptr: u32, len: u32) -> u32;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardError {
+    /// the text is over 1 MiB
+    TooLong,
+    /// the shell couldn't read the text
+    Invalid,
+}
+
+pub fn set(text: &str) -> Result<(), ClipboardError> {
+    match unsafe { set_clipboard(text.as_ptr() as u32, text.len() as u32) } {
+        0 => Ok(()),
+        1 => Err(ClipboardError::TooLong),
+        _ => Err(ClipboardError::Invalid),
+    }
+}
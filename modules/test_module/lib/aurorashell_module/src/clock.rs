@@ -0,0 +1,95 @@
+//! the time as the shell formatted it for the module's `Clock` register,
+//! along with the date so a calendar can be drawn without date maths
+//!
+//! example:
+//! ```
+//! let text = clock::now().map(|time| time.text).unwrap_or_default();
+//! ```
+
+use crate::bytes::{read_u16, read_u32, string, take};
+
+unsafe extern "C" {
+    /// host function to get the size of the time in bytes, 0 if the module
+    /// hasn't been given one yet
+    fn clock_size() -> u32;
+    /// host function to copy the time to `ptr`, returns how many bytes were
+    /// written or 0 if it didn't fit in `len`
+    fn read_clock(ptr: u32, len: u32) -> u32;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockTime {
+    /// the time in the register's format
+    pub text: String,
+    pub calendar: Calendar,
+}
+
+/// the date in the register's timezone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calendar {
+    pub year: i32,
+    /// 1 - 12
+    pub month: u8,
+    /// 1 - 31
+    pub day: u8,
+    /// 0 for monday to 6 for sunday
+    pub weekday: u8,
+    /// the day of the year, 1 - 366
+    pub ordinal: u16,
+    /// the iso 8601 week number, 1 - 53
+    pub iso_week: u8,
+    pub days_in_month: u8,
+    /// the weekday of the 1st of the month, like `weekday`
+    pub first_weekday: u8,
+    /// the timezone's offset from utc
+    pub utc_offset_seconds: i32,
+}
+
+/// the last time the shell gave the module, `None` before the first one
+/// arrives or without a `Clock` register
+pub fn now() -> Option<ClockTime> {
+    let size = unsafe { clock_size() };
+    if size == 0 {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = vec![0; size as usize];
+    let written = unsafe { read_clock(bytes.as_mut_ptr() as u32, size) };
+    if written == 0 {
+        return None;
+    }
+    bytes.truncate(written as usize);
+
+    return parse(&bytes);
+}
+
+/// reads the time written by the host, `None` if it's cut short
+///
+/// the year and utc offset as i32s, the month, day and weekday as u8s, the
+/// ordinal as a u16, the iso week, days in month and first weekday as u8s,
+/// then the text as a u16 length followed by that many bytes of utf-8
+fn parse(bytes: &[u8]) -> Option<ClockTime> {
+    let mut cursor = 0;
+
+    let year = read_u32(bytes, &mut cursor)? as i32;
+    let utc_offset_seconds = read_u32(bytes, &mut cursor)? as i32;
+    let [month, day, weekday] = take(bytes, &mut cursor, 3)?.try_into().ok()?;
+    let ordinal = read_u16(bytes, &mut cursor)?;
+    let [iso_week, days_in_month, first_weekday] = take(bytes, &mut cursor, 3)?.try_into().ok()?;
+    let text = string(bytes, &mut cursor)?;
+
+    return Some(ClockTime {
+        text,
+        calendar: Calendar {
+            year,
+            month,
+            day,
+            weekday,
+            ordinal,
+            iso_week,
+            days_in_month,
+            first_weekday,
+            utc_offset_seconds,
+        },
+    });
+}
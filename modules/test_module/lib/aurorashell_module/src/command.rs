@@ -0,0 +1,215 @@
+//! asks the shell to run something for the module that it can't do from
+//! inside wasm, like running a program or fetching a url
+//!
+//! the command starts once the module is done with the message it's
+//! handling and runs while the module carries on. once it's done the
+//! function given to `perform` gets its output and returns the message id
+//! along with the output to pass to it, which goes through `update` like a
+//! widget's callback
+//!
+//! the user has to list the module's name in `spawn` or `fetch` under
+//! `[modules]` in their config first, or the programs it can run under
+//! `[modules.programs]`, until then `perform` is `Err(NotAllowed)`
+//!
+//! example:
+//! ```
+//! // in update
+//! Command::spawn("uptime", &["-p"])
+//!     .perform(Box::new(|output| (Message::Uptime(output.clone()).into(), output)))
+//!     .ok();
+//!
+//! // with `#[derive(ModuleMessage)]` on `Message`
+//! Uptime(Output),
+//! ```
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+unsafe extern "C" {
+    /// host function to run a command once the module is done with the
+    /// message it's handling, returns 0 if it will be
+    fn perform_command(kind: u32, ptr: u32, len: u32, handle: u32) -> u32;
+    /// host function to copy the output of the command being handed to
+    /// `run_command` to `ptr`, returns how many bytes were written
+    fn read_command_output(ptr: u32, len: u32) -> u32;
+}
+
+/// gets the output of a command and returns the message id along with the
+/// output to pass to it
+pub type CommandFn = Box<dyn FnOnce(Output) -> (u32, Output) + Send>;
+
+/// the functions waiting for their command to finish, by handle
+static PENDING: LazyLock<Mutex<HashMap<u32, CommandFn>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// the handle of the next command, 0 is never used
+static NEXT_HANDLE: AtomicU32 = AtomicU32::new(1);
+
+/// something for the shell to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    kind: u32,
+    /// the program and its arguments separated by nul bytes, the url, or the
+    /// method, url and body separated by nul bytes
+    arg: String,
+}
+
+impl Command {
+    /// runs `program` with `args`, without a shell. it's stopped if it
+    /// doesn't exit within 30 seconds
+    pub fn spawn(program: &str, args: &[&str]) -> Self {
+        let mut arg = program.to_string();
+        for part in args {
+            arg.push('\0');
+            arg.push_str(part);
+        }
+
+        Self { kind: 0, arg }
+    }
+
+    /// a get request to an http or https url
+    pub fn fetch(url: &str) -> Self {
+        Self {
+            kind: 1,
+            arg: url.to_string(),
+        }
+    }
+
+    /// a request to an http or https url with `method` (`GET`, `HEAD`,
+    /// `POST`, `PUT`, `PATCH` or `DELETE`) and `body`, which isn't sent when
+    /// it's empty. it needs the module listed in `fetch` like `fetch` does
+    ///
+    /// example:
+    /// ```
+    /// Command::request("POST", "https://example.com/api", r#"{"read":true}"#)
+    ///     .perform(Box::new(|output| (Message::Sent(output.clone()).into(), output)))
+    ///     .ok();
+    /// ```
+    pub fn request(method: &str, url: &str, body: &str) -> Self {
+        Self {
+            kind: 2,
+            arg: format!("{method}\0{url}\0{body}"),
+        }
+    }
+
+    /// starts the command, `f` gets its output once it's done
+    pub fn perform(self, f: CommandFn) -> Result<(), CommandError> {
+        let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+        let result = unsafe {
+            perform_command(
+                self.kind,
+                self.arg.as_ptr() as u32,
+                self.arg.len() as u32,
+                handle,
+            )
+        };
+
+        match result {
+            0 => {
+                PENDING.lock().unwrap().insert(handle, f);
+                Ok(())
+            }
+            1 => Err(CommandError::NotAllowed),
+            2 => Err(CommandError::Invalid),
+            _ => Err(CommandError::TooMany),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandError {
+    /// the module isn't listed in `spawn` or `fetch`, or the program isn't
+    /// in the module's list under `[modules.programs]`
+    NotAllowed,
+    /// the program is empty, the url isn't http or https, the method isn't
+    /// one the shell sends, or it's longer than 4096 bytes (64 KiB for a
+    /// request with its body)
+    Invalid,
+    /// the module already has 16 commands running
+    TooMany,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    /// the program exited, `status` is -1 if it was killed by a signal.
+    /// stdout and stderr are cut off after a mebibyte
+    Exited {
+        status: i32,
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+    },
+    /// the server answered, whatever the status was. the body is cut off
+    /// after a mebibyte, and the request fails if it took longer than 30
+    /// seconds
+    Response { status: u16, body: Vec<u8> },
+    /// it couldn't be run or took longer than 30 seconds, with the reason
+    Failed(String),
+}
+
+/// defines an external function to be called by the wasm host once a
+/// command finished, `len` is the size of its output
+///
+/// returns the message id and a pointer to its data merged into one u64 like
+/// `run_callback`, or 0 if there's no command with the handle
+#[unsafe(no_mangle)]
+fn run_command(handle: u32, len: u32) -> u64 {
+    let Some(f) = PENDING.lock().unwrap().remove(&handle) else {
+        eprintln!("module: there's no command with handle {}", handle);
+        return 0;
+    };
+
+    let mut bytes: Vec<u8> = vec![0; len as usize];
+    let written = match len {
+        0 => 0,
+        len => unsafe { read_command_output(bytes.as_mut_ptr() as u32, len) },
+    };
+    bytes.truncate(written as usize);
+
+    let output = parse(&bytes).unwrap_or_else(|| {
+        Output::Failed("the shell sent output that couldn't be read".to_string())
+    });
+
+    let (message_id, data) = f(output);
+
+    let leaked_data = Box::leak(Box::new(data));
+    let data_ptr = leaked_data as *mut Output;
+
+    return (message_id as u64) << 32 | data_ptr as u32 as u64;
+}
+
+/// a u8 that's 0 when the program exited, 1 for a response and 2 when it
+/// failed, then the status as a little endian i32 and two byte strings as a
+/// little endian u32 length then the bytes
+fn parse(bytes: &[u8]) -> Option<Output> {
+    let (&tag, rest) = bytes.split_first()?;
+    let (status, rest) = rest.split_first_chunk::<4>()?;
+    let status = i32::from_le_bytes(*status);
+
+    let (first, rest) = byte_string(rest)?;
+    let (second, _) = byte_string(rest)?;
+
+    match tag {
+        0 => Some(Output::Exited {
+            status,
+            stdout: first,
+            stderr: second,
+        }),
+        1 => Some(Output::Response {
+            status: status as u16,
+            body: first,
+        }),
+        2 => Some(Output::Failed(String::from_utf8_lossy(&first).into_owned())),
+        _ => None,
+    }
+}
+
+fn byte_string(bytes: &[u8]) -> Option<(Vec<u8>, &[u8])> {
+    let (len, rest) = bytes.split_first_chunk::<4>()?;
+    let len = u32::from_le_bytes(*len) as usize;
+    if rest.len() < len {
+        return None;
+    }
+
+    let (string, rest) = rest.split_at(len);
+    return Some((string.to_vec(), rest));
+}
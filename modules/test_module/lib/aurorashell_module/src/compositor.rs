@@ -0,0 +1,57 @@
+//! asks the shell which wayland protocols the compositor supports, so a
+//! module can leave out what won't work on it instead of the shell turning
+//! it off with a warning
+//!
+//! example:
+//! ```
+//! let keyboard_interactivity = match compositor::supports(Protocol::LayerShell, 4) {
+//!     true => KeyboardInteractivity::OnDemand,
+//!     false => KeyboardInteractivity::Exclusive,
+//! };
+//! ```
+
+unsafe extern "C" {
+    /// host function to get the newest version of a protocol the compositor
+    /// supports, 0 if it doesn't and `u32::MAX` if it's not known
+    fn compositor_version(protocol: u32) -> u32;
+}
+
+/// the protocols a module can ask about
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// `zwlr_layer_shell_v1`, every surface is a layer surface
+    LayerShell = 0,
+    /// `zwlr_foreign_toplevel_manager_v1`
+    ForeignToplevelManagement = 1,
+    /// `ext_foreign_toplevel_list_v1`
+    ForeignToplevelList = 2,
+    /// `zwp_idle_inhibit_manager_v1`
+    IdleInhibit = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Support {
+    /// the newest version the compositor supports
+    Version(u32),
+    Unsupported,
+    /// the shell couldn't ask the compositor
+    Unknown,
+}
+
+pub fn version(protocol: Protocol) -> Support {
+    match unsafe { compositor_version(protocol as u32) } {
+        0 => Support::Unsupported,
+        u32::MAX => Support::Unknown,
+        version => Support::Version(version),
+    }
+}
+
+/// whether the protocol can be used at `version`, true when it's not known
+pub fn supports(protocol: Protocol, version: u32) -> bool {
+    match self::version(protocol) {
+        Support::Version(supported) => supported >= version,
+        Support::Unsupported => false,
+        Support::Unknown => true,
+    }
+}
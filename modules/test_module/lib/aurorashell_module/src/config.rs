@@ -0,0 +1,64 @@
+//! the module's options from the user's config, set under
+//! `[modules.settings.<name>]` where the name is the module's file name
+//! without the extension. they can be read in `setup`, so a module can pick
+//! its surfaces' position from them
+//!
+//! example:
+//! ```toml
+//! [modules.settings.clock]
+//! format = "%H:%M"
+//! ```
+//!
+//! ```
+//! let format = config::get("format").unwrap_or("%a %H:%M".to_string());
+//! ```
+
+use crate::bytes::{read_u16, string};
+
+unsafe extern "C" {
+    /// host function to get the size of the options in bytes, 0 if the
+    /// module has none
+    fn settings_size() -> u32;
+    /// host function to copy the options to `ptr`, returns how many bytes
+    /// were written or 0 if they didn't fit in `len`
+    fn read_settings(ptr: u32, len: u32) -> u32;
+}
+
+/// the option set for `key`, values that aren't strings in the config are
+/// given as they were written like `24` or `true`
+pub fn get(key: &str) -> Option<String> {
+    all().into_iter().find(|(k, _)| k == key).map(|(_, value)| value)
+}
+
+/// every option set for the module as `(key, value)`, sorted by key
+pub fn all() -> Vec<(String, String)> {
+    let size = unsafe { settings_size() };
+    if size == 0 {
+        return vec![];
+    }
+
+    let mut bytes: Vec<u8> = vec![0; size as usize];
+    let written = unsafe { read_settings(bytes.as_mut_ptr() as u32, size) };
+    if written == 0 {
+        return vec![];
+    }
+    bytes.truncate(written as usize);
+
+    return parse(&bytes).unwrap_or_default();
+}
+
+/// a u16 amount of options, then the key and value of each as a u16 length
+/// followed by that many bytes of utf-8
+fn parse(bytes: &[u8]) -> Option<Vec<(String, String)>> {
+    let mut cursor = 0;
+
+    let count = read_u16(bytes, &mut cursor)?;
+    let mut settings = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let key = string(bytes, &mut cursor)?;
+        let value = string(bytes, &mut cursor)?;
+        settings.push((key, value));
+    }
+
+    return Some(settings);
+}
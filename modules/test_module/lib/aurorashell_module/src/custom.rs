@@ -0,0 +1,75 @@
+//! the output of the sensors (scripts) set in the shell's config, given to
+//! modules with a `Custom` register as `ServiceEvent::Custom` when they're
+//! made with a service event function (see `create_module!`). only the
+//! sensors named in the register are sent
+//!
+//! example:
+//! ```
+//! fn service_event(event: &ServiceEvent) -> u32 {
+//!     match event {
+//!         ServiceEvent::Custom(CustomEvent::SensorUpdated { name, value }) => {
+//!             Message::Sensor(name.clone(), value.clone()).into()
+//!         }
+//!         _ => 0,
+//!     }
+//! }
+//! ```
+
+use crate::bytes::{read_u32, string, take};
+
+/// what a sensor did, `name` is the sensor's name from the config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CustomEvent {
+    SensorUpdated {
+        name: String,
+        value: SensorValue,
+    },
+    /// the command failed to run, timed out, exited with an error or its
+    /// output couldn't be parsed
+    SensorFailed {
+        name: String,
+        error: String,
+    },
+}
+
+/// the output of a sensor, depends on the sensor's parse mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SensorValue {
+    /// trimmed stdout of the command
+    Plain(String),
+    /// stdout of the command, already checked to be json by the shell
+    Json(String),
+}
+
+/// reads an event from the shell, `None` for a kind this version doesn't
+/// know
+///
+/// a u8 for the kind of event and the sensor's name, then a u8 for the parse
+/// mode and the output with a u32 length, or the error
+pub(crate) fn parse_event(bytes: &[u8]) -> Option<CustomEvent> {
+    let mut cursor = 0;
+
+    let kind = take(bytes, &mut cursor, 1)?[0];
+    let name = string(bytes, &mut cursor)?;
+
+    let event = match kind {
+        0 => {
+            let parse = take(bytes, &mut cursor, 1)?[0];
+            let len = read_u32(bytes, &mut cursor)?;
+            let output = String::from_utf8_lossy(take(bytes, &mut cursor, len as usize)?);
+            let value = match parse {
+                0 => SensorValue::Plain(output.into_owned()),
+                1 => SensorValue::Json(output.into_owned()),
+                _ => return None,
+            };
+            CustomEvent::SensorUpdated { name, value }
+        }
+        1 => CustomEvent::SensorFailed {
+            name,
+            error: string(bytes, &mut cursor)?,
+        },
+        _ => return None,
+    };
+
+    return Some(event);
+}
@@ -0,0 +1,230 @@
+//! what the programs a module watches with its `Dbus` registers send on
+//! dbus
+//!
+//! the module is rendered again with each of them. modules made with a
+//! service event function are also given what was sent, with the bus name,
+//! path and interface of the register it's for
+//!
+//! example:
+//! ```
+//! fn service_event(event: &ServiceEvent) -> u32 {
+//!     match event {
+//!         ServiceEvent::Dbus(DbusEvent::PropertiesChanged { changed, .. }) => {
+//!             let title = changed
+//!                 .iter()
+//!                 .find(|(name, _)| name == "Metadata")
+//!                 .and_then(|(_, metadata)| metadata.get("xesam:title"))
+//!                 .and_then(Value::as_str);
+//!             match title {
+//!                 Some(title) => Message::Title(title.to_string()).into(),
+//!                 None => 0,
+//!             }
+//!         }
+//!         _ => 0,
+//!     }
+//! }
+//! ```
+
+use crate::bytes::{read_u16, read_u32, string, take};
+
+/// a dbus value, the types a module doesn't need to tell apart are merged.
+/// every integer is an `Int` or `Uint`, object paths and signatures are
+/// strings and variants are unwrapped
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    Double(f64),
+    String(String),
+    Array(Vec<Value>),
+    Dict(Vec<(Value, Value)>),
+    Struct(Vec<Value>),
+}
+
+impl Value {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// the value of an `Int`, or a `Uint` that fits
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int(value) => Some(*value),
+            Self::Uint(value) => i64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    /// the value of a `Double`, or an integer as one
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Double(value) => Some(*value),
+            Self::Int(value) => Some(*value as f64),
+            Self::Uint(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// the values of an `Array` or `Struct`
+    pub fn as_slice(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(values) | Self::Struct(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// the value for a string key of a `Dict`, like the `a{sv}` dicts many
+    /// programs use
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Dict(entries) => entries
+                .iter()
+                .find(|(entry, _)| entry.as_str() == Some(key))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+/// what was sent, given to modules as `ServiceEvent::Dbus` when they're made
+/// with a service event function (see `create_module!`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum DbusEvent {
+    /// the interface's properties changed. it's also sent with all of them
+    /// when the module registers and when the bus name gets a new owner
+    PropertiesChanged {
+        name: String,
+        path: String,
+        interface: String,
+        changed: Vec<(String, Value)>,
+        /// properties that changed without their new value being sent
+        invalidated: Vec<String>,
+    },
+    /// any other signal of the interface
+    Signal {
+        name: String,
+        path: String,
+        interface: String,
+        member: String,
+        args: Vec<Value>,
+    },
+}
+
+/// reads an event from the shell, `None` for a kind this version doesn't
+/// know
+///
+/// a u8 for the kind of event, the bus name, path and interface, then for
+/// 0 the changed properties and the invalidated ones, and for 1 the signal's
+/// name and its arguments
+pub(crate) fn parse_event(bytes: &[u8]) -> Option<DbusEvent> {
+    let mut cursor = 0;
+
+    let kind = take(bytes, &mut cursor, 1)?[0];
+    let name = string(bytes, &mut cursor)?;
+    let path = string(bytes, &mut cursor)?;
+    let interface = string(bytes, &mut cursor)?;
+
+    let event = match kind {
+        0 => {
+            let count = read_u16(bytes, &mut cursor)?;
+            let mut changed = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let property = string(bytes, &mut cursor)?;
+                changed.push((property, value(bytes, &mut cursor)?));
+            }
+
+            let count = read_u16(bytes, &mut cursor)?;
+            let mut invalidated = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                invalidated.push(string(bytes, &mut cursor)?);
+            }
+
+            DbusEvent::PropertiesChanged {
+                name,
+                path,
+                interface,
+                changed,
+                invalidated,
+            }
+        }
+        1 => {
+            let member = string(bytes, &mut cursor)?;
+
+            let count = read_u16(bytes, &mut cursor)?;
+            let mut args = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                args.push(value(bytes, &mut cursor)?);
+            }
+
+            DbusEvent::Signal {
+                name,
+                path,
+                interface,
+                member,
+                args,
+            }
+        }
+        _ => return None,
+    };
+
+    return Some(event);
+}
+
+/// a u8 for the type then the value
+///
+/// - 0 a bool as a u8
+/// - 1 an i64, 2 a u64 and 3 an f64
+/// - 4 a string as a u32 length then the bytes
+/// - 5 an array and 7 a struct, a u32 for the amount of values then each
+///   value
+/// - 6 a dict, a u32 for the amount of entries then each key and value
+fn value(bytes: &[u8], cursor: &mut usize) -> Option<Value> {
+    let value = match take(bytes, cursor, 1)?[0] {
+        0 => Value::Bool(take(bytes, cursor, 1)?[0] != 0),
+        1 => Value::Int(i64::from_le_bytes(take(bytes, cursor, 8)?.try_into().ok()?)),
+        2 => Value::Uint(u64::from_le_bytes(take(bytes, cursor, 8)?.try_into().ok()?)),
+        3 => Value::Double(f64::from_le_bytes(take(bytes, cursor, 8)?.try_into().ok()?)),
+        4 => {
+            let len = read_u32(bytes, cursor)?;
+            Value::String(String::from_utf8_lossy(take(bytes, cursor, len as usize)?).into_owned())
+        }
+        kind @ (5 | 7) => {
+            let count = read_u32(bytes, cursor)?;
+            // every value is at least 2 bytes, so a bad count can't reserve
+            // more than the event holds
+            let mut values = Vec::with_capacity((count as usize).min(bytes.len() / 2));
+            for _ in 0..count {
+                values.push(value(bytes, cursor)?);
+            }
+
+            match kind {
+                5 => Value::Array(values),
+                _ => Value::Struct(values),
+            }
+        }
+        6 => {
+            let count = read_u32(bytes, cursor)?;
+            let mut entries = Vec::with_capacity((count as usize).min(bytes.len() / 4));
+            for _ in 0..count {
+                let key = value(bytes, cursor)?;
+                entries.push((key, value(bytes, cursor)?));
+            }
+
+            Value::Dict(entries)
+        }
+        _ => return None,
+    };
+
+    return Some(value);
+}
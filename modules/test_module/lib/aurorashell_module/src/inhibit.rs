@@ -0,0 +1,85 @@
+//! keeps the session from going idle, for a "caffeine" toggle. the screen
+//! doesn't blank or lock and the system doesn't suspend while it's on
+//!
+//! it's the shell keeping the session awake, so every module sees the same
+//! state and turning it off in one module turns it off for all of them.
+//! modules with an `Inhibit` register are told when it changes
+//!
+//! example:
+//! ```
+//! let on = inhibit::inhibited().unwrap_or(false);
+//! inhibit::set_inhibited(!on)?;
+//! ```
+
+unsafe extern "C" {
+    /// host function to get whether the session is kept from going idle, 1
+    /// if it is, 0 if it isn't and 2 if the shell doesn't know yet
+    fn idle_inhibited() -> u32;
+    /// host function to keep the session from going idle (action 1) or let
+    /// it go idle again (0), returns 0 if it was sent
+    fn inhibit_request(action: u32) -> u32;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestError {
+    /// the shell doesn't know the action
+    Unsupported,
+}
+
+/// what changed, given to modules as `ServiceEvent::Inhibit` when they're
+/// made with a service event function (see `create_module!`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InhibitEvent {
+    InhibitChanged {
+        inhibited: bool,
+    },
+    /// there's no logind to ask, show a disabled toggle until `Available`
+    Unavailable {
+        reason: String,
+    },
+    Available,
+}
+
+/// whether the session is kept from going idle, `None` before the inhibit
+/// service first updates or when it isn't running
+pub fn inhibited() -> Option<bool> {
+    match unsafe { idle_inhibited() } {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
+
+/// keeps the session from going idle, or lets it go idle again. modules
+/// are told it changed once it did
+pub fn set_inhibited(inhibited: bool) -> Result<(), RequestError> {
+    match unsafe { inhibit_request(inhibited as u32) } {
+        0 => Ok(()),
+        _ => Err(RequestError::Unsupported),
+    }
+}
+
+/// reads an event from the shell, `None` for a kind this version doesn't
+/// know
+///
+/// a u8 for the kind of event, then a u8 for whether it's inhibited or the
+/// reason the service is unavailable
+pub(crate) fn parse_event(bytes: &[u8]) -> Option<InhibitEvent> {
+    let (&kind, rest) = bytes.split_first()?;
+
+    let event = match kind {
+        0 => InhibitEvent::InhibitChanged {
+            inhibited: *rest.first()? != 0,
+        },
+        1 => {
+            let len = u16::from_le_bytes(rest.get(..2)?.try_into().ok()?) as usize;
+            InhibitEvent::Unavailable {
+                reason: String::from_utf8_lossy(rest.get(2..2 + len)?).into_owned(),
+            }
+        }
+        2 => InhibitEvent::Available,
+        _ => return None,
+    };
+
+    return Some(event);
+}
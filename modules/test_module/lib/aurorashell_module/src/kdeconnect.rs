@@ -0,0 +1,207 @@
+//! devices paired with kde connect as the shell last saw them, for modules
+//! with a `KdeConnect` register, and actions to run on them
+//!
+//! `device` in the actions is the device's `id`, `None` for the first
+//! connected device
+//!
+//! example:
+//! ```
+//! let phone = kdeconnect::devices()?.devices.into_iter().find(|device| device.connected)?;
+//! let charge = phone.battery.map(|battery| battery.charge);
+//! ```
+
+use crate::bytes::{read_u16, string, take};
+
+unsafe extern "C" {
+    /// host function to get the size of the devices in bytes, 0 if the shell
+    /// hasn't heard from kde connect yet
+    fn kdeconnect_size() -> u32;
+    /// host function to copy the devices to `ptr`, returns how many bytes
+    /// were written or 0 if they didn't fit in `len`
+    fn read_kdeconnect(ptr: u32, len: u32) -> u32;
+    /// host function to run an action on the device with the id at `ptr`, or
+    /// the first connected one when `len` is 0. returns 0 if it was sent
+    fn kdeconnect_request(action: u32, ptr: u32, len: u32) -> u32;
+}
+
+/// written by the host instead of a device's index when it's not known
+const NO_INDEX: u16 = u16::MAX;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Devices {
+    /// every paired device, sorted by name
+    pub devices: Vec<Device>,
+    /// the notifications shown on the devices, oldest first
+    pub notifications: Vec<Notification>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    /// what the actions take as `device`
+    pub id: String,
+    pub name: String,
+    /// like `phone`, `tablet` or `desktop`
+    pub kind: String,
+    pub connected: bool,
+    /// `None` when the device doesn't share its battery or isn't connected
+    pub battery: Option<Battery>,
+    /// `None` when nothing is playing or the device doesn't share it
+    pub media: Option<Media>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Battery {
+    /// 0 - 100
+    pub charge: u8,
+    pub charging: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Media {
+    /// the app that's playing, like `Spotify`
+    pub player: String,
+    pub title: String,
+    pub artist: String,
+    pub playing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// the index into `Devices::devices` of the device it's shown on
+    pub device: Option<u16>,
+    pub id: String,
+    pub app_name: String,
+    pub title: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaAction {
+    PlayPause,
+    Next,
+    Previous,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestError {
+    /// the device id is too long to be one
+    InvalidDevice,
+    /// the shell doesn't know the action, it's older than this library
+    Unsupported,
+}
+
+/// the last devices the shell gave the module, `None` before kde connect
+/// first updates
+pub fn devices() -> Option<Devices> {
+    let size = unsafe { kdeconnect_size() };
+    if size == 0 {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = vec![0; size as usize];
+    let written = unsafe { read_kdeconnect(bytes.as_mut_ptr() as u32, size) };
+    if written == 0 {
+        return None;
+    }
+    bytes.truncate(written as usize);
+
+    return parse(&bytes);
+}
+
+/// makes the device ring, to find it
+pub fn ring(device: Option<&str>) -> Result<(), RequestError> {
+    request(0, device)
+}
+
+/// sends a ping notification to the device
+pub fn ping(device: Option<&str>) -> Result<(), RequestError> {
+    request(1, device)
+}
+
+/// controls what's playing on the device
+pub fn media(device: Option<&str>, action: MediaAction) -> Result<(), RequestError> {
+    let action = match action {
+        MediaAction::PlayPause => 2,
+        MediaAction::Next => 3,
+        MediaAction::Previous => 4,
+    };
+
+    request(action, device)
+}
+
+fn request(action: u32, device: Option<&str>) -> Result<(), RequestError> {
+    let device = device.unwrap_or_default();
+
+    match unsafe { kdeconnect_request(action, device.as_ptr() as u32, device.len() as u32) } {
+        0 => Ok(()),
+        1 => Err(RequestError::Unsupported),
+        _ => Err(RequestError::InvalidDevice),
+    }
+}
+
+/// reads the devices written by the host, `None` if they're cut short
+///
+/// strings are a u16 length followed by that many bytes of utf-8
+///
+/// - u16 amount of devices, then u16 amount of notifications
+/// - each device is its id, name and kind, a u8 of flags (bit 0 being
+///   connected, bit 1 having a battery, bit 2 charging, bit 3 having media and
+///   bit 4 playing) and the u8 battery charge. devices with media then have
+///   the player, title and artist
+/// - each notification is the u16 index of its device, then its id, app name,
+///   title and text
+fn parse(bytes: &[u8]) -> Option<Devices> {
+    let mut cursor = 0;
+
+    let n_devices = read_u16(bytes, &mut cursor)?;
+    let n_notifications = read_u16(bytes, &mut cursor)?;
+
+    let mut devices = vec![];
+    for _ in 0..n_devices {
+        let id = string(bytes, &mut cursor)?;
+        let name = string(bytes, &mut cursor)?;
+        let kind = string(bytes, &mut cursor)?;
+        let [flags, charge] = take(bytes, &mut cursor, 2)?.try_into().ok()?;
+
+        let battery = (flags & (1 << 1) != 0).then_some(Battery {
+            charge,
+            charging: flags & (1 << 2) != 0,
+        });
+        let media = match flags & (1 << 3) != 0 {
+            true => Some(Media {
+                player: string(bytes, &mut cursor)?,
+                title: string(bytes, &mut cursor)?,
+                artist: string(bytes, &mut cursor)?,
+                playing: flags & (1 << 4) != 0,
+            }),
+            false => None,
+        };
+
+        devices.push(Device {
+            id,
+            name,
+            kind,
+            connected: flags & 1 != 0,
+            battery,
+            media,
+        });
+    }
+
+    let mut notifications = vec![];
+    for _ in 0..n_notifications {
+        let device = read_u16(bytes, &mut cursor)?;
+
+        notifications.push(Notification {
+            device: (device != NO_INDEX).then_some(device),
+            id: string(bytes, &mut cursor)?,
+            app_name: string(bytes, &mut cursor)?,
+            title: string(bytes, &mut cursor)?,
+            text: string(bytes, &mut cursor)?,
+        });
+    }
+
+    return Some(Devices {
+        devices,
+        notifications,
+    });
+}
@@ -1,8 +1,31 @@
+pub mod audio;
+pub mod brightness;
+mod bytes;
+pub mod clipboard;
+pub mod clock;
+pub mod command;
+pub mod compositor;
+pub mod config;
+pub mod custom;
+pub mod dbus;
+pub mod inhibit;
+pub mod kdeconnect;
+pub mod network;
+pub mod open;
+pub mod outputs;
+pub mod region;
 pub mod register;
+pub mod screenshot;
+pub mod services;
+pub mod session;
 pub mod setup;
 pub mod surface;
+pub mod sysinfo;
 pub mod theme;
+pub mod unicode;
 mod view;
+pub mod watch;
+pub mod weather;
 pub mod widget;
 
 pub use widget::Element;
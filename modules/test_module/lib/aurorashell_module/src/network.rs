@@ -0,0 +1,140 @@
+//! the state of the network from NetworkManager, given to modules with a
+//! `Network` register as `ServiceEvent::Network` when they're made with a
+//! service event function (see `create_module!`)
+//!
+//! example:
+//! ```
+//! fn service_event(event: &ServiceEvent) -> u32 {
+//!     match event {
+//!         ServiceEvent::Network(NetworkEvent::WirelessStrengthChanged { .. }) => {
+//!             Message::Network(event.clone()).into()
+//!         }
+//!         _ => 0,
+//!     }
+//! }
+//! ```
+
+use crate::bytes::{read_u16, string, take};
+
+/// what changed, only the kinds the module registered for are sent along
+/// with `Unavailable` and `Available`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkEvent {
+    /// a saved connection was added or removed
+    ConnectionsChanged {
+        connections: Vec<Connection>,
+    },
+    /// the connection the default route goes through changed, `None` when
+    /// offline
+    ActiveConnectionChanged {
+        active: Option<ActiveConnection>,
+    },
+    /// the signal strength (0 - 100) of the access point the active
+    /// connection uses, `None` when it isn't wifi
+    WirelessStrengthChanged {
+        strength: Option<u8>,
+    },
+    WifiEnabledChanged {
+        enabled: bool,
+    },
+    /// NetworkManager can't be reached, show a disabled state until
+    /// `Available`
+    Unavailable {
+        reason: String,
+    },
+    Available,
+}
+
+/// a saved connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    /// the name of the connection, what `nmcli` shows as NAME
+    pub id: String,
+    pub uuid: String,
+    pub kind: ConnectionKind,
+}
+
+/// the connection the default route goes through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveConnection {
+    pub id: String,
+    pub uuid: String,
+    pub kind: ConnectionKind,
+    /// the name of the wifi network, `None` if it isn't wifi
+    pub ssid: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionKind {
+    Wifi,
+    Ethernet,
+    /// any other type, like a vpn or bridge, with NetworkManager's name for it
+    Other(String),
+}
+
+/// reads an event from the shell, `None` for a kind this version doesn't
+/// know
+///
+/// a u8 for the kind of event then what changed, connections are their
+/// name and uuid followed by a u8 for the kind
+pub(crate) fn parse_event(bytes: &[u8]) -> Option<NetworkEvent> {
+    let mut cursor = 0;
+
+    let event = match take(bytes, &mut cursor, 1)?[0] {
+        0 => {
+            let count = read_u16(bytes, &mut cursor)?;
+            let mut connections = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let (id, uuid, kind) = parse_connection(bytes, &mut cursor)?;
+                connections.push(Connection { id, uuid, kind });
+            }
+            NetworkEvent::ConnectionsChanged { connections }
+        }
+        1 => {
+            let active = match take(bytes, &mut cursor, 1)?[0] {
+                0 => None,
+                _ => {
+                    let (id, uuid, kind) = parse_connection(bytes, &mut cursor)?;
+                    let ssid = string(bytes, &mut cursor)?;
+                    Some(ActiveConnection {
+                        id,
+                        uuid,
+                        kind,
+                        ssid: (!ssid.is_empty()).then_some(ssid),
+                    })
+                }
+            };
+            NetworkEvent::ActiveConnectionChanged { active }
+        }
+        2 => NetworkEvent::WirelessStrengthChanged {
+            strength: match take(bytes, &mut cursor, 1)?[0] {
+                u8::MAX => None,
+                strength => Some(strength),
+            },
+        },
+        3 => NetworkEvent::WifiEnabledChanged {
+            enabled: take(bytes, &mut cursor, 1)?[0] != 0,
+        },
+        4 => NetworkEvent::Unavailable {
+            reason: string(bytes, &mut cursor)?,
+        },
+        5 => NetworkEvent::Available,
+        _ => return None,
+    };
+
+    return Some(event);
+}
+
+/// the name, uuid and kind of a connection, the kind is a u8 with 0 for
+/// wifi, 1 for ethernet and 2 for anything else followed by its name
+fn parse_connection(bytes: &[u8], cursor: &mut usize) -> Option<(String, String, ConnectionKind)> {
+    let id = string(bytes, cursor)?;
+    let uuid = string(bytes, cursor)?;
+    let kind = match take(bytes, cursor, 1)?[0] {
+        0 => ConnectionKind::Wifi,
+        1 => ConnectionKind::Ethernet,
+        _ => ConnectionKind::Other(string(bytes, cursor)?),
+    };
+
+    return Some((id, uuid, kind));
+}
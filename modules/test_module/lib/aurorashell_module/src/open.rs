@@ -0,0 +1,36 @@
+//! opens links and files in the user's default application
+//!
+//! the user has to list the module's name in `open_uri` under `[modules]`
+//! in their config first, until then every call is `Err(NotAllowed)`
+//!
+//! example:
+//! ```
+//! if let Err(err) = open::uri("https://example.com") {
+//!     println!("couldn't open the link: {err:?}");
+//! }
+//! ```
+
+unsafe extern "C" {
+    /// host function to open a uri, returns 0 if it was opened
+    fn open_uri(ptr: u32, len: u32) -> u32;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenError {
+    /// the module isn't listed in `open_uri`
+    NotAllowed,
+    /// the uri isn't an absolute path or an http, https, mailto or file uri
+    Invalid,
+    /// the shell couldn't find a program to open it with
+    Failed,
+}
+
+/// opens an absolute path, or an http, https, mailto or file uri
+pub fn uri(uri: &str) -> Result<(), OpenError> {
+    match unsafe { open_uri(uri.as_ptr() as u32, uri.len() as u32) } {
+        0 => Ok(()),
+        1 => Err(OpenError::NotAllowed),
+        2 => Err(OpenError::Invalid),
+        _ => Err(OpenError::Failed),
+    }
+}
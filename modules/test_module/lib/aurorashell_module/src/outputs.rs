@@ -0,0 +1,137 @@
+//! the monitors plugged in, so a module can fit its layout to them (like
+//! drawing a vertical bar on a portrait monitor)
+//!
+//! outputs can be read in `setup` to pick where surfaces go, but the shell
+//! may not know about every monitor yet when modules are first loaded. a
+//! module with the `Outputs` register is rendered again whenever one is
+//! plugged in, unplugged or changes, so reading them in `view` stays up to
+//! date
+//!
+//! example:
+//! ```
+//! let vertical = outputs::list().first().is_some_and(|output| output.is_portrait());
+//! ```
+
+use crate::bytes::take;
+
+unsafe extern "C" {
+    /// host function to get the size of the outputs table in bytes
+    fn outputs_size() -> u32;
+    /// host function to copy the outputs table to `ptr`, returns how many
+    /// bytes were written or 0 if it didn't fit in `len`
+    fn read_outputs(ptr: u32, len: u32) -> u32;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    /// the connector, like `DP-1`
+    pub name: String,
+    /// the make and model, as the compositor describes it
+    pub description: String,
+    /// the resolution in physical pixels
+    pub width: u32,
+    pub height: u32,
+    /// the size after scaling and rotating, which is what surfaces are sized
+    /// in
+    pub logical_width: u32,
+    pub logical_height: u32,
+    /// the integer scale, fractional scales are rounded up
+    pub scale: u32,
+    pub transform: Transform,
+}
+
+impl Output {
+    /// whether the output is taller than it is wide once rotated
+    pub fn is_portrait(&self) -> bool {
+        self.logical_height > self.logical_width
+    }
+}
+
+/// how an output is rotated and flipped
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    Normal = 0,
+    Rotate90 = 1,
+    Rotate180 = 2,
+    Rotate270 = 3,
+    Flipped = 4,
+    Flipped90 = 5,
+    Flipped180 = 6,
+    Flipped270 = 7,
+}
+
+impl Transform {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Rotate90,
+            2 => Self::Rotate180,
+            3 => Self::Rotate270,
+            4 => Self::Flipped,
+            5 => Self::Flipped90,
+            6 => Self::Flipped180,
+            7 => Self::Flipped270,
+            _ => Self::Normal,
+        }
+    }
+}
+
+/// the outputs in the order the compositor announced them
+pub fn list() -> Vec<Output> {
+    // an output can be plugged in between asking for the size and reading,
+    // so it's tried again once
+    for _ in 0..2 {
+        let size = unsafe { outputs_size() };
+        let mut bytes: Vec<u8> = vec![0; size as usize];
+
+        let written = unsafe { read_outputs(bytes.as_mut_ptr() as u32, size) };
+        if written != 0 {
+            bytes.truncate(written as usize);
+            return parse(&bytes).unwrap_or_default();
+        }
+    }
+
+    return vec![];
+}
+
+/// reads the table written by the host, `None` if it's cut short
+///
+/// a u16 for the amount of outputs, then for each one the width, height,
+/// logical width, logical height and scale as u32s, the transform as a u8 and
+/// the name and description as a u16 length followed by that many bytes of
+/// utf-8
+fn parse(bytes: &[u8]) -> Option<Vec<Output>> {
+    let mut cursor = 0;
+    let mut next = |len: usize| take(bytes, &mut cursor, len);
+
+    let count = u16::from_le_bytes(next(2)?.try_into().ok()?);
+    let mut outputs = Vec::with_capacity(count as usize);
+
+    for _ in 0..count {
+        let mut numbers = [0u32; 5];
+        for number in &mut numbers {
+            *number = u32::from_le_bytes(next(4)?.try_into().ok()?);
+        }
+        let transform = Transform::from_u8(next(1)?[0]);
+
+        let mut strings = [String::new(), String::new()];
+        for string in &mut strings {
+            let len = u16::from_le_bytes(next(2)?.try_into().ok()?);
+            *string = String::from_utf8_lossy(next(len as usize)?).into_owned();
+        }
+        let [name, description] = strings;
+
+        outputs.push(Output {
+            name,
+            description,
+            width: numbers[0],
+            height: numbers[1],
+            logical_width: numbers[2],
+            logical_height: numbers[3],
+            scale: numbers[4],
+            transform,
+        });
+    }
+
+    return Some(outputs);
+}
@@ -0,0 +1,90 @@
+//! asks the shell to dim the screen and let the user drag out a rectangle,
+//! for tools like screenshots or zooming that need part of the screen
+//!
+//! the pick happens after the module is done with the message it's
+//! handling, the module is rendered again once it's done and `take` gives
+//! the result once
+//!
+//! example:
+//! ```
+//! // in update
+//! region::select(None);
+//!
+//! // in view
+//! if let Some(Selection::Picked(region)) = region::take() {
+//!     println!("picked {}x{}", region.width, region.height);
+//! }
+//! ```
+
+unsafe extern "C" {
+    /// host function to open the overlay on the output at `output`, or the
+    /// one with focus when it's `u32::MAX`
+    fn select_region(output: u32);
+    /// host function to copy the last region picked to `ptr`, returns how
+    /// many bytes were written or 0 if there's none waiting
+    fn read_region(ptr: u32, len: u32) -> u32;
+}
+
+/// the size of a region in the layout the host writes it in
+const REGION_SIZE: usize = 17;
+
+/// a rectangle on the output the region was picked on, in logical pixels from
+/// its top left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Picked(Region),
+    /// the user pressed escape or right clicked, a region was already being
+    /// picked, or the output isn't plugged in
+    Cancelled,
+}
+
+/// opens the overlay on the output at `output` in the order of
+/// `outputs::list`, or the one with focus when it's `None`
+pub fn select(output: Option<u32>) {
+    unsafe { select_region(output.unwrap_or(u32::MAX)) }
+}
+
+/// the outcome of the last `select`, `None` until it's done or after it was
+/// taken
+pub fn take() -> Option<Selection> {
+    let mut bytes = [0u8; REGION_SIZE];
+    let written = unsafe { read_region(bytes.as_mut_ptr() as u32, REGION_SIZE as u32) };
+    if written as usize != REGION_SIZE {
+        return None;
+    }
+
+    return Some(parse(&bytes));
+}
+
+/// a u8 that's 1 when a region was picked, then the x, y, width and height as
+/// little endian u32s
+fn parse(bytes: &[u8; REGION_SIZE]) -> Selection {
+    if bytes[0] == 0 {
+        return Selection::Cancelled;
+    }
+
+    let value = |index: usize| {
+        let start = 1 + index * 4;
+        u32::from_le_bytes([
+            bytes[start],
+            bytes[start + 1],
+            bytes[start + 2],
+            bytes[start + 3],
+        ])
+    };
+
+    Selection::Picked(Region {
+        x: value(0),
+        y: value(1),
+        width: value(2),
+        height: value(3),
+    })
+}
@@ -0,0 +1,60 @@
+use super::{IntoRegister, RegisterTrait};
+
+/// sends the module `AudioEvent::LevelsChanged` with the levels of the
+/// default output and input, for a vu-meter next to the volume slider. the
+/// module needs a service event function (see `create_module!`) to get them
+///
+/// example:
+/// ```
+/// AudioMeter::per_second(30)
+/// ```
+#[derive(Debug)]
+pub struct AudioMeter {
+    /// how many times a second the levels are sent, between 1 - 60
+    ///
+    /// the shell measures at the fastest rate any module asked for, so a
+    /// module can get them more often than it asked
+    rate: u32,
+}
+
+impl AudioMeter {
+    pub fn per_second(rate: u32) -> Self {
+        Self { rate }
+    }
+}
+
+impl Default for AudioMeter {
+    fn default() -> Self {
+        Self::per_second(25)
+    }
+}
+
+impl RegisterTrait for AudioMeter {
+    fn id(&self) -> u16 {
+        AudioMeter::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        AudioMeter::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        0
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        return Some(self.rate.to_le_bytes().to_vec());
+    }
+}
+
+impl IntoRegister for AudioMeter {}
+
+impl AudioMeter {
+    pub const fn const_id() -> u16 {
+        0x00_11
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
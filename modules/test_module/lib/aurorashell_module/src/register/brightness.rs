@@ -0,0 +1,81 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use super::{IntoRegister, RegisterTrait};
+
+/// subscribes to the brightness of the screen's backlight
+///
+/// the shell only runs the service on machines with a backlight, like
+/// laptops, see `services::version(Service::Brightness)`
+///
+/// example:
+/// ```
+/// Brightness::BRIGHTNESS_CHANGED
+/// ```
+#[derive(Debug)]
+pub struct Brightness(u8);
+
+impl Brightness {
+    /// subscribes to the brightness changing, or a backlight being added or
+    /// removed
+    pub const BRIGHTNESS_CHANGED: Self = Self(0b_0000_0001);
+}
+
+impl Brightness {
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    pub fn all() -> Self {
+        Self(0b0000_0001)
+    }
+}
+
+impl Default for Brightness {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl BitOr for Brightness {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Brightness {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl RegisterTrait for Brightness {
+    fn id(&self) -> u16 {
+        Brightness::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Brightness::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        self.0 as u32
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        return None;
+    }
+}
+
+impl IntoRegister for Brightness {}
+
+impl Brightness {
+    pub const fn const_id() -> u16 {
+        0x00_0B
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
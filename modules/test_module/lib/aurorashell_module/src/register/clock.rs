@@ -0,0 +1,74 @@
+use super::{IntoRegister, RegisterTrait};
+
+/// subscribes to the time, formatted by the shell, read it with
+/// `clock::now()`
+///
+/// the module is rendered again when the formatted text changes, so a
+/// `%H:%M` clock is rendered once a minute rather than on an `Interval`
+///
+/// example:
+/// ```
+/// Clock::new("%a %d %b %H:%M").timezone("Europe/Berlin")
+/// ```
+#[derive(Debug)]
+pub struct Clock {
+    /// strftime style, like `%H:%M:%S`
+    format: String,
+    /// an iana name, `None` for the local timezone
+    timezone: Option<String>,
+}
+
+impl Clock {
+    pub fn new(format: impl Into<String>) -> Self {
+        Self {
+            format: format.into(),
+            timezone: None,
+        }
+    }
+
+    /// formats the time in another timezone, like `America/New_York`
+    pub fn timezone(mut self, timezone: impl Into<String>) -> Self {
+        self.timezone = Some(timezone.into());
+        self
+    }
+}
+
+impl RegisterTrait for Clock {
+    fn id(&self) -> u16 {
+        Clock::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Clock::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        0
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        // the format then the timezone, each a u16 for the length followed by
+        // the bytes, an empty timezone is the local one
+        let mut bytes: Vec<u8> = vec![];
+        let timezone = self.timezone.as_deref().unwrap_or("");
+
+        for string in [self.format.as_str(), timezone] {
+            bytes.extend((string.len() as u16).to_le_bytes());
+            bytes.extend(string.as_bytes());
+        }
+
+        return Some(bytes);
+    }
+}
+
+impl IntoRegister for Clock {}
+
+impl Clock {
+    pub const fn const_id() -> u16 {
+        0x00_0C
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
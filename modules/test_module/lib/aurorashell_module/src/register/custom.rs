@@ -0,0 +1,68 @@
+use super::{IntoRegister, RegisterTrait};
+
+/// subscribes to the output of sensors (scripts) set in the shell's config
+///
+/// example:
+/// ```
+/// Custom::sensor("cpu_temp").and("weather")
+/// ```
+#[derive(Debug)]
+pub struct Custom {
+    /// the names of the sensors, matches `name` in the config
+    sensors: Vec<String>,
+}
+
+impl Custom {
+    pub fn sensor(name: impl Into<String>) -> Self {
+        Self {
+            sensors: vec![name.into()],
+        }
+    }
+
+    /// subscribes to another sensor
+    pub fn and(mut self, name: impl Into<String>) -> Self {
+        self.sensors.push(name.into());
+        self
+    }
+}
+
+impl RegisterTrait for Custom {
+    fn id(&self) -> u16 {
+        Custom::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Custom::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        0
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        // a u16 for the amount of names then each name is a u16 for the length
+        // of the name followed by the name's bytes
+        let mut bytes: Vec<u8> = vec![];
+
+        bytes.extend((self.sensors.len() as u16).to_le_bytes());
+
+        for name in &self.sensors {
+            bytes.extend((name.len() as u16).to_le_bytes());
+            bytes.extend(name.as_bytes());
+        }
+
+        return Some(bytes);
+    }
+}
+
+impl IntoRegister for Custom {}
+
+impl Custom {
+    pub const fn const_id() -> u16 {
+        0x00_04
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
@@ -0,0 +1,96 @@
+use super::{IntoRegister, RegisterTrait};
+
+/// relays what a program on dbus sends to the module, the properties of
+/// `interface` on the object at `path` owned by the bus name `name`, and the
+/// interface's other signals. the module is rendered again with each of them
+///
+/// the bus name has to be listed for the module under `[modules.dbus]` in
+/// the shell's config, otherwise the register is ignored. modules with a
+/// service event function are given what was sent with `DbusEvent`. a module
+/// can have more than one `Dbus` register
+///
+/// example:
+/// ```
+/// Dbus::session(
+///     "org.mpris.MediaPlayer2.spotify",
+///     "/org/mpris/MediaPlayer2",
+///     "org.mpris.MediaPlayer2.Player",
+/// )
+/// ```
+#[derive(Debug)]
+pub struct Dbus {
+    system: bool,
+    name: String,
+    path: String,
+    interface: String,
+}
+
+impl Dbus {
+    /// watches something on the session bus, where most desktop programs are
+    pub fn session(
+        name: impl Into<String>,
+        path: impl Into<String>,
+        interface: impl Into<String>,
+    ) -> Self {
+        Self {
+            system: false,
+            name: name.into(),
+            path: path.into(),
+            interface: interface.into(),
+        }
+    }
+
+    /// watches something on the system bus, like `org.freedesktop.UPower`
+    pub fn system(
+        name: impl Into<String>,
+        path: impl Into<String>,
+        interface: impl Into<String>,
+    ) -> Self {
+        Self {
+            system: true,
+            ..Self::session(name, path, interface)
+        }
+    }
+}
+
+impl RegisterTrait for Dbus {
+    fn id(&self) -> u16 {
+        Dbus::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Dbus::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        // the first bit is set for the system bus
+        self.system as u32
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        // a u16 for the amount of strings (always 3) then the bus name, path
+        // and interface, each a u16 for its length followed by its bytes
+        let mut bytes: Vec<u8> = vec![];
+
+        bytes.extend(3u16.to_le_bytes());
+
+        for string in [&self.name, &self.path, &self.interface] {
+            bytes.extend((string.len() as u16).to_le_bytes());
+            bytes.extend(string.as_bytes());
+        }
+
+        return Some(bytes);
+    }
+}
+
+impl IntoRegister for Dbus {}
+
+impl Dbus {
+    pub const fn const_id() -> u16 {
+        0x00_16
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        true
+    }
+}
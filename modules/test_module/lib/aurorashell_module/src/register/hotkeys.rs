@@ -0,0 +1,130 @@
+use std::sync::{LazyLock, Mutex};
+
+use super::{IntoRegister, RegisterTrait};
+
+/// gets called when its hotkey is pressed and returns the message id to run
+/// through `update`, or 0 to do nothing. the message gets no data
+pub type HotkeyFn = Box<dyn Fn() -> u32 + Send>;
+
+/// the functions of the hotkeys, in the order they were bound
+static HANDLERS: LazyLock<Mutex<Vec<HotkeyFn>>> = LazyLock::new(|| Mutex::new(vec![]));
+
+/// keybinds that work anywhere on the desktop, through the desktop portal's
+/// global shortcuts. the desktop may ask the user to confirm them or pick
+/// other keys the first time
+///
+/// `trigger` is the keys the module would like, in the portal's format
+/// (like `LOGO+a` or `CTRL+ALT+t`), which is only a suggestion. an empty
+/// one leaves it to the user
+///
+/// example:
+/// ```
+/// Hotkeys::new().bind(
+///     "toggle-popup",
+///     "open or close the launcher",
+///     "LOGO+space",
+///     Box::new(|| Message::TogglePopup.into()),
+/// )
+/// ```
+pub struct Hotkeys {
+    hotkeys: Vec<Hotkey>,
+}
+
+#[derive(Debug)]
+struct Hotkey {
+    /// unique within the module
+    id: String,
+    description: String,
+    trigger: String,
+}
+
+impl Hotkeys {
+    /// the hotkeys of an older `Hotkeys` are forgotten, a module only has
+    /// one
+    pub fn new() -> Self {
+        HANDLERS.lock().unwrap().clear();
+        Self { hotkeys: vec![] }
+    }
+
+    pub fn bind(mut self, id: &str, description: &str, trigger: &str, f: HotkeyFn) -> Self {
+        HANDLERS.lock().unwrap().push(f);
+        self.hotkeys.push(Hotkey {
+            id: id.to_string(),
+            description: description.to_string(),
+            trigger: trigger.to_string(),
+        });
+        self
+    }
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Hotkeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hotkeys")
+            .field("hotkeys", &self.hotkeys)
+            .finish()
+    }
+}
+
+impl RegisterTrait for Hotkeys {
+    fn id(&self) -> u16 {
+        Hotkeys::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Hotkeys::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        0
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        // the amount of hotkeys as a u16, then each one's id, description and
+        // trigger, each a u16 for the length followed by the bytes
+        let mut bytes: Vec<u8> = vec![];
+        bytes.extend((self.hotkeys.len() as u16).to_le_bytes());
+
+        for hotkey in &self.hotkeys {
+            for string in [&hotkey.id, &hotkey.description, &hotkey.trigger] {
+                bytes.extend((string.len() as u16).to_le_bytes());
+                bytes.extend(string.as_bytes());
+            }
+        }
+
+        return Some(bytes);
+    }
+}
+
+impl IntoRegister for Hotkeys {}
+
+impl Hotkeys {
+    pub const fn const_id() -> u16 {
+        0x00_0E
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
+
+/// defines an external function to be called by the wasm host when a hotkey
+/// was pressed, `index` is the order it was bound in
+///
+/// returns the message id merged into a u64 like `run_callback`, with no
+/// data, or 0 to do nothing
+#[unsafe(no_mangle)]
+fn run_hotkey(index: u32) -> u64 {
+    let handlers = HANDLERS.lock().unwrap();
+    let Some(f) = handlers.get(index as usize) else {
+        eprintln!("module: there's no hotkey with index {}", index);
+        return 0;
+    };
+
+    return (f() as u64) << 32;
+}
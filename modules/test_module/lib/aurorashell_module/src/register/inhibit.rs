@@ -0,0 +1,78 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use super::{IntoRegister, RegisterTrait};
+
+/// renders the module again when the session starts or stops being kept
+/// from going idle, read it with `inhibit::inhibited`
+///
+/// example:
+/// ```
+/// Inhibit::INHIBIT_CHANGED
+/// ```
+#[derive(Debug)]
+pub struct Inhibit(u8);
+
+impl Inhibit {
+    /// subscribes to the idle inhibitor being turned on or off
+    pub const INHIBIT_CHANGED: Self = Self(0b_0000_0001);
+}
+
+impl Inhibit {
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    pub fn all() -> Self {
+        Self(0b0000_0001)
+    }
+}
+
+impl Default for Inhibit {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl BitOr for Inhibit {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Inhibit {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl RegisterTrait for Inhibit {
+    fn id(&self) -> u16 {
+        Inhibit::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Inhibit::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        self.0 as u32
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        return None;
+    }
+}
+
+impl IntoRegister for Inhibit {}
+
+impl Inhibit {
+    pub const fn const_id() -> u16 {
+        0x00_12
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
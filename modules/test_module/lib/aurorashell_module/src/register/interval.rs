@@ -103,10 +103,10 @@ impl RegisterTrait for Interval {
         // 16 bytes (0x10) for extra data
         let mut bytes: [u8; 0x10] = [0; 0x10];
 
-        let milliseconds_bytes: [u8; 0x08] = self.milliseconds.to_be_bytes();
+        let milliseconds_bytes: [u8; 0x08] = self.milliseconds.to_le_bytes();
         bytes[0x00..0x08].copy_from_slice(&milliseconds_bytes);
 
-        let offset_bytes: [u8; 0x04] = self.offset.to_be_bytes();
+        let offset_bytes: [u8; 0x04] = self.offset.to_le_bytes();
         bytes[0x08..0x0C].copy_from_slice(&offset_bytes);
 
         return Some(bytes.to_vec());
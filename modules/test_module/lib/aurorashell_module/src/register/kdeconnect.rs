@@ -0,0 +1,81 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use super::{IntoRegister, RegisterTrait};
+
+/// subscribes to the devices paired with kde connect, like phones, see
+/// `kdeconnect::devices()`
+///
+/// example:
+/// ```
+/// KdeConnect::DEVICES_CHANGED | KdeConnect::NOTIFICATIONS
+/// ```
+#[derive(Debug)]
+pub struct KdeConnect(u8);
+
+impl KdeConnect {
+    /// subscribes to devices connecting or disconnecting, and their battery
+    /// and what's playing on them changing
+    pub const DEVICES_CHANGED: Self = Self(0b_0000_0001);
+    /// subscribes to notifications being posted or dismissed on a device
+    pub const NOTIFICATIONS: Self = Self(0b_0000_0010);
+}
+
+impl KdeConnect {
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    pub fn all() -> Self {
+        Self(0b0000_0011)
+    }
+}
+
+impl Default for KdeConnect {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl BitOr for KdeConnect {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for KdeConnect {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl RegisterTrait for KdeConnect {
+    fn id(&self) -> u16 {
+        KdeConnect::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        KdeConnect::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        self.0 as u32
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        return None;
+    }
+}
+
+impl IntoRegister for KdeConnect {}
+
+impl KdeConnect {
+    pub const fn const_id() -> u16 {
+        0x00_0D
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
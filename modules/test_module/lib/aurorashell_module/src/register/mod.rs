@@ -1,10 +1,38 @@
+mod audio_meter;
+mod brightness;
+mod clock;
+mod custom;
+mod dbus;
+mod hotkeys;
+mod inhibit;
 mod interval;
+mod kdeconnect;
+mod network;
+mod outputs;
 mod pulseaudio;
+mod sysinfo;
+mod theme;
+mod watch;
+mod weather;
 
 use std::{collections::HashSet, fmt::Debug};
 
+pub use audio_meter::*;
+pub use brightness::*;
+pub use clock::*;
+pub use custom::*;
+pub use dbus::*;
+pub use hotkeys::*;
+pub use inhibit::*;
 pub use interval::*;
+pub use kdeconnect::*;
+pub use network::*;
+pub use outputs::*;
 pub use pulseaudio::*;
+pub use sysinfo::*;
+pub use theme::*;
+pub use watch::*;
+pub use weather::*;
 
 #[derive(Debug, Default)]
 pub struct Registers {
@@ -13,7 +41,9 @@ pub struct Registers {
 
 impl Registers {
     // only increase version when potential breaking changes have been made
-    const SERIALIZED_VERSION: [u8; 2] = [0x00, 0x01];
+    //
+    // version 1 was big endian, from 2 everything is little endian
+    const SERIALIZED_VERSION: u16 = 2;
 
     /// serializes `Registers` into a binary table of bitflags
     ///
@@ -23,10 +53,10 @@ impl Registers {
         let mut serialized_bytes: Vec<u8> = vec![0; 0x10];
 
         // add the version
-        serialized_bytes[0x04..0x06].copy_from_slice(&Self::SERIALIZED_VERSION);
+        serialized_bytes[0x04..0x06].copy_from_slice(&Self::SERIALIZED_VERSION.to_le_bytes());
 
         // add how many registers are in the table
-        let n_registers_bytes: [u8; 0x02] = (self.registers.len() as u16).to_be_bytes();
+        let n_registers_bytes: [u8; 0x02] = (self.registers.len() as u16).to_le_bytes();
         serialized_bytes[0x06..0x08].copy_from_slice(&n_registers_bytes);
 
         // this gets incremented as extra data is added
@@ -36,7 +66,7 @@ impl Registers {
         // allowed to have duplicates
         let mut seen: HashSet<u16> = HashSet::new();
 
-        // bytes for the extra data 
+        // bytes for the extra data
         let mut extra_data: Vec<u8> = vec![];
 
         // adds the entry for the id per
@@ -53,14 +83,14 @@ impl Registers {
             // 16 bytes (0x10) per entry in the registers table
             let mut entry_bytes: [u8; 0x10] = [0; 0x10];
 
-            let id_bytes = id.to_be_bytes();
+            let id_bytes = id.to_le_bytes();
             entry_bytes[0x00..0x02].copy_from_slice(&id_bytes);
 
-            let registers_bytes = register.registers().to_be_bytes();
+            let registers_bytes = register.registers().to_le_bytes();
             entry_bytes[0x02..0x06].copy_from_slice(&registers_bytes);
 
             if let Some(extra_data_bytes) = register.serialize() {
-                let offset_bytes: [u8; 0x04] = (offset).to_be_bytes();
+                let offset_bytes: [u8; 0x04] = (offset).to_le_bytes();
                 entry_bytes[0x06..0x0A].copy_from_slice(&offset_bytes);
 
                 offset += extra_data_bytes.len() as u32;
@@ -73,9 +103,9 @@ impl Registers {
 
         // add the extra data to the output
         serialized_bytes.extend(extra_data);
-        
+
         // then add the size of the bytes
-        let size_bytes: [u8; 0x04] = (serialized_bytes.len() as u32).to_be_bytes();
+        let size_bytes: [u8; 0x04] = (serialized_bytes.len() as u32).to_le_bytes();
         serialized_bytes[0x00..0x04].copy_from_slice(&size_bytes);
 
         return serialized_bytes.into_boxed_slice();
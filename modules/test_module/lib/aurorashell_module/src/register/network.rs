@@ -0,0 +1,84 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use super::{IntoRegister, RegisterTrait};
+
+/// subscribes to the state of the network from NetworkManager
+///
+/// example:
+/// ```
+/// Network::ACTIVE_CONNECTION_CHANGED | Network::WIRELESS_STRENGTH_CHANGED
+/// ```
+#[derive(Debug)]
+pub struct Network(u8);
+
+impl Network {
+    /// subscribes to the saved connections changing
+    pub const CONNECTIONS_CHANGED: Self = Self(0b_0000_0001);
+    /// subscribes to the active connection changing, like connecting to
+    /// another wifi network
+    pub const ACTIVE_CONNECTION_CHANGED: Self = Self(0b_0000_0010);
+    /// subscribes to the wifi signal strength changing
+    pub const WIRELESS_STRENGTH_CHANGED: Self = Self(0b_0000_0100);
+    /// subscribes to wifi being turned on or off
+    pub const WIFI_ENABLED_CHANGED: Self = Self(0b_0000_1000);
+}
+
+impl Network {
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    pub fn all() -> Self {
+        Self(0b0000_1111)
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl BitOr for Network {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Network {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl RegisterTrait for Network {
+    fn id(&self) -> u16 {
+        Network::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Network::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        self.0 as u32
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        return None;
+    }
+}
+
+impl IntoRegister for Network {}
+
+impl Network {
+    pub const fn const_id() -> u16 {
+        0x00_05
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
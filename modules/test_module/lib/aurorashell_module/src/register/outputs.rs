@@ -0,0 +1,79 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use super::{IntoRegister, RegisterTrait};
+
+/// renders the module again when the monitors change, read them with
+/// `outputs::list`
+///
+/// example:
+/// ```
+/// Outputs::OUTPUTS_CHANGED
+/// ```
+#[derive(Debug)]
+pub struct Outputs(u8);
+
+impl Outputs {
+    /// subscribes to a monitor being plugged in, unplugged, rotated or
+    /// rescaled
+    pub const OUTPUTS_CHANGED: Self = Self(0b_0000_0001);
+}
+
+impl Outputs {
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    pub fn all() -> Self {
+        Self(0b0000_0001)
+    }
+}
+
+impl Default for Outputs {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl BitOr for Outputs {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Outputs {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl RegisterTrait for Outputs {
+    fn id(&self) -> u16 {
+        Outputs::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Outputs::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        self.0 as u32
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        return None;
+    }
+}
+
+impl IntoRegister for Outputs {}
+
+impl Outputs {
+    pub const fn const_id() -> u16 {
+        0x00_09
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
@@ -20,6 +20,9 @@ impl PulseAudio {
     pub const SINK_PROFILE_CHANGED: Self = Self(0b_0010_0000);
     /// subscribes to default sink's current profile changing
     pub const SOURCE_PROFILE_CHANGED: Self = Self(0b_0100_0000);
+    /// subscribes to applications starting or stopping playing or
+    /// recording, or their streams changing
+    pub const STREAMS_CHANGED: Self = Self(0b_1000_0000);
 }
 
 impl PulseAudio {
@@ -28,7 +31,7 @@ impl PulseAudio {
     }
 
     pub fn all() -> Self {
-        Self(0b0111_1111)
+        Self(0b1111_1111)
     }
 }
 
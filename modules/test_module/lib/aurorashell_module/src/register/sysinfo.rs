@@ -0,0 +1,108 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use super::{IntoRegister, RegisterTrait};
+
+/// subscribes to the cpu, memory, disk and temperature readings of the
+/// system, sampled every `interval` (2 seconds unless set)
+///
+/// the shell samples as often as the module with the shortest interval asks
+/// for, and no more often than every half a second
+///
+/// example:
+/// ```
+/// (Sysinfo::CPU_USAGE_CHANGED | Sysinfo::MEMORY_CHANGED).interval_millis(1000)
+/// ```
+#[derive(Debug)]
+pub struct Sysinfo {
+    events: u8,
+    /// 0 for the shell's default
+    interval_ms: u64,
+}
+
+impl Sysinfo {
+    /// subscribes to the cpu usage changing
+    pub const CPU_USAGE_CHANGED: Self = Self::from_events(0b_0000_0001);
+    /// subscribes to the space used on disks changing
+    pub const DISK_CHANGED: Self = Self::from_events(0b_0000_0100);
+    /// subscribes to the memory in use changing
+    pub const MEMORY_CHANGED: Self = Self::from_events(0b_0000_0010);
+    /// subscribes to temperatures changing
+    pub const TEMPERATURE_CHANGED: Self = Self::from_events(0b_0000_1000);
+}
+
+impl Sysinfo {
+    const fn from_events(events: u8) -> Self {
+        Self {
+            events,
+            interval_ms: 0,
+        }
+    }
+
+    pub fn none() -> Self {
+        Self::from_events(0)
+    }
+
+    pub fn all() -> Self {
+        Self::from_events(0b0000_1111)
+    }
+
+    /// how often the system should be sampled
+    pub fn interval_millis(mut self, millis: u64) -> Self {
+        self.interval_ms = millis;
+        self
+    }
+}
+
+impl Default for Sysinfo {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl BitOr for Sysinfo {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self {
+            events: self.events | rhs.events,
+            interval_ms: self.interval_ms.max(rhs.interval_ms),
+        }
+    }
+}
+
+impl BitOrAssign for Sysinfo {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.events |= rhs.events;
+        self.interval_ms = self.interval_ms.max(rhs.interval_ms);
+    }
+}
+
+impl RegisterTrait for Sysinfo {
+    fn id(&self) -> u16 {
+        Sysinfo::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Sysinfo::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        self.events as u32
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        return Some(self.interval_ms.to_le_bytes().to_vec());
+    }
+}
+
+impl IntoRegister for Sysinfo {}
+
+impl Sysinfo {
+    pub const fn const_id() -> u16 {
+        0x00_0A
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
@@ -0,0 +1,78 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use super::{IntoRegister, RegisterTrait};
+
+/// renders the module again when the shell's theme changes, read it with
+/// `theme::palette`
+///
+/// example:
+/// ```
+/// Theme::THEME_CHANGED
+/// ```
+#[derive(Debug)]
+pub struct Theme(u8);
+
+impl Theme {
+    /// subscribes to `colors.toml` being reloaded
+    pub const THEME_CHANGED: Self = Self(0b_0000_0001);
+}
+
+impl Theme {
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    pub fn all() -> Self {
+        Self(0b0000_0001)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl BitOr for Theme {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Theme {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl RegisterTrait for Theme {
+    fn id(&self) -> u16 {
+        Theme::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Theme::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        self.0 as u32
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        return None;
+    }
+}
+
+impl IntoRegister for Theme {}
+
+impl Theme {
+    pub const fn const_id() -> u16 {
+        0x00_0F
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
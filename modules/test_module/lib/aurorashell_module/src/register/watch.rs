@@ -0,0 +1,74 @@
+use super::{IntoRegister, RegisterTrait};
+
+/// renders the module again when a file matching one of the globs is
+/// created, written to or removed. `~` is the home directory, `*` matches
+/// inside a directory and `**` goes into its subdirectories
+///
+/// the files have to be inside the directories listed for the module under
+/// `[modules.watch]` in the shell's config, the other globs are ignored.
+/// modules with a service event function are also told which file changed
+/// with `WatchEvent::FileChanged`
+///
+/// example:
+/// ```
+/// Watch::glob("~/notes/todo.md").and("~/notes/daily/*.md")
+/// ```
+#[derive(Debug)]
+pub struct Watch {
+    globs: Vec<String>,
+}
+
+impl Watch {
+    pub fn glob(glob: impl Into<String>) -> Self {
+        Self {
+            globs: vec![glob.into()],
+        }
+    }
+
+    /// watches the files matching another glob
+    pub fn and(mut self, glob: impl Into<String>) -> Self {
+        self.globs.push(glob.into());
+        self
+    }
+}
+
+impl RegisterTrait for Watch {
+    fn id(&self) -> u16 {
+        Watch::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Watch::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        0
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        // a u16 for the amount of globs then each glob is a u16 for its
+        // length followed by its bytes
+        let mut bytes: Vec<u8> = vec![];
+
+        bytes.extend((self.globs.len() as u16).to_le_bytes());
+
+        for glob in &self.globs {
+            bytes.extend((glob.len() as u16).to_le_bytes());
+            bytes.extend(glob.as_bytes());
+        }
+
+        return Some(bytes);
+    }
+}
+
+impl IntoRegister for Watch {}
+
+impl Watch {
+    pub const fn const_id() -> u16 {
+        0x00_15
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
@@ -0,0 +1,78 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use super::{IntoRegister, RegisterTrait};
+
+/// renders the module again when the weather is fetched, read it with
+/// `weather::current`
+///
+/// example:
+/// ```
+/// Weather::WEATHER_CHANGED
+/// ```
+#[derive(Debug)]
+pub struct Weather(u8);
+
+impl Weather {
+    /// subscribes to the weather being fetched, or failing to be
+    pub const WEATHER_CHANGED: Self = Self(0b_0000_0001);
+}
+
+impl Weather {
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    pub fn all() -> Self {
+        Self(0b0000_0001)
+    }
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl BitOr for Weather {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Weather {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl RegisterTrait for Weather {
+    fn id(&self) -> u16 {
+        Weather::const_id()
+    }
+
+    fn allow_duplicates(&self) -> bool {
+        Weather::const_allow_duplicates()
+    }
+
+    fn registers(&self) -> u32 {
+        self.0 as u32
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        return None;
+    }
+}
+
+impl IntoRegister for Weather {}
+
+impl Weather {
+    pub const fn const_id() -> u16 {
+        0x00_06
+    }
+
+    pub const fn const_allow_duplicates() -> bool {
+        false
+    }
+}
@@ -0,0 +1,94 @@
+//! asks the shell for a screenshot of an output or part of one, like for a
+//! bar button that takes one and shows a thumbnail
+//!
+//! the screenshot is taken after the module is done with the message it's
+//! handling, the module is rendered again once it's saved and `take` gives
+//! the path once. `Image::screenshot` draws it
+//!
+//! the module has to be listed in `screenshot` under `[modules]` in the
+//! shell's config, and it gets one screenshot at a time. asking for another
+//! before the last one is done fails
+//!
+//! example:
+//! ```
+//! // in update
+//! screenshot::output(None);
+//!
+//! // in view
+//! if let Some(Capture::Saved(path)) = screenshot::take() {
+//!     self.thumbnail = Some(path);
+//! }
+//! ```
+
+use crate::region::Region;
+
+unsafe extern "C" {
+    /// host function to take a screenshot of the output at `output`, or of
+    /// every output when it's `u32::MAX`
+    fn capture_output(output: u32);
+    /// host function to take a screenshot of a rectangle on the output at
+    /// `output`, returns 0 if it was asked for, 1 if the rectangle is empty
+    /// and 2 if the module isn't allowed to or one is being taken
+    fn capture_region(output: u32, x: u32, y: u32, width: u32, height: u32) -> u32;
+    /// host function to get the size of the last screenshot's outcome in
+    /// bytes, 0 if there's none waiting
+    fn screenshot_size() -> u32;
+    /// host function to copy the last screenshot's outcome to `ptr`, returns
+    /// how many bytes were written or 0 if there's none waiting
+    fn read_screenshot(ptr: u32, len: u32) -> u32;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Capture {
+    /// the path the png was saved to
+    Saved(String),
+    /// why it couldn't be taken, like the output being unplugged or `grim`
+    /// not being installed
+    Failed(String),
+}
+
+/// takes a screenshot of the output at `output` in the order of
+/// `outputs::list`, or of every output when it's `None`. when it's refused
+/// `take` gives why
+pub fn output(output: Option<u32>) {
+    unsafe { capture_output(output.unwrap_or(u32::MAX)) }
+}
+
+/// takes a screenshot of `region` on the output at `output`, like one picked
+/// with `region::select(Some(output))`. `false` when the region is empty,
+/// the module isn't allowed to take screenshots or one is being taken
+pub fn region(output: u32, region: Region) -> bool {
+    let result = unsafe { capture_region(output, region.x, region.y, region.width, region.height) };
+
+    return result == 0;
+}
+
+/// the outcome of the last screenshot, `None` until it's done or after it
+/// was taken
+pub fn take() -> Option<Capture> {
+    let size = unsafe { screenshot_size() };
+    if size == 0 {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = vec![0; size as usize];
+    let written = unsafe { read_screenshot(bytes.as_mut_ptr() as u32, size) };
+    if written != size {
+        return None;
+    }
+
+    return parse(&bytes);
+}
+
+/// a u8 that's 1 when it was saved, then the path or the reason it failed as
+/// a u16 length followed by that many bytes of utf-8
+fn parse(bytes: &[u8]) -> Option<Capture> {
+    let (&saved, rest) = bytes.split_first()?;
+    let len = u16::from_le_bytes(rest.get(..2)?.try_into().ok()?) as usize;
+    let text = String::from_utf8_lossy(rest.get(2..2 + len)?).into_owned();
+
+    match saved {
+        0 => Some(Capture::Failed(text)),
+        _ => Some(Capture::Saved(text)),
+    }
+}
@@ -0,0 +1,160 @@
+//! asks the shell which services are running, so a module can hide the parts
+//! of its ui that need a service this system doesn't have
+//!
+//! the shell renders modules again when a service starts or stops, so
+//! checking in `view` is enough to stay up to date
+//!
+//! example:
+//! ```
+//! if services::available(Service::Network) {
+//!     // show the wifi segment
+//! }
+//! ```
+//!
+//! modules can also be told about the events of the services they
+//! registered for by giving `create_module!` a function taking a
+//! `&ServiceEvent` as its last argument. it returns the id of the message to
+//! run through `update` (0 to do nothing), which gets the event as its data
+//!
+//! example:
+//! ```
+//! fn service_event(event: &ServiceEvent) -> u32 {
+//!     match event {
+//!         ServiceEvent::Audio(AudioEvent::DefaultSinkChanged { .. }) => {
+//!             Message::Audio(event.clone()).into()
+//!         }
+//!         _ => 0,
+//!     }
+//! }
+//!
+//! // with `#[derive(ModuleMessage)]` on `Message`
+//! Audio(ServiceEvent),
+//! ```
+
+use crate::audio::{self, AudioEvent};
+use crate::brightness::{self, BrightnessEvent};
+use crate::custom::{self, CustomEvent};
+use crate::dbus::{self, DbusEvent};
+use crate::inhibit::{self, InhibitEvent};
+use crate::network::{self, NetworkEvent};
+use crate::sysinfo::{self, SysinfoEvent};
+use crate::watch::{self, WatchEvent};
+use crate::weather::{self, WeatherEvent};
+
+unsafe extern "C" {
+    /// host function to get the version of a running service, 0 if the
+    /// service isn't running
+    fn service_version(service_id: u32) -> u32;
+    /// host function to copy the event `service_event` was called with to
+    /// `ptr`, returns how many bytes were written or 0 if it didn't fit in
+    /// `len`
+    fn read_service_event(ptr: u32, len: u32) -> u32;
+}
+
+/// an event from a service the module registered for
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ServiceEvent {
+    Audio(AudioEvent),
+    Brightness(BrightnessEvent),
+    Custom(CustomEvent),
+    Dbus(DbusEvent),
+    Inhibit(InhibitEvent),
+    Network(NetworkEvent),
+    Sysinfo(SysinfoEvent),
+    Watch(WatchEvent),
+    Weather(WeatherEvent),
+}
+
+/// the services a module can ask about, the ids are the same as their
+/// registers
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    PulseAudio = 0x00_01,
+    Interval = 0x00_03,
+    Custom = 0x00_04,
+    Network = 0x00_05,
+    Weather = 0x00_06,
+    Ipc = 0x00_07,
+    Tray = 0x00_08,
+    Sysinfo = 0x00_0A,
+    Brightness = 0x00_0B,
+    Clock = 0x00_0C,
+    KdeConnect = 0x00_0D,
+    Hotkeys = 0x00_0E,
+    Appearance = 0x00_10,
+    Inhibit = 0x00_12,
+    Screenshot = 0x00_13,
+    Session = 0x00_14,
+    Watch = 0x00_15,
+    Dbus = 0x00_16,
+}
+
+/// the version of the data the service gives modules, `None` if it isn't
+/// running
+pub fn version(service: Service) -> Option<u32> {
+    match unsafe { service_version(service as u32) } {
+        0 => None,
+        version => Some(version),
+    }
+}
+
+/// whether the service is running
+pub fn available(service: Service) -> bool {
+    version(service).is_some()
+}
+
+/// what the `service_event` export made by `create_module!` runs, reads the
+/// event and hands it to `f`
+///
+/// returns the message id merged into a u64 like `run_callback`, with the
+/// event leaked as its data, or 0 to do nothing
+#[doc(hidden)]
+pub fn run_event(service: u32, len: u32, f: impl FnOnce(&ServiceEvent) -> u32) -> u64 {
+    let mut bytes: Vec<u8> = vec![0; len as usize];
+    let written = match len {
+        0 => 0,
+        len => unsafe { read_service_event(bytes.as_mut_ptr() as u32, len) },
+    };
+    bytes.truncate(written as usize);
+
+    let event = match service {
+        id if id == Service::PulseAudio as u32 => {
+            audio::parse_event(&bytes).map(ServiceEvent::Audio)
+        }
+        id if id == Service::Brightness as u32 => {
+            brightness::parse_event(&bytes).map(ServiceEvent::Brightness)
+        }
+        id if id == Service::Custom as u32 => custom::parse_event(&bytes).map(ServiceEvent::Custom),
+        id if id == Service::Dbus as u32 => dbus::parse_event(&bytes).map(ServiceEvent::Dbus),
+        id if id == Service::Inhibit as u32 => {
+            inhibit::parse_event(&bytes).map(ServiceEvent::Inhibit)
+        }
+        id if id == Service::Network as u32 => {
+            network::parse_event(&bytes).map(ServiceEvent::Network)
+        }
+        id if id == Service::Sysinfo as u32 => {
+            sysinfo::parse_event(&bytes).map(ServiceEvent::Sysinfo)
+        }
+        id if id == Service::Watch as u32 => watch::parse_event(&bytes).map(ServiceEvent::Watch),
+        id if id == Service::Weather as u32 => {
+            weather::parse_event(&bytes).map(ServiceEvent::Weather)
+        }
+        _ => None,
+    };
+    // a service or kind of event this version of the library doesn't know
+    let Some(event) = event else {
+        return 0;
+    };
+
+    let message_id = f(&event);
+    if message_id == 0 {
+        return 0;
+    }
+
+    let leaked_data = Box::leak(Box::new(event));
+    let data_ptr = leaked_data as *mut ServiceEvent;
+
+    return (message_id as u64) << 32 | data_ptr as u32 as u64;
+}
@@ -0,0 +1,79 @@
+//! suspends, hibernates, reboots or powers off the system and locks the
+//! session, for a power menu
+//!
+//! only modules listed in `session = ["name"]` under `[modules]` in the
+//! shell's config can, the others get `RequestError::NotAllowed`
+//!
+//! example:
+//! ```
+//! if session::capabilities().is_some_and(|can| can.power_off) {
+//!     // show the power off button
+//! }
+//!
+//! // in update
+//! session::request(Action::PowerOff)?;
+//! ```
+
+unsafe extern "C" {
+    /// host function to run an action, returns 0 if it was sent
+    fn session_request(action: u32) -> u32;
+    /// host function to get what the system can do as one bit for each
+    /// action, 0 if the shell doesn't know yet
+    fn session_capabilities() -> u32;
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Suspend = 0,
+    Hibernate = 1,
+    Reboot = 2,
+    PowerOff = 3,
+    /// asks the screen locker to lock the session, which only does
+    /// something when one is listening to logind
+    LockSession = 4,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestError {
+    /// the module isn't listed in `session` under `[modules]`
+    NotAllowed,
+    /// the shell doesn't know the action
+    Unsupported,
+}
+
+/// what logind says the system can do, locking always can
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub suspend: bool,
+    pub hibernate: bool,
+    pub reboot: bool,
+    pub power_off: bool,
+}
+
+/// what the system can do, `None` when the shell can't reach logind
+pub fn capabilities() -> Option<Capabilities> {
+    let bits = unsafe { session_capabilities() };
+    if bits == 0 {
+        return None;
+    }
+
+    let can = |action: Action| bits & (1 << action as u32) != 0;
+
+    return Some(Capabilities {
+        suspend: can(Action::Suspend),
+        hibernate: can(Action::Hibernate),
+        reboot: can(Action::Reboot),
+        power_off: can(Action::PowerOff),
+    });
+}
+
+/// asks the shell to run `action`, it happens once the module is done with
+/// the message it's handling. polkit may ask for a password first
+pub fn request(action: Action) -> Result<(), RequestError> {
+    match unsafe { session_request(action as u32) } {
+        0 => Ok(()),
+        1 => Err(RequestError::NotAllowed),
+        _ => Err(RequestError::Unsupported),
+    }
+}
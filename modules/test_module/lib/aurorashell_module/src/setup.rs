@@ -5,7 +5,7 @@ use std::{
 
 use crate::{
     register::Registers,
-    surface::{LayerSurface, LayerSurfaceRaw, Limits, Margin},
+    surface::{LayerSurface, LayerSurfaceRaw},
 };
 
 #[derive(Debug)]
@@ -34,43 +34,7 @@ impl From<SetupData> for *const SetupFuncData {
         let layer_surfaces_raw: Vec<LayerSurfaceRaw> = leaked_self
             .layer_surfaces
             .iter()
-            .map(|surface| {
-                let mut size_flags = 0u8;
-                let mut size_x = 0u32;
-                let mut size_y = 0u32;
-
-                if let Some((x, y)) = surface.size {
-                    // 1st bit
-                    size_flags = size_flags | 0b001;
-                    if let Some(x) = x {
-                        // 2nd bit
-                        size_flags = size_flags | 0b010;
-                        size_x = x;
-                    }
-                    if let Some(y) = y {
-                        // 3rd bit
-                        size_flags = size_flags | 0b100;
-                        size_y = y;
-                    }
-                }
-
-                LayerSurfaceRaw {
-                    id: surface.id.get_id(),
-                    layer: surface.layer.clone() as u8,
-                    anchor: surface.anchor.0,
-                    size_flags,
-                    size_x,
-                    size_y,
-                    margin_ptr: (&surface.margin as *const Margin) as u32,
-                    limits_ptr: (&surface.limits as *const Limits) as u32,
-                    exclusive_zone: surface.exclusive_zone,
-                    keyboard_interactivity: surface.keyboard_interactivity.clone() as u8,
-                    pointer_interactivity: match surface.pointer_interactivity {
-                        false => 0,
-                        true => 1,
-                    },
-                }
-            })
+            .map(LayerSurface::raw)
             .collect();
 
         let leaked_layer_surfaces = Box::leak(Box::new(layer_surfaces_raw));
@@ -11,9 +11,100 @@ pub struct LayerSurface {
     pub size: Option<(Option<u32>, Option<u32>)>,
     pub margin: Margin,
     pub limits: Limits,
-    pub exclusive_zone: i32,
+    pub exclusive_zone: ExclusiveZone,
     pub keyboard_interactivity: KeyboardInteractivity,
     pub pointer_interactivity: bool,
+    /// what the surface is for, the host changes the settings a role doesn't
+    /// allow (with a warning) so `None` leaves it all up to the module
+    pub role: Option<SurfaceRole>,
+}
+
+impl LayerSurface {
+    /// a surface with the settings `role` usually has, which can still be
+    /// changed after
+    pub fn with_role(role: SurfaceRole) -> Self {
+        let surface = Self {
+            role: Some(role),
+            ..Default::default()
+        };
+
+        match role {
+            SurfaceRole::Bar => Self {
+                layer: Layer::Top,
+                anchor: Anchor::TOP_EDGE,
+                exclusive_zone: ExclusiveZone::Auto,
+                keyboard_interactivity: KeyboardInteractivity::None,
+                ..surface
+            },
+            SurfaceRole::Popup => Self {
+                layer: Layer::Top,
+                keyboard_interactivity: KeyboardInteractivity::OnDemand,
+                ..surface
+            },
+            SurfaceRole::Osd => Self {
+                layer: Layer::Overlay,
+                keyboard_interactivity: KeyboardInteractivity::None,
+                pointer_interactivity: false,
+                ..surface
+            },
+            SurfaceRole::Overlay => Self {
+                layer: Layer::Overlay,
+                keyboard_interactivity: KeyboardInteractivity::OnDemand,
+                ..surface
+            },
+            SurfaceRole::Background => Self {
+                layer: Layer::Background,
+                anchor: Anchor::all(),
+                exclusive_zone: ExclusiveZone::Fixed(-1),
+                keyboard_interactivity: KeyboardInteractivity::None,
+                ..surface
+            },
+        }
+    }
+
+    /// the surface in the layout the host reads, pointing at `self`'s margin
+    /// and limits
+    pub(crate) fn raw(&self) -> LayerSurfaceRaw {
+        let mut size_flags = 0u8;
+        let mut size_x = 0u32;
+        let mut size_y = 0u32;
+
+        if let Some((x, y)) = self.size {
+            // 1st bit
+            size_flags = size_flags | 0b001;
+            if let Some(x) = x {
+                // 2nd bit
+                size_flags = size_flags | 0b010;
+                size_x = x;
+            }
+            if let Some(y) = y {
+                // 3rd bit
+                size_flags = size_flags | 0b100;
+                size_y = y;
+            }
+        }
+
+        LayerSurfaceRaw {
+            id: self.id.get_id(),
+            layer: self.layer.clone() as u8,
+            anchor: self.anchor.0,
+            size_flags,
+            size_x,
+            size_y,
+            margin_ptr: (&self.margin as *const Margin) as u32,
+            limits_ptr: (&self.limits as *const Limits) as u32,
+            exclusive_zone: self.exclusive_zone.raw(),
+            keyboard_interactivity: self.keyboard_interactivity.clone() as u8,
+            pointer_interactivity: match self.pointer_interactivity {
+                false => 0,
+                true => 1,
+            },
+            role: match self.role {
+                Some(role) => role as u8,
+                None => 0,
+            },
+        }
+    }
 }
 
 impl Default for LayerSurface {
@@ -28,6 +119,7 @@ impl Default for LayerSurface {
             exclusive_zone: Default::default(),
             keyboard_interactivity: Default::default(),
             pointer_interactivity: true,
+            role: None,
         }
     }
 }
@@ -50,12 +142,37 @@ pub struct LayerSurfaceRaw {
     pub size_y: u32,
     pub margin_ptr: u32,
     pub limits_ptr: u32,
+    /// `ExclusiveZone::Auto` is `i32::MIN`
     pub exclusive_zone: i32,
     /// `KeyboardInteractivity` gets converted to a u8
     pub keyboard_interactivity: u8,
     /// boolean for pointer interactivity is converted to a u8 to be safe
     /// to transport between wasm host and guest
     pub pointer_interactivity: u8,
+    /// `SurfaceRole` as a u8, 0 for none
+    pub role: u8,
+}
+
+/// what a surface is for, the host keeps its settings to what the role
+/// allows
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceRole {
+    /// along an edge on the top or bottom layer, never takes the keyboard
+    /// for itself
+    Bar = 1,
+    /// opened from a bar or a hotkey on the top or overlay layer, only
+    /// takes the keyboard when clicked and doesn't push windows away
+    Popup,
+    /// a volume or brightness popup on the overlay layer, never takes the
+    /// keyboard
+    Osd,
+    /// above everything including fullscreen windows, only takes the
+    /// keyboard when clicked
+    Overlay,
+    /// a wallpaper or desktop widgets, behind windows covering the whole
+    /// output
+    Background,
 }
 
 #[repr(u32)]
@@ -68,6 +185,82 @@ pub enum IdType {
 unsafe extern "C" {
     /// host function to get a unique id from the wasm runtime
     fn get_unique_id(id_type: u32) -> u32;
+    /// host function to open the surface at `ptr` after setup, returns 0 if
+    /// it will be, 1 if it's invalid and 2 if it's already open
+    fn open_surface(ptr: u32) -> u32;
+    /// host function to close a surface, returns 0 if it will be and 1 if
+    /// it isn't open
+    fn close_surface(id: u32) -> u32;
+    /// host function to mark a surface as changed, returns 0 if it was and
+    /// 1 if it isn't open
+    fn mark_surface_dirty(id: u32) -> u32;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceError {
+    /// the id didn't come from `Id::unique` or the settings can't be used
+    Invalid,
+    AlreadyOpen,
+    NotOpen,
+}
+
+/// opens another surface after setup, like a panel popping open from a
+/// button on the bar
+///
+/// the surface is opened once the module is done with the message it's
+/// handling, then `view` is called with its id like for the surfaces from
+/// setup
+///
+/// example:
+/// ```
+/// let panel = LayerSurface {
+///     id: self.panel_id,
+///     layer: Layer::Overlay,
+///     anchor: Anchor::TOP | Anchor::RIGHT,
+///     size: Some((Some(320), Some(200))),
+///     ..Default::default()
+/// };
+/// surface::open(&panel)?;
+/// ```
+pub fn open(surface: &LayerSurface) -> Result<(), SurfaceError> {
+    let raw = surface.raw();
+    match unsafe { open_surface((&raw as *const LayerSurfaceRaw) as u32) } {
+        0 => Ok(()),
+        2 => Err(SurfaceError::AlreadyOpen),
+        _ => Err(SurfaceError::Invalid),
+    }
+}
+
+/// closes a surface opened at setup or with `open`, once the module is done
+/// with the message it's handling. it can be opened again with the same id
+pub fn close(id: Id) -> Result<(), SurfaceError> {
+    match unsafe { close_surface(id.get_id()) } {
+        0 => Ok(()),
+        _ => Err(SurfaceError::NotOpen),
+    }
+}
+
+/// marks a surface as changed by the message the module is handling, so
+/// only the marked surfaces have `view` called once it's done. a module
+/// with a bar and a closed panel can skip rendering the bar when the panel
+/// is the only thing that changed
+///
+/// every surface is rendered when none are marked
+///
+/// example:
+/// ```
+/// fn update(&mut self, message: Message) {
+///     if let Message::NextPage = message {
+///         self.page += 1;
+///         surface::mark_dirty(self.panel_id).ok();
+///     }
+/// }
+/// ```
+pub fn mark_dirty(id: Id) -> Result<(), SurfaceError> {
+    match unsafe { mark_surface_dirty(id.get_id()) } {
+        0 => Ok(()),
+        _ => Err(SurfaceError::NotOpen),
+    }
 }
 
 /// represents an id that is determined by the wasm host
@@ -102,6 +295,15 @@ impl Anchor {
     pub const BOTTOM: Self = Self(0b0010);
     pub const LEFT: Self = Self(0b0100);
     pub const RIGHT: Self = Self(0b1000);
+
+    /// a bar across the top of the output
+    pub const TOP_EDGE: Self = Self(0b1101);
+    /// a bar across the bottom of the output
+    pub const BOTTOM_EDGE: Self = Self(0b1110);
+    /// a vertical bar down the left of the output
+    pub const LEFT_EDGE: Self = Self(0b0111);
+    /// a vertical bar down the right of the output
+    pub const RIGHT_EDGE: Self = Self(0b1011);
 }
 
 impl Anchor {
@@ -112,6 +314,13 @@ impl Anchor {
     pub fn all() -> Anchor {
         Anchor(0b1111)
     }
+
+    /// whether the surface is stretched from top to bottom but not left to
+    /// right, like a bar on the left or right edge
+    pub fn is_vertical(&self) -> bool {
+        let stretched = |edges: Anchor| self.0 & edges.0 == edges.0;
+        stretched(Self::TOP | Self::BOTTOM) && !stretched(Self::LEFT | Self::RIGHT)
+    }
 }
 
 impl From<u8> for Anchor {
@@ -163,6 +372,46 @@ impl Default for Limits {
     }
 }
 
+/// how much of the output other surfaces and windows keep clear of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExclusiveZone {
+    /// the amount of pixels from the anchored edge, 0 to only keep clear of
+    /// other surfaces' zones and -1 to ignore them too
+    Fixed(i32),
+    /// worked out by the shell from the surface's size and margin on the
+    /// edge it's anchored to, so it doesn't have to be kept in step with
+    /// the size
+    ///
+    /// the surface has to be anchored to one edge (or one edge and both
+    /// edges next to it) and have a size in that direction, otherwise no
+    /// zone is kept clear
+    Auto,
+}
+
+impl ExclusiveZone {
+    /// what `LayerSurfaceRaw::exclusive_zone` holds for `Auto`
+    const AUTO: i32 = i32::MIN;
+
+    fn raw(self) -> i32 {
+        match self {
+            Self::Fixed(pixels) => pixels,
+            Self::Auto => Self::AUTO,
+        }
+    }
+}
+
+impl Default for ExclusiveZone {
+    fn default() -> Self {
+        Self::Fixed(0)
+    }
+}
+
+impl From<i32> for ExclusiveZone {
+    fn from(pixels: i32) -> Self {
+        Self::Fixed(pixels)
+    }
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone)]
 pub enum KeyboardInteractivity {
@@ -0,0 +1,127 @@
+//! the cpu, memory, disk and temperature readings the shell samples, given
+//! to modules with a `Sysinfo` register as `ServiceEvent::Sysinfo` when
+//! they're made with a service event function (see `create_module!`)
+//!
+//! example:
+//! ```
+//! fn service_event(event: &ServiceEvent) -> u32 {
+//!     match event {
+//!         ServiceEvent::Sysinfo(SysinfoEvent::CpuUsageChanged { .. }) => {
+//!             Message::Sysinfo(event.clone()).into()
+//!         }
+//!         _ => 0,
+//!     }
+//! }
+//! ```
+
+use crate::bytes::{read_u16, read_u32, read_u64, string, take};
+
+/// a new reading, only the kinds the module registered for are sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SysinfoEvent {
+    CpuUsageChanged {
+        usage: CpuUsage,
+    },
+    MemoryChanged {
+        memory: Memory,
+    },
+    /// every mounted disk
+    DiskChanged {
+        disks: Vec<Disk>,
+    },
+    /// every temperature sensor
+    TemperatureChanged {
+        sensors: Vec<Temperature>,
+    },
+}
+
+/// how busy the cpu was between two samples, in percent
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuUsage {
+    /// across all cores
+    pub total: u8,
+    /// each core, in the order the kernel numbers them
+    pub cores: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Memory {
+    pub total_bytes: u64,
+    /// what can be used without swapping, which counts caches that can be
+    /// dropped
+    pub available_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_free_bytes: u64,
+}
+
+/// a mounted block device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disk {
+    /// where it's mounted, the first mount if it's mounted more than once
+    pub mount: String,
+    pub total_bytes: u64,
+    /// what can be used by the user, which leaves out the space reserved
+    /// for root
+    pub available_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Temperature {
+    /// the chip and the sensor's label, like `coretemp Package id 0`
+    pub name: String,
+    pub millidegrees: i32,
+}
+
+/// reads an event from the shell, `None` for a kind this version doesn't
+/// know
+///
+/// a u8 for the kind of event then the reading, lists start with a u16 for
+/// their length
+pub(crate) fn parse_event(bytes: &[u8]) -> Option<SysinfoEvent> {
+    let mut cursor = 0;
+
+    let event = match take(bytes, &mut cursor, 1)?[0] {
+        0 => {
+            let total = take(bytes, &mut cursor, 1)?[0];
+            let count = read_u16(bytes, &mut cursor)?;
+            let cores = take(bytes, &mut cursor, count as usize)?.to_vec();
+            SysinfoEvent::CpuUsageChanged {
+                usage: CpuUsage { total, cores },
+            }
+        }
+        1 => SysinfoEvent::MemoryChanged {
+            memory: Memory {
+                total_bytes: read_u64(bytes, &mut cursor)?,
+                available_bytes: read_u64(bytes, &mut cursor)?,
+                swap_total_bytes: read_u64(bytes, &mut cursor)?,
+                swap_free_bytes: read_u64(bytes, &mut cursor)?,
+            },
+        },
+        2 => {
+            let count = read_u16(bytes, &mut cursor)?;
+            let mut disks = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                disks.push(Disk {
+                    mount: string(bytes, &mut cursor)?,
+                    total_bytes: read_u64(bytes, &mut cursor)?,
+                    available_bytes: read_u64(bytes, &mut cursor)?,
+                });
+            }
+            SysinfoEvent::DiskChanged { disks }
+        }
+        3 => {
+            let count = read_u16(bytes, &mut cursor)?;
+            let mut sensors = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                sensors.push(Temperature {
+                    name: string(bytes, &mut cursor)?,
+                    millidegrees: read_u32(bytes, &mut cursor)? as i32,
+                });
+            }
+            SysinfoEvent::TemperatureChanged { sensors }
+        }
+        _ => return None,
+    };
+
+    return Some(event);
+}
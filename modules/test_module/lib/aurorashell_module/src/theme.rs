@@ -1,3 +1,24 @@
+//! the shell's colors, which widgets can use by name with `Color` so a
+//! module matches the rest of the shell
+//!
+//! modules that want the colors themselves, like to mix them, can read the
+//! palette with `palette()`. with a `Theme` register the module is rendered
+//! again when it changes
+//!
+//! example:
+//! ```
+//! let accent = theme::palette().map(|palette| palette.rgba(&Color::Color14));
+//! ```
+
+unsafe extern "C" {
+    /// host function to get the size of the palette in bytes, 0 if the
+    /// shell hasn't sent it yet
+    fn theme_size() -> u32;
+    /// host function to copy the palette to `ptr`, returns how many bytes
+    /// were written or 0 if they didn't fit in `len`
+    fn read_theme(ptr: u32, len: u32) -> u32;
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone)]
 pub enum Color {
@@ -47,3 +68,125 @@ impl From<&Color> for u8 {
         }
     }
 }
+
+impl Color {
+    /// a `Custom` color as rgba packed into a u32, red in the highest byte,
+    /// `None` for theme colors or if it isn't `#rrggbb` or `#rrggbbaa`
+    pub(crate) fn custom_rgba(&self) -> Option<u32> {
+        let Color::Custom(hex) = self else {
+            return None;
+        };
+
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        let rgba = match hex.len() {
+            6 => (u32::from_str_radix(hex, 16).ok()? << 8) | 0xFF,
+            8 => u32::from_str_radix(hex, 16).ok()?,
+            _ => return None,
+        };
+
+        return Some(rgba);
+    }
+}
+
+/// the shell's colors as red, green, blue and alpha
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    /// `Color01` to `Color16`
+    pub colors: [[u8; 4]; 16],
+    pub background: [u8; 4],
+    pub foreground: [u8; 4],
+    /// the name of the shell's font
+    pub font: String,
+    /// the size text is drawn at when the module doesn't pick one, in
+    /// logical pixels
+    pub text_size: f32,
+    /// whether the user would like light or dark colors, which picked the
+    /// palette
+    pub scheme: ColorScheme,
+}
+
+/// the user's light or dark preference from the desktop, the shell switches
+/// palettes when it changes
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorScheme {
+    /// the desktop doesn't say or the shell can't ask it, the palette is
+    /// usually dark
+    #[default]
+    NoPreference = 0,
+    Dark = 1,
+    Light = 2,
+}
+
+impl Palette {
+    /// what `color` is drawn as, `Custom` colors that can't be read are the
+    /// foreground like they are in the shell
+    pub fn rgba(&self, color: &Color) -> [u8; 4] {
+        match color {
+            Color::ColorForeground => self.foreground,
+            Color::ColorBackground => self.background,
+            Color::Custom(_) => match color.custom_rgba() {
+                Some(rgba) => rgba.to_be_bytes(),
+                None => self.foreground,
+            },
+            color => self.colors[u8::from(color) as usize - 1],
+        }
+    }
+}
+
+/// the palette the shell last gave the module, `None` before it's sent
+pub fn palette() -> Option<Palette> {
+    let size = unsafe { theme_size() };
+    if size == 0 {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = vec![0; size as usize];
+    let written = unsafe { read_theme(bytes.as_mut_ptr() as u32, size) };
+    if written == 0 {
+        return None;
+    }
+    bytes.truncate(written as usize);
+
+    return parse(&bytes);
+}
+
+/// reads the palette written by the host, `None` if it's cut short
+///
+/// the 16 colors, the background and the foreground as 4 bytes each, then
+/// the f32 text size, the font as a u16 length followed by that many bytes
+/// of utf-8 and the color scheme as a byte, which older shells don't send
+fn parse(bytes: &[u8]) -> Option<Palette> {
+    let mut cursor = 0;
+    let mut next = |len: usize| {
+        let taken = bytes.get(cursor..cursor + len)?;
+        cursor += len;
+        Some(taken)
+    };
+
+    let mut colors = [[0; 4]; 16];
+    for color in colors.iter_mut() {
+        *color = next(4)?.try_into().ok()?;
+    }
+    let background = next(4)?.try_into().ok()?;
+    let foreground = next(4)?.try_into().ok()?;
+    let text_size = f32::from_le_bytes(next(4)?.try_into().ok()?);
+
+    let len = u16::from_le_bytes(next(2)?.try_into().ok()?);
+    let font = String::from_utf8_lossy(next(len as usize)?).into_owned();
+
+    let scheme = match next(1).map(|byte| byte[0]) {
+        Some(1) => ColorScheme::Dark,
+        Some(2) => ColorScheme::Light,
+        _ => ColorScheme::NoPreference,
+    };
+
+    return Some(Palette {
+        colors,
+        background,
+        foreground,
+        font,
+        text_size,
+        scheme,
+    });
+}
@@ -0,0 +1,138 @@
+//! searches the emoji and unicode characters the shell keeps, so a picker
+//! doesn't have to carry the whole table in its memory
+//!
+//! every word of the query has to be in a character's name (or one of an
+//! emoji's shortcodes, like `fox_face`), an empty query finds everything.
+//! `offset` and `limit` fetch the results a page at a time, like the cells a
+//! `Grid` shows
+//!
+//! example:
+//! ```
+//! let results = unicode::search_emoji("fox", 0, 64)?;
+//! let first = results.characters.first().map(|found| found.text.clone());
+//! ```
+
+use crate::bytes::{read_u16, read_u32, string, take};
+
+unsafe extern "C" {
+    /// host function to search the table, returns the size of the results
+    /// in bytes or 0 if the search couldn't be run
+    fn unicode_search(kind: u32, ptr: u32, len: u32, offset: u32, limit: u32) -> u32;
+    /// host function to copy the results of the last search to `ptr`,
+    /// returns how many bytes were written or 0 if they didn't fit in `len`
+    fn read_unicode_results(ptr: u32, len: u32) -> u32;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResults {
+    /// how many characters matched, not just the ones in `characters`
+    pub total: u32,
+    pub characters: Vec<Character>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Character {
+    /// the character itself, emoji can be more than one code point
+    pub text: String,
+    /// like `fox` or `LATIN SMALL LETTER A`
+    pub name: String,
+    /// `None` for characters that aren't emoji
+    pub group: Option<EmojiGroup>,
+}
+
+/// the groups emoji pickers sort emoji into, in the order they're shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmojiGroup {
+    SmileysAndEmotion,
+    PeopleAndBody,
+    /// skin tones and hair styles
+    Component,
+    AnimalsAndNature,
+    FoodAndDrink,
+    TravelAndPlaces,
+    Activities,
+    Objects,
+    Symbols,
+    Flags,
+}
+
+impl EmojiGroup {
+    fn from_raw(group: u8) -> Option<Self> {
+        Some(match group {
+            1 => Self::SmileysAndEmotion,
+            2 => Self::PeopleAndBody,
+            3 => Self::Component,
+            4 => Self::AnimalsAndNature,
+            5 => Self::FoodAndDrink,
+            6 => Self::TravelAndPlaces,
+            7 => Self::Activities,
+            8 => Self::Objects,
+            9 => Self::Symbols,
+            10 => Self::Flags,
+            _ => return None,
+        })
+    }
+}
+
+/// searches only the emoji
+pub fn search_emoji(query: &str, offset: u32, limit: u32) -> Option<SearchResults> {
+    search_kind(0, query, offset, limit)
+}
+
+/// searches the emoji and then every other character with a name
+pub fn search(query: &str, offset: u32, limit: u32) -> Option<SearchResults> {
+    search_kind(1, query, offset, limit)
+}
+
+fn search_kind(kind: u32, query: &str, offset: u32, limit: u32) -> Option<SearchResults> {
+    let size = unsafe {
+        unicode_search(
+            kind,
+            query.as_ptr() as u32,
+            query.len() as u32,
+            offset,
+            limit,
+        )
+    };
+    if size == 0 {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = vec![0; size as usize];
+    let written = unsafe { read_unicode_results(bytes.as_mut_ptr() as u32, size) };
+    if written == 0 {
+        return None;
+    }
+    bytes.truncate(written as usize);
+
+    return parse(&bytes);
+}
+
+/// reads the results written by the host, `None` if they're cut short
+///
+/// strings are a u16 length followed by that many bytes of utf-8
+///
+/// - u32 amount of matches in total, then u16 amount of characters
+/// - each character is its text and name, then a u8 for its group (0 for
+///   characters that aren't emoji)
+fn parse(bytes: &[u8]) -> Option<SearchResults> {
+    let mut cursor = 0;
+
+    let total = read_u32(bytes, &mut cursor)?;
+    let count = read_u16(bytes, &mut cursor)?;
+
+    let mut characters = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let text = string(bytes, &mut cursor)?;
+        let name = string(bytes, &mut cursor)?;
+        let [group] = take(bytes, &mut cursor, 1)?.try_into().ok()?;
+
+        characters.push(Character {
+            text,
+            name,
+            group: EmojiGroup::from_raw(group),
+        });
+    }
+
+    return Some(SearchResults { total, characters });
+}
@@ -6,11 +6,27 @@ use std::{
 };
 
 use crate::widget::{
-    ButtonFn, Element, RawElement, SliderFn, SliderNumberType,
+    ButtonFn, Element, GridFn, LongPressFn, RawElement, Scroll, ScrollFn, SliderFn,
+    SliderNumberType, TextInputFn, TogglerFn, TrayClick, TrayIconFn,
+    container::RawContainerData,
+    grid::RawGridData,
+    image::RawImageData,
+    layout::RawLayoutData,
+    mouse_area::RawMouseAreaData,
+    progress_bar::RawProgressBarData,
     slider::RawSliderData,
     text::{self, RawTextData},
+    text_input::RawTextInputData,
+    tooltip::RawTooltipData,
 };
 
+unsafe extern "C" {
+    /// host function to copy the text the running callback was given to
+    /// `ptr`, returns how many bytes were written or 0 if they didn't fit in
+    /// `len`
+    fn read_callback_text(ptr: u32, len: u32) -> u32;
+}
+
 /// used as part of the exposed `view()` function to store element data
 /// for the host to read
 #[repr(C)]
@@ -30,6 +46,26 @@ pub struct ElementsMemoryArena {
     pub(crate) text_style: Vec<text::RawStyle>,
 
     pub(crate) slider_data: Vec<RawSliderData>,
+
+    pub(crate) container_data: Vec<RawContainerData>,
+
+    /// icon names and pngs, `image_data` points into these
+    pub(crate) image_bytes: Vec<Vec<u8>>,
+    pub(crate) image_data: Vec<RawImageData>,
+
+    pub(crate) progress_bar_data: Vec<RawProgressBarData>,
+
+    /// the values and placeholders are kept in `text_strings`
+    pub(crate) text_input_data: Vec<RawTextInputData>,
+    pub(crate) grid_data: Vec<RawGridData>,
+
+    /// the spacing, padding, size and alignment of rows and columns
+    pub(crate) layout_data: Vec<RawLayoutData>,
+
+    /// the texts are kept in `text_strings`
+    pub(crate) tooltip_data: Vec<RawTooltipData>,
+    /// the ids of the callbacks of mouse areas
+    pub(crate) mouse_area_data: Vec<RawMouseAreaData>,
 }
 
 impl ElementsMemoryArena {
@@ -42,14 +78,38 @@ impl ElementsMemoryArena {
             text_data: vec![],
             text_style: vec![],
             slider_data: vec![],
+            container_data: vec![],
+            image_bytes: vec![],
+            image_data: vec![],
+            progress_bar_data: vec![],
+            text_input_data: vec![],
+            grid_data: vec![],
+            layout_data: vec![],
+            tooltip_data: vec![],
+            mouse_area_data: vec![],
         }
     }
 }
 
+/// the layout version of the data handed to the host, the host reads it
+/// through `abi_version()`
+///
+/// everything is little endian
+pub const ABI_VERSION: u32 = 12;
+
+/// defines an external function for the wasm host to check which layout
+/// the module hands data over in
+#[unsafe(no_mangle)]
+fn abi_version() -> u32 {
+    ABI_VERSION
+}
+
 #[repr(C)]
 #[derive(Debug)]
 /// struct used for telling the host where offsets into the arena are
 pub struct ViewFuncData {
+    /// always `ABI_VERSION`, lets the host check it's reading the right layout
+    pub(crate) version: u32,
     /// the index to the head of the tree
     pub(crate) head_index: u32,
     /// pointer to `ElementsMemoryArena.elements`
@@ -62,17 +122,46 @@ pub struct ViewFuncData {
     pub text_style_ptr: u32,
     /// pointer to `ElementsMemoryArena.raw_slider_data`
     pub(crate) slider_data_ptr: u32,
+    /// counts the views of the surface, the host hands it back to
+    /// `run_callback` so callbacks from an older view can be rejected
+    pub(crate) generation: u32,
+    /// pointer to `ElementsMemoryArena.container_data`
+    pub(crate) container_data_ptr: u32,
+    /// pointer to `ElementsMemoryArena.image_data`
+    pub(crate) image_data_ptr: u32,
+    /// pointer to `ElementsMemoryArena.progress_bar_data`
+    pub(crate) progress_bar_data_ptr: u32,
+    /// pointer to `ElementsMemoryArena.text_input_data`
+    pub(crate) text_input_data_ptr: u32,
+    /// pointer to `ElementsMemoryArena.grid_data`
+    pub(crate) grid_data_ptr: u32,
+    /// pointer to `ElementsMemoryArena.layout_data`
+    pub(crate) layout_data_ptr: u32,
+    /// pointer to `ElementsMemoryArena.tooltip_data`
+    pub(crate) tooltip_data_ptr: u32,
+    /// pointer to `ElementsMemoryArena.mouse_area_data`
+    pub(crate) mouse_area_data_ptr: u32,
 }
 
 impl ViewFuncData {
     pub fn new() -> Self {
         Self {
+            version: ABI_VERSION,
             head_index: 0,
             elements_ptr: 0,
             children_ptr: 0,
             text_data_ptr: 0,
             text_style_ptr: 0,
             slider_data_ptr: 0,
+            generation: 0,
+            container_data_ptr: 0,
+            image_data_ptr: 0,
+            progress_bar_data_ptr: 0,
+            text_input_data_ptr: 0,
+            grid_data_ptr: 0,
+            layout_data_ptr: 0,
+            tooltip_data_ptr: 0,
+            mouse_area_data_ptr: 0,
         }
     }
 }
@@ -83,6 +172,53 @@ pub enum CallbackType {
         ty: SliderNumberType,
         func: Box<dyn Any + Send + Sync>,
     },
+    TrayIcon(TrayIconFn),
+    LongPress(LongPressFn),
+    TextInput(TextInputFn),
+    Grid(GridFn),
+    Scroll(ScrollFn),
+    Toggler(TogglerFn),
+}
+
+/// what the `data` given to `run_callback` is, the same as the host's
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CallbackDataKind {
+    /// the widget has no data
+    None = 0,
+    /// a number, or a few packed into the 64 bits
+    Bits = 1,
+    /// the length of the text, read with `read_callback_text`
+    Text = 2,
+    /// 0 or 1
+    Bool = 3,
+}
+
+impl CallbackDataKind {
+    fn from_u32(kind: u32) -> Option<Self> {
+        match kind {
+            0 => Some(Self::None),
+            1 => Some(Self::Bits),
+            2 => Some(Self::Text),
+            3 => Some(Self::Bool),
+            _ => None,
+        }
+    }
+}
+
+impl CallbackType {
+    /// the kind of data the host gives this callback
+    fn data_kind(&self) -> CallbackDataKind {
+        match self {
+            CallbackType::Button(_) | CallbackType::LongPress(_) => CallbackDataKind::None,
+            CallbackType::Slider { .. }
+            | CallbackType::TrayIcon(_)
+            | CallbackType::Grid(_)
+            | CallbackType::Scroll(_) => CallbackDataKind::Bits,
+            CallbackType::TextInput(_) => CallbackDataKind::Text,
+            CallbackType::Toggler(_) => CallbackDataKind::Bool,
+        }
+    }
 }
 
 static ARENA: LazyLock<Mutex<ElementsMemoryArena>> =
@@ -91,6 +227,9 @@ static VIEW_FUNC_DATA: LazyLock<Mutex<ViewFuncData>> =
     LazyLock::new(|| Mutex::new(ViewFuncData::new()));
 static CALLBACKS_MAP: LazyLock<Mutex<HashMap<u32, Vec<CallbackType>>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
+/// the generation of the callbacks in `CALLBACKS_MAP` for each surface
+static GENERATIONS: LazyLock<Mutex<HashMap<u32, u32>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
 
 pub fn view_build_ui<Message>(mut root_element: Element<Message>, id: u32) -> *const ViewFuncData
 where
@@ -114,16 +253,31 @@ where
 
     let index = root_element.widget.arena_index(&mut arena, &mut callbacks);
 
+    let mut generations = GENERATIONS.lock().unwrap();
+    let generation = generations.entry(id).or_insert(0);
+    *generation = generation.wrapping_add(1);
+    let generation = *generation;
+
     arena.children_ptrs = arena.children.iter().map(|v| v.as_ptr() as u32).collect();
 
     let mut view_func_data = VIEW_FUNC_DATA.lock().unwrap();
     *view_func_data = ViewFuncData {
+        version: ABI_VERSION,
         head_index: index,
         elements_ptr: arena.elements.as_ptr() as u32,
         children_ptr: arena.children_ptrs.as_ptr() as u32,
         text_data_ptr: arena.text_data.as_ptr() as u32,
         text_style_ptr: arena.text_style.as_ptr() as u32,
         slider_data_ptr: arena.slider_data.as_ptr() as u32,
+        generation,
+        container_data_ptr: arena.container_data.as_ptr() as u32,
+        image_data_ptr: arena.image_data.as_ptr() as u32,
+        progress_bar_data_ptr: arena.progress_bar_data.as_ptr() as u32,
+        text_input_data_ptr: arena.text_input_data.as_ptr() as u32,
+        grid_data_ptr: arena.grid_data.as_ptr() as u32,
+        layout_data_ptr: arena.layout_data.as_ptr() as u32,
+        tooltip_data_ptr: arena.tooltip_data.as_ptr() as u32,
+        mouse_area_data_ptr: arena.mouse_area_data.as_ptr() as u32,
     };
 
     return &*view_func_data as *const ViewFuncData;
@@ -133,13 +287,29 @@ where
 /// to run a callback via its id and optionally a pointer
 ///
 /// `data` can either be data or a ptr to data depending on the type of callback
+///
+/// `generation` is the view the callback came from, callback ids are only
+/// valid for the view they were made in so ones from an older view are
+/// rejected
+///
+/// `kind` says what `data` is (see `CallbackDataKind`), a callback given
+/// something other than what its widget calls back with is dropped
 #[unsafe(no_mangle)]
-fn run_callback(surface_id: u32, callback_id: u32, data: u64) -> u64 {
+fn run_callback(surface_id: u32, callback_id: u32, data: u64, generation: u32, kind: u32) -> u64 {
     // id of 0 means no callback
     if callback_id == 0 {
         return 0;
     }
 
+    let current = GENERATIONS.lock().unwrap().get(&surface_id).copied();
+    if current != Some(generation) {
+        eprintln!(
+            "module: dropping callback {} from view {} of surface {}, the current view is {:?}",
+            callback_id, generation, surface_id, current
+        );
+        return 0;
+    }
+
     let callbacks = CALLBACKS_MAP.lock().unwrap();
 
     let callback = match callbacks.get(&surface_id) {
@@ -164,9 +334,19 @@ fn run_callback(surface_id: u32, callback_id: u32, data: u64) -> u64 {
         }
     };
 
+    let expected = callback.data_kind();
+    if CallbackDataKind::from_u32(kind) != Some(expected) {
+        eprintln!(
+            "module: callback {} of surface {} takes {:?} data but was given kind {}",
+            callback_id, surface_id, expected, kind
+        );
+        return 0;
+    }
+
     // (message_id, data_ptr)
     let data: (u32, u32) = match callback {
         CallbackType::Button(func) => (func(), 0),
+        CallbackType::LongPress(func) => (func(), 0),
         CallbackType::Slider { ty, func } => match ty {
             SliderNumberType::I32 => {
                 if let Some(func) = func.downcast_ref::<SliderFn<i32>>() {
@@ -208,6 +388,53 @@ fn run_callback(surface_id: u32, callback_id: u32, data: u64) -> u64 {
                 }
             }
         },
+        CallbackType::TrayIcon(func) => match TrayClick::from_data(data) {
+            Some(click) => (func(click), 0),
+            None => {
+                eprintln!("module: unknown tray click {}", data);
+                (0, 0)
+            }
+        },
+        // `data` is the length of the text, which is read from the host
+        CallbackType::TextInput(func) => {
+            let mut bytes: Vec<u8> = vec![0; data as usize];
+            let written = match data {
+                0 => 0,
+                len => unsafe { read_callback_text(bytes.as_mut_ptr() as u32, len as u32) },
+            };
+            bytes.truncate(written as usize);
+
+            let (message_id, data) = func(String::from_utf8_lossy(&bytes).into_owned());
+
+            let leaked_data = Box::leak(Box::new(data));
+            let data_ptr = leaked_data as *mut String;
+
+            (message_id, data_ptr as u32)
+        }
+        CallbackType::Grid(func) => {
+            let (message_id, data) = func(data as u32);
+
+            let leaked_data = Box::leak(Box::new(data));
+            let data_ptr = leaked_data as *mut u32;
+
+            (message_id, data_ptr as u32)
+        }
+        CallbackType::Scroll(func) => {
+            let (message_id, data) = func(Scroll::from_data(data));
+
+            let leaked_data = Box::leak(Box::new(data));
+            let data_ptr = leaked_data as *mut Scroll;
+
+            (message_id, data_ptr as u32)
+        }
+        CallbackType::Toggler(func) => {
+            let (message_id, data) = func(data != 0);
+
+            let leaked_data = Box::leak(Box::new(data));
+            let data_ptr = leaked_data as *mut bool;
+
+            (message_id, data_ptr as u32)
+        }
     };
 
     // merge message id and data ptr into one u64
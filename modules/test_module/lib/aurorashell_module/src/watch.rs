@@ -0,0 +1,46 @@
+//! the files a module watches with its `Watch` register
+//!
+//! the module is rendered again whenever one of them changes, so reading the
+//! file in `view` is enough to stay up to date. modules made with a service
+//! event function are also told which file it was
+//!
+//! example:
+//! ```
+//! fn service_event(event: &ServiceEvent) -> u32 {
+//!     match event {
+//!         ServiceEvent::Watch(WatchEvent::FileChanged { path }) => {
+//!             Message::Reload(path.clone()).into()
+//!         }
+//!         _ => 0,
+//!     }
+//! }
+//! ```
+
+/// what changed, given to modules as `ServiceEvent::Watch` when they're
+/// made with a service event function (see `create_module!`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// the file was created, written to or removed
+    FileChanged { path: String },
+}
+
+/// reads an event from the shell, `None` for a kind this version doesn't
+/// know
+///
+/// a u8 for the kind of event, then the path as a u16 length followed by
+/// that many bytes of utf-8
+pub(crate) fn parse_event(bytes: &[u8]) -> Option<WatchEvent> {
+    let (&kind, rest) = bytes.split_first()?;
+
+    let event = match kind {
+        0 => {
+            let len = u16::from_le_bytes(rest.get(..2)?.try_into().ok()?) as usize;
+            WatchEvent::FileChanged {
+                path: String::from_utf8_lossy(rest.get(2..2 + len)?).into_owned(),
+            }
+        }
+        _ => return None,
+    };
+
+    return Some(event);
+}
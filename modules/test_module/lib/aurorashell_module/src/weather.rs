@@ -0,0 +1,226 @@
+//! the weather the shell fetches for the location in `[services.weather]`,
+//! so modules don't each need to make their own requests
+//!
+//! modules with a `Weather` register are rendered again when it's fetched,
+//! and `current` gives the last weather at any time
+//!
+//! example:
+//! ```
+//! if let Some(weather) = weather::current() {
+//!     let text = format!(
+//!         "{:.0}{} {}",
+//!         weather.current.temperature,
+//!         weather.units.temperature_symbol(),
+//!         weather.current.kind.description(),
+//!     );
+//! }
+//! ```
+
+use crate::bytes::{read_f32, read_u16, string, take};
+
+unsafe extern "C" {
+    /// host function to get how many bytes `read_weather` needs, 0 when
+    /// there's no weather yet
+    fn weather_size() -> u32;
+    /// host function to copy the last weather to `ptr`, returns how many
+    /// bytes were written or 0 if it didn't fit in `len`
+    fn read_weather(ptr: u32, len: u32) -> u32;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weather {
+    /// the units every number is in
+    pub units: Units,
+    /// the name of the location, if the shell knows it
+    pub location: Option<String>,
+    pub current: Conditions,
+    /// starts with today
+    pub daily: Vec<Day>,
+}
+
+/// the weather right now
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conditions {
+    pub temperature: f32,
+    /// what the temperature feels like
+    pub apparent_temperature: f32,
+    pub wind_speed: f32,
+    pub kind: Kind,
+    pub is_day: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Day {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub temperature_min: f32,
+    pub temperature_max: f32,
+    /// chance of precipitation in percent
+    pub precipitation_probability: Option<f32>,
+    pub kind: Kind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Units {
+    /// celsius and km/h
+    Metric,
+    /// fahrenheit and mph
+    Imperial,
+}
+
+impl Units {
+    pub fn temperature_symbol(&self) -> &'static str {
+        match self {
+            Self::Metric => "°C",
+            Self::Imperial => "°F",
+        }
+    }
+
+    pub fn wind_speed_symbol(&self) -> &'static str {
+        match self {
+            Self::Metric => "km/h",
+            Self::Imperial => "mph",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Clear,
+    PartlyCloudy,
+    Cloudy,
+    Fog,
+    Drizzle,
+    Rain,
+    Snow,
+    Thunderstorm,
+    Unknown,
+}
+
+impl Kind {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Clear => "clear",
+            Self::PartlyCloudy => "partly cloudy",
+            Self::Cloudy => "cloudy",
+            Self::Fog => "fog",
+            Self::Drizzle => "drizzle",
+            Self::Rain => "rain",
+            Self::Snow => "snow",
+            Self::Thunderstorm => "thunderstorm",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    fn from_id(id: u8) -> Self {
+        match id {
+            0 => Self::Clear,
+            1 => Self::PartlyCloudy,
+            2 => Self::Cloudy,
+            3 => Self::Fog,
+            4 => Self::Drizzle,
+            5 => Self::Rain,
+            6 => Self::Snow,
+            7 => Self::Thunderstorm,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// what changed, given to modules as `ServiceEvent::Weather` when they're
+/// made with a service event function (see `create_module!`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum WeatherEvent {
+    WeatherChanged {
+        weather: Weather,
+    },
+    /// the weather couldn't be fetched, the last one is still the one to
+    /// show
+    Failed {
+        error: String,
+    },
+}
+
+/// the last weather the shell fetched, `None` before the first fetch or
+/// when the weather service isn't running
+pub fn current() -> Option<Weather> {
+    let size = unsafe { weather_size() };
+    if size == 0 {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = vec![0; size as usize];
+    let written = unsafe { read_weather(bytes.as_mut_ptr() as u32, size) };
+    if written != size {
+        return None;
+    }
+
+    return parse_weather(&bytes, &mut 0);
+}
+
+/// reads an event from the shell, `None` for a kind this version doesn't
+/// know
+///
+/// a u8 for the kind of event, then the weather or the error
+pub(crate) fn parse_event(bytes: &[u8]) -> Option<WeatherEvent> {
+    let mut cursor = 0;
+
+    let event = match take(bytes, &mut cursor, 1)?[0] {
+        0 => WeatherEvent::WeatherChanged {
+            weather: parse_weather(bytes, &mut cursor)?,
+        },
+        1 => WeatherEvent::Failed {
+            error: string(bytes, &mut cursor)?,
+        },
+        _ => return None,
+    };
+
+    return Some(event);
+}
+
+/// a u8 for the units, the location, the current conditions then a u8 for
+/// the amount of days followed by each of them
+fn parse_weather(bytes: &[u8], cursor: &mut usize) -> Option<Weather> {
+    let units = match take(bytes, cursor, 1)?[0] {
+        0 => Units::Metric,
+        _ => Units::Imperial,
+    };
+    let location = string(bytes, cursor)?;
+
+    let current = Conditions {
+        temperature: read_f32(bytes, cursor)?,
+        apparent_temperature: read_f32(bytes, cursor)?,
+        wind_speed: read_f32(bytes, cursor)?,
+        kind: Kind::from_id(take(bytes, cursor, 1)?[0]),
+        is_day: take(bytes, cursor, 1)?[0] != 0,
+    };
+
+    let days = take(bytes, cursor, 1)?[0];
+    let mut daily = Vec::with_capacity(days as usize);
+    for _ in 0..days {
+        let year = read_u16(bytes, cursor)?;
+        let [month, day] = take(bytes, cursor, 2)?.try_into().ok()?;
+        let temperature_min = read_f32(bytes, cursor)?;
+        let temperature_max = read_f32(bytes, cursor)?;
+        let precipitation_probability = read_f32(bytes, cursor)?;
+
+        daily.push(Day {
+            year,
+            month,
+            day,
+            temperature_min,
+            temperature_max,
+            precipitation_probability: (!precipitation_probability.is_nan())
+                .then_some(precipitation_probability),
+            kind: Kind::from_id(take(bytes, cursor, 1)?[0]),
+        });
+    }
+
+    return Some(Weather {
+        units,
+        location: (!location.is_empty()).then_some(location),
+        current,
+        daily,
+    });
+}
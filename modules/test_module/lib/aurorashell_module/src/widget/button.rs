@@ -42,6 +42,7 @@ impl<'a, Message> Widget<Message> for Button<'a, Message> {
         let element = RawElement {
             tag: ElementTag::Button as u8,
             child_count: 1,
+            flags: 0,
             children_index,
             data_index: 0,
             callback_index,
@@ -0,0 +1,45 @@
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{Element, Widget, element_flags};
+
+/// marks an element and its children as static
+///
+/// the shell reuses the widgets of a static subtree as long as it's the same
+/// as last view, so it's worth wrapping the parts of a ui that rarely change,
+/// like labels next to a clock. it's still laid out and drawn every frame
+pub struct Cached<'a, Message> {
+    inner: Element<'a, Message>,
+}
+
+impl<'a, Message> Cached<'a, Message> {
+    pub fn new(inner: impl Into<Element<'a, Message>>) -> Self {
+        Self {
+            inner: inner.into(),
+        }
+    }
+}
+
+impl<'a, Message> Widget<Message> for Cached<'a, Message> {
+    fn arena_index(
+        &mut self,
+        arena: &mut ElementsMemoryArena,
+        callbacks: &mut Vec<CallbackType>,
+    ) -> u32 {
+        let index = self.inner.widget.arena_index(arena, callbacks);
+
+        if let Some(element) = arena.elements.get_mut(index as usize) {
+            element.flags |= element_flags::STATIC;
+        }
+
+        return index;
+    }
+}
+
+impl<'a, Message> From<Cached<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(cached: Cached<'a, Message>) -> Self {
+        Self::new(cached)
+    }
+}
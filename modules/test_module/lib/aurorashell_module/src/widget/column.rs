@@ -1,20 +1,66 @@
 use crate::{CallbackType, ElementsMemoryArena};
 
+use super::layout::{Alignment, Layout, Length};
 use super::{Element, ElementTag, RawElement, Widget};
 
 pub struct Column<'a, Message> {
     children: Vec<Element<'a, Message>>,
+    layout: Layout,
 }
 
 impl<'a, Message> Column<'a, Message> {
     pub fn new() -> Self {
         Self {
             children: Vec::new(),
+            layout: Layout::default(),
         }
     }
 
     pub fn from_vec(children: Vec<Element<'a, Message>>) -> Self {
-        Self { children }
+        Self {
+            children,
+            layout: Layout::default(),
+        }
+    }
+
+    /// the space between children in logical pixels
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.layout.spacing = spacing;
+        self
+    }
+
+    /// the same space on every side
+    pub fn padding(mut self, padding: f32) -> Self {
+        self.layout.padding = [padding; 4];
+        self
+    }
+
+    /// space above and below, then left and right
+    pub fn padding_xy(mut self, vertical: f32, horizontal: f32) -> Self {
+        self.layout.padding = [vertical, horizontal, vertical, horizontal];
+        self
+    }
+
+    /// space on the top, right, bottom and left
+    pub fn padding_sides(mut self, padding: [f32; 4]) -> Self {
+        self.layout.padding = padding;
+        self
+    }
+
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.layout.width = width.into();
+        self
+    }
+
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.layout.height = height.into();
+        self
+    }
+
+    /// where the children sit between the left and right
+    pub fn align_x(mut self, align: Alignment) -> Self {
+        self.layout.align = align;
+        self
     }
 }
 
@@ -44,8 +90,9 @@ impl<'a, Message> Widget<Message> for Column<'a, Message> {
                 Some(v) => v,
                 None => 255,
             },
+            flags: 0,
             children_index,
-            data_index: 0,
+            data_index: self.layout.arena_index(arena),
             callback_index: 0,
             style_index: 0,
         };
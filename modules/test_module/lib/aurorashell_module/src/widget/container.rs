@@ -0,0 +1,145 @@
+use crate::theme::Color;
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{Element, ElementTag, RawElement, Widget};
+
+/// draws a background and a border around `inner`, with space between them
+pub struct Container<'a, Message> {
+    pub inner: Element<'a, Message>,
+    /// top, right, bottom and left in logical pixels
+    pub padding: [f32; 4],
+    pub background: Option<Color>,
+    pub border: Option<Border>,
+}
+
+/// the line around a container
+pub struct Border {
+    pub width: f32,
+    pub radius: f32,
+    /// the theme's foreground if not set
+    pub color: Option<Color>,
+}
+
+impl<'a, Message> Container<'a, Message> {
+    pub fn new(inner: impl Into<Element<'a, Message>>) -> Self {
+        Self {
+            inner: inner.into(),
+            padding: [0.0; 4],
+            background: None,
+            border: None,
+        }
+    }
+
+    /// the same space on every side
+    pub fn padding(mut self, padding: f32) -> Self {
+        self.padding = [padding; 4];
+        self
+    }
+
+    /// space above and below, then left and right
+    pub fn padding_xy(mut self, vertical: f32, horizontal: f32) -> Self {
+        self.padding = [vertical, horizontal, vertical, horizontal];
+        self
+    }
+
+    /// space on the top, right, bottom and left
+    pub fn padding_sides(mut self, padding: [f32; 4]) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// a border `width` wide with its corners rounded by `radius`
+    pub fn border(mut self, width: f32, radius: f32, color: Option<Color>) -> Self {
+        self.border = Some(Border {
+            width,
+            radius,
+            color,
+        });
+        self
+    }
+}
+
+impl<'a, Message> Widget<Message> for Container<'a, Message> {
+    fn arena_index(
+        &mut self,
+        arena: &mut ElementsMemoryArena,
+        callbacks: &mut Vec<CallbackType>,
+    ) -> u32 {
+        let inner = vec![self.inner.widget.arena_index(arena, callbacks)];
+        arena.children.push(inner);
+        let children_index = (arena.children.len() - 1) as u32;
+
+        let (background, background_custom) = raw_color(self.background.as_ref());
+        let (border_color, border_custom) = raw_color(
+            self.border
+                .as_ref()
+                .and_then(|border| border.color.as_ref()),
+        );
+
+        let raw_data = RawContainerData {
+            padding: self.padding,
+            border_width: self.border.as_ref().map_or(0.0, |border| border.width),
+            border_radius: self.border.as_ref().map_or(0.0, |border| border.radius),
+            background_custom,
+            border_custom,
+            background,
+            border_color,
+        };
+
+        arena.container_data.push(raw_data);
+        let data_index = (arena.container_data.len() - 1) as u32;
+
+        let element = RawElement {
+            tag: ElementTag::Container as u8,
+            child_count: 1,
+            flags: 0,
+            children_index,
+            data_index,
+            callback_index: 0,
+            style_index: 0,
+        };
+
+        arena.elements.push(element);
+
+        let index = (arena.elements.len() - 1) as u32;
+        return index as u32;
+    }
+}
+
+/// the color as the host reads it, 0 when not set and a custom color that
+/// can't be read is treated as not set
+pub(super) fn raw_color(color: Option<&Color>) -> (u8, u32) {
+    let custom = color.and_then(Color::custom_rgba);
+    match color {
+        Some(Color::Custom(_)) if custom.is_none() => (0, 0),
+        Some(color) => (color.into(), custom.unwrap_or(0)),
+        None => (0, 0),
+    }
+}
+
+/// the same layout as the host's `RawContainerData`
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct RawContainerData {
+    pub padding: [f32; 4],
+    pub border_width: f32,
+    pub border_radius: f32,
+    pub background_custom: u32,
+    pub border_custom: u32,
+    pub background: u8,
+    pub border_color: u8,
+}
+
+impl<'a, Message> From<Container<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(container: Container<'a, Message>) -> Self {
+        Self::new(container)
+    }
+}
@@ -0,0 +1,73 @@
+use crate::surface::Anchor;
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{Column, Element, Row, Widget};
+
+/// which way a bar runs, so a module can be laid out the same on every edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+}
+
+impl Orientation {
+    /// vertical for surfaces stretched down the left or right edge,
+    /// horizontal otherwise
+    pub fn from_anchor(anchor: Anchor) -> Self {
+        match anchor.is_vertical() {
+            true => Self::Vertical,
+            false => Self::Horizontal,
+        }
+    }
+
+    pub fn is_vertical(self) -> bool {
+        self == Self::Vertical
+    }
+}
+
+/// a `Row` when horizontal and a `Column` when vertical, for the parts of a
+/// bar that follow its edge
+pub struct Flex<'a, Message> {
+    orientation: Orientation,
+    children: Vec<Element<'a, Message>>,
+}
+
+impl<'a, Message> Flex<'a, Message> {
+    pub fn new(orientation: Orientation) -> Self {
+        Self {
+            orientation,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn from_vec(orientation: Orientation, children: Vec<Element<'a, Message>>) -> Self {
+        Self {
+            orientation,
+            children,
+        }
+    }
+}
+
+impl<'a, Message> Widget<Message> for Flex<'a, Message> {
+    fn arena_index(
+        &mut self,
+        arena: &mut ElementsMemoryArena,
+        callbacks: &mut Vec<CallbackType>,
+    ) -> u32 {
+        let children = std::mem::take(&mut self.children);
+
+        match self.orientation {
+            Orientation::Horizontal => Row::from_vec(children).arena_index(arena, callbacks),
+            Orientation::Vertical => Column::from_vec(children).arena_index(arena, callbacks),
+        }
+    }
+}
+
+impl<'a, Message> From<Flex<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(flex: Flex<'a, Message>) -> Self {
+        Self::new(flex)
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{Element, ElementTag, RawElement, Widget};
+
+/// gets the first item that can be seen after the grid was scrolled and
+/// returns the message id along with the item to pass to it
+pub type GridFn = Box<dyn Fn(u32) -> (u32, u32) + Send + Sync>;
+
+/// `total` items laid out in rows of `columns` that scroll, for lists too
+/// long to build every view like an emoji picker's
+///
+/// only the cells that can be seen from `first` are built with `cell`, the
+/// shell leaves room for the rest. keep the item from `on_scroll` and pass
+/// it back as `first` so the cells follow the scrolling
+///
+/// example:
+/// ```
+/// Grid::new(8, results.len() as u32, self.first, |index| {
+///     Text::new(results[index as usize].text.clone()).size(20.0).into()
+/// })
+/// .cell_size(32.0, 32.0)
+/// .height(256.0)
+/// .on_scroll(Box::new(|first| (Message::Scrolled(first).into(), first)))
+/// ```
+pub struct Grid<'a, Message> {
+    pub columns: u32,
+    pub total: u32,
+    /// the first item that can be seen
+    pub first: u32,
+    /// in logical pixels, 32 if not set
+    pub cell_width: Option<f32>,
+    pub cell_height: Option<f32>,
+    /// in logical pixels, filling the space it's given if not set
+    pub height: Option<f32>,
+    pub cell: Box<dyn FnMut(u32) -> Element<'a, Message> + 'a>,
+    pub on_scroll: Option<GridFn>,
+}
+
+impl<'a, Message> Grid<'a, Message> {
+    /// how big cells are when not set, the same as the shell's default
+    const DEFAULT_CELL_SIZE: f32 = 32.0;
+    /// how many rows are built when the grid fills the space it's given, as
+    /// the module can't know how tall that is
+    const FILL_ROWS: u32 = 16;
+    /// the most children the host reads from one element
+    const MAX_CELLS: u32 = u8::MAX as u32;
+
+    pub fn new(
+        columns: u32,
+        total: u32,
+        first: u32,
+        cell: impl FnMut(u32) -> Element<'a, Message> + 'a,
+    ) -> Self {
+        Self {
+            columns: columns.max(1),
+            total,
+            first,
+            cell_width: None,
+            cell_height: None,
+            height: None,
+            cell: Box::new(cell),
+            on_scroll: None,
+        }
+    }
+
+    pub fn cell_size(mut self, width: f32, height: f32) -> Self {
+        self.cell_width = Some(width);
+        self.cell_height = Some(height);
+        self
+    }
+
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    pub fn on_scroll(mut self, f: GridFn) -> Self {
+        self.on_scroll = Some(f);
+        self
+    }
+
+    /// the items the cells are built for, from the start of the row with
+    /// `first` to the end of the last row that can be seen
+    fn visible(&self) -> std::ops::Range<u32> {
+        let cell_height = self.cell_height.unwrap_or(Self::DEFAULT_CELL_SIZE);
+        // one more row for the one cut off at the bottom
+        let rows = match self.height {
+            Some(height) if cell_height > 0.0 => (height / cell_height).ceil() as u32 + 1,
+            _ => Self::FILL_ROWS,
+        };
+        // whole rows, so the host can tell where the cells that weren't
+        // built go
+        let rows = rows.min(Self::MAX_CELLS / self.columns).max(1);
+
+        let start = self.first.min(self.total) / self.columns * self.columns;
+        let end = start
+            .saturating_add(rows.saturating_mul(self.columns))
+            .min(self.total);
+        return start..end;
+    }
+}
+
+impl<'a, Message> Widget<Message> for Grid<'a, Message> {
+    fn arena_index(
+        &mut self,
+        arena: &mut ElementsMemoryArena,
+        callbacks: &mut Vec<CallbackType>,
+    ) -> u32 {
+        let visible = self.visible();
+
+        let mut children = vec![];
+        for index in visible.clone() {
+            let mut cell = (self.cell)(index);
+            children.push(cell.widget.arena_index(arena, callbacks));
+        }
+        let child_count = children.len();
+        arena.children.push(children);
+        let children_index = (arena.children.len() - 1) as u32;
+
+        let raw_data = RawGridData {
+            columns: self.columns,
+            total: self.total,
+            first: visible.start,
+            // 0 is unset for all of them
+            cell_width: self.cell_width.unwrap_or(0.0),
+            cell_height: self.cell_height.unwrap_or(0.0),
+            height: self.height.unwrap_or(0.0),
+        };
+
+        arena.grid_data.push(raw_data);
+        let data_index = (arena.grid_data.len() - 1) as u32;
+
+        let mut callback_index: u32 = 0;
+        if let Some(callback) = self.on_scroll.take() {
+            callbacks.push(CallbackType::Grid(callback));
+            callback_index = callbacks.len() as u32;
+        }
+
+        let element = RawElement {
+            tag: ElementTag::Grid as u8,
+            child_count: child_count.min(u8::MAX as usize) as u8,
+            flags: 0,
+            children_index,
+            data_index,
+            callback_index,
+            style_index: 0,
+        };
+
+        arena.elements.push(element);
+
+        let index = (arena.elements.len() - 1) as u32;
+        return index as u32;
+    }
+}
+
+/// the same layout as the host's `RawGridData`
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct RawGridData {
+    pub columns: u32,
+    pub total: u32,
+    pub first: u32,
+    pub cell_width: f32,
+    pub cell_height: f32,
+    pub height: f32,
+}
+
+impl<'a, Message> From<Grid<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(grid: Grid<'a, Message>) -> Self {
+        Self::new(grid)
+    }
+}
@@ -0,0 +1,148 @@
+use std::borrow::Cow;
+
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{Element, ElementTag, RawElement, Widget};
+
+/// an icon from the user's icon theme, a png the module has or a screenshot
+/// the shell took for it
+///
+/// example:
+/// ```
+/// Image::icon("audio-volume-high").size(16.0)
+/// Image::png(&include_bytes!("logo.png")[..])
+/// Image::screenshot(&path).width(160.0)
+/// ```
+pub struct Image {
+    pub source: Source,
+    /// in logical pixels, the image's own size if not set
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+}
+
+pub enum Source {
+    /// found by the shell in the icon theme, like `firefox` or
+    /// `network-wireless-symbolic`
+    Icon(String),
+    /// the bytes of a png file, the shell keeps the decoded image while the
+    /// module keeps sending the same bytes
+    Png(Cow<'static, [u8]>),
+    /// the path `screenshot::take` gave, drawn as missing if it's some other
+    /// file
+    Screenshot(String),
+}
+
+impl Source {
+    fn kind(&self) -> u8 {
+        match self {
+            Self::Icon(_) => 0,
+            Self::Png(_) => 1,
+            Self::Screenshot(_) => 2,
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Self::Icon(name) | Self::Screenshot(name) => name.as_bytes(),
+            Self::Png(bytes) => bytes,
+        }
+    }
+}
+
+impl Image {
+    pub fn icon(name: impl Into<String>) -> Self {
+        Self::new(Source::Icon(name.into()))
+    }
+
+    pub fn png(bytes: impl Into<Cow<'static, [u8]>>) -> Self {
+        Self::new(Source::Png(bytes.into()))
+    }
+
+    pub fn screenshot(path: impl Into<String>) -> Self {
+        Self::new(Source::Screenshot(path.into()))
+    }
+
+    fn new(source: Source) -> Self {
+        Self {
+            source,
+            width: None,
+            height: None,
+        }
+    }
+
+    /// the same width and height
+    pub fn size(mut self, size: f32) -> Self {
+        self.width = Some(size);
+        self.height = Some(size);
+        self
+    }
+
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = Some(height);
+        self
+    }
+}
+
+impl<Message> Widget<Message> for Image {
+    fn arena_index(&mut self, arena: &mut ElementsMemoryArena, _: &mut Vec<CallbackType>) -> u32 {
+        let bytes: &[u8] = match &self.source {
+            // static bytes stay where they are, so aren't copied every view
+            Source::Png(Cow::Borrowed(bytes)) => bytes,
+            source => {
+                arena.image_bytes.push(source.bytes().to_vec());
+                &arena.image_bytes[arena.image_bytes.len() - 1]
+            }
+        };
+
+        let raw_data = RawImageData {
+            source_ptr: bytes.as_ptr() as u32,
+            source_len: bytes.len() as u32,
+            // 0 is the image's own size
+            width: self.width.unwrap_or(0.0),
+            height: self.height.unwrap_or(0.0),
+            kind: self.source.kind(),
+        };
+
+        arena.image_data.push(raw_data);
+        let data_index = (arena.image_data.len() - 1) as u32;
+
+        let element = RawElement {
+            tag: ElementTag::Image as u8,
+            child_count: 0,
+            flags: 0,
+            children_index: 0,
+            data_index,
+            callback_index: 0,
+            style_index: 0,
+        };
+
+        arena.elements.push(element);
+
+        let index = (arena.elements.len() - 1) as u32;
+        return index as u32;
+    }
+}
+
+/// the same layout as the host's `RawImageData`
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct RawImageData {
+    /// the icon name or screenshot path as utf-8, or the png's bytes
+    pub source_ptr: u32,
+    pub source_len: u32,
+    pub width: f32,
+    pub height: f32,
+    /// 0 for an icon name, 1 for a png and 2 for a screenshot
+    pub kind: u8,
+}
+
+impl<'a, Message> From<Image> for Element<'a, Message> {
+    fn from(image: Image) -> Self {
+        Self::new(image)
+    }
+}
@@ -0,0 +1,86 @@
+use crate::ElementsMemoryArena;
+
+/// how much space a `Row` or `Column` takes up
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Length {
+    /// only as much as its children need
+    #[default]
+    Shrink,
+    Fill,
+    /// a share of the space left, `FillPortion(2)` gets twice as much as
+    /// `FillPortion(1)` or `Fill` next to it
+    FillPortion(u16),
+    /// in logical pixels
+    Fixed(f32),
+}
+
+impl From<f32> for Length {
+    fn from(pixels: f32) -> Self {
+        Self::Fixed(pixels)
+    }
+}
+
+/// where children go across a `Row` or `Column`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alignment {
+    /// the top of a row or the left of a column
+    #[default]
+    Start = 0,
+    Center = 1,
+    End = 2,
+}
+
+/// the spacing, padding, size and alignment shared by rows and columns
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Layout {
+    /// top, right, bottom and left in logical pixels
+    pub padding: [f32; 4],
+    pub spacing: f32,
+    pub width: Length,
+    pub height: Length,
+    pub align: Alignment,
+}
+
+impl Layout {
+    /// adds the layout to the arena, returning its index
+    pub fn arena_index(&self, arena: &mut ElementsMemoryArena) -> u32 {
+        let (width_kind, width) = raw_length(self.width);
+        let (height_kind, height) = raw_length(self.height);
+
+        arena.layout_data.push(RawLayoutData {
+            padding: self.padding,
+            spacing: self.spacing,
+            width,
+            height,
+            width_kind,
+            height_kind,
+            align: self.align as u8,
+        });
+
+        return (arena.layout_data.len() - 1) as u32;
+    }
+}
+
+/// the kind of length as the host reads it, along with its portion or pixels
+fn raw_length(length: Length) -> (u8, f32) {
+    match length {
+        Length::Shrink => (0, 0.0),
+        Length::Fill => (1, 0.0),
+        Length::FillPortion(portion) => (2, portion as f32),
+        Length::Fixed(pixels) => (3, pixels),
+    }
+}
+
+/// the same layout as the host's `RawLayoutData`
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct RawLayoutData {
+    pub padding: [f32; 4],
+    pub spacing: f32,
+    pub width: f32,
+    pub height: f32,
+    pub width_kind: u8,
+    pub height_kind: u8,
+    pub align: u8,
+}
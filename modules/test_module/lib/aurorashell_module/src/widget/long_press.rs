@@ -0,0 +1,70 @@
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{Element, ElementTag, RawElement, Widget};
+
+pub type LongPressFn = Box<dyn Send + Sync + Fn() -> u32>;
+
+/// calls `on_long_press` when a finger is held on `inner` on a touchscreen
+///
+/// taps and drags still go to `inner`, so a button can be tapped and long
+/// pressed for different things, and a long press doesn't also press it
+pub struct LongPress<'a, Message> {
+    pub inner: Element<'a, Message>,
+    pub callback: Option<LongPressFn>,
+}
+
+impl<'a, Message> LongPress<'a, Message> {
+    pub fn new(inner: Element<'a, Message>) -> Self {
+        Self {
+            inner,
+            callback: None,
+        }
+    }
+
+    pub fn on_long_press(mut self, f: LongPressFn) -> Self {
+        self.callback = Some(f);
+        self
+    }
+}
+
+impl<'a, Message> Widget<Message> for LongPress<'a, Message> {
+    fn arena_index(
+        &mut self,
+        arena: &mut ElementsMemoryArena,
+        callbacks: &mut Vec<CallbackType>,
+    ) -> u32 {
+        let inner = vec![self.inner.widget.arena_index(arena, callbacks)];
+        arena.children.push(inner);
+        let children_index = (arena.children.len() - 1) as u32;
+
+        let mut callback_index: u32 = 0;
+        if let Some(callback) = self.callback.take() {
+            callbacks.push(CallbackType::LongPress(callback));
+            callback_index = callbacks.len() as u32;
+        }
+
+        let element = RawElement {
+            tag: ElementTag::LongPress as u8,
+            child_count: 1,
+            flags: 0,
+            children_index,
+            data_index: 0,
+            callback_index,
+            style_index: 0,
+        };
+
+        arena.elements.push(element);
+
+        let index = (arena.elements.len() - 1) as u32;
+        return index as u32;
+    }
+}
+
+impl<'a, Message> From<LongPress<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(long_press: LongPress<'a, Message>) -> Self {
+        Self::new(long_press)
+    }
+}
@@ -1,18 +1,44 @@
 use crate::{CallbackType, ElementsMemoryArena};
 
 pub(crate) mod button;
+pub(crate) mod cached;
 pub(crate) mod column;
+pub(crate) mod container;
+pub(crate) mod flex;
+pub(crate) mod grid;
+pub(crate) mod image;
+pub(crate) mod layout;
+pub(crate) mod long_press;
+pub(crate) mod mouse_area;
+pub(crate) mod progress_bar;
 pub(crate) mod row;
 pub(crate) mod slider;
 pub(crate) mod stack;
 pub(crate) mod text;
+pub(crate) mod text_input;
+pub(crate) mod toggler;
+pub(crate) mod tooltip;
+pub(crate) mod tray_icon;
 
 pub use button::{Button, ButtonFn};
+pub use cached::Cached;
 pub use column::Column;
+pub use container::{Border, Container};
+pub use flex::{Flex, Orientation};
+pub use grid::{Grid, GridFn};
+pub use image::Image;
+pub use layout::{Alignment, Length};
+pub use long_press::{LongPress, LongPressFn};
+pub use mouse_area::{MouseArea, Scroll, ScrollFn};
+pub use progress_bar::ProgressBar;
 pub use row::Row;
 pub use slider::{Slider, SliderFn, SliderNumberType};
 pub use stack::Stack;
 pub use text::Text;
+pub use text_input::{TextInput, TextInputFn};
+pub use toggler::{Toggler, TogglerFn};
+pub use tooltip::{Tooltip, TooltipPosition};
+pub use tray_icon::{TrayClick, TrayIcon, TrayIconFn};
 
 pub trait Widget<Message> {
     /// gets the index to the underlying RawElement that is stored
@@ -45,6 +71,26 @@ pub enum ElementTag {
     Button = 4,
     Slider = 5,
     Stack = 6,
+    TrayIcon = 7,
+    LongPress = 8,
+    Container = 9,
+    Image = 10,
+    ProgressBar = 11,
+    TextInput = 12,
+    Grid = 13,
+    Tooltip = 14,
+    MouseArea = 15,
+    Toggler = 16,
+}
+
+/// bits of `RawElement::flags`
+pub(crate) mod element_flags {
+    /// the element and its children don't change between views, so the shell
+    /// can reuse what it built last time
+    pub const STATIC: u8 = 1 << 0;
+    /// text, sliders and progress bars run top to bottom, for bars on the
+    /// left or right edge
+    pub const VERTICAL: u8 = 1 << 1;
 }
 
 // we use u32 to pass pointers instead of *const u8 because the host side
@@ -56,6 +102,7 @@ pub enum ElementTag {
 pub struct RawElement {
     pub tag: u8,
     pub child_count: u8,
+    pub flags: u8,
     pub children_index: u32,
     pub data_index: u32,
     pub callback_index: u32,
@@ -0,0 +1,146 @@
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{ButtonFn, Element, ElementTag, RawElement, Widget};
+
+/// gets how far the wheel was scrolled and returns the message id along with
+/// the scroll to pass to it
+pub type ScrollFn = Box<dyn Fn(Scroll) -> (u32, Scroll) + Send + Sync>;
+
+/// how far the wheel or touchpad was scrolled, in lines
+///
+/// scrolling up and to the left is positive, touchpads scroll less than a
+/// line at a time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scroll {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Scroll {
+    /// unpacks the scroll the host sends to `run_callback`, the bits of `x`
+    /// then the bits of `y`
+    pub(crate) fn from_data(data: u64) -> Self {
+        Self {
+            x: f32::from_bits((data >> 32) as u32),
+            y: f32::from_bits(data as u32),
+        }
+    }
+}
+
+/// calls back when the pointer goes over or leaves `inner`, when it's right
+/// clicked and when it's scrolled over
+///
+/// left clicks still go to `inner`, so a button can be wrapped to also be
+/// scrolled
+///
+/// example:
+/// ```
+/// MouseArea::new(icon.into())
+///     .on_right_press(Box::new(|| Message::ToggleMute.into()))
+///     .on_scroll(Box::new(|scroll| (Message::Scrolled(scroll).into(), scroll)))
+/// ```
+pub struct MouseArea<'a, Message> {
+    pub inner: Element<'a, Message>,
+    pub on_enter: Option<ButtonFn>,
+    pub on_exit: Option<ButtonFn>,
+    pub on_right_press: Option<ButtonFn>,
+    pub on_scroll: Option<ScrollFn>,
+}
+
+impl<'a, Message> MouseArea<'a, Message> {
+    pub fn new(inner: Element<'a, Message>) -> Self {
+        Self {
+            inner,
+            on_enter: None,
+            on_exit: None,
+            on_right_press: None,
+            on_scroll: None,
+        }
+    }
+
+    pub fn on_enter(mut self, f: ButtonFn) -> Self {
+        self.on_enter = Some(f);
+        self
+    }
+
+    pub fn on_exit(mut self, f: ButtonFn) -> Self {
+        self.on_exit = Some(f);
+        self
+    }
+
+    pub fn on_right_press(mut self, f: ButtonFn) -> Self {
+        self.on_right_press = Some(f);
+        self
+    }
+
+    pub fn on_scroll(mut self, f: ScrollFn) -> Self {
+        self.on_scroll = Some(f);
+        self
+    }
+}
+
+impl<'a, Message> Widget<Message> for MouseArea<'a, Message> {
+    fn arena_index(
+        &mut self,
+        arena: &mut ElementsMemoryArena,
+        callbacks: &mut Vec<CallbackType>,
+    ) -> u32 {
+        let inner = vec![self.inner.widget.arena_index(arena, callbacks)];
+        arena.children.push(inner);
+        let children_index = (arena.children.len() - 1) as u32;
+
+        // 0 is no callback for all of them
+        let mut push = |callback: Option<CallbackType>| match callback {
+            Some(callback) => {
+                callbacks.push(callback);
+                callbacks.len() as u32
+            }
+            None => 0,
+        };
+
+        let raw_data = RawMouseAreaData {
+            on_enter: push(self.on_enter.take().map(CallbackType::Button)),
+            on_exit: push(self.on_exit.take().map(CallbackType::Button)),
+            on_right_press: push(self.on_right_press.take().map(CallbackType::Button)),
+            on_scroll: push(self.on_scroll.take().map(CallbackType::Scroll)),
+        };
+
+        arena.mouse_area_data.push(raw_data);
+        let data_index = (arena.mouse_area_data.len() - 1) as u32;
+
+        let element = RawElement {
+            tag: ElementTag::MouseArea as u8,
+            child_count: 1,
+            flags: 0,
+            children_index,
+            data_index,
+            // the callbacks are in `RawMouseAreaData`
+            callback_index: 0,
+            style_index: 0,
+        };
+
+        arena.elements.push(element);
+
+        let index = (arena.elements.len() - 1) as u32;
+        return index as u32;
+    }
+}
+
+/// the same layout as the host's `RawMouseAreaData`
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct RawMouseAreaData {
+    pub on_enter: u32,
+    pub on_exit: u32,
+    pub on_right_press: u32,
+    pub on_scroll: u32,
+}
+
+impl<'a, Message> From<MouseArea<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(mouse_area: MouseArea<'a, Message>) -> Self {
+        Self::new(mouse_area)
+    }
+}
@@ -0,0 +1,130 @@
+use crate::theme::Color;
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::container::raw_color;
+use super::{Element, ElementTag, RawElement, Widget, element_flags};
+
+/// a bar filled up to how far `value` is through `range`, for showing levels
+/// like the volume or battery
+///
+/// example:
+/// ```
+/// ProgressBar::new(0.0..=100.0, charge as f32).length(48.0).color(Color::Color12)
+/// ```
+pub struct ProgressBar {
+    pub range: std::ops::RangeInclusive<f32>,
+    pub value: f32,
+    /// in logical pixels, filling the space it's given if not set
+    pub length: Option<f32>,
+    /// in logical pixels, the shell's default if not set
+    pub thickness: Option<f32>,
+    /// the theme's foreground if not set
+    pub color: Option<Color>,
+    /// `Color03` if not set
+    pub background: Option<Color>,
+    /// fills from the bottom up, for bars on the left or right edge
+    pub vertical: bool,
+}
+
+impl ProgressBar {
+    pub fn new(range: std::ops::RangeInclusive<f32>, value: f32) -> Self {
+        Self {
+            range,
+            value,
+            length: None,
+            thickness: None,
+            color: None,
+            background: None,
+            vertical: false,
+        }
+    }
+
+    pub fn length(mut self, length: f32) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    pub fn thickness(mut self, thickness: f32) -> Self {
+        self.thickness = Some(thickness);
+        self
+    }
+
+    /// the color of the filled part
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// the color of the empty part
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    pub fn vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+}
+
+impl<Message> Widget<Message> for ProgressBar {
+    fn arena_index(&mut self, arena: &mut ElementsMemoryArena, _: &mut Vec<CallbackType>) -> u32 {
+        let (bar, bar_custom) = raw_color(self.color.as_ref());
+        let (background, background_custom) = raw_color(self.background.as_ref());
+
+        let raw_data = RawProgressBarData {
+            min: *self.range.start(),
+            max: *self.range.end(),
+            value: self.value,
+            // 0 is unset for both
+            length: self.length.unwrap_or(0.0),
+            thickness: self.thickness.unwrap_or(0.0),
+            bar_custom,
+            background_custom,
+            bar,
+            background,
+        };
+
+        arena.progress_bar_data.push(raw_data);
+        let data_index = (arena.progress_bar_data.len() - 1) as u32;
+
+        let element = RawElement {
+            tag: ElementTag::ProgressBar as u8,
+            child_count: 0,
+            flags: match self.vertical {
+                true => element_flags::VERTICAL,
+                false => 0,
+            },
+            children_index: 0,
+            data_index,
+            callback_index: 0,
+            style_index: 0,
+        };
+
+        arena.elements.push(element);
+
+        let index = (arena.elements.len() - 1) as u32;
+        return index as u32;
+    }
+}
+
+/// the same layout as the host's `RawProgressBarData`
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct RawProgressBarData {
+    pub min: f32,
+    pub max: f32,
+    pub value: f32,
+    pub length: f32,
+    pub thickness: f32,
+    pub bar_custom: u32,
+    pub background_custom: u32,
+    pub bar: u8,
+    pub background: u8,
+}
+
+impl<'a, Message> From<ProgressBar> for Element<'a, Message> {
+    fn from(progress_bar: ProgressBar) -> Self {
+        Self::new(progress_bar)
+    }
+}
@@ -2,12 +2,14 @@ use std::any::Any;
 
 use crate::{CallbackType, ElementsMemoryArena};
 
-use super::{Element, ElementTag, RawElement, Widget};
+use super::{Element, ElementTag, RawElement, Widget, element_flags};
 
 pub struct Slider<T> {
     pub range: std::ops::RangeInclusive<T>,
     pub value: T,
     pub on_change: Option<SliderFn<T>>,
+    /// slides up and down, for bars on the left or right edge
+    pub vertical: bool,
 }
 
 impl<T: SliderNumber> Slider<T> {
@@ -16,8 +18,14 @@ impl<T: SliderNumber> Slider<T> {
             range,
             value,
             on_change: Some(on_change),
+            vertical: false,
         }
     }
+
+    pub fn vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
 }
 
 impl<'a, Message, T: SliderNumber + 'static> Widget<Message> for Slider<T> {
@@ -57,6 +65,10 @@ impl<'a, Message, T: SliderNumber + 'static> Widget<Message> for Slider<T> {
         let element = RawElement {
             tag: ElementTag::Slider as u8,
             child_count: 0,
+            flags: match self.vertical {
+                true => element_flags::VERTICAL,
+                false => 0,
+            },
             children_index: 0,
             data_index,
             callback_index,
@@ -41,6 +41,7 @@ impl<'a, Message> Widget<Message> for Stack<'a, Message> {
                 Some(v) => v,
                 None => 255,
             },
+            flags: 0,
             children_index,
             data_index: 0,
             callback_index: 0,
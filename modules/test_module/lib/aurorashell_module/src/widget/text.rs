@@ -1,8 +1,9 @@
 use std::borrow::Cow;
 
-use crate::{CallbackType, ElementsMemoryArena, theme::Color};
+use crate::theme::Color;
+use crate::{CallbackType, ElementsMemoryArena};
 
-use super::{Element, ElementTag, RawElement, Widget};
+use super::{Element, ElementTag, RawElement, Widget, element_flags};
 
 pub struct Text<'a> {
     pub fragment: Fragment<'a>,
@@ -21,6 +22,23 @@ impl<'a> Text<'a> {
         self.style = Some(style);
         self
     }
+
+    /// the color of the text, the theme's foreground if not set
+    pub fn color(mut self, color: Color) -> Self {
+        self.style.get_or_insert_default().text_color = Some(color);
+        self
+    }
+
+    /// the size of the text in logical pixels, 11 if not set
+    pub fn size(mut self, size: f32) -> Self {
+        self.style.get_or_insert_default().size = Some(size);
+        self
+    }
+
+    pub fn font(mut self, font: Font) -> Self {
+        self.style.get_or_insert_default().font = font;
+        self
+    }
 }
 
 impl<'a, Message> Widget<Message> for Text<'a> {
@@ -36,19 +54,42 @@ impl<'a, Message> Widget<Message> for Text<'a> {
 
         let mut style_index = 0;
         if let Some(style) = &self.style {
+            // a custom color that can't be read is left to the theme
+            let custom_color = style.text_color.as_ref().and_then(Color::custom_rgba);
+            let text_color = match &style.text_color {
+                Some(Color::Custom(_)) if custom_color.is_none() => 0,
+                Some(color) => color.into(),
+                None => 0,
+            };
+
+            let mut font_flags = 0;
+            if style.font.bold {
+                font_flags |= font_flags::BOLD;
+            }
+            if style.font.italic {
+                font_flags |= font_flags::ITALIC;
+            }
+
             let raw_style = RawStyle {
-                text_color: match &style.text_color {
-                    Some(color) => color.into(),
-                    None => 0,
-                },
+                text_color,
+                font_family: style.font.family as u8,
+                font_flags,
+                custom_color: custom_color.unwrap_or(0),
+                size: style.size.unwrap_or(0.0),
             };
             arena.text_style.push(raw_style);
             style_index = arena.text_style.len() as u32;
         }
 
+        let mut flags = 0;
+        if self.style.as_ref().is_some_and(|style| style.vertical) {
+            flags |= element_flags::VERTICAL;
+        }
+
         let element = RawElement {
             tag: ElementTag::Text as u8,
             child_count: 0,
+            flags,
             children_index: 0,
             data_index,
             callback_index: 0,
@@ -112,15 +153,100 @@ impl<'a> IntoFragment<'a> for String {
 }
 
 /// style of the `Text` widget
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Style {
     /// color of the text
     text_color: Option<Color>,
+    /// size of the text in logical pixels
+    size: Option<f32>,
+    font: Font,
+    /// the letters are stacked top to bottom, for bars on the left or right
+    /// edge
+    vertical: bool,
+}
+
+impl Style {
+    pub fn text_color(mut self, color: Color) -> Self {
+        self.text_color = Some(color);
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn font(mut self, font: Font) -> Self {
+        self.font = font;
+        self
+    }
+
+    pub fn vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+}
+
+/// the font of the `Text` widget
+///
+/// example:
+/// ```
+/// Font::new(Family::Monospace).bold()
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Font {
+    family: Family,
+    bold: bool,
+    italic: bool,
+}
+
+impl Font {
+    pub fn new(family: Family) -> Self {
+        Self {
+            family,
+            ..Default::default()
+        }
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Default, Clone, Copy)]
+pub enum Family {
+    /// the font the shell's own widgets use
+    #[default]
+    Shell = 0,
+    SansSerif = 1,
+    Serif = 2,
+    Monospace = 3,
+}
+
+mod font_flags {
+    pub const BOLD: u8 = 1 << 0;
+    pub const ITALIC: u8 = 1 << 1;
 }
 
 /// style of the `Text` widget
+#[repr(C)]
 #[derive(Debug)]
 pub struct RawStyle {
     /// color of the text
     text_color: u8,
+    /// `Family` as a u8
+    font_family: u8,
+    /// `font_flags`
+    font_flags: u8,
+    /// rgba when `text_color` is `Color::Custom`
+    custom_color: u32,
+    /// 0 leaves it to the shell
+    size: f32,
 }
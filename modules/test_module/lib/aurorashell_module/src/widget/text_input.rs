@@ -0,0 +1,123 @@
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{Element, ElementTag, RawElement, Widget};
+
+/// gets the whole text after every change and returns the message id along
+/// with the text to pass to it
+pub type TextInputFn = Box<dyn Fn(String) -> (u32, String) + Send + Sync>;
+
+/// a box to type into, showing `value`
+///
+/// the surface needs `KeyboardInteractivity::OnDemand` (or `Exclusive`) for
+/// the box to get typed into
+///
+/// example:
+/// ```
+/// TextInput::new("search emoji", &self.query)
+///     .on_input(Box::new(|text| (Message::Search(text.clone()).into(), text)))
+/// ```
+pub struct TextInput {
+    pub placeholder: String,
+    pub value: String,
+    /// in logical pixels, filling the space it's given if not set
+    pub width: Option<f32>,
+    /// the size of the text in logical pixels, 11 if not set
+    pub size: Option<f32>,
+    pub on_input: Option<TextInputFn>,
+}
+
+impl TextInput {
+    pub fn new(placeholder: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            placeholder: placeholder.into(),
+            value: value.into(),
+            width: None,
+            size: None,
+            on_input: None,
+        }
+    }
+
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn on_input(mut self, f: TextInputFn) -> Self {
+        self.on_input = Some(f);
+        self
+    }
+}
+
+impl<Message> Widget<Message> for TextInput {
+    fn arena_index(
+        &mut self,
+        arena: &mut ElementsMemoryArena,
+        callbacks: &mut Vec<CallbackType>,
+    ) -> u32 {
+        arena.text_strings.push(std::mem::take(&mut self.value));
+        let value = &arena.text_strings[arena.text_strings.len() - 1];
+        let (value_ptr, value_len) = (value.as_ptr() as u32, value.len() as u32);
+
+        arena
+            .text_strings
+            .push(std::mem::take(&mut self.placeholder));
+        let placeholder = &arena.text_strings[arena.text_strings.len() - 1];
+
+        let raw_data = RawTextInputData {
+            value_ptr,
+            value_len,
+            placeholder_ptr: placeholder.as_ptr() as u32,
+            placeholder_len: placeholder.len() as u32,
+            // 0 is unset for both
+            width: self.width.unwrap_or(0.0),
+            size: self.size.unwrap_or(0.0),
+        };
+
+        arena.text_input_data.push(raw_data);
+        let data_index = (arena.text_input_data.len() - 1) as u32;
+
+        let mut callback_index: u32 = 0;
+        if let Some(callback) = self.on_input.take() {
+            callbacks.push(CallbackType::TextInput(callback));
+            callback_index = callbacks.len() as u32;
+        }
+
+        let element = RawElement {
+            tag: ElementTag::TextInput as u8,
+            child_count: 0,
+            flags: 0,
+            children_index: 0,
+            data_index,
+            callback_index,
+            style_index: 0,
+        };
+
+        arena.elements.push(element);
+
+        let index = (arena.elements.len() - 1) as u32;
+        return index as u32;
+    }
+}
+
+/// the same layout as the host's `RawTextInputData`
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct RawTextInputData {
+    pub value_ptr: u32,
+    pub value_len: u32,
+    pub placeholder_ptr: u32,
+    pub placeholder_len: u32,
+    pub width: f32,
+    pub size: f32,
+}
+
+impl<'a, Message> From<TextInput> for Element<'a, Message> {
+    fn from(text_input: TextInput) -> Self {
+        Self::new(text_input)
+    }
+}
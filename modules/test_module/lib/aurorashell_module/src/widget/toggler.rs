@@ -0,0 +1,75 @@
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{Element, ElementTag, RawElement, Widget};
+
+/// gets whether the toggler is on after it was flipped and returns the
+/// message id along with it
+pub type TogglerFn = Box<dyn Fn(bool) -> (u32, bool) + Send + Sync>;
+
+/// a switch that's on or off, like for turning wifi on and off
+///
+/// it doesn't flip itself, the module has to pass the new state back in on
+/// its next view
+///
+/// example:
+/// ```
+/// Toggler::new(self.wifi_enabled)
+///     .on_toggle(Box::new(|on| (Message::WifiToggled(on).into(), on)))
+/// ```
+pub struct Toggler {
+    pub is_toggled: bool,
+    pub on_toggle: Option<TogglerFn>,
+}
+
+impl Toggler {
+    pub fn new(is_toggled: bool) -> Self {
+        Self {
+            is_toggled,
+            on_toggle: None,
+        }
+    }
+
+    pub fn on_toggle(mut self, f: TogglerFn) -> Self {
+        self.on_toggle = Some(f);
+        self
+    }
+}
+
+impl<Message> Widget<Message> for Toggler {
+    fn arena_index(
+        &mut self,
+        arena: &mut ElementsMemoryArena,
+        callbacks: &mut Vec<CallbackType>,
+    ) -> u32 {
+        let mut callback_index: u32 = 0;
+        if let Some(callback) = self.on_toggle.take() {
+            callbacks.push(CallbackType::Toggler(callback));
+            callback_index = callbacks.len() as u32;
+        }
+
+        let element = RawElement {
+            tag: ElementTag::Toggler as u8,
+            child_count: 0,
+            flags: 0,
+            children_index: 0,
+            // whether it's on is all the shell needs, so there's no data for it
+            data_index: self.is_toggled as u32,
+            callback_index,
+            style_index: 0,
+        };
+
+        arena.elements.push(element);
+
+        let index = (arena.elements.len() - 1) as u32;
+        return index as u32;
+    }
+}
+
+impl<'a, Message> From<Toggler> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(toggler: Toggler) -> Self {
+        Self::new(toggler)
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{Element, ElementTag, RawElement, Widget};
+
+/// where the tooltip shows up next to the element it's on
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TooltipPosition {
+    Top = 0,
+    Bottom = 1,
+    Left = 2,
+    Right = 3,
+    /// next to the pointer, moving with it
+    FollowCursor = 4,
+}
+
+/// shows `text` next to `inner` while the pointer is over it
+///
+/// the tooltip is drawn inside the module's surface, so pick a position with
+/// room for it, like `Bottom` on a bar at the top of the screen with some
+/// height to spare, or `Left` and `Right` on a wide one
+///
+/// example:
+/// ```
+/// Tooltip::new(icon.into(), "battery at 80%", TooltipPosition::Bottom)
+/// ```
+pub struct Tooltip<'a, Message> {
+    pub inner: Element<'a, Message>,
+    pub text: String,
+    pub position: TooltipPosition,
+}
+
+impl<'a, Message> Tooltip<'a, Message> {
+    pub fn new(
+        inner: Element<'a, Message>,
+        text: impl Into<String>,
+        position: TooltipPosition,
+    ) -> Self {
+        Self {
+            inner,
+            text: text.into(),
+            position,
+        }
+    }
+}
+
+impl<'a, Message> Widget<Message> for Tooltip<'a, Message> {
+    fn arena_index(
+        &mut self,
+        arena: &mut ElementsMemoryArena,
+        callbacks: &mut Vec<CallbackType>,
+    ) -> u32 {
+        let inner = vec![self.inner.widget.arena_index(arena, callbacks)];
+        arena.children.push(inner);
+        let children_index = (arena.children.len() - 1) as u32;
+
+        arena.text_strings.push(std::mem::take(&mut self.text));
+        let text = &arena.text_strings[arena.text_strings.len() - 1];
+
+        let raw_data = RawTooltipData {
+            text_ptr: text.as_ptr() as u32,
+            text_len: text.len() as u32,
+            position: self.position as u8,
+        };
+
+        arena.tooltip_data.push(raw_data);
+        let data_index = (arena.tooltip_data.len() - 1) as u32;
+
+        let element = RawElement {
+            tag: ElementTag::Tooltip as u8,
+            child_count: 1,
+            flags: 0,
+            children_index,
+            data_index,
+            callback_index: 0,
+            style_index: 0,
+        };
+
+        arena.elements.push(element);
+
+        let index = (arena.elements.len() - 1) as u32;
+        return index as u32;
+    }
+}
+
+/// the same layout as the host's `RawTooltipData`
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct RawTooltipData {
+    pub text_ptr: u32,
+    pub text_len: u32,
+    pub position: u8,
+}
+
+impl<'a, Message> From<Tooltip<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(tooltip: Tooltip<'a, Message>) -> Self {
+        Self::new(tooltip)
+    }
+}
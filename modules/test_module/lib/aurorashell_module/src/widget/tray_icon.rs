@@ -0,0 +1,92 @@
+use crate::{CallbackType, ElementsMemoryArena};
+
+use super::{Element, ElementTag, RawElement, Widget};
+
+pub type TrayIconFn = Box<dyn Send + Sync + Fn(TrayClick) -> u32>;
+
+/// the icon of an app in the system tray, the shell fills it in with the
+/// item at `slot` (counting from 0 in the order the apps showed up) and
+/// draws nothing if there isn't one
+///
+/// the click is passed on to the app either way, `on_click` is only needed
+/// if the module wants to know about it too
+pub struct TrayIcon {
+    pub slot: u32,
+    pub callback: Option<TrayIconFn>,
+}
+
+impl TrayIcon {
+    pub fn new(slot: u32) -> Self {
+        Self {
+            slot,
+            callback: None,
+        }
+    }
+
+    pub fn on_click(mut self, f: TrayIconFn) -> Self {
+        self.callback = Some(f);
+        self
+    }
+}
+
+impl<Message> Widget<Message> for TrayIcon {
+    fn arena_index(
+        &mut self,
+        arena: &mut ElementsMemoryArena,
+        callbacks: &mut Vec<CallbackType>,
+    ) -> u32 {
+        let mut callback_index: u32 = 0;
+        if let Some(callback) = self.callback.take() {
+            callbacks.push(CallbackType::TrayIcon(callback));
+            callback_index = callbacks.len() as u32;
+        }
+
+        let element = RawElement {
+            tag: ElementTag::TrayIcon as u8,
+            child_count: 0,
+            flags: 0,
+            children_index: 0,
+            // the slot is all the shell needs, so there's no data for it
+            data_index: self.slot,
+            callback_index,
+            style_index: 0,
+        };
+
+        arena.elements.push(element);
+
+        let index = (arena.elements.len() - 1) as u32;
+        return index as u32;
+    }
+}
+
+impl<'a, Message> From<TrayIcon> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(tray_icon: TrayIcon) -> Self {
+        Self::new(tray_icon)
+    }
+}
+
+/// how a tray icon was clicked
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayClick {
+    /// left click
+    Activate = 0,
+    /// right click, the app shows its menu
+    ContextMenu = 1,
+    /// middle click
+    SecondaryActivate = 2,
+}
+
+impl TrayClick {
+    pub(crate) fn from_data(data: u64) -> Option<Self> {
+        match data {
+            0 => Some(Self::Activate),
+            1 => Some(Self::ContextMenu),
+            2 => Some(Self::SecondaryActivate),
+            _ => None,
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
-    Expr, Ident, Path, Token, Type,
+    Data, DeriveInput, Expr, Fields, Ident, Path, Token, Type,
     parse::{Parse, ParseStream},
     parse_macro_input,
     punctuated::Punctuated,
@@ -11,6 +11,10 @@ use syn::{
 ///
 /// using this macro more than once will result in a compile error
 ///
+/// a function taking a `&ServiceEvent` can be given last, which makes the
+/// module get the events of the services it registered for (see
+/// `aurorashell_module::services`)
+///
 /// note: add example usage code
 #[proc_macro]
 pub fn create_module(input: TokenStream) -> TokenStream {
@@ -20,8 +24,19 @@ pub fn create_module(input: TokenStream) -> TokenStream {
         update_fn,
         view_fn,
         message_ident,
+        service_event_fn,
     } = parse_macro_input!(input as CreateModuleArgs);
 
+    // the host only gives events to modules with the export
+    let service_event = service_event_fn.map(|service_event_fn| {
+        quote! {
+            #[unsafe(no_mangle)]
+            fn service_event(service: u32, len: u32) -> u64 {
+                ::aurorashell_module::services::run_event(service, len, #service_event_fn)
+            }
+        }
+    });
+
     let expanded = quote! {
         static STATE: std::sync::LazyLock<std::sync::Mutex<Option<Box<#module_ident>>>> =
             std::sync::LazyLock::new(|| std::sync::Mutex::new(None));
@@ -71,6 +86,8 @@ pub fn create_module(input: TokenStream) -> TokenStream {
 
             ::aurorashell_module::view_build_ui(element, id)
         }
+
+        #service_event
     };
 
     TokenStream::from(expanded)
@@ -82,6 +99,7 @@ struct CreateModuleArgs {
     update_fn: Path,
     view_fn: Path,
     message_ident: Ident,
+    service_event_fn: Option<Path>,
 }
 
 impl Parse for CreateModuleArgs {
@@ -97,18 +115,153 @@ impl Parse for CreateModuleArgs {
         let message_ident: Ident = input.parse()?;
         input.parse::<Token![,]>()?;
 
+        let service_event_fn = match input.is_empty() {
+            true => None,
+            false => {
+                let service_event_fn: Path = input.parse()?;
+                if !input.is_empty() {
+                    input.parse::<Token![,]>()?;
+                }
+                Some(service_event_fn)
+            }
+        };
+
         Ok(CreateModuleArgs {
             module_ident,
             new_fn,
             update_fn,
             view_fn,
             message_ident,
+            service_event_fn,
         })
     }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// gives a module's message enum the ids and data `create_module!` needs
+///
+/// variants are numbered from 1 in the order they're declared, 0 is left for
+/// "no message". a variant can carry one value, which is what the widget
+/// callback or command returned along with the id, so a slider's
+/// `SliderValue(f64)` or a text input's `Search(String)` read their value on
+/// their own
+///
+/// it generates `From<Message> for u32` to get a message's id and
+/// `Message::try_from(id, data_ptr)` to turn an id and the data the shell
+/// passed to `update` back into the message
+///
+/// the ids only mean something to the running module, so reordering the
+/// variants is fine as long as the callbacks build their messages through
+/// `.into()` instead of using the numbers
+///
+/// example:
+/// ```rust
+/// #[derive(Debug, ModuleMessage)]
+/// pub enum Message {
+///     ButtonClicked,
+///     SliderValue(f64),
+///     Search(String),
+///     Uptime(Output),
+/// }
+/// ```
+#[proc_macro_derive(ModuleMessage)]
+pub fn derive_module_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    return match module_message(input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(err) => TokenStream::from(err.to_compile_error()),
+    };
+}
+
+fn module_message(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`ModuleMessage` can only be derived for enums",
+            ));
+        }
+    };
+
+    let mut into_arms = Vec::new();
+    let mut from_arms = Vec::new();
+
+    for (index, variant) in variants.iter().enumerate() {
+        let ident = &variant.ident;
+        let id = index as u32 + 1;
+
+        match &variant.fields {
+            Fields::Unit => {
+                into_arms.push(quote! { #name::#ident => #id });
+                from_arms.push(quote! { #id => #name::#ident });
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed[0].ty;
+                into_arms.push(quote! { #name::#ident(..) => #id });
+                from_arms.push(quote! {
+                    #id => {
+                        if data_ptr == 0 {
+                            return Err(::aurorashell_module::MessageError(format!(
+                                "message {} ({}) needs data but got none",
+                                id,
+                                stringify!(#ident),
+                            )));
+                        }
+                        // safety: the callbacks that return this id leak a
+                        // `Box` of the variant's value and the shell passes
+                        // it to `update` once
+                        let data = unsafe { Box::from_raw(data_ptr as *mut #ty) };
+                        #name::#ident(*data)
+                    }
+                });
+            }
+            fields => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "a message can only carry one value, put more in a struct or tuple",
+                ));
+            }
+        }
+    }
+
+    return Ok(quote! {
+        impl #impl_generics From<#name #type_generics> for u32 #where_clause {
+            fn from(value: #name #type_generics) -> Self {
+                match value {
+                    #(#into_arms,)*
+                }
+            }
+        }
+
+        impl #impl_generics #name #type_generics #where_clause {
+            /// turns the id and data the shell passed to `update` back into
+            /// the message
+            pub fn try_from(
+                id: u32,
+                data_ptr: u32,
+            ) -> Result<Self, ::aurorashell_module::MessageError> {
+                Ok(match id {
+                    #(#from_arms,)*
+                    _ => {
+                        return Err(::aurorashell_module::MessageError(format!(
+                            "{} is not a valid message id",
+                            id
+                        )));
+                    }
+                })
+            }
+        }
+    });
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 /// A procedural macro that creates a `Registers` collection with compile-time duplicate detection.
 ///
 /// # Example
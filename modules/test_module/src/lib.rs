@@ -1,6 +1,6 @@
 use aurorashell_module::{
-    Element, MessageError, column,
-    macros::{create_module, registers},
+    Element, column,
+    macros::{ModuleMessage, create_module, registers},
     register::{Interval, PulseAudio},
     row,
     setup::SetupData,
@@ -26,40 +26,13 @@ pub struct Module {
     slider_value2: f64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, ModuleMessage)]
 pub enum Message {
     ButtonClicked,
     SliderValue(f64),
     SliderValue2(f64),
 }
 
-impl From<Message> for u32 {
-    fn from(value: Message) -> Self {
-        match value {
-            Message::ButtonClicked => 1,
-            Message::SliderValue(_) => 2,
-            Message::SliderValue2(_) => 3,
-        }
-    }
-}
-
-impl Message {
-    fn try_from(id: u32, data_ptr: u32) -> Result<Self, MessageError> {
-        Ok(match id {
-            1 => Message::ButtonClicked,
-            2 => {
-                let data = unsafe { Box::from_raw(data_ptr as *mut f64) };
-                Message::SliderValue(*data)
-            }
-            3 => {
-                let data = unsafe { Box::from_raw(data_ptr as *mut f64) };
-                Message::SliderValue2(*data)
-            }
-            _ => return Err(MessageError(format!("{} is not a valid message id", id))),
-        })
-    }
-}
-
 impl Module {
     fn new() -> (Module, SetupData) {
         let id = Id::unique(IdType::LayerSurface);
@@ -103,10 +76,7 @@ impl Module {
                         ..Default::default()
                     },
                 ],
-                registers: registers![
-                    Interval::from_millis(1000),
-                    Interval::from_millis(2000),
-                ],
+                registers: registers![Interval::from_millis(1000), Interval::from_millis(2000),],
             },
         )
     }
@@ -1,22 +1,107 @@
-use crate::runtime::wasm::{self, WasmCallbackData, WasmRuntime, WasmState, WasmUiNode};
-use crate::runtime::{RuntimeEvent, RuntimeModuleId, RuntimeRequest, RuntimeService, RuntimeState};
-use crate::services::audio::AudioService;
-use crate::services::{Service, ServiceEvent, ServiceRequest, SubscriptionData};
+use crate::builtin::region::Region;
+use crate::builtin::{self, Builtins};
+use crate::instrumented::InstrumentedSender;
+use crate::outputs::{self, Output};
+use crate::runtime::wasm::{
+    self, Layout, ModuleAlignment, ModuleColor, ModuleError, ModuleImage, ModuleLength, TextFont,
+    TextFontFamily, TooltipPosition, WasmCallbackData, WasmRuntime, WasmState, WasmUiNode,
+};
+use crate::runtime::{
+    RequestError, RuntimeEvent, RuntimeModuleId, RuntimeRequest, RuntimeService, RuntimeState,
+};
+use crate::services::appearance::{self, AppearanceService, AppearanceState, ColorScheme};
+use crate::services::audio::{self, AudioService, AudioState};
+use crate::services::brightness::{self, BrightnessService, BrightnessState};
+use crate::services::clock::{self, ClockService};
+use crate::services::custom::{CustomService, CustomState};
+use crate::services::dbus::{self, DbusService};
+use crate::services::hotkeys::{self, HotkeysService, HotkeysState};
+use crate::services::inhibit::{self, InhibitService, InhibitState};
+use crate::services::interval::{self, IntervalService, IntervalSubscriptionData};
+use crate::services::ipc::protocol::{
+    AudioInfo, CustomInfo, ModuleErrorInfo, ModuleInfo, Query, Response, ServiceInfo,
+    StateSnapshot, SurfaceInfo, TreeInfo,
+};
+use crate::services::ipc::{self, IpcService};
+use crate::services::kdeconnect::{self, KdeConnectService, KdeConnectState};
+use crate::services::network::{self, NetworkService};
+use crate::services::screenshot::{self, Geometry, ScreenshotService, ScreenshotState};
+use crate::services::session::{self, SessionService, SessionState};
+use crate::services::sysinfo::{SysinfoService, SysinfoState};
+use crate::services::tray::{self, TrayClick, TrayIcon, TrayItems, TrayService, TrayState};
+use crate::services::watch::{self, WatchService};
+use crate::services::weather::WeatherService;
+use crate::services::{
+    self, Service, ServiceEvent, ServiceRequest, ServiceState, SubscriptionData,
+};
 use crate::theme::Base16Color;
+use crate::touch::long_press;
+use crate::{audit, compositor, config, diagnostics, fixture, metrics, notify};
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use iced::daemon::Appearance;
-use iced::platform_specific::shell::commands::layer_surface::destroy_layer_surface;
-use iced::widget::{Column, Row, Stack, button, column, container, row, slider, text};
+use iced::event::wayland::{self, OutputEvent};
+use iced::event::{self, PlatformSpecific};
+use iced::runtime::platform_specific::wayland::layer_surface::IcedOutput;
+use iced::widget::{
+    Column, Row, Stack, button, column, container, image, lazy, mouse_area, row, scrollable,
+    slider, svg, text, text_input, toggler, tooltip, vertical_slider,
+};
 use iced::window::Id;
-use iced::{Background, Color, Element, Font, Subscription, Task, Theme, border};
+use iced::{
+    Alignment, Background, Color, Element, Event, Font, Length, Subscription, Task, Theme, border,
+    clipboard, font, mouse,
+};
+use wayland_client::protocol::wl_output::WlOutput;
+
+/// the width and height of tray icons, in logical pixels
+const TRAY_ICON_SIZE: f32 = 16.0;
+/// the size of a module's text when it didn't pick one, in logical pixels
+const MODULE_TEXT_SIZE: f32 = 11.0;
+/// how many parts a progress bar's length is split into
+const PROGRESS_PORTIONS: u16 = 1000;
+/// touchpads scroll in pixels, modules are told how far in lines
+const SCROLL_PIXELS_PER_LINE: f32 = 20.0;
 
 #[derive(Debug)]
 pub struct App {
     font: Font,
     base_16_theme: Base16Color,
+    /// the user's light or dark preference, picks which colors file the
+    /// theme is read from
+    color_scheme: ColorScheme,
 
     service: AppServices,
     runtime: AppRuntimes,
+    /// the bar and widgets drawn by the shell itself
+    builtin: Builtins,
+
+    /// a copy of the appearance service's state
+    appearance_state: AppearanceState,
+    /// a copy of the audio service's state, used to answer ipc queries
+    audio_state: AudioState,
+    /// a copy of the brightness service's state
+    brightness_state: BrightnessState,
+    /// a copy of the custom service's state, used to answer ipc queries
+    custom_state: CustomState,
+    /// a copy of the hotkeys service's state
+    hotkeys_state: HotkeysState,
+    /// a copy of the inhibit service's state
+    inhibit_state: InhibitState,
+    /// a copy of the kde connect service's state, serialized for modules
+    kdeconnect_state: KdeConnectState,
+    /// a copy of the screenshot service's state
+    screenshot_state: ScreenshotState,
+    /// a copy of the session service's state
+    session_state: SessionState,
+    /// a copy of the sysinfo service's state
+    sysinfo_state: SysinfoState,
+    /// a copy of the tray service's state, its items are drawn in the
+    /// modules' tray slots
+    tray_state: TrayState,
 }
 
 /// stores the channels required to communicate with services
@@ -25,7 +110,24 @@ pub struct App {
 /// enabled/loaded are `Some(ServiceRequest<Service>)`
 #[derive(Debug, Default)]
 struct AppServices {
-    audio: Option<flume::Sender<ServiceRequest<AudioService>>>,
+    appearance: Option<InstrumentedSender<flume::Sender<ServiceRequest<AppearanceService>>>>,
+    audio: Option<InstrumentedSender<flume::Sender<ServiceRequest<AudioService>>>>,
+    brightness: Option<InstrumentedSender<flume::Sender<ServiceRequest<BrightnessService>>>>,
+    clock: Option<InstrumentedSender<flume::Sender<ServiceRequest<ClockService>>>>,
+    custom: Option<InstrumentedSender<flume::Sender<ServiceRequest<CustomService>>>>,
+    dbus: Option<InstrumentedSender<flume::Sender<ServiceRequest<DbusService>>>>,
+    hotkeys: Option<InstrumentedSender<flume::Sender<ServiceRequest<HotkeysService>>>>,
+    inhibit: Option<InstrumentedSender<flume::Sender<ServiceRequest<InhibitService>>>>,
+    interval: Option<InstrumentedSender<flume::Sender<ServiceRequest<IntervalService>>>>,
+    ipc: Option<InstrumentedSender<flume::Sender<ServiceRequest<IpcService>>>>,
+    kdeconnect: Option<InstrumentedSender<flume::Sender<ServiceRequest<KdeConnectService>>>>,
+    network: Option<InstrumentedSender<flume::Sender<ServiceRequest<NetworkService>>>>,
+    screenshot: Option<InstrumentedSender<flume::Sender<ServiceRequest<ScreenshotService>>>>,
+    session: Option<InstrumentedSender<flume::Sender<ServiceRequest<SessionService>>>>,
+    sysinfo: Option<InstrumentedSender<flume::Sender<ServiceRequest<SysinfoService>>>>,
+    tray: Option<InstrumentedSender<flume::Sender<ServiceRequest<TrayService>>>>,
+    watch: Option<InstrumentedSender<flume::Sender<ServiceRequest<WatchService>>>>,
+    weather: Option<InstrumentedSender<flume::Sender<ServiceRequest<WeatherService>>>>,
 }
 
 /// stores all the state for the runtimes that the app needs to know about
@@ -44,11 +146,45 @@ pub enum AppMessage {
 
     /// requests that need to be relayed to a service or runtime
     Request(SubscriptionRequest),
+
+    /// messages for the bar and widgets drawn by the shell
+    Builtin(builtin::Message),
+
+    /// the compositor announced or changed a monitor, `output` is `None`
+    /// when it was unplugged
+    Output {
+        wl_output: WlOutput,
+        output: Option<Output>,
+    },
+
+    /// a region of the screen was picked for a module, `None` when it was
+    /// cancelled
+    RegionSelected {
+        module_id: u32,
+        region: Option<Region>,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub enum ServiceMessage {
+    Appearance(ServiceEvent<AppearanceService>),
     Audio(ServiceEvent<AudioService>),
+    Brightness(ServiceEvent<BrightnessService>),
+    Clock(ServiceEvent<ClockService>),
+    Custom(ServiceEvent<CustomService>),
+    Dbus(ServiceEvent<DbusService>),
+    Hotkeys(ServiceEvent<HotkeysService>),
+    Inhibit(ServiceEvent<InhibitService>),
+    Interval(ServiceEvent<IntervalService>),
+    Ipc(ServiceEvent<IpcService>),
+    KdeConnect(ServiceEvent<KdeConnectService>),
+    Network(ServiceEvent<NetworkService>),
+    Screenshot(ServiceEvent<ScreenshotService>),
+    Session(ServiceEvent<SessionService>),
+    Sysinfo(ServiceEvent<SysinfoService>),
+    Tray(ServiceEvent<TrayService>),
+    Watch(ServiceEvent<WatchService>),
+    Weather(ServiceEvent<WeatherService>),
 }
 
 #[derive(Debug, Clone)]
@@ -59,23 +195,102 @@ pub enum RuntimeMessage {
 #[derive(Debug, Clone)]
 pub enum SubscriptionRequest {
     Wasm(wasm::Request),
+    Audio(audio::Request),
+    Brightness(brightness::Request),
+    Inhibit(inhibit::Request),
+    KdeConnect(kdeconnect::Request),
+    Network(network::Request),
+    Screenshot(screenshot::Request),
+    Session(session::Request),
+}
+
+impl AppMessage {
+    /// what sent the message and what kind of message it is, like
+    /// `("service:audio", "update")`
+    fn audit_source(&self) -> (&'static str, &'static str) {
+        fn service_kind<S: Service>(event: &ServiceEvent<S>) -> &'static str {
+            match event {
+                ServiceEvent::Init { .. } => "init",
+                ServiceEvent::Update { .. } => "update",
+            }
+        }
+
+        match self {
+            AppMessage::Service(message) => match message {
+                ServiceMessage::Appearance(event) => ("service:appearance", service_kind(event)),
+                ServiceMessage::Audio(event) => ("service:audio", service_kind(event)),
+                ServiceMessage::Brightness(event) => ("service:brightness", service_kind(event)),
+                ServiceMessage::Clock(event) => ("service:clock", service_kind(event)),
+                ServiceMessage::Custom(event) => ("service:custom", service_kind(event)),
+                ServiceMessage::Dbus(event) => ("service:dbus", service_kind(event)),
+                ServiceMessage::Hotkeys(event) => ("service:hotkeys", service_kind(event)),
+                ServiceMessage::Inhibit(event) => ("service:inhibit", service_kind(event)),
+                ServiceMessage::Interval(event) => ("service:interval", service_kind(event)),
+                ServiceMessage::Ipc(event) => ("service:ipc", service_kind(event)),
+                ServiceMessage::KdeConnect(event) => ("service:kdeconnect", service_kind(event)),
+                ServiceMessage::Network(event) => ("service:network", service_kind(event)),
+                ServiceMessage::Screenshot(event) => ("service:screenshot", service_kind(event)),
+                ServiceMessage::Session(event) => ("service:session", service_kind(event)),
+                ServiceMessage::Sysinfo(event) => ("service:sysinfo", service_kind(event)),
+                ServiceMessage::Tray(event) => ("service:tray", service_kind(event)),
+                ServiceMessage::Watch(event) => ("service:watch", service_kind(event)),
+                ServiceMessage::Weather(event) => ("service:weather", service_kind(event)),
+            },
+            AppMessage::Runtime(RuntimeMessage::Wasm(event)) => match event {
+                RuntimeEvent::Init(_) => ("runtime:wasm", "init"),
+                RuntimeEvent::Update(_) => ("runtime:wasm", "update"),
+                RuntimeEvent::RequestFailed { .. } => ("runtime:wasm", "request_failed"),
+            },
+            AppMessage::Request(request) => match request {
+                SubscriptionRequest::Wasm(_) => ("request:wasm", "request"),
+                SubscriptionRequest::Audio(_) => ("request:audio", "request"),
+                SubscriptionRequest::Brightness(_) => ("request:brightness", "request"),
+                SubscriptionRequest::Inhibit(_) => ("request:inhibit", "request"),
+                SubscriptionRequest::KdeConnect(_) => ("request:kdeconnect", "request"),
+                SubscriptionRequest::Network(_) => ("request:network", "request"),
+                SubscriptionRequest::Screenshot(_) => ("request:screenshot", "request"),
+                SubscriptionRequest::Session(_) => ("request:session", "request"),
+            },
+            AppMessage::Builtin(_) => ("builtin", "message"),
+            AppMessage::Output { .. } => ("wayland:output", "update"),
+            AppMessage::RegionSelected { .. } => ("builtin:region", "selected"),
+        }
+    }
 }
 
 impl App {
     pub fn new() -> (App, Task<AppMessage>) {
-        let theme = match Base16Color::from_config() {
+        let theme = match Base16Color::from_config(ColorScheme::NoPreference) {
             Ok(theme) => theme,
             Err(_) => Base16Color::default(),
         };
 
+        let (builtin, builtin_task) = Builtins::new(config::get());
+
+        // the config, theme and widgets have all been read by now
+        diagnostics::report();
+
         (
             Self {
                 font: Font::with_name("DepartureMono Nerd Font"),
                 base_16_theme: theme,
+                color_scheme: ColorScheme::NoPreference,
                 service: Default::default(),
                 runtime: Default::default(),
+                builtin,
+                appearance_state: AppearanceState::init(),
+                audio_state: AudioState::init(),
+                brightness_state: BrightnessState::init(),
+                custom_state: CustomState::init(),
+                hotkeys_state: HotkeysState::init(),
+                inhibit_state: InhibitState::init(),
+                kdeconnect_state: KdeConnectState::init(),
+                screenshot_state: ScreenshotState::init(),
+                session_state: SessionState::init(),
+                sysinfo_state: SysinfoState::init(),
+                tray_state: TrayState::init(),
             },
-            Task::none(),
+            builtin_task,
         )
     }
 
@@ -86,15 +301,81 @@ impl App {
     pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
         let mut command = Task::none();
 
+        let (source, kind) = message.audit_source();
+        audit::record(source, kind, &message);
+
         match message {
             AppMessage::Service(event) => match event {
                 ServiceMessage::Audio(event) => match event {
                     ServiceEvent::Init { request_tx } => {
                         self.service.audio = Some(request_tx);
+                        self.set_service_available::<AudioService>(true);
                         log::debug!("[app] audio service initalized");
                     }
+                    // only modules with an `AudioMeter` register want these,
+                    // they come too often to redraw the widgets for
+                    ServiceEvent::Update {
+                        event: event @ audio::Event::LevelsChanged { .. },
+                    } => {
+                        metrics::increment("service.audio.levels");
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::ServiceData {
+                                    data: Box::new(event),
+                                },
+                            )
+                        {
+                            log::error!(
+                                "[app] could not send the audio levels to the wasm runtime: {err}"
+                            );
+                        }
+                    }
                     ServiceEvent::Update { event } => {
-                        if let Some(audio) = &self.service.audio {
+                        metrics::increment("service.audio.events");
+                        match event {
+                            audio::Event::ServiceUnavailable { .. } => {
+                                self.set_service_available::<AudioService>(false);
+                            }
+                            audio::Event::ServiceAvailable => {
+                                self.set_service_available::<AudioService>(true);
+                            }
+                            _ => {}
+                        }
+                        // nothing comes back when a snapshot matched the
+                        // events before it
+                        let changed = !self.audio_state.update(event.clone()).is_empty();
+                        command = Task::batch([
+                            self.builtin.update(builtin::Message::Audio(
+                                builtin::audio::Message::Service(event.clone()),
+                            )),
+                            self.builtin.update(builtin::Message::QuickSettings(
+                                builtin::quick_settings::Message::Audio(event.clone()),
+                            )),
+                        ]);
+
+                        if self.service.audio.is_some() {
+                            // modules get a summary, the full lists are only
+                            // serialized for the devices they ask about
+                            if changed
+                                && let Some(wasm) = &mut self.runtime.wasm
+                                && let Err(err) = WasmRuntime::request(
+                                    wasm,
+                                    RuntimeRequest::new(wasm::Request::AudioChanged {
+                                        devices: Arc::new(self.audio_state.devices()),
+                                        subscription: event.subscription(),
+                                    }),
+                                )
+                            {
+                                log::error!(
+                                    "[app] could not send the audio devices to the wasm runtime: \
+                                     {err}"
+                                );
+                            }
+
+                            // sent after the devices so modules handling the
+                            // event read the devices it's about
                             if let Some(wasm) = &mut self.runtime.wasm
                                 && let Err(err) = WasmRuntime::request(
                                     wasm,
@@ -115,6 +396,502 @@ impl App {
                         }
                     }
                 },
+                ServiceMessage::Appearance(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.appearance = Some(request_tx);
+                        self.set_service_available::<AppearanceService>(true);
+                        log::debug!("[app] appearance service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.appearance.events");
+                        log::trace!("[app] appearance update: {event:?}");
+                        self.appearance_state.update(event.clone());
+
+                        match event {
+                            appearance::Event::SchemeChanged { .. } => {
+                                if self.appearance_state.scheme != self.color_scheme {
+                                    self.color_scheme = self.appearance_state.scheme;
+                                    self.load_theme();
+                                }
+                            }
+                            appearance::Event::ServiceUnavailable { .. } => {
+                                self.set_service_available::<AppearanceService>(false);
+                            }
+                            appearance::Event::ServiceAvailable => {
+                                self.set_service_available::<AppearanceService>(true);
+                            }
+                        }
+                    }
+                },
+                ServiceMessage::Brightness(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.brightness = Some(request_tx);
+                        self.set_service_available::<BrightnessService>(true);
+                        log::debug!("[app] brightness service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.brightness.events");
+                        match event {
+                            brightness::Event::ServiceUnavailable { .. } => {
+                                self.set_service_available::<BrightnessService>(false);
+                            }
+                            brightness::Event::ServiceAvailable => {
+                                self.set_service_available::<BrightnessService>(true);
+                            }
+                            _ => {}
+                        }
+                        self.brightness_state.update(event.clone());
+                        command = self.builtin.update(builtin::Message::QuickSettings(
+                            builtin::quick_settings::Message::Brightness(event.clone()),
+                        ));
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::ServiceData {
+                                    data: Box::new(event.clone()),
+                                },
+                            )
+                        {
+                            log::error!(
+                                "[app] could not send ServiceData request to brightness service: \
+                                 {err}"
+                            );
+                        }
+
+                        log::trace!("[app] brightness update: {event:?}");
+                    }
+                },
+                ServiceMessage::Inhibit(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.inhibit = Some(request_tx);
+                        self.set_service_available::<InhibitService>(true);
+                        log::debug!("[app] inhibit service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.inhibit.events");
+                        match event {
+                            inhibit::Event::ServiceUnavailable { .. } => {
+                                self.set_service_available::<InhibitService>(false);
+                            }
+                            inhibit::Event::ServiceAvailable => {
+                                self.set_service_available::<InhibitService>(true);
+                            }
+                            _ => {}
+                        }
+                        self.inhibit_state.update(event.clone());
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::ServiceData {
+                                    data: Box::new(event.clone()),
+                                },
+                            )
+                        {
+                            log::error!(
+                                "[app] could not send ServiceData request to inhibit service: \
+                                 {err}"
+                            );
+                        }
+
+                        log::trace!("[app] inhibit update: {event:?}");
+                    }
+                },
+                ServiceMessage::Clock(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.clock = Some(request_tx);
+                        self.set_service_available::<ClockService>(true);
+                        log::debug!("[app] clock service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.clock.events");
+                        log::trace!("[app] clock update: {event:?}");
+
+                        let clock::Event::TimeChanged { module, time } = event;
+                        let RuntimeModuleId::Wasm(module_id) = module;
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::new(wasm::Request::ClockChanged {
+                                    module_id,
+                                    time,
+                                }),
+                            )
+                        {
+                            log::error!("[app] could not send the time to the wasm runtime: {err}");
+                        }
+                    }
+                },
+                ServiceMessage::Interval(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.interval = Some(request_tx);
+                        self.set_service_available::<IntervalService>(true);
+                        log::debug!("[app] interval service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.interval.events");
+                        log::trace!("[app] interval update: {event:?}");
+
+                        let interval::Event::Elapsed { module } = event;
+                        let RuntimeModuleId::Wasm(module_id) = module;
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::new(wasm::Request::IntervalElapsed { module_id }),
+                            )
+                        {
+                            log::error!(
+                                "[app] could not tell the wasm runtime an interval elapsed: {err}"
+                            );
+                        }
+                    }
+                },
+                ServiceMessage::Custom(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.custom = Some(request_tx);
+                        self.set_service_available::<CustomService>(true);
+                        log::debug!("[app] custom service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.custom.events");
+                        self.custom_state.update(event.clone());
+
+                        if self.service.custom.is_some() {
+                            if let Some(wasm) = &mut self.runtime.wasm
+                                && let Err(err) = WasmRuntime::request(
+                                    wasm,
+                                    RuntimeRequest::ServiceData {
+                                        data: Box::new(event.clone()),
+                                    },
+                                )
+                            {
+                                log::error!(
+                                    "[app] could not send ServiceData request to custom service: \
+                                     {err}"
+                                );
+                            }
+
+                            log::trace!("[app] custom update: {event:?}");
+                        } else {
+                            log::error!("[app] custom service not initalized");
+                        }
+                    }
+                },
+                ServiceMessage::Dbus(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.dbus = Some(request_tx);
+                        self.set_service_available::<DbusService>(true);
+                        log::debug!("[app] dbus service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.dbus.events");
+                        log::trace!("[app] dbus update: {event:?}");
+
+                        let dbus::Event::Signal { module, signal } = event;
+                        let RuntimeModuleId::Wasm(module_id) = module;
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::new(wasm::Request::DbusSignal {
+                                    module_id,
+                                    signal,
+                                }),
+                            )
+                        {
+                            log::error!(
+                                "[app] could not give the wasm runtime a dbus signal: {err}"
+                            );
+                        }
+                    }
+                },
+                ServiceMessage::Hotkeys(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.hotkeys = Some(request_tx);
+                        self.set_service_available::<HotkeysService>(true);
+                        log::debug!("[app] hotkeys service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.hotkeys.events");
+                        log::trace!("[app] hotkeys update: {event:?}");
+                        self.hotkeys_state.update(event.clone());
+
+                        match event {
+                            hotkeys::Event::ServiceUnavailable { .. } => {
+                                self.set_service_available::<HotkeysService>(false);
+                            }
+                            hotkeys::Event::ServiceAvailable => {
+                                self.set_service_available::<HotkeysService>(true);
+                            }
+                            hotkeys::Event::KeyPressed { module, index } => {
+                                let RuntimeModuleId::Wasm(module_id) = module;
+
+                                if let Some(wasm) = &mut self.runtime.wasm
+                                    && let Err(err) = WasmRuntime::request(
+                                        wasm,
+                                        RuntimeRequest::new(wasm::Request::HotkeyPressed {
+                                            module_id,
+                                            index,
+                                        }),
+                                    )
+                                {
+                                    log::error!(
+                                        "[app] could not send a hotkey to the wasm runtime: {err}"
+                                    );
+                                }
+                            }
+                        }
+                    }
+                },
+                ServiceMessage::Ipc(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.ipc = Some(request_tx);
+                        self.set_service_available::<IpcService>(true);
+                        log::debug!("[app] ipc service initalized");
+                    }
+                    ServiceEvent::Update { event } => match event {
+                        ipc::Event::Query { query, reply_tx } => {
+                            let (response, task) = self.answer_query(query);
+                            command = task;
+
+                            if let Err(err) = reply_tx.send(response) {
+                                log::error!("[app] could not reply to ipc query: {err}");
+                            }
+                        }
+                    },
+                },
+                ServiceMessage::KdeConnect(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.kdeconnect = Some(request_tx);
+                        self.set_service_available::<KdeConnectService>(true);
+                        log::debug!("[app] kdeconnect service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.kdeconnect.events");
+                        match event {
+                            kdeconnect::Event::ServiceUnavailable { .. } => {
+                                self.set_service_available::<KdeConnectService>(false);
+                            }
+                            kdeconnect::Event::ServiceAvailable => {
+                                self.set_service_available::<KdeConnectService>(true);
+                            }
+                            _ => {}
+                        }
+                        self.kdeconnect_state.update(event.clone());
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::new(wasm::Request::KdeConnectChanged {
+                                    state: Arc::new(self.kdeconnect_state.serialize()),
+                                    subscription: event.subscription(),
+                                }),
+                            )
+                        {
+                            log::error!(
+                                "[app] could not send the kde connect devices to the wasm \
+                                 runtime: {err}"
+                            );
+                        }
+
+                        log::trace!("[app] kdeconnect update: {event:?}");
+                    }
+                },
+                ServiceMessage::Network(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.network = Some(request_tx);
+                        self.set_service_available::<NetworkService>(true);
+                        log::debug!("[app] network service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.network.events");
+                        match event {
+                            network::Event::ServiceUnavailable { .. } => {
+                                self.set_service_available::<NetworkService>(false);
+                            }
+                            network::Event::ServiceAvailable => {
+                                self.set_service_available::<NetworkService>(true);
+                            }
+                            _ => {}
+                        }
+                        command = self.builtin.update(builtin::Message::QuickSettings(
+                            builtin::quick_settings::Message::Network(event.clone()),
+                        ));
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::ServiceData {
+                                    data: Box::new(event.clone()),
+                                },
+                            )
+                        {
+                            log::error!(
+                                "[app] could not send ServiceData request to network service: \
+                                 {err}"
+                            );
+                        }
+
+                        log::trace!("[app] network update: {event:?}");
+                    }
+                },
+                ServiceMessage::Screenshot(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.screenshot = Some(request_tx);
+                        self.set_service_available::<ScreenshotService>(true);
+                        log::debug!("[app] screenshot service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.screenshot.events");
+                        log::trace!("[app] screenshot update: {event:?}");
+                        self.screenshot_state.update(event.clone());
+
+                        match event {
+                            screenshot::Event::Captured {
+                                module: RuntimeModuleId::Wasm(module_id),
+                                path,
+                            } => self.screenshot_taken(module_id, Ok(path)),
+                            screenshot::Event::CaptureFailed {
+                                module: RuntimeModuleId::Wasm(module_id),
+                                reason,
+                            } => self.screenshot_taken(module_id, Err(reason)),
+                            screenshot::Event::ServiceUnavailable { .. } => {
+                                self.set_service_available::<ScreenshotService>(false);
+                            }
+                            screenshot::Event::ServiceAvailable => {
+                                self.set_service_available::<ScreenshotService>(true);
+                            }
+                        }
+                    }
+                },
+                ServiceMessage::Session(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.session = Some(request_tx);
+                        self.set_service_available::<SessionService>(true);
+                        log::debug!("[app] session service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.session.events");
+                        match event {
+                            session::Event::ServiceUnavailable { .. } => {
+                                self.set_service_available::<SessionService>(false);
+                            }
+                            session::Event::ServiceAvailable => {
+                                self.set_service_available::<SessionService>(true);
+                            }
+                            _ => {}
+                        }
+                        self.session_state.update(event.clone());
+
+                        log::trace!("[app] session update: {event:?}");
+                    }
+                },
+                ServiceMessage::Sysinfo(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.sysinfo = Some(request_tx);
+                        self.set_service_available::<SysinfoService>(true);
+                        log::debug!("[app] sysinfo service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.sysinfo.events");
+                        self.sysinfo_state.update(event.clone());
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::ServiceData {
+                                    data: Box::new(event.clone()),
+                                },
+                            )
+                        {
+                            log::error!(
+                                "[app] could not send ServiceData request to sysinfo service: \
+                                 {err}"
+                            );
+                        }
+
+                        log::trace!("[app] sysinfo update: {event:?}");
+                    }
+                },
+                ServiceMessage::Tray(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.tray = Some(request_tx);
+                        self.set_service_available::<TrayService>(true);
+                        log::debug!("[app] tray service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.tray.events");
+                        match event {
+                            tray::Event::ServiceUnavailable { .. } => {
+                                self.set_service_available::<TrayService>(false);
+                            }
+                            tray::Event::ServiceAvailable => {
+                                self.set_service_available::<TrayService>(true);
+                            }
+                            _ => {}
+                        }
+
+                        log::trace!("[app] tray update: {event:?}");
+                        self.tray_state.update(event);
+                    }
+                },
+                ServiceMessage::Watch(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.watch = Some(request_tx);
+                        self.set_service_available::<WatchService>(true);
+                        log::debug!("[app] watch service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.watch.events");
+                        log::trace!("[app] watch update: {event:?}");
+
+                        let watch::Event::FileChanged { module, path } = event;
+                        let RuntimeModuleId::Wasm(module_id) = module;
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::new(wasm::Request::FileChanged { module_id, path }),
+                            )
+                        {
+                            log::error!(
+                                "[app] could not tell the wasm runtime a file changed: {err}"
+                            );
+                        }
+                    }
+                },
+                ServiceMessage::Weather(event) => match event {
+                    ServiceEvent::Init { request_tx } => {
+                        self.service.weather = Some(request_tx);
+                        self.set_service_available::<WeatherService>(true);
+                        log::debug!("[app] weather service initalized");
+                    }
+                    ServiceEvent::Update { event } => {
+                        metrics::increment("service.weather.events");
+                        log::trace!("[app] weather update: {event:?}");
+
+                        if let Some(wasm) = &mut self.runtime.wasm
+                            && let Err(err) = WasmRuntime::request(
+                                wasm,
+                                RuntimeRequest::ServiceData {
+                                    data: Box::new(event.clone()),
+                                },
+                            )
+                        {
+                            log::error!(
+                                "[app] could not send ServiceData request to weather service: \
+                                 {err}"
+                            );
+                        }
+
+                        command = self.builtin.update(builtin::Message::Weather(
+                            builtin::weather::Message::Service(event),
+                        ));
+                    }
+                },
             },
             AppMessage::Runtime(event) => match event {
                 RuntimeMessage::Wasm(event) => match event {
@@ -124,20 +901,102 @@ impl App {
 
                             // destroy all layer surfaces related to modules
                             for layer_id in wasm.surface_module_ids.keys() {
-                                tasks.push(destroy_layer_surface(*layer_id));
+                                tasks.push(compositor::destroy_layer_surface(*layer_id));
                             }
 
                             command = Task::batch(tasks);
                         }
 
                         self.runtime.wasm = Some(init);
+                        self.send_theme();
 
                         log::debug!("wasm service initalized");
                     }
                     RuntimeEvent::Update(event) => {
+                        metrics::increment("runtime.wasm.events");
+
                         if let Some(wasm) = &mut self.runtime.wasm {
                             command = wasm.update(event.clone());
 
+                            // the services forget the module, if it's being
+                            // reloaded it registers again once it's loaded
+                            if let wasm::Event::ModuleUnloaded { module_id } = &event {
+                                self.unsubscribe_module(RuntimeModuleId::Wasm(*module_id));
+                            }
+
+                            if let wasm::Event::KdeConnectRequest { module_id, request } = &event {
+                                log::debug!(
+                                    "[app] module {module_id} asked kde connect for {request:?}"
+                                );
+                                command = Task::batch([
+                                    command,
+                                    Task::done(AppMessage::Request(
+                                        SubscriptionRequest::KdeConnect(request.clone()),
+                                    )),
+                                ]);
+                            }
+
+                            if let wasm::Event::InhibitRequest { module_id, request } = &event {
+                                log::debug!(
+                                    "[app] module {module_id} asked the inhibit service for \
+                                     {request:?}"
+                                );
+                                command = Task::batch([
+                                    command,
+                                    Task::done(AppMessage::Request(SubscriptionRequest::Inhibit(
+                                        request.clone(),
+                                    ))),
+                                ]);
+                            }
+
+                            if let wasm::Event::SessionRequest { module_id, request } = &event {
+                                log::info!("[app] module {module_id} asked to {request}");
+                                command = Task::batch([
+                                    command,
+                                    Task::done(AppMessage::Request(SubscriptionRequest::Session(
+                                        *request,
+                                    ))),
+                                ]);
+                            }
+
+                            if let wasm::Event::AudioRequest { module_id, request } = &event {
+                                log::debug!(
+                                    "[app] module {module_id} asked the audio service for \
+                                     {request:?}"
+                                );
+                                command = Task::batch([
+                                    command,
+                                    Task::done(AppMessage::Request(SubscriptionRequest::Audio(
+                                        request.clone(),
+                                    ))),
+                                ]);
+                            }
+
+                            if let wasm::Event::SetClipboard { module_id, text } = &event {
+                                log::debug!(
+                                    "[app] module {module_id} put {} bytes on the clipboard",
+                                    text.len()
+                                );
+                                command = Task::batch([command, clipboard::write(text.clone())]);
+                            }
+
+                            if let wasm::Event::SelectRegion { module_id, output } = &event {
+                                command =
+                                    Task::batch([command, self.select_region(*module_id, *output)]);
+                            }
+
+                            if let wasm::Event::CaptureScreenshot {
+                                module_id,
+                                output,
+                                region,
+                            } = &event
+                            {
+                                command = Task::batch([
+                                    command,
+                                    self.capture_screenshot(*module_id, *output, *region),
+                                ]);
+                            }
+
                             // note: maybe have this event separate from
                             // regular events
                             // so not part of `RuntimeEvent::Update`
@@ -150,58 +1009,419 @@ impl App {
                                     SubscriptionData::Interval {
                                         milliseconds,
                                         offset,
-                                    } => {}
-                                    SubscriptionData::PulseAudio { data } => {
-                                        if let Some(audio) = &self.service.audio {
+                                    } => {
+                                        if let Some(interval) = &self.service.interval {
                                             if let Err(err) =
-                                                audio.send(ServiceRequest::SubscribeModule {
+                                                interval.send(ServiceRequest::SubscribeModule {
                                                     id: RuntimeModuleId::Wasm(module_id),
-                                                    data: data,
+                                                    data: IntervalSubscriptionData {
+                                                        milliseconds,
+                                                        offset,
+                                                    },
                                                 })
                                             {
                                                 log::error!(
                                                     "[app] failed to send SubscriptionData to \
-                                                     audio service: {err}"
+                                                     interval service: {err}"
                                                 );
                                             }
                                         }
                                     }
-                                }
-                            }
-                        } else {
-                            eprintln!("[app] [wasm:update] wasm runtime not initalized");
-                        }
-                    }
-                },
-            },
-            AppMessage::Request(request) => match request {
-                SubscriptionRequest::Wasm(request) => {
-                    if let Some(wasm) = &mut self.runtime.wasm {
-                        match WasmRuntime::request(wasm, RuntimeRequest::Request { request }) {
-                            Ok(_) => (),
-                            Err(err) => {
-                                eprintln!(
-                                    "[app] [wasm] could not send request to the wasm runtime: {}",
-                                    err
-                                );
-                            }
-                        };
-                    } else {
-                        eprintln!("[app] [wasm:request] wasm runtime not initalized");
-                    }
-                }
-            },
+                                    SubscriptionData::Brightness { data } => {
+                                        if let Some(brightness) = &self.service.brightness {
+                                            if let Err(err) =
+                                                brightness.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     brightness service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::Clock { data } => {
+                                        if let Some(clock) = &self.service.clock {
+                                            if let Err(err) =
+                                                clock.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     clock service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::Hotkeys { data } => {
+                                        if let Some(hotkeys) = &self.service.hotkeys {
+                                            if let Err(err) =
+                                                hotkeys.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     hotkeys service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::Inhibit { data } => {
+                                        if let Some(inhibit) = &self.service.inhibit {
+                                            if let Err(err) =
+                                                inhibit.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     inhibit service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::KdeConnect { data } => {
+                                        if let Some(kdeconnect) = &self.service.kdeconnect {
+                                            if let Err(err) =
+                                                kdeconnect.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     kdeconnect service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::Sysinfo { data } => {
+                                        if let Some(sysinfo) = &self.service.sysinfo {
+                                            if let Err(err) =
+                                                sysinfo.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     sysinfo service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::Watch { data } => {
+                                        if let Some(watch) = &self.service.watch {
+                                            if let Err(err) =
+                                                watch.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     watch service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::Weather => {
+                                        if let Some(weather) = &self.service.weather {
+                                            if let Err(err) =
+                                                weather.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data: (),
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     weather service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    // handled by the runtime
+                                    SubscriptionData::Outputs | SubscriptionData::Theme => {}
+                                    SubscriptionData::PulseAudio { data } => {
+                                        if let Some(audio) = &self.service.audio {
+                                            if let Err(err) =
+                                                audio.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data: data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     audio service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::AudioMeter { rate } => {
+                                        if let Some(audio) = &self.service.audio {
+                                            if let Err(err) = audio.send(ServiceRequest::Request {
+                                                request: audio::Request::WatchLevels {
+                                                    module: RuntimeModuleId::Wasm(module_id),
+                                                    rate,
+                                                },
+                                            }) {
+                                                log::error!(
+                                                    "[app] failed to send the level meter to the \
+                                                     audio service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::Custom { data } => {
+                                        if let Some(custom) = &self.service.custom {
+                                            if let Err(err) =
+                                                custom.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     custom service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::Dbus { data } => {
+                                        if let Some(dbus) = &self.service.dbus {
+                                            if let Err(err) =
+                                                dbus.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     dbus service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                    SubscriptionData::Network { data } => {
+                                        if let Some(network) = &self.service.network {
+                                            if let Err(err) =
+                                                network.send(ServiceRequest::SubscribeModule {
+                                                    id: RuntimeModuleId::Wasm(module_id),
+                                                    data,
+                                                })
+                                            {
+                                                log::error!(
+                                                    "[app] failed to send SubscriptionData to \
+                                                     network service: {err}"
+                                                );
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        } else {
+                            eprintln!("[app] [wasm:update] wasm runtime not initalized");
+                        }
+                    }
+                    RuntimeEvent::RequestFailed { id, error } => {
+                        log::warn!("[app] wasm request {id} failed: {error}");
+
+                        // only failures inside a module are worth telling the
+                        // user about, the rest happen when a module reloads
+                        if let RequestError::Module {
+                            module: RuntimeModuleId::Wasm(module_id),
+                            message,
+                        } = &error
+                        {
+                            let name = self
+                                .runtime
+                                .wasm
+                                .as_ref()
+                                .and_then(|wasm| wasm.modules.get(module_id))
+                                .map_or("a module", |module| module.module_name.as_str());
+                            notify::send(&format!("couldn't run {name}'s action"), message);
+                        }
+                    }
+                },
+            },
+            AppMessage::Request(request) => match request {
+                SubscriptionRequest::Wasm(request) => {
+                    // a click on a tray icon goes to the app owning the item,
+                    // the module only hears about it if it has a callback
+                    if let wasm::Request::CallbackEvent {
+                        data: Some(WasmCallbackData::Tray { item, click }),
+                        ..
+                    } = &request
+                    {
+                        self.click_tray_item(item.clone(), *click);
+                    }
+
+                    if let wasm::Request::CallbackEvent { callback_id: 0, .. } = request {
+                        // nothing for the module to do
+                    } else if let Some(wasm) = &mut self.runtime.wasm {
+                        match WasmRuntime::request(wasm, RuntimeRequest::new(request)) {
+                            Ok(_) => (),
+                            Err(err) => {
+                                eprintln!(
+                                    "[app] [wasm] could not send request to the wasm runtime: {}",
+                                    err
+                                );
+                            }
+                        };
+                    } else {
+                        eprintln!("[app] [wasm:request] wasm runtime not initalized");
+                    }
+                }
+                SubscriptionRequest::Audio(request) => {
+                    if let Some(audio) = &self.service.audio {
+                        if let Err(err) = audio.send(ServiceRequest::Request { request }) {
+                            log::error!("[app] could not send request to the audio service: {err}");
+                        }
+                    } else {
+                        log::error!("[app] audio service not initalized");
+                    }
+                }
+                SubscriptionRequest::Brightness(request) => {
+                    if let Some(brightness) = &self.service.brightness {
+                        if let Err(err) = brightness.send(ServiceRequest::Request { request }) {
+                            log::error!(
+                                "[app] could not send request to the brightness service: {err}"
+                            );
+                        }
+                    } else {
+                        log::error!("[app] brightness service not initalized");
+                    }
+                }
+                SubscriptionRequest::Inhibit(request) => {
+                    if let Some(inhibit) = &self.service.inhibit {
+                        if let Err(err) = inhibit.send(ServiceRequest::Request { request }) {
+                            log::error!(
+                                "[app] could not send request to the inhibit service: {err}"
+                            );
+                        }
+                    } else {
+                        log::error!("[app] inhibit service not initalized");
+                    }
+                }
+                SubscriptionRequest::KdeConnect(request) => {
+                    if let Some(kdeconnect) = &self.service.kdeconnect {
+                        if let Err(err) = kdeconnect.send(ServiceRequest::Request { request }) {
+                            log::error!(
+                                "[app] could not send request to the kdeconnect service: {err}"
+                            );
+                        }
+                    } else {
+                        log::error!("[app] kdeconnect service not initalized");
+                    }
+                }
+                SubscriptionRequest::Network(request) => {
+                    if let Some(network) = &self.service.network {
+                        if let Err(err) = network.send(ServiceRequest::Request { request }) {
+                            log::error!(
+                                "[app] could not send request to the network service: {err}"
+                            );
+                        }
+                    } else {
+                        log::error!("[app] network service not initalized");
+                    }
+                }
+                SubscriptionRequest::Session(request) => {
+                    if let Some(session) = &self.service.session {
+                        if let Err(err) = session.send(ServiceRequest::Request { request }) {
+                            log::error!(
+                                "[app] could not send request to the session service: {err}"
+                            );
+                        }
+                    } else {
+                        log::error!("[app] session service not initalized");
+                    }
+                }
+                SubscriptionRequest::Screenshot(request) => {
+                    let RuntimeModuleId::Wasm(module_id) = request.module().clone();
+
+                    let sent = match &self.service.screenshot {
+                        Some(screenshot) => screenshot
+                            .send(ServiceRequest::Request { request })
+                            .map_err(|err| {
+                                format!("could not reach the screenshot service: {err}")
+                            }),
+                        None => Err("the screenshot service isn't running".to_string()),
+                    };
+
+                    if let Err(reason) = sent {
+                        log::error!("[app] {reason}");
+                        self.screenshot_taken(module_id, Err(reason));
+                    }
+                }
+            },
+            AppMessage::Builtin(builtin::Message::RefreshInspector) => {
+                self.builtin.refresh_inspector(self.runtime.wasm.as_ref());
+            }
+            AppMessage::Builtin(message) => {
+                command = self.builtin.update(message);
+            }
+            AppMessage::Output { wl_output, output } => {
+                let changed = match output {
+                    Some(output) => {
+                        log::debug!("[app] output: {output:?}");
+                        outputs::update(wl_output, output)
+                    }
+                    None => outputs::remove(&wl_output),
+                };
+
+                if changed
+                    && let Some(wasm) = &mut self.runtime.wasm
+                    && let Err(err) = WasmRuntime::request(
+                        wasm,
+                        RuntimeRequest::new(wasm::Request::OutputsChanged),
+                    )
+                {
+                    log::error!(
+                        "[app] could not tell the wasm runtime that outputs changed: {err}"
+                    );
+                }
+            }
+            AppMessage::RegionSelected { module_id, region } => {
+                if let Some(wasm) = &mut self.runtime.wasm
+                    && let Err(err) = WasmRuntime::request(
+                        wasm,
+                        RuntimeRequest::new(wasm::Request::RegionSelected { module_id, region }),
+                    )
+                {
+                    log::error!(
+                        "[app] could not give module {module_id} the region it picked: {err}"
+                    );
+                }
+            }
         }
 
         return command;
     }
 
     pub fn view(&self, id: Id) -> Element<'_, AppMessage> {
+        if self.builtin.owns(id) {
+            return self.builtin.view(id, &self.base_16_theme);
+        }
+
         if let Some(wasm) = &self.runtime.wasm {
             if let Some(module_id) = wasm.surface_module_ids.get(&id) {
-                if let Some(map) = wasm.module_ui_trees.get(module_id) {
-                    if let Some(tree) = map.get(&id) {
-                        return build_tree(*module_id, id, &tree);
+                if let Some(snapshot) = wasm.module_ui_trees.get(module_id) {
+                    if let Some(surface) = snapshot.trees.get(&id) {
+                        return build_tree(
+                            *module_id,
+                            id,
+                            surface.generation,
+                            &self.tray_state.items,
+                            &self.base_16_theme,
+                            &surface.tree,
+                        );
                     }
                 }
             }
@@ -216,17 +1436,634 @@ impl App {
     }
 
     pub fn subscription(&self) -> Subscription<AppMessage> {
+        // services are replaced with ones replaying the fixture's state
+        let (audio, custom) = match fixture::get() {
+            Some(snapshot) => (
+                fixture::subscribe("audio", AudioService::fixture_events(&snapshot.audio)),
+                fixture::subscribe("custom", CustomService::fixture_events(&snapshot.custom)),
+            ),
+            None => (AudioService::subscribe(), CustomService::subscribe()),
+        };
+
+        // the services a fixture has no state for aren't started while one is
+        // loaded, so modules don't mix the dump with this machine's state.
+        // clock, interval and ipc only keep time or take commands so they run
+        // either way
+        let live = match fixture::get() {
+            Some(_) => vec![],
+            None => self.live_services(),
+        };
+
         Subscription::batch(vec![
             Subscription::batch(vec![
-                AudioService::subscribe()
-                    .map(|event| AppMessage::Service(ServiceMessage::Audio(event))),
+                audio.map(|event| AppMessage::Service(ServiceMessage::Audio(event))),
+                ClockService::subscribe()
+                    .map(|event| AppMessage::Service(ServiceMessage::Clock(event))),
+                custom.map(|event| AppMessage::Service(ServiceMessage::Custom(event))),
+                IntervalService::subscribe()
+                    .map(|event| AppMessage::Service(ServiceMessage::Interval(event))),
+                IpcService::subscribe()
+                    .map(|event| AppMessage::Service(ServiceMessage::Ipc(event))),
             ]),
+            Subscription::batch(live),
             Subscription::batch(vec![
                 WasmRuntime::run(()).map(|event| AppMessage::Runtime(RuntimeMessage::Wasm(event))),
             ]),
+            self.builtin.subscription().map(AppMessage::Builtin),
+            event::listen_with(|event, _status, _id| match event {
+                Event::PlatformSpecific(PlatformSpecific::Wayland(wayland::Event::Output(
+                    event,
+                    wl_output,
+                ))) => match event {
+                    OutputEvent::Created(Some(info)) | OutputEvent::InfoUpdate(info) => {
+                        let mode = info.modes.iter().find(|mode| mode.current);
+
+                        Some(AppMessage::Output {
+                            wl_output,
+                            output: Some(Output::new(
+                                info.name,
+                                info.description,
+                                mode.map(|mode| mode.dimensions),
+                                info.logical_size,
+                                info.logical_position,
+                                info.scale_factor,
+                                info.transform,
+                            )),
+                        })
+                    }
+                    OutputEvent::Removed => Some(AppMessage::Output {
+                        wl_output,
+                        output: None,
+                    }),
+                    // the info comes in a later `InfoUpdate`
+                    OutputEvent::Created(None) => None,
+                },
+                _ => None,
+            }),
         ])
     }
 
+    /// the services that read this machine's state, see `fixture`
+    fn live_services(&self) -> Vec<Subscription<AppMessage>> {
+        // the weather service needs a location to do anything
+        let weather = match config::get().services.weather.is_configured() {
+            true => WeatherService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::Weather(event))),
+            false => Subscription::none(),
+        };
+
+        return vec![
+            AppearanceService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::Appearance(event))),
+            BrightnessService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::Brightness(event))),
+            DbusService::subscribe().map(|event| AppMessage::Service(ServiceMessage::Dbus(event))),
+            HotkeysService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::Hotkeys(event))),
+            InhibitService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::Inhibit(event))),
+            KdeConnectService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::KdeConnect(event))),
+            NetworkService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::Network(event))),
+            ScreenshotService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::Screenshot(event))),
+            SessionService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::Session(event))),
+            SysinfoService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::Sysinfo(event))),
+            TrayService::subscribe().map(|event| AppMessage::Service(ServiceMessage::Tray(event))),
+            WatchService::subscribe()
+                .map(|event| AppMessage::Service(ServiceMessage::Watch(event))),
+            weather,
+        ];
+    }
+
+    /// answers a query from the ipc service using the app's state
+    ///
+    /// queries that are actions return a task to run
+    fn answer_query(&mut self, query: Query) -> (Response, Task<AppMessage>) {
+        let response = match query {
+            Query::Modules => Response::Modules {
+                modules: self.modules_info(),
+            },
+            Query::Services => Response::Services {
+                services: self.services_info(),
+            },
+            Query::Audio => Response::Audio(AudioInfo::from_state(&self.audio_state)),
+            Query::Metrics => Response::Metrics {
+                metrics: metrics::snapshot(),
+            },
+            Query::Compositor => Response::Compositor(compositor::get().clone()),
+            Query::DumpState => Response::State(self.snapshot()),
+            Query::TogglePopup { widget } => {
+                let task = self.builtin.update(builtin::Message::TogglePopup(widget));
+                return (Response::Done, task);
+            }
+            Query::ToggleInspector => {
+                let task = self.builtin.toggle_inspector(self.runtime.wasm.as_ref());
+                return (Response::Done, task);
+            }
+            Query::Reload => return (Response::Done, self.reload()),
+            Query::DoNotDisturb { enabled } => {
+                let task = Task::done(AppMessage::Request(SubscriptionRequest::KdeConnect(
+                    kdeconnect::Request::SetDoNotDisturb { enabled },
+                )));
+                return (Response::Done, task);
+            }
+            Query::RestartService { service } => match services::restart(service.id()) {
+                true => {
+                    log::info!("[app] restarting the {} service", service.as_str());
+                    Response::Done
+                }
+                false => Response::Error {
+                    message: format!("the {} service isn't running", service.as_str()),
+                },
+            },
+            Query::ReloadModules { module } => self.reload_modules(module.as_deref()),
+            Query::ToggleSurface { module, surface } => self.toggle_surface(&module, surface),
+        };
+
+        return (response, Task::none());
+    }
+
+    /// re-reads `colors.toml` and the parts of `config.toml` that can change
+    /// while running, the bar and widgets
+    ///
+    /// services keep the config they were started with
+    fn reload(&mut self) -> Task<AppMessage> {
+        self.load_theme();
+
+        let task = match config::Config::from_file() {
+            Ok(config) => self.builtin.reconfigure(&config),
+            Err(err) => {
+                log::warn!("[app] could not reload the config: {err}");
+                Task::none()
+            }
+        };
+
+        diagnostics::report();
+        log::info!("[app] reloaded the theme and config");
+
+        return task;
+    }
+
+    /// reads the colors file for the current color scheme and gives it to
+    /// modules, the old theme is kept when it can't be read
+    fn load_theme(&mut self) {
+        match Base16Color::from_config(self.color_scheme) {
+            Ok(theme) => {
+                self.base_16_theme = theme;
+                self.send_theme();
+            }
+            Err(err) => log::warn!("[app] could not reload the theme: {err}"),
+        }
+    }
+
+    /// gives the palette and font to the wasm runtime for modules to read
+    fn send_theme(&mut self) {
+        let font = match self.font.family {
+            font::Family::Name(name) => name,
+            _ => "",
+        };
+        let theme = Arc::new(self.base_16_theme.serialize(
+            font,
+            MODULE_TEXT_SIZE,
+            self.color_scheme,
+        ));
+
+        if let Some(wasm) = &mut self.runtime.wasm
+            && let Err(err) = WasmRuntime::request(
+                wasm,
+                RuntimeRequest::new(wasm::Request::ThemeChanged { theme }),
+            )
+        {
+            log::error!("[app] could not send the theme to the wasm runtime: {err}");
+        }
+    }
+
+    /// asks the wasm runtime to load modules again from their files, like the
+    /// module watcher does when one changes. every module is reloaded when
+    /// `name` is `None`
+    fn reload_modules(&mut self, name: Option<&str>) -> Response {
+        let Some(wasm) = &mut self.runtime.wasm else {
+            return Response::Error {
+                message: "the wasm runtime isn't running".to_string(),
+            };
+        };
+
+        let paths = wasm
+            .modules
+            .values()
+            .filter(|module| name.is_none_or(|name| module.module_name == name))
+            .map(|module| module.file_path.clone())
+            .collect::<Vec<PathBuf>>();
+
+        if let Some(name) = name
+            && paths.is_empty()
+        {
+            return Response::Error {
+                message: format!("no module named {name} is loaded"),
+            };
+        }
+
+        for path in paths {
+            if let Err(err) = WasmRuntime::request(
+                wasm,
+                RuntimeRequest::new(wasm::Request::ReloadModule { path }),
+            ) {
+                return Response::Error {
+                    message: format!("could not ask the wasm runtime to reload: {err}"),
+                };
+            }
+        }
+
+        log::info!("[app] reloading {}", name.unwrap_or("every module"));
+
+        return Response::Done;
+    }
+
+    /// asks the wasm runtime to close one of a module's surfaces, or open it
+    /// again if it's closed
+    fn toggle_surface(&mut self, name: &str, surface: u32) -> Response {
+        let Some(wasm) = &mut self.runtime.wasm else {
+            return Response::Error {
+                message: "the wasm runtime isn't running".to_string(),
+            };
+        };
+
+        let Some(module_id) = wasm
+            .modules
+            .iter()
+            .find(|(_, module)| module.module_name == name)
+            .map(|(id, _)| *id)
+        else {
+            return Response::Error {
+                message: format!("no module named {name} is loaded"),
+            };
+        };
+
+        return match WasmRuntime::request(
+            wasm,
+            RuntimeRequest::new(wasm::Request::ToggleSurface { module_id, surface }),
+        ) {
+            Ok(()) => Response::Done,
+            Err(err) => Response::Error {
+                message: format!("could not ask the wasm runtime to toggle the surface: {err}"),
+            },
+        };
+    }
+
+    /// everything the app knows, for debugging
+    fn snapshot(&self) -> StateSnapshot {
+        let mut surfaces = match &self.runtime.wasm {
+            Some(wasm) => wasm
+                .surface_module_ids
+                .iter()
+                .map(|(surface_id, module_id)| SurfaceInfo {
+                    surface_id: format!("{surface_id:?}"),
+                    module_id: *module_id,
+                    tree: wasm
+                        .module_ui_trees
+                        .get(module_id)
+                        .and_then(|snapshot| snapshot.trees.get(surface_id))
+                        .map(|surface| TreeInfo::from_tree(&surface.tree)),
+                })
+                .collect::<Vec<SurfaceInfo>>(),
+            None => vec![],
+        };
+        surfaces.sort_by(|a, b| (a.module_id, &a.surface_id).cmp(&(b.module_id, &b.surface_id)));
+
+        StateSnapshot {
+            version: StateSnapshot::VERSION,
+            taken_at: humantime::format_rfc3339_millis(SystemTime::now()).to_string(),
+            services: self.services_info(),
+            audio: AudioInfo::from_state(&self.audio_state),
+            custom: CustomInfo::from_state(&self.custom_state),
+            modules: self.modules_info(),
+            surfaces,
+        }
+    }
+
+    fn modules_info(&self) -> Vec<ModuleInfo> {
+        let Some(wasm) = &self.runtime.wasm else {
+            return vec![];
+        };
+
+        let errors = |id: &u32| -> (u64, Vec<ModuleErrorInfo>) {
+            match wasm.module_errors.get(id) {
+                Some(errors) => (
+                    errors.count,
+                    errors
+                        .recent
+                        .iter()
+                        .map(ModuleErrorInfo::from_error)
+                        .collect(),
+                ),
+                None => (0, vec![]),
+            }
+        };
+
+        let mut modules = wasm
+            .modules
+            .iter()
+            .map(|(id, module)| {
+                let (error_count, errors) = errors(id);
+
+                ModuleInfo {
+                    id: *id,
+                    name: module.module_name.clone(),
+                    runtime: "wasm".to_string(),
+                    file_path: module.file_path.display().to_string(),
+                    surfaces: wasm
+                        .module_ui_trees
+                        .get(id)
+                        .map_or(0, |snapshot| snapshot.trees.len()),
+                    disabled: module.disabled.clone(),
+                    error_count,
+                    errors,
+                }
+            })
+            .collect::<Vec<ModuleInfo>>();
+
+        // modules that couldn't be loaded are listed too, they have no name
+        // so they go by their file
+        for (id, module_errors) in &wasm.module_errors {
+            let Some(ModuleError::SetupFailed { file_path, message }) = module_errors.recent.back()
+            else {
+                continue;
+            };
+            // loaded since, or its file was removed
+            if wasm.modules.contains_key(id) || !file_path.exists() {
+                continue;
+            }
+
+            let (error_count, errors) = errors(id);
+            modules.push(ModuleInfo {
+                id: *id,
+                name: file_path
+                    .file_stem()
+                    .map_or(String::new(), |stem| stem.to_string_lossy().into_owned()),
+                runtime: "wasm".to_string(),
+                file_path: file_path.display().to_string(),
+                surfaces: 0,
+                disabled: Some(format!("could not be loaded: {message}")),
+                error_count,
+                errors,
+            });
+        }
+        modules.sort_by_key(|module| module.id);
+
+        modules
+    }
+
+    /// passes a click on a tray icon on to the tray service
+    fn click_tray_item(&self, item: String, click: TrayClick) {
+        match &self.service.tray {
+            Some(tray) => {
+                if let Err(err) = tray.send(ServiceRequest::Request {
+                    request: tray::Request::Click { item, click },
+                }) {
+                    log::error!("[app] could not send request to the tray service: {err}");
+                }
+            }
+            None => log::error!("[app] tray service not initalized"),
+        }
+    }
+
+    /// opens the overlay for a module to pick a region of the output at
+    /// `output` with, or of the one with focus when it's `None`
+    fn select_region(&mut self, module_id: u32, output: Option<u32>) -> Task<AppMessage> {
+        let output = match output {
+            None => IcedOutput::Active,
+            Some(index) => match outputs::wl_output(index as usize) {
+                Some(wl_output) => IcedOutput::Output(wl_output),
+                // the module's region would be on some other output
+                None => {
+                    log::warn!(
+                        "[app] module {module_id} asked for a region of output {index}, which \
+                         isn't plugged in"
+                    );
+                    return Task::done(AppMessage::RegionSelected {
+                        module_id,
+                        region: None,
+                    });
+                }
+            },
+        };
+
+        log::debug!("[app] module {module_id} asked for a region to be picked");
+        return self.builtin.select_region(module_id, output);
+    }
+
+    /// asks the screenshot service for a screenshot of the output at `output`
+    /// in the order of `outputs::get`, or of every output when it's `None`.
+    /// `region` is a rectangle on that output instead of all of it
+    fn capture_screenshot(
+        &mut self,
+        module_id: u32,
+        output: Option<u32>,
+        region: Option<Region>,
+    ) -> Task<AppMessage> {
+        let module = RuntimeModuleId::Wasm(module_id);
+
+        let output = match output {
+            None => None,
+            Some(index) => match outputs::get().into_iter().nth(index as usize) {
+                Some(output) => Some(output),
+                None => {
+                    log::warn!(
+                        "[app] module {module_id} asked for a screenshot of output {index}, \
+                         which isn't plugged in"
+                    );
+                    self.screenshot_taken(module_id, Err("the output isn't plugged in".into()));
+                    return Task::none();
+                }
+            },
+        };
+
+        let request = match (output, region) {
+            (output, None) => screenshot::Request::CaptureOutput {
+                module,
+                output: output.map(|output| output.name),
+            },
+            // the region is from the output's top left corner
+            (Some(output), Some(region)) => screenshot::Request::CaptureRegion {
+                module,
+                region: Geometry {
+                    x: output.x.saturating_add_unsigned(region.x),
+                    y: output.y.saturating_add_unsigned(region.y),
+                    width: region.width,
+                    height: region.height,
+                },
+            },
+            (None, Some(_)) => {
+                self.screenshot_taken(module_id, Err("a region needs an output".into()));
+                return Task::none();
+            }
+        };
+
+        log::debug!("[app] module {module_id} asked for {request:?}");
+        return Task::done(AppMessage::Request(SubscriptionRequest::Screenshot(
+            request,
+        )));
+    }
+
+    /// gives a module the screenshot it asked for, or why there isn't one
+    fn screenshot_taken(&mut self, module_id: u32, result: Result<PathBuf, String>) {
+        if let Some(wasm) = &mut self.runtime.wasm
+            && let Err(err) = WasmRuntime::request(
+                wasm,
+                RuntimeRequest::new(wasm::Request::ScreenshotTaken { module_id, result }),
+            )
+        {
+            log::error!("[app] could not give module {module_id} its screenshot: {err}");
+        }
+    }
+
+    /// tells every service a module registers with that it's gone
+    fn unsubscribe_module(&self, id: RuntimeModuleId) {
+        fn unsubscribe<S: Service>(
+            name: &str,
+            service: &Option<InstrumentedSender<flume::Sender<ServiceRequest<S>>>>,
+            id: &RuntimeModuleId,
+        ) {
+            if let Some(service) = service
+                && let Err(err) = service.send(ServiceRequest::UnsubscribeModule { id: id.clone() })
+            {
+                log::error!("[app] could not unsubscribe {id:?} from the {name} service: {err}");
+            }
+        }
+
+        unsubscribe("audio", &self.service.audio, &id);
+        unsubscribe("brightness", &self.service.brightness, &id);
+        unsubscribe("clock", &self.service.clock, &id);
+        unsubscribe("custom", &self.service.custom, &id);
+        unsubscribe("dbus", &self.service.dbus, &id);
+        unsubscribe("hotkeys", &self.service.hotkeys, &id);
+        unsubscribe("inhibit", &self.service.inhibit, &id);
+        unsubscribe("interval", &self.service.interval, &id);
+        unsubscribe("kdeconnect", &self.service.kdeconnect, &id);
+        unsubscribe("network", &self.service.network, &id);
+        unsubscribe("sysinfo", &self.service.sysinfo, &id);
+        unsubscribe("watch", &self.service.watch, &id);
+        unsubscribe("weather", &self.service.weather, &id);
+    }
+
+    /// marks a service as running or not for modules asking about it, the
+    /// modules are rendered again if that changed
+    fn set_service_available<S: Service>(&mut self, available: bool) {
+        if !services::set_available::<S>(available) {
+            return;
+        }
+
+        if let Some(wasm) = &mut self.runtime.wasm
+            && let Err(err) =
+                WasmRuntime::request(wasm, RuntimeRequest::new(wasm::Request::ServicesChanged))
+        {
+            log::error!("[app] could not tell the wasm runtime that services changed: {err}");
+        }
+    }
+
+    fn services_info(&self) -> Vec<ServiceInfo> {
+        vec![
+            ServiceInfo {
+                name: "appearance".to_string(),
+                version: AppearanceService::VERSION,
+                running: self.service.appearance.is_some()
+                    && self.appearance_state.unavailable.is_none(),
+            },
+            ServiceInfo {
+                name: "audio".to_string(),
+                version: AudioService::VERSION,
+                running: self.service.audio.is_some() && self.audio_state.unavailable.is_none(),
+            },
+            ServiceInfo {
+                name: "brightness".to_string(),
+                version: BrightnessService::VERSION,
+                running: self.service.brightness.is_some()
+                    && self.brightness_state.unavailable.is_none(),
+            },
+            ServiceInfo {
+                name: "clock".to_string(),
+                version: ClockService::VERSION,
+                running: self.service.clock.is_some(),
+            },
+            ServiceInfo {
+                name: "custom".to_string(),
+                version: CustomService::VERSION,
+                running: self.service.custom.is_some(),
+            },
+            ServiceInfo {
+                name: "dbus".to_string(),
+                version: DbusService::VERSION,
+                running: self.service.dbus.is_some(),
+            },
+            ServiceInfo {
+                name: "hotkeys".to_string(),
+                version: HotkeysService::VERSION,
+                running: self.service.hotkeys.is_some() && self.hotkeys_state.unavailable.is_none(),
+            },
+            ServiceInfo {
+                name: "inhibit".to_string(),
+                version: InhibitService::VERSION,
+                running: self.service.inhibit.is_some() && self.inhibit_state.unavailable.is_none(),
+            },
+            ServiceInfo {
+                name: "interval".to_string(),
+                version: IntervalService::VERSION,
+                running: self.service.interval.is_some(),
+            },
+            ServiceInfo {
+                name: "ipc".to_string(),
+                version: IpcService::VERSION,
+                running: self.service.ipc.is_some(),
+            },
+            ServiceInfo {
+                name: "kdeconnect".to_string(),
+                version: KdeConnectService::VERSION,
+                running: self.service.kdeconnect.is_some()
+                    && self.kdeconnect_state.unavailable.is_none(),
+            },
+            ServiceInfo {
+                name: "network".to_string(),
+                version: NetworkService::VERSION,
+                running: self.service.network.is_some(),
+            },
+            ServiceInfo {
+                name: "screenshot".to_string(),
+                version: ScreenshotService::VERSION,
+                running: self.service.screenshot.is_some()
+                    && self.screenshot_state.unavailable.is_none(),
+            },
+            ServiceInfo {
+                name: "session".to_string(),
+                version: SessionService::VERSION,
+                running: self.service.session.is_some() && self.session_state.unavailable.is_none(),
+            },
+            ServiceInfo {
+                name: "sysinfo".to_string(),
+                version: SysinfoService::VERSION,
+                running: self.service.sysinfo.is_some(),
+            },
+            ServiceInfo {
+                name: "tray".to_string(),
+                running: self.service.tray.is_some() && self.tray_state.unavailable.is_none(),
+                version: TrayService::VERSION,
+            },
+            ServiceInfo {
+                name: "watch".to_string(),
+                version: WatchService::VERSION,
+                running: self.service.watch.is_some(),
+            },
+            ServiceInfo {
+                name: "weather".to_string(),
+                version: WeatherService::VERSION,
+                running: self.service.weather.is_some(),
+            },
+        ]
+    }
+
     pub fn style(&self, theme: &Theme) -> Appearance {
         Appearance {
             background_color: Color::TRANSPARENT,
@@ -236,38 +2073,104 @@ impl App {
     }
 }
 
-pub fn build_tree(module_id: u32, surface_id: Id, node: &WasmUiNode) -> Element<'_, AppMessage> {
+/// sets the generation of a callback coming from a subtree kept by `lazy`
+fn with_generation(mut message: AppMessage, generation: u32) -> AppMessage {
+    if let AppMessage::Request(SubscriptionRequest::Wasm(wasm::Request::CallbackEvent {
+        generation: sent,
+        ..
+    })) = &mut message
+    {
+        *sent = generation;
+    }
+
+    return message;
+}
+
+/// turns a module's ui tree into widgets
+///
+/// the widgets own everything they need so static subtrees can be kept by
+/// `lazy` between views
+///
+/// `generation` is sent with every callback so the runtime can drop the ones
+/// from a tree that has since been replaced
+///
+/// `tray` is what's drawn in the tree's tray icon slots, `theme` colors the
+/// text
+pub fn build_tree(
+    module_id: u32,
+    surface_id: Id,
+    generation: u32,
+    tray: &TrayItems,
+    theme: &Base16Color,
+    node: &WasmUiNode,
+) -> Element<'static, AppMessage> {
     match node {
-        WasmUiNode::Row { children } => Row::with_children(
+        WasmUiNode::Row { children, layout } => Row::with_children(
             children
                 .iter()
-                .map(|child| build_tree(module_id, surface_id, child))
+                .map(|child| build_tree(module_id, surface_id, generation, tray, theme, child))
                 .collect::<Vec<Element<AppMessage>>>(),
         )
+        .spacing(layout.spacing)
+        .padding(layout_padding(layout))
+        .width(module_length(layout.width))
+        .height(module_length(layout.height))
+        .align_y(module_alignment(layout.align))
         .into(),
-        WasmUiNode::Column { children } => Column::with_children(
+        WasmUiNode::Column { children, layout } => Column::with_children(
             children
                 .iter()
-                .map(|child| build_tree(module_id, surface_id, child))
+                .map(|child| build_tree(module_id, surface_id, generation, tray, theme, child))
                 .collect::<Vec<Element<AppMessage>>>(),
         )
+        .spacing(layout.spacing)
+        .padding(layout_padding(layout))
+        .width(module_length(layout.width))
+        .height(module_length(layout.height))
+        .align_x(module_alignment(layout.align))
         .into(),
-        WasmUiNode::Text { content, style } => {
-            let mut widget = text(content.clone()).size(11);
+        WasmUiNode::Text {
+            content,
+            style,
+            vertical,
+        } => {
+            let color = module_color(style.color, theme);
+            let size = style.size.unwrap_or(MODULE_TEXT_SIZE);
+            let font = text_font(style.font);
 
-            widget = widget.style(Box::new(|_: &Theme| *style));
+            // iced can't rotate text, so vertical text is a column of upright
+            // letters
+            if *vertical {
+                return Column::with_children(content.chars().map(|letter| {
+                    text(letter.to_string())
+                        .size(size)
+                        .font(font)
+                        .color(color)
+                        .into()
+                }))
+                .align_x(Alignment::Center)
+                .into();
+            }
 
-            widget.into()
+            text(content.clone())
+                .size(size)
+                .font(font)
+                .color(color)
+                .into()
         }
         WasmUiNode::Button { inner, callback_id } => {
-            let mut widget = button(build_tree(module_id, surface_id, inner));
+            let callback_id = *callback_id;
+            let mut widget = button(build_tree(
+                module_id, surface_id, generation, tray, theme, inner,
+            ));
 
-            if *callback_id != 0 {
+            if callback_id != 0 {
                 widget = widget.on_press_with(move || {
                     AppMessage::Request(SubscriptionRequest::Wasm(wasm::Request::CallbackEvent {
                         module_id,
                         surface_id,
-                        callback_id: *callback_id,
+                        callback_id,
+                        generation,
                         data: None,
                     }))
                 });
@@ -280,59 +2183,501 @@ pub fn build_tree(module_id: u32, surface_id: Id, node: &WasmUiNode) -> Element<
             range,
             value,
             callback_id,
-        } => match number_type {
-            wasm::SliderNumberType::I32 => {
-                let start = *range.start() as i32;
-                let end = *range.end() as i32;
-                let range = start..=end;
+            vertical,
+        } => {
+            let callback_id = *callback_id;
+            let vertical = *vertical;
+            // the value is sent to the module as the bits of its number type
+            let message = move |bits: u64| {
+                AppMessage::Request(SubscriptionRequest::Wasm(wasm::Request::CallbackEvent {
+                    module_id,
+                    surface_id,
+                    callback_id,
+                    generation,
+                    data: Some(WasmCallbackData::Slider(bits)),
+                }))
+            };
 
-                slider(range, *value as i32, move |new_value| {
-                    AppMessage::Request(SubscriptionRequest::Wasm(wasm::Request::CallbackEvent {
+            match number_type {
+                wasm::SliderNumberType::I32 => {
+                    let start = *range.start() as i32;
+                    let end = *range.end() as i32;
+                    let range = start..=end;
+                    let value = *value as i32;
+                    let on_change = move |new_value: i32| message(new_value as u64);
+
+                    match vertical {
+                        true => vertical_slider(range, value, on_change).into(),
+                        false => slider(range, value, on_change).into(),
+                    }
+                }
+                wasm::SliderNumberType::F32 => {
+                    let start = f32::from_bits(*range.start() as u32);
+                    let end = f32::from_bits(*range.end() as u32);
+                    let range = start..=end;
+                    let value = f32::from_bits(*value as u32);
+                    let on_change = move |new_value: f32| message(new_value.to_bits() as u64);
+
+                    match vertical {
+                        true => vertical_slider(range, value, on_change).into(),
+                        false => slider(range, value, on_change).into(),
+                    }
+                }
+                wasm::SliderNumberType::F64 => {
+                    let start = f64::from_bits(*range.start());
+                    let end = f64::from_bits(*range.end());
+                    let range = start..=end;
+                    let value = f64::from_bits(*value);
+                    let on_change = move |new_value: f64| message(new_value.to_bits());
+
+                    match vertical {
+                        true => vertical_slider(range, value, on_change).into(),
+                        false => slider(range, value, on_change).into(),
+                    }
+                }
+            }
+        }
+        WasmUiNode::Stack { children } => Stack::with_children(
+            children
+                .iter()
+                .map(|child| build_tree(module_id, surface_id, generation, tray, theme, child))
+                .collect::<Vec<Element<AppMessage>>>(),
+        )
+        .into(),
+        WasmUiNode::LongPress { inner, callback_id } => {
+            let callback_id = *callback_id;
+            let mut widget = long_press(build_tree(
+                module_id, surface_id, generation, tray, theme, inner,
+            ));
+
+            if callback_id != 0 {
+                widget = widget.on_long_press(AppMessage::Request(SubscriptionRequest::Wasm(
+                    wasm::Request::CallbackEvent {
                         module_id,
                         surface_id,
-                        callback_id: *callback_id,
-                        data: Some(WasmCallbackData::Slider(new_value as u64)),
-                    }))
+                        callback_id,
+                        generation,
+                        data: None,
+                    },
+                )));
+            }
+
+            widget.into()
+        }
+        WasmUiNode::Container { inner, style } => {
+            let [top, right, bottom, left] = style.padding;
+            let background = style
+                .background
+                .map(|color| Background::Color(module_color(Some(color), theme)));
+            let border = border::rounded(style.border_radius)
+                .width(style.border_width)
+                .color(module_color(style.border_color, theme));
+
+            container(build_tree(
+                module_id, surface_id, generation, tray, theme, inner,
+            ))
+            .padding(iced::Padding {
+                top,
+                right,
+                bottom,
+                left,
+            })
+            .style(move |_| container::Style {
+                background,
+                border,
+                ..container::Style::default()
+            })
+            .into()
+        }
+        WasmUiNode::Tooltip {
+            inner,
+            text: content,
+            position,
+        } => {
+            let position = match position {
+                TooltipPosition::Top => tooltip::Position::Top,
+                TooltipPosition::Bottom => tooltip::Position::Bottom,
+                TooltipPosition::Left => tooltip::Position::Left,
+                TooltipPosition::Right => tooltip::Position::Right,
+                TooltipPosition::FollowCursor => tooltip::Position::FollowCursor,
+            };
+            // the same look as the shell's popups, copied as the widget
+            // outlives the theme it's built with
+            let (background, foreground, border_color) =
+                (theme.background, theme.foreground, theme.color01);
+
+            tooltip(
+                build_tree(module_id, surface_id, generation, tray, theme, inner),
+                container(text(content.clone()).size(11))
+                    .padding([4, 8])
+                    .style(move |_| container::Style {
+                        background: Some(Background::Color(background)),
+                        text_color: Some(foreground),
+                        border: border::width(1).rounded(8).color(border_color),
+                        ..container::Style::default()
+                    }),
+                position,
+            )
+            .gap(4)
+            .into()
+        }
+        WasmUiNode::MouseArea { inner, callbacks } => {
+            let callbacks = *callbacks;
+            let callback = move |callback_id: u32, data: Option<WasmCallbackData>| {
+                AppMessage::Request(SubscriptionRequest::Wasm(wasm::Request::CallbackEvent {
+                    module_id,
+                    surface_id,
+                    callback_id,
+                    generation,
+                    data,
+                }))
+            };
+
+            let mut widget = mouse_area(build_tree(
+                module_id, surface_id, generation, tray, theme, inner,
+            ));
+
+            if callbacks.on_enter != 0 {
+                widget = widget.on_enter(callback(callbacks.on_enter, None));
+            }
+            if callbacks.on_exit != 0 {
+                widget = widget.on_exit(callback(callbacks.on_exit, None));
+            }
+            if callbacks.on_right_press != 0 {
+                widget = widget.on_right_press(callback(callbacks.on_right_press, None));
+            }
+            if callbacks.on_scroll != 0 {
+                widget = widget.on_scroll(move |delta| {
+                    let (x, y) = match delta {
+                        mouse::ScrollDelta::Lines { x, y } => (x, y),
+                        mouse::ScrollDelta::Pixels { x, y } => {
+                            (x / SCROLL_PIXELS_PER_LINE, y / SCROLL_PIXELS_PER_LINE)
+                        }
+                    };
+                    callback(callbacks.on_scroll, Some(WasmCallbackData::Scroll { x, y }))
+                });
+            }
+
+            widget.into()
+        }
+        WasmUiNode::Static { key, child } => {
+            let child = Arc::clone(child);
+            let tray = tray.clone();
+            let theme = *theme;
+            // the subtree is built without a generation so it's kept when the
+            // rest of the tree is replaced, the current one is set on its
+            // callbacks as they come out. the tray's revision is part of the
+            // key for any tray icons in the subtree and the theme's for the
+            // colors of its text
+            let subtree = lazy((*key, tray.revision, theme.fingerprint()), move |_| {
+                build_tree(module_id, surface_id, 0, &tray, &theme, &child)
+            });
+
+            Element::from(subtree).map(move |message| with_generation(message, generation))
+        }
+        WasmUiNode::Image {
+            image: module_image,
+            width,
+            height,
+        } => {
+            let width = width.map_or(Length::Shrink, Length::Fixed);
+            let height = height.map_or(Length::Shrink, Length::Fixed);
+
+            match module_image {
+                ModuleImage::File(path) if path.extension().is_some_and(|ext| ext == "svg") => {
+                    svg(svg::Handle::from_path(path))
+                        .width(width)
+                        .height(height)
+                        .into()
+                }
+                ModuleImage::File(path) => image(image::Handle::from_path(path))
+                    .width(width)
+                    .height(height)
+                    .into(),
+                // the cache hands out the same handle for the same bytes, so
+                // iced doesn't decode it again
+                ModuleImage::Png { handle, .. } => {
+                    image(handle.clone()).width(width).height(height).into()
+                }
+                // an icon that couldn't be found takes up its space
+                ModuleImage::Missing => Row::new().width(width).height(height).into(),
+            }
+        }
+        WasmUiNode::ProgressBar {
+            filled,
+            style,
+            vertical,
+        } => {
+            let vertical = *vertical;
+            let length = style.length.map_or(Length::Fill, Length::Fixed);
+            let thickness = Length::Fixed(style.thickness);
+            let radius = style.thickness / 2.0;
+            let bar = Background::Color(module_color(style.bar, theme));
+            let background = Background::Color(module_color(
+                Some(style.background.unwrap_or(ModuleColor::Base16(2))),
+                theme,
+            ));
+
+            // the filled and empty parts share the length by portions, an
+            // empty part with a portion of 0 takes up no space
+            let portion = (filled * PROGRESS_PORTIONS as f32).round() as u16;
+            let fill = Length::FillPortion(portion);
+            let rest = Length::FillPortion(PROGRESS_PORTIONS - portion);
+            let filled_part = move |width: Length, height: Length| {
+                container(Row::new())
+                    .width(width)
+                    .height(height)
+                    .style(move |_| container::Style {
+                        background: Some(bar),
+                        border: border::rounded(radius),
+                        ..container::Style::default()
+                    })
+            };
+
+            // vertical bars fill from the bottom up
+            let parts: Element<'static, AppMessage> = match vertical {
+                true => Column::new()
+                    .push(Row::new().width(Length::Fill).height(rest))
+                    .push(filled_part(Length::Fill, fill))
+                    .into(),
+                false => Row::new()
+                    .push(filled_part(fill, Length::Fill))
+                    .push(Row::new().width(rest).height(Length::Fill))
+                    .into(),
+            };
+            let (width, height) = match vertical {
+                true => (thickness, length),
+                false => (length, thickness),
+            };
+
+            container(parts)
+                .width(width)
+                .height(height)
+                .style(move |_| container::Style {
+                    background: Some(background),
+                    border: border::rounded(radius),
+                    ..container::Style::default()
                 })
                 .into()
-            }
-            wasm::SliderNumberType::F32 => {
-                let start = f32::from_bits(*range.start() as u32);
-                let end = f32::from_bits(*range.end() as u32);
-                let range = start..=end;
+        }
+        WasmUiNode::TextInput {
+            value,
+            placeholder,
+            width,
+            size,
+            callback_id,
+        } => {
+            let callback_id = *callback_id;
+            let mut widget = text_input(placeholder, value)
+                .width(width.map_or(Length::Fill, Length::Fixed))
+                .size(size.unwrap_or(11.0));
 
-                slider(range, f32::from_bits(*value as u32), move |new_value| {
+            if callback_id != 0 {
+                widget = widget.on_input(move |text| {
                     AppMessage::Request(SubscriptionRequest::Wasm(wasm::Request::CallbackEvent {
                         module_id,
                         surface_id,
-                        callback_id: *callback_id,
-                        data: Some(WasmCallbackData::Slider(new_value.to_bits() as u64)),
+                        callback_id,
+                        generation,
+                        data: Some(WasmCallbackData::Text(text)),
                     }))
-                })
-                .into()
+                });
             }
-            wasm::SliderNumberType::F64 => {
-                let start = f64::from_bits(*range.start());
-                let end = f64::from_bits(*range.end());
-                let range = start..=end;
 
-                slider(range, f64::from_bits(*value), move |new_value| {
+            widget.into()
+        }
+        WasmUiNode::Grid {
+            children,
+            layout,
+            callback_id,
+        } => {
+            let callback_id = *callback_id;
+            let layout = *layout;
+            let columns = layout.columns as usize;
+            let cell_width = Length::Fixed(layout.cell_width);
+            let cell_height = Length::Fixed(layout.cell_height);
+
+            // the rows the module didn't send cells for are left as space,
+            // so the scrollbar is as long as if they were all there
+            let rows = |items: u32| items.div_ceil(layout.columns) as f32 * layout.cell_height;
+            let before = rows(layout.first);
+            let sent = layout.first + children.len() as u32;
+            let after = rows(layout.total.saturating_sub(sent));
+
+            let mut grid = Column::new().push(Row::new().height(Length::Fixed(before)));
+            for row_cells in children.chunks(columns) {
+                let cells = row_cells.iter().map(|cell| {
+                    container(build_tree(
+                        module_id, surface_id, generation, tray, theme, cell,
+                    ))
+                    .center_x(cell_width)
+                    .center_y(cell_height)
+                    .into()
+                });
+                grid = grid.push(Row::with_children(
+                    cells.collect::<Vec<Element<AppMessage>>>(),
+                ));
+            }
+            grid = grid.push(Row::new().height(Length::Fixed(after)));
+
+            let mut widget = scrollable(grid)
+                .width(Length::Fixed(layout.cell_width * layout.columns as f32))
+                .height(layout.height.map_or(Length::Fill, Length::Fixed));
+
+            if callback_id != 0 {
+                widget = widget.on_scroll(move |viewport| {
+                    let row = (viewport.absolute_offset().y / layout.cell_height).max(0.0) as u32;
                     AppMessage::Request(SubscriptionRequest::Wasm(wasm::Request::CallbackEvent {
                         module_id,
                         surface_id,
-                        callback_id: *callback_id,
-                        data: Some(WasmCallbackData::Slider(new_value.to_bits() as u64)),
+                        callback_id,
+                        generation,
+                        data: Some(WasmCallbackData::Grid(row * layout.columns)),
                     }))
-                })
+                });
+            }
+
+            widget.into()
+        }
+        WasmUiNode::TrayImage { slot, callback_id } => {
+            let callback_id = *callback_id;
+            let item = match tray.get(*slot) {
+                Some(item) => item,
+                // fewer items than slots, the extra slots are left empty
+                None => return Row::new().into(),
+            };
+
+            let icon: Element<'static, AppMessage> = match &item.icon {
+                Some(TrayIcon::File(path)) if path.extension().is_some_and(|ext| ext == "svg") => {
+                    svg(svg::Handle::from_path(path))
+                        .width(TRAY_ICON_SIZE)
+                        .height(TRAY_ICON_SIZE)
+                        .into()
+                }
+                Some(TrayIcon::File(path)) => image(image::Handle::from_path(path))
+                    .width(TRAY_ICON_SIZE)
+                    .height(TRAY_ICON_SIZE)
+                    .into(),
+                Some(TrayIcon::Pixmap(handle)) => image(handle.clone())
+                    .width(TRAY_ICON_SIZE)
+                    .height(TRAY_ICON_SIZE)
+                    .into(),
+                // no icon we could find, so the first letter of its name
+                None => text(item.title.chars().next().unwrap_or('?').to_string())
+                    .size(11)
+                    .into(),
+            };
+
+            let on_click = |click: TrayClick| {
+                AppMessage::Request(SubscriptionRequest::Wasm(wasm::Request::CallbackEvent {
+                    module_id,
+                    surface_id,
+                    callback_id,
+                    generation,
+                    data: Some(WasmCallbackData::Tray {
+                        item: item.address.clone(),
+                        click,
+                    }),
+                }))
+            };
+
+            // touchscreens have no right button, so holding a finger on the
+            // icon opens its menu instead, activating on release means a held
+            // finger doesn't also activate it
+            let icon = mouse_area(icon)
+                .on_release(on_click(TrayClick::Activate))
+                .on_right_press(on_click(TrayClick::ContextMenu))
+                .on_middle_press(on_click(TrayClick::SecondaryActivate));
+
+            long_press(icon)
+                .on_long_press(on_click(TrayClick::ContextMenu))
                 .into()
+        }
+        WasmUiNode::Toggler {
+            is_toggled,
+            callback_id,
+        } => {
+            let callback_id = *callback_id;
+            let mut widget = toggler(*is_toggled).size(14);
+
+            if callback_id != 0 {
+                widget = widget.on_toggle(move |is_toggled| {
+                    AppMessage::Request(SubscriptionRequest::Wasm(wasm::Request::CallbackEvent {
+                        module_id,
+                        surface_id,
+                        callback_id,
+                        generation,
+                        data: Some(WasmCallbackData::Bool(is_toggled)),
+                    }))
+                });
             }
+
+            widget.into()
+        }
+    }
+}
+
+/// the padding a module asked a row or column to have
+fn layout_padding(layout: &Layout) -> iced::Padding {
+    let [top, right, bottom, left] = layout.padding;
+    iced::Padding {
+        top,
+        right,
+        bottom,
+        left,
+    }
+}
+
+/// the iced length for what a module asked a row or column to take up
+fn module_length(length: ModuleLength) -> Length {
+    match length {
+        ModuleLength::Shrink => Length::Shrink,
+        ModuleLength::Fill => Length::Fill,
+        ModuleLength::FillPortion(portion) => Length::FillPortion(portion),
+        ModuleLength::Fixed(pixels) => Length::Fixed(pixels),
+    }
+}
+
+fn module_alignment(alignment: ModuleAlignment) -> Alignment {
+    match alignment {
+        ModuleAlignment::Start => Alignment::Start,
+        ModuleAlignment::Center => Alignment::Center,
+        ModuleAlignment::End => Alignment::End,
+    }
+}
+
+/// the iced color for what a module asked for, the theme's foreground when
+/// it didn't
+fn module_color(color: Option<ModuleColor>, theme: &Base16Color) -> Color {
+    match color {
+        Some(ModuleColor::Base16(index)) => theme.base16(index),
+        Some(ModuleColor::Foreground) | None => theme.foreground,
+        Some(ModuleColor::Background) => theme.background,
+        Some(ModuleColor::Rgba([r, g, b, a])) => Color::from_rgba8(r, g, b, a as f32 / 255.0),
+    }
+}
+
+/// the iced font for what a module asked for
+fn text_font(requested: TextFont) -> Font {
+    let mut font = match requested.family {
+        TextFontFamily::Shell => Font::DEFAULT,
+        TextFontFamily::SansSerif => Font {
+            family: font::Family::SansSerif,
+            ..Font::DEFAULT
         },
-        WasmUiNode::Stack { children } => Stack::with_children(
-            children
-                .iter()
-                .map(|child| build_tree(module_id, surface_id, child))
-                .collect::<Vec<Element<AppMessage>>>(),
-        )
-        .into(),
+        TextFontFamily::Serif => Font {
+            family: font::Family::Serif,
+            ..Font::DEFAULT
+        },
+        TextFontFamily::Monospace => Font::MONOSPACE,
+    };
+
+    if requested.bold {
+        font.weight = font::Weight::Bold;
+    }
+    if requested.italic {
+        font.style = font::Style::Italic;
     }
+
+    return font;
 }
@@ -0,0 +1,269 @@
+//! writes every message the app handles to a file when the shell is started
+//! with `--audit-messages`, one json object per line
+//!
+//! each message gets a sequence id in the order the app handled it, along
+//! with where it came from, so `aurorashell audit analyze` can point out
+//! messages that went missing or came in an order that shouldn't happen
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::{env, fmt, fs};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+/// the debug output of a message is cut off after this many characters, so
+/// ui trees don't make the file huge
+const MAX_MESSAGE_LEN: usize = 300;
+
+static AUDIT: OnceLock<Mutex<AuditLog>> = OnceLock::new();
+
+struct AuditLog {
+    file: LineWriter<File>,
+    next_seq: u64,
+    start: Instant,
+}
+
+/// a line of the audit file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    /// counts up from 0 in the order the app handled the messages
+    pub seq: u64,
+    /// when the message was handled
+    pub at: String,
+    /// microseconds since the audit started, unlike `at` it can't go
+    /// backwards
+    pub elapsed_micros: u64,
+    /// what sent the message, like `service:audio`
+    pub source: String,
+    /// what kind of message it was for its source, like `init` or `update`
+    pub kind: String,
+    /// the debug output of the message
+    pub message: String,
+}
+
+/// starts writing messages to `path`, or to
+/// `~/.cache/aurorashell/audit-<unix time>.jsonl` if it's `None`
+pub fn init(path: Option<PathBuf>) -> anyhow::Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => default_path()?,
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let file = File::create(&path)?;
+
+    let log = AuditLog {
+        file: LineWriter::new(file),
+        next_seq: 0,
+        start: Instant::now(),
+    };
+
+    if AUDIT.set(Mutex::new(log)).is_err() {
+        return Err(anyhow!("[audit] already started"));
+    }
+
+    log::warn!("[audit] writing every app message to {}", path.display());
+
+    return Ok(());
+}
+
+/// writes a message to the audit file, does nothing unless `init` was called
+pub fn record(source: &str, kind: &str, message: &impl fmt::Debug) {
+    let audit = match AUDIT.get() {
+        Some(audit) => audit,
+        None => return,
+    };
+
+    let mut audit = match audit.lock() {
+        Ok(audit) => audit,
+        Err(err) => {
+            log::error!("[audit] could not lock the audit file: {err}");
+            return;
+        }
+    };
+
+    let mut message = format!("{message:?}");
+    if let Some((index, _)) = message.char_indices().nth(MAX_MESSAGE_LEN) {
+        message.truncate(index);
+        message.push_str("...");
+    }
+
+    let entry = Entry {
+        seq: audit.next_seq,
+        at: humantime::format_rfc3339_micros(SystemTime::now()).to_string(),
+        elapsed_micros: audit.start.elapsed().as_micros() as u64,
+        source: source.to_string(),
+        kind: kind.to_string(),
+        message,
+    };
+    audit.next_seq += 1;
+
+    let result = serde_json::to_string(&entry)
+        .map_err(anyhow::Error::from)
+        .and_then(|line| Ok(writeln!(audit.file, "{line}")?));
+
+    if let Err(err) = result {
+        log::error!("[audit] could not write message {}: {err}", entry.seq);
+    }
+}
+
+fn default_path() -> anyhow::Result<PathBuf> {
+    let home = env::var("HOME")?;
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+
+    return Ok(PathBuf::from(home).join(format!(".cache/aurorashell/audit-{secs}.jsonl")));
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// analysis
+
+/// something in an audit file that shouldn't happen
+#[derive(Debug)]
+pub enum Anomaly {
+    /// sequence ids were skipped, so messages are missing from the file
+    Gap { after: u64, missing: u64 },
+    /// a sequence id came after a higher one, the file was written by more
+    /// than one shell or lines were moved around
+    OutOfOrder { seq: u64, after: u64 },
+    /// a message was handled before an earlier one, by the monotonic clock
+    TimeWentBackwards { seq: u64, by_micros: u64 },
+    /// a service or runtime sent an update before telling the app it
+    /// started
+    BeforeInit { seq: u64, source: String },
+    /// a line that isn't an entry
+    Unreadable { line: usize, error: String },
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gap { after, missing } => {
+                write!(f, "{missing} message(s) missing after #{after}")
+            }
+            Self::OutOfOrder { seq, after } => write!(f, "#{seq} came after #{after}"),
+            Self::TimeWentBackwards { seq, by_micros } => {
+                write!(
+                    f,
+                    "#{seq} was handled {by_micros}us before the message ahead of it"
+                )
+            }
+            Self::BeforeInit { seq, source } => {
+                write!(
+                    f,
+                    "#{seq} from {source} came before {source} was initialized"
+                )
+            }
+            Self::Unreadable { line, error } => write!(f, "line {line} can't be read: {error}"),
+        }
+    }
+}
+
+/// what `analyze` found
+#[derive(Debug, Default)]
+pub struct Report {
+    pub messages: u64,
+    /// the amount of messages from each source
+    pub sources: HashMap<String, u64>,
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} message(s)", self.messages)?;
+
+        let mut sources = self.sources.iter().collect::<Vec<(&String, &u64)>>();
+        sources.sort();
+        for (source, count) in sources {
+            writeln!(f, "  {source}: {count}")?;
+        }
+
+        if self.anomalies.is_empty() {
+            return writeln!(f, "no anomalies found");
+        }
+
+        writeln!(f, "{} anomalies:", self.anomalies.len())?;
+        for anomaly in &self.anomalies {
+            writeln!(f, "  {anomaly}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// reads an audit file and looks for gaps and messages in the wrong order
+pub fn analyze(path: &Path) -> anyhow::Result<Report> {
+    let file = BufReader::new(File::open(path)?);
+
+    let mut report = Report::default();
+    let mut last: Option<(u64, u64)> = None;
+    // the sources that have sent their init
+    let mut initialized: HashSet<String> = HashSet::new();
+
+    for (index, line) in file.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry: Entry = match serde_json::from_str(&line) {
+            Ok(entry) => entry,
+            Err(err) => {
+                report.anomalies.push(Anomaly::Unreadable {
+                    line: index + 1,
+                    error: err.to_string(),
+                });
+                continue;
+            }
+        };
+
+        report.messages += 1;
+        *report.sources.entry(entry.source.clone()).or_insert(0) += 1;
+
+        if let Some((seq, elapsed)) = last {
+            if entry.seq > seq + 1 {
+                report.anomalies.push(Anomaly::Gap {
+                    after: seq,
+                    missing: entry.seq - seq - 1,
+                });
+            } else if entry.seq <= seq {
+                report.anomalies.push(Anomaly::OutOfOrder {
+                    seq: entry.seq,
+                    after: seq,
+                });
+            }
+
+            if entry.elapsed_micros < elapsed {
+                report.anomalies.push(Anomaly::TimeWentBackwards {
+                    seq: entry.seq,
+                    by_micros: elapsed - entry.elapsed_micros,
+                });
+            }
+        }
+        last = Some((entry.seq, entry.elapsed_micros));
+
+        match entry.kind.as_str() {
+            "init" => {
+                initialized.insert(entry.source);
+            }
+            "update" if !initialized.contains(&entry.source) => {
+                report.anomalies.push(Anomaly::BeforeInit {
+                    seq: entry.seq,
+                    source: entry.source,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    return Ok(report);
+}
@@ -0,0 +1,203 @@
+//! the default output's volume for the bar, clicking it opens a popup for
+//! switching the default devices and setting each device's volume
+
+use super::Message as BuiltinMessage;
+
+use crate::app::{AppMessage, SubscriptionRequest};
+use crate::config::BarWidget;
+use crate::services::ServiceState;
+use crate::services::audio::{self, AudioState};
+use crate::theme::{self, Base16Color};
+
+use iced::alignment::Vertical;
+use iced::widget::{Column, button, column, container, row, scrollable, slider, text};
+use iced::{Element, Length, Task};
+use pulse::volume::ChannelVolumes;
+
+/// width and height of the device popup
+pub const POPUP_SIZE: (u32, u32) = (320, 360);
+
+#[derive(Debug)]
+pub struct AudioWidget {
+    state: AudioState,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// an event from the audio service
+    Service(audio::Event),
+}
+
+/// whether a device is an output or an input
+#[derive(Debug, Clone, Copy)]
+enum DeviceKind {
+    Sink,
+    Source,
+}
+
+/// the parts of a sink or source shown in the popup
+struct Device<'a> {
+    kind: DeviceKind,
+    name: &'a str,
+    description: &'a str,
+    volume: ChannelVolumes,
+    mute: bool,
+    is_default: bool,
+}
+
+impl AudioWidget {
+    pub fn new() -> Self {
+        Self {
+            state: AudioState::init(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<AppMessage> {
+        match message {
+            Message::Service(event) => {
+                self.state.update(event);
+            }
+        }
+
+        return Task::none();
+    }
+
+    /// the default output's volume, shown in the bar
+    pub fn view<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let label = match self.state.get_default_sink() {
+            Some(sink) if self.state.unavailable.is_none() => format!(
+                "{} {}%",
+                mute_icon(sink.mute),
+                AudioState::volume_percent(sink.volume)
+            ),
+            _ => format!("{} --", mute_icon(true)),
+        };
+
+        button(text(label).style(theme::text_style(theme)).size(11))
+            .padding([2, 6])
+            .on_press(AppMessage::Builtin(BuiltinMessage::TogglePopup(
+                BarWidget::Audio,
+            )))
+            .style(theme::bar_button_style(theme))
+            .into()
+    }
+
+    /// every sink and source with their volume
+    pub fn popup<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        if let Some(reason) = &self.state.unavailable {
+            return text(format!("audio unavailable: {reason}"))
+                .style(theme::text_style(theme))
+                .size(11)
+                .into();
+        }
+
+        let sinks = self.state.sinks.iter().map(|sink| Device {
+            kind: DeviceKind::Sink,
+            name: &sink.name,
+            description: &sink.description,
+            volume: sink.volume,
+            mute: sink.mute,
+            is_default: self.state.default_sink.as_ref() == Some(&sink.name),
+        });
+        let sources = self.state.sources.iter().map(|source| Device {
+            kind: DeviceKind::Source,
+            name: &source.name,
+            description: &source.description,
+            volume: source.volume,
+            mute: source.mute,
+            is_default: self.state.default_source.as_ref() == Some(&source.name),
+        });
+
+        scrollable(
+            column![
+                text("Output").style(theme::text_style(theme)).size(11),
+                Column::with_children(sinks.map(|sink| device(sink, theme))).spacing(8),
+                text("Input").style(theme::text_style(theme)).size(11),
+                Column::with_children(sources.map(|source| device(source, theme))).spacing(8),
+            ]
+            .spacing(8),
+        )
+        .into()
+    }
+}
+
+/// a device's name, which sets it as the default when pressed, and its
+/// volume
+fn device<'a>(device: Device<'a>, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+    let name = device.name.to_string();
+    let volume = device.volume;
+
+    let set_default = match device.kind {
+        DeviceKind::Sink => audio::Request::SetDefaultSink { name: name.clone() },
+        DeviceKind::Source => audio::Request::SetDefaultSource { name: name.clone() },
+    };
+
+    let name_style = match device.is_default {
+        true => theme::selected_button_style(theme),
+        false => theme::bar_button_style(theme),
+    };
+
+    let mut mute =
+        button(text(mute_icon(device.mute)).size(11)).style(theme::volume_button_style(theme));
+    // there's no request for muting sources yet
+    if let DeviceKind::Sink = device.kind {
+        mute = mute.on_press(request(audio::Request::SetSinkMute {
+            name: name.clone(),
+            state: !device.mute,
+        }));
+    }
+
+    column![
+        button(text(device.description).size(11))
+            .width(Length::Fill)
+            .padding([2, 6])
+            .on_press(request(set_default))
+            .style(name_style),
+        row![
+            mute,
+            text(format!("{}%", AudioState::volume_percent(volume)))
+                .style(theme::text_style(theme))
+                .size(11)
+                .width(Length::Fixed(36.0)),
+            container(
+                slider(
+                    0.0..=100.0,
+                    AudioState::volume_percent(volume),
+                    move |percent| {
+                        let volume = AudioState::set_channel_volume(volume, percent);
+                        request(match device.kind {
+                            DeviceKind::Sink => audio::Request::SetSinkVolume {
+                                name: name.clone(),
+                                volume,
+                            },
+                            DeviceKind::Source => audio::Request::SetSourceVolume {
+                                name: name.clone(),
+                                volume,
+                            },
+                        })
+                    }
+                )
+                .style(theme::slider_style(theme))
+                .step(5.0)
+                .shift_step(1.0)
+            )
+            .height(6)
+            .style(theme::slider_rail_style(theme)),
+        ]
+        .spacing(8)
+        .align_y(Vertical::Center),
+    ]
+    .spacing(4)
+    .into()
+}
+
+fn request(request: audio::Request) -> AppMessage {
+    AppMessage::Request(SubscriptionRequest::Audio(request))
+}
+
+fn mute_icon(mute: bool) -> &'static str {
+    match mute {
+        true => "",
+        false => "",
+    }
+}
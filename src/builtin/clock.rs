@@ -0,0 +1,250 @@
+//! a clock for the bar, clicking it opens a calendar of the month with week
+//! numbers
+
+use super::Message as BuiltinMessage;
+
+use crate::app::AppMessage;
+use crate::config::{BarWidget, ClockConfig};
+use crate::diagnostics::{self, Diagnostic};
+use crate::theme::{self, Base16Color};
+
+use std::env;
+use std::fmt::{self, Write as _};
+use std::path::Path;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Days, Local, Locale, Months, NaiveDate};
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::{Column, Row, button, column, container, row, text};
+use iced::{Element, Length, Task};
+
+/// width and height of the calendar popup
+pub const POPUP_SIZE: (u32, u32) = (260, 230);
+
+/// width of each cell in the calendar
+const CELL_WIDTH: f32 = 28.0;
+
+#[derive(Debug)]
+pub struct Clock {
+    format: String,
+    locale: Locale,
+    now: DateTime<Local>,
+    /// the first day of the month shown in the calendar
+    shown_month: NaiveDate,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// updates the time
+    Tick,
+    PreviousMonth,
+    NextMonth,
+    /// shows the current month in the calendar
+    CurrentMonth,
+}
+
+impl Clock {
+    pub fn new(config: &ClockConfig) -> Self {
+        // chrono can't format invalid specifiers, so check them up front
+        let format = if StrftimeItems::new(&config.format).any(|item| item == Item::Error) {
+            let default_format = ClockConfig::default().format;
+            diagnostics::push(
+                Diagnostic::new(
+                    Path::new("config.toml"),
+                    format!("`{}` is not a valid clock format", config.format),
+                )
+                .with_help(Some(format!(
+                    "see https://docs.rs/chrono/latest/chrono/format/strftime/index.html, using \
+                     `{default_format}` for now"
+                ))),
+            );
+            default_format
+        } else {
+            config.format.clone()
+        };
+
+        let now = Local::now();
+
+        Self {
+            format,
+            locale: locale(config.locale.as_deref()),
+            now,
+            shown_month: first_of_month(now.date_naive()),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<AppMessage> {
+        match message {
+            Message::Tick => {
+                self.now = Local::now();
+            }
+            Message::PreviousMonth => {
+                if let Some(month) = self.shown_month.checked_sub_months(Months::new(1)) {
+                    self.shown_month = month;
+                }
+            }
+            Message::NextMonth => {
+                if let Some(month) = self.shown_month.checked_add_months(Months::new(1)) {
+                    self.shown_month = month;
+                }
+            }
+            Message::CurrentMonth => self.show_current_month(),
+        }
+
+        return Task::none();
+    }
+
+    pub fn show_current_month(&mut self) {
+        self.shown_month = first_of_month(self.now.date_naive());
+    }
+
+    /// the time, shown in the bar
+    pub fn view<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        button(
+            text(display(
+                self.now.format_localized(&self.format, self.locale),
+            ))
+            .style(theme::text_style(theme))
+            .size(11),
+        )
+        .padding([2, 6])
+        .on_press(AppMessage::Builtin(BuiltinMessage::TogglePopup(
+            BarWidget::Clock,
+        )))
+        .style(theme::bar_button_style(theme))
+        .into()
+    }
+
+    /// the calendar of `shown_month`, with today highlighted
+    pub fn popup<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let nav_button = |label: &'static str, message: Message| {
+            button(text(label).style(theme::text_style(theme)).size(11))
+                .padding([2, 6])
+                .on_press(AppMessage::Builtin(BuiltinMessage::Clock(message)))
+                .style(theme::bar_button_style(theme))
+        };
+
+        let title = button(
+            text(display(
+                self.shown_month.format_localized("%B %Y", self.locale),
+            ))
+            .style(theme::text_style(theme))
+            .size(11),
+        )
+        .padding([2, 6])
+        .on_press(AppMessage::Builtin(BuiltinMessage::Clock(
+            Message::CurrentMonth,
+        )))
+        .style(theme::bar_button_style(theme));
+
+        let header = row![
+            nav_button("<", Message::PreviousMonth),
+            container(title)
+                .width(Length::Fill)
+                .align_x(Horizontal::Center),
+            nav_button(">", Message::NextMonth),
+        ]
+        .align_y(Vertical::Center);
+
+        let today = self.now.date_naive();
+        // iso weeks start on monday
+        let first_day =
+            self.shown_month - Days::new(self.shown_month.weekday().num_days_from_monday() as u64);
+
+        let mut weekdays = vec![cell("wk", theme::calendar_week_style(theme))];
+        for day in first_day.iter_days().take(7) {
+            weekdays.push(cell(
+                display(day.format_localized("%a", self.locale)),
+                theme::calendar_week_style(theme),
+            ));
+        }
+
+        let last_day = self
+            .shown_month
+            .checked_add_months(Months::new(1))
+            .and_then(|next_month| next_month.pred_opt())
+            .unwrap_or(self.shown_month);
+
+        let mut weeks = vec![Row::with_children(weekdays).into()];
+        for week_start in first_day
+            .iter_weeks()
+            .take_while(|week_start| *week_start <= last_day)
+        {
+            let mut days = vec![cell(
+                week_start.iso_week().week().to_string(),
+                theme::calendar_week_style(theme),
+            )];
+
+            for day in week_start.iter_days().take(7) {
+                let style = if day == today {
+                    theme::calendar_today_style(theme)
+                } else if day.month() != self.shown_month.month() {
+                    theme::calendar_other_month_style(theme)
+                } else {
+                    theme::calendar_day_style(theme)
+                };
+
+                days.push(cell(day.day().to_string(), style));
+            }
+
+            weeks.push(Row::with_children(days).into());
+        }
+
+        column![header, Column::with_children(weeks).spacing(2)]
+            .spacing(6)
+            .into()
+    }
+}
+
+fn cell<'a>(
+    content: impl text::IntoFragment<'a>,
+    style: container::StyleFn<'a, iced::Theme>,
+) -> Element<'a, AppMessage> {
+    container(text(content).size(11))
+        .width(CELL_WIDTH)
+        .align_x(Horizontal::Center)
+        .style(style)
+        .into()
+}
+
+/// writes a formatted date to a string, logging instead of panicking if the
+/// format is invalid
+pub(super) fn display(formatted: impl fmt::Display) -> String {
+    let mut string = String::new();
+
+    if write!(string, "{formatted}").is_err() {
+        log::warn!("[builtin:clock] could not format date");
+    }
+
+    return string;
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// the configured locale, or the one from the environment
+pub(crate) fn locale(configured: Option<&str>) -> Locale {
+    let name = match configured {
+        Some(name) => Some(name.to_string()),
+        None => ["LC_ALL", "LC_TIME", "LANG"]
+            .iter()
+            .find_map(|var| env::var(var).ok().filter(|value| !value.is_empty())),
+    };
+
+    let name = match name {
+        Some(name) => name,
+        None => return Locale::POSIX,
+    };
+
+    // `en_GB.UTF-8` and `de_DE@euro` are named `en_GB` and `de_DE` by chrono
+    let stripped = name.split(['.', '@']).next().unwrap_or_default();
+
+    match Locale::try_from(stripped) {
+        Ok(locale) => locale,
+        Err(_) => {
+            log::warn!("[builtin:clock] unknown locale `{name}`, using POSIX");
+            Locale::POSIX
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! a panel for module developers showing what every loaded module is doing,
+//! like top but for modules. it's toggled with `aurorashell modules inspect`
+//! or opened from the start with `--inspector`
+//!
+//! the app hands it the wasm runtime's state once a second, see `refresh`.
+//! the counters in `ModuleStats` only go up, so the rates shown are how much
+//! they went up since the last refresh
+
+use super::Message as BuiltinMessage;
+
+use crate::app::AppMessage;
+use crate::runtime::wasm::{ModuleStats, WasmState};
+use crate::theme::{self, Base16Color};
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use iced::alignment::Vertical;
+use iced::widget::{Column, button, column, container, row, scrollable, text};
+use iced::{Element, Length};
+
+/// width and height of the panel
+pub const SIZE: (u32, u32) = (640, 360);
+
+/// how often the panel is refreshed while it's open
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// set by `--inspector`
+static OPEN_AT_STARTUP: AtomicBool = AtomicBool::new(false);
+
+/// opens the inspector as soon as the shell starts
+pub fn open_at_startup() {
+    OPEN_AT_STARTUP.store(true, Ordering::Relaxed);
+}
+
+pub(super) fn opens_at_startup() -> bool {
+    OPEN_AT_STARTUP.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Default)]
+pub struct Inspector {
+    modules: Vec<ModuleRow>,
+    /// each module's stats as of the last refresh, by module id
+    previous: HashMap<u32, ModuleStats>,
+    refreshed_at: Option<Instant>,
+}
+
+/// what's shown for one module
+#[derive(Debug)]
+struct ModuleRow {
+    id: u32,
+    name: String,
+    disabled: Option<String>,
+    /// the amount of surfaces the module has rendered a ui to
+    surfaces: usize,
+    services: Vec<&'static str>,
+    stats: ModuleStats,
+    renders_per_sec: f32,
+    events_per_sec: f32,
+    error_count: u64,
+    last_error: Option<String>,
+}
+
+impl Inspector {
+    /// reads the modules out of the runtime's state again, `None` when the
+    /// runtime hasn't started
+    pub fn refresh(&mut self, wasm: Option<&WasmState>) {
+        let now = Instant::now();
+        let elapsed = self
+            .refreshed_at
+            .map(|refreshed_at| (now - refreshed_at).as_secs_f32())
+            .filter(|elapsed| *elapsed > 0.0);
+        self.refreshed_at = Some(now);
+
+        let Some(wasm) = wasm else {
+            self.modules.clear();
+            self.previous.clear();
+            return;
+        };
+
+        let rate = |now: u64, before: Option<u64>| -> f32 {
+            match (before, elapsed) {
+                (Some(before), Some(elapsed)) => now.saturating_sub(before) as f32 / elapsed,
+                _ => 0.0,
+            }
+        };
+
+        self.modules = wasm
+            .modules
+            .iter()
+            .map(|(id, module)| {
+                let stats = wasm.module_stats.get(id).copied().unwrap_or_default();
+                let previous = self.previous.get(id);
+                let errors = wasm.module_errors.get(id);
+
+                ModuleRow {
+                    id: *id,
+                    name: module.module_name.clone(),
+                    disabled: module.disabled.clone(),
+                    surfaces: wasm
+                        .module_ui_trees
+                        .get(id)
+                        .map_or(0, |snapshot| snapshot.trees.len()),
+                    services: module.services.clone(),
+                    stats,
+                    renders_per_sec: rate(stats.renders, previous.map(|stats| stats.renders)),
+                    events_per_sec: rate(stats.events, previous.map(|stats| stats.events)),
+                    error_count: errors.map_or(0, |errors| errors.count),
+                    last_error: errors
+                        .and_then(|errors| errors.recent.back())
+                        .map(|error| format!("[{}] {error}", error.kind())),
+                }
+            })
+            .collect();
+        self.modules.sort_by_key(|module| module.id);
+
+        self.previous = self
+            .modules
+            .iter()
+            .map(|module| (module.id, module.stats))
+            .collect();
+    }
+
+    pub fn view<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let header = row![
+            text(format!("{} modules", self.modules.len()))
+                .style(theme::text_style(theme))
+                .size(11)
+                .width(Length::Fill),
+            button(text("close").size(11))
+                .style(theme::bar_button_style(theme))
+                .on_press(AppMessage::Builtin(BuiltinMessage::CloseInspector)),
+        ]
+        .align_y(Vertical::Center);
+
+        let columns = row![
+            cell("id", 32.0, theme),
+            cell("module", 140.0, theme),
+            cell("surfaces", 60.0, theme),
+            cell("renders/s", 70.0, theme),
+            cell("events/s", 70.0, theme),
+            cell("last render", 110.0, theme),
+            cell("errors", 50.0, theme),
+        ];
+
+        let now = Instant::now();
+        let modules = self.modules.iter().map(|module| module.view(now, theme));
+
+        column![
+            header,
+            columns,
+            scrollable(Column::with_children(modules).spacing(8)).height(Length::Fill),
+        ]
+        .spacing(8)
+        .into()
+    }
+}
+
+impl ModuleRow {
+    fn view<'a>(&'a self, now: Instant, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let last_render = match self.stats.rendered_at {
+            Some(rendered_at) => format!(
+                "{}s ago, {:.1}ms",
+                (now - rendered_at).as_secs(),
+                self.stats.render_time.as_secs_f32() * 1000.0
+            ),
+            None => "never".to_string(),
+        };
+
+        let name = match &self.disabled {
+            Some(_) => format!("{} (disabled)", self.name),
+            None => self.name.clone(),
+        };
+
+        let services = match self.services.is_empty() {
+            true => "no services".to_string(),
+            false => self.services.join(", "),
+        };
+        let mut details = format!(
+            "{services}, {} renders, {} events, {} dropped",
+            self.stats.renders, self.stats.events, self.stats.dropped_events
+        );
+        if let Some(reason) = &self.disabled {
+            details.push_str(&format!("\ndisabled: {reason}"));
+        }
+        if let Some(error) = &self.last_error {
+            details.push_str(&format!("\nlast error: {error}"));
+        }
+
+        column![
+            row![
+                cell(self.id.to_string(), 32.0, theme),
+                cell(name, 140.0, theme),
+                cell(self.surfaces.to_string(), 60.0, theme),
+                cell(format!("{:.1}", self.renders_per_sec), 70.0, theme),
+                cell(format!("{:.1}", self.events_per_sec), 70.0, theme),
+                cell(last_render, 110.0, theme),
+                cell(self.error_count.to_string(), 50.0, theme),
+            ],
+            container(text(details).style(theme::separator_style(theme)).size(10)).padding([0, 32]),
+        ]
+        .spacing(2)
+        .into()
+    }
+}
+
+fn cell<'a>(
+    label: impl text::IntoFragment<'a>,
+    width: f32,
+    theme: &'a Base16Color,
+) -> Element<'a, AppMessage> {
+    text(label)
+        .style(theme::text_style(theme))
+        .size(11)
+        .width(Length::Fixed(width))
+        .into()
+}
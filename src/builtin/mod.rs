@@ -0,0 +1,679 @@
+//! widgets drawn by the shell itself instead of by a module, so a usable bar
+//! can be set up from the config alone
+//!
+//! the bar is a layer surface with the widgets listed in `[bar]`, widgets can
+//! open a popup which is a layer surface of its own next to the bar. popups
+//! can also be toggled over ipc with `aurorashell toggle <widget>`, which
+//! works without the bar
+//!
+//! `aurorashell reload` re-reads `[bar]` and `[widgets]`, if the bar moved
+//! it's swapped onto a new surface, see `surface`
+//!
+//! the quick settings panel is a popup like any other, but on touchscreens it
+//! can also be swiped down from the top edge of the screen and swiped back up
+//!
+//! modules can ask for a region of the screen to be picked, which opens an
+//! overlay of its own, see `region`
+//!
+//! module developers can open a panel showing what each module is doing,
+//! see `inspector`
+
+pub mod audio;
+pub mod clock;
+pub mod inspector;
+pub mod quick_settings;
+pub mod region;
+pub mod surface;
+pub mod weather;
+
+use surface::{Surface, SurfaceRegistry};
+
+use crate::app::AppMessage;
+use crate::config::{BarConfig, BarItem, BarPosition, BarWidget, Config};
+use crate::runtime::wasm::WasmState;
+use crate::theme::{self, Base16Color};
+
+use std::time::Duration;
+
+use iced::alignment::{Horizontal, Vertical};
+use iced::keyboard::{self, key};
+use iced::platform_specific::shell::commands::layer_surface::{
+    Anchor, KeyboardInteractivity, Layer,
+};
+use iced::runtime::platform_specific::wayland::layer_surface::{
+    IcedMargin, IcedOutput, SctkLayerSurfaceSettings,
+};
+use iced::widget::{Row, container, row, text};
+use iced::window::Id;
+use iced::{Element, Event, Length, Subscription, Task, event, mouse, touch};
+
+/// the gap between the bar and a popup
+const POPUP_MARGIN: i32 = 4;
+
+/// the height of the strip that catches swipes from the top edge, in logical
+/// pixels
+const SWIPE_EDGE_HEIGHT: u32 = 4;
+/// how far a finger has to move for a swipe to open or close the quick
+/// settings panel
+const SWIPE_DISTANCE: f32 = 40.0;
+
+#[derive(Debug)]
+pub struct Builtins {
+    config: BarConfig,
+    /// the bar's surface and the surfaces of open popups
+    surfaces: SurfaceRegistry,
+
+    clock: clock::Clock,
+    weather: weather::WeatherWidget,
+    audio: audio::AudioWidget,
+    quick_settings: quick_settings::QuickSettings,
+
+    /// the finger that went down on the swipe edge or the quick settings
+    /// panel, and where it went down
+    swipe: Option<Swipe>,
+    /// the region being picked, while its overlay is open
+    region: Option<region::RegionSelect>,
+    /// the module inspector, while it's open
+    inspector: Option<inspector::Inspector>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Swipe {
+    surface: Surface,
+    finger: touch::Finger,
+    start_y: f32,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Clock(clock::Message),
+    Weather(weather::Message),
+    Audio(audio::Message),
+    QuickSettings(quick_settings::Message),
+    /// opens the widget's popup, or closes it if it's already open
+    TogglePopup(BarWidget),
+    OpenPopup(BarWidget),
+    ClosePopup(BarWidget),
+    /// a surface has drawn a frame, or it took too long to
+    SurfacePresented(Id),
+    /// a finger touched, moved on or left one of the surfaces
+    Touch(Id, touch::Event),
+    /// the pointer, a finger or a key did something on a surface while a
+    /// region is being picked
+    Region(Id, region::Message),
+    /// the inspector is due a refresh, which the app does since it has the
+    /// runtime's state, see `Builtins::refresh_inspector`
+    RefreshInspector,
+    CloseInspector,
+}
+
+/// which part of the bar a widget is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Left,
+    Center,
+    Right,
+}
+
+impl Builtins {
+    /// sets up the widgets and creates the bar's surface if it's enabled
+    pub fn new(config: &Config) -> (Self, Task<AppMessage>) {
+        let mut builtins = Self {
+            config: config.bar.clone(),
+            surfaces: SurfaceRegistry::default(),
+            clock: clock::Clock::new(&config.widgets.clock),
+            weather: weather::WeatherWidget::new(config),
+            audio: audio::AudioWidget::new(),
+            quick_settings: quick_settings::QuickSettings::new(&config.widgets.quick_settings),
+            swipe: None,
+            region: None,
+            inspector: None,
+        };
+
+        let mut tasks = vec![];
+
+        if builtins.config.enabled {
+            tasks.push(
+                builtins
+                    .surfaces
+                    .create(Surface::Bar, bar_settings(&builtins.config)),
+            );
+        }
+        if config.widgets.quick_settings.swipe {
+            tasks.push(
+                builtins
+                    .surfaces
+                    .create(Surface::SwipeEdge, swipe_edge_settings()),
+            );
+        }
+        if inspector::opens_at_startup() {
+            tasks.push(builtins.toggle_inspector(None));
+        }
+
+        return (builtins, Task::batch(tasks));
+    }
+
+    /// applies a reloaded config
+    ///
+    /// the bar and open popups are moved to new surfaces if their placement
+    /// changed, anything else only needs a redraw
+    pub fn reconfigure(&mut self, config: &Config) -> Task<AppMessage> {
+        let previous = std::mem::replace(&mut self.config, config.bar.clone());
+        self.clock = clock::Clock::new(&config.widgets.clock);
+        self.quick_settings
+            .reconfigure(&config.widgets.quick_settings);
+
+        let mut tasks = vec![];
+
+        let swipe = self.surfaces.get(Surface::SwipeEdge).is_some();
+        match config.widgets.quick_settings.swipe {
+            true if !swipe => tasks.push(
+                self.surfaces
+                    .create(Surface::SwipeEdge, swipe_edge_settings()),
+            ),
+            false if swipe => tasks.push(self.surfaces.destroy(Surface::SwipeEdge)),
+            _ => {}
+        }
+
+        if !self.config.enabled {
+            tasks.push(self.surfaces.destroy(Surface::Bar));
+        } else if !previous.enabled
+            || previous.position != self.config.position
+            || previous.height != self.config.height
+        {
+            tasks.push(
+                self.surfaces
+                    .replace(Surface::Bar, bar_settings(&self.config)),
+            );
+        }
+
+        for widget in [
+            BarWidget::Clock,
+            BarWidget::Weather,
+            BarWidget::Audio,
+            BarWidget::QuickSettings,
+        ] {
+            let popup = Surface::Popup(widget);
+
+            if self.surfaces.get(popup).is_some()
+                && popup_anchor(&previous, widget) != popup_anchor(&self.config, widget)
+            {
+                tasks.push(
+                    self.surfaces
+                        .replace(popup, popup_settings(&self.config, widget)),
+                );
+            }
+        }
+
+        return Task::batch(tasks);
+    }
+
+    /// true if the surface is the bar or one of its popups
+    pub fn owns(&self, id: Id) -> bool {
+        self.surfaces.shown(id).is_some()
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<AppMessage> {
+        match message {
+            Message::Clock(message) => self.clock.update(message),
+            Message::Weather(message) => self.weather.update(message),
+            Message::Audio(message) => self.audio.update(message),
+            Message::QuickSettings(message) => self.quick_settings.update(message),
+            Message::TogglePopup(widget) => match self.popup_id(widget) {
+                Some(_) => self.close_popup(widget),
+                None => self.open_popup(widget),
+            },
+            Message::OpenPopup(widget) => self.open_popup(widget),
+            Message::ClosePopup(widget) => self.close_popup(widget),
+            Message::SurfacePresented(id) => self.surfaces.presented(id),
+            Message::Touch(id, event) => self.touch(id, event),
+            Message::Region(id, message) => self.region(id, message),
+            Message::RefreshInspector => Task::none(),
+            Message::CloseInspector => {
+                self.inspector = None;
+                self.surfaces.destroy(Surface::Inspector)
+            }
+        }
+    }
+
+    /// opens the module inspector, or closes it if it's open
+    pub fn toggle_inspector(&mut self, wasm: Option<&WasmState>) -> Task<AppMessage> {
+        if self.inspector.is_some() {
+            return self.update(Message::CloseInspector);
+        }
+
+        let mut inspector = inspector::Inspector::default();
+        inspector.refresh(wasm);
+        self.inspector = Some(inspector);
+
+        return self
+            .surfaces
+            .create(Surface::Inspector, inspector_settings());
+    }
+
+    /// reads the modules shown in the inspector out of the runtime's state
+    /// again, if it's open
+    pub fn refresh_inspector(&mut self, wasm: Option<&WasmState>) {
+        if let Some(inspector) = &mut self.inspector {
+            inspector.refresh(wasm);
+        }
+    }
+
+    /// opens the overlay to pick a region of `output` with, the region is
+    /// sent back to the module as `AppMessage::RegionSelected`
+    ///
+    /// the module gets `None` straight away if a region is already being
+    /// picked
+    pub fn select_region(&mut self, module_id: u32, output: IcedOutput) -> Task<AppMessage> {
+        if self.region.is_some() {
+            log::debug!(
+                "[builtin:region] module {module_id} asked for a region while one is being picked"
+            );
+            return Task::done(AppMessage::RegionSelected {
+                module_id,
+                region: None,
+            });
+        }
+
+        self.region = Some(region::RegionSelect::new(module_id));
+        return self
+            .surfaces
+            .create(Surface::RegionSelect, region_settings(output));
+    }
+
+    pub fn view<'a>(&'a self, id: Id, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        if let Some(Surface::RegionSelect) = self.surfaces.shown(id) {
+            return match &self.region {
+                Some(region) => region.view(theme),
+                None => row![].into(),
+            };
+        }
+
+        if let Some(Surface::Inspector) = self.surfaces.shown(id) {
+            let inspector = match &self.inspector {
+                Some(inspector) => inspector.view(theme),
+                None => row![].into(),
+            };
+
+            return container(inspector)
+                .padding(8)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(theme::popup_style(theme))
+                .into();
+        }
+
+        if let Some(Surface::SwipeEdge) = self.surfaces.shown(id) {
+            // nothing is drawn, the surface is only there for its touches
+            return container(row![])
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into();
+        }
+
+        if let Some(Surface::Popup(widget)) = self.surfaces.shown(id) {
+            let popup = match widget {
+                BarWidget::Clock => self.clock.popup(theme),
+                BarWidget::Weather => self.weather.popup(theme),
+                BarWidget::Audio => self.audio.popup(theme),
+                BarWidget::QuickSettings => self.quick_settings.popup(theme),
+            };
+
+            return container(popup)
+                .padding(8)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(theme::popup_style(theme))
+                .into();
+        }
+
+        let section = |items: &'a [BarItem]| -> Row<'a, AppMessage> {
+            let mut children = vec![];
+
+            for (i, item) in items.iter().enumerate() {
+                if i != 0 && !self.config.separator.is_empty() {
+                    children.push(
+                        text(self.config.separator.as_str())
+                            .style(theme::separator_style(theme))
+                            .size(11)
+                            .into(),
+                    );
+                }
+
+                children.push(self.item(item, theme));
+            }
+
+            Row::with_children(children)
+                .spacing(4)
+                .align_y(Vertical::Center)
+        };
+
+        container(row![
+            container(section(&self.config.left))
+                .width(Length::Fill)
+                .align_x(Horizontal::Left),
+            container(section(&self.config.center)),
+            container(section(&self.config.right))
+                .width(Length::Fill)
+                .align_x(Horizontal::Right),
+        ])
+        .padding([0, 4])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_y(Vertical::Center)
+        .style(theme::bar_style(theme))
+        .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![self.surfaces.subscription()];
+
+        if self.config.enabled {
+            subscriptions.push(
+                iced::time::every(Duration::from_secs(1))
+                    .map(|_| Message::Clock(clock::Message::Tick)),
+            );
+        }
+
+        if self.surfaces.get(Surface::SwipeEdge).is_some()
+            || self.popup_id(BarWidget::QuickSettings).is_some()
+        {
+            subscriptions.push(event::listen_with(|event, _status, id| match event {
+                Event::Touch(event) => Some(Message::Touch(id, event)),
+                _ => None,
+            }));
+        }
+
+        if self.inspector.is_some() {
+            subscriptions.push(
+                iced::time::every(inspector::REFRESH_INTERVAL).map(|_| Message::RefreshInspector),
+            );
+        }
+
+        if self.region.is_some() {
+            subscriptions.push(event::listen_with(|event, _status, id| {
+                let message = match event {
+                    Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                        region::Message::Moved(position)
+                    }
+                    Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                        region::Message::Pressed(None)
+                    }
+                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                        region::Message::Released
+                    }
+                    Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
+                        region::Message::Cancelled
+                    }
+                    Event::Touch(touch::Event::FingerPressed { position, .. }) => {
+                        region::Message::Pressed(Some(position))
+                    }
+                    Event::Touch(touch::Event::FingerMoved { position, .. }) => {
+                        region::Message::Moved(position)
+                    }
+                    Event::Touch(touch::Event::FingerLifted { .. }) => region::Message::Released,
+                    Event::Touch(touch::Event::FingerLost { .. }) => region::Message::Cancelled,
+                    Event::Keyboard(keyboard::Event::KeyPressed {
+                        key: keyboard::Key::Named(key::Named::Escape),
+                        ..
+                    }) => region::Message::Cancelled,
+                    _ => return None,
+                };
+                Some(Message::Region(id, message))
+            }));
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    fn item<'a>(&'a self, item: &'a BarItem, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        match item {
+            BarItem::Widget(widget) => self.widget(*widget, theme),
+            BarItem::Group { group } => container(
+                Row::with_children(group.iter().map(|widget| self.widget(*widget, theme)))
+                    .align_y(Vertical::Center),
+            )
+            .padding([0, 4])
+            .style(theme::pill_style(theme))
+            .into(),
+        }
+    }
+
+    fn widget<'a>(&'a self, widget: BarWidget, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        match widget {
+            BarWidget::Clock => self.clock.view(theme),
+            BarWidget::Weather => self.weather.view(theme),
+            BarWidget::Audio => self.audio.view(theme),
+            BarWidget::QuickSettings => self.quick_settings.view(theme),
+        }
+    }
+
+    /// the surface of the widget's popup, if it's open
+    fn popup_id(&self, widget: BarWidget) -> Option<Id> {
+        self.surfaces.get(Surface::Popup(widget))
+    }
+
+    fn close_popup(&mut self, widget: BarWidget) -> Task<AppMessage> {
+        self.surfaces.destroy(Surface::Popup(widget))
+    }
+
+    fn open_popup(&mut self, widget: BarWidget) -> Task<AppMessage> {
+        if self.popup_id(widget).is_some() {
+            return Task::none();
+        }
+
+        match widget {
+            BarWidget::Clock => self.clock.show_current_month(),
+            BarWidget::Weather | BarWidget::Audio | BarWidget::QuickSettings => {}
+        }
+
+        return self
+            .surfaces
+            .create(Surface::Popup(widget), popup_settings(&self.config, widget));
+    }
+
+    /// opens the quick settings panel when a finger swipes down from the top
+    /// edge, and closes it when one swipes up on the panel
+    fn touch(&mut self, id: Id, event: touch::Event) -> Task<AppMessage> {
+        let panel = Surface::Popup(BarWidget::QuickSettings);
+
+        match event {
+            touch::Event::FingerPressed {
+                id: finger,
+                position,
+            } => {
+                if let Some(surface) = self.surfaces.shown(id)
+                    && (surface == Surface::SwipeEdge || surface == panel)
+                {
+                    self.swipe = Some(Swipe {
+                        surface,
+                        finger,
+                        start_y: position.y,
+                    });
+                }
+            }
+            touch::Event::FingerMoved {
+                id: finger,
+                position,
+            } => {
+                let Some(swipe) = self.swipe else {
+                    return Task::none();
+                };
+                if swipe.finger != finger {
+                    return Task::none();
+                }
+
+                let moved = position.y - swipe.start_y;
+                if swipe.surface == Surface::SwipeEdge && moved > SWIPE_DISTANCE {
+                    self.swipe = None;
+                    return self.open_popup(BarWidget::QuickSettings);
+                }
+                if swipe.surface == panel && moved < -SWIPE_DISTANCE {
+                    self.swipe = None;
+                    return self.close_popup(BarWidget::QuickSettings);
+                }
+            }
+            touch::Event::FingerLifted { id: finger, .. }
+            | touch::Event::FingerLost { id: finger, .. } => {
+                if self.swipe.is_some_and(|swipe| swipe.finger == finger) {
+                    self.swipe = None;
+                }
+            }
+        }
+
+        return Task::none();
+    }
+
+    /// moves the region being picked along, and closes the overlay once it's
+    /// picked or cancelled
+    fn region(&mut self, id: Id, message: region::Message) -> Task<AppMessage> {
+        if self.surfaces.shown(id) != Some(Surface::RegionSelect) {
+            return Task::none();
+        }
+        let Some(select) = &mut self.region else {
+            return Task::none();
+        };
+        let Some(region) = select.update(message) else {
+            return Task::none();
+        };
+
+        let module_id = select.module_id();
+        self.region = None;
+        log::debug!("[builtin:region] module {module_id} was given {region:?}");
+
+        return Task::batch([
+            self.surfaces.destroy(Surface::RegionSelect),
+            Task::done(AppMessage::RegionSelected { module_id, region }),
+        ]);
+    }
+}
+
+fn bar_settings(config: &BarConfig) -> SctkLayerSurfaceSettings {
+    let anchor = match config.position {
+        BarPosition::Top => Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+        BarPosition::Bottom => Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+    };
+
+    SctkLayerSurfaceSettings {
+        namespace: "aurorashell".to_string(),
+        output: IcedOutput::Active,
+        layer: Layer::Top,
+        anchor,
+        size: Some((None, Some(config.height))),
+        exclusive_zone: config.height as i32,
+        keyboard_interactivity: KeyboardInteractivity::None,
+        ..Default::default()
+    }
+}
+
+/// a strip along the whole top edge, over the bar and any windows
+fn swipe_edge_settings() -> SctkLayerSurfaceSettings {
+    SctkLayerSurfaceSettings {
+        namespace: "aurorashell".to_string(),
+        output: IcedOutput::Active,
+        layer: Layer::Overlay,
+        anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+        size: Some((None, Some(SWIPE_EDGE_HEIGHT))),
+        // -1 puts it at the very edge instead of under the bar
+        exclusive_zone: -1,
+        keyboard_interactivity: KeyboardInteractivity::None,
+        ..Default::default()
+    }
+}
+
+/// covers the whole output, above everything, and takes the keyboard so
+/// escape reaches it
+fn region_settings(output: IcedOutput) -> SctkLayerSurfaceSettings {
+    SctkLayerSurfaceSettings {
+        namespace: "aurorashell".to_string(),
+        output,
+        layer: Layer::Overlay,
+        anchor: Anchor::TOP | Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+        size: Some((None, None)),
+        exclusive_zone: -1,
+        keyboard_interactivity: KeyboardInteractivity::Exclusive,
+        ..Default::default()
+    }
+}
+
+/// a panel in the top right corner, above windows so it can be read over
+/// whatever a module is drawing
+fn inspector_settings() -> SctkLayerSurfaceSettings {
+    let (width, height) = inspector::SIZE;
+
+    SctkLayerSurfaceSettings {
+        namespace: "aurorashell".to_string(),
+        output: IcedOutput::Active,
+        layer: Layer::Overlay,
+        anchor: Anchor::TOP | Anchor::RIGHT,
+        size: Some((Some(width), Some(height))),
+        margin: IcedMargin {
+            top: POPUP_MARGIN,
+            right: POPUP_MARGIN,
+            bottom: POPUP_MARGIN,
+            left: POPUP_MARGIN,
+        },
+        exclusive_zone: 0,
+        keyboard_interactivity: KeyboardInteractivity::None,
+        ..Default::default()
+    }
+}
+
+fn popup_settings(config: &BarConfig, widget: BarWidget) -> SctkLayerSurfaceSettings {
+    let (width, height) = match widget {
+        BarWidget::Clock => clock::POPUP_SIZE,
+        BarWidget::Weather => weather::POPUP_SIZE,
+        BarWidget::Audio => audio::POPUP_SIZE,
+        BarWidget::QuickSettings => quick_settings::POPUP_SIZE,
+    };
+
+    // the popup doesn't set an exclusive zone so the compositor places it
+    // next to the bar
+    SctkLayerSurfaceSettings {
+        namespace: "aurorashell".to_string(),
+        output: IcedOutput::Active,
+        layer: Layer::Top,
+        anchor: popup_anchor(config, widget),
+        size: Some((Some(width), Some(height))),
+        margin: IcedMargin {
+            top: POPUP_MARGIN,
+            right: POPUP_MARGIN,
+            bottom: POPUP_MARGIN,
+            left: POPUP_MARGIN,
+        },
+        exclusive_zone: 0,
+        keyboard_interactivity: KeyboardInteractivity::None,
+        ..Default::default()
+    }
+}
+
+/// popups open on the bar's edge of the screen, under the section the widget
+/// is in
+fn popup_anchor(config: &BarConfig, widget: BarWidget) -> Anchor {
+    let mut anchor = match config.position {
+        BarPosition::Top => Anchor::TOP,
+        BarPosition::Bottom => Anchor::BOTTOM,
+    };
+
+    match section(config, widget) {
+        Some(Section::Left) => anchor |= Anchor::LEFT,
+        Some(Section::Right) => anchor |= Anchor::RIGHT,
+        Some(Section::Center) | None => {}
+    }
+
+    return anchor;
+}
+
+fn section(config: &BarConfig, widget: BarWidget) -> Option<Section> {
+    let contains = |items: &[BarItem]| items.iter().any(|item| item.contains(widget));
+
+    if contains(&config.left) {
+        Some(Section::Left)
+    } else if contains(&config.center) {
+        Some(Section::Center)
+    } else if contains(&config.right) {
+        Some(Section::Right)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,303 @@
+//! a pull-down panel of toggles and sliders for tablet style use, opened from
+//! the bar, with `aurorashell toggle quick-settings` or by swiping down from
+//! the top edge of the screen (see `[widgets.quick_settings]`)
+//!
+//! wifi, volume and brightness come from their services. there are no
+//! bluetooth or do not disturb services, so those tiles run the commands set
+//! in the config and remember what they were last set to. kde connect is told
+//! about do not disturb so it stops forwarding notifications from phones
+
+use super::Message as BuiltinMessage;
+
+use crate::app::{AppMessage, SubscriptionRequest};
+use crate::config::{BarWidget, QuickSettingsConfig, QuickSettingsTile};
+use crate::services::ServiceState;
+use crate::services::audio::{self, AudioState};
+use crate::services::brightness::{self, BrightnessState};
+use crate::services::kdeconnect;
+use crate::services::network::{self, NetworkState};
+use crate::theme::{self, Base16Color};
+
+use std::process::{Command, Stdio};
+
+use iced::alignment::Vertical;
+use iced::widget::{Column, Row, button, container, row, slider, text};
+use iced::{Alignment, Element, Length, Task};
+
+/// width and height of the panel
+pub const POPUP_SIZE: (u32, u32) = (320, 160);
+
+#[derive(Debug)]
+pub struct QuickSettings {
+    config: QuickSettingsConfig,
+
+    audio: AudioState,
+    brightness: BrightnessState,
+    network: NetworkState,
+    /// what the bluetooth command was last run with
+    bluetooth: bool,
+    /// what the do not disturb command was last run with
+    dnd: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Audio(audio::Event),
+    Brightness(brightness::Event),
+    Network(network::Event),
+    SetBluetooth(bool),
+    SetDnd(bool),
+}
+
+impl QuickSettings {
+    pub fn new(config: &QuickSettingsConfig) -> Self {
+        Self {
+            config: config.clone(),
+            audio: AudioState::init(),
+            brightness: BrightnessState::init(),
+            network: NetworkState::init(),
+            bluetooth: false,
+            dnd: false,
+        }
+    }
+
+    /// applies a reloaded config, the services' state is kept
+    pub fn reconfigure(&mut self, config: &QuickSettingsConfig) {
+        self.config = config.clone();
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<AppMessage> {
+        match message {
+            Message::Audio(event) => {
+                self.audio.update(event);
+            }
+            Message::Brightness(event) => {
+                self.brightness.update(event);
+            }
+            Message::Network(event) => {
+                self.network.update(event);
+            }
+            Message::SetBluetooth(on) => {
+                if run_toggle_command(&self.config.bluetooth_command, on) {
+                    self.bluetooth = on;
+                }
+            }
+            Message::SetDnd(on) => {
+                if let Some(command) = &self.config.dnd_command
+                    && run_toggle_command(command, on)
+                {
+                    self.dnd = on;
+
+                    return Task::done(AppMessage::Request(SubscriptionRequest::KdeConnect(
+                        kdeconnect::Request::SetDoNotDisturb { enabled: on },
+                    )));
+                }
+            }
+        }
+
+        return Task::none();
+    }
+
+    /// an icon in the bar that opens the panel
+    pub fn view<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        button(text("").style(theme::text_style(theme)).size(11))
+            .padding([2, 6])
+            .on_press(AppMessage::Builtin(BuiltinMessage::TogglePopup(
+                BarWidget::QuickSettings,
+            )))
+            .style(theme::bar_button_style(theme))
+            .into()
+    }
+
+    /// the toggles in a row, then a slider for each of volume and brightness
+    pub fn popup<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let mut toggles = vec![];
+        let mut sliders = vec![];
+
+        for tile in &self.config.tiles {
+            match tile {
+                QuickSettingsTile::Wifi => toggles.push(self.wifi(theme)),
+                QuickSettingsTile::Bluetooth => toggles.push(toggle(
+                    "",
+                    "Bluetooth",
+                    Some(self.bluetooth),
+                    quick_settings(Message::SetBluetooth(!self.bluetooth)),
+                    theme,
+                )),
+                QuickSettingsTile::Dnd => {
+                    // without a command there's nothing to toggle
+                    let state = self.config.dnd_command.as_ref().map(|_| self.dnd);
+                    toggles.push(toggle(
+                        "",
+                        "Do not disturb",
+                        state,
+                        quick_settings(Message::SetDnd(!self.dnd)),
+                        theme,
+                    ));
+                }
+                QuickSettingsTile::Volume => sliders.push(self.volume(theme)),
+                QuickSettingsTile::Brightness => sliders.push(self.brightness(theme)),
+            }
+        }
+
+        Column::new()
+            .push(Row::with_children(toggles).spacing(8))
+            .push(Column::with_children(sliders).spacing(12))
+            .spacing(16)
+            .into()
+    }
+
+    fn wifi<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let enabled = self.network.wifi_enabled;
+        let state = self.network.unavailable.is_none().then_some(enabled);
+
+        toggle(
+            "",
+            "Wi-Fi",
+            state,
+            AppMessage::Request(SubscriptionRequest::Network(
+                network::Request::SetWifiEnabled { enabled: !enabled },
+            )),
+            theme,
+        )
+    }
+
+    fn volume<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let sink = match self.audio.get_default_sink() {
+            Some(sink) if self.audio.unavailable.is_none() => sink,
+            _ => return unavailable("", "audio unavailable", theme),
+        };
+        let name = sink.name.clone();
+        let volume = sink.volume;
+
+        labelled_slider(
+            "",
+            AudioState::volume_percent(volume),
+            move |percent| {
+                AppMessage::Request(SubscriptionRequest::Audio(audio::Request::SetSinkVolume {
+                    name: name.clone(),
+                    volume: AudioState::set_channel_volume(volume, percent),
+                }))
+            },
+            theme,
+        )
+    }
+
+    fn brightness<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let backlight = match self.brightness.backlights.first() {
+            Some(backlight) if self.brightness.unavailable.is_none() => backlight,
+            _ => return unavailable("", "no backlight", theme),
+        };
+        let device = backlight.name.clone();
+
+        labelled_slider(
+            "",
+            backlight.percent() as f32,
+            move |percent| {
+                AppMessage::Request(SubscriptionRequest::Brightness(
+                    brightness::Request::SetBrightness {
+                        device: Some(device.clone()),
+                        percent: percent as u8,
+                    },
+                ))
+            },
+            theme,
+        )
+    }
+}
+
+/// a tile that's highlighted when `state` is on, and can't be pressed when
+/// it's `None`
+fn toggle<'a>(
+    icon: &'a str,
+    label: &'a str,
+    state: Option<bool>,
+    on_press: AppMessage,
+    theme: &'a Base16Color,
+) -> Element<'a, AppMessage> {
+    let style = match state {
+        Some(true) => theme::selected_button_style(theme),
+        _ => theme::bar_button_style(theme),
+    };
+
+    let content = Column::new()
+        .push(text(icon).size(16))
+        .push(text(label).size(10))
+        .spacing(4)
+        .align_x(Alignment::Center);
+
+    button(content)
+        .width(Length::Fill)
+        .padding([8, 4])
+        .on_press_maybe(state.map(|_| on_press))
+        .style(style)
+        .into()
+}
+
+fn labelled_slider<'a>(
+    icon: &'a str,
+    percent: f32,
+    on_change: impl Fn(f32) -> AppMessage + 'a,
+    theme: &'a Base16Color,
+) -> Element<'a, AppMessage> {
+    row![
+        text(icon).style(theme::text_style(theme)).size(11),
+        text(format!("{percent:.0}%"))
+            .style(theme::text_style(theme))
+            .size(11)
+            .width(Length::Fixed(36.0)),
+        container(
+            slider(0.0..=100.0, percent, on_change)
+                .style(theme::slider_style(theme))
+                .step(5.0)
+                .shift_step(1.0)
+        )
+        .height(6)
+        .style(theme::slider_rail_style(theme)),
+    ]
+    .spacing(8)
+    .align_y(Vertical::Center)
+    .into()
+}
+
+fn unavailable<'a>(
+    icon: &'a str,
+    reason: &'a str,
+    theme: &'a Base16Color,
+) -> Element<'a, AppMessage> {
+    row![
+        text(icon).style(theme::text_style(theme)).size(11),
+        text(reason).style(theme::separator_style(theme)).size(11),
+    ]
+    .spacing(8)
+    .into()
+}
+
+fn quick_settings(message: Message) -> AppMessage {
+    AppMessage::Builtin(BuiltinMessage::QuickSettings(message))
+}
+
+/// runs a tile's command with `{state}` replaced by `on` or `off`, returns
+/// whether it could be started
+fn run_toggle_command(command: &str, on: bool) -> bool {
+    let state = match on {
+        true => "on",
+        false => "off",
+    };
+    let command = command.replace("{state}", state);
+
+    let res = Command::new("sh")
+        .args(["-c", &command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    match res {
+        Ok(_) => return true,
+        Err(err) => {
+            log::warn!("[builtin:quick_settings] could not run `{command}`: {err}");
+            return false;
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! a full screen overlay for picking part of the screen, for tools like
+//! screenshots and zooming that need a rectangle from the user
+//!
+//! the screen is dimmed and a rectangle is dragged out with the pointer or a
+//! finger, letting go hands it to whoever asked for it. escape, a right click
+//! or a rectangle without any size cancel it
+//!
+//! only one selection is open at a time, asking for another while one is
+//! open cancels the new one
+
+use crate::app::AppMessage;
+use crate::theme::{self, Base16Color};
+
+use iced::widget::{column, container, row};
+use iced::{Element, Length, Point};
+
+/// a rectangle on the output the overlay was opened on, in logical pixels
+/// from its top left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Message {
+    /// the pointer or a finger moved on the overlay
+    Moved(Point),
+    /// the left button or a finger went down, fingers say where
+    Pressed(Option<Point>),
+    Released,
+    Cancelled,
+}
+
+#[derive(Debug)]
+pub struct RegionSelect {
+    /// the module that asked for the selection
+    module_id: u32,
+    /// where the drag started, `None` until the button goes down
+    start: Option<Point>,
+    /// the last place the pointer was seen
+    cursor: Point,
+}
+
+impl RegionSelect {
+    pub fn new(module_id: u32) -> Self {
+        Self {
+            module_id,
+            start: None,
+            cursor: Point::ORIGIN,
+        }
+    }
+
+    pub fn module_id(&self) -> u32 {
+        self.module_id
+    }
+
+    /// returns the outcome once the selection is done, `Some(None)` when it
+    /// was cancelled
+    pub fn update(&mut self, message: Message) -> Option<Option<Region>> {
+        match message {
+            Message::Moved(position) => self.cursor = position,
+            Message::Pressed(position) => {
+                if let Some(position) = position {
+                    self.cursor = position;
+                }
+                self.start = Some(self.cursor);
+            }
+            Message::Released => {
+                // a release without a press is from the click that asked for
+                // the selection
+                if self.start.is_none() {
+                    return None;
+                }
+
+                let region = self
+                    .region()
+                    .filter(|region| region.width > 0 && region.height > 0);
+                return Some(region);
+            }
+            Message::Cancelled => return Some(None),
+        }
+
+        return None;
+    }
+
+    /// the rectangle between where the drag started and the cursor
+    fn region(&self) -> Option<Region> {
+        let start = self.start?;
+        let (left, right) = (start.x.min(self.cursor.x), start.x.max(self.cursor.x));
+        let (top, bottom) = (start.y.min(self.cursor.y), start.y.max(self.cursor.y));
+
+        Some(Region {
+            x: left.max(0.0).round() as u32,
+            y: top.max(0.0).round() as u32,
+            width: (right - left).round() as u32,
+            height: (bottom - top).round() as u32,
+        })
+    }
+
+    /// dims everything but the rectangle being dragged out
+    ///
+    /// the rectangle is cut out of the dimmed screen with strips above,
+    /// below and on either side of it
+    pub fn view<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let dim = |width: Length, height: Length| -> Element<'a, AppMessage> {
+            container(row![])
+                .width(width)
+                .height(height)
+                .style(theme::region_dim_style(theme))
+                .into()
+        };
+
+        let Some(region) = self.region() else {
+            return dim(Length::Fill, Length::Fill);
+        };
+
+        let (x, y) = (region.x as f32, region.y as f32);
+        let (width, height) = (region.width as f32, region.height as f32);
+
+        column![
+            dim(Length::Fill, Length::Fixed(y)),
+            row![
+                dim(Length::Fixed(x), Length::Fill),
+                container(row![])
+                    .width(width)
+                    .height(Length::Fill)
+                    .style(theme::region_selection_style(theme)),
+                dim(Length::Fill, Length::Fill),
+            ]
+            .height(height),
+            dim(Length::Fill, Length::Fill),
+        ]
+        .into()
+    }
+}
+
+/// the outcome of a selection in the layout modules read it in, little endian
+///
+/// a u8 that's 1 when a region was picked and 0 when the selection was
+/// cancelled, then the x, y, width and height as u32s (all 0 when cancelled)
+pub fn serialize(region: Option<Region>) -> Vec<u8> {
+    let mut bytes = vec![region.is_some() as u8];
+    let region = region.unwrap_or(Region {
+        x: 0,
+        y: 0,
+        width: 0,
+        height: 0,
+    });
+
+    for value in [region.x, region.y, region.width, region.height] {
+        bytes.extend(value.to_le_bytes());
+    }
+
+    return bytes;
+}
@@ -0,0 +1,173 @@
+//! keeps track of the layer surfaces the built-in widgets are drawn on
+//!
+//! a layer surface's anchor, size and layer can't be changed once it's
+//! created, so moving the bar needs a new surface. destroying the old one
+//! first leaves the screen empty until the new one is drawn, which flickers.
+//! instead `replace` creates the new surface and keeps the old one around
+//! until the new one has drawn its first frame
+
+use super::Message;
+
+use crate::app::AppMessage;
+use crate::compositor::{destroy_layer_surface, get_layer_surface};
+use crate::config::BarWidget;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use iced::runtime::platform_specific::wayland::layer_surface::SctkLayerSurfaceSettings;
+use iced::window::{self, Id};
+use iced::{Event, Subscription, Task, event};
+
+/// how long an old surface is kept if the new one never draws, like when the
+/// compositor doesn't map it
+const SWAP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// what a surface shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Surface {
+    Bar,
+    Popup(BarWidget),
+    /// a thin strip along the top edge that catches swipes down, which open
+    /// the quick settings panel
+    SwipeEdge,
+    /// the overlay a region of the screen is picked on, see `region`
+    RegionSelect,
+    /// the panel showing what each module is doing, see `inspector`
+    Inspector,
+}
+
+#[derive(Debug, Default)]
+pub struct SurfaceRegistry {
+    /// the surface each `Surface` is currently shown on
+    current: HashMap<Surface, Id>,
+    /// maps new surfaces to the surface they're replacing, the old surface is
+    /// destroyed once the new one has drawn
+    replacing: HashMap<Id, Id>,
+    /// what every surface shows, including the ones being replaced so they
+    /// keep drawing until they're destroyed
+    shown: HashMap<Id, Surface>,
+}
+
+impl SurfaceRegistry {
+    /// the surface `surface` is currently shown on
+    pub fn get(&self, surface: Surface) -> Option<Id> {
+        self.current.get(&surface).copied()
+    }
+
+    /// what is drawn on the surface `id`
+    pub fn shown(&self, id: Id) -> Option<Surface> {
+        self.shown.get(&id).copied()
+    }
+
+    /// creates a surface with `settings`, the id in `settings` is replaced
+    /// with a new one
+    ///
+    /// if `surface` is already shown it's replaced instead
+    pub fn create(
+        &mut self,
+        surface: Surface,
+        settings: SctkLayerSurfaceSettings,
+    ) -> Task<AppMessage> {
+        if self.current.contains_key(&surface) {
+            return self.replace(surface, settings);
+        }
+
+        let id = self.insert(surface);
+        log::debug!("[builtin:surface] created {surface:?} on {id:?}");
+
+        return get_layer_surface(SctkLayerSurfaceSettings { id, ..settings }, "builtin");
+    }
+
+    /// moves `surface` to a new surface with `settings` without a gap where
+    /// neither is drawn
+    pub fn replace(
+        &mut self,
+        surface: Surface,
+        settings: SctkLayerSurfaceSettings,
+    ) -> Task<AppMessage> {
+        let old = match self.current.get(&surface) {
+            Some(old) => *old,
+            None => return self.create(surface, settings),
+        };
+
+        let id = self.insert(surface);
+        let mut tasks = vec![get_layer_surface(
+            SctkLayerSurfaceSettings { id, ..settings },
+            "builtin",
+        )];
+
+        // if the old surface was itself a replacement that hasn't drawn yet
+        // it was never seen, so it goes now and the one before it waits for
+        // the new surface instead
+        let waiting = match self.replacing.remove(&old) {
+            Some(older) => {
+                self.shown.remove(&old);
+                tasks.push(destroy_layer_surface(old));
+                older
+            }
+            None => old,
+        };
+        self.replacing.insert(id, waiting);
+
+        tasks.push(Task::perform(tokio::time::sleep(SWAP_TIMEOUT), move |_| {
+            AppMessage::Builtin(Message::SurfacePresented(id))
+        }));
+
+        log::debug!("[builtin:surface] replacing {surface:?} on {waiting:?} with {id:?}");
+
+        return Task::batch(tasks);
+    }
+
+    /// destroys `surface`, along with the surface it's replacing if the swap
+    /// hasn't finished
+    pub fn destroy(&mut self, surface: Surface) -> Task<AppMessage> {
+        let id = match self.current.remove(&surface) {
+            Some(id) => id,
+            None => return Task::none(),
+        };
+
+        let mut tasks = vec![destroy_layer_surface(id)];
+        self.shown.remove(&id);
+
+        if let Some(old) = self.replacing.remove(&id) {
+            self.shown.remove(&old);
+            tasks.push(destroy_layer_surface(old));
+        }
+
+        return Task::batch(tasks);
+    }
+
+    /// the surface `id` has drawn a frame, so the surface it's replacing can
+    /// be destroyed
+    pub fn presented(&mut self, id: Id) -> Task<AppMessage> {
+        match self.replacing.remove(&id) {
+            Some(old) => {
+                self.shown.remove(&old);
+                log::debug!("[builtin:surface] {id:?} has drawn, destroying {old:?}");
+                destroy_layer_surface(old)
+            }
+            None => Task::none(),
+        }
+    }
+
+    /// listens for frames while a swap is waiting on one
+    pub fn subscription(&self) -> Subscription<Message> {
+        if self.replacing.is_empty() {
+            return Subscription::none();
+        }
+
+        // `listen_with` leaves out redraws, so the raw events are needed
+        event::listen_raw(|event, _status, id| match event {
+            Event::Window(window::Event::RedrawRequested(_)) => Some(Message::SurfacePresented(id)),
+            _ => None,
+        })
+    }
+
+    fn insert(&mut self, surface: Surface) -> Id {
+        let id = Id::unique();
+        self.current.insert(surface, id);
+        self.shown.insert(id, surface);
+        return id;
+    }
+}
@@ -0,0 +1,173 @@
+//! the current weather for the bar, hovering it shows the forecast
+
+use super::{Message as BuiltinMessage, clock};
+
+use crate::app::AppMessage;
+use crate::config::{self, BarWidget, WeatherUnits};
+use crate::services::ServiceState;
+use crate::services::weather::{self, WeatherKind, WeatherState};
+use crate::theme::{self, Base16Color};
+
+use chrono::Locale;
+use iced::alignment::Vertical;
+use iced::widget::{Column, column, container, mouse_area, row, text};
+use iced::{Element, Length, Task};
+
+/// width and height of the forecast popup
+pub const POPUP_SIZE: (u32, u32) = (240, 190);
+
+#[derive(Debug)]
+pub struct WeatherWidget {
+    state: WeatherState,
+    units: WeatherUnits,
+    /// used for the names of days in the forecast
+    locale: Locale,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// an event from the weather service
+    Service(weather::Event),
+}
+
+impl WeatherWidget {
+    pub fn new(config: &config::Config) -> Self {
+        Self {
+            state: WeatherState::init(),
+            units: config.services.weather.units,
+            locale: clock::locale(config.widgets.clock.locale.as_deref()),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<AppMessage> {
+        match message {
+            Message::Service(event) => {
+                self.state.update(event);
+            }
+        }
+
+        return Task::none();
+    }
+
+    /// the current temperature, shown in the bar
+    pub fn view<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let label = match &self.state.weather {
+            Some(weather) => format!(
+                "{} {:.0}{}",
+                icon(weather.current.kind, weather.current.is_day),
+                weather.current.temperature,
+                self.units.temperature_symbol()
+            ),
+            None => format!("{} --", icon(WeatherKind::Unknown, true)),
+        };
+
+        mouse_area(container(text(label).style(theme::text_style(theme)).size(11)).padding([2, 6]))
+            .on_enter(AppMessage::Builtin(BuiltinMessage::OpenPopup(
+                BarWidget::Weather,
+            )))
+            .on_exit(AppMessage::Builtin(BuiltinMessage::ClosePopup(
+                BarWidget::Weather,
+            )))
+            .into()
+    }
+
+    /// the current conditions and the forecast for the next days
+    pub fn popup<'a>(&'a self, theme: &'a Base16Color) -> Element<'a, AppMessage> {
+        let weather = match &self.state.weather {
+            Some(weather) => weather,
+            None => {
+                let message = match &self.state.error {
+                    Some(error) => format!("could not get the weather:\n{error}"),
+                    None => "getting the weather...".to_string(),
+                };
+                return text(message)
+                    .style(theme::text_style(theme))
+                    .size(11)
+                    .into();
+            }
+        };
+
+        let current = &weather.current;
+        let temperature = self.units.temperature_symbol();
+
+        let mut details = vec![
+            text(format!(
+                "{} {:.0}{temperature}, {}",
+                icon(current.kind, current.is_day),
+                current.temperature,
+                current.kind.description()
+            ))
+            .style(theme::text_style(theme))
+            .size(11)
+            .into(),
+            text(format!(
+                "feels like {:.0}{temperature}, wind {:.0} {}",
+                current.apparent_temperature,
+                current.wind_speed,
+                self.units.wind_speed_symbol()
+            ))
+            .style(theme::text_style(theme))
+            .size(11)
+            .into(),
+        ];
+        if let Some(location) = &weather.location {
+            details.insert(
+                0,
+                text(location.clone())
+                    .style(theme::text_style(theme))
+                    .size(11)
+                    .into(),
+            );
+        }
+
+        let days = weather.daily.iter().map(|day| {
+            row![
+                text(clock::display(day.date.format_localized("%a", self.locale)))
+                    .style(theme::text_style(theme))
+                    .size(11)
+                    .width(Length::Fixed(36.0)),
+                text(icon(day.kind, true))
+                    .style(theme::text_style(theme))
+                    .size(11)
+                    .width(Length::Fixed(20.0)),
+                text(format!(
+                    "{:.0}{temperature} / {:.0}{temperature}",
+                    day.temperature_min, day.temperature_max
+                ))
+                .style(theme::text_style(theme))
+                .size(11)
+                .width(Length::Fill),
+                text(match day.precipitation_probability {
+                    Some(probability) => format!("{probability:.0}%"),
+                    None => String::new(),
+                })
+                .style(theme::text_style(theme))
+                .size(11),
+            ]
+            .align_y(Vertical::Center)
+            .into()
+        });
+
+        column![
+            Column::with_children(details).spacing(2),
+            Column::with_children(days).spacing(4),
+        ]
+        .spacing(8)
+        .into()
+    }
+}
+
+/// nerd font icon for the weather
+fn icon(kind: WeatherKind, is_day: bool) -> &'static str {
+    match kind {
+        WeatherKind::Clear if is_day => "󰖙",
+        WeatherKind::Clear => "󰖔",
+        WeatherKind::PartlyCloudy => "󰖕",
+        WeatherKind::Cloudy | WeatherKind::Unknown => "󰖐",
+        WeatherKind::Fog => "󰖑",
+        WeatherKind::Drizzle => "󰖗",
+        WeatherKind::Rain => "󰖖",
+        WeatherKind::Snow => "󰖘",
+        WeatherKind::Thunderstorm => "󰖓",
+    }
+}
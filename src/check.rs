@@ -0,0 +1,160 @@
+//! `aurorashell check`, reads the config, the theme and every module without
+//! connecting to wayland and reports what's wrong with them, so dotfiles can
+//! be checked before they're committed
+//!
+//! modules are set up like they are on startup, so their `setup()` runs and
+//! the surfaces and registers they ask for are read, but nothing is shown
+//! and no services are started
+//!
+//! `aurorashell modules validate` does the same for a single module file,
+//! printing what it asked for
+
+use crate::config::{self, Config};
+use crate::diagnostics::{self, Diagnostic};
+use crate::runtime::wasm::{self, ModuleCheck};
+use crate::services::appearance::ColorScheme;
+use crate::theme::Base16Color;
+
+use std::path::Path;
+use std::{fmt, io};
+
+#[derive(Debug, Default)]
+pub struct Report {
+    /// files that couldn't be read at all
+    pub errors: Vec<String>,
+    /// problems in `config.toml` and `colors.toml`
+    pub diagnostics: Vec<Diagnostic>,
+    pub modules: Vec<ModuleCheck>,
+    /// prints the surfaces and registers each module asked for
+    pub detailed: bool,
+}
+
+impl Report {
+    /// the amount of problems found, the check fails if there are any
+    pub fn problems(&self) -> usize {
+        let modules = self
+            .modules
+            .iter()
+            .map(|module| module.error.is_some() as usize + module.warnings.len())
+            .sum::<usize>();
+
+        return self.errors.len() + self.diagnostics.len() + modules;
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for error in &self.errors {
+            writeln!(f, "{error}")?;
+        }
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{diagnostic}")?;
+        }
+
+        writeln!(f, "{} module(s)", self.modules.len())?;
+        for module in &self.modules {
+            let file_name = module.path.file_name().map_or_else(
+                || module.path.display().to_string(),
+                |name| name.to_string_lossy().to_string(),
+            );
+
+            match (&module.name, &module.error) {
+                (_, Some(error)) => writeln!(f, "  {file_name}: failed, {error}")?,
+                (name, None) => writeln!(
+                    f,
+                    "  {file_name}: `{}` (abi version {}), {} surface(s), {} register(s)",
+                    name.as_deref().unwrap_or_default(),
+                    module.abi_version.as_deref().unwrap_or_default(),
+                    module.surfaces,
+                    module.registers
+                )?,
+            }
+
+            if self.detailed {
+                for declared in &module.declared {
+                    writeln!(f, "    {declared}")?;
+                }
+            }
+            for warning in &module.warnings {
+                writeln!(f, "    {warning}")?;
+            }
+        }
+
+        match self.problems() {
+            0 => writeln!(f, "no problems found"),
+            n => writeln!(f, "{n} problem(s) found"),
+        }
+    }
+}
+
+/// reads everything the shell reads on startup and collects the problems
+pub fn run() -> anyhow::Result<Report> {
+    let mut report = Report::default();
+    read_config(&mut report);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    report.modules = runtime.block_on(wasm::check_modules())?;
+
+    return Ok(report);
+}
+
+/// sets up the module at `path` with the shell's config, without installing
+/// it
+pub fn module(path: &Path) -> anyhow::Result<Report> {
+    let mut report = Report {
+        detailed: true,
+        ..Default::default()
+    };
+    read_config(&mut report);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    report.modules = vec![runtime.block_on(wasm::validate_module(path))?];
+
+    return Ok(report);
+}
+
+/// reads the config and theme, adding their problems to `report`
+fn read_config(report: &mut Report) {
+    match Config::from_file() {
+        // modules get their environment from it
+        Ok(config) => config::init(config),
+        Err(err) => report
+            .errors
+            .push(format!("could not read config.toml: {err}")),
+    }
+
+    // the light and dark files are only checked when there are any, they
+    // fall back to `colors.toml` otherwise
+    let mut checked = vec![];
+    for scheme in [
+        ColorScheme::NoPreference,
+        ColorScheme::Dark,
+        ColorScheme::Light,
+    ] {
+        let Ok(path) = Base16Color::path(scheme) else {
+            continue;
+        };
+        if checked.contains(&path) {
+            continue;
+        }
+
+        if let Err(err) = Base16Color::from_config(scheme) {
+            // the shell starts with the default colors without one
+            let missing = err
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() == io::ErrorKind::NotFound);
+            if !missing {
+                report
+                    .errors
+                    .push(format!("could not read {}: {err}", path.display()));
+            }
+        }
+        checked.push(path);
+    }
+
+    report.diagnostics = diagnostics::take();
+}
@@ -0,0 +1,268 @@
+//! what the compositor supports, probed once on startup from the globals it
+//! advertises so features it's missing can be turned off with a warning
+//! instead of the compositor refusing them (or killing the connection)
+//!
+//! the probe uses its own short lived connection as iced's isn't reachable
+//! from here. if it fails nothing is known and nothing is turned off, unless
+//! there's no wayland at all (like under x11)
+//!
+//! without layer shell surfaces aren't shown, or are shown as windows with
+//! `window_fallback` under `[compositor]`, see `crate::fallback`
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use iced::Task;
+use iced::platform_specific::shell::commands::layer_surface::{self, KeyboardInteractivity};
+use iced::runtime::platform_specific::wayland::layer_surface::SctkLayerSurfaceSettings;
+use iced::window::Id;
+use serde::{Deserialize, Serialize};
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::{Connection, Dispatch, QueueHandle};
+
+use crate::{config, fallback};
+
+static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+
+/// the protocols the shell and modules care about, the ids are what modules
+/// ask for them with
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    /// every surface the shell draws is a layer surface
+    LayerShell = 0,
+    /// wlroots' list of windows that can also be activated and closed
+    ForeignToplevelManagement = 1,
+    /// the newer read only list of windows
+    ForeignToplevelList = 2,
+    /// keeps the screen from going idle while a surface is shown
+    IdleInhibit = 3,
+}
+
+impl Protocol {
+    pub const ALL: [Self; 4] = [
+        Self::LayerShell,
+        Self::ForeignToplevelManagement,
+        Self::ForeignToplevelList,
+        Self::IdleInhibit,
+    ];
+
+    /// the name of the global the compositor advertises
+    pub fn interface(self) -> &'static str {
+        match self {
+            Self::LayerShell => "zwlr_layer_shell_v1",
+            Self::ForeignToplevelManagement => "zwlr_foreign_toplevel_manager_v1",
+            Self::ForeignToplevelList => "ext_foreign_toplevel_list_v1",
+            Self::IdleInhibit => "zwp_idle_inhibit_manager_v1",
+        }
+    }
+
+    pub fn from_raw(id: u32) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|protocol| *protocol as u32 == id)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// false if the compositor couldn't be asked, everything is assumed to
+    /// be supported then
+    pub probed: bool,
+    /// true when there's no wayland display to connect to, like under x11,
+    /// so there's certainly no layer shell
+    #[serde(default)]
+    pub no_wayland: bool,
+    /// the version of every global the compositor advertised, by interface
+    pub globals: BTreeMap<String, u32>,
+}
+
+impl Capabilities {
+    /// the newest version of the protocol the compositor supports, `None`
+    /// if it doesn't or the compositor couldn't be asked
+    pub fn version(&self, protocol: Protocol) -> Option<u32> {
+        self.globals.get(protocol.interface()).copied()
+    }
+
+    /// whether the protocol can be used at `version`, true when the
+    /// compositor couldn't be asked
+    pub fn supports(&self, protocol: Protocol, version: u32) -> bool {
+        if !self.probed {
+            return true;
+        }
+
+        return self
+            .version(protocol)
+            .is_some_and(|supported| supported >= version);
+    }
+
+    /// whether surfaces can be shown as layer surfaces
+    pub fn has_layer_shell(&self) -> bool {
+        !self.no_wayland && self.supports(Protocol::LayerShell, 1)
+    }
+}
+
+struct Probe;
+
+impl Dispatch<WlRegistry, GlobalListContents> for Probe {
+    fn event(
+        _state: &mut Self,
+        _registry: &WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // only the globals advertised when connecting are needed
+    }
+}
+
+/// asks the compositor what it supports, only the first call does anything
+pub fn probe() {
+    let capabilities = match probe_globals() {
+        Ok(globals) => Capabilities {
+            probed: true,
+            no_wayland: false,
+            globals,
+        },
+        Err(_)
+            if std::env::var_os("WAYLAND_DISPLAY").is_none()
+                && std::env::var_os("WAYLAND_SOCKET").is_none() =>
+        {
+            log::warn!("[compositor] not running under wayland, there's no layer shell");
+            Capabilities {
+                no_wayland: true,
+                ..Capabilities::default()
+            }
+        }
+        Err(err) => {
+            log::warn!(
+                "[compositor] could not probe the compositor, assuming it supports everything: {err}"
+            );
+            Capabilities::default()
+        }
+    };
+
+    if capabilities.probed {
+        for protocol in Protocol::ALL {
+            match capabilities.version(protocol) {
+                Some(version) => {
+                    log::info!("[compositor] {} version {version}", protocol.interface())
+                }
+                None => log::info!("[compositor] {} not supported", protocol.interface()),
+            }
+        }
+    }
+
+    if !capabilities.has_layer_shell() {
+        if config::get().compositor.window_fallback {
+            log::warn!("[compositor] no layer shell, surfaces are shown as windows instead");
+        } else {
+            log::error!(
+                "[compositor] no layer shell, no surfaces can be shown. set `window_fallback` \
+                 under `[compositor]` to show them as windows"
+            );
+        }
+    }
+
+    let _ = CAPABILITIES.set(capabilities);
+}
+
+fn probe_globals() -> anyhow::Result<BTreeMap<String, u32>> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, _queue) = registry_queue_init::<Probe>(&conn)?;
+
+    return Ok(globals.contents().with_list(|list| {
+        list.iter()
+            .map(|global| (global.interface.clone(), global.version))
+            .collect()
+    }));
+}
+
+/// what the compositor supports, nothing is known if `probe` wasn't called
+pub fn get() -> &'static Capabilities {
+    CAPABILITIES.get_or_init(Capabilities::default)
+}
+
+/// whether surfaces are shown as windows, see `crate::fallback`
+pub fn window_fallback() -> bool {
+    config::get().compositor.window_fallback && !get().has_layer_shell()
+}
+
+/// changes a layer surface's settings to what the compositor supports,
+/// warning about each change. `None` if it can't be shown at all
+///
+/// the settings are kept as they are when surfaces are shown as windows
+///
+/// `owner` is used in the warnings, like `module:bar.wasm` or `builtin`
+pub fn fit_layer_surface(
+    mut settings: SctkLayerSurfaceSettings,
+    owner: &str,
+) -> Option<SctkLayerSurfaceSettings> {
+    let capabilities = get();
+
+    if window_fallback() {
+        return Some(settings);
+    }
+
+    if !capabilities.has_layer_shell() {
+        log::warn!(
+            "[compositor] [{owner}] not creating surface {:?}, the compositor doesn't support \
+             layer shell",
+            settings.id
+        );
+        return None;
+    }
+
+    // on demand keyboard focus came in version 4, older compositors treat it
+    // as a protocol error
+    if matches!(
+        settings.keyboard_interactivity,
+        KeyboardInteractivity::OnDemand
+    ) && !capabilities.supports(Protocol::LayerShell, 4)
+    {
+        log::warn!(
+            "[compositor] [{owner}] surface {:?} asked for on demand keyboard focus, which the \
+             compositor doesn't support. it won't get keyboard input",
+            settings.id
+        );
+        settings.keyboard_interactivity = KeyboardInteractivity::None;
+    }
+
+    return Some(settings);
+}
+
+/// creates a layer surface fitted to the compositor with `fit_layer_surface`
+pub fn get_layer_surface<Message>(settings: SctkLayerSurfaceSettings, owner: &str) -> Task<Message>
+where
+    Message: Send + 'static,
+{
+    match fit_layer_surface(settings, owner) {
+        Some(settings) => create_layer_surface(settings),
+        None => Task::none(),
+    }
+}
+
+/// creates a layer surface that was already fitted with `fit_layer_surface`,
+/// or a window standing in for it
+pub fn create_layer_surface<Message>(settings: SctkLayerSurfaceSettings) -> Task<Message>
+where
+    Message: Send + 'static,
+{
+    if window_fallback() {
+        return fallback::open(settings);
+    }
+
+    return layer_surface::get_layer_surface(settings);
+}
+
+/// destroys a layer surface or the window standing in for it
+pub fn destroy_layer_surface<Message>(id: Id) -> Task<Message> {
+    if window_fallback() {
+        return fallback::close(id);
+    }
+
+    return layer_surface::destroy_layer_surface(id);
+}
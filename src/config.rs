@@ -0,0 +1,1322 @@
+//! config for the shell daemon
+//!
+//! the config is read once on startup from `~/.config/aurorashell/config.toml`
+//! and every section is optional, falling back to its defaults. problems in
+//! the file are reported through `crate::diagnostics` instead of failing
+//! startup
+//!
+//! options given on the command line, like `--log-level`, win over the file
+//!
+//! example:
+//! ```toml
+//! [log]
+//! level = "debug"
+//!
+//! [services.audio]
+//! restore = true
+//! update_interval_ms = 50
+//! bluetooth_profile = "a2dp"
+//! bluetooth_autoswitch = true
+//!
+//! [services.audio.eq_presets]
+//! flat = [0, 0, 0, 0, 0]
+//! bass = [6, 4, 0, 0, 0]
+//!
+//! [[services.custom.sensors]]
+//! name = "cpu_temp"
+//! command = "sensors -j"
+//! interval_ms = 5000
+//! parse = "json"
+//!
+//! [services.weather]
+//! provider = "open-meteo"
+//! location = "Berlin"
+//! units = "metric"
+//!
+//! [services.kdeconnect]
+//! forward_notifications = true
+//!
+//! [services.screenshot]
+//! directory = "~/Pictures/Screenshots"
+//!
+//! [ipc.remote]
+//! enabled = true
+//! address = "0.0.0.0:7420"
+//! token = "a long random string"
+//! tls_cert = "/home/user/.config/aurorashell/cert.pem"
+//! tls_key = "/home/user/.config/aurorashell/key.pem"
+//!
+//! [bar]
+//! enabled = true
+//! center = ["clock"]
+//! right = [{ group = ["audio", "weather"] }]
+//! separator = "|"
+//!
+//! [widgets.clock]
+//! format = "%H:%M"
+//!
+//! [widgets.quick_settings]
+//! swipe = true
+//! dnd_command = "swaync-client --dnd-{state}"
+//!
+//! [icons]
+//! theme = "Papirus-Dark"
+//!
+//! [modules]
+//! dirs = ["~/.local/share/aurorashell/modules", "~/code/modules/target"]
+//! disabled = ["battery"]
+//! order = ["bar", "launcher"]
+//! call_timeout_ms = 1000
+//! max_traps = 3
+//! crash_restarts = 3
+//!
+//! [modules.wasi.weather]
+//! env = { WEATHER_CITY = "Berlin" }
+//! args = ["--metric"]
+//!
+//! [modules.settings.clock]
+//! format = "%H:%M"
+//! position = "top"
+//! ```
+//!
+//! sections can be overridden on some machines with profiles, so one config
+//! can be shared between them. a profile applies when the hostname is one of
+//! `hostname` and every variable in `env` is set to its value, or when its
+//! name is in `$AURORASHELL_PROFILE` (comma separated)
+//!
+//! ```toml
+//! [profiles.laptop]
+//! hostname = ["thinkpad", "framework"]
+//!
+//! [profiles.laptop.bar]
+//! position = "bottom"
+//!
+//! [profiles.work]
+//! env = { XDG_CURRENT_DESKTOP = "sway" }
+//! widgets.clock.format = "%H:%M"
+//! ```
+//!
+//! profiles are applied over the rest of the config in the order they're
+//! written, then the ones named in `$AURORASHELL_PROFILE` in the order
+//! they're named, so later profiles win. tables are merged key by key while
+//! anything else, arrays included, is replaced. keys inside a profile are
+//! only checked once it applies
+
+use crate::diagnostics::{self, Diagnostic};
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::{env, fs, io};
+
+use serde::{Deserialize, Serialize};
+use toml_edit::ImDocument;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// names profiles to apply whatever machine the shell is on
+const PROFILE_ENV: &str = "AURORASHELL_PROFILE";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub log: LogConfig,
+    pub services: ServicesConfig,
+    pub ipc: IpcConfig,
+    pub watchdog: WatchdogConfig,
+    pub compositor: CompositorConfig,
+    pub modules: ModulesConfig,
+    pub bar: BarConfig,
+    pub widgets: WidgetsConfig,
+    pub icons: IconsConfig,
+    /// sections that only apply on some machines, keyed by the profile's
+    /// name
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    /// the profiles that were applied, in the order they were
+    #[serde(skip)]
+    pub active_profiles: Vec<String>,
+}
+
+/// overrides for the rest of the config, see the top of this file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProfileConfig {
+    /// the machines the profile applies on
+    pub hostname: Option<Hostnames>,
+    /// environment variables that all have to be set to these values
+    pub env: BTreeMap<String, String>,
+    /// the sections merged over the rest of the config, like `bar`
+    #[serde(flatten)]
+    pub sections: toml::Table,
+}
+
+/// written as `"laptop"` or `["laptop", "desktop"]`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Hostnames {
+    One(String),
+    Any(Vec<String>),
+}
+
+impl Hostnames {
+    fn contains(&self, hostname: &str) -> bool {
+        match self {
+            Hostnames::One(name) => name == hostname,
+            Hostnames::Any(names) => names.iter().any(|name| name == hostname),
+        }
+    }
+}
+
+impl ProfileConfig {
+    /// whether the profile applies on this machine, a profile without a
+    /// hostname or env only applies when it's named in `$AURORASHELL_PROFILE`
+    fn matches(&self, hostname: Option<&str>) -> bool {
+        if self.hostname.is_none() && self.env.is_empty() {
+            return false;
+        }
+
+        let hostname_matches = match &self.hostname {
+            Some(names) => hostname.is_some_and(|hostname| names.contains(hostname)),
+            None => true,
+        };
+        let env_matches = self
+            .env
+            .iter()
+            .all(|(name, expected)| env::var(name).is_ok_and(|value| value == *expected));
+
+        return hostname_matches && env_matches;
+    }
+}
+
+/// what the shell logs
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// the level for the shell's own logs, `--log-level` wins over it. info
+    /// when neither is set
+    pub level: Option<LogLevel>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for log::LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => log::LevelFilter::Off,
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// options for each service
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ServicesConfig {
+    pub audio: AudioServiceConfig,
+    pub custom: CustomServiceConfig,
+    pub weather: WeatherServiceConfig,
+    pub kdeconnect: KdeConnectServiceConfig,
+    pub screenshot: ScreenshotServiceConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AudioServiceConfig {
+    /// remembers the default sink and source and their volumes, setting them
+    /// again the first time the service connects. for sound servers that
+    /// forget them across reboots
+    pub restore: bool,
+    /// the least time between changes to a volume sent to the sound server,
+    /// dragging a slider sends far more than it can keep up with
+    pub update_interval_ms: u64,
+    /// how many events and requests can wait in the service's channels
+    pub channel_capacity: usize,
+    /// the profile bluetooth headsets are switched to when they connect,
+    /// left to the sound server when unset
+    pub bluetooth_profile: Option<BluetoothProfile>,
+    /// switches the bluetooth headset being played to over to its headset
+    /// profile while something records, so its microphone can be used, and
+    /// back once nothing does
+    pub bluetooth_autoswitch: bool,
+    /// presets for a pipewire `filter-chain` equalizer by name, the gain of
+    /// each of its bands in dB from the lowest band up
+    pub eq_presets: BTreeMap<String, Vec<f32>>,
+}
+
+/// the kinds of profiles a bluetooth headset has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BluetoothProfile {
+    /// high quality playback without the microphone
+    A2dp,
+    /// playback and the microphone at call quality
+    Hfp,
+}
+
+impl Default for AudioServiceConfig {
+    fn default() -> Self {
+        Self {
+            restore: false,
+            update_interval_ms: 100,
+            channel_capacity: 64,
+            bluetooth_profile: None,
+            bluetooth_autoswitch: false,
+            eq_presets: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CustomServiceConfig {
+    /// scripts that are ran periodically, with their output emitted as
+    /// events keyed by the sensor's name
+    pub sensors: Vec<SensorConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SensorConfig {
+    /// must be unique, modules use this name to subscribe to the sensor
+    pub name: String,
+    /// ran with `sh -c`
+    pub command: String,
+    /// the time between each run of the command
+    #[serde(default = "SensorConfig::default_interval_ms")]
+    pub interval_ms: u64,
+    /// the command is killed if it runs for longer than this
+    #[serde(default = "SensorConfig::default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// how the command's stdout is interpreted
+    #[serde(default)]
+    pub parse: SensorParseMode,
+}
+
+impl SensorConfig {
+    fn default_interval_ms() -> u64 {
+        5000
+    }
+
+    fn default_timeout_ms() -> u64 {
+        2000
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorParseMode {
+    /// stdout is trimmed and used as is
+    #[default]
+    Plain,
+    /// stdout is parsed as json
+    Json,
+}
+
+/// the weather service only runs if a location or coordinates are set
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WeatherServiceConfig {
+    /// where the weather is fetched from
+    pub provider: WeatherProvider,
+    /// a place name like `Berlin`, looked up once on start. also used as the
+    /// name shown for the coordinates if they're set
+    pub location: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub units: WeatherUnits,
+    /// the time between each fetch
+    pub interval_minutes: u64,
+    /// the amount of days in the forecast, including today
+    pub forecast_days: u8,
+}
+
+impl WeatherServiceConfig {
+    pub fn is_configured(&self) -> bool {
+        self.location.is_some() || (self.latitude.is_some() && self.longitude.is_some())
+    }
+}
+
+impl Default for WeatherServiceConfig {
+    fn default() -> Self {
+        Self {
+            provider: WeatherProvider::OpenMeteo,
+            location: None,
+            latitude: None,
+            longitude: None,
+            units: WeatherUnits::Metric,
+            interval_minutes: 15,
+            forecast_days: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum WeatherProvider {
+    /// <https://open-meteo.com>
+    #[default]
+    #[serde(rename = "open-meteo")]
+    OpenMeteo,
+    /// <https://wttr.in>, which only forecasts up to 3 days
+    #[serde(rename = "wttr.in")]
+    Wttr,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeatherUnits {
+    /// celsius and km/h
+    #[default]
+    Metric,
+    /// fahrenheit and mph
+    Imperial,
+}
+
+impl WeatherUnits {
+    pub fn temperature_symbol(&self) -> &'static str {
+        match self {
+            Self::Metric => "°C",
+            Self::Imperial => "°F",
+        }
+    }
+
+    pub fn wind_speed_symbol(&self) -> &'static str {
+        match self {
+            Self::Metric => "km/h",
+            Self::Imperial => "mph",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct KdeConnectServiceConfig {
+    /// shows the notifications posted on paired devices on the desktop,
+    /// except while do not disturb is on
+    pub forward_notifications: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScreenshotServiceConfig {
+    /// where screenshots are saved, `~` is the home directory.
+    /// `$XDG_PICTURES_DIR/Screenshots` or `~/Pictures/Screenshots` when unset
+    pub directory: Option<PathBuf>,
+}
+
+/// options for the ipc service
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct IpcConfig {
+    pub remote: RemoteIpcConfig,
+}
+
+/// exposes the ipc protocol over tcp so the shell can be controlled from
+/// another device
+///
+/// connections must use tls and send the token before anything else
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RemoteIpcConfig {
+    pub enabled: bool,
+    /// the address and port to listen on
+    pub address: String,
+    /// the listener won't start if this is empty
+    pub token: String,
+    /// pem encoded certificate chain
+    pub tls_cert: Option<PathBuf>,
+    /// pem encoded private key
+    pub tls_key: Option<PathBuf>,
+}
+
+impl Default for RemoteIpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "0.0.0.0:7420".to_string(),
+            token: String::new(),
+            tls_cert: None,
+            tls_key: None,
+        }
+    }
+}
+
+/// options for detecting stalled loops (the wasm runtime and the audio
+/// service), see `crate::watchdog`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    pub enabled: bool,
+    /// how long a loop can spend handling one message before it's restarted
+    pub threshold_ms: u64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold_ms: 10_000,
+        }
+    }
+}
+
+/// how surfaces are shown, see `crate::compositor`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CompositorConfig {
+    /// show surfaces as undecorated always on top windows when layer shell
+    /// isn't there, like under x11 or gnome
+    pub window_fallback: bool,
+}
+
+/// limits on what a module can use
+///
+/// a module going over one of the byte limits is disabled, 0 turns them off.
+/// a ui tree going over the depth or node limit isn't drawn
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ModulesConfig {
+    /// the directories modules are loaded from, `~` is the home directory.
+    /// modules are installed to the first one, which is created if it
+    /// doesn't exist. `~/.local/share/aurorashell/modules` when empty
+    pub dirs: Vec<PathBuf>,
+    /// when not empty only the modules named here are loaded, by their file
+    /// name without the extension like `weather`
+    pub enabled: Vec<String>,
+    /// modules that aren't loaded, by their file name without the extension
+    pub disabled: Vec<String>,
+    /// modules that are loaded first, in this order, by their file name
+    /// without the extension. the rest are loaded after them sorted by name
+    pub order: Vec<String>,
+    /// the size of the ui trees the host keeps for a module, across all of
+    /// its surfaces
+    pub max_tree_bytes: usize,
+    /// the size of a module's linear memory
+    pub max_memory_bytes: usize,
+    /// how deeply a module's ui can be nested
+    pub max_tree_depth: usize,
+    /// the amount of elements in one of a module's ui trees
+    pub max_tree_nodes: usize,
+    /// how long a callback should take to show up on screen, slower ones
+    /// are counted in `latency.callback.over_budget`
+    pub callback_budget_ms: u64,
+    /// how long a call into a module, like its `view` or `update`, can run
+    /// before it's stopped and counted as a trap. 0 lets it run forever
+    pub call_timeout_ms: u64,
+    /// how many times in a row a module's functions can trap before it's
+    /// unloaded
+    pub max_traps: u32,
+    /// how many times a module unloaded for trapping is loaded again, with
+    /// a longer wait each time. 0 leaves it unloaded
+    pub crash_restarts: u32,
+    /// the names of the modules allowed to open links and files, see
+    /// `crate::open`
+    pub open_uri: Vec<String>,
+    /// the names of the modules allowed to run programs, see
+    /// `runtime::wasm::command`
+    pub spawn: Vec<String>,
+    /// the programs a module can run, by the module's file name without the
+    /// extension. a module listed here can only run these, as it names them
+    /// (`playerctl` and `/usr/bin/playerctl` are different), even if it's in
+    /// `spawn`
+    pub programs: BTreeMap<String, Vec<String>>,
+    /// the names of the modules allowed to fetch urls
+    pub fetch: Vec<String>,
+    /// the names of the modules allowed to suspend, reboot or power off the
+    /// system and lock the session, see `services::session`
+    pub session: Vec<String>,
+    /// the names of the modules allowed to take screenshots, see
+    /// `services::screenshot`
+    pub screenshot: Vec<String>,
+    /// the directories a module can watch files in with its `Watch`
+    /// register, by the module's file name without the extension. `~` is the
+    /// home directory, see `services::watch`
+    pub watch: BTreeMap<String, Vec<PathBuf>>,
+    /// the bus names a module can watch with its `Dbus` registers, by the
+    /// module's file name without the extension, like
+    /// `org.mpris.MediaPlayer2.spotify`. see `services::dbus`
+    pub dbus: BTreeMap<String, Vec<String>>,
+    /// environment variables and arguments for modules, by their file name
+    /// without the extension (`weather` for `weather.wasm`)
+    pub wasi: BTreeMap<String, ModuleWasiConfig>,
+    /// options for modules by their file name without the extension, read
+    /// with `config::get` in the module. values that aren't strings are given
+    /// to the module as they're written in the toml, like `24` or `true`
+    pub settings: BTreeMap<String, BTreeMap<String, toml::Value>>,
+}
+
+impl ModulesConfig {
+    /// whether the module with the file name (without the extension) is
+    /// loaded
+    pub fn is_enabled(&self, name: &str) -> bool {
+        if !self.enabled.is_empty() && !self.enabled.iter().any(|enabled| enabled == name) {
+            return false;
+        }
+
+        return !self.disabled.iter().any(|disabled| disabled == name);
+    }
+
+    /// where the module with the file name (without the extension) is in
+    /// `order`, modules that aren't in it come after the ones that are
+    pub fn load_position(&self, name: &str) -> usize {
+        return self
+            .order
+            .iter()
+            .position(|ordered| ordered == name)
+            .unwrap_or(self.order.len());
+    }
+}
+
+impl Default for ModulesConfig {
+    fn default() -> Self {
+        Self {
+            dirs: vec![],
+            enabled: vec![],
+            disabled: vec![],
+            order: vec![],
+            max_tree_bytes: 4 * 1024 * 1024,
+            max_memory_bytes: 256 * 1024 * 1024,
+            max_tree_depth: 64,
+            max_tree_nodes: 10_000,
+            callback_budget_ms: 50,
+            call_timeout_ms: 1000,
+            max_traps: 3,
+            crash_restarts: 3,
+            open_uri: vec![],
+            spawn: vec![],
+            programs: BTreeMap::new(),
+            fetch: vec![],
+            session: vec![],
+            screenshot: vec![],
+            watch: BTreeMap::new(),
+            dbus: BTreeMap::new(),
+            wasi: BTreeMap::new(),
+            settings: BTreeMap::new(),
+        }
+    }
+}
+
+/// what a module reads through `std::env`, given to it when it's loaded
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ModuleWasiConfig {
+    pub env: BTreeMap<String, String>,
+    /// passed after the module's file name, which is always the first
+    pub args: Vec<String>,
+}
+
+/// where icons asked for by name are found, see `crate::icons`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct IconsConfig {
+    /// the name of the icon theme's folder, only `hicolor` is used if unset
+    pub theme: Option<String>,
+}
+
+/// the bar drawn by the shell itself, see `crate::builtin`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BarConfig {
+    pub enabled: bool,
+    /// the edge of the screen the bar is on
+    pub position: BarPosition,
+    /// in logical pixels
+    pub height: u32,
+    /// the widgets on each side of the bar, in order
+    pub left: Vec<BarItem>,
+    pub center: Vec<BarItem>,
+    pub right: Vec<BarItem>,
+    /// drawn between each item on a side of the bar, like `|`. nothing is
+    /// drawn if it's empty
+    pub separator: String,
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            position: BarPosition::Top,
+            height: 28,
+            left: vec![],
+            center: vec![BarItem::Widget(BarWidget::Clock)],
+            right: vec![],
+            separator: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarPosition {
+    #[default]
+    Top,
+    Bottom,
+}
+
+/// an entry in one of the sides of the bar
+///
+/// written as `"clock"` for a widget or `{ group = ["audio", "clock"] }` for
+/// a group
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum BarItem {
+    Widget(BarWidget),
+    /// widgets drawn together on a pill shaped background, without
+    /// separators between them
+    Group {
+        group: Vec<BarWidget>,
+    },
+}
+
+impl BarItem {
+    pub fn contains(&self, widget: BarWidget) -> bool {
+        match self {
+            BarItem::Widget(item) => *item == widget,
+            BarItem::Group { group } => group.contains(&widget),
+        }
+    }
+}
+
+/// the widgets the shell can draw in its bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarWidget {
+    Clock,
+    /// needs `[services.weather]` to be set up
+    Weather,
+    /// the default output's volume, opens a popup for switching devices
+    Audio,
+    /// opens the quick settings panel, see `QuickSettingsConfig`
+    #[serde(rename = "quick_settings")]
+    QuickSettings,
+}
+
+/// options for the widgets in the bar
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WidgetsConfig {
+    pub clock: ClockConfig,
+    pub quick_settings: QuickSettingsConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ClockConfig {
+    /// strftime style, see
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    pub format: String,
+    /// like `en_GB`, defaults to `LC_ALL`, `LC_TIME` or `LANG`
+    pub locale: Option<String>,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            format: "%a %d %b %H:%M".to_string(),
+            locale: None,
+        }
+    }
+}
+
+/// the panel opened by the `quick_settings` widget or
+/// `aurorashell toggle quick-settings`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct QuickSettingsConfig {
+    /// opens the panel when a finger swipes down from the top edge of the
+    /// screen, this puts a thin surface along the edge to catch it
+    pub swipe: bool,
+    /// the tiles shown, in order. toggles are put in a row above the sliders
+    pub tiles: Vec<QuickSettingsTile>,
+    /// ran with `sh -c` when the bluetooth tile is pressed, `{state}` is
+    /// replaced by `on` or `off`
+    pub bluetooth_command: String,
+    /// like `bluetooth_command`, the tile can't be pressed if it isn't set
+    /// as every notification daemon does this differently
+    pub dnd_command: Option<String>,
+}
+
+impl Default for QuickSettingsConfig {
+    fn default() -> Self {
+        Self {
+            swipe: false,
+            tiles: vec![
+                QuickSettingsTile::Wifi,
+                QuickSettingsTile::Bluetooth,
+                QuickSettingsTile::Dnd,
+                QuickSettingsTile::Volume,
+                QuickSettingsTile::Brightness,
+            ],
+            bluetooth_command: "bluetoothctl power {state}".to_string(),
+            dnd_command: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuickSettingsTile {
+    /// turns wifi on or off, needs NetworkManager
+    Wifi,
+    Bluetooth,
+    /// do not disturb
+    Dnd,
+    /// the default output's volume
+    Volume,
+    /// the first backlight's brightness
+    Brightness,
+}
+
+impl Config {
+    /// reads the config file, a missing file gives the default config
+    ///
+    /// problems in the file are added to the startup report, unknown keys
+    /// are ignored and a file that can't be deserialized gives the default
+    /// config
+    pub fn from_file() -> anyhow::Result<Self> {
+        let config_path = config_dir()?.join("config.toml");
+
+        let source = match fs::read_to_string(&config_path) {
+            Ok(source) => source,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        return Ok(Self::from_source(&config_path, &source));
+    }
+
+    fn from_source(path: &Path, source: &str) -> Self {
+        let document = match ImDocument::parse(source) {
+            Ok(document) => document,
+            Err(err) => {
+                diagnostics::push(
+                    Diagnostic::new(path, err.message().trim_end()).with_span(source, err.span()),
+                );
+                return Config::default();
+            }
+        };
+
+        let mut unknown_keys = vec![];
+        let deserializer = toml::de::Deserializer::new(source);
+        let config = serde_ignored::deserialize(deserializer, |key| {
+            unknown_keys.push(key.to_string());
+        });
+
+        for key in unknown_keys {
+            let path_segments = key.split('.').collect::<Vec<&str>>();
+            let (name, table) = match path_segments.split_last() {
+                Some((name, table)) => (*name, table),
+                None => continue,
+            };
+            let table = table
+                .iter()
+                .filter(|segment| segment.parse::<usize>().is_err())
+                .copied()
+                .collect::<Vec<&str>>()
+                .join(".");
+
+            let message = match table.as_str() {
+                "" => format!("unknown key `{name}`"),
+                table => format!("unknown key `{name}` in `{table}`"),
+            };
+            let help = diagnostics::did_you_mean(name, known_keys(&table))
+                .map(|known| format!("did you mean `{known}`?"));
+
+            diagnostics::push(
+                Diagnostic::new(path, message)
+                    .with_span(source, diagnostics::key_span(&document, &path_segments))
+                    .with_help(help.or(Some("this key is ignored".to_string()))),
+            );
+        }
+
+        return match config {
+            Ok(config) => {
+                let named = env::var(PROFILE_ENV).unwrap_or_default();
+                config.with_profiles(path, source, &document, hostname().as_deref(), &named)
+            }
+            Err(err) => {
+                let message = err.message().to_string();
+                let help = diagnostics::suggest_from_message(&message).or_else(|| {
+                    // untagged enums don't say what they expected
+                    message.contains("BarItem").then(|| {
+                        "expected a widget like `\"clock\"` or a group like `{ group = [\"clock\"] \
+                         }`"
+                        .to_string()
+                    })
+                });
+
+                diagnostics::push(
+                    Diagnostic::new(path, format!("{message}, using the default config"))
+                        .with_span(source, err.span())
+                        .with_help(help),
+                );
+                Config::default()
+            }
+        };
+    }
+
+    /// applies the profiles for the machine called `hostname` and the ones
+    /// in `named` (from `$AURORASHELL_PROFILE`), see the top of this file
+    ///
+    /// problems with a profile are added to the startup report, a profile
+    /// that can't be deserialized leaves the config without any of them
+    fn with_profiles(
+        mut self,
+        path: &Path,
+        source: &str,
+        document: &ImDocument<&str>,
+        hostname: Option<&str>,
+        named: &str,
+    ) -> Self {
+        // in the order they're written, `profiles` is sorted by name
+        let written: Vec<&str> = document
+            .get("profiles")
+            .and_then(|item| item.as_table_like())
+            .map(|profiles| profiles.iter().map(|(name, _)| name).collect())
+            .unwrap_or_default();
+
+        let sections = known_keys("");
+        for (name, profile) in &self.profiles {
+            for key in profile.sections.keys() {
+                if sections.contains(&key.as_str()) && key != "profiles" {
+                    continue;
+                }
+
+                let help = diagnostics::did_you_mean(key, &profile_keys())
+                    .map(|known| format!("did you mean `{known}`?"));
+                diagnostics::push(
+                    Diagnostic::new(path, format!("unknown key `{key}` in `profiles.{name}`"))
+                        .with_span(
+                            source,
+                            diagnostics::key_span(
+                                document,
+                                &["profiles", name.as_str(), key.as_str()],
+                            ),
+                        )
+                        .with_help(help.or(Some("this key is ignored".to_string()))),
+                );
+            }
+        }
+
+        let mut active: Vec<String> = written
+            .iter()
+            .filter(|name| {
+                self.profiles
+                    .get(**name)
+                    .is_some_and(|profile| profile.matches(hostname))
+            })
+            .map(|name| name.to_string())
+            .collect();
+
+        for name in named
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if !self.profiles.contains_key(name) {
+                let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                let help = diagnostics::did_you_mean(name, &known)
+                    .map(|known| format!("did you mean `{known}`?"));
+                diagnostics::push(
+                    Diagnostic::new(
+                        path,
+                        format!("`${PROFILE_ENV}` names `{name}` but there's no such profile"),
+                    )
+                    .with_help(help),
+                );
+                continue;
+            }
+
+            // named profiles go last so they win over the matched ones
+            active.retain(|active| active != name);
+            active.push(name.to_string());
+        }
+
+        if active.is_empty() {
+            return self;
+        }
+
+        let mut merged: toml::Table = match toml::from_str(source) {
+            Ok(table) => table,
+            Err(_) => return self,
+        };
+        merged.remove("profiles");
+        for name in &active {
+            let sections = self.profiles[name].sections.clone();
+            merge_tables(&mut merged, without_unknown(sections));
+        }
+
+        let mut unknown_keys = vec![];
+        let config: Result<Config, _> =
+            serde_ignored::deserialize(toml::Value::Table(merged), |key| {
+                unknown_keys.push(key.to_string());
+            });
+
+        // keys outside of profiles were reported on the first read
+        for key in unknown_keys {
+            let segments: Vec<&str> = key.split('.').collect();
+            let Some(name) = active
+                .iter()
+                .rev()
+                .find(|name| lookup(&self.profiles[*name].sections, &segments).is_some())
+            else {
+                continue;
+            };
+
+            let mut profile_path = vec!["profiles", name.as_str()];
+            profile_path.extend(&segments);
+            diagnostics::push(
+                Diagnostic::new(path, format!("unknown key `{key}` in `profiles.{name}`"))
+                    .with_span(source, diagnostics::key_span(document, &profile_path))
+                    .with_help(Some("this key is ignored".to_string())),
+            );
+        }
+
+        return match config {
+            Ok(mut config) => {
+                config.profiles = std::mem::take(&mut self.profiles);
+                config.active_profiles = active;
+                config
+            }
+            Err(err) => {
+                diagnostics::push(
+                    Diagnostic::new(
+                        path,
+                        format!(
+                            "{} with the profiles {active:?}, using the config without them",
+                            err.message()
+                        ),
+                    )
+                    .with_help(diagnostics::suggest_from_message(err.message())),
+                );
+                self
+            }
+        };
+    }
+}
+
+/// the keys a profile accepts, for suggesting fixes for misspelled ones
+fn profile_keys() -> Vec<&'static str> {
+    let mut keys = vec!["hostname", "env"];
+    keys.extend(known_keys("").iter().filter(|key| **key != "profiles"));
+    return keys;
+}
+
+/// the sections of a profile that are part of the config, the rest are
+/// reported when the profile is read
+fn without_unknown(mut sections: toml::Table) -> toml::Table {
+    let known = known_keys("");
+    sections.retain(|key, _| known.contains(&key.as_str()) && key != "profiles");
+    return sections;
+}
+
+/// merges `overrides` into `table`, tables are merged key by key and
+/// anything else is replaced
+fn merge_tables(table: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(value)) => {
+                merge_tables(existing, value)
+            }
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+/// the value at a path like the ones `serde_ignored` gives, array indices
+/// included
+fn lookup<'a>(table: &'a toml::Table, path: &[&str]) -> Option<&'a toml::Value> {
+    let (first, rest) = path.split_first()?;
+    let mut value = table.get(*first)?;
+
+    for segment in rest {
+        value = match (value, segment.parse::<usize>()) {
+            (toml::Value::Array(array), Ok(index)) => array.get(index)?,
+            (toml::Value::Table(table), _) => table.get(*segment)?,
+            _ => return None,
+        };
+    }
+
+    return Some(value);
+}
+
+/// the machine's hostname, `None` if it can't be read
+fn hostname() -> Option<String> {
+    let mut buffer = [0u8; 256];
+    if unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) } != 0 {
+        return None;
+    }
+
+    let len = buffer
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(buffer.len());
+    return String::from_utf8(buffer[..len].to_vec()).ok();
+}
+
+/// the keys each table of the config accepts, used to suggest fixes for
+/// misspelled keys
+///
+/// `table` is the path of the table with array indices removed, like
+/// `services.custom.sensors`
+fn known_keys(table: &str) -> &'static [&'static str] {
+    match table {
+        "" => &[
+            "log",
+            "services",
+            "ipc",
+            "watchdog",
+            "compositor",
+            "modules",
+            "bar",
+            "widgets",
+            "icons",
+            "profiles",
+        ],
+        "services" => &["audio", "custom", "weather", "kdeconnect", "screenshot"],
+        "log" => &["level"],
+        "services.audio" => &[
+            "restore",
+            "update_interval_ms",
+            "channel_capacity",
+            "bluetooth_profile",
+            "bluetooth_autoswitch",
+            "eq_presets",
+        ],
+        "services.custom" => &["sensors"],
+        "services.custom.sensors" => &["name", "command", "interval_ms", "timeout_ms", "parse"],
+        "services.weather" => &[
+            "provider",
+            "location",
+            "latitude",
+            "longitude",
+            "units",
+            "interval_minutes",
+            "forecast_days",
+        ],
+        "services.kdeconnect" => &["forward_notifications"],
+        "services.screenshot" => &["directory"],
+        "ipc" => &["remote"],
+        "ipc.remote" => &["enabled", "address", "token", "tls_cert", "tls_key"],
+        "watchdog" => &["enabled", "threshold_ms"],
+        "compositor" => &["window_fallback"],
+        "modules" => &[
+            "dirs",
+            "enabled",
+            "disabled",
+            "order",
+            "max_tree_bytes",
+            "max_memory_bytes",
+            "max_tree_depth",
+            "max_tree_nodes",
+            "callback_budget_ms",
+            "call_timeout_ms",
+            "max_traps",
+            "crash_restarts",
+            "open_uri",
+            "spawn",
+            "programs",
+            "fetch",
+            "session",
+            "screenshot",
+            "watch",
+            "dbus",
+            "wasi",
+            "settings",
+        ],
+        // tables under `modules.wasi` are named after modules
+        table if table.starts_with("modules.wasi.") => &["env", "args"],
+        "bar" => &[
+            "enabled",
+            "position",
+            "height",
+            "left",
+            "center",
+            "right",
+            "separator",
+        ],
+        "widgets" => &["clock", "quick_settings"],
+        "widgets.clock" => &["format", "locale"],
+        "widgets.quick_settings" => &["swipe", "tiles", "bluetooth_command", "dnd_command"],
+        "icons" => &["theme"],
+        _ => &[],
+    }
+}
+
+/// sets the config for the rest of the program
+///
+/// only the first call does anything
+pub fn init(config: Config) {
+    if let Err(_) = CONFIG.set(config) {
+        log::warn!("[config] config was already initialized");
+    }
+}
+
+/// gets the config, or the default config if `init` hasn't been called
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// `$HOME/.config/aurorashell`
+pub fn config_dir() -> anyhow::Result<PathBuf> {
+    let home = match env::var("HOME") {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("no environment variable `HOME` or it could not be interpreted");
+            return Err(e.into());
+        }
+    };
+
+    return Ok(PathBuf::from(home).join(".config/aurorashell"));
+}
+
+/// `$HOME/.local/state/aurorashell`, for what the shell remembers between
+/// starts
+pub fn state_dir() -> anyhow::Result<PathBuf> {
+    let home = match env::var("HOME") {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("no environment variable `HOME` or it could not be interpreted");
+            return Err(e.into());
+        }
+    };
+
+    return Ok(PathBuf::from(home).join(".local/state/aurorashell"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(path: &str, source: &str, hostname: Option<&str>, named: &str) -> Config {
+        let document = ImDocument::parse(source).unwrap();
+        let config: Config = toml::from_str(source).unwrap();
+        return config.with_profiles(Path::new(path), source, &document, hostname, named);
+    }
+
+    fn strings(list: &[&str]) -> Vec<String> {
+        return list.iter().map(|item| item.to_string()).collect();
+    }
+
+    #[test]
+    fn profile_matches_hostname_and_env() {
+        let profile = |source: &str| toml::from_str::<ProfileConfig>(source).unwrap();
+
+        assert!(!profile("").matches(Some("laptop")));
+        assert!(profile("hostname = \"laptop\"").matches(Some("laptop")));
+        assert!(profile("hostname = [\"desktop\", \"laptop\"]").matches(Some("laptop")));
+        assert!(!profile("hostname = \"desktop\"").matches(Some("laptop")));
+        assert!(!profile("hostname = \"laptop\"").matches(None));
+        assert!(!profile("env = { AURORASHELL_TEST_NEVER_SET = \"1\" }").matches(None));
+    }
+
+    #[test]
+    fn profile_without_conditions_only_applies_when_named() {
+        let source = r#"
+            [modules]
+            spawn = ["base"]
+
+            [profiles.work.modules]
+            spawn = ["work"]
+        "#;
+
+        let config = load("unnamed.toml", source, Some("laptop"), "");
+        assert_eq!(config.modules.spawn, strings(&["base"]));
+        assert!(config.active_profiles.is_empty());
+
+        let config = load("named.toml", source, Some("laptop"), "work");
+        assert_eq!(config.modules.spawn, strings(&["work"]));
+        assert_eq!(config.active_profiles, strings(&["work"]));
+    }
+
+    #[test]
+    fn named_profiles_go_last() {
+        let source = r#"
+            [profiles.work.modules]
+            spawn = ["work"]
+
+            [profiles.laptop]
+            hostname = "laptop"
+            modules.spawn = ["laptop"]
+        "#;
+
+        let config = load("order.toml", source, Some("laptop"), "work");
+        assert_eq!(config.active_profiles, strings(&["laptop", "work"]));
+        assert_eq!(config.modules.spawn, strings(&["work"]));
+    }
+
+    #[test]
+    fn merge_tables_merges_tables_and_replaces_arrays() {
+        let mut table: toml::Table = toml::from_str(
+            r#"
+            [modules]
+            spawn = ["a", "b"]
+            fetch = ["weather"]
+            "#,
+        )
+        .unwrap();
+        let overrides: toml::Table = toml::from_str("modules.spawn = [\"c\"]").unwrap();
+
+        merge_tables(&mut table, overrides);
+
+        let expected: toml::Table = toml::from_str(
+            r#"
+            [modules]
+            spawn = ["c"]
+            fetch = ["weather"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(table, expected);
+    }
+
+    #[test]
+    fn lookup_follows_tables_and_arrays() {
+        let table: toml::Table =
+            toml::from_str("bar.left = [\"clock\", { group = [\"audio\"] }]").unwrap();
+
+        assert_eq!(
+            lookup(&table, &["bar", "left", "0"]),
+            Some(&toml::Value::String("clock".to_string()))
+        );
+        assert!(lookup(&table, &["bar", "left", "1", "group"]).is_some());
+        assert!(lookup(&table, &["bar", "left", "2"]).is_none());
+        assert!(lookup(&table, &["bar", "right"]).is_none());
+        assert!(lookup(&table, &[]).is_none());
+    }
+
+    #[test]
+    fn unknown_keys_are_reported_in_their_profile() {
+        let path = "unknown-in-profile.toml";
+        let source = r#"
+            [profiles.laptop]
+            hostname = "laptop"
+            modules.spwan = ["x"]
+        "#;
+
+        load(path, source, Some("laptop"), "");
+
+        let messages: Vec<String> = diagnostics::take()
+            .into_iter()
+            .filter(|diagnostic| diagnostic.file == Path::new(path))
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        assert_eq!(
+            messages,
+            strings(&["unknown key `modules.spwan` in `profiles.laptop`"])
+        );
+    }
+}
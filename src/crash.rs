@@ -0,0 +1,216 @@
+//! writes a bundle to `~/.cache/aurorashell/crash/` when any thread panics
+//!
+//! the bundle is a directory with the panic, recent logs, loaded modules and
+//! versions so a report has more to go on than "it just disappeared". the
+//! next time the shell starts it offers to open any new bundles
+
+use crate::notify;
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs, thread};
+
+/// the amount of log lines kept for the bundle
+const LOG_CAPACITY: usize = 500;
+
+/// created in a bundle once it has been shown to the user
+const REPORTED_MARKER: &str = ".reported";
+
+static LOGS: LazyLock<Mutex<VecDeque<String>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(LOG_CAPACITY)));
+
+static MODULES: LazyLock<Mutex<Vec<String>>> = LazyLock::new(|| Mutex::new(vec![]));
+
+/// installs the panic hook, the default hook still runs after the bundle is
+/// written
+pub fn init() {
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        match write_bundle(info) {
+            Ok(path) => eprintln!("[crash] crash report written to {}", path.display()),
+            Err(err) => eprintln!("[crash] could not write crash report: {err}"),
+        }
+
+        default_hook(info);
+    }));
+}
+
+/// a fern output that keeps the latest log lines for the bundle
+pub fn log_output() -> fern::Output {
+    fern::Output::call(|record| {
+        let line = format!("[{}] {}", record.level(), record.args());
+
+        if let Ok(mut logs) = LOGS.lock() {
+            if logs.len() == LOG_CAPACITY {
+                logs.pop_front();
+            }
+            logs.push_back(line);
+        }
+    })
+}
+
+/// adds a module to the list written to the bundle
+pub fn record_module(runtime: &str, name: &str, file_path: &Path) {
+    if let Ok(mut modules) = MODULES.lock() {
+        modules.push(format!("{runtime} {name} {}", file_path.display()));
+    }
+}
+
+/// forgets the modules of a runtime, used when it restarts
+pub fn clear_modules(runtime: &str) {
+    if let Ok(mut modules) = MODULES.lock() {
+        let prefix = format!("{runtime} ");
+        modules.retain(|module| !module.starts_with(&prefix));
+    }
+}
+
+/// `$HOME/.cache/aurorashell/crash`
+pub fn crash_dir() -> anyhow::Result<PathBuf> {
+    let home = match env::var("HOME") {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("no environment variable `HOME` or it could not be interpreted");
+            return Err(e.into());
+        }
+    };
+
+    return Ok(PathBuf::from(home).join(".cache/aurorashell/crash"));
+}
+
+/// shows a notification for bundles written since the last start
+pub fn notify_unreported() {
+    let dir = match crash_dir() {
+        Ok(dir) => dir,
+        Err(_) => return,
+    };
+
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        // no crashes yet
+        Err(_) => return,
+    };
+
+    let mut unreported = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir() && !path.join(REPORTED_MARKER).exists())
+        .collect::<Vec<PathBuf>>();
+    unreported.sort();
+
+    let latest = match unreported.last() {
+        Some(latest) => latest.clone(),
+        None => return,
+    };
+
+    for bundle in &unreported {
+        if let Err(err) = fs::write(bundle.join(REPORTED_MARKER), "") {
+            log::warn!("[crash] could not mark {:?} as reported: {err}", bundle);
+        }
+    }
+
+    log::warn!(
+        "[crash] aurorashell crashed last time, report at {}",
+        latest.display()
+    );
+
+    notify::send_with_action(
+        "aurorashell crashed",
+        &format!(
+            "a crash report was saved to {}\nplease include it when reporting the bug",
+            latest.display()
+        ),
+        "Open report",
+        move || {
+            if let Err(err) = Command::new("xdg-open").arg(&latest).status() {
+                log::warn!("[crash] could not open crash report: {err}");
+            }
+        },
+    );
+}
+
+fn write_bundle(info: &PanicHookInfo) -> anyhow::Result<PathBuf> {
+    let now = SystemTime::now();
+    let secs = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+
+    let bundle = crash_dir()?.join(format!("{secs}-{}", std::process::id()));
+    fs::create_dir_all(&bundle)?;
+
+    let mut panic = String::new();
+    let _ = writeln!(panic, "time: {}", humantime::format_rfc3339_millis(now));
+    let _ = writeln!(
+        panic,
+        "thread: {}",
+        thread::current().name().unwrap_or("<unnamed>")
+    );
+    if let Some(location) = info.location() {
+        let _ = writeln!(panic, "location: {location}");
+    }
+    let _ = writeln!(panic, "message: {}", panic_message(info));
+    let _ = writeln!(
+        panic,
+        "\nbacktrace:\n{}",
+        std::backtrace::Backtrace::force_capture()
+    );
+    fs::write(bundle.join("panic.txt"), panic)?;
+
+    // the panic might have happened while a lock was held, so don't wait on
+    // them
+    let logs = match LOGS.try_lock() {
+        Ok(logs) => logs.iter().cloned().collect::<Vec<String>>().join("\n"),
+        Err(_) => "<logs were locked>".to_string(),
+    };
+    fs::write(bundle.join("log.txt"), logs)?;
+
+    let modules = match MODULES.try_lock() {
+        Ok(modules) => modules.join("\n"),
+        Err(_) => "<modules were locked>".to_string(),
+    };
+    fs::write(bundle.join("modules.txt"), modules)?;
+
+    fs::write(bundle.join("versions.txt"), versions())?;
+
+    return Ok(bundle);
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    if let Some(message) = info.payload().downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    if let Some(message) = info.payload().downcast_ref::<String>() {
+        return message.clone();
+    }
+    return "<non-string panic payload>".to_string();
+}
+
+fn versions() -> String {
+    let mut versions = String::new();
+
+    let _ = writeln!(versions, "aurorashell: {}", env!("CARGO_PKG_VERSION"));
+
+    if let Ok(kernel) = fs::read_to_string("/proc/sys/kernel/osrelease") {
+        let _ = writeln!(versions, "kernel: {}", kernel.trim());
+    }
+
+    if let Ok(os_release) = fs::read_to_string("/etc/os-release")
+        && let Some(name) = os_release
+            .lines()
+            .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+    {
+        let _ = writeln!(versions, "os: {}", name.trim_matches('"'));
+    }
+
+    for var in ["XDG_CURRENT_DESKTOP", "WAYLAND_DISPLAY"] {
+        if let Ok(value) = env::var(var) {
+            let _ = writeln!(versions, "{var}: {value}");
+        }
+    }
+
+    return versions;
+}
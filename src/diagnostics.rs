@@ -0,0 +1,255 @@
+//! human readable problems found in the config files
+//!
+//! reading `config.toml` and `colors.toml` doesn't fail startup, anything
+//! that can't be used falls back to its default and the problem is collected
+//! here. each problem is logged when it's found and `report` shows a summary
+//! once the shell is running
+
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+use toml_edit::{ImDocument, Item, TableLike};
+
+/// the amount of problems listed in the notification
+const NOTIFY_LIMIT: usize = 3;
+
+static DIAGNOSTICS: LazyLock<Mutex<Vec<Diagnostic>>> = LazyLock::new(|| Mutex::new(vec![]));
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    pub message: String,
+    /// where in the file the problem is, if it's tied to a place
+    pub location: Option<Location>,
+    /// a suggested fix
+    pub help: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Location {
+    /// starts at 1
+    pub line: usize,
+    /// starts at 1, counted in chars
+    pub column: usize,
+    /// the line the problem is on
+    pub source_line: String,
+    /// the amount of chars to underline
+    pub width: usize,
+}
+
+impl Diagnostic {
+    pub fn new(file: &Path, message: impl Into<String>) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            message: message.into(),
+            location: None,
+            help: None,
+        }
+    }
+
+    /// points the diagnostic at a byte range of `source`
+    pub fn with_span(mut self, source: &str, span: Option<Range<usize>>) -> Self {
+        if let Some(span) = span {
+            self.location = Some(Location::from_span(source, span));
+        }
+        self
+    }
+
+    pub fn with_help(mut self, help: Option<String>) -> Self {
+        self.help = help;
+        self
+    }
+
+    /// the file name and position, like `config.toml:4:1`
+    fn position(&self) -> String {
+        let name = self.file.file_name().map_or_else(
+            || self.file.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        );
+
+        match &self.location {
+            Some(location) => format!("{name}:{}:{}", location.line, location.column),
+            None => name,
+        }
+    }
+}
+
+impl Location {
+    fn from_span(source: &str, span: Range<usize>) -> Self {
+        let start = span.start.min(source.len());
+        let end = span.end.clamp(start, source.len());
+
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let source_line = source[line_start..line_end].trim_end_matches('\r');
+
+        Self {
+            line: source[..start].matches('\n').count() + 1,
+            column: source[line_start..start].chars().count() + 1,
+            source_line: source_line.to_string(),
+            width: source[start..end.min(line_end)].chars().count().max(1),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// formatted like a compiler error:
+    ///
+    /// ```text
+    /// config.toml:4:1: unknown key `intervl_ms` in `services.custom.sensors`
+    ///   |
+    /// 4 | intervl_ms = 5000
+    ///   | ^^^^^^^^^^
+    ///   = help: did you mean `interval_ms`?
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.position(), self.message)?;
+
+        let gutter = match &self.location {
+            Some(location) => {
+                let gutter = " ".repeat(location.line.to_string().len());
+                write!(
+                    f,
+                    "\n{gutter} |\n{} | {}\n{gutter} | {}{}",
+                    location.line,
+                    location.source_line,
+                    " ".repeat(location.column - 1),
+                    "^".repeat(location.width)
+                )?;
+                gutter
+            }
+            None => String::new(),
+        };
+
+        if let Some(help) = &self.help {
+            write!(f, "\n{gutter} = help: {help}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// adds a problem to the startup report
+pub fn push(diagnostic: Diagnostic) {
+    log::warn!("[config] {diagnostic}");
+
+    if let Ok(mut diagnostics) = DIAGNOSTICS.lock() {
+        diagnostics.push(diagnostic);
+    }
+}
+
+/// shows a notification summarizing the problems found so far, if there
+/// were any
+pub fn report() {
+    let diagnostics = take();
+
+    if diagnostics.is_empty() {
+        return;
+    }
+
+    let mut body = diagnostics
+        .iter()
+        .take(NOTIFY_LIMIT)
+        .map(|diagnostic| format!("{}: {}", diagnostic.position(), diagnostic.message))
+        .collect::<Vec<String>>();
+    if diagnostics.len() > NOTIFY_LIMIT {
+        body.push(format!("and {} more", diagnostics.len() - NOTIFY_LIMIT));
+    }
+    body.push("the defaults are used instead, see the log for details".to_string());
+
+    let summary = match diagnostics.len() {
+        1 => "aurorashell found a problem in its config".to_string(),
+        n => format!("aurorashell found {n} problems in its config"),
+    };
+
+    crate::notify::send(&summary, &body.join("\n"));
+}
+
+/// the problems found so far, leaving none behind
+pub fn take() -> Vec<Diagnostic> {
+    match DIAGNOSTICS.lock() {
+        Ok(mut diagnostics) => std::mem::take(&mut *diagnostics),
+        Err(_) => vec![],
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// helpers
+
+/// finds the span of the key at `path` in a toml document, like
+/// `["services", "custom", "sensors", "0", "name"]`
+///
+/// numbers index into arrays, if the full path can't be found the span of
+/// the deepest key that could be is returned
+pub fn key_span(document: &ImDocument<&str>, path: &[&str]) -> Option<Range<usize>> {
+    fn find(table: &dyn TableLike, path: &[&str]) -> Option<Range<usize>> {
+        let (segment, rest) = path.split_first()?;
+        let (key, item) = table.get_key_value(segment)?;
+
+        let found = match rest.split_first() {
+            None => None,
+            Some((next, after)) => match (next.parse::<usize>(), item) {
+                (Ok(index), Item::ArrayOfTables(tables)) => {
+                    tables.get(index).and_then(|table| find(table, after))
+                }
+                (Ok(index), _) => item
+                    .as_array()
+                    .and_then(|array| array.get(index))
+                    .and_then(|value| value.as_inline_table())
+                    .and_then(|table| find(table, after)),
+                (Err(_), _) => item.as_table_like().and_then(|table| find(table, rest)),
+            },
+        };
+
+        return found.or_else(|| key.span());
+    }
+
+    return find(document.as_table(), path);
+}
+
+/// suggests the closest of `candidates` to a misspelled `name`
+pub fn did_you_mean<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    return candidates
+        .iter()
+        .map(|candidate| (levenshtein(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate);
+}
+
+/// suggests a fix for serde's `unknown variant`/`unknown field` errors, which
+/// quote the unknown name first and the expected names after it
+pub fn suggest_from_message(message: &str) -> Option<String> {
+    if !message.starts_with("unknown variant") && !message.starts_with("unknown field") {
+        return None;
+    }
+
+    let quoted = message.split('`').skip(1).step_by(2).collect::<Vec<&str>>();
+    let (name, candidates) = quoted.split_first()?;
+
+    return did_you_mean(name, candidates).map(|candidate| format!("did you mean `{candidate}`?"));
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut row = (0..=b.len()).collect::<Vec<usize>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous + usize::from(a != *b);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+
+    return row[b.len()];
+}
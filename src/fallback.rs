@@ -0,0 +1,138 @@
+//! shows surfaces as plain windows when there's no layer shell, like under
+//! x11 or gnome, if `window_fallback` is set under `[compositor]`
+//!
+//! the windows are undecorated and kept above the other windows (or below
+//! them for the background and bottom layers), then moved to where the layer
+//! surface would have been anchored once the size of the monitor is known.
+//! the window manager picks the monitor, and exclusive zones and keyboard
+//! interactivity are left out
+
+use iced::platform_specific::shell::commands::layer_surface::{Anchor, Layer};
+use iced::runtime::platform_specific::wayland::layer_surface::SctkLayerSurfaceSettings;
+use iced::runtime::{self, task};
+use iced::window::{self, Id, Level, Position};
+use iced::{Point, Size, Task};
+
+/// where a window goes, from the layer surface it stands in for
+#[derive(Debug, Clone, Copy)]
+struct Placement {
+    anchor: Anchor,
+    /// `None` or 0 stretches between the margins like layer shell does
+    width: Option<u32>,
+    height: Option<u32>,
+    /// top, right, bottom and left
+    margin: [i32; 4],
+}
+
+impl Placement {
+    /// the position and size of the window on a monitor of `monitor`
+    fn place(&self, monitor: Size) -> (Point, Size) {
+        let [top, right, bottom, left] = self.margin.map(|margin| margin as f32);
+
+        let (x, width) = axis(
+            self.anchor.contains(Anchor::LEFT),
+            self.anchor.contains(Anchor::RIGHT),
+            self.width,
+            monitor.width,
+            (left, right),
+        );
+        let (y, height) = axis(
+            self.anchor.contains(Anchor::TOP),
+            self.anchor.contains(Anchor::BOTTOM),
+            self.height,
+            monitor.height,
+            (top, bottom),
+        );
+
+        return (Point::new(x, y), Size::new(width, height));
+    }
+}
+
+/// the position and length along one side of the monitor, anchored to the
+/// `start` edge, the `end` edge, both or neither (which centers it)
+fn axis(
+    start: bool,
+    end: bool,
+    length: Option<u32>,
+    monitor: f32,
+    margin: (f32, f32),
+) -> (f32, f32) {
+    let space = (monitor - margin.0 - margin.1).max(1.0);
+    let length = match length {
+        Some(length) if length > 0 => length as f32,
+        _ => space,
+    };
+
+    let position = match (start, end) {
+        (true, false) => margin.0,
+        (false, true) => monitor - margin.1 - length,
+        _ => margin.0 + (space - length) / 2.0,
+    };
+
+    return (position.max(0.0), length);
+}
+
+/// opens a window with the surface's id standing in for it
+pub fn open<Message>(settings: SctkLayerSurfaceSettings) -> Task<Message>
+where
+    Message: Send + 'static,
+{
+    let (width, height) = settings.size.unwrap_or((None, None));
+    let placement = Placement {
+        anchor: settings.anchor,
+        width,
+        height,
+        margin: [
+            settings.margin.top,
+            settings.margin.right,
+            settings.margin.bottom,
+            settings.margin.left,
+        ],
+    };
+
+    // sides that stretch are sized once the monitor is known
+    let side = |length: Option<u32>| length.filter(|length| *length > 0).unwrap_or(1) as f32;
+
+    let window_settings = window::Settings {
+        size: Size::new(side(width), side(height)),
+        position: Position::Default,
+        decorations: false,
+        resizable: false,
+        transparent: true,
+        level: match settings.layer {
+            Layer::Background | Layer::Bottom => Level::AlwaysOnBottom,
+            Layer::Top | Layer::Overlay => Level::AlwaysOnTop,
+        },
+        exit_on_close_request: false,
+        ..window::Settings::default()
+    };
+
+    let id = settings.id;
+    log::debug!("[compositor] opening surface {id:?} as a window at {placement:?}");
+
+    return task::oneshot(move |channel| {
+        runtime::Action::Window(runtime::window::Action::Open(id, window_settings, channel))
+    })
+    .then(|id| window::monitor_size(id).map(move |monitor| (id, monitor)))
+    .then(move |(id, monitor)| -> Task<()> {
+        match monitor {
+            Some(monitor) => {
+                let (position, size) = placement.place(monitor);
+                Task::batch([window::resize(id, size), window::move_to(id, position)])
+            }
+            None => {
+                log::warn!(
+                    "[compositor] the size of the monitor window {id:?} is on isn't known, \
+                     leaving it where the window manager put it"
+                );
+                Task::none()
+            }
+        }
+    })
+    .discard();
+}
+
+/// closes a window opened with `open`
+pub fn close<Message>(id: Id) -> Task<Message> {
+    window::close(id)
+}
@@ -0,0 +1,104 @@
+//! lets the shell boot from a state dump (`aurorashell dump-state`) with the
+//! services replaced by ones that replay the dumped state
+//!
+//! this is a dev tool for reproducing rendering bugs without needing the
+//! same audio devices, sensors etc as whoever hit the bug
+//!
+//! only audio and the custom sensors are in the dump, so they're the only
+//! services replayed. the ones that would read this machine's state instead
+//! (network, sysinfo, brightness, tray, weather and the rest) aren't started
+//! while a fixture is loaded, see `App::subscription`. clock, interval and
+//! ipc run as usual
+
+use crate::instrumented;
+use crate::services::ipc::protocol::StateSnapshot;
+use crate::services::{Service, ServiceEvent, ServiceRequest};
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::SinkExt;
+use iced::stream::channel;
+
+static FIXTURE: OnceLock<StateSnapshot> = OnceLock::new();
+
+/// reads a state dump and uses it for the rest of the program
+pub fn load(path: &Path) -> anyhow::Result<()> {
+    let snapshot: StateSnapshot = serde_json::from_slice(&std::fs::read(path)?)?;
+
+    if snapshot.version != StateSnapshot::VERSION {
+        return Err(anyhow!(
+            "[fixture] {:?} is version {} but only version {} is supported",
+            path,
+            snapshot.version,
+            StateSnapshot::VERSION
+        ));
+    }
+
+    log::warn!(
+        "[fixture] loaded state from {:?} (taken at {}), audio and custom sensors are \
+         replayed and the other services aren't started",
+        path,
+        snapshot.taken_at
+    );
+
+    if FIXTURE.set(snapshot).is_err() {
+        log::warn!("[fixture] fixture was already loaded");
+    }
+
+    Ok(())
+}
+
+/// the loaded fixture, if any
+pub fn get() -> Option<&'static StateSnapshot> {
+    FIXTURE.get()
+}
+
+/// stands in for `S::subscribe`, emitting `events` on start and again
+/// whenever a module subscribes so late modules get the state too
+///
+/// all other requests are logged and dropped
+pub fn subscribe<S>(name: &'static str, events: Vec<S::Event>) -> Subscription<ServiceEvent<S>>
+where
+    S: Service + 'static,
+    S::Event: Send + 'static,
+    S::Request: Send + 'static,
+    S::SubscriptionData: Send + 'static,
+{
+    Subscription::run_with_id(
+        (std::any::TypeId::of::<S>(), "fixture"),
+        channel(64, async move |mut chan| {
+            let (tx, rx) = instrumented::bounded::<ServiceRequest<S>>("fixture.requests", 64);
+
+            if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                log::error!("[fixture] [service:{name}] could not send init event: {err}");
+                return;
+            }
+
+            loop {
+                for event in events.iter().cloned() {
+                    if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                        log::error!("[fixture] [service:{name}] could not send event: {err}");
+                    }
+                }
+
+                // wait for a module to subscribe before replaying
+                loop {
+                    match rx.recv_async().await {
+                        Ok(ServiceRequest::SubscribeModule { .. }) => break,
+                        Ok(ServiceRequest::Request { request }) => {
+                            log::info!("[fixture] [service:{name}] ignoring request: {request:?}");
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { .. }) => {}
+                        Err(_) => {
+                            // nothing can send requests anymore, just idle
+                            std::future::pending::<()>().await;
+                        }
+                    }
+                }
+            }
+        }),
+    )
+}
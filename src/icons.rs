@@ -0,0 +1,224 @@
+//! finds icon files by name, following the freedesktop icon theme spec
+//!
+//! the theme set in `[icons]` is looked in first, then the themes it
+//! inherits from and `hicolor` last, which every app installs its icons
+//! into. see <https://specifications.freedesktop.org/icon-theme-spec/latest/>
+
+use crate::config;
+
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+/// the theme every other theme falls back to
+const FALLBACK_THEME: &str = "hicolor";
+
+/// stops themes that inherit from each other from being followed forever
+const MAX_THEMES: usize = 16;
+
+/// the icon file called `name` closest to `size` logical pixels, which
+/// could already be a path
+///
+/// `extra_dirs` are looked in before any theme, like the theme path a tray
+/// item gives
+pub fn find(name: &str, size: u32, extra_dirs: &[PathBuf]) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+
+    for dir in extra_dirs {
+        if let Some(path) = find_in(dir, name) {
+            return Some(path);
+        }
+    }
+
+    let mut themes: Vec<String> = vec![];
+    if let Some(theme) = &config::get().icons.theme {
+        themes.push(theme.clone());
+    }
+
+    // themes are added as they're inherited, so the list grows while it's
+    // walked
+    let mut index = 0;
+    while index < themes.len() && index < MAX_THEMES {
+        let theme = Theme::read(&themes[index]);
+
+        for dir in theme.dirs_by_size(size) {
+            if let Some(path) = find_in(&dir, name) {
+                return Some(path);
+            }
+        }
+
+        for parent in theme.inherits {
+            if !themes.contains(&parent) && parent != FALLBACK_THEME {
+                themes.push(parent);
+            }
+        }
+
+        index += 1;
+    }
+
+    for dir in Theme::read(FALLBACK_THEME).dirs_by_size(size) {
+        if let Some(path) = find_in(&dir, name) {
+            return Some(path);
+        }
+    }
+
+    // icons that aren't in any theme
+    for data_dir in data_dirs() {
+        if let Some(path) = find_in(&data_dir.join("pixmaps"), name) {
+            return Some(path);
+        }
+    }
+
+    return None;
+}
+
+fn find_in(dir: &Path, name: &str) -> Option<PathBuf> {
+    ["svg", "png"]
+        .iter()
+        .map(|extension| dir.join(format!("{name}.{extension}")))
+        .find(|path| path.is_file())
+}
+
+/// an icon theme's `index.theme`, read from every base directory it's in
+#[derive(Debug, Default)]
+struct Theme {
+    /// the theme's folder in each base directory
+    roots: Vec<PathBuf>,
+    directories: Vec<ThemeDirectory>,
+    inherits: Vec<String>,
+}
+
+#[derive(Debug)]
+struct ThemeDirectory {
+    path: String,
+    size: u32,
+    scalable: bool,
+}
+
+impl Theme {
+    /// a theme without an `index.theme` has no directories, so nothing is
+    /// found in it
+    fn read(name: &str) -> Self {
+        let mut theme = Self::default();
+
+        for base in base_dirs() {
+            let root = base.join(name);
+            if !root.is_dir() {
+                continue;
+            }
+
+            // the first `index.theme` found is the one that counts
+            if theme.directories.is_empty()
+                && let Ok(index) = fs::read_to_string(root.join("index.theme"))
+            {
+                theme.parse_index(&index);
+            }
+
+            theme.roots.push(root);
+        }
+
+        return theme;
+    }
+
+    fn parse_index(&mut self, index: &str) {
+        let mut directories: Vec<String> = vec![];
+        let mut section = String::new();
+
+        for line in index.lines().map(str::trim) {
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                section = name.to_string();
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            if section == "Icon Theme" {
+                let list = || value.split(',').map(|item| item.trim().to_string());
+                match key {
+                    "Directories" | "ScaledDirectories" => directories.extend(list()),
+                    "Inherits" => self.inherits.extend(list()),
+                    _ => {}
+                }
+            } else if directories.contains(&section) {
+                let directory = match self.directories.iter_mut().find(|dir| dir.path == section) {
+                    Some(directory) => directory,
+                    None => {
+                        self.directories.push(ThemeDirectory {
+                            path: section.clone(),
+                            size: 0,
+                            scalable: false,
+                        });
+                        self.directories
+                            .last_mut()
+                            .expect("a directory was just pushed")
+                    }
+                };
+
+                match key {
+                    "Size" => directory.size = value.parse().unwrap_or(0),
+                    "Type" => directory.scalable = value == "Scalable",
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// the theme's directories with the ones that fit `size` best first,
+    /// scalable ones fit every size
+    fn dirs_by_size(&self, size: u32) -> Vec<PathBuf> {
+        let mut directories: Vec<&ThemeDirectory> = self.directories.iter().collect();
+        directories.sort_by_key(|dir| match dir.scalable {
+            true => 0,
+            false => dir.size.abs_diff(size) + 1,
+        });
+
+        directories
+            .iter()
+            .flat_map(|dir| self.roots.iter().map(|root| root.join(&dir.path)))
+            .collect()
+    }
+}
+
+/// where themes are looked for, `$HOME/.icons` then the `icons` folder of
+/// every data directory
+fn base_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+
+    if let Ok(home) = env::var("HOME") {
+        dirs.push(PathBuf::from(home).join(".icons"));
+    }
+
+    dirs.extend(data_dirs().into_iter().map(|dir| dir.join("icons")));
+
+    return dirs;
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+
+    match env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => dirs.push(PathBuf::from(dir)),
+        _ => {
+            if let Ok(home) = env::var("HOME") {
+                dirs.push(PathBuf::from(home).join(".local/share"));
+            }
+        }
+    }
+
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(data_dirs.split(':').map(PathBuf::from));
+
+    return dirs;
+}
@@ -0,0 +1,88 @@
+//! senders that keep track of how their channel is doing in `metrics`
+//!
+//! each channel is named like `service.audio.requests`, and every send updates:
+//! - `channel.<name>.sent` and `channel.<name>.dropped`, the values that were
+//!   sent and the ones that weren't because the receiver is gone
+//! - `channel.<name>.send_micros` and `channel.<name>.max_send_micros`, how
+//!   long sending took in total and at most. sending only takes a while when
+//!   the channel is full
+//! - `channel.<name>.depth` and `channel.<name>.max_depth`, how many values
+//!   were waiting in the channel after the last send and at most. only flume
+//!   channels have these, iced's channels can't be asked
+
+use crate::metrics;
+
+use std::time::Instant;
+
+use iced::futures::SinkExt;
+use iced::futures::channel::mpsc;
+
+/// the sender of a flume or iced channel, with every send recorded under the
+/// channel's name
+#[derive(Debug, Clone)]
+pub struct InstrumentedSender<S> {
+    name: &'static str,
+    inner: S,
+}
+
+/// creates a bounded flume channel with an instrumented sender
+pub fn bounded<T>(
+    name: &'static str,
+    capacity: usize,
+) -> (InstrumentedSender<flume::Sender<T>>, flume::Receiver<T>) {
+    let (tx, rx) = flume::bounded(capacity);
+    return (InstrumentedSender::new(name, tx), rx);
+}
+
+impl<S> InstrumentedSender<S> {
+    pub fn new(name: &'static str, inner: S) -> Self {
+        Self { name, inner }
+    }
+
+    fn record(&self, start: Instant, sent: bool, depth: Option<usize>) {
+        let name = self.name;
+        let micros = start.elapsed().as_micros() as u64;
+
+        metrics::add(format!("channel.{name}.send_micros"), micros);
+        metrics::max(format!("channel.{name}.max_send_micros"), micros);
+
+        if sent {
+            metrics::increment(format!("channel.{name}.sent"));
+        } else {
+            metrics::increment(format!("channel.{name}.dropped"));
+        }
+
+        if let Some(depth) = depth {
+            metrics::set(format!("channel.{name}.depth"), depth as u64);
+            metrics::max(format!("channel.{name}.max_depth"), depth as u64);
+        }
+    }
+}
+
+impl<T> InstrumentedSender<flume::Sender<T>> {
+    /// sends a value, blocking while the channel is full
+    pub fn send(&self, value: T) -> Result<(), flume::SendError<T>> {
+        let start = Instant::now();
+        let result = self.inner.send(value);
+        self.record(start, result.is_ok(), Some(self.inner.len()));
+        return result;
+    }
+
+    /// sends a value, waiting while the channel is full
+    pub async fn send_async(&self, value: T) -> Result<(), flume::SendError<T>> {
+        let start = Instant::now();
+        let result = self.inner.send_async(value).await;
+        self.record(start, result.is_ok(), Some(self.inner.len()));
+        return result;
+    }
+}
+
+impl<T> InstrumentedSender<mpsc::Sender<T>> {
+    /// sends a value, waiting while the channel is full
+    pub async fn send(&mut self, value: T) -> Result<(), mpsc::SendError> {
+        let start = Instant::now();
+        let result = self.inner.send(value).await;
+        self.record(start, result.is_ok(), None);
+        return result;
+    }
+}
@@ -1,14 +1,37 @@
 mod app;
+mod audit;
+mod builtin;
+mod check;
+mod compositor;
+mod config;
+mod crash;
+mod diagnostics;
+mod fallback;
+mod fixture;
+mod icons;
+mod instrumented;
+mod metrics;
+mod notify;
+mod open;
+mod outputs;
 mod runtime;
 mod services;
+mod startup;
 mod theme;
+mod touch;
+mod unicode;
+mod watchdog;
 
 use app::App;
+use config::BarWidget;
+use services::ipc;
+use services::ipc::protocol::{Query, Response, ServiceName};
 
-use std::time::SystemTime;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use clap::Parser;
 use clap::builder::TypedValueParser;
+use clap::{Parser, Subcommand, ValueEnum};
 use fern::colors::{Color, ColoredLevelConfig};
 use log::LevelFilter;
 
@@ -18,12 +41,408 @@ struct Args {
     /// add more v's to increase verbosity (example: `-vvv`)
     #[arg(short = 'v', long, action = clap::ArgAction::Count)]
     verbosity: u8,
-    /// changes the log level
-    #[arg(
-        long = "log-level",
-        default_value_t = LevelFilter::Info,
-    )]
-    log_level: LevelFilter,
+    /// changes the log level, wins over `level` under `[log]` in the config.
+    /// info when neither is set
+    #[arg(long = "log-level")]
+    log_level: Option<LevelFilter>,
+    /// (dev) boots the shell from a `dump-state` file with services mocked
+    #[arg(long = "load-state-fixture", value_name = "PATH")]
+    load_state_fixture: Option<PathBuf>,
+    /// (dev) writes every message the app handles to a file, for finding
+    /// messages that arrive out of order
+    ///
+    /// defaults to `~/.cache/aurorashell/audit-<unix time>.jsonl`, check it
+    /// with `aurorashell audit analyze`
+    #[arg(long = "audit-messages", value_name = "PATH", num_args = 0..=1)]
+    audit_messages: Option<Option<PathBuf>>,
+    /// (dev) opens the module inspector when the shell starts, see
+    /// `aurorashell modules inspect`
+    #[arg(long)]
+    inspector: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// starts the shell, the same as running it without a command
+    Run,
+    /// asks the running shell for its state
+    Query {
+        target: QueryTarget,
+        /// prints the response as json
+        #[arg(long)]
+        json: bool,
+    },
+    /// writes the running shell's state to a json file for debugging
+    DumpState {
+        /// where to write the dump, `-` for stdout
+        ///
+        /// defaults to `aurorashell-state-<unix time>.json`
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// opens a built-in widget's popup, or closes it if it's open
+    Toggle { widget: ToggleTarget },
+    /// re-reads the theme and the bar's config without restarting
+    Reload,
+    /// tells the shell do not disturb was turned on or off, for notification
+    /// daemons toggled outside of it. notifications from phones aren't
+    /// forwarded while it's on
+    Dnd { state: DndState },
+    /// the same as `modules install`
+    #[command(hide = true)]
+    Install { path: PathBuf },
+    /// reads the config, the theme and every module without starting the
+    /// shell, printing the problems found. exits with an error if there
+    /// are any, for checking dotfiles before committing them
+    Check,
+    /// manages the running shell's services
+    Service {
+        #[command(subcommand)]
+        command: ServiceCommand,
+    },
+    /// manages the running shell's modules
+    Modules {
+        #[command(subcommand)]
+        command: ModulesCommand,
+    },
+    /// (dev) works with files written by `--audit-messages`
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ServiceCommand {
+    /// stops a service and starts it again without touching the other
+    /// services or modules, like after restarting pulseaudio
+    Restart { service: ServiceTarget },
+}
+
+#[derive(Subcommand)]
+enum ModulesCommand {
+    /// lists the running shell's modules
+    List {
+        /// prints the response as json
+        #[arg(long)]
+        json: bool,
+    },
+    /// sets up a module without installing it or starting the shell,
+    /// printing the surfaces and registers it asks for and its problems
+    Validate { path: PathBuf },
+    /// precompiles a module built for `wasm32-wasip1` into the modules
+    /// directory, so it starts faster than a `.wasm`
+    ///
+    /// the precompiled module only works with this version of aurorashell,
+    /// install it again after updating
+    Install { path: PathBuf },
+    /// loads a module again from its file, or every module if no name is
+    /// given
+    Reload { name: Option<String> },
+    /// closes one of a module's surfaces, or opens it again if it's closed
+    ToggleSurface {
+        module: String,
+        /// the id the module gave the surface
+        surface: u32,
+    },
+    /// opens a panel showing what each module is doing, like its renders
+    /// and events a second and its last error, or closes it if it's open
+    Inspect,
+}
+
+#[derive(Subcommand)]
+enum AuditCommand {
+    /// looks for missing messages and messages in the wrong order
+    Analyze { path: PathBuf },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DndState {
+    On,
+    Off,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum QueryTarget {
+    /// the loaded modules
+    Modules,
+    /// whether each service is running
+    Services,
+    /// audio devices and their volumes
+    Audio,
+    /// counters for what the shell is doing
+    Metrics,
+    /// the wayland protocols the compositor supports
+    Compositor,
+}
+
+impl From<QueryTarget> for Query {
+    fn from(target: QueryTarget) -> Self {
+        match target {
+            QueryTarget::Modules => Query::Modules,
+            QueryTarget::Services => Query::Services,
+            QueryTarget::Audio => Query::Audio,
+            QueryTarget::Metrics => Query::Metrics,
+            QueryTarget::Compositor => Query::Compositor,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ToggleTarget {
+    /// the calendar
+    Clock,
+    /// the forecast
+    Weather,
+    /// audio devices
+    Audio,
+    /// the quick settings panel
+    QuickSettings,
+}
+
+impl From<ToggleTarget> for BarWidget {
+    fn from(target: ToggleTarget) -> Self {
+        match target {
+            ToggleTarget::Clock => BarWidget::Clock,
+            ToggleTarget::Weather => BarWidget::Weather,
+            ToggleTarget::Audio => BarWidget::Audio,
+            ToggleTarget::QuickSettings => BarWidget::QuickSettings,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ServiceTarget {
+    Appearance,
+    Audio,
+    Brightness,
+    Clock,
+    Custom,
+    Dbus,
+    Hotkeys,
+    Inhibit,
+    Interval,
+    Ipc,
+    #[value(name = "kdeconnect")]
+    KdeConnect,
+    Network,
+    Screenshot,
+    Session,
+    Sysinfo,
+    Tray,
+    Watch,
+    Weather,
+}
+
+impl From<ServiceTarget> for ServiceName {
+    fn from(target: ServiceTarget) -> Self {
+        match target {
+            ServiceTarget::Appearance => ServiceName::Appearance,
+            ServiceTarget::Audio => ServiceName::Audio,
+            ServiceTarget::Brightness => ServiceName::Brightness,
+            ServiceTarget::Clock => ServiceName::Clock,
+            ServiceTarget::Custom => ServiceName::Custom,
+            ServiceTarget::Dbus => ServiceName::Dbus,
+            ServiceTarget::Hotkeys => ServiceName::Hotkeys,
+            ServiceTarget::Inhibit => ServiceName::Inhibit,
+            ServiceTarget::Interval => ServiceName::Interval,
+            ServiceTarget::Ipc => ServiceName::Ipc,
+            ServiceTarget::KdeConnect => ServiceName::KdeConnect,
+            ServiceTarget::Network => ServiceName::Network,
+            ServiceTarget::Screenshot => ServiceName::Screenshot,
+            ServiceTarget::Session => ServiceName::Session,
+            ServiceTarget::Sysinfo => ServiceName::Sysinfo,
+            ServiceTarget::Tray => ServiceName::Tray,
+            ServiceTarget::Watch => ServiceName::Watch,
+            ServiceTarget::Weather => ServiceName::Weather,
+        }
+    }
+}
+
+/// runs a cli command against the running shell
+fn run_command(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Run => unreachable!("main starts the shell itself"),
+        Command::Query { target, json } => {
+            let response = ipc::client::query(target.into())?;
+
+            if json {
+                println!("{}", serde_json::to_string(&response)?);
+            } else {
+                print!("{response}");
+            }
+
+            if let Response::Error { message } = response {
+                return Err(anyhow::anyhow!(message));
+            }
+        }
+        Command::DumpState { output } => {
+            let snapshot = match ipc::client::query(Query::DumpState)? {
+                Response::State(snapshot) => snapshot,
+                Response::Error { message } => return Err(anyhow::anyhow!(message)),
+                response => {
+                    return Err(anyhow::anyhow!("unexpected response: {response:?}"));
+                }
+            };
+
+            let json = serde_json::to_string_pretty(&snapshot)?;
+
+            let output = output.unwrap_or_else(|| {
+                let secs = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |time| time.as_secs());
+                PathBuf::from(format!("aurorashell-state-{secs}.json"))
+            });
+
+            if output.as_os_str() == "-" {
+                println!("{json}");
+            } else {
+                std::fs::write(&output, json)?;
+                eprintln!("state written to {}", output.display());
+            }
+        }
+        Command::Toggle { widget } => {
+            let query = Query::TogglePopup {
+                widget: widget.into(),
+            };
+
+            match ipc::client::query(query)? {
+                Response::Done => {}
+                Response::Error { message } => return Err(anyhow::anyhow!(message)),
+                response => {
+                    return Err(anyhow::anyhow!("unexpected response: {response:?}"));
+                }
+            }
+        }
+        Command::Reload => match ipc::client::query(Query::Reload)? {
+            Response::Done => {}
+            Response::Error { message } => return Err(anyhow::anyhow!(message)),
+            response => {
+                return Err(anyhow::anyhow!("unexpected response: {response:?}"));
+            }
+        },
+        Command::Dnd { state } => {
+            let query = Query::DoNotDisturb {
+                enabled: matches!(state, DndState::On),
+            };
+
+            match ipc::client::query(query)? {
+                Response::Done => {}
+                Response::Error { message } => return Err(anyhow::anyhow!(message)),
+                response => {
+                    return Err(anyhow::anyhow!("unexpected response: {response:?}"));
+                }
+            }
+        }
+        Command::Install { path }
+        | Command::Modules {
+            command: ModulesCommand::Install { path },
+        } => {
+            let output = runtime::wasm::install_module(&path)?;
+            eprintln!("installed to {}", output.display());
+        }
+        Command::Modules {
+            command: ModulesCommand::List { json },
+        } => {
+            let response = ipc::client::query(Query::Modules)?;
+
+            if json {
+                println!("{}", serde_json::to_string(&response)?);
+            } else {
+                print!("{response}");
+            }
+
+            if let Response::Error { message } = response {
+                return Err(anyhow::anyhow!(message));
+            }
+        }
+        Command::Modules {
+            command: ModulesCommand::Validate { path },
+        } => {
+            let report = check::module(&path)?;
+            print!("{report}");
+
+            if report.problems() != 0 {
+                return Err(anyhow::anyhow!("found {} problem(s)", report.problems()));
+            }
+        }
+        Command::Check => {
+            let report = check::run()?;
+            print!("{report}");
+
+            if report.problems() != 0 {
+                return Err(anyhow::anyhow!("found {} problem(s)", report.problems()));
+            }
+        }
+        Command::Service {
+            command: ServiceCommand::Restart { service },
+        } => {
+            let query = Query::RestartService {
+                service: service.into(),
+            };
+
+            match ipc::client::query(query)? {
+                Response::Done => {}
+                Response::Error { message } => return Err(anyhow::anyhow!(message)),
+                response => {
+                    return Err(anyhow::anyhow!("unexpected response: {response:?}"));
+                }
+            }
+        }
+        Command::Modules {
+            command: ModulesCommand::Reload { name },
+        } => {
+            let query = Query::ReloadModules { module: name };
+
+            match ipc::client::query(query)? {
+                Response::Done => {}
+                Response::Error { message } => return Err(anyhow::anyhow!(message)),
+                response => {
+                    return Err(anyhow::anyhow!("unexpected response: {response:?}"));
+                }
+            }
+        }
+        Command::Modules {
+            command: ModulesCommand::ToggleSurface { module, surface },
+        } => {
+            let query = Query::ToggleSurface { module, surface };
+
+            match ipc::client::query(query)? {
+                Response::Done => {}
+                Response::Error { message } => return Err(anyhow::anyhow!(message)),
+                response => {
+                    return Err(anyhow::anyhow!("unexpected response: {response:?}"));
+                }
+            }
+        }
+        Command::Modules {
+            command: ModulesCommand::Inspect,
+        } => match ipc::client::query(Query::ToggleInspector)? {
+            Response::Done => {}
+            Response::Error { message } => return Err(anyhow::anyhow!(message)),
+            response => {
+                return Err(anyhow::anyhow!("unexpected response: {response:?}"));
+            }
+        },
+        Command::Audit {
+            command: AuditCommand::Analyze { path },
+        } => {
+            let report = audit::analyze(&path)?;
+            print!("{report}");
+
+            if !report.anomalies.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "found {} anomalies",
+                    report.anomalies.len()
+                ));
+            }
+        }
+    }
+
+    Ok(())
 }
 
 fn setup_logger(verbosity: u8, log_level: LevelFilter) -> anyhow::Result<()> {
@@ -122,6 +541,7 @@ fn setup_logger(verbosity: u8, log_level: LevelFilter) -> anyhow::Result<()> {
     logger
         .level_for("aurorashell", log_level)
         .chain(std::io::stdout())
+        .chain(crash::log_output())
         .apply()?;
 
     Ok(())
@@ -130,11 +550,59 @@ fn setup_logger(verbosity: u8, log_level: LevelFilter) -> anyhow::Result<()> {
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    setup_logger(args.verbosity, args.log_level)?;
+    match args.command {
+        None | Some(Command::Run) => {}
+        Some(command) => {
+            setup_logger(args.verbosity, args.log_level.unwrap_or(LevelFilter::Info))?;
+            return run_command(command);
+        }
+    }
+
+    // read before the logger is set up as it can change the log level, its
+    // problems are in the startup report rather than the log
+    let config = config::Config::from_file();
+    let config_level = config.as_ref().ok().and_then(|config| config.log.level);
+    let log_level = args
+        .log_level
+        .or(config_level.map(LevelFilter::from))
+        .unwrap_or(LevelFilter::Info);
+
+    setup_logger(args.verbosity, log_level)?;
+
+    crash::init();
+    metrics::init();
+
+    if let Some(path) = &args.load_state_fixture {
+        fixture::load(path)?;
+    }
+
+    if let Some(path) = args.audit_messages {
+        audit::init(path)?;
+    }
+
+    if args.inspector {
+        builtin::inspector::open_at_startup();
+    }
 
     log::debug!("debug enabled");
     log::trace!("trace enabled");
 
+    let config = match config {
+        Ok(config) => config,
+        Err(err) => {
+            log::error!("[config] could not read config, using the default config: {err}");
+            config::Config::default()
+        }
+    };
+    if !config.active_profiles.is_empty() {
+        log::info!("[config] applied the profiles {:?}", config.active_profiles);
+    }
+    config::init(config);
+    startup::ready("config");
+
+    compositor::probe();
+    crash::notify_unreported();
+
     // run app!!! :3
     Ok(iced::daemon(App::title, App::update, App::view)
         .subscription(App::subscription)
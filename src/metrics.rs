@@ -0,0 +1,85 @@
+//! simple counters for keeping track of what the shell is doing
+//!
+//! counters are keyed by a dotted name like `service.audio.events` and can be
+//! read through the ipc service. gauges like `module.clock.memory_bytes` are
+//! counters that are set instead of added to
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+static COUNTERS: LazyLock<Mutex<BTreeMap<Cow<'static, str>, u64>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+static START_TIME: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/// should be called early on startup so the uptime is accurate
+pub fn init() {
+    LazyLock::force(&START_TIME);
+}
+
+/// how long the shell has been running
+pub fn uptime() -> Duration {
+    START_TIME.elapsed()
+}
+
+/// adds 1 to a counter
+pub fn increment(name: impl Into<Cow<'static, str>>) {
+    add(name, 1);
+}
+
+/// adds `amount` to a counter, creating it if it doesn't exist
+pub fn add(name: impl Into<Cow<'static, str>>, amount: u64) {
+    match COUNTERS.lock() {
+        Ok(mut counters) => {
+            *counters.entry(name.into()).or_insert(0) += amount;
+        }
+        Err(err) => {
+            log::error!("[metrics] could not lock counters: {err}");
+        }
+    }
+}
+
+/// sets a gauge to `value`, creating it if it doesn't exist
+pub fn set(name: impl Into<Cow<'static, str>>, value: u64) {
+    match COUNTERS.lock() {
+        Ok(mut counters) => {
+            counters.insert(name.into(), value);
+        }
+        Err(err) => {
+            log::error!("[metrics] could not lock counters: {err}");
+        }
+    }
+}
+
+/// raises a gauge to `value` if it's lower, creating it if it doesn't exist
+pub fn max(name: impl Into<Cow<'static, str>>, value: u64) {
+    match COUNTERS.lock() {
+        Ok(mut counters) => {
+            let gauge = counters.entry(name.into()).or_insert(0);
+            *gauge = (*gauge).max(value);
+        }
+        Err(err) => {
+            log::error!("[metrics] could not lock counters: {err}");
+        }
+    }
+}
+
+/// copies all counters, along with the uptime in seconds
+pub fn snapshot() -> BTreeMap<String, u64> {
+    let mut snapshot = match COUNTERS.lock() {
+        Ok(counters) => counters
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect::<BTreeMap<String, u64>>(),
+        Err(err) => {
+            log::error!("[metrics] could not lock counters: {err}");
+            BTreeMap::new()
+        }
+    };
+
+    snapshot.insert("uptime_secs".to_string(), START_TIME.elapsed().as_secs());
+
+    return snapshot;
+}
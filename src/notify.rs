@@ -0,0 +1,48 @@
+//! desktop notifications through `notify-send`
+
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// shows a notification, failing silently if `notify-send` isn't installed
+pub fn send(summary: &str, body: &str) {
+    let res = Command::new("notify-send")
+        .args(["--app-name=aurorashell", summary, body])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    if let Err(err) = res {
+        log::warn!("[notify] could not run notify-send: {err}");
+    }
+}
+
+/// shows a notification with a button, running `on_action` on another
+/// thread if the button is pressed
+pub fn send_with_action<F>(summary: &str, body: &str, action_label: &str, on_action: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let mut command = Command::new("notify-send");
+    command
+        .args(["--app-name=aurorashell", "--wait"])
+        .arg(format!("--action=default={action_label}"))
+        .args([summary, body])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+
+    thread::spawn(move || {
+        // notify-send prints the name of the action that was pressed
+        let output = match command.output() {
+            Ok(output) => output,
+            Err(err) => {
+                log::warn!("[notify] could not run notify-send: {err}");
+                return;
+            }
+        };
+
+        if String::from_utf8_lossy(&output.stdout).trim() == "default" {
+            on_action();
+        }
+    });
+}
@@ -0,0 +1,69 @@
+//! opens links and files in the user's default application, through
+//! `xdg-open` or `gio open` if that isn't installed
+
+use std::process::{Command, Stdio};
+use std::{io, thread};
+
+/// the schemes that can be opened, others could hand the uri to any program
+/// that registered itself as a handler
+const SCHEMES: &[&str] = &["http", "https", "mailto", "file"];
+
+/// longer uris aren't opened
+pub const MAX_LEN: usize = 4096;
+
+/// why `uri` can't be opened, `None` if it can
+///
+/// absolute paths and uris with one of `SCHEMES` are allowed
+pub fn check(uri: &str) -> Option<&'static str> {
+    if uri.is_empty() || uri.len() > MAX_LEN {
+        return Some("it is empty or too long");
+    }
+
+    if uri.chars().any(char::is_control) {
+        return Some("it has control characters");
+    }
+
+    if uri.starts_with('/') {
+        return None;
+    }
+
+    match uri.split_once(':') {
+        Some((scheme, _)) if SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) => None,
+        Some(_) => Some("its scheme is not one of http, https, mailto or file"),
+        None => Some("it is not an absolute path or a uri"),
+    }
+}
+
+/// starts opening `uri`, the opener is waited on from another thread so a
+/// failure only shows in the logs
+pub fn uri(uri: &str) -> io::Result<()> {
+    let mut child = match opener("xdg-open", &[], uri).spawn() {
+        Ok(child) => child,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            opener("gio", &["open"], uri).spawn()?
+        }
+        Err(err) => return Err(err),
+    };
+
+    let uri = uri.to_string();
+    thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => {
+            log::warn!("[open] could not open {uri}: the opener exited with {status}");
+        }
+        Ok(_) => {}
+        Err(err) => log::warn!("[open] could not wait for the opener of {uri}: {err}"),
+    });
+
+    return Ok(());
+}
+
+fn opener(program: &str, args: &[&str], uri: &str) -> Command {
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .arg(uri)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    command
+}
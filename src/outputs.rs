@@ -0,0 +1,206 @@
+//! the monitors the compositor has told the shell about, kept so modules can
+//! read their size and scale and lay themselves out to fit
+//!
+//! outputs are kept in the order they were announced, which is also the
+//! order modules see them in
+
+use std::sync::{LazyLock, Mutex};
+
+use serde::Serialize;
+use wayland_client::protocol::wl_output::{self, WlOutput};
+
+static OUTPUTS: LazyLock<Mutex<Vec<(WlOutput, Output)>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Output {
+    /// the connector, like `DP-1`
+    pub name: String,
+    /// the make and model, as the compositor describes it
+    pub description: String,
+    /// the resolution of the current mode, in physical pixels
+    pub width: u32,
+    pub height: u32,
+    /// the size after scaling and rotating, what surfaces are laid out in
+    pub logical_width: u32,
+    pub logical_height: u32,
+    /// where its top left corner is in the compositor's layout, in logical
+    /// pixels
+    pub x: i32,
+    pub y: i32,
+    /// the integer scale the compositor asks for, fractional scales are
+    /// rounded up
+    pub scale: u32,
+    pub transform: Transform,
+}
+
+/// how an output is rotated and flipped, `Rotate90` and `Rotate270` are the
+/// ones a portrait monitor uses
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    Normal = 0,
+    Rotate90 = 1,
+    Rotate180 = 2,
+    Rotate270 = 3,
+    Flipped = 4,
+    Flipped90 = 5,
+    Flipped180 = 6,
+    Flipped270 = 7,
+}
+
+impl Transform {
+    fn from_wayland(transform: wl_output::Transform) -> Self {
+        match transform {
+            wl_output::Transform::_90 => Self::Rotate90,
+            wl_output::Transform::_180 => Self::Rotate180,
+            wl_output::Transform::_270 => Self::Rotate270,
+            wl_output::Transform::Flipped => Self::Flipped,
+            wl_output::Transform::Flipped90 => Self::Flipped90,
+            wl_output::Transform::Flipped180 => Self::Flipped180,
+            wl_output::Transform::Flipped270 => Self::Flipped270,
+            _ => Self::Normal,
+        }
+    }
+}
+
+impl Output {
+    /// takes the parts of sctk's `OutputInfo` that modules get to see
+    ///
+    /// `mode` is the resolution of the current mode, if the compositor sent
+    /// one. compositors without xdg-output don't send a position either, so
+    /// those outputs are all at 0,0
+    pub fn new(
+        name: Option<String>,
+        description: Option<String>,
+        mode: Option<(i32, i32)>,
+        logical_size: Option<(i32, i32)>,
+        logical_position: Option<(i32, i32)>,
+        scale_factor: i32,
+        transform: wl_output::Transform,
+    ) -> Self {
+        let (width, height) = mode.unwrap_or((0, 0));
+
+        // compositors without xdg-output don't send a logical size, so it's
+        // worked out the same way they would
+        let transform = Transform::from_wayland(transform);
+        let scale = scale_factor.max(1);
+        let (logical_width, logical_height) = match logical_size {
+            Some(size) => size,
+            None => match transform {
+                Transform::Rotate90
+                | Transform::Rotate270
+                | Transform::Flipped90
+                | Transform::Flipped270 => (height / scale, width / scale),
+                _ => (width / scale, height / scale),
+            },
+        };
+
+        let (x, y) = logical_position.unwrap_or((0, 0));
+
+        Self {
+            name: name.unwrap_or_default(),
+            description: description.unwrap_or_default(),
+            width: width.max(0) as u32,
+            height: height.max(0) as u32,
+            logical_width: logical_width.max(0) as u32,
+            logical_height: logical_height.max(0) as u32,
+            x,
+            y,
+            scale: scale as u32,
+            transform,
+        }
+    }
+}
+
+/// adds an output or replaces what's known about it, returns whether that
+/// changed anything
+pub fn update(wl_output: WlOutput, output: Output) -> bool {
+    let mut outputs = match OUTPUTS.lock() {
+        Ok(outputs) => outputs,
+        Err(err) => {
+            log::error!("[outputs] could not lock outputs: {err}");
+            return false;
+        }
+    };
+
+    match outputs.iter_mut().find(|(known, _)| *known == wl_output) {
+        Some((_, known)) if *known == output => return false,
+        Some((_, known)) => *known = output,
+        None => outputs.push((wl_output, output)),
+    }
+
+    return true;
+}
+
+/// forgets an output that was unplugged, returns whether it was known
+pub fn remove(wl_output: &WlOutput) -> bool {
+    let mut outputs = match OUTPUTS.lock() {
+        Ok(outputs) => outputs,
+        Err(err) => {
+            log::error!("[outputs] could not lock outputs: {err}");
+            return false;
+        }
+    };
+
+    let len = outputs.len();
+    outputs.retain(|(known, _)| known != wl_output);
+
+    return outputs.len() != len;
+}
+
+/// copies the outputs that are plugged in
+pub fn get() -> Vec<Output> {
+    match OUTPUTS.lock() {
+        Ok(outputs) => outputs.iter().map(|(_, output)| output.clone()).collect(),
+        Err(err) => {
+            log::error!("[outputs] could not lock outputs: {err}");
+            vec![]
+        }
+    }
+}
+
+/// the wayland output at `index` in the order modules see them, for putting
+/// surfaces on it
+pub fn wl_output(index: usize) -> Option<WlOutput> {
+    match OUTPUTS.lock() {
+        Ok(outputs) => outputs.get(index).map(|(wl_output, _)| wl_output.clone()),
+        Err(err) => {
+            log::error!("[outputs] could not lock outputs: {err}");
+            None
+        }
+    }
+}
+
+/// the outputs in the layout modules read them in, little endian
+///
+/// a u16 for the amount of outputs, then for each one the width, height,
+/// logical width, logical height and scale as u32s, the transform as a u8 and
+/// the name and description as a u16 length followed by that many bytes of
+/// utf-8
+pub fn serialize(outputs: &[Output]) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.extend((outputs.len() as u16).to_le_bytes());
+
+    for output in outputs {
+        for value in [
+            output.width,
+            output.height,
+            output.logical_width,
+            output.logical_height,
+            output.scale,
+        ] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.push(output.transform as u8);
+
+        for string in [&output.name, &output.description] {
+            // nothing real comes close, but the length has to fit
+            let string = &string.as_bytes()[..string.len().min(u16::MAX as usize)];
+            bytes.extend((string.len() as u16).to_le_bytes());
+            bytes.extend(string);
+        }
+    }
+
+    return bytes;
+}
@@ -1,7 +1,9 @@
 pub mod module;
 pub mod wasm;
 
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
 use iced::{Subscription, Task};
 
@@ -25,18 +27,100 @@ pub enum RuntimeEvent<R: RuntimeService> {
     Init(R::State),
     /// events emitted from the runtime
     Update(R::Event),
+    /// the request with this id couldn't be carried out
+    RequestFailed { id: RequestId, error: RequestError },
 }
 
 /// ensures all runtimes have a standard api for requests
 #[derive(Debug, Clone)]
 pub enum RuntimeRequest<R: RuntimeService> {
-    /// a request to the runtime
-    Request { request: R::Request },
+    /// a request to the runtime, if it fails the runtime emits
+    /// `RuntimeEvent::RequestFailed` with the same id
+    Request {
+        id: RequestId,
+        /// when the request was made, to time how long it took
+        sent: Instant,
+        request: R::Request,
+    },
     /// data emitted from a service, that a module from a runtime requested
     /// through a register
     ServiceData { data: R::ServiceData },
 }
 
+impl<R: RuntimeService> RuntimeRequest<R> {
+    /// a request with a new id, for callers that don't keep track of it
+    pub fn new(request: R::Request) -> Self {
+        Self::Request {
+            id: RequestId::next(),
+            sent: Instant::now(),
+            request,
+        }
+    }
+}
+
+/// ties a `RuntimeEvent::RequestFailed` to the request that failed
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub struct RequestId(u64);
+
+impl RequestId {
+    /// an id no other request has, shared between every runtime
+    pub fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// why a runtime couldn't carry out a request
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestError {
+    /// the runtime isn't running, so the request never reached it
+    Closed,
+    /// no module with this id is loaded
+    UnknownModule(RuntimeModuleId),
+    /// the module is loaded but didn't get the request, like when it's
+    /// disabled or has too many events waiting
+    Dropped {
+        module: RuntimeModuleId,
+        reason: String,
+    },
+    /// the module got the request but couldn't handle it
+    Module {
+        module: RuntimeModuleId,
+        message: String,
+    },
+}
+
+impl RequestError {
+    /// the module the request was for, if it got as far as finding one
+    pub fn module(&self) -> Option<&RuntimeModuleId> {
+        match self {
+            Self::Closed => None,
+            Self::UnknownModule(module)
+            | Self::Dropped { module, .. }
+            | Self::Module { module, .. } => Some(module),
+        }
+    }
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Closed => write!(f, "the runtime is not running"),
+            Self::UnknownModule(module) => write!(f, "{module:?} is not loaded"),
+            Self::Dropped { module, reason } => write!(f, "{module:?} did not get it: {reason}"),
+            Self::Module { module, message } => write!(f, "{module:?} failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for RequestError {}
+
 pub trait RuntimeService: Debug + Clone + Sized {
     /// the init data
     type Init: Debug;
@@ -63,7 +147,10 @@ pub trait RuntimeService: Debug + Clone + Sized {
 
     /// a call to this function internally calls a channel on `Self::State`
     /// to send a request to the Runtime
-    fn request(state: &mut Self::State, request: RuntimeRequest<Self>) -> anyhow::Result<()>;
+    ///
+    /// only fails here if the request couldn't be sent, failures while the
+    /// runtime handles it come back as `RuntimeEvent::RequestFailed`
+    fn request(state: &mut Self::State, request: RuntimeRequest<Self>) -> Result<(), RequestError>;
 }
 
 /// an id that represents an id from a module in a particular runtime
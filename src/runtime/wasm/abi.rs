@@ -0,0 +1,252 @@
+//! the versions of the layout modules hand their data to the host in
+//!
+//! a module says which version it uses through its `abi_version` export,
+//! which the host checks when the module is loaded
+//!
+//! - version 0: modules built before versioning, without `abi_version`.
+//!   `ViewFuncData` has no header and the registers table is big endian
+//! - version 1: `ViewFuncData` starts with the version it was written for and
+//!   everything is little endian, like wasm itself. the registers table is
+//!   version 2 of its own format
+//! - version 2: `ViewFuncData` ends with the generation of the view, which the
+//!   host hands back to `run_callback` so callbacks from an older view are
+//!   rejected
+//! - version 3: `ViewFuncData` ends with a pointer to the data of container
+//!   elements, and text styles are read (the host ignored them before)
+//! - version 4: `ViewFuncData` ends with a pointer to the data of image
+//!   elements
+//! - version 5: `ViewFuncData` ends with a pointer to the data of progress
+//!   bars
+//! - version 6: `ViewFuncData` ends with pointers to the data of text inputs
+//!   and grids
+//! - version 7: `ViewFuncData` ends with a pointer to the layout of rows and
+//!   columns (their spacing, padding, size and alignment)
+//! - version 8: `ViewFuncData` ends with a pointer to the data of tooltips
+//! - version 9: `ViewFuncData` ends with a pointer to the callbacks of mouse
+//!   areas
+//! - version 10: a surface's exclusive zone can be `i32::MIN` for the host to
+//!   work it out from the surface's size and anchor. the layout is the same
+//!   as version 9, older hosts would hand the value to the compositor as is
+//! - version 11: `LayerSurfaceRaw` has the surface's role after
+//!   `pointer_interactivity`, in what was padding before
+//! - version 12: `run_callback` takes what kind of data it's given after
+//!   the generation (see `CallbackDataKind`), so text and bools can be told
+//!   apart from numbers. the view layout is the same as version 11
+//!
+//! the structs the host reads straight out of a module's memory (like
+//! `RawElement`) are `#[repr(C)]` and little endian in every version, so the
+//! host has to be little endian too
+
+use super::WasiContext;
+
+use std::fmt;
+
+use anyhow::anyhow;
+use wasmtime::{Instance, Store};
+
+#[cfg(not(target_endian = "little"))]
+compile_error!("module data is read as is from wasm memory, which is little endian");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AbiVersion {
+    V0,
+    V1,
+    V2,
+    V3,
+    V4,
+    V5,
+    V6,
+    V7,
+    V8,
+    V9,
+    V10,
+    V11,
+    V12,
+}
+
+/// the byte order of integers written by a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    Big,
+    Little,
+}
+
+impl AbiVersion {
+    /// the newest version the host understands
+    pub const CURRENT: Self = Self::V12;
+
+    /// asks the module which version it was built for
+    pub async fn negotiate(
+        store: &mut Store<WasiContext>,
+        instance: &Instance,
+    ) -> anyhow::Result<Self> {
+        let func = match instance.get_typed_func::<(), u32>(&mut *store, "abi_version") {
+            Ok(func) => func,
+            // modules built before versioning don't export it
+            Err(_) => return Ok(Self::V0),
+        };
+
+        let version = func.call_async(&mut *store, ()).await?;
+
+        return Self::try_from(version);
+    }
+
+    /// the byte order of the registers table
+    pub fn registers_byte_order(self) -> ByteOrder {
+        match self {
+            Self::V0 => ByteOrder::Big,
+            Self::V1
+            | Self::V2
+            | Self::V3
+            | Self::V4
+            | Self::V5
+            | Self::V6
+            | Self::V7
+            | Self::V8
+            | Self::V9
+            | Self::V10
+            | Self::V11
+            | Self::V12 => ByteOrder::Little,
+        }
+    }
+
+    /// the version written in the registers table
+    pub fn registers_version(self) -> u16 {
+        match self {
+            Self::V0 => 1,
+            Self::V1
+            | Self::V2
+            | Self::V3
+            | Self::V4
+            | Self::V5
+            | Self::V6
+            | Self::V7
+            | Self::V8
+            | Self::V9
+            | Self::V10
+            | Self::V11
+            | Self::V12 => 2,
+        }
+    }
+
+    /// whether `ViewFuncData` has the view's generation and `run_callback`
+    /// takes it back
+    pub fn has_generations(self) -> bool {
+        self >= Self::V2
+    }
+
+    /// whether `ViewFuncData` has the pointer to container data
+    pub fn has_containers(self) -> bool {
+        self >= Self::V3
+    }
+
+    /// whether `ViewFuncData` has the pointer to image data
+    pub fn has_images(self) -> bool {
+        self >= Self::V4
+    }
+
+    /// whether `ViewFuncData` has the pointer to progress bar data
+    pub fn has_progress_bars(self) -> bool {
+        self >= Self::V5
+    }
+
+    /// whether `ViewFuncData` has the pointers to text input and grid data
+    pub fn has_inputs_and_grids(self) -> bool {
+        self >= Self::V6
+    }
+
+    /// whether `ViewFuncData` has the pointer to row and column layouts
+    pub fn has_layouts(self) -> bool {
+        self >= Self::V7
+    }
+
+    /// whether `ViewFuncData` has the pointer to tooltip data
+    pub fn has_tooltips(self) -> bool {
+        self >= Self::V8
+    }
+
+    /// whether `ViewFuncData` has the pointer to mouse area data
+    pub fn has_mouse_areas(self) -> bool {
+        self >= Self::V9
+    }
+
+    /// whether `LayerSurfaceRaw` has the surface's role
+    pub fn has_surface_roles(self) -> bool {
+        self >= Self::V11
+    }
+
+    /// whether `run_callback` takes the kind of data it's given
+    pub fn has_tagged_callbacks(self) -> bool {
+        self >= Self::V12
+    }
+}
+
+impl TryFrom<u32> for AbiVersion {
+    type Error = anyhow::Error;
+
+    fn try_from(version: u32) -> anyhow::Result<Self> {
+        match version {
+            0 => Ok(Self::V0),
+            1 => Ok(Self::V1),
+            2 => Ok(Self::V2),
+            3 => Ok(Self::V3),
+            4 => Ok(Self::V4),
+            5 => Ok(Self::V5),
+            6 => Ok(Self::V6),
+            7 => Ok(Self::V7),
+            8 => Ok(Self::V8),
+            9 => Ok(Self::V9),
+            10 => Ok(Self::V10),
+            11 => Ok(Self::V11),
+            12 => Ok(Self::V12),
+            n => Err(anyhow!(
+                "module uses abi version {n} but the shell only supports up to {}, the shell \
+                 needs updating",
+                Self::CURRENT
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AbiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V0 => write!(f, "0"),
+            Self::V1 => write!(f, "1"),
+            Self::V2 => write!(f, "2"),
+            Self::V3 => write!(f, "3"),
+            Self::V4 => write!(f, "4"),
+            Self::V5 => write!(f, "5"),
+            Self::V6 => write!(f, "6"),
+            Self::V7 => write!(f, "7"),
+            Self::V8 => write!(f, "8"),
+            Self::V9 => write!(f, "9"),
+            Self::V10 => write!(f, "10"),
+            Self::V11 => write!(f, "11"),
+            Self::V12 => write!(f, "12"),
+        }
+    }
+}
+
+impl ByteOrder {
+    pub fn u16(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Self::Big => u16::from_be_bytes(bytes),
+            Self::Little => u16::from_le_bytes(bytes),
+        }
+    }
+
+    pub fn u32(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Self::Big => u32::from_be_bytes(bytes),
+            Self::Little => u32::from_le_bytes(bytes),
+        }
+    }
+
+    pub fn u64(self, bytes: [u8; 8]) -> u64 {
+        match self {
+            Self::Big => u64::from_be_bytes(bytes),
+            Self::Little => u64::from_le_bytes(bytes),
+        }
+    }
+}
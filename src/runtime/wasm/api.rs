@@ -1,7 +1,28 @@
 use wasmtime::{Caller, Linker};
 
-use super::WasiContext;
+use super::command::{self, CommandRequest};
+use super::fs::LayerSurfaceRaw;
 use super::id::IdType;
+use super::{ModuleError, SurfaceRequest, WasiContext};
+
+use crate::builtin::region::Region;
+use crate::compositor::{self, Protocol};
+use crate::services::audio::{self, AudioDetail};
+use crate::services::{inhibit, kdeconnect, screenshot, session, weather};
+use crate::{config, open, outputs, services, unicode};
+
+/// kde connect's device ids are uuids, anything much longer isn't one
+const MAX_DEVICE_ID_LEN: usize = 256;
+
+/// the most text an audio request reads, enough for the names of plenty of
+/// sinks
+const MAX_AUDIO_REQUEST_LEN: usize = 16 * 1024;
+
+/// longer text isn't put on the clipboard
+const MAX_CLIPBOARD_LEN: usize = 1024 * 1024;
+
+/// searches of the unicode table longer than this aren't run
+const MAX_QUERY_LEN: usize = 256;
 
 /// links necessary functions for the modules
 pub fn get_api_functions(linker: &mut Linker<WasiContext>) -> anyhow::Result<()> {
@@ -22,5 +43,837 @@ pub fn get_api_functions(linker: &mut Linker<WasiContext>) -> anyhow::Result<()>
         },
     )?;
 
+    // the version of a service if it's running, 0 if it isn't or there's no
+    // service with the id
+    linker.func_wrap(
+        "env",
+        "service_version",
+        |_caller: Caller<'_, WasiContext>, service_id: u32| -> u32 {
+            u16::try_from(service_id)
+                .ok()
+                .and_then(services::version)
+                .unwrap_or(0)
+        },
+    )?;
+
+    // the newest version of a wayland protocol the compositor supports (see
+    // `compositor::Protocol` for the ids), 0 if it doesn't or the id is
+    // unknown. `u32::MAX` if the compositor couldn't be probed
+    linker.func_wrap(
+        "env",
+        "compositor_version",
+        |_caller: Caller<'_, WasiContext>, protocol: u32| -> u32 {
+            let capabilities = compositor::get();
+            if !capabilities.probed {
+                return u32::MAX;
+            }
+
+            Protocol::from_raw(protocol)
+                .and_then(|protocol| capabilities.version(protocol))
+                .unwrap_or(0)
+        },
+    )?;
+
+    // the size of the outputs in the layout of `outputs::serialize`, so the
+    // module knows how much room to make for `read_outputs`
+    linker.func_wrap(
+        "env",
+        "outputs_size",
+        |_caller: Caller<'_, WasiContext>| -> u32 {
+            outputs::serialize(&outputs::get()).len() as u32
+        },
+    )?;
+
+    // copies the outputs into the module's memory at `ptr`, returns how many
+    // bytes were written or 0 if they don't fit in `len` (an output was
+    // plugged in since `outputs_size` was called)
+    linker.func_wrap(
+        "env",
+        "read_outputs",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = outputs::serialize(&outputs::get());
+            if bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "outputs")
+        },
+    )?;
+
+    // the size of the last time the clock service gave the module, in the
+    // layout of `ClockTime::serialize`, 0 if it hasn't had one yet
+    linker.func_wrap(
+        "env",
+        "clock_size",
+        |caller: Caller<'_, WasiContext>| -> u32 { caller.data().clock.len() as u32 },
+    )?;
+
+    // copies the time into the module's memory at `ptr`, returns how many
+    // bytes were written or 0 if they don't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_clock",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = caller.data().clock.clone();
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "the clock")
+        },
+    )?;
+
+    // the size of the module's options in the layout of
+    // `fs::serialize_settings`, 0 if it has none. they're there from before
+    // `setup` runs
+    linker.func_wrap(
+        "env",
+        "settings_size",
+        |caller: Caller<'_, WasiContext>| -> u32 { caller.data().settings.len() as u32 },
+    )?;
+
+    // copies the module's options into its memory at `ptr`, returns how many
+    // bytes were written or 0 if they don't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_settings",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = caller.data().settings.clone();
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "the settings")
+        },
+    )?;
+
+    // the size of the audio summary in the layout of `AudioDevices::summary`,
+    // 0 if the audio service hasn't updated yet
+    linker.func_wrap(
+        "env",
+        "audio_summary_size",
+        |caller: Caller<'_, WasiContext>| -> u32 { caller.data().audio_summary.len() as u32 },
+    )?;
+
+    // copies the audio summary into the module's memory at `ptr`, returns
+    // how many bytes were written or 0 if they don't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_audio_summary",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = caller.data().audio_summary.clone();
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "the audio summary")
+        },
+    )?;
+
+    // the size of one sink (kind 0), source (1) or card (2) in the layout of
+    // `AudioDevices::detail`, 0 if there's none at `index`
+    linker.func_wrap(
+        "env",
+        "audio_detail_size",
+        |caller: Caller<'_, WasiContext>, kind: u32, index: u32| -> u32 {
+            audio_detail(&caller, kind, index).map_or(0, |bytes| bytes.len() as u32)
+        },
+    )?;
+
+    // copies one sink, source or card into the module's memory at `ptr`,
+    // returns how many bytes were written or 0 if they don't fit in `len`.
+    // the details are only serialized here, so modules showing the default
+    // device don't pay for every profile of every card
+    linker.func_wrap(
+        "env",
+        "read_audio_detail",
+        |mut caller: Caller<'_, WasiContext>, kind: u32, index: u32, ptr: u32, len: u32| -> u32 {
+            let bytes = match audio_detail(&caller, kind, index) {
+                Some(bytes) if bytes.len() <= len as usize => bytes,
+                _ => return 0,
+            };
+
+            write_bytes(&mut caller, ptr, &bytes, "an audio device")
+        },
+    )?;
+
+    // the size of the devices paired with kde connect in the layout of
+    // `KdeConnectState::serialize`, 0 if the service hasn't updated yet
+    linker.func_wrap(
+        "env",
+        "kdeconnect_size",
+        |caller: Caller<'_, WasiContext>| -> u32 { caller.data().kdeconnect.len() as u32 },
+    )?;
+
+    // copies the devices into the module's memory at `ptr`, returns how many
+    // bytes were written or 0 if they don't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_kdeconnect",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = caller.data().kdeconnect.clone();
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "the kde connect devices")
+        },
+    )?;
+
+    // the size of the shell's palette and font in the layout of
+    // `Base16Color::serialize`, 0 if the app hasn't sent it yet
+    linker.func_wrap(
+        "env",
+        "theme_size",
+        |caller: Caller<'_, WasiContext>| -> u32 { caller.data().theme.len() as u32 },
+    )?;
+
+    // copies the palette into the module's memory at `ptr`, returns how many
+    // bytes were written or 0 if they don't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_theme",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = caller.data().theme.clone();
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "the theme")
+        },
+    )?;
+
+    // asks kde connect to do something on a device, see
+    // `kdeconnect::Request::from_module` for the actions. the device is the
+    // id at `ptr`, or the first connected one when `len` is 0. returns 0 when
+    // the request was sent, 1 for an unknown action and 2 when the id can't
+    // be read
+    linker.func_wrap(
+        "env",
+        "kdeconnect_request",
+        |mut caller: Caller<'_, WasiContext>, action: u32, ptr: u32, len: u32| -> u32 {
+            let device = match len {
+                0 => None,
+                len if len as usize > MAX_DEVICE_ID_LEN => return 2,
+                len => match read_string(&mut caller, ptr, len) {
+                    Some(device) => Some(device),
+                    None => return 2,
+                },
+            };
+
+            match kdeconnect::Request::from_module(action, device) {
+                Some(request) => {
+                    caller.data_mut().kdeconnect_requests.push(request);
+                    0
+                }
+                None => 1,
+            }
+        },
+    )?;
+
+    // asks the audio service for something, see `audio::Request::from_module`
+    // for the actions. the text at `ptr` is what the action takes. returns 0
+    // when the request was sent, 1 for an unknown action or text it can't
+    // take and 2 when the text can't be read
+    linker.func_wrap(
+        "env",
+        "audio_request",
+        |mut caller: Caller<'_, WasiContext>, action: u32, ptr: u32, len: u32| -> u32 {
+            let text = match len {
+                0 => String::new(),
+                len if len as usize > MAX_AUDIO_REQUEST_LEN => return 2,
+                len => match read_string(&mut caller, ptr, len) {
+                    Some(text) => text,
+                    None => return 2,
+                },
+            };
+
+            // the volume of a stream is set on each of its channels
+            let devices = caller.data().audio.clone();
+            match audio::Request::from_module(action, &text, devices.as_deref()) {
+                Some(request) => {
+                    caller.data_mut().audio_requests.push(request);
+                    0
+                }
+                None => 1,
+            }
+        },
+    )?;
+
+    // keeps the session from going idle or lets it go idle again, see
+    // `inhibit::Request::from_module` for the actions. returns 0 when the
+    // request was sent and 1 for an unknown action
+    linker.func_wrap(
+        "env",
+        "inhibit_request",
+        |mut caller: Caller<'_, WasiContext>, action: u32| -> u32 {
+            match inhibit::Request::from_module(action) {
+                Some(request) => {
+                    caller.data_mut().inhibit_requests.push(request);
+                    0
+                }
+                None => 1,
+            }
+        },
+    )?;
+
+    // 1 when the session is kept from going idle, 0 when it isn't and 2 when
+    // the inhibit service hasn't found out yet
+    linker.func_wrap(
+        "env",
+        "idle_inhibited",
+        |_caller: Caller<'_, WasiContext>| -> u32 {
+            match inhibit::inhibited() {
+                Some(inhibited) => inhibited as u32,
+                None => 2,
+            }
+        },
+    )?;
+
+    // suspends (action 0), hibernates (1), reboots (2) or powers off (3) the
+    // system or locks the session (4), only for modules listed in `session`
+    // under `[modules]`. returns 0 when the request was sent, 1 when the
+    // module isn't allowed to and 2 for an unknown action
+    linker.func_wrap(
+        "env",
+        "session_request",
+        |mut caller: Caller<'_, WasiContext>, action: u32| -> u32 {
+            let module_name = caller.data().module_name.clone();
+
+            if !config::get().modules.session.contains(&module_name) {
+                log::warn!(
+                    "[wasm] [module:{module_name}] tried to use the session service without \
+                     being listed in `session`"
+                );
+                return 1;
+            }
+
+            match session::Request::from_module(action) {
+                Some(request) => {
+                    caller.data_mut().session_requests.push(request);
+                    0
+                }
+                None => 2,
+            }
+        },
+    )?;
+
+    // what the system can do in the layout of `session::Capabilities::bits`,
+    // 0 when the session service isn't running
+    linker.func_wrap(
+        "env",
+        "session_capabilities",
+        |_caller: Caller<'_, WasiContext>| -> u32 { session::capabilities() as u32 },
+    )?;
+
+    // how many bytes `read_weather` needs, 0 before the weather was first
+    // fetched or when the weather service isn't running
+    linker.func_wrap(
+        "env",
+        "weather_size",
+        |_caller: Caller<'_, WasiContext>| -> u32 { weather::serialized().len() as u32 },
+    )?;
+
+    // copies the last weather into the module's memory at `ptr` in the
+    // layout of `weather::se::serialize_weather`, returns how many bytes
+    // were written or 0 if there's none or it doesn't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_weather",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = weather::serialized();
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "the weather")
+        },
+    )?;
+
+    // opens a link or file in the default application, only for modules
+    // listed in `open_uri` under `[modules]`. returns 0 when it was opened,
+    // 1 when the module isn't allowed to, 2 when the uri can't be opened and
+    // 3 when no opener could be run
+    linker.func_wrap(
+        "env",
+        "open_uri",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let module_name = caller.data().module_name.clone();
+
+            if !config::get().modules.open_uri.contains(&module_name) {
+                log::warn!(
+                    "[wasm] [module:{module_name}] tried to open a uri without being listed in \
+                     `open_uri`"
+                );
+                return 1;
+            }
+
+            if len as usize > open::MAX_LEN {
+                log::warn!("[wasm] [module:{module_name}] tried to open a uri {len} bytes long");
+                return 2;
+            }
+
+            let Some(uri) = read_string(&mut caller, ptr, len) else {
+                return 2;
+            };
+
+            if let Some(reason) = open::check(&uri) {
+                log::warn!("[wasm] [module:{module_name}] can't open `{uri}` because {reason}");
+                return 2;
+            }
+
+            match open::uri(&uri) {
+                Ok(()) => {
+                    log::debug!("[wasm] [module:{module_name}] opened {uri}");
+                    0
+                }
+                Err(err) => {
+                    log::error!("[wasm] [module:{module_name}] could not open {uri}: {err}");
+                    3
+                }
+            }
+        },
+    )?;
+
+    // puts the text at `ptr` on the clipboard once the module is done
+    // handling the event it's in. returns 0 when it will be, 1 when the text
+    // is too long and 2 when it can't be read
+    linker.func_wrap(
+        "env",
+        "set_clipboard",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            if len as usize > MAX_CLIPBOARD_LEN {
+                return 1;
+            }
+
+            match read_string(&mut caller, ptr, len) {
+                Some(text) => {
+                    caller.data_mut().clipboard = Some(text);
+                    0
+                }
+                None => 2,
+            }
+        },
+    )?;
+
+    // copies the text the running callback was given (like a text input's)
+    // into the module's memory at `ptr`, its length is what the callback was
+    // given.
+    // returns how many bytes were written or 0 if they don't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_callback_text",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let text = std::mem::take(&mut caller.data_mut().callback_text);
+            if text.is_empty() || text.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, text.as_bytes(), "the callback's text")
+        },
+    )?;
+
+    // searches the unicode table for the query at `ptr`, emoji only (kind 0)
+    // or every character (1), skipping the first `offset` matches. returns
+    // the size of the results in the layout of `unicode::serialize`, which
+    // are kept for `read_unicode_results`, or 0 if the search couldn't be run
+    linker.func_wrap(
+        "env",
+        "unicode_search",
+        |mut caller: Caller<'_, WasiContext>,
+         kind: u32,
+         ptr: u32,
+         len: u32,
+         offset: u32,
+         limit: u32|
+         -> u32 {
+            let Ok(kind) = unicode::Kind::try_from(kind) else {
+                return 0;
+            };
+
+            let query = match len {
+                0 => String::new(),
+                len if len as usize > MAX_QUERY_LEN => return 0,
+                len => match read_string(&mut caller, ptr, len) {
+                    Some(query) => query,
+                    None => return 0,
+                },
+            };
+
+            let (total, entries) = unicode::search(&query, kind, offset as usize, limit as usize);
+            let bytes = unicode::serialize(total, &entries);
+            let size = bytes.len() as u32;
+            caller.data_mut().unicode_results = bytes;
+
+            size
+        },
+    )?;
+
+    // copies the results of the module's last `unicode_search` into its
+    // memory at `ptr`, returns how many bytes were written or 0 if they
+    // don't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_unicode_results",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = std::mem::take(&mut caller.data_mut().unicode_results);
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "the unicode search results")
+        },
+    )?;
+
+    // opens the overlay to pick a region of the screen with, on the output
+    // at `output` in the order of `read_outputs` or the one with focus when
+    // it's `u32::MAX`. the module is rendered again once it's picked and
+    // reads it with `read_region`
+    linker.func_wrap(
+        "env",
+        "select_region",
+        |mut caller: Caller<'_, WasiContext>, output: u32| {
+            caller.data_mut().region_request = Some(output);
+        },
+    )?;
+
+    // copies the last region picked for the module into its memory at `ptr`
+    // in the layout of `region::serialize`, returns how many bytes were
+    // written or 0 if there's none waiting or it doesn't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_region",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = std::mem::take(&mut caller.data_mut().region);
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "the region")
+        },
+    )?;
+
+    // asks for a screenshot of the output at `output` in the order of
+    // `read_outputs`, or of every output when it's `u32::MAX`. the module is
+    // rendered again once it's saved and reads the path with
+    // `read_screenshot`. only for modules listed in `screenshot` under
+    // `[modules]`, when one is refused the module reads why instead
+    linker.func_wrap(
+        "env",
+        "capture_output",
+        |mut caller: Caller<'_, WasiContext>, output: u32| {
+            let context = caller.data_mut();
+
+            if let Some(reason) = screenshot_refused(context) {
+                context.screenshot = screenshot::serialize(&Err(reason.to_string()));
+                return;
+            }
+
+            context.screenshot_request = Some((output, None));
+        },
+    )?;
+
+    // asks for a screenshot of a rectangle on the output at `output`, in
+    // logical pixels from its top left corner like `read_region` gives.
+    // returns 0 when it was asked for, 1 when the rectangle is empty or
+    // there's no output and 2 when the module isn't listed in `screenshot`
+    // or one is already being taken for it
+    linker.func_wrap(
+        "env",
+        "capture_region",
+        |mut caller: Caller<'_, WasiContext>,
+         output: u32,
+         x: u32,
+         y: u32,
+         width: u32,
+         height: u32|
+         -> u32 {
+            if output == u32::MAX || width == 0 || height == 0 {
+                return 1;
+            }
+            if screenshot_refused(caller.data()).is_some() {
+                return 2;
+            }
+
+            let region = Region {
+                x,
+                y,
+                width,
+                height,
+            };
+            caller.data_mut().screenshot_request = Some((output, Some(region)));
+            0
+        },
+    )?;
+
+    // the size of the outcome of the module's last screenshot, 0 if there's
+    // none waiting
+    linker.func_wrap(
+        "env",
+        "screenshot_size",
+        |caller: Caller<'_, WasiContext>| -> u32 { caller.data().screenshot.len() as u32 },
+    )?;
+
+    // copies the outcome of the module's last screenshot into its memory at
+    // `ptr` in the layout of `screenshot::serialize`, returns how many bytes
+    // were written or 0 if there's none waiting or it doesn't fit in `len`.
+    // it's only given once
+    linker.func_wrap(
+        "env",
+        "read_screenshot",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = caller.data().screenshot.clone();
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            let written = write_bytes(&mut caller, ptr, &bytes, "the screenshot");
+            if written != 0 {
+                caller.data_mut().screenshot.clear();
+            }
+            written
+        },
+    )?;
+
+    // runs a program (kind 0), fetches a url (kind 1) or sends a request with
+    // a method and body (kind 2) once the event the module is handling is
+    // done, see `CommandRequest::from_module` for what `ptr` holds. the
+    // output goes to the module's `run_command` with `handle`. only for
+    // modules listed in `spawn` or `fetch` (for both kinds of requests) under
+    // `[modules]`, or with the program listed under `[modules.programs]`.
+    // returns 0 when it will be run, 1 when the module isn't allowed to, 2
+    // when the command is invalid and 3 when the module has too many running
+    linker.func_wrap(
+        "env",
+        "perform_command",
+        |mut caller: Caller<'_, WasiContext>, kind: u32, ptr: u32, len: u32, handle: u32| -> u32 {
+            let module_name = caller.data().module_name.clone();
+
+            if len as usize > CommandRequest::max_len(kind) {
+                log::warn!("[wasm] [module:{module_name}] tried to run a command {len} bytes long");
+                return 2;
+            }
+
+            let Some(request) = read_string(&mut caller, ptr, len)
+                .and_then(|arg| CommandRequest::from_module(kind, arg))
+            else {
+                return 2;
+            };
+
+            let modules = &config::get().modules;
+            if !request.allowed(modules, &module_name) {
+                log::warn!(
+                    "[wasm] [module:{module_name}] tried to run {request:?} without being listed \
+                     in `{}`",
+                    request.config_key(modules, &module_name)
+                );
+                return 1;
+            }
+
+            let context = caller.data_mut();
+            if context.pending_commands.len() >= command::MAX_RUNNING {
+                return 3;
+            }
+
+            let ticket = command::next_ticket();
+            context.pending_commands.insert(ticket, handle);
+            context.command_requests.push((ticket, request));
+            0
+        },
+    )?;
+
+    // copies the output of the command whose `run_command` is running into
+    // the module's memory at `ptr` in the layout of `command::serialize`, its
+    // length is what `run_command` was given. returns how many bytes were
+    // written or 0 if they don't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_command_output",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = std::mem::take(&mut caller.data_mut().command_output);
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "the command's output")
+        },
+    )?;
+
+    // copies the event whose `service_event` is running into the module's
+    // memory at `ptr` in the layout of its service's `WasmSerializable`, its
+    // length is what `service_event` was given. returns how many bytes were
+    // written or 0 if they don't fit in `len`
+    linker.func_wrap(
+        "env",
+        "read_service_event",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32, len: u32| -> u32 {
+            let bytes = std::mem::take(&mut caller.data_mut().service_event);
+            if bytes.is_empty() || bytes.len() > len as usize {
+                return 0;
+            }
+
+            write_bytes(&mut caller, ptr, &bytes, "the service event")
+        },
+    )?;
+
+    // opens the surface at `ptr` in the layout of `LayerSurfaceRaw` after
+    // setup, its id has to come from `get_unique_id`. the surface is created
+    // once the event the module is handling is done and rendered with the
+    // module's other surfaces. returns 0 if it will be, 1 if the surface is
+    // invalid and 2 if it's already open
+    linker.func_wrap(
+        "env",
+        "open_surface",
+        |mut caller: Caller<'_, WasiContext>, ptr: u32| -> u32 {
+            let memory = match caller
+                .get_export("memory")
+                .and_then(|export| export.into_memory())
+            {
+                Some(memory) => memory,
+                None => return 1,
+            };
+            let (memory, context) = memory.data_and_store_mut(&mut caller);
+
+            let Some(raw) = LayerSurfaceRaw::read(memory, ptr) else {
+                return 1;
+            };
+            if !context.surface_wasm_id.has_lease(raw.id) {
+                return 1;
+            }
+            if context.used_surface_ids.borrow().contains(&raw.id) {
+                return 2;
+            }
+
+            let Some(layer) = raw.into_iced(
+                memory,
+                &context.surface_wasm_id,
+                &context.module_name,
+                context.abi_version,
+            ) else {
+                return 1;
+            };
+
+            context.used_surface_ids.borrow_mut().push(raw.id);
+            context
+                .surface_requests
+                .push(SurfaceRequest::Open(raw.id, layer));
+            0
+        },
+    )?;
+
+    // closes a surface the module opened, at setup or with `open_surface`,
+    // once the event the module is handling is done. it can be opened again
+    // with the same id. returns 0 if it will be and 1 if it isn't open
+    linker.func_wrap(
+        "env",
+        "close_surface",
+        |mut caller: Caller<'_, WasiContext>, id: u32| -> u32 {
+            let context = caller.data_mut();
+
+            let mut used = context.used_surface_ids.borrow_mut();
+            let Some(index) = used.iter().position(|used| *used == id) else {
+                return 1;
+            };
+            used.remove(index);
+            drop(used);
+
+            context.surface_requests.push(SurfaceRequest::Close(id));
+            0
+        },
+    )?;
+
+    // marks a surface as changed by the event the module is handling, once
+    // it's done only the marked surfaces are rendered. every surface is when
+    // none were marked. returns 0 if it was marked and 1 if it isn't open
+    linker.func_wrap(
+        "env",
+        "mark_surface_dirty",
+        |mut caller: Caller<'_, WasiContext>, id: u32| -> u32 {
+            let context = caller.data_mut();
+
+            if !context.used_surface_ids.borrow().contains(&id) {
+                return 1;
+            }
+
+            context.dirty_surfaces.insert(id);
+            0
+        },
+    )?;
+
     return Ok(());
 }
+
+/// why the module can't take a screenshot, if it can't
+///
+/// a module gets one screenshot at a time, so one that asks on every tick of
+/// a fast `Interval` doesn't keep `grim` running and fill up the directory
+fn screenshot_refused(context: &WasiContext) -> Option<&'static str> {
+    let module_name = &context.module_name;
+
+    if !config::get().modules.screenshot.contains(module_name) {
+        log::warn!(
+            "[wasm] [module:{module_name}] tried to take a screenshot without being listed in \
+             `screenshot`"
+        );
+        return Some("the module isn't listed in `screenshot` under `[modules]`");
+    }
+
+    if context.screenshot_pending || context.screenshot_request.is_some() {
+        log::debug!(
+            "[wasm] [module:{module_name}] asked for a screenshot while one is being taken"
+        );
+        return Some("a screenshot is already being taken");
+    }
+
+    return None;
+}
+
+/// copies `bytes` into the module's memory at `ptr`, returns how many bytes
+/// were written or 0 if it couldn't
+fn write_bytes(caller: &mut Caller<'_, WasiContext>, ptr: u32, bytes: &[u8], what: &str) -> u32 {
+    let memory = match caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+    {
+        Some(memory) => memory,
+        None => return 0,
+    };
+
+    match memory.write(&mut *caller, ptr as usize, bytes) {
+        Ok(()) => bytes.len() as u32,
+        Err(err) => {
+            log::error!("[wasm] could not write {what} to module memory: {err}");
+            caller.data_mut().errors.push(ModuleError::BadPointer {
+                what: format!("the buffer for {what}"),
+                offset: ptr as usize,
+                len: Some(bytes.len()),
+            });
+            0
+        }
+    }
+}
+
+/// reads `len` bytes of utf-8 from the module's memory at `ptr`
+fn read_string(caller: &mut Caller<'_, WasiContext>, ptr: u32, len: u32) -> Option<String> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())?;
+
+    let mut bytes = vec![0; len as usize];
+    if let Err(err) = memory.read(&caller, ptr as usize, &mut bytes) {
+        log::error!("[wasm] could not read a string from module memory: {err}");
+        caller.data_mut().errors.push(ModuleError::BadPointer {
+            what: "a string".to_string(),
+            offset: ptr as usize,
+            len: Some(len as usize),
+        });
+        return None;
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+fn audio_detail(caller: &Caller<'_, WasiContext>, kind: u32, index: u32) -> Option<Vec<u8>> {
+    let kind = AudioDetail::try_from(kind).ok()?;
+    caller.data().audio.as_ref()?.detail(kind, index as usize)
+}
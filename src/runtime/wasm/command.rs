@@ -0,0 +1,374 @@
+//! commands modules ask the shell to run for them, like running a program
+//! or fetching a url, which the module can't do itself from inside wasm
+//!
+//! they run on the runtime's tokio thread while the module carries on, the
+//! output is handed to the module's `run_command` once it's done and the
+//! message that returns goes through its `update` like a callback's
+//!
+//! modules have to be listed in `spawn` or `fetch` under `[modules]` in the
+//! config before they can use them, or have the programs they can run listed
+//! under `[modules.programs]`
+
+use crate::config::ModulesConfig;
+
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// longer commands or urls aren't run
+pub const MAX_LEN: usize = 4096;
+
+/// longer requests aren't sent, counting the method, url and body
+pub const MAX_REQUEST_LEN: usize = 64 * 1024;
+
+/// the methods modules can send requests with
+const METHODS: [&str; 6] = ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE"];
+
+/// how many commands a module can have running, it has to wait for one to
+/// finish before starting another
+pub const MAX_RUNNING: usize = 16;
+
+/// the output of a program or the body of a response is cut off here, the
+/// rest is never kept
+const MAX_OUTPUT: usize = 1024 * 1024;
+
+/// commands that take longer are stopped and fail
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// tells commands apart across every module and every load of them, so the
+/// output of a command started by a module that has since been reloaded
+/// isn't given to the new one
+static NEXT_TICKET: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_ticket() -> u64 {
+    NEXT_TICKET.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandRequest {
+    /// runs a program with arguments, without a shell
+    Spawn { program: String, args: Vec<String> },
+    /// a request to an http or https url, a get without a body unless the
+    /// module asked for something else
+    Fetch {
+        method: reqwest::Method,
+        url: String,
+        body: Vec<u8>,
+    },
+}
+
+impl CommandRequest {
+    /// the most bytes the `arg` of a command of `kind` can have
+    pub fn max_len(kind: u32) -> usize {
+        match kind {
+            2 => MAX_REQUEST_LEN,
+            _ => MAX_LEN,
+        }
+    }
+
+    /// `kind` is 0 for spawn, where `arg` is the program and its arguments
+    /// separated by nul bytes, 1 for fetch, where it's the url, and 2 for a
+    /// request, where it's the method, the url and the body separated by nul
+    /// bytes
+    pub fn from_module(kind: u32, arg: String) -> Option<Self> {
+        if arg.is_empty() || arg.len() > Self::max_len(kind) {
+            return None;
+        }
+
+        match kind {
+            0 => {
+                let mut parts = arg.split('\0').map(str::to_string);
+                let program = parts.next().filter(|program| !program.is_empty())?;
+                Some(Self::Spawn {
+                    program,
+                    args: parts.collect(),
+                })
+            }
+            1 => {
+                if !is_http(&arg) {
+                    return None;
+                }
+                Some(Self::Fetch {
+                    method: reqwest::Method::GET,
+                    url: arg,
+                    body: vec![],
+                })
+            }
+            2 => {
+                let mut parts = arg.splitn(3, '\0');
+                let method = parts.next()?;
+                let url = parts.next()?;
+                let body = parts.next().unwrap_or_default();
+
+                if !METHODS.contains(&method) || !is_http(url) || url.len() > MAX_LEN {
+                    return None;
+                }
+                Some(Self::Fetch {
+                    method: reqwest::Method::from_bytes(method.as_bytes()).ok()?,
+                    url: url.to_string(),
+                    body: body.as_bytes().to_vec(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// whether the module is listed for this kind of command in the config
+    ///
+    /// a module with its own list under `[modules.programs]` can only run
+    /// the programs in it, whether or not it's in `spawn`
+    pub fn allowed(&self, modules: &ModulesConfig, module_name: &str) -> bool {
+        let list = match self {
+            Self::Spawn { program, .. } => match modules.programs.get(module_name) {
+                Some(programs) => return programs.iter().any(|allowed| allowed == program),
+                None => &modules.spawn,
+            },
+            Self::Fetch { .. } => &modules.fetch,
+        };
+
+        return list.iter().any(|name| name == module_name);
+    }
+
+    /// the config key that decides whether the module can run it
+    pub fn config_key(&self, modules: &ModulesConfig, module_name: &str) -> String {
+        match self {
+            Self::Spawn { .. } if modules.programs.contains_key(module_name) => {
+                format!("programs.{module_name}")
+            }
+            Self::Spawn { .. } => "spawn".to_string(),
+            Self::Fetch { .. } => "fetch".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandOutput {
+    /// the program exited, `status` is -1 if it was killed by a signal
+    Exited {
+        status: i32,
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+    },
+    /// the server answered, whatever the status was
+    Response { status: u16, body: Vec<u8> },
+    /// it couldn't be run or took longer than `TIMEOUT`
+    Failed(String),
+}
+
+/// runs the command to the end
+pub async fn run(request: CommandRequest) -> CommandOutput {
+    let result = match request {
+        CommandRequest::Spawn { program, args } => spawn(&program, &args).await,
+        CommandRequest::Fetch { method, url, body } => fetch(method, &url, body).await,
+    };
+
+    return result.unwrap_or_else(|err| CommandOutput::Failed(err.to_string()));
+}
+
+async fn spawn(program: &str, args: &[String]) -> anyhow::Result<CommandOutput> {
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    // both are read at once so the program can't get stuck writing to one
+    // while the other is being read
+    let output = async {
+        let (stdout, stderr) = tokio::try_join!(read_output(stdout), read_output(stderr))?;
+        let status = child.wait().await?;
+        anyhow::Ok((status, stdout, stderr))
+    };
+
+    // the child is killed when it's dropped if it's still running
+    let (status, stdout, stderr) = match tokio::time::timeout(TIMEOUT, output).await {
+        Ok(output) => output?,
+        Err(_) => anyhow::bail!("it didn't exit within {} seconds", TIMEOUT.as_secs()),
+    };
+
+    return Ok(CommandOutput::Exited {
+        status: status.code().unwrap_or(-1),
+        stdout,
+        stderr,
+    });
+}
+
+/// reads the first `MAX_OUTPUT` bytes of a program's stdout or stderr, the
+/// rest is read and thrown away so the program isn't stuck on a full pipe
+async fn read_output(pipe: Option<impl AsyncRead + Unpin>) -> std::io::Result<Vec<u8>> {
+    let Some(mut pipe) = pipe else {
+        return Ok(vec![]);
+    };
+
+    let mut output = vec![];
+    (&mut pipe)
+        .take(MAX_OUTPUT as u64)
+        .read_to_end(&mut output)
+        .await?;
+    tokio::io::copy(&mut pipe, &mut tokio::io::sink()).await?;
+
+    return Ok(output);
+}
+
+async fn fetch(method: reqwest::Method, url: &str, body: Vec<u8>) -> anyhow::Result<CommandOutput> {
+    let client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .user_agent(concat!("aurorashell/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+
+    let mut request = client.request(method, url);
+    if !body.is_empty() {
+        request = request.body(body);
+    }
+
+    let mut response = request.send().await?;
+    let status = response.status().as_u16();
+
+    // read a chunk at a time so a huge body isn't kept whole
+    let mut body = vec![];
+    while let Some(chunk) = response.chunk().await? {
+        let left = MAX_OUTPUT - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(left)]);
+        if body.len() >= MAX_OUTPUT {
+            break;
+        }
+    }
+
+    return Ok(CommandOutput::Response { status, body });
+}
+
+/// whether `url` is an http or https url
+fn is_http(url: &str) -> bool {
+    let Some((scheme, _)) = url.split_once("://") else {
+        return false;
+    };
+
+    return scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https");
+}
+
+/// the output in the layout modules read it in, little endian
+///
+/// a u8 that's 0 when the program exited, 1 for a response and 2 when it
+/// failed, then the status as an i32 (0 when it failed) and two byte strings
+/// as a u32 length then the bytes: stdout and stderr, the body and nothing,
+/// or the reason it failed and nothing
+pub fn serialize(output: &CommandOutput) -> Vec<u8> {
+    let (tag, status, first, second): (u8, i32, &[u8], &[u8]) = match output {
+        CommandOutput::Exited {
+            status,
+            stdout,
+            stderr,
+        } => (0, *status, stdout.as_slice(), stderr.as_slice()),
+        CommandOutput::Response { status, body } => (1, *status as i32, body.as_slice(), &[]),
+        CommandOutput::Failed(reason) => (2, 0, reason.as_bytes(), &[]),
+    };
+
+    let mut bytes = Vec::with_capacity(13 + first.len() + second.len());
+    bytes.push(tag);
+    bytes.extend(status.to_le_bytes());
+    for part in [first, second] {
+        bytes.extend((part.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(part);
+    }
+
+    return bytes;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_request(program: &str) -> CommandRequest {
+        return CommandRequest::Spawn {
+            program: program.to_string(),
+            args: vec![],
+        };
+    }
+
+    fn modules(spawn: &[&str], programs: &[(&str, &[&str])]) -> ModulesConfig {
+        return ModulesConfig {
+            spawn: spawn.iter().map(|name| name.to_string()).collect(),
+            programs: programs
+                .iter()
+                .map(|(name, list)| {
+                    let list = list.iter().map(|program| program.to_string()).collect();
+                    (name.to_string(), list)
+                })
+                .collect(),
+            ..ModulesConfig::default()
+        };
+    }
+
+    #[test]
+    fn programs_list_overrides_spawn() {
+        let modules = modules(&["media"], &[("media", &["playerctl"])]);
+
+        assert!(spawn_request("playerctl").allowed(&modules, "media"));
+        assert!(!spawn_request("rm").allowed(&modules, "media"));
+        assert_eq!(
+            spawn_request("rm").config_key(&modules, "media"),
+            "programs.media"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_spawn() {
+        let modules = modules(&["media"], &[("other", &["playerctl"])]);
+
+        assert!(spawn_request("rm").allowed(&modules, "media"));
+        assert!(!spawn_request("rm").allowed(&modules, "clock"));
+        assert_eq!(spawn_request("rm").config_key(&modules, "media"), "spawn");
+    }
+
+    #[test]
+    fn fetch_needs_fetch() {
+        let request = CommandRequest::from_module(1, "https://example.com".to_string()).unwrap();
+        let mut modules = modules(&["weather"], &[]);
+
+        assert!(!request.allowed(&modules, "weather"));
+        assert_eq!(request.config_key(&modules, "weather"), "fetch");
+
+        modules.fetch = vec!["weather".to_string()];
+        assert!(request.allowed(&modules, "weather"));
+    }
+
+    #[test]
+    fn parses_spawn() {
+        assert_eq!(
+            CommandRequest::from_module(0, "playerctl\0play-pause".to_string()),
+            Some(CommandRequest::Spawn {
+                program: "playerctl".to_string(),
+                args: vec!["play-pause".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_empty_program() {
+        assert_eq!(CommandRequest::from_module(0, String::new()), None);
+        assert_eq!(CommandRequest::from_module(0, "\0--help".to_string()), None);
+    }
+
+    #[test]
+    fn rejects_bad_urls_and_methods() {
+        assert_eq!(
+            CommandRequest::from_module(1, "file:///etc/passwd".to_string()),
+            None
+        );
+        assert_eq!(
+            CommandRequest::from_module(2, "TRACE\0https://example.com".to_string()),
+            None
+        );
+        assert_eq!(
+            CommandRequest::from_module(3, "playerctl".to_string()),
+            None
+        );
+    }
+}
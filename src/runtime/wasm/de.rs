@@ -5,16 +5,31 @@
 //! `crate::runtime::module`
 
 pub trait Deserialize: Sized {
-    fn deserialize(data: &[u8]) -> anyhow::Result<Self>;
+    fn deserialize(data: &[u8], version: AbiVersion) -> anyhow::Result<Self>;
 }
 
+use super::abi::{AbiVersion, ByteOrder};
+use super::memory;
+
 use crate::services::SubscriptionData;
 use crate::services::audio::AudioSubscriptionData;
+use crate::services::brightness::BrightnessSubscriptionData;
+use crate::services::clock::ClockSubscriptionData;
+use crate::services::custom::CustomSubscriptionData;
+use crate::services::dbus::{Bus, DbusSubscriptionData};
+use crate::services::hotkeys::{Hotkey, HotkeysSubscriptionData};
+use crate::services::inhibit::InhibitSubscriptionData;
+use crate::services::kdeconnect::KdeConnectSubscriptionData;
+use crate::services::network::NetworkSubscriptionData;
+use crate::services::sysinfo::SysinfoSubscriptionData;
+use crate::services::watch::WatchSubscriptionData;
 
 use anyhow::anyhow;
 
 impl Deserialize for Vec<SubscriptionData> {
-    fn deserialize(data: &[u8]) -> anyhow::Result<Self> {
+    fn deserialize(data: &[u8], version: AbiVersion) -> anyhow::Result<Self> {
+        let byte_order = version.registers_byte_order();
+
         // must have at least 0x20 bytes for the header
         if data.len() < 0x10 {
             return Err(anyhow!(
@@ -23,8 +38,29 @@ impl Deserialize for Vec<SubscriptionData> {
         }
 
         // shouldn't fail as we check for at least 0x10 bytes beforehand
+        let table_version: u16 = match data[0x04..0x06].try_into() {
+            Ok(bytes) => byte_order.u16(bytes),
+            Err(err) => {
+                return Err(anyhow!(
+                    "[wasm] [Registers::deserialize] data[0x04..0x06] to [u8; 2] failed somehow: \
+                     {}",
+                    err
+                ));
+            }
+        };
+
+        if table_version != version.registers_version() {
+            return Err(anyhow!(
+                "[wasm] [Registers::deserialize] abi version {} uses registers table version {} \
+                 but the table is version {}",
+                version,
+                version.registers_version(),
+                table_version
+            ));
+        }
+
         let num_registers: u16 = match data[0x06..0x08].try_into() {
-            Ok(bytes) => u16::from_be_bytes(bytes),
+            Ok(bytes) => byte_order.u16(bytes),
             Err(err) => {
                 return Err(anyhow!(
                     "[wasm] [Registers::deserialize] data[0x06..0x08] to [u8; 2] failed somehow: \
@@ -52,7 +88,12 @@ impl Deserialize for Vec<SubscriptionData> {
         let registers: Vec<SubscriptionData> = data[0x10..registers_table_end]
             .chunks_exact(0x10)
             .map(|entry_bytes| {
-                SubscriptionData::from_entry_bytes(data, entry_bytes, registers_table_end)
+                SubscriptionData::from_entry_bytes(
+                    data,
+                    entry_bytes,
+                    registers_table_end,
+                    byte_order,
+                )
             })
             .collect::<anyhow::Result<Vec<SubscriptionData>>>()?;
 
@@ -88,6 +129,7 @@ impl SubscriptionData {
         data: &[u8],
         entry_bytes: &[u8],
         extra_data_start: usize,
+        byte_order: ByteOrder,
     ) -> anyhow::Result<SubscriptionData> {
         let entry_bytes: [u8; 0x10] = match entry_bytes.try_into() {
             Ok(bytes) => bytes,
@@ -100,7 +142,7 @@ impl SubscriptionData {
             }
         };
 
-        let entry = SubscriptionData::get_entry_data(entry_bytes)?;
+        let entry = SubscriptionData::get_entry_data(entry_bytes, byte_order)?;
 
         let res = match entry.id {
             1 => SubscriptionData::PulseAudio {
@@ -109,21 +151,13 @@ impl SubscriptionData {
             3 => {
                 let offset = entry.extra_data_offset as usize + extra_data_start;
                 // Interval's extra data is 0x10 bytes long
-                let end = offset + 0x10;
-                if offset > data.len() || end > data.len() {
-                    return Err(anyhow!(
-                        "[wasm] [Registers] Interval offsets out of bounds: {:02X}-{:02X}, data \
-                         size: {:02X}",
-                        offset,
-                        end,
-                        data.len(),
-                    ));
-                }
-
-                let extra_data = &data[offset..end];
+                let extra_data = match memory::guest_bytes(data, offset, 0x10) {
+                    Ok(bytes) => bytes,
+                    Err(err) => return Err(anyhow!("[wasm] [Registers] Interval {}", err)),
+                };
 
                 let milliseconds: u64 = match extra_data[0x00..0x08].try_into() {
-                    Ok(bytes) => u64::from_be_bytes(bytes),
+                    Ok(bytes) => byte_order.u64(bytes),
                     Err(err) => {
                         return Err(anyhow!(
                             "[wasm] [Registers] Interval milliseconds to u64 failed: {}\nbytes: \
@@ -135,7 +169,7 @@ impl SubscriptionData {
                 };
 
                 let offset: u32 = match extra_data[0x08..0x0C].try_into() {
-                    Ok(bytes) => u32::from_be_bytes(bytes),
+                    Ok(bytes) => byte_order.u32(bytes),
                     Err(err) => {
                         return Err(anyhow!(
                             "[wasm] [Registers] Interval offset to u64 failed: {}\nbytes: {:?}",
@@ -150,6 +184,109 @@ impl SubscriptionData {
                     offset,
                 }
             }
+            4 => {
+                let offset = entry.extra_data_offset as usize + extra_data_start;
+                SubscriptionData::Custom {
+                    data: CustomSubscriptionData {
+                        sensors: SubscriptionData::get_strings(data, offset, byte_order, "Custom")?,
+                    },
+                }
+            }
+            5 => SubscriptionData::Network {
+                data: NetworkSubscriptionData(entry.registers as u8),
+            },
+            9 => SubscriptionData::Outputs,
+            10 => {
+                let offset = entry.extra_data_offset as usize + extra_data_start;
+                // Sysinfo's extra data is the interval as a u64
+                let interval_ms: u64 = match memory::guest_read::<[u8; 8]>(data, offset) {
+                    Ok(bytes) => byte_order.u64(bytes),
+                    Err(err) => return Err(anyhow!("[wasm] [Registers] Sysinfo {}", err)),
+                };
+
+                SubscriptionData::Sysinfo {
+                    data: SysinfoSubscriptionData {
+                        events: entry.registers as u8,
+                        interval_ms,
+                    },
+                }
+            }
+            6 => SubscriptionData::Weather,
+            11 => SubscriptionData::Brightness {
+                data: BrightnessSubscriptionData(entry.registers as u8),
+            },
+            12 => {
+                let offset = entry.extra_data_offset as usize + extra_data_start;
+                SubscriptionData::Clock {
+                    data: SubscriptionData::get_clock_data(data, offset, byte_order)?,
+                }
+            }
+            13 => SubscriptionData::KdeConnect {
+                data: KdeConnectSubscriptionData(entry.registers as u8),
+            },
+            14 => {
+                let offset = entry.extra_data_offset as usize + extra_data_start;
+                SubscriptionData::Hotkeys {
+                    data: HotkeysSubscriptionData {
+                        // filled in once the module's name is known
+                        module: String::new(),
+                        hotkeys: SubscriptionData::get_hotkeys(data, offset, byte_order)?,
+                    },
+                }
+            }
+            15 => SubscriptionData::Theme,
+            17 => {
+                let offset = entry.extra_data_offset as usize + extra_data_start;
+                // AudioMeter's extra data is the rate as a u32
+                let rate: u32 = match memory::guest_read::<[u8; 4]>(data, offset) {
+                    Ok(bytes) => byte_order.u32(bytes),
+                    Err(err) => return Err(anyhow!("[wasm] [Registers] AudioMeter {}", err)),
+                };
+
+                SubscriptionData::AudioMeter { rate }
+            }
+            18 => SubscriptionData::Inhibit {
+                data: InhibitSubscriptionData(entry.registers as u8),
+            },
+            21 => {
+                let offset = entry.extra_data_offset as usize + extra_data_start;
+                SubscriptionData::Watch {
+                    data: WatchSubscriptionData {
+                        // filled in once the module's name is known
+                        module: String::new(),
+                        globs: SubscriptionData::get_strings(data, offset, byte_order, "Watch")?,
+                    },
+                }
+            }
+            22 => {
+                let offset = entry.extra_data_offset as usize + extra_data_start;
+                // the first register bit is set for the system bus, the extra
+                // data is the bus name, path and interface as strings
+                let strings = SubscriptionData::get_strings(data, offset, byte_order, "Dbus")?;
+                let [name, path, interface] = match <[String; 3]>::try_from(strings) {
+                    Ok(strings) => strings,
+                    Err(strings) => {
+                        return Err(anyhow!(
+                            "[wasm] [Registers] Dbus has {} strings, expected 3",
+                            strings.len()
+                        ));
+                    }
+                };
+
+                SubscriptionData::Dbus {
+                    data: DbusSubscriptionData {
+                        // filled in once the module's name is known
+                        module: String::new(),
+                        bus: match entry.registers & 1 {
+                            0 => Bus::Session,
+                            _ => Bus::System,
+                        },
+                        name,
+                        path,
+                        interface,
+                    },
+                }
+            }
             _ => {
                 return Err(anyhow!("[wasm] [MODULE_HERE] value = {}", entry.id));
             }
@@ -158,11 +295,132 @@ impl SubscriptionData {
         return Ok(res);
     }
 
+    /// reads the sensor names of a Custom register, the globs of a Watch
+    /// register or what a Dbus register watches, `register` is its name for
+    /// errors
+    ///
+    /// the extra data is a u16 for the amount of strings, then each string is
+    /// a u16 for its length followed by that many bytes of utf-8
+    fn get_strings(
+        data: &[u8],
+        offset: usize,
+        byte_order: ByteOrder,
+        register: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let read_u16 = |offset: usize| -> anyhow::Result<u16> {
+            match memory::guest_read::<[u8; 2]>(data, offset) {
+                Ok(bytes) => Ok(byte_order.u16(bytes)),
+                Err(err) => Err(anyhow!("[wasm] [Registers] {register} {}", err)),
+            }
+        };
+
+        let count = read_u16(offset)?;
+        let mut cursor = offset + 2;
+        let mut names = Vec::with_capacity(count as usize);
+
+        for _ in 0..count {
+            let len = read_u16(cursor)? as usize;
+            cursor += 2;
+
+            match memory::guest_str(data, cursor, len) {
+                Ok(name) => names.push(name.to_string()),
+                Err(err) => {
+                    return Err(anyhow!("[wasm] [Registers] {register} string {}", err));
+                }
+            }
+            cursor += len;
+        }
+
+        return Ok(names);
+    }
+
+    /// reads the format and timezone of a Clock register
+    ///
+    /// the extra data is a u16 for the length of the format followed by that
+    /// many bytes of utf-8, then the same for the timezone, where a length of
+    /// 0 means the local timezone
+    fn get_clock_data(
+        data: &[u8],
+        offset: usize,
+        byte_order: ByteOrder,
+    ) -> anyhow::Result<ClockSubscriptionData> {
+        let read_string = |offset: usize| -> anyhow::Result<(String, usize)> {
+            let len = match memory::guest_read::<[u8; 2]>(data, offset) {
+                Ok(bytes) => byte_order.u16(bytes) as usize,
+                Err(err) => return Err(anyhow!("[wasm] [Registers] Clock {}", err)),
+            };
+
+            let start = offset + 2;
+            match memory::guest_str(data, start, len) {
+                Ok(string) => Ok((string.to_string(), start + len)),
+                Err(err) => Err(anyhow!("[wasm] [Registers] Clock {}", err)),
+            }
+        };
+
+        let (format, cursor) = read_string(offset)?;
+        let (timezone, _) = read_string(cursor)?;
+
+        return Ok(ClockSubscriptionData {
+            format,
+            timezone: (!timezone.is_empty()).then_some(timezone),
+        });
+    }
+
+    /// reads the hotkeys of a Hotkeys register
+    ///
+    /// the extra data is a u16 for the amount of hotkeys, then each hotkey
+    /// is its id, description and trigger, each a u16 for its length
+    /// followed by that many bytes of utf-8
+    fn get_hotkeys(
+        data: &[u8],
+        offset: usize,
+        byte_order: ByteOrder,
+    ) -> anyhow::Result<Vec<Hotkey>> {
+        let read_u16 = |offset: usize| -> anyhow::Result<u16> {
+            match memory::guest_read::<[u8; 2]>(data, offset) {
+                Ok(bytes) => Ok(byte_order.u16(bytes)),
+                Err(err) => Err(anyhow!("[wasm] [Registers] Hotkeys {}", err)),
+            }
+        };
+
+        let read_string = |offset: usize| -> anyhow::Result<(String, usize)> {
+            let len = read_u16(offset)? as usize;
+
+            let start = offset + 2;
+            match memory::guest_str(data, start, len) {
+                Ok(string) => Ok((string.to_string(), start + len)),
+                Err(err) => Err(anyhow!("[wasm] [Registers] Hotkeys {}", err)),
+            }
+        };
+
+        let count = read_u16(offset)?;
+        let mut cursor = offset + 2;
+        let mut hotkeys = Vec::with_capacity(count as usize);
+
+        for _ in 0..count {
+            let (id, next) = read_string(cursor)?;
+            let (description, next) = read_string(next)?;
+            let (trigger, next) = read_string(next)?;
+            cursor = next;
+
+            hotkeys.push(Hotkey {
+                id,
+                description,
+                trigger,
+            });
+        }
+
+        return Ok(hotkeys);
+    }
+
     /// takes a 0x10 byte array and converts it to a usable
-    fn get_entry_data(bytes: [u8; 0x10]) -> anyhow::Result<RegisterEntryData> {
+    fn get_entry_data(
+        bytes: [u8; 0x10],
+        byte_order: ByteOrder,
+    ) -> anyhow::Result<RegisterEntryData> {
         Ok(RegisterEntryData {
             id: match bytes[0x00..0x02].try_into() {
-                Ok(bytes) => u16::from_be_bytes(bytes),
+                Ok(bytes) => byte_order.u16(bytes),
                 Err(err) => {
                     return Err(anyhow!(
                         "[wasm] [Registers] id entry bytes to u16 failed: {}\nbytes: {:?}",
@@ -172,7 +430,7 @@ impl SubscriptionData {
                 }
             },
             registers: match bytes[0x02..0x06].try_into() {
-                Ok(bytes) => u32::from_be_bytes(bytes),
+                Ok(bytes) => byte_order.u32(bytes),
                 Err(err) => {
                     return Err(anyhow!(
                         "[wasm] [Registers] registers entry bytes to u32 failed: {}\nbytes: {:?}",
@@ -182,7 +440,7 @@ impl SubscriptionData {
                 }
             },
             extra_data_offset: match bytes[0x06..0x0A].try_into() {
-                Ok(bytes) => u32::from_be_bytes(bytes),
+                Ok(bytes) => byte_order.u32(bytes),
                 Err(err) => {
                     return Err(anyhow!(
                         "[wasm] [Registers] extra_data_offset entry bytes to u32 failed: \
@@ -0,0 +1,114 @@
+//! what went wrong in a module, kept by the app so module developers can
+//! see it with `aurorashell modules list` instead of digging through the
+//! log
+//!
+//! the runtime still logs each of these and carries on like it did, a module
+//! that faults is skipped for that render or event rather than stopped. the
+//! errors are collected in the module's `WasiContext` while it runs and sent
+//! to the app as `Event::ModuleError` once the runtime is done with it
+
+use super::memory::GuestReadError;
+
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModuleError {
+    /// the module couldn't be loaded, like when its file isn't valid wasm
+    /// or its `setup` trapped. it has no name yet, only its file
+    SetupFailed { file_path: PathBuf, message: String },
+    /// the module's `view` trapped or ran too long while rendering the
+    /// surface with this id
+    ViewTrap { surface: u32, message: String },
+    /// one of the module's other functions trapped or ran too long, like
+    /// `run_callback` or `update`
+    Trap {
+        function: &'static str,
+        message: String,
+    },
+    /// the module gave the host a pointer to `len` bytes at `offset` that
+    /// aren't inside its memory, `len` is `None` when it goes past the end
+    /// of the address space
+    BadPointer {
+        what: String,
+        offset: usize,
+        len: Option<usize>,
+    },
+    /// the module gave the host something it couldn't read, like a ui tree
+    /// with an element that doesn't exist
+    DeserializeFailed { what: String, message: String },
+    /// the module doesn't export a function the host needed, or exports it
+    /// with the wrong type
+    MissingExport { name: &'static str, message: String },
+}
+
+impl ModuleError {
+    /// a failure reading `what` out of the module, a `BadPointer` when it's
+    /// because of memory the module doesn't have
+    pub(super) fn read(what: impl Into<String>, err: &anyhow::Error) -> Self {
+        let what = what.into();
+
+        return match err.downcast_ref::<GuestReadError>() {
+            Some(GuestReadError::OutOfBounds { start, end, .. }) => Self::BadPointer {
+                what,
+                offset: *start,
+                len: end.map(|end| end - start),
+            },
+            _ => Self::DeserializeFailed {
+                what,
+                message: format!("{err:#}"),
+            },
+        };
+    }
+
+    /// a short name for the kind of error, like `view_trap`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::SetupFailed { .. } => "setup_failed",
+            Self::ViewTrap { .. } => "view_trap",
+            Self::Trap { .. } => "trap",
+            Self::BadPointer { .. } => "bad_pointer",
+            Self::DeserializeFailed { .. } => "deserialize_failed",
+            Self::MissingExport { .. } => "missing_export",
+        }
+    }
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SetupFailed { file_path, message } => {
+                write!(f, "{} could not be loaded: {message}", file_path.display())
+            }
+            Self::ViewTrap { surface, message } => {
+                write!(f, "view trapped for surface {surface}: {message}")
+            }
+            Self::Trap { function, message } => write!(f, "{function} trapped: {message}"),
+            Self::BadPointer {
+                what,
+                offset,
+                len: Some(len),
+            } => write!(
+                f,
+                "{what} is outside of the module's memory: {len} bytes at {offset:#X}"
+            ),
+            Self::BadPointer {
+                what,
+                offset,
+                len: None,
+            } => write!(
+                f,
+                "{what} is outside of the module's memory: {offset:#X} with a length past the \
+                 end of the address space"
+            ),
+            Self::DeserializeFailed { what, message } => {
+                write!(f, "could not read {what}: {message}")
+            }
+            Self::MissingExport { name, message } => {
+                write!(f, "{name} is not exported or has the wrong type: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModuleError {}
@@ -1,25 +1,44 @@
 use std::cell::RefCell;
-use std::path::PathBuf;
-use std::{env, fs, str};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{env, fs};
 
+use anyhow::anyhow;
 use iced::Limits as IcedLimits;
-use iced::futures::channel::mpsc::Sender as IcedSender;
-use iced::futures::{SinkExt, StreamExt};
+use iced::futures::channel::mpsc;
 use iced::platform_specific::shell::commands::layer_surface::{
     Anchor, KeyboardInteractivity, Layer,
 };
 use iced::runtime::platform_specific::wayland::layer_surface::{
     IcedMargin, IcedOutput, SctkLayerSurfaceSettings,
 };
-use wasmtime::{Module, Store};
+use wasmtime::{Engine, Module, Store};
 use wasmtime_wasi::WasiCtxBuilder;
 
+use super::abi::AbiVersion;
 use super::de::Deserialize;
 use super::id::WasmId;
-use super::{Event, WasiContext, WasmHost, WasmModule, WasmRuntime};
+use super::images::ImageCache;
+use super::memory::{self, FromBytes};
+use super::queue::EventQueue;
+use super::{
+    Event, ModuleError, ModuleStats, Request, WasiContext, WasmHost, WasmModule, WasmRuntime,
+    limits, target,
+};
 
-use crate::runtime::RuntimeEvent;
+use crate::instrumented::InstrumentedSender;
+use crate::runtime::{RuntimeEvent, RuntimeRequest};
 use crate::services::SubscriptionData;
+use crate::{compositor, config};
+
+/// how often the modules directory is checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// what `LayerSurfaceRaw::exclusive_zone` holds when the module wants the
+/// host to work it out from the size and anchor, `ExclusiveZone::Auto` in
+/// the module library
+const EXCLUSIVE_ZONE_AUTO: i32 = i32::MIN;
 
 #[repr(C)]
 #[derive(Debug)]
@@ -31,11 +50,13 @@ pub struct SetupFuncData {
     registers_bytes_ptr: u32,
 }
 
+unsafe impl FromBytes for SetupFuncData {}
+
 /// represents the raw data for a `LayerSurface` so the wasm host can safely
 /// read the data
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
-struct LayerSurfaceRaw {
+pub(super) struct LayerSurfaceRaw {
     pub id: u32,
     /// `Layer` gets converted to a u8
     pub layer: u8,
@@ -51,12 +72,101 @@ struct LayerSurfaceRaw {
     pub margin_ptr: u32,
     /// pointer to the Limits object
     pub limits_ptr: u32,
+    /// `EXCLUSIVE_ZONE_AUTO` for the host to work it out
     pub exclusive_zone: i32,
     /// `KeyboardInteractivity` gets converted to a u8
     pub keyboard_interactivity: u8,
     /// boolean for pointer interactivity is converted to a u8 to be safe
     /// to transport between wasm host and guest
     pub pointer_interactivity: u8,
+    /// `SurfaceRole` as a u8 from abi version 11, it's in what was padding
+    /// before so older modules can have anything there
+    pub role: u8,
+}
+
+/// what a surface is for, which the host holds its settings to so a module
+/// can't make one that takes the keyboard from the whole session by mistake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SurfaceRole {
+    /// the module's settings are used as they are
+    None,
+    /// always shown along an edge, on the top or bottom layer without
+    /// taking the keyboard
+    Bar,
+    /// opened from a bar or a hotkey, can be given the keyboard when
+    /// clicked but never keeps it and doesn't push windows away
+    Popup,
+    /// a volume or brightness popup, on the overlay layer and never takes
+    /// the keyboard
+    Osd,
+    /// on top of everything including fullscreen windows, can be given the
+    /// keyboard when clicked but never keeps it
+    Overlay,
+    /// a wallpaper or desktop widgets, behind windows and under the other
+    /// surfaces
+    Background,
+}
+
+impl TryFrom<u8> for SurfaceRole {
+    type Error = ();
+
+    fn try_from(role: u8) -> Result<Self, ()> {
+        match role {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Bar),
+            2 => Ok(Self::Popup),
+            3 => Ok(Self::Osd),
+            4 => Ok(Self::Overlay),
+            5 => Ok(Self::Background),
+            _ => Err(()),
+        }
+    }
+}
+
+impl SurfaceRole {
+    /// changes what `settings` has that the role doesn't allow, returning
+    /// what was changed
+    fn enforce(self, settings: &mut SctkLayerSurfaceSettings) -> Vec<&'static str> {
+        let mut changed = vec![];
+
+        let layer = match (self, settings.layer) {
+            (Self::None, layer) => layer,
+            (Self::Bar, Layer::Top | Layer::Bottom) => settings.layer,
+            (Self::Bar, _) => Layer::Top,
+            (Self::Popup, Layer::Top | Layer::Overlay) => settings.layer,
+            (Self::Popup, _) => Layer::Top,
+            (Self::Osd | Self::Overlay, _) => Layer::Overlay,
+            (Self::Background, _) => Layer::Background,
+        };
+        if layer != settings.layer {
+            settings.layer = layer;
+            changed.push("layer");
+        }
+
+        let keyboard_interactivity = match (self, settings.keyboard_interactivity) {
+            (Self::None, keyboard_interactivity) => keyboard_interactivity,
+            (Self::Osd | Self::Background, _) => KeyboardInteractivity::None,
+            (_, KeyboardInteractivity::Exclusive) => KeyboardInteractivity::OnDemand,
+            (_, keyboard_interactivity) => keyboard_interactivity,
+        };
+        if keyboard_interactivity != settings.keyboard_interactivity {
+            settings.keyboard_interactivity = keyboard_interactivity;
+            changed.push("keyboard interactivity");
+        }
+
+        let exclusive_zone = match self {
+            Self::None | Self::Bar => settings.exclusive_zone,
+            Self::Popup | Self::Osd | Self::Overlay => settings.exclusive_zone.min(0),
+            // covers the whole output, bars and all
+            Self::Background => -1,
+        };
+        if exclusive_zone != settings.exclusive_zone {
+            settings.exclusive_zone = exclusive_zone;
+            changed.push("exclusive zone");
+        }
+
+        return changed;
+    }
 }
 
 #[repr(C)]
@@ -77,12 +187,23 @@ pub struct Limits {
     pub max_height: f32,
 }
 
+unsafe impl FromBytes for LayerSurfaceRaw {}
+unsafe impl FromBytes for Margin {}
+unsafe impl FromBytes for Limits {}
+
 impl LayerSurfaceRaw {
-    fn into_iced(
+    /// reads the surface a module wrote at `ptr`, `None` if it's out of
+    /// bounds
+    pub(super) fn read(memory: &[u8], ptr: u32) -> Option<Self> {
+        return memory::guest_read(memory, ptr as usize).ok();
+    }
+
+    pub(super) fn into_iced(
         self,
         memory: &[u8],
         wasm_id: &WasmId,
         file_name: &str,
+        abi_version: AbiVersion,
     ) -> Option<SctkLayerSurfaceSettings> {
         // we must get the iced::window::Id that the surface id maps to
         // so iced knows what surface we're actually rendering on
@@ -114,25 +235,17 @@ impl LayerSurfaceRaw {
             }
         }
 
-        let margin = {
-            let offset = self.margin_ptr as usize;
-            let end = offset + std::mem::size_of::<Margin>();
-
-            if offset >= memory.len() || end >= memory.len() {
+        let margin: Margin = match memory::guest_read(memory, self.margin_ptr as usize) {
+            Ok(margin) => margin,
+            Err(err) => {
                 log::error!(
-                    "[wasm] [module:{}] setup_func_data: offsets out of bounds: {}-{}, memory \
-                     size: {}",
+                    "[wasm] [module:{}] surface {} margin: {}",
                     file_name,
-                    offset,
-                    end,
-                    memory.len()
+                    self.id,
+                    err
                 );
                 return None;
             }
-
-            let bytes = &memory[offset..end];
-
-            unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const Margin) }
         };
 
         let margin = IcedMargin {
@@ -142,25 +255,17 @@ impl LayerSurfaceRaw {
             left: margin.left,
         };
 
-        let limits = {
-            let offset = self.limits_ptr as usize;
-            let end = offset + std::mem::size_of::<Limits>();
-
-            if offset >= memory.len() || end >= memory.len() {
+        let limits: Limits = match memory::guest_read(memory, self.limits_ptr as usize) {
+            Ok(limits) => limits,
+            Err(err) => {
                 log::error!(
-                    "[wasm] [module:{}] setup_func_data: offsets out of bounds: {}-{}, memory \
-                     size: {}",
+                    "[wasm] [module:{}] surface {} limits: {}",
                     file_name,
-                    offset,
-                    end,
-                    memory.len()
+                    self.id,
+                    err
                 );
                 return None;
             }
-
-            let bytes = &memory[offset..end];
-
-            unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const Limits) }
         };
 
         let limits = IcedLimits::new(
@@ -181,6 +286,23 @@ impl LayerSurfaceRaw {
             _ => return None,
         };
 
+        let exclusive_zone = match self.exclusive_zone {
+            EXCLUSIVE_ZONE_AUTO => match auto_exclusive_zone(anchor, size, &margin) {
+                Some(zone) => zone,
+                None => {
+                    log::warn!(
+                        "[wasm] [module:{}] surface {} has an automatic exclusive zone but isn't \
+                         anchored to one edge with a size in that direction, not reserving any \
+                         space",
+                        file_name,
+                        self.id
+                    );
+                    0
+                }
+            },
+            zone => zone,
+        };
+
         // fix: needs to be redone since SctkLayerSurfaceSettings updated grr
         let pointer_interactivity = match self.pointer_interactivity {
             0 => false,
@@ -188,7 +310,12 @@ impl LayerSurfaceRaw {
             _ => return None,
         };
 
-        Some(SctkLayerSurfaceSettings {
+        let role = match abi_version.has_surface_roles() {
+            true => SurfaceRole::try_from(self.role).ok()?,
+            false => SurfaceRole::None,
+        };
+
+        let mut settings = SctkLayerSurfaceSettings {
             namespace: "aurorashell".to_string(),
             output: IcedOutput::Active,
             id,
@@ -197,13 +324,55 @@ impl LayerSurfaceRaw {
             size,
             margin,
             size_limits: limits,
-            exclusive_zone: self.exclusive_zone,
+            exclusive_zone,
             keyboard_interactivity,
             ..Default::default()
-        })
+        };
+
+        let changed = role.enforce(&mut settings);
+        if !changed.is_empty() {
+            log::warn!(
+                "[wasm] [module:{}] surface {} is a {:?} surface, so its {} had to be changed",
+                file_name,
+                self.id,
+                role,
+                changed.join(", ")
+            );
+        }
+
+        return compositor::fit_layer_surface(settings, &format!("module:{file_name}"));
     }
 }
 
+/// the size of a surface plus its margin on the edge it's anchored to, so
+/// other surfaces and windows stay clear of all of it
+///
+/// `None` if the surface isn't anchored to exactly one edge (optionally
+/// stretched along it) or has no size away from that edge
+fn auto_exclusive_zone(
+    anchor: Anchor,
+    size: Option<(Option<u32>, Option<u32>)>,
+    margin: &IcedMargin,
+) -> Option<i32> {
+    let (width, height) = size?;
+    let top = anchor.contains(Anchor::TOP);
+    let bottom = anchor.contains(Anchor::BOTTOM);
+    let left = anchor.contains(Anchor::LEFT);
+    let right = anchor.contains(Anchor::RIGHT);
+
+    // anchored to opposite edges the surface isn't against one edge, and
+    // anchored to a corner the compositor can't tell which edge is meant
+    let zone = match (top != bottom, left != right) {
+        (true, false) if top => height? as i32 + margin.top,
+        (true, false) => height? as i32 + margin.bottom,
+        (false, true) if left => width? as i32 + margin.left,
+        (false, true) => width? as i32 + margin.right,
+        _ => return None,
+    };
+
+    return Some(zone.max(0));
+}
+
 /// gets the Instance and Memory objects for each module
 /// and calls `setup()` on each to get their module name and any events
 /// that they're registering to
@@ -211,17 +380,8 @@ impl LayerSurfaceRaw {
 /// app must have received `WasmState` before this is called
 pub async fn load_modules(
     host: &mut WasmHost,
-    chan: &mut IcedSender<RuntimeEvent<WasmRuntime>>,
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
 ) -> anyhow::Result<Vec<WasmModule>> {
-    use std::sync::Arc;
-
-    use tokio::sync::Mutex;
-
-    let stream = tokio_stream::iter(get_module_paths("wasm")?);
-
-    let host = Arc::new(Mutex::new(host));
-    let chan = Arc::new(Mutex::new(chan));
-
     // fix: each module that fails to load needs to state that it was skipped after
     // right now that doesn't happen and just either logs an error or warning
     // stating what happened but doesn't say the module was skipped
@@ -231,328 +391,632 @@ pub async fn load_modules(
     // if loading the module fails, say that the module with the file name,
     // was skipped :3
 
-    let modules = Ok(stream
-        .enumerate()
-        .filter_map(|(id, path)| {
-            let host = Arc::clone(&host);
-            let chan = Arc::clone(&chan);
-
-            async move {
-                let file_name = match path.file_name() {
-                    Some(res) => res,
-                    None => {
-                        log::error!(
-                            "[wasm] [module] path does not have a file name?? path: {:?}",
-                            path
-                        );
-                        return None;
-                    }
-                }
-                .to_string_lossy()
-                .to_string();
-
-                let context = WasiContext {
-                    wasip1: WasiCtxBuilder::new()
-                        .inherit_stdout()
-                        .inherit_stderr()
-                        .build_p1(),
-                    surface_wasm_id: Default::default(),
-                    used_surface_ids: RefCell::new(vec![]),
-                };
-
-                let mut store = Store::new(&host.lock().await.engine, context);
-
-                let module = match Module::from_file(&host.lock().await.engine, &path) {
-                    Ok(res) => res,
-                    Err(err) => {
-                        log::error!(
-                            "[wasm] [module] could not load module at `{}`, error: {}",
-                            path.to_string_lossy(),
-                            err
-                        );
-                        return None;
-                    }
-                };
-
-                let instance = match host
-                    .lock()
-                    .await
-                    .linker
-                    .instantiate_async(&mut store, &module)
-                    .await
-                {
-                    Ok(res) => res,
-                    Err(err) => {
-                        log::error!(
-                            "[wasm] [module:{}] could not instantiate module: {}",
-                            file_name,
-                            err
-                        );
-                        return None;
-                    }
-                };
-
-                let memory = match instance.get_memory(&mut store, "memory") {
-                    Some(mem) => mem,
-                    None => {
-                        log::error!(
-                            "[wasm] [module:{}] couldn't get memory from instance",
-                            file_name
-                        );
-                        return None;
-                    }
-                };
-
-                let setup_func = match instance.get_typed_func::<(), u32>(&mut store, "setup") {
-                    Ok(func) => func,
-                    Err(err) => {
-                        log::error!(
-                            "[wasm] [module:{}] setup function does not exist or is incorrect \
-                             type: {}",
-                            file_name,
-                            err
-                        );
-                        return None;
-                    }
-                };
-                let offset = match setup_func.call_async(&mut store, ()).await {
-                    Ok(res) => res,
-                    Err(err) => {
-                        log::error!(
-                            "[wasm] [module:{}] calling `setup` failed: {}",
-                            file_name,
-                            err
-                        );
-                        return None;
-                    }
-                };
-
-                let memory_bytes = memory.data(&store);
-
-                let setup_func_data = {
-                    let offset = offset as usize;
-                    let end = offset as usize + std::mem::size_of::<SetupFuncData>();
-
-                    if offset >= memory_bytes.len() || end >= memory_bytes.len() {
-                        log::error!(
-                            "[wasm] [module:{}] setup_func_data: offsets out of bounds: \
-                             {:02X}-{:02X}, memory size: {:02X}",
-                            file_name,
-                            offset,
-                            end,
-                            memory_bytes.len()
-                        );
-                        return None;
-                    }
+    let mut modules = vec![];
 
-                    let bytes = &memory_bytes[offset..end];
-                    unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const SetupFuncData) }
-                };
-
-                let module_name = {
-                    let offset = setup_func_data.module_name_ptr as usize;
-                    let len = setup_func_data.module_name_len as usize;
-                    let end = offset + len;
-
-                    if offset >= memory_bytes.len() || end >= memory_bytes.len() {
-                        log::error!(
-                            "[wasm] [module:{}] module_name: offsets out of bounds: \
-                             {:02X}-{:02X}, memory size: {:02X}",
-                            file_name,
-                            offset,
-                            end,
-                            memory_bytes.len()
-                        );
-                        return None;
-                    }
+    for (id, path) in module_paths()?.into_iter().enumerate() {
+        if let Some(module) = load_module(host, chan, id as u32, path).await {
+            modules.push(module);
+        }
+    }
 
-                    let bytes = &memory_bytes[offset..end];
+    return Ok(modules);
+}
 
-                    match str::from_utf8(bytes).ok() {
-                        Some(s) => s,
-                        None => {
-                            log::error!(
-                                "[wasm] [module:{}] failed to get module name: failed to convert \
-                                 string from bytes: {:?}",
-                                file_name,
-                                bytes
-                            );
-                            return None;
-                        }
-                    }
-                    .to_string()
-                };
-
-                let layer_surfaces = {
-                    let offset = setup_func_data.layer_surfaces_ptr as usize;
-                    let len = setup_func_data.layer_surfaces_len as usize;
-                    let end = offset + len * std::mem::size_of::<LayerSurfaceRaw>();
-
-                    if offset >= memory_bytes.len() || end >= memory_bytes.len() {
-                        log::error!(
-                            "[wasm] [module:{}] layer_surfaces: offsets out of bounds: \
-                             {:02X}-{:02X}, memory size: {:02X}",
-                            file_name,
-                            offset,
-                            end,
-                            memory_bytes.len()
-                        );
-                        return None;
-                    }
+/// instantiates the module at `path` and calls its `setup()`, requesting
+/// the layer surfaces it asks for
+///
+/// returns `None` if the module couldn't be loaded, the reason is logged and
+/// sent to the app as a `ModuleError::SetupFailed`
+pub async fn load_module(
+    host: &mut WasmHost,
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    id: u32,
+    path: PathBuf,
+) -> Option<WasmModule> {
+    let setup = match setup_module(host, id, path.clone()).await {
+        Ok(setup) => setup,
+        Err(err) => {
+            log::error!("{err}");
+
+            let error = ModuleError::SetupFailed {
+                file_path: path,
+                message: err.to_string(),
+            };
+            if let Err(err) = chan
+                .send(RuntimeEvent::Update(Event::ModuleError {
+                    module_id: id,
+                    error,
+                }))
+                .await
+            {
+                log::warn!("[wasm] could not tell the app a module failed to load: {err}");
+            }
+            return None;
+        }
+    };
 
-                    let bytes = &memory_bytes[offset..end];
+    for warning in &setup.warnings {
+        log::warn!("{warning}");
+    }
 
-                    unsafe {
-                        std::slice::from_raw_parts(bytes.as_ptr() as *const LayerSurfaceRaw, len)
-                    }
-                };
+    for layer in setup.surfaces {
+        // request the app to create a layer surface for us
+        if let Err(err) = chan
+            .send(RuntimeEvent::Update(Event::CreateLayerSurface(layer)))
+            .await
+        {
+            log::warn!(
+                "[wasm] [module:{}] layer surface could not be created (skipped): {}",
+                setup.module.module_name,
+                err
+            );
+        }
+    }
 
-                for surface in layer_surfaces {
-                    // if the id that the surface uses was leased to the module we add
-                    // it to a list of ids that this module uses
-                    if store.data().surface_wasm_id.has_lease(surface.id) {
-                        store.data().used_surface_ids.borrow_mut().push(surface.id);
-                    }
-                    let layer_settings =
-                        surface.into_iced(memory_bytes, &store.data().surface_wasm_id, &file_name);
-                    if let Some(layer) = layer_settings {
-                        // request the app to create a layer surface for us
-                        match chan
-                            .lock()
-                            .await
-                            .send(RuntimeEvent::Update(Event::CreateLayerSurface(layer)))
-                            .await
-                        {
-                            Ok(_) => {}
-                            Err(err) => {
-                                log::warn!(
-                                    "[wasm] [module:{}] layer surface could not be created \
-                                     (skipped): {}",
-                                    file_name,
-                                    err
-                                );
-                                continue;
-                            }
-                        };
-                    } else {
-                        log::warn!(
-                            "[wasm] [module:{}] layer surface invalid (skipped): {:?}",
-                            file_name,
-                            surface
-                        );
-                    }
-                }
+    return Some(setup.module);
+}
 
-                let registers_bytes = {
-                    let offset = setup_func_data.registers_bytes_ptr as usize;
-
-                    if offset >= memory_bytes.len() || offset + 4 >= memory_bytes.len() {
-                        log::error!(
-                            "[wasm] [module:{}] registers: offsets out of bounds: {:02X}-{:02X}, \
-                             memory size: {:02X}",
-                            file_name,
-                            offset,
-                            offset + 4,
-                            memory_bytes.len(),
-                        );
-                        return None;
-                    }
+/// a module whose `setup()` ran, before the layer surfaces it asked for are
+/// created
+pub struct SetupModule {
+    pub module: WasmModule,
+    pub surfaces: Vec<SctkLayerSurfaceSettings>,
+    /// problems that didn't stop the module from loading, like layer
+    /// surfaces that are invalid and were skipped
+    pub warnings: Vec<String>,
+}
 
-                    let size_bytes: [u8; 4] = match memory_bytes[offset..offset + 4].try_into() {
-                        Ok(bytes) => bytes,
-                        Err(err) => {
-                            log::error!(
-                                "[wasm] [module:{}] somehow couldn't convert a slice of length 4 \
-                                 to an array of length 4: {}",
-                                file_name,
-                                err,
-                            );
-                            return None;
-                        }
-                    };
-                    let size = u32::from_be_bytes(size_bytes);
-
-                    let end = offset + size as usize;
-
-                    if end >= memory_bytes.len() {
-                        log::error!(
-                            "[wasm] [module:{}] registers: end offset out of bounds: {:02X}, \
-                             memory size: {:02X}",
-                            file_name,
-                            end,
-                            memory_bytes.len(),
-                        );
-                        return None;
-                    }
+/// instantiates the module at `path` and calls its `setup()`, reading the
+/// layer surfaces and registers it asks for without creating anything
+///
+/// doesn't need the app, so `aurorashell check` can run it without a
+/// wayland connection
+pub async fn setup_module(host: &WasmHost, id: u32, path: PathBuf) -> anyhow::Result<SetupModule> {
+    let file_name = match path.file_name() {
+        Some(res) => res,
+        None => {
+            return Err(anyhow!(
+                "[wasm] [module] path does not have a file name?? path: {:?}",
+                path
+            ));
+        }
+    }
+    .to_string_lossy()
+    .to_string();
+
+    let mut wasi = WasiCtxBuilder::new();
+    wasi.inherit_stdout().inherit_stderr().arg(&file_name);
+
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy());
+    if let Some(settings) = stem
+        .as_ref()
+        .and_then(|stem| config::get().modules.wasi.get(stem.as_ref()))
+    {
+        for (key, value) in &settings.env {
+            wasi.env(key, value);
+        }
+        wasi.args(&settings.args);
+    }
 
-                    let registers_bytes = &memory_bytes[offset..end];
-                    registers_bytes
-                };
-
-                let registers: Vec<SubscriptionData> =
-                    match Deserialize::deserialize(registers_bytes) {
-                        Ok(res) => res,
-                        Err(err) => {
-                            log::error!(
-                                "[wasm] [module:{}] could not deserialize registers bytes: {}",
-                                file_name,
-                                err
-                            );
-                            return None;
-                        }
-                    };
-
-                let setup_cleanup_func =
-                    match instance.get_typed_func::<(), ()>(&mut store, "setup_cleanup") {
-                        Ok(func) => func,
-                        Err(err) => {
-                            log::error!(
-                                "[wasm] [module:{}] setup_cleanup function does not exist or is \
-                                 incorrect type: {}",
-                                file_name,
-                                err
-                            );
-                            return None;
-                        }
-                    };
-                match setup_cleanup_func.call_async(&mut store, ()).await {
-                    Ok(_) => {}
-                    Err(err) => {
-                        log::error!(
-                            "[wasm] [module:{}] calling `setup_cleanup` failed: {}",
-                            file_name,
-                            err
-                        );
-                        return None;
-                    }
-                };
-
-                Some(WasmModule {
-                    id: id as u32,
-                    module_name,
-                    file_path: path,
-                    registers,
-                    store,
-                    instance,
-                    memory,
-                })
+    let settings = stem
+        .and_then(|stem| config::get().modules.settings.get(stem.as_ref()))
+        .map(serialize_settings)
+        .unwrap_or_default();
+
+    let context = WasiContext {
+        wasip1: wasi.build_p1(),
+        surface_wasm_id: Default::default(),
+        used_surface_ids: RefCell::new(vec![]),
+        clock: vec![],
+        audio: host.audio.clone(),
+        audio_summary: host
+            .audio
+            .as_ref()
+            .map(|devices| devices.summary(host.audio_revision))
+            .unwrap_or_default(),
+        kdeconnect: host.kdeconnect.clone(),
+        theme: host.theme.clone(),
+        kdeconnect_requests: vec![],
+        audio_requests: vec![],
+        inhibit_requests: vec![],
+        session_requests: vec![],
+        clipboard: None,
+        callback_text: String::new(),
+        unicode_results: vec![],
+        region_request: None,
+        region: vec![],
+        screenshot_request: None,
+        screenshot: vec![],
+        screenshot_pending: false,
+        surface_requests: vec![],
+        dirty_surfaces: HashSet::new(),
+        surfaces: HashMap::new(),
+        command_requests: vec![],
+        pending_commands: HashMap::new(),
+        command_output: vec![],
+        service_event: vec![],
+        module_name: String::new(),
+        abi_version: AbiVersion::V0,
+        settings,
+        errors: vec![],
+    };
+
+    let mut store = Store::new(&host.engine, context);
+    limits::set_deadlines(&mut store);
+
+    let module = match target::compile(&host.engine, &path) {
+        Ok(res) => res,
+        Err(err) => {
+            return Err(anyhow!(
+                "[wasm] [module] could not load module at `{}`, error: {}",
+                path.to_string_lossy(),
+                err
+            ));
+        }
+    };
+
+    let instance = match host.linker.instantiate_async(&mut store, &module).await {
+        Ok(res) => res,
+        Err(err) => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] could not instantiate module: {}",
+                file_name,
+                err
+            ));
+        }
+    };
+
+    let memory = match instance.get_memory(&mut store, "memory") {
+        Some(mem) => mem,
+        None => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] couldn't get memory from instance",
+                file_name
+            ));
+        }
+    };
+
+    let abi_version = match AbiVersion::negotiate(&mut store, &instance).await {
+        Ok(version) => version,
+        Err(err) => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] could not get the abi version: {}",
+                file_name,
+                err
+            ));
+        }
+    };
+    log::debug!("[wasm] [module:{file_name}] uses abi version {abi_version}");
+    store.data_mut().abi_version = abi_version;
+
+    let setup_func = match instance.get_typed_func::<(), u32>(&mut store, "setup") {
+        Ok(func) => func,
+        Err(err) => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] setup function does not exist or is incorrect type: {}",
+                file_name,
+                err
+            ));
+        }
+    };
+    let offset = match setup_func.call_async(&mut store, ()).await {
+        Ok(res) => res,
+        Err(err) => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] calling `setup` failed: {}",
+                file_name,
+                err
+            ));
+        }
+    };
+
+    let memory_bytes = memory.data(&store);
+
+    let setup_func_data: SetupFuncData = match memory::guest_read(memory_bytes, offset as usize) {
+        Ok(data) => data,
+        Err(err) => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] setup_func_data: {}",
+                file_name,
+                err
+            ));
+        }
+    };
+
+    let module_name = match memory::guest_str(
+        memory_bytes,
+        setup_func_data.module_name_ptr as usize,
+        setup_func_data.module_name_len as usize,
+    ) {
+        Ok(name) => name.to_string(),
+        Err(err) => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] module_name: {}",
+                file_name,
+                err
+            ));
+        }
+    };
+
+    let layer_surfaces: Vec<LayerSurfaceRaw> = match memory::guest_read_slice(
+        memory_bytes,
+        setup_func_data.layer_surfaces_ptr,
+        setup_func_data.layer_surfaces_len,
+    ) {
+        Ok(surfaces) => surfaces,
+        Err(err) => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] layer_surfaces: {}",
+                file_name,
+                err
+            ));
+        }
+    };
+
+    let mut surfaces = vec![];
+    let mut warnings = vec![];
+    for surface in &layer_surfaces {
+        // if the id that the surface uses was leased to the module we add
+        // it to a list of ids that this module uses
+        if store.data().surface_wasm_id.has_lease(surface.id) {
+            store.data().used_surface_ids.borrow_mut().push(surface.id);
+        }
+        let layer_settings = surface.into_iced(
+            memory_bytes,
+            &store.data().surface_wasm_id,
+            &file_name,
+            abi_version,
+        );
+        match layer_settings {
+            Some(layer) => {
+                store.data_mut().surfaces.insert(surface.id, layer.clone());
+                surfaces.push(layer);
             }
-        })
-        .collect::<Vec<WasmModule>>()
-        .await);
+            None => warnings.push(format!(
+                "[wasm] [module:{}] layer surface invalid (skipped): {:?}",
+                file_name, surface
+            )),
+        }
+    }
+
+    let registers_bytes = {
+        let offset = setup_func_data.registers_bytes_ptr as usize;
+
+        // the table starts with its size, in the byte order of the abi version
+        let size = match memory::guest_read::<[u8; 4]>(memory_bytes, offset) {
+            Ok(size_bytes) => abi_version.registers_byte_order().u32(size_bytes),
+            Err(err) => {
+                return Err(anyhow!("[wasm] [module:{}] registers: {}", file_name, err));
+            }
+        };
+
+        match memory::guest_bytes(memory_bytes, offset, size as usize) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                return Err(anyhow!("[wasm] [module:{}] registers: {}", file_name, err));
+            }
+        }
+    };
+
+    let mut registers: Vec<SubscriptionData> =
+        match Deserialize::deserialize(registers_bytes, abi_version) {
+            Ok(res) => res,
+            Err(err) => {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] could not deserialize registers bytes: {}",
+                    file_name,
+                    err
+                ));
+            }
+        };
+
+    let setup_cleanup_func = match instance.get_typed_func::<(), ()>(&mut store, "setup_cleanup") {
+        Ok(func) => func,
+        Err(err) => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] setup_cleanup function does not exist or is incorrect type: {}",
+                file_name,
+                err
+            ));
+        }
+    };
+    match setup_cleanup_func.call_async(&mut store, ()).await {
+        Ok(_) => {}
+        Err(err) => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] calling `setup_cleanup` failed: {}",
+                file_name,
+                err
+            ));
+        }
+    };
+
+    // the portal keeps hotkeys by the module's name, see
+    // `HotkeysSubscriptionData::module`, and what a module can watch is
+    // listed by its name
+    for register in &mut registers {
+        match register {
+            SubscriptionData::Hotkeys { data } => data.module = module_name.clone(),
+            SubscriptionData::Watch { data } => data.module = module_name.clone(),
+            SubscriptionData::Dbus { data } => data.module = module_name.clone(),
+            _ => {}
+        }
+    }
+
+    store.data_mut().module_name = module_name.clone();
+
+    let has_service_event = instance.get_export(&mut store, "service_event").is_some();
+
+    let module = WasmModule {
+        id,
+        module_name,
+        file_path: path,
+        registers,
+        store,
+        instance,
+        memory,
+        abi_version,
+        tree_bytes: HashMap::new(),
+        generations: HashMap::new(),
+        sent_views: HashMap::new(),
+        events: EventQueue::default(),
+        disabled: false,
+        traps: 0,
+        has_service_event,
+        images: ImageCache::default(),
+        callback_timing: None,
+        stats: ModuleStats::default(),
+        sent_stats: ModuleStats::default(),
+    };
 
-    return modules;
+    return Ok(SetupModule {
+        module,
+        surfaces,
+        warnings,
+    });
 }
 
-/// get file paths for modules in $HOME/.local/share/aurorashell/modules
-///
-/// if the directory doesn't exist, it will be created
+/// what `check_modules` found out about a module
+#[derive(Debug)]
+pub struct ModuleCheck {
+    pub path: PathBuf,
+    /// the name the module gave itself, `None` if it couldn't be set up
+    pub name: Option<String>,
+    /// like `8`
+    pub abi_version: Option<String>,
+    pub surfaces: usize,
+    pub registers: usize,
+    /// why the module couldn't be set up
+    pub error: Option<String>,
+    /// problems that didn't stop the module from loading
+    pub warnings: Vec<String>,
+    /// a line for each surface and register the module asked for
+    pub declared: Vec<String>,
+}
+
+/// sets up every module like the shell would on startup, without creating
+/// their surfaces or rendering them
+pub async fn check_modules() -> anyhow::Result<Vec<ModuleCheck>> {
+    let host = WasmHost::new()?;
+    let mut checks = vec![];
+
+    for (id, path) in module_paths()?.into_iter().enumerate() {
+        checks.push(check_module(&host, id as u32, path).await);
+    }
+
+    return Ok(checks);
+}
+
+/// sets up the module at `path` like `check_modules`, it doesn't have to be
+/// in a modules directory
+pub async fn validate_module(path: &Path) -> anyhow::Result<ModuleCheck> {
+    if !path.try_exists()? {
+        return Err(anyhow!("{} does not exist", path.display()));
+    }
+
+    let host = WasmHost::new()?;
+    return Ok(check_module(&host, 0, path.to_path_buf()).await);
+}
+
+async fn check_module(host: &WasmHost, id: u32, path: PathBuf) -> ModuleCheck {
+    let mut check = ModuleCheck {
+        path: path.clone(),
+        name: None,
+        abi_version: None,
+        surfaces: 0,
+        registers: 0,
+        error: None,
+        warnings: vec![],
+        declared: vec![],
+    };
+
+    match setup_module(host, id, path).await {
+        Ok(mut setup) => {
+            let module = &mut setup.module;
+            // setup doesn't call it, but the module can't be shown
+            // without one
+            if let Err(err) = module
+                .instance
+                .get_typed_func::<u32, u32>(&mut module.store, "view")
+            {
+                setup.warnings.push(format!(
+                    "[wasm] [module:{}] view function does not exist or is incorrect type: {}",
+                    module.module_name, err
+                ));
+            }
+
+            check.name = Some(module.module_name.clone());
+            check.abi_version = Some(module.abi_version.to_string());
+            check.surfaces = setup.surfaces.len();
+            check.registers = module.registers.len();
+            check.warnings = setup.warnings;
+
+            for surface in &setup.surfaces {
+                check.declared.push(format!(
+                    "surface: {:?} layer, anchored {:?}, size {:?}, exclusive zone {}",
+                    surface.layer, surface.anchor, surface.size, surface.exclusive_zone
+                ));
+            }
+            for register in &module.registers {
+                check.declared.push(format!("register {register:?}"));
+            }
+        }
+        Err(err) => check.error = Some(err.to_string()),
+    }
+
+    return check;
+}
+
+/// watches the modules directory, sending a `Request::ReloadModule` when a
+/// module is added, rebuilt or removed
 ///
-/// no filter returns files with no extension
-/// "*" filter returns all files
+/// the directory is polled, a file is only reported once it has stopped
+/// changing for one poll so half written modules aren't loaded. the watcher
+/// stops once the runtime is gone
+pub fn watch_modules(request_tx: InstrumentedSender<flume::Sender<RuntimeRequest<WasmRuntime>>>) {
+    /// what's compared to tell if a file changed
+    type Stamp = (Option<SystemTime>, u64);
+
+    fn scan() -> HashMap<PathBuf, Stamp> {
+        let paths = match module_paths() {
+            Ok(paths) => paths,
+            Err(err) => {
+                log::warn!("[wasm] [watcher] could not read the modules directory: {err}");
+                return HashMap::new();
+            }
+        };
+
+        return paths
+            .into_iter()
+            .filter_map(|path| {
+                let metadata = fs::metadata(&path).ok()?;
+                Some((path, (metadata.modified().ok(), metadata.len())))
+            })
+            .collect();
+    }
+
+    tokio::spawn(async move {
+        let mut seen = scan();
+        // files that changed on the last poll and might still be written to
+        let mut settling: HashSet<PathBuf> = HashSet::new();
+
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+
+            let current = scan();
+            let mut changed = vec![];
+
+            for (path, stamp) in &current {
+                if seen.get(path) != Some(stamp) {
+                    settling.insert(path.clone());
+                } else if settling.remove(path) {
+                    changed.push(path.clone());
+                }
+            }
+
+            for path in seen.keys().filter(|path| !current.contains_key(*path)) {
+                settling.remove(path);
+                changed.push(path.clone());
+            }
+
+            seen = current;
+
+            for path in changed {
+                log::info!("[wasm] [watcher] {} changed, reloading", path.display());
+
+                let request = RuntimeRequest::new(Request::ReloadModule { path });
+                if request_tx.send_async(request).await.is_err() {
+                    log::debug!("[wasm] [watcher] runtime stopped, no longer watching modules");
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// precompiles the module at `path` for this machine and version of
+/// wasmtime, writing it to the modules directory as a `.cwasm` which starts
+/// faster than compiling the `.wasm` each time
 ///
-/// `filter`: file extension to filter by
-fn get_module_paths(filter: &str) -> anyhow::Result<Vec<PathBuf>> {
+/// a `.wasm` of the same name in the modules directory is removed so the
+/// module isn't loaded twice, returns where the module was written
+pub fn install_module(path: &Path) -> anyhow::Result<PathBuf> {
+    let Some(name) = path.file_stem() else {
+        return Err(anyhow!("{} is not a file", path.display()));
+    };
+    let file_name = path.file_name().unwrap_or(name).to_string_lossy();
+
+    let engine = Engine::new(&target::engine_config())?;
+    let bytes = fs::read(path)?;
+
+    target::check_binary(&engine, &bytes)?;
+    let module = Module::new(&engine, &bytes)?;
+    target::check_imports(&module, &file_name)?;
+
+    let dir = modules_dir()?;
+    let output = dir.join(name).with_extension("cwasm");
+    fs::write(&output, module.serialize()?)?;
+
+    let wasm = dir.join(name).with_extension("wasm");
+    if wasm.try_exists()? {
+        fs::remove_file(&wasm)?;
+        log::info!(
+            "[wasm] removed {}, it was replaced by {}",
+            wasm.display(),
+            output.display()
+        );
+    }
+
+    return Ok(output);
+}
+
+/// the paths of `.wasm` modules and `.cwasm` modules precompiled by
+/// `install_module` in every modules directory, without the modules turned
+/// off with `enabled` or `disabled` under `[modules]`
+fn module_paths() -> anyhow::Result<Vec<PathBuf>> {
+    let modules = &config::get().modules;
+    let mut paths = vec![];
+
+    for dir in modules_dirs()? {
+        // only the first directory is created, the others may be on a drive
+        // that isn't mounted yet
+        if !dir.try_exists()? {
+            continue;
+        }
+
+        paths.extend(get_module_paths(&dir, "wasm")?);
+        paths.extend(get_module_paths(&dir, "cwasm")?);
+    }
+
+    paths.retain(|path| {
+        path.file_stem()
+            .is_some_and(|name| modules.is_enabled(&name.to_string_lossy()))
+    });
+
+    // the directory is read in no particular order, so without sorting the
+    // modules would get different ids and surfaces would stack differently
+    // between starts
+    paths.sort_by_cached_key(|path| {
+        let name = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        (modules.load_position(&name), name)
+    });
+
+    return Ok(paths);
+}
+
+/// the directory modules are installed to, the first of `modules_dirs`
+fn modules_dir() -> anyhow::Result<PathBuf> {
+    let mut dirs = modules_dirs()?;
+    return Ok(dirs.remove(0));
+}
+
+/// the directories modules are loaded from, `dirs` under `[modules]` or
+/// $HOME/.local/share/aurorashell/modules. the first one is created if it
+/// doesn't exist
+fn modules_dirs() -> anyhow::Result<Vec<PathBuf>> {
     let home_path = match env::var("HOME") {
         Ok(v) => v,
         Err(e) => {
@@ -560,13 +1024,57 @@ fn get_module_paths(filter: &str) -> anyhow::Result<Vec<PathBuf>> {
             return Err(e.into());
         }
     };
+    let home = PathBuf::from(home_path);
+
+    let dirs = &config::get().modules.dirs;
+    let dirs: Vec<PathBuf> = match dirs.is_empty() {
+        true => vec![home.join(".local/share/aurorashell/modules")],
+        false => dirs
+            .iter()
+            .map(|dir| match dir.strip_prefix("~") {
+                Ok(rest) => home.join(rest),
+                Err(_) => dir.clone(),
+            })
+            .collect(),
+    };
 
-    let path = PathBuf::from(home_path).join(".local/share/aurorashell/modules");
+    if let false = dirs[0].try_exists()? {
+        fs::create_dir_all(dirs[0].as_path())?;
+    }
+
+    return Ok(dirs);
+}
 
-    if let false = path.try_exists()? {
-        fs::create_dir_all(path.as_path())?;
+/// the module's options from `[modules.settings.<name>]` in the layout
+/// `config::get` reads them in, a u16 amount of options then each one's key
+/// and value as a u16 length followed by that many bytes of utf-8
+fn serialize_settings(settings: &BTreeMap<String, toml::Value>) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.extend((settings.len().min(u16::MAX as usize) as u16).to_le_bytes());
+
+    for (key, value) in settings.iter().take(u16::MAX as usize) {
+        let value = match value {
+            toml::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+
+        for string in [key.as_str(), value.as_str()] {
+            let string = &string.as_bytes()[..string.len().min(u16::MAX as usize)];
+            bytes.extend((string.len() as u16).to_le_bytes());
+            bytes.extend(string);
+        }
     }
 
+    return bytes;
+}
+
+/// get file paths for modules in `path`
+///
+/// no filter returns files with no extension
+/// "*" filter returns all files
+///
+/// `filter`: file extension to filter by
+fn get_module_paths(path: &Path, filter: &str) -> anyhow::Result<Vec<PathBuf>> {
     let files = fs::read_dir(&path)?
         .filter_map(|p| match p {
             Ok(entry) => {
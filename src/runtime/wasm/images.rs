@@ -0,0 +1,94 @@
+//! keeps the images a module draws between its views, so an icon is only
+//! looked up once and a png is only decoded once while the module keeps
+//! sending the same one
+//!
+//! modules can also draw a screenshot the shell saved for them by its path,
+//! any other file is drawn as missing
+//!
+//! iced keeps a decoded image for as long as its handle is drawn, so handing
+//! back the same handle for the same bytes is what stops it decoding again
+
+use super::ui::ModuleImage;
+
+use crate::icons;
+use crate::services::screenshot;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use iced::advanced::image::Bytes;
+use iced::widget::image;
+
+/// the icon names kept, the cache is emptied when it goes over
+const MAX_ICONS: usize = 256;
+/// the pngs kept, the cache is emptied when it goes over
+const MAX_PNGS: usize = 64;
+
+/// the size icons are looked up for when the module didn't give one
+const DEFAULT_ICON_SIZE: u32 = 16;
+
+/// the images of one module
+#[derive(Debug, Default)]
+pub struct ImageCache {
+    /// `None` when there's no icon with the name
+    icons: HashMap<(String, u32), Option<PathBuf>>,
+    pngs: HashMap<Bytes, image::Handle>,
+}
+
+impl ImageCache {
+    /// the icon called `name` in the icon theme, `size` is the size it's
+    /// drawn at in logical pixels
+    pub fn icon(&mut self, name: &str, size: Option<f32>) -> ModuleImage {
+        let size = size.map_or(DEFAULT_ICON_SIZE, |size| size.round() as u32);
+        let key = (name.to_string(), size);
+
+        if !self.icons.contains_key(&key) && self.icons.len() >= MAX_ICONS {
+            self.icons.clear();
+        }
+
+        let path = self
+            .icons
+            .entry(key)
+            .or_insert_with(|| icons::find(name, size, &[]));
+
+        match path {
+            Some(path) => ModuleImage::File(path.clone()),
+            None => ModuleImage::Missing,
+        }
+    }
+
+    /// the screenshot at `path`, if it's one the screenshot service saved
+    pub fn screenshot(&mut self, path: &str) -> ModuleImage {
+        let path = Path::new(path);
+
+        match screenshot::saved(path) {
+            true => ModuleImage::File(path.to_path_buf()),
+            false => ModuleImage::Missing,
+        }
+    }
+
+    /// a handle for the png in `bytes`, the same one as last time if the
+    /// bytes are the same
+    pub fn png(&mut self, bytes: &[u8]) -> ModuleImage {
+        if let Some(handle) = self.pngs.get(bytes) {
+            return ModuleImage::Png {
+                handle: handle.clone(),
+                size: bytes.len(),
+            };
+        }
+
+        if self.pngs.len() >= MAX_PNGS {
+            self.pngs.clear();
+        }
+
+        // the handle shares the bytes with the key
+        let bytes = Bytes::copy_from_slice(bytes);
+        let handle = image::Handle::from_bytes(bytes.clone());
+        self.pngs.insert(bytes.clone(), handle.clone());
+
+        return ModuleImage::Png {
+            handle,
+            size: bytes.len(),
+        };
+    }
+}
@@ -0,0 +1,65 @@
+//! times a widget callback on its way through the shell, from the app
+//! getting the click to the module's new ui reaching its surface
+//!
+//! each stage adds to `latency.callback.<stage>_micros` and raises
+//! `latency.callback.max_<stage>_micros`, divide by `latency.callback.count`
+//! for the average
+//!
+//! - `queue`: waiting for the runtime and the module's event queue
+//! - `update`: the module's `run_callback` and `update`
+//! - `view`: waiting for the module to be rendered, then its `view`
+//! - `deliver`: the tree going back to the app
+//! - `total`: all of them, checked against `callback_budget_ms`
+
+use crate::{config, metrics};
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy)]
+pub struct CallbackTiming {
+    started: Instant,
+    /// when the last stage ended
+    last: Instant,
+}
+
+impl CallbackTiming {
+    /// starts timing a callback the app sent at `started`
+    pub fn since(started: Instant) -> Self {
+        Self {
+            started,
+            last: started,
+        }
+    }
+
+    /// records the time since the last stage as `stage`
+    pub fn stage(&mut self, stage: &str) {
+        let now = Instant::now();
+        record(stage, now - self.last);
+        self.last = now;
+    }
+
+    /// records the last stage and the whole trip, once the tree reached the
+    /// app
+    pub fn finish(mut self, module_name: &str) {
+        self.stage("deliver");
+
+        let total = self.last - self.started;
+        record("total", total);
+        metrics::increment("latency.callback.count");
+
+        let budget = config::get().modules.callback_budget_ms;
+        if budget != 0 && total > Duration::from_millis(budget) {
+            metrics::increment("latency.callback.over_budget");
+            log::debug!(
+                "[wasm] [module:{module_name}] callback took {}ms, over the budget of {budget}ms",
+                total.as_millis()
+            );
+        }
+    }
+}
+
+fn record(stage: &str, time: Duration) {
+    let micros = time.as_micros() as u64;
+    metrics::add(format!("latency.callback.{stage}_micros"), micros);
+    metrics::max(format!("latency.callback.max_{stage}_micros"), micros);
+}
@@ -0,0 +1,128 @@
+use super::{WasiContext, WasmModule};
+
+use crate::{config, metrics};
+
+use std::thread;
+use std::time::Duration;
+
+use wasmtime::{CallHook, Engine, Store, Trap};
+
+/// how often the engine's epoch goes up, deadlines for calls into modules
+/// are counted in these
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// what a module is using, checked against `[modules]` in the config after
+/// each view
+#[derive(Debug, Clone, Copy)]
+pub struct Usage {
+    /// the size of the module's ui trees across all its surfaces
+    pub tree_bytes: usize,
+    /// the size of the module's linear memory
+    pub memory_bytes: usize,
+}
+
+impl Usage {
+    pub fn of(module: &WasmModule) -> Self {
+        Self {
+            tree_bytes: module.tree_bytes.values().sum(),
+            memory_bytes: module.memory.data_size(&module.store),
+        }
+    }
+
+    /// sets the module's gauges, like `module.clock.memory_bytes`
+    pub fn record(&self, module_name: &str) {
+        metrics::set(
+            format!("module.{module_name}.tree_bytes"),
+            self.tree_bytes as u64,
+        );
+        metrics::set(
+            format!("module.{module_name}.memory_bytes"),
+            self.memory_bytes as u64,
+        );
+    }
+
+    /// why the module should be disabled, if it's over a limit
+    pub fn breach(&self) -> Option<String> {
+        let limits = &config::get().modules;
+
+        if limits.max_tree_bytes != 0 && self.tree_bytes > limits.max_tree_bytes {
+            return Some(format!(
+                "its ui uses {} which is over the limit of {}",
+                format_bytes(self.tree_bytes),
+                format_bytes(limits.max_tree_bytes)
+            ));
+        }
+
+        if limits.max_memory_bytes != 0 && self.memory_bytes > limits.max_memory_bytes {
+            return Some(format!(
+                "it uses {} of memory which is over the limit of {}",
+                format_bytes(self.memory_bytes),
+                format_bytes(limits.max_memory_bytes)
+            ));
+        }
+
+        return None;
+    }
+}
+
+/// makes the engine's epoch go up every `EPOCH_TICK` in a thread, which
+/// stops once the engine is dropped
+pub fn tick_epochs(engine: &Engine) {
+    let engine = engine.weak();
+
+    thread::spawn(move || {
+        loop {
+            thread::sleep(EPOCH_TICK);
+
+            match engine.upgrade() {
+                Some(engine) => engine.increment_epoch(),
+                None => break,
+            }
+        }
+    });
+}
+
+/// gives every call into the module `call_timeout_ms` under `[modules]` to
+/// return before it traps, so a module stuck in a loop can't hold up the
+/// runtime and every other module with it
+pub fn set_deadlines(store: &mut Store<WasiContext>) {
+    store.epoch_deadline_trap();
+    store.call_hook(|mut store, hook| {
+        if let CallHook::CallingWasm = hook {
+            store.set_epoch_deadline(deadline_ticks());
+        }
+        Ok(())
+    });
+}
+
+/// whether the module trapped because it ran past its deadline
+pub fn timed_out(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<Trap>(), Some(Trap::Interrupt))
+}
+
+/// `call_timeout_ms` in epoch ticks, rounded up
+fn deadline_ticks() -> u64 {
+    match config::get().modules.call_timeout_ms {
+        // far enough away to never be reached, without overflowing once
+        // it's added to the current epoch
+        0 => u64::MAX / 2,
+        timeout_ms => timeout_ms.div_ceil(EPOCH_TICK.as_millis() as u64),
+    }
+}
+
+/// `1536` as `1.5 KiB`
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{value:.1} {}", UNITS[unit]),
+    }
+}
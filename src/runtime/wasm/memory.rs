@@ -0,0 +1,153 @@
+//! reading what modules put in their memory
+//!
+//! every struct the host copies out of a module goes through here, so the
+//! bounds checks and the one `unsafe` read live in one place instead of next
+//! to each struct. a module can write anything to its memory, so the types
+//! read have to be fine with any bytes, which is what `FromBytes` promises
+//!
+//! nothing in here uses the rest of the shell, so the fuzz targets in `fuzz/`
+//! build it on its own
+
+use std::fmt;
+
+/// a type that can be copied out of a module's memory as is
+///
+/// # Safety
+///
+/// the type has to be `#[repr(C)]` (or a primitive) with only integer and
+/// float fields, arrays of them included, so any bytes are a valid value.
+/// bools, enums, chars, references and pointers aren't, the host reads those
+/// as integers and checks them itself
+pub(super) unsafe trait FromBytes: Sized {}
+
+unsafe impl FromBytes for u8 {}
+unsafe impl FromBytes for u16 {}
+unsafe impl FromBytes for u32 {}
+unsafe impl FromBytes for u64 {}
+unsafe impl FromBytes for i32 {}
+unsafe impl FromBytes for f32 {}
+unsafe impl<T: FromBytes, const N: usize> FromBytes for [T; N] {}
+
+/// why something couldn't be read from a module's memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum GuestReadError {
+    /// `start..end` isn't inside the module's memory, `end` is `None` when
+    /// it's past `usize::MAX`
+    OutOfBounds {
+        start: usize,
+        end: Option<usize>,
+        memory: usize,
+    },
+    /// the string at `start..end` isn't utf-8
+    NotUtf8 { start: usize, end: usize },
+}
+
+impl fmt::Display for GuestReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds {
+                start,
+                end: Some(end),
+                memory,
+            } => write!(
+                f,
+                "offsets out of bounds: {start:02X}-{end:02X}, memory size: {memory:02X}"
+            ),
+            Self::OutOfBounds {
+                start,
+                end: None,
+                memory,
+            } => write!(
+                f,
+                "offsets out of bounds: {start:02X} with a length past the end of the address \
+                 space, memory size: {memory:02X}"
+            ),
+            Self::NotUtf8 { start, end } => {
+                write!(f, "string at {start:02X}-{end:02X} is not utf-8")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GuestReadError {}
+
+/// the `len` bytes at `offset`
+pub(super) fn guest_bytes(
+    memory: &[u8],
+    offset: usize,
+    len: usize,
+) -> Result<&[u8], GuestReadError> {
+    let end = offset.checked_add(len);
+
+    return match end.and_then(|end| memory.get(offset..end)) {
+        Some(bytes) => Ok(bytes),
+        None => Err(GuestReadError::OutOfBounds {
+            start: offset,
+            end,
+            memory: memory.len(),
+        }),
+    };
+}
+
+/// the utf-8 string of `len` bytes at `offset`
+pub(super) fn guest_str(memory: &[u8], offset: usize, len: usize) -> Result<&str, GuestReadError> {
+    let bytes = guest_bytes(memory, offset, len)?;
+
+    return std::str::from_utf8(bytes).map_err(|_| GuestReadError::NotUtf8 {
+        start: offset,
+        end: offset + len,
+    });
+}
+
+/// copies the `T` at `offset`, it doesn't have to be aligned
+pub(super) fn guest_read<T: FromBytes>(memory: &[u8], offset: usize) -> Result<T, GuestReadError> {
+    let bytes = guest_bytes(memory, offset, size_of::<T>())?;
+
+    // safety: `bytes` is as long as a `T` and `FromBytes` means any bytes
+    // are a valid `T`, `read_unaligned` is fine with wherever they start
+    return Ok(unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const T) });
+}
+
+/// copies the `T` at `index` of the array the module has at `ptr`
+pub(super) fn guest_read_at<T: FromBytes>(
+    memory: &[u8],
+    ptr: u32,
+    index: u32,
+) -> Result<T, GuestReadError> {
+    let offset = (index as usize)
+        .checked_mul(size_of::<T>())
+        .and_then(|offset| offset.checked_add(ptr as usize));
+
+    return match offset {
+        Some(offset) => guest_read(memory, offset),
+        None => Err(GuestReadError::OutOfBounds {
+            start: ptr as usize,
+            end: None,
+            memory: memory.len(),
+        }),
+    };
+}
+
+/// copies the `len` `T`s of the array the module has at `ptr`
+pub(super) fn guest_read_slice<T: FromBytes>(
+    memory: &[u8],
+    ptr: u32,
+    len: u32,
+) -> Result<Vec<T>, GuestReadError> {
+    // checked all at once so a huge `len` fails before anything is copied
+    let size = (len as usize).checked_mul(size_of::<T>());
+    let bytes = match size {
+        Some(size) => guest_bytes(memory, ptr as usize, size)?,
+        None => {
+            return Err(GuestReadError::OutOfBounds {
+                start: ptr as usize,
+                end: None,
+                memory: memory.len(),
+            });
+        }
+    };
+
+    return (0..len)
+        .map(|index| guest_read_at(bytes, 0, index))
+        .collect();
+}
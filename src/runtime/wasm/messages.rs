@@ -1,18 +1,32 @@
 use iced::runtime::platform_specific::wayland::layer_surface::SctkLayerSurfaceSettings;
 
-use crate::runtime::wasm::{WasmCallbackData, WasmUiNode};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::builtin::region::Region;
+use crate::runtime::wasm::command::CommandOutput;
+use crate::runtime::wasm::latency::CallbackTiming;
+use crate::runtime::wasm::{ModuleError, ModuleStats, WasmCallbackData, WasmUiNode};
 use crate::services::SubscriptionData;
+use crate::services::audio::{self, AudioDevices, AudioSubscriptionData};
+use crate::services::clock::ClockTime;
+use crate::services::dbus;
+use crate::services::kdeconnect::{self, KdeConnectSubscriptionData};
+use crate::services::{inhibit, session};
 
 /// messages that the wasm thread sends to the iced thread
 #[derive(Debug, Clone)]
 pub enum Event {
     /// representation of what a module returned from its view() function
+    /// for each of its surfaces in one render, so they're swapped in
+    /// together and a frame never mixes trees from different renders
     ///
     /// this gets sent the iced ui thread :3
     ModViewData {
         module_id: u32,
-        surface_id: iced::window::Id,
-        tree: Box<WasmUiNode>,
+        views: Vec<SurfaceView>,
+        /// the callback this render is the result of, if any
+        timing: Option<CallbackTiming>,
     },
     /// allows a wasm module to request for the iced thread to
     /// create a layer surface
@@ -20,12 +34,74 @@ pub enum Event {
     /// allows a wasm module to request for the iced thread to
     /// destroy a layer surface
     DestroyLayerSurface(iced::window::Id),
+    /// a module was loaded by the runtime
+    ModuleLoaded {
+        module_id: u32,
+        module_name: String,
+        file_path: PathBuf,
+    },
+    /// the module was torn down to be reloaded or because its file was
+    /// removed, its surfaces have already been destroyed
+    ModuleUnloaded { module_id: u32 },
+    /// the module went over a limit and won't be rendered anymore
+    ModuleDisabled { module_id: u32, reason: String },
+    /// something went wrong in the module that the runtime carried on
+    /// from, or it couldn't be loaded at all
+    ModuleError { module_id: u32, error: ModuleError },
+    /// the module's counters changed since they were last sent, see `stats`
+    ModuleStats { module_id: u32, stats: ModuleStats },
     /// registers a module to a service, linking the items that the module
     /// wants to be aware of from the service
     RegisterModuleToService {
         module_id: u32,
         register: SubscriptionData,
     },
+    /// a module asked for something to be done on a device paired with kde
+    /// connect, like ringing it or skipping a song playing on it
+    KdeConnectRequest {
+        module_id: u32,
+        request: kdeconnect::Request,
+    },
+    /// a module asked the audio service for something, like a sink playing
+    /// to every output
+    AudioRequest {
+        module_id: u32,
+        request: audio::Request,
+    },
+    /// a module asked for the session to be kept from going idle, or to be
+    /// let go idle again
+    InhibitRequest {
+        module_id: u32,
+        request: inhibit::Request,
+    },
+    /// a module listed in `session` under `[modules]` asked for the system
+    /// to be suspended, rebooted or powered off or the session to be locked
+    SessionRequest {
+        module_id: u32,
+        request: session::Request,
+    },
+    /// a module put text on the clipboard, like an emoji picked from a
+    /// picker
+    SetClipboard { module_id: u32, text: String },
+    /// a module asked for a region of the screen to be picked, on the output
+    /// at that index or the one with focus when it's `None`
+    SelectRegion { module_id: u32, output: Option<u32> },
+    /// a module asked for a screenshot of the output at that index, or of
+    /// every output when it's `None`. `region` is part of the output instead
+    CaptureScreenshot {
+        module_id: u32,
+        output: Option<u32>,
+        region: Option<Region>,
+    },
+}
+
+/// the tree a module returned from its view() function for one surface
+#[derive(Debug, Clone)]
+pub struct SurfaceView {
+    pub surface_id: iced::window::Id,
+    /// which view of the surface this is, sent back with its callbacks
+    pub generation: u32,
+    pub tree: Arc<WasmUiNode>,
 }
 
 /// messages that the wasm thread receives from the iced thread
@@ -37,6 +113,84 @@ pub enum Request {
         module_id: u32,
         surface_id: iced::window::Id,
         callback_id: u32,
+        /// the generation of the tree the callback's widget was built from
+        generation: u32,
         data: Option<WasmCallbackData>,
     },
+    /// the module file at `path` was added, changed or removed, sent by the
+    /// module watcher
+    ReloadModule { path: PathBuf },
+    /// loads the module at `path` again after it was unloaded for trapping
+    /// too many times, sent once the wait before restarting it is over
+    RestartModule { path: PathBuf },
+    /// closes one of the module's surfaces if it's open, or opens it again
+    /// with the settings it was last opened with. `surface` is the id the
+    /// module gave it
+    ToggleSurface { module_id: u32, surface: u32 },
+    /// a service started or stopped, modules are rendered again so they can
+    /// show or hide what depends on it
+    ServicesChanged,
+    /// a monitor was plugged in, unplugged or changed, modules with an
+    /// `Outputs` register are rendered again so they can fit the new layout
+    OutputsChanged,
+    /// the palette and font in the layout of `Base16Color::serialize`,
+    /// given to every module and modules with a `Theme` register are
+    /// rendered again
+    ThemeChanged { theme: Arc<Vec<u8>> },
+    /// the clock service formatted a new time for a module with a `Clock`
+    /// register, the module is rendered again so it can show it
+    ClockChanged { module_id: u32, time: ClockTime },
+    /// one of the module's `Interval` registers came around, the module is
+    /// rendered again
+    IntervalElapsed { module_id: u32 },
+    /// something about audio changed, modules get a summary of it and read
+    /// the devices they need through `read_audio_detail`. the modules with a
+    /// `PulseAudio` register for `subscription` are rendered again, all of
+    /// them are when it's `None`
+    AudioChanged {
+        devices: Arc<AudioDevices>,
+        subscription: Option<AudioSubscriptionData>,
+    },
+    /// the devices paired with kde connect or their notifications changed,
+    /// in the layout of `KdeConnectState::serialize`. the modules with a
+    /// `KdeConnect` register for `subscription` are rendered again, all of
+    /// them are when it's `None`
+    KdeConnectChanged {
+        state: Arc<Vec<u8>>,
+        subscription: Option<KdeConnectSubscriptionData>,
+    },
+    /// the region a module asked for was picked, or cancelled when it's
+    /// `None`. the module is rendered again so it can read it
+    RegionSelected {
+        module_id: u32,
+        region: Option<Region>,
+    },
+    /// the screenshot a module asked for was saved to the path, or couldn't
+    /// be for the reason. the module is rendered again so it can read it
+    ScreenshotTaken {
+        module_id: u32,
+        result: Result<PathBuf, String>,
+    },
+    /// a command a module started finished, its output is run through the
+    /// module's `update` like a callback
+    CommandFinished {
+        module_id: u32,
+        /// see `command::next_ticket`
+        ticket: u64,
+        output: CommandOutput,
+    },
+    /// one of the hotkeys a module registered was pressed, `index` is where
+    /// it is in the register. the message the module's `run_hotkey` returns
+    /// goes through its `update` like a callback
+    HotkeyPressed { module_id: u32, index: u32 },
+    /// a file matching one of the module's `Watch` globs changed, the module
+    /// is given the path through `service_event` and rendered again
+    FileChanged { module_id: u32, path: PathBuf },
+    /// something one of the module's `Dbus` registers watches sent a signal
+    /// or changed its properties, the module is given it through
+    /// `service_event` and rendered again
+    DbusSignal {
+        module_id: u32,
+        signal: dbus::Signal,
+    },
 }
@@ -1,41 +1,91 @@
+mod abi;
 mod api;
+mod command;
 mod de;
+mod error;
 mod fs;
 mod id;
+mod images;
+mod latency;
+mod limits;
+mod memory;
 mod messages;
+mod queue;
 mod state;
+mod stats;
+mod target;
 mod ui;
 
-pub use messages::{Event, Request};
+pub use error::ModuleError;
+pub use fs::{ModuleCheck, check_modules, install_module, validate_module};
+pub use messages::{Event, Request, SurfaceView};
 pub use state::WasmState;
-pub use ui::{SliderNumberType, WasmUiNode};
+pub use stats::ModuleStats;
+pub use ui::{
+    ContainerStyle, Layout, ModuleAlignment, ModuleColor, ModuleImage, ModuleLength,
+    MouseAreaCallbacks, SliderNumberType, TextFont, TextFontFamily, TooltipPosition, WasmUiNode,
+};
 
+use abi::AbiVersion;
 use api::get_api_functions;
-use fs::load_modules;
+use command::CommandRequest;
+use fs::{load_module, load_modules, watch_modules};
 use id::WasmId;
+use images::ImageCache;
+use latency::CallbackTiming;
+use limits::Usage;
+use queue::{EventQueue, ModuleEvent, RenderQueue};
 use ui::get_element_tree;
 
-use super::{RuntimeEvent, RuntimeRequest, RuntimeService};
+use super::{
+    RequestError, RequestId, RuntimeEvent, RuntimeModuleId, RuntimeRequest, RuntimeService,
+};
 
+use crate::builtin::region::{self, Region};
+use crate::instrumented::{self, InstrumentedSender};
 use crate::services::SubscriptionData;
+use crate::services::audio::{self, AudioDevices};
+use crate::services::dbus::{self, DbusService};
+use crate::services::tray::TrayClick;
+use crate::services::watch::{self, WatchService};
+use crate::services::{Service, inhibit, kdeconnect, screenshot, session};
+use crate::watchdog::Heartbeat;
+use crate::{config, crash, metrics, notify, startup};
 
 use std::any::TypeId;
 use std::cell::RefCell;
-use std::collections::{HashMap, VecDeque};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use derivative::Derivative;
 use iced::Subscription;
-use iced::futures::SinkExt;
-use iced::futures::channel::mpsc::Sender as IcedSender;
+use iced::futures::channel::mpsc;
+use iced::runtime::platform_specific::wayland::layer_surface::SctkLayerSurfaceSettings;
 use iced::stream::channel;
-use wasmtime::{Config, Engine, Instance, Linker, Memory, Store};
+use wasmtime::{Engine, Instance, Linker, Memory, Store};
 use wasmtime_wasi::preview1::WasiP1Ctx;
 
+/// the first wait before loading a module that crashed again, doubles with
+/// each crash
+const CRASH_BACKOFF_MIN: Duration = Duration::from_secs(2);
+/// the longest wait before loading a crashed module again
+const CRASH_BACKOFF_MAX: Duration = Duration::from_secs(2 * 60);
+
+/// a service's event, given to the modules registered for it through their
+/// `service_event` export
 pub trait WasmSerializable: std::fmt::Debug + Send + Sync {
-    fn serialise(self) -> &'static [u8];
+    /// the id of the service, see `Service::ID`
+    fn service_id(&self) -> u16;
+
+    /// the event in the layout modules read it in, `None` for the services
+    /// whose events get to modules through a `Request` of their own
+    fn serialize(&self) -> Option<Vec<u8>>;
+
+    /// whether a module registered with `register` gets the event
+    fn is_for(&self, register: &SubscriptionData) -> bool;
 }
 
 #[derive(Debug, Clone)]
@@ -53,14 +103,22 @@ impl RuntimeService for WasmRuntime {
 
         Subscription::run_with_id(
             id,
-            channel(100, async move |mut chan| {
+            channel(100, async move |chan| {
+                let mut chan = InstrumentedSender::new("runtime.wasm.events", chan);
+                let heartbeat = Heartbeat::spawn("wasm");
+
                 loop {
-                    match WasmRuntime::_run(&mut chan).await {
-                        Ok(_) => {
-                            log::warn!("[wasm] thread exited. restarting...");
-                        }
-                        Err(err) => {
-                            log::error!("[wasm] crash! error: {}", err);
+                    tokio::select! {
+                        res = WasmRuntime::_run(&mut chan, &heartbeat) => match res {
+                            Ok(_) => {
+                                log::warn!("[wasm] thread exited. restarting...");
+                            }
+                            Err(err) => {
+                                log::error!("[wasm] crash! error: {}", err);
+                            }
+                        },
+                        _ = heartbeat.tripped() => {
+                            log::error!("[wasm] watchdog tripped. restarting...");
                         }
                     };
                 }
@@ -68,82 +126,83 @@ impl RuntimeService for WasmRuntime {
         )
     }
 
-    fn request(state: &mut Self::State, request: RuntimeRequest<Self>) -> anyhow::Result<()> {
-        state.channel.send(request)?;
+    fn request(state: &mut Self::State, request: RuntimeRequest<Self>) -> Result<(), RequestError> {
+        state
+            .channel
+            .send(request)
+            .map_err(|_| RequestError::Closed)?;
         return Ok(());
     }
 }
 
 impl WasmRuntime {
-    async fn _run(chan: &mut IcedSender<RuntimeEvent<Self>>) -> anyhow::Result<()> {
-        let (request_tx, request_rx) = flume::bounded::<RuntimeRequest<Self>>(100);
+    async fn _run(
+        chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<Self>>>,
+        heartbeat: &Heartbeat,
+    ) -> anyhow::Result<()> {
+        let (request_tx, request_rx) =
+            instrumented::bounded::<RuntimeRequest<Self>>("runtime.wasm.requests", 100);
 
-        let mut config = Config::new();
-        config.async_support(true);
-        let engine = Engine::new(&config)?;
-
-        let mut linker: Linker<WasiContext> = Linker::new(&engine);
-        wasmtime_wasi::preview1::add_to_linker_async(&mut linker, |context| &mut context.wasip1)?;
-
-        get_api_functions(&mut linker)?;
-
-        let mut host = WasmHost {
-            engine,
-            linker,
-            modules: vec![],
-        };
+        let mut host = WasmHost::new()?;
 
         chan.send(RuntimeEvent::Init(WasmState {
-            channel: request_tx,
+            channel: request_tx.clone(),
             surface_module_ids: HashMap::new(),
             module_ui_trees: HashMap::new(),
+            modules: HashMap::new(),
+            module_errors: HashMap::new(),
+            module_stats: HashMap::new(),
         }))
         .await?;
 
+        startup::wait_for_dependencies("wasm").await;
         host.modules = load_modules(&mut host, chan).await?;
+        record_crash_modules(&host);
+        startup::ready("wasm");
 
-        let mut modules_registers_map: Vec<(u32, SubscriptionData)> = vec![];
-        // assign registers from each module to a service
-        for module in &host.modules {
-            for register in &module.registers {
-                let id = module.id;
-                let register = register.clone();
-
-                modules_registers_map.push((id, register));
-            }
+        // need to be collected before sending as `host.modules` is not `Send`
+        let events = host
+            .modules
+            .iter()
+            .flat_map(module_events)
+            .collect::<Vec<Event>>();
+        for event in events {
+            chan.send(RuntimeEvent::Update(event)).await?;
         }
 
-        // need to be separated from the loop above as it says `host.modules`
-        // is not `Send`
-        for register in modules_registers_map {
-            chan.send(RuntimeEvent::Update(Event::RegisterModuleToService {
-                module_id: register.0,
-                register: register.1,
-            }))
-            .await?;
-        }
+        // modules are reloaded when their file changes
+        watch_modules(request_tx.clone());
 
-        let mut render_queue: VecDeque<u32> = VecDeque::from(
-            host.modules
-                .iter()
-                .map(|module| module.id)
-                .collect::<Vec<u32>>(),
-        );
+        let mut render_queue = host
+            .modules
+            .iter()
+            .map(|module| module.id)
+            .collect::<RenderQueue>();
 
         log::debug!("[wasm] setup finished, starting loop");
 
         'main: loop {
             // re-render all queued modules
             'render: loop {
-                let module_id = match render_queue.pop_front() {
-                    Some(id) => id,
+                let (module_id, dirty) = match render_queue.pop_front() {
+                    Some(next) => next,
                     None => {
                         // break loop if there is no more to render
                         break 'render;
                     }
                 };
 
-                let module = &mut host.modules[module_id as usize];
+                // the module could have been removed since it was queued
+                let module = match host.module_mut(module_id) {
+                    Some(module) => module,
+                    None => continue 'render,
+                };
+
+                if module.disabled || module.has_crashed() {
+                    continue 'render;
+                }
+
+                let _busy = heartbeat.busy(|| format!("render of module {}", module.module_name));
 
                 let view_func = match module
                     .instance
@@ -157,25 +216,54 @@ impl WasmRuntime {
                             module.module_name,
                             err
                         );
+                        module.fault(ModuleError::MissingExport {
+                            name: "view",
+                            message: err.to_string(),
+                        });
                         continue 'render;
                     }
                 };
 
-                let surface_ids = module.store.data().used_surface_ids.borrow().clone();
+                // the trees of every surface are sent together once they're
+                // all rendered
+                let mut views = vec![];
+                let started = Instant::now();
+
+                let mut surface_ids = module.store.data().used_surface_ids.borrow().clone();
+                // a module that marked surfaces dirty only has those rendered,
+                // along with surfaces the app has no view of yet
+                if let Some(dirty) = &dirty {
+                    surface_ids.retain(|surface_id| {
+                        dirty.contains(surface_id) || !module.sent_views.contains_key(surface_id)
+                    });
+                }
+
                 for surface_id in surface_ids.iter() {
                     let offset = match view_func.call_async(&mut module.store, *surface_id).await {
-                        Ok(res) => res,
+                        Ok(res) => {
+                            module.traps = 0;
+                            res
+                        }
                         Err(err) => {
-                            log::warn!("[wasm] view function call failed: {err}");
+                            let message = module.trapped("view", &err);
+                            module.fault(ModuleError::ViewTrap {
+                                surface: *surface_id,
+                                message,
+                            });
+                            if module.has_crashed() {
+                                continue 'render;
+                            }
                             continue;
                         }
                     };
 
-                    let ui_tree = match get_element_tree(
+                    let element_tree = match get_element_tree(
                         &module.module_name,
                         &module.store,
                         module.memory,
                         offset,
+                        module.abi_version,
+                        &mut module.images,
                     ) {
                         Ok(tree) => tree,
                         Err(err) => {
@@ -184,6 +272,10 @@ impl WasmRuntime {
                                 module.module_name,
                                 err
                             );
+                            module.fault(ModuleError::read(
+                                format!("the ui tree of surface {surface_id}"),
+                                &err,
+                            ));
                             continue;
                         }
                     };
@@ -206,13 +298,88 @@ impl WasmRuntime {
                             }
                         };
 
-                    chan.send(RuntimeEvent::Update(Event::ModViewData {
-                        module_id: module.id,
+                    let ui_tree = element_tree.root;
+                    // modules before abi version 2 don't count their views,
+                    // so the host counts for them
+                    let generation = element_tree.generation.unwrap_or_else(|| {
+                        module
+                            .generations
+                            .get(surface_id)
+                            .map_or(0, |generation| generation.wrapping_add(1))
+                    });
+                    module.generations.insert(*surface_id, generation);
+
+                    module.tree_bytes.insert(*surface_id, ui_tree.size_bytes());
+
+                    let usage = Usage::of(module);
+                    usage.record(&module.module_name);
+
+                    if let Some(reason) = usage.breach() {
+                        disable_module(chan, module, reason).await?;
+                        continue 'render;
+                    }
+
+                    // the app keeps drawing its tree when the new one came
+                    // out the same, which saves it laying the surface out
+                    // again
+                    let fingerprint = ui_tree.fingerprint();
+                    if module
+                        .sent_views
+                        .get(surface_id)
+                        .is_some_and(|sent| sent.fingerprint == fingerprint)
+                    {
+                        metrics::increment("runtime.wasm.views_unchanged");
+                        continue;
+                    }
+                    module.sent_views.insert(
+                        *surface_id,
+                        SentView {
+                            fingerprint,
+                            generation,
+                        },
+                    );
+
+                    views.push(SurfaceView {
                         surface_id: *iced_surface_id,
-                        tree: Box::new(ui_tree),
-                    }))
-                    .await?;
+                        generation,
+                        tree: Arc::new(ui_tree),
+                    });
+                }
+
+                module.stats.rendered(started);
+                if views.is_empty() {
+                    // nothing reaches the app, so there's no delivery to time
+                    module.callback_timing = None;
+                    continue 'render;
                 }
+
+                let timing = module.callback_timing.take().map(|mut timing| {
+                    timing.stage("view");
+                    timing
+                });
+
+                chan.send(RuntimeEvent::Update(Event::ModViewData {
+                    module_id: module.id,
+                    views,
+                    timing,
+                }))
+                .await?;
+            }
+
+            // before the crashed modules are unloaded, so the app knows why
+            send_module_errors(&mut host, chan).await?;
+            send_module_stats(&mut host, chan).await?;
+
+            // modules that trapped too many times while handling the last
+            // messages or rendering
+            let crashed = host
+                .modules
+                .iter()
+                .filter(|module| module.has_crashed())
+                .map(|module| module.id)
+                .collect::<Vec<u32>>();
+            for module_id in crashed {
+                crash_module(&mut host, chan, &request_tx, module_id).await?;
             }
 
             let msg = match request_rx.recv_async().await {
@@ -228,96 +395,1385 @@ impl WasmRuntime {
                 }
             };
 
-            match msg {
-                RuntimeRequest::Request {
-                    request:
-                        Request::CallbackEvent {
-                            module_id,
-                            surface_id,
-                            callback_id,
-                            data,
-                        },
-                } => {
-                    if let Some(module) = host.modules.get_mut(module_id as usize) {
-                        // we turn the iced id to a u32 that the module knows about
-                        let surface_id =
-                            match module.store.data().surface_wasm_id.get_id(&surface_id) {
-                                Some(id) => *id,
-                                None => {
-                                    log::warn!(
-                                        "[wasm] [module:{}] iced surface id {} does not map to a \
-                                         u32",
-                                        module.module_name,
-                                        surface_id
-                                    );
-                                    continue 'main;
-                                }
-                            };
-
-                        let callback_func =
-                            match module.instance.get_typed_func::<(u32, u32, u64), u64>(
-                                &mut module.store,
-                                "run_callback",
-                            ) {
-                                Ok(func) => func,
-                                Err(err) => {
-                                    log::warn!(
-                                        "[wasm] [module:{}] run_callback function does not exist \
-                                         or is incorrect type: {}",
-                                        module.module_name,
-                                        err
-                                    );
-                                    continue 'main;
-                                }
-                            };
-
-                        let data_value = match data {
-                            Some(data) => match data {
-                                WasmCallbackData::Slider(value) => value,
+            // everything already waiting is taken too, so a burst of events is
+            // handled before the modules are rendered again
+            let mut messages = vec![msg];
+            messages.extend(request_rx.try_iter());
+
+            for msg in messages {
+                let _busy = heartbeat.busy(|| format!("{msg:?}"));
+
+                match msg {
+                    RuntimeRequest::Request {
+                        id,
+                        sent,
+                        request:
+                            Request::CallbackEvent {
+                                module_id,
+                                surface_id,
+                                callback_id,
+                                generation,
+                                data,
                             },
-                            None => 0, // no data for the associated widget
+                    } => {
+                        let queued = match host.module_mut(module_id) {
+                            Some(module) => queue_event(
+                                module,
+                                ModuleEvent::Callback {
+                                    request: id,
+                                    timing: CallbackTiming::since(sent),
+                                    surface_id,
+                                    callback_id,
+                                    generation,
+                                    data,
+                                },
+                            ),
+                            None => Err(RequestError::UnknownModule(RuntimeModuleId::Wasm(
+                                module_id,
+                            ))),
                         };
 
-                        let callback_data = callback_func
-                            .call_async(&mut module.store, (surface_id, callback_id, data_value))
-                            .await?;
-
-                        let message_id = (callback_data >> 32) as u32;
-                        let data_ptr = (callback_data & u32::MAX as u64) as u32;
+                        if let Err(error) = queued {
+                            request_failed(chan, id, error).await?;
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::ReloadModule { path },
+                        ..
+                    } => {
+                        // events queued for the old module go with it
+                        if let Some(module_id) = reload_module(&mut host, chan, &path).await? {
+                            render_queue.push_back(module_id);
+                        }
 
-                        let update_func = match module
-                            .instance
-                            .get_typed_func::<(u32, u32), u32>(&mut module.store, "update")
+                        // the module was changed, so it may not crash anymore
+                        host.crashed.remove(&path);
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::RestartModule { path },
+                        ..
+                    } => {
+                        // it was loaded again some other way while waiting
+                        if !host.crashed.contains_key(&path)
+                            || host.modules.iter().any(|module| module.file_path == path)
                         {
-                            Ok(func) => func,
-                            Err(err) => {
-                                eprintln!(
-                                    "[wasm] [module:{}] update function does not exist or is \
-                                     incorrect type: {}",
-                                    module.module_name, err
+                            continue;
+                        }
+
+                        log::info!("[wasm] loading {} again after it crashed", path.display());
+                        if let Some(module_id) = reload_module(&mut host, chan, &path).await? {
+                            render_queue.push_back(module_id);
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        id,
+                        request: Request::ToggleSurface { module_id, surface },
+                        ..
+                    } => {
+                        let Some(module) = host.module_mut(module_id) else {
+                            let error =
+                                RequestError::UnknownModule(RuntimeModuleId::Wasm(module_id));
+                            request_failed(chan, id, error).await?;
+                            continue;
+                        };
+
+                        // the module draws to the surface once it's rendered
+                        if toggle_surface(chan, module, surface).await? {
+                            render_queue.push_back(module_id);
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::ServicesChanged,
+                        ..
+                    } => {
+                        for module in &host.modules {
+                            render_queue.push_back(module.id);
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::OutputsChanged,
+                        ..
+                    } => {
+                        for module in &host.modules {
+                            let registered = module
+                                .registers
+                                .iter()
+                                .any(|register| matches!(register, SubscriptionData::Outputs));
+
+                            if registered {
+                                render_queue.push_back(module.id);
+                            }
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::ThemeChanged { theme },
+                        ..
+                    } => {
+                        for module in host.modules.iter_mut() {
+                            module.store.data_mut().theme = Arc::clone(&theme);
+
+                            let registered = module
+                                .registers
+                                .iter()
+                                .any(|register| matches!(register, SubscriptionData::Theme));
+
+                            if registered {
+                                render_queue.push_back(module.id);
+                            }
+                        }
+
+                        host.theme = theme;
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::ClockChanged { module_id, time },
+                        ..
+                    } => {
+                        if let Some(module) = host.module_mut(module_id) {
+                            module.store.data_mut().clock = time.serialize();
+
+                            render_queue.push_back(module_id);
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::IntervalElapsed { module_id },
+                        ..
+                    } => {
+                        if host.module_mut(module_id).is_some() {
+                            render_queue.push_back(module_id);
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        request:
+                            Request::AudioChanged {
+                                devices,
+                                subscription,
+                            },
+                        ..
+                    } => {
+                        host.audio_revision = host.audio_revision.wrapping_add(1);
+                        let summary = devices.summary(host.audio_revision);
+
+                        for module in host.modules.iter_mut() {
+                            let context = module.store.data_mut();
+                            context.audio = Some(Arc::clone(&devices));
+                            context.audio_summary = summary.clone();
+
+                            // only the modules registered for this kind of
+                            // event are rendered, the rest read it next time
+                            let registered = module.registers.iter().any(|register| {
+                                matches!(
+                                    register,
+                                    SubscriptionData::PulseAudio { data }
+                                        if subscription.is_none_or(|bit| data.is_set(bit))
+                                )
+                            });
+
+                            if registered {
+                                render_queue.push_back(module.id);
+                            }
+                        }
+
+                        host.audio = Some(devices);
+                    }
+                    RuntimeRequest::Request {
+                        request:
+                            Request::KdeConnectChanged {
+                                state,
+                                subscription,
+                            },
+                        ..
+                    } => {
+                        for module in host.modules.iter_mut() {
+                            module.store.data_mut().kdeconnect = Arc::clone(&state);
+
+                            let registered = module.registers.iter().any(|register| {
+                                matches!(
+                                    register,
+                                    SubscriptionData::KdeConnect { data }
+                                        if subscription.is_none_or(|bit| data.is_set(bit))
+                                )
+                            });
+
+                            if registered {
+                                render_queue.push_back(module.id);
+                            }
+                        }
+
+                        host.kdeconnect = state;
+                    }
+                    RuntimeRequest::Request {
+                        id,
+                        sent,
+                        request:
+                            Request::CommandFinished {
+                                module_id,
+                                ticket,
+                                output,
+                            },
+                    } => {
+                        let Some(module) = host.module_mut(module_id) else {
+                            continue;
+                        };
+
+                        // the module was reloaded since it started the command
+                        let Some(handle) = module.store.data_mut().pending_commands.remove(&ticket)
+                        else {
+                            log::debug!(
+                                "[wasm] [module:{}] dropping the output of command {} from before \
+                                 it was reloaded",
+                                module.module_name,
+                                ticket
+                            );
+                            continue;
+                        };
+
+                        let queued = queue_event(
+                            module,
+                            ModuleEvent::Command {
+                                request: id,
+                                timing: CallbackTiming::since(sent),
+                                handle,
+                                output: command::serialize(&output),
+                            },
+                        );
+
+                        if let Err(error) = queued {
+                            request_failed(chan, id, error).await?;
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        id,
+                        sent,
+                        request: Request::HotkeyPressed { module_id, index },
+                    } => {
+                        let queued = match host.module_mut(module_id) {
+                            Some(module) => queue_event(
+                                module,
+                                ModuleEvent::Hotkey {
+                                    request: id,
+                                    timing: CallbackTiming::since(sent),
+                                    index,
+                                },
+                            ),
+                            None => Err(RequestError::UnknownModule(RuntimeModuleId::Wasm(
+                                module_id,
+                            ))),
+                        };
+
+                        if let Err(error) = queued {
+                            request_failed(chan, id, error).await?;
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::RegionSelected { module_id, region },
+                        ..
+                    } => {
+                        if let Some(module) = host.module_mut(module_id) {
+                            log::debug!(
+                                "[wasm] [module:{}] was given the region {:?}",
+                                module.module_name,
+                                region
+                            );
+                            module.store.data_mut().region = region::serialize(region);
+
+                            render_queue.push_back(module_id);
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::ScreenshotTaken { module_id, result },
+                        ..
+                    } => {
+                        if let Some(module) = host.module_mut(module_id) {
+                            log::debug!(
+                                "[wasm] [module:{}] was given the screenshot {:?}",
+                                module.module_name,
+                                result
+                            );
+                            let context = module.store.data_mut();
+                            context.screenshot = screenshot::serialize(&result);
+                            context.screenshot_pending = false;
+
+                            render_queue.push_back(module_id);
+                        }
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::FileChanged { module_id, path },
+                        ..
+                    } => {
+                        let Some(module) = host.module_mut(module_id) else {
+                            continue;
+                        };
+
+                        if module.has_service_event {
+                            let queued = queue_event(
+                                module,
+                                ModuleEvent::Service {
+                                    request: RequestId::next(),
+                                    timing: CallbackTiming::since(Instant::now()),
+                                    service: WatchService::ID,
+                                    data: watch::serialize_changed(&path),
+                                },
+                            );
+                            if let Err(err) = queued {
+                                log::debug!(
+                                    "[wasm] [module:{}] dropped the change to {}: {err}",
+                                    module.module_name,
+                                    path.display()
                                 );
-                                continue 'main;
                             }
+                        }
+
+                        render_queue.push_back(module_id);
+                    }
+                    RuntimeRequest::Request {
+                        request: Request::DbusSignal { module_id, signal },
+                        ..
+                    } => {
+                        let Some(module) = host.module_mut(module_id) else {
+                            continue;
                         };
-                        // note: needs to be put back into the module if its not
-                        // 0 as the module might be trying to trigger side effects
-                        let message_id = update_func
-                            .call_async(&mut module.store, (message_id, data_ptr))
-                            .await?;
+
+                        if module.has_service_event {
+                            let queued = queue_event(
+                                module,
+                                ModuleEvent::Service {
+                                    request: RequestId::next(),
+                                    timing: CallbackTiming::since(Instant::now()),
+                                    service: DbusService::ID,
+                                    data: dbus::serialize_signal(&signal),
+                                },
+                            );
+                            if let Err(err) = queued {
+                                log::debug!(
+                                    "[wasm] [module:{}] dropped a signal from {}: {err}",
+                                    module.module_name,
+                                    signal.name
+                                );
+                            }
+                        }
 
                         render_queue.push_back(module_id);
                     }
+                    RuntimeRequest::ServiceData { data } => {
+                        let Some(bytes) = data.serialize() else {
+                            log::debug!(
+                                "[wasm] service {} has no layout for {data:?}, it isn't given \
+                                 to modules",
+                                data.service_id()
+                            );
+                            continue;
+                        };
+
+                        for module in host.modules.iter_mut() {
+                            let registered = module
+                                .registers
+                                .iter()
+                                .any(|register| data.is_for(register));
+                            if !registered {
+                                continue;
+                            }
+
+                            // modules that can't be given the event read what
+                            // changed when they're rendered again, the others
+                            // are rendered after handling it
+                            if !module.has_service_event {
+                                render_queue.push_back(module.id);
+                                continue;
+                            }
+
+                            // nothing is waiting on the event, so there's no
+                            // one to tell when it's dropped
+                            let queued = queue_event(
+                                module,
+                                ModuleEvent::Service {
+                                    request: RequestId::next(),
+                                    timing: CallbackTiming::since(Instant::now()),
+                                    service: data.service_id(),
+                                    data: bytes.clone(),
+                                },
+                            );
+                            if let Err(err) = queued {
+                                log::debug!(
+                                    "[wasm] [module:{}] dropped an event from service {}: {err}",
+                                    module.module_name,
+                                    data.service_id()
+                                );
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            // each module's events are handled in the order they arrived, with
+            // the modules taken in load order
+            for module in host.modules.iter_mut() {
+                let mut handled = false;
+                // the surfaces the module marked dirty while handling the
+                // events, `None` once one was handled without marking any
+                let mut dirty = Some(HashSet::new());
+
+                while let Some((seq, event)) = module.events.pop() {
+                    let _busy = heartbeat.busy(|| {
+                        format!("event #{seq} of module {}: {event:?}", module.module_name)
+                    });
+                    log::trace!(
+                        "[wasm] [module:{}] handling event #{}: {:?}",
+                        module.module_name,
+                        seq,
+                        event
+                    );
+
+                    let handled_event = handle_event(chan, module, event).await?;
+                    let marked = std::mem::take(&mut module.store.data_mut().dirty_surfaces);
+                    if handled_event {
+                        handled = true;
+                        dirty = match dirty {
+                            Some(mut dirty) if !marked.is_empty() => {
+                                dirty.extend(marked);
+                                Some(dirty)
+                            }
+                            _ => None,
+                        };
+                    }
+
+                    send_module_requests(chan, &request_tx, module).await?;
+                }
+
+                if handled {
+                    match dirty {
+                        Some(dirty) => render_queue.push_surfaces(module.id, dirty),
+                        None => render_queue.push_back(module.id),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// adds `event` to the end of the module's queue
+fn queue_event(module: &mut WasmModule, event: ModuleEvent) -> Result<(), RequestError> {
+    if module.disabled {
+        return Err(RequestError::Dropped {
+            module: RuntimeModuleId::Wasm(module.id),
+            reason: "the module is disabled".to_string(),
+        });
+    }
+
+    match module.events.push(event) {
+        Some(seq) => {
+            metrics::increment("runtime.wasm.events_queued");
+            module.stats.events += 1;
+            log::trace!(
+                "[wasm] [module:{}] queued event #{}",
+                module.module_name,
+                seq
+            );
+            return Ok(());
+        }
+        None => {
+            metrics::increment("runtime.wasm.events_dropped");
+            module.stats.dropped_events += 1;
+            log::warn!(
+                "[wasm] [module:{}] has too many events waiting, dropping one",
+                module.module_name
+            );
+            return Err(RequestError::Dropped {
+                module: RuntimeModuleId::Wasm(module.id),
+                reason: "the module has too many events waiting".to_string(),
+            });
+        }
+    }
+}
+
+/// closes one of the module's surfaces if it's open, or opens it again with
+/// the settings it was last opened with, like the module calling
+/// `close_surface` or `open_surface` itself
+///
+/// returns whether the surface was opened
+async fn toggle_surface(
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    module: &mut WasmModule,
+    surface: u32,
+) -> anyhow::Result<bool> {
+    let context = module.store.data_mut();
+    let open = context.used_surface_ids.borrow().contains(&surface);
+
+    if open {
+        context
+            .used_surface_ids
+            .borrow_mut()
+            .retain(|used| *used != surface);
+        module.generations.remove(&surface);
+        module.tree_bytes.remove(&surface);
+        module.sent_views.remove(&surface);
+
+        if let Some(iced_id) = context.surface_wasm_id.get_iced_id(&surface) {
+            chan.send(RuntimeEvent::Update(Event::DestroyLayerSurface(*iced_id)))
+                .await?;
+        }
+        log::info!(
+            "[wasm] [module:{}] closed surface {surface}",
+            module.module_name
+        );
+
+        return Ok(false);
+    }
+
+    let Some(layer) = context.surfaces.get(&surface).cloned() else {
+        log::warn!(
+            "[wasm] [module:{}] has never opened a surface {surface}",
+            module.module_name
+        );
+        return Ok(false);
+    };
+
+    context.used_surface_ids.borrow_mut().push(surface);
+    chan.send(RuntimeEvent::Update(Event::CreateLayerSurface(layer)))
+        .await?;
+    log::info!(
+        "[wasm] [module:{}] opened surface {surface}",
+        module.module_name
+    );
+
+    return Ok(true);
+}
+
+/// passes on what the module asked of kde connect, audio, the inhibit and
+/// session services, the clipboard, the region overlay and the screenshot
+/// service while handling an event, and starts the commands it asked for
+async fn send_module_requests(
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    request_tx: &InstrumentedSender<flume::Sender<RuntimeRequest<WasmRuntime>>>,
+    module: &mut WasmModule,
+) -> anyhow::Result<()> {
+    let requests = std::mem::take(&mut module.store.data_mut().kdeconnect_requests);
+
+    for request in requests {
+        chan.send(RuntimeEvent::Update(Event::KdeConnectRequest {
+            module_id: module.id,
+            request,
+        }))
+        .await?;
+    }
+
+    let requests = std::mem::take(&mut module.store.data_mut().audio_requests);
+
+    for request in requests {
+        chan.send(RuntimeEvent::Update(Event::AudioRequest {
+            module_id: module.id,
+            request,
+        }))
+        .await?;
+    }
+
+    let requests = std::mem::take(&mut module.store.data_mut().inhibit_requests);
+
+    for request in requests {
+        chan.send(RuntimeEvent::Update(Event::InhibitRequest {
+            module_id: module.id,
+            request,
+        }))
+        .await?;
+    }
+
+    let requests = std::mem::take(&mut module.store.data_mut().session_requests);
+
+    for request in requests {
+        chan.send(RuntimeEvent::Update(Event::SessionRequest {
+            module_id: module.id,
+            request,
+        }))
+        .await?;
+    }
+
+    // only the last text set while handling the event would be left on the
+    // clipboard anyway
+    if let Some(text) = module.store.data_mut().clipboard.take() {
+        chan.send(RuntimeEvent::Update(Event::SetClipboard {
+            module_id: module.id,
+            text,
+        }))
+        .await?;
+    }
+
+    let requests = std::mem::take(&mut module.store.data_mut().surface_requests);
+
+    for request in requests {
+        let event = match request {
+            SurfaceRequest::Open(id, layer) => {
+                module.store.data_mut().surfaces.insert(id, layer.clone());
+                Event::CreateLayerSurface(layer)
+            }
+            SurfaceRequest::Close(id) => {
+                // callbacks from the closed surface are dropped like ones
+                // from an old view
+                module.generations.remove(&id);
+                module.tree_bytes.remove(&id);
+                module.sent_views.remove(&id);
+
+                match module.store.data().surface_wasm_id.get_iced_id(&id) {
+                    Some(surface) => Event::DestroyLayerSurface(*surface),
+                    None => continue,
+                }
+            }
+        };
+
+        chan.send(RuntimeEvent::Update(event)).await?;
+    }
+
+    if let Some(output) = module.store.data_mut().region_request.take() {
+        chan.send(RuntimeEvent::Update(Event::SelectRegion {
+            module_id: module.id,
+            output: (output != u32::MAX).then_some(output),
+        }))
+        .await?;
+    }
+
+    if let Some((output, region)) = module.store.data_mut().screenshot_request.take() {
+        module.store.data_mut().screenshot_pending = true;
+        chan.send(RuntimeEvent::Update(Event::CaptureScreenshot {
+            module_id: module.id,
+            output: (output != u32::MAX).then_some(output),
+            region,
+        }))
+        .await?;
+    }
+
+    let requests = std::mem::take(&mut module.store.data_mut().command_requests);
+
+    for (ticket, request) in requests {
+        log::debug!(
+            "[wasm] [module:{}] running command {}: {:?}",
+            module.module_name,
+            ticket,
+            request
+        );
+        metrics::increment("runtime.wasm.commands");
+
+        let module_id = module.id;
+        let request_tx = request_tx.clone();
+        tokio::spawn(async move {
+            let output = command::run(request).await;
+            let request = RuntimeRequest::new(Request::CommandFinished {
+                module_id,
+                ticket,
+                output,
+            });
+
+            if request_tx.send_async(request).await.is_err() {
+                log::debug!("[wasm] runtime stopped before command {ticket} finished");
+            }
+        });
+    }
+
+    return Ok(());
+}
+
+/// tells the app the request with `id` failed
+async fn request_failed(
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    id: RequestId,
+    error: RequestError,
+) -> anyhow::Result<()> {
+    log::debug!("[wasm] request {id} failed: {error}");
+    metrics::increment("runtime.wasm.requests_failed");
+    chan.send(RuntimeEvent::RequestFailed { id, error }).await?;
+    return Ok(());
+}
+
+/// runs a queued event through the module, returns whether the module needs
+/// to be rendered again
+///
+/// an event the module couldn't handle is sent back to the app as a failed
+/// request, errors are only returned when the runtime can't continue
+async fn handle_event(
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    module: &mut WasmModule,
+    event: ModuleEvent,
+) -> anyhow::Result<bool> {
+    // the rest of its events go with it when it's unloaded
+    if module.disabled || module.has_crashed() {
+        return Ok(false);
+    }
+
+    let module_id = RuntimeModuleId::Wasm(module.id);
+
+    match event {
+        ModuleEvent::Callback {
+            request,
+            timing,
+            surface_id,
+            callback_id,
+            generation,
+            data,
+        } => {
+            // we turn the iced id to a u32 that the module knows about
+            let surface_id = match module.store.data().surface_wasm_id.get_id(&surface_id) {
+                Some(id) => *id,
+                None => {
+                    log::warn!(
+                        "[wasm] [module:{}] iced surface id {} does not map to a u32",
+                        module.module_name,
+                        surface_id
+                    );
+                    let error = RequestError::Dropped {
+                        module: module_id,
+                        reason: "the surface is no longer open".to_string(),
+                    };
+                    request_failed(chan, request, error).await?;
+                    return Ok(false);
+                }
+            };
+
+            // the click was on a view that has since been replaced, so the
+            // callback id could belong to a different widget now
+            //
+            // views that came out the same as the one the app has weren't
+            // sent, so the callback goes to the module as from the latest
+            if module
+                .sent_views
+                .get(&surface_id)
+                .map(|sent| sent.generation)
+                != Some(generation)
+            {
+                log::debug!(
+                    "[wasm] [module:{}] dropping callback {} from an old view of surface {}",
+                    module.module_name,
+                    callback_id,
+                    surface_id
+                );
+                metrics::increment("runtime.wasm.stale_callbacks");
+                let error = RequestError::Dropped {
+                    module: module_id,
+                    reason: "the view changed before the callback ran".to_string(),
+                };
+                request_failed(chan, request, error).await?;
+                return Ok(false);
+            }
+            let generation = module
+                .generations
+                .get(&surface_id)
+                .copied()
+                .unwrap_or(generation);
+
+            let (data_value, kind) = match data {
+                Some(data) => match data {
+                    WasmCallbackData::Slider(value) => (value, CallbackDataKind::Bits),
+                    WasmCallbackData::Tray { click, .. } => (click as u64, CallbackDataKind::Bits),
+                    WasmCallbackData::Grid(first) => (first as u64, CallbackDataKind::Bits),
+                    WasmCallbackData::Scroll { x, y } => (
+                        (x.to_bits() as u64) << 32 | y.to_bits() as u64,
+                        CallbackDataKind::Bits,
+                    ),
+                    // the module reads the text through `read_callback_text`
+                    WasmCallbackData::Text(text) => {
+                        let len = text.len() as u64;
+                        module.store.data_mut().callback_text = text;
+                        (len, CallbackDataKind::Text)
+                    }
+                    WasmCallbackData::Bool(value) => (value as u64, CallbackDataKind::Bool),
+                },
+                // no data for the associated widget
+                None => (0, CallbackDataKind::None),
+            };
+
+            let result = run_callback(
+                module,
+                surface_id,
+                callback_id,
+                data_value,
+                kind,
+                generation,
+            )
+            .await;
+            let callback_data = match result {
+                Ok(Some(callback_data)) => callback_data,
+                Err(err) => {
+                    return module_trapped(chan, module, request, "run_callback", err).await;
+                }
+                Ok(None) => {
+                    let error = RequestError::Module {
+                        module: module_id,
+                        message: "it has no run_callback function".to_string(),
+                    };
+                    request_failed(chan, request, error).await?;
+                    return Ok(false);
+                }
+            };
+
+            return update_module(chan, module, request, timing, callback_data).await;
+        }
+        ModuleEvent::Command {
+            request,
+            timing,
+            handle,
+            output,
+        } => {
+            // the module reads the output through `read_command_output`
+            let len = output.len() as u32;
+            module.store.data_mut().command_output = output;
+
+            let run_command = match module
+                .instance
+                .get_typed_func::<(u32, u32), u64>(&mut module.store, "run_command")
+            {
+                Ok(func) => func,
+                Err(err) => {
+                    log::warn!(
+                        "[wasm] [module:{}] run_command function does not exist or is incorrect \
+                         type: {}",
+                        module.module_name,
+                        err
+                    );
+                    module.fault(ModuleError::MissingExport {
+                        name: "run_command",
+                        message: err.to_string(),
+                    });
+                    let error = RequestError::Module {
+                        module: module_id,
+                        message: "it has no run_command function".to_string(),
+                    };
+                    request_failed(chan, request, error).await?;
+                    return Ok(false);
                 }
-                _ => {}
+            };
+            let result = run_command
+                .call_async(&mut module.store, (handle, len))
+                .await;
+            module.store.data_mut().command_output.clear();
+
+            let callback_data = match result {
+                Ok(callback_data) => callback_data,
+                Err(err) => {
+                    return module_trapped(chan, module, request, "run_command", err).await;
+                }
+            };
+
+            // the module didn't know the handle
+            if callback_data >> 32 == 0 {
+                return Ok(false);
             }
+
+            return update_module(chan, module, request, timing, callback_data).await;
+        }
+        ModuleEvent::Hotkey {
+            request,
+            timing,
+            index,
+        } => {
+            let run_hotkey = match module
+                .instance
+                .get_typed_func::<u32, u64>(&mut module.store, "run_hotkey")
+            {
+                Ok(func) => func,
+                Err(err) => {
+                    log::warn!(
+                        "[wasm] [module:{}] run_hotkey function does not exist or is incorrect \
+                         type: {}",
+                        module.module_name,
+                        err
+                    );
+                    module.fault(ModuleError::MissingExport {
+                        name: "run_hotkey",
+                        message: err.to_string(),
+                    });
+                    let error = RequestError::Module {
+                        module: module_id,
+                        message: "it has no run_hotkey function".to_string(),
+                    };
+                    request_failed(chan, request, error).await?;
+                    return Ok(false);
+                }
+            };
+            let callback_data = match run_hotkey.call_async(&mut module.store, index).await {
+                Ok(callback_data) => callback_data,
+                Err(err) => {
+                    return module_trapped(chan, module, request, "run_hotkey", err).await;
+                }
+            };
+
+            // the hotkey has nothing to do
+            if callback_data >> 32 == 0 {
+                return Ok(false);
+            }
+
+            return update_module(chan, module, request, timing, callback_data).await;
+        }
+        ModuleEvent::Service {
+            request,
+            timing,
+            service,
+            data,
+        } => {
+            // the module reads the event through `read_service_event`
+            let len = data.len() as u32;
+            module.store.data_mut().service_event = data;
+
+            let service_event = match module
+                .instance
+                .get_typed_func::<(u32, u32), u64>(&mut module.store, "service_event")
+            {
+                Ok(func) => func,
+                Err(err) => {
+                    log::warn!(
+                        "[wasm] [module:{}] service_event function is of an incorrect type: {}",
+                        module.module_name,
+                        err
+                    );
+                    return Ok(false);
+                }
+            };
+            let result = service_event
+                .call_async(&mut module.store, (service as u32, len))
+                .await;
+            module.store.data_mut().service_event.clear();
+
+            let callback_data = match result {
+                Ok(callback_data) => callback_data,
+                Err(err) => {
+                    return module_trapped(chan, module, request, "service_event", err).await;
+                }
+            };
+
+            // the module doesn't do anything with the event
+            if callback_data >> 32 == 0 {
+                return Ok(false);
+            }
+
+            return update_module(chan, module, request, timing, callback_data).await;
+        }
+    }
+}
+
+/// runs the message from a callback, command or hotkey through the module's
+/// `update`, `callback_data` is the message id and the pointer to its data
+/// merged into one u64
+async fn update_module(
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    module: &mut WasmModule,
+    request: RequestId,
+    mut timing: CallbackTiming,
+    callback_data: u64,
+) -> anyhow::Result<bool> {
+    let message_id = (callback_data >> 32) as u32;
+    let data_ptr = (callback_data & u32::MAX as u64) as u32;
+
+    let update_func = match module
+        .instance
+        .get_typed_func::<(u32, u32), u32>(&mut module.store, "update")
+    {
+        Ok(func) => func,
+        Err(err) => {
+            eprintln!(
+                "[wasm] [module:{}] update function does not exist or is incorrect type: {}",
+                module.module_name, err
+            );
+            module.fault(ModuleError::MissingExport {
+                name: "update",
+                message: err.to_string(),
+            });
+            let error = RequestError::Module {
+                module: RuntimeModuleId::Wasm(module.id),
+                message: "it has no update function".to_string(),
+            };
+            request_failed(chan, request, error).await?;
+            return Ok(false);
+        }
+    };
+    // note: needs to be put back into the module if its not
+    // 0 as the module might be trying to trigger side effects
+    let message_id = match update_func
+        .call_async(&mut module.store, (message_id, data_ptr))
+        .await
+    {
+        Ok(message_id) => message_id,
+        Err(err) => return module_trapped(chan, module, request, "update", err).await,
+    };
+    module.traps = 0;
+
+    // the first callback since the last render is timed until the
+    // module's next view reaches the app
+    timing.stage("update");
+    module.callback_timing.get_or_insert(timing);
+
+    return Ok(true);
+}
+
+/// tears down the module loaded from `path`, then loads the file again if it
+/// still exists
+///
+/// a reloaded module keeps its id, returns the id if a module was loaded
+async fn reload_module(
+    host: &mut WasmHost,
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    path: &Path,
+) -> anyhow::Result<Option<u32>> {
+    let id = match host
+        .modules
+        .iter()
+        .position(|module| module.file_path == path)
+    {
+        Some(index) => unload_module(host, chan, index).await?,
+        None => match host.crashed.get(path) {
+            Some(crashed) => crashed.id,
+            None => host.next_module_id(),
+        },
+    };
+
+    if !path.exists() {
+        record_crash_modules(host);
+        return Ok(None);
+    }
+
+    let module = match load_module(host, chan, id, path.to_path_buf()).await {
+        Some(module) => module,
+        None => {
+            log::warn!("[wasm] could not reload the module at {}", path.display());
+            record_crash_modules(host);
+            return Ok(None);
+        }
+    };
+
+    let events = module_events(&module);
+    log::info!(
+        "[wasm] [module:{}] loaded from {}",
+        module.module_name,
+        path.display()
+    );
+    host.modules.push(module);
+    record_crash_modules(host);
+
+    for event in events {
+        chan.send(RuntimeEvent::Update(event)).await?;
+    }
+
+    return Ok(Some(id));
+}
+
+/// removes the module at `index` in `host.modules` and destroys its surfaces,
+/// the app takes it off the services it registered for. returns its id
+async fn unload_module(
+    host: &mut WasmHost,
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    index: usize,
+) -> anyhow::Result<u32> {
+    let module = host.modules.remove(index);
+
+    // the iced ids are collected first as `module` is not `Send`
+    let surfaces = module
+        .store
+        .data()
+        .used_surface_ids
+        .borrow()
+        .iter()
+        .filter_map(|id| module.store.data().surface_wasm_id.get_iced_id(id))
+        .copied()
+        .collect::<Vec<iced::window::Id>>();
+    let id = module.id;
+    let path = module.file_path.clone();
+    drop(module);
+
+    for surface in surfaces {
+        chan.send(RuntimeEvent::Update(Event::DestroyLayerSurface(surface)))
+            .await?;
+    }
+    chan.send(RuntimeEvent::Update(Event::ModuleUnloaded {
+        module_id: id,
+    }))
+    .await?;
+
+    log::info!("[wasm] unloaded module {id} from {}", path.display());
+
+    return Ok(id);
+}
+
+/// unloads a module that trapped `max_traps` times in a row, then loads it
+/// again once a wait that doubles with each crash is over, at most
+/// `crash_restarts` times
+async fn crash_module(
+    host: &mut WasmHost,
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    request_tx: &InstrumentedSender<flume::Sender<RuntimeRequest<WasmRuntime>>>,
+    module_id: u32,
+) -> anyhow::Result<()> {
+    let Some(index) = host
+        .modules
+        .iter()
+        .position(|module| module.id == module_id)
+    else {
+        return Ok(());
+    };
+    let name = host.modules[index].module_name.clone();
+    let path = host.modules[index].file_path.clone();
+
+    unload_module(host, chan, index).await?;
+    record_crash_modules(host);
+    metrics::increment("runtime.wasm.modules_crashed");
+
+    let crashed = host.crashed.entry(path.clone()).or_insert(Crashed {
+        id: module_id,
+        restarts: 0,
+    });
+
+    if crashed.restarts >= config::get().modules.crash_restarts {
+        log::error!("[wasm] [module:{name}] kept crashing, it won't be loaded again");
+        notify::send(
+            &format!("aurorashell unloaded the module {name}"),
+            "it kept crashing, rebuild it or restart the shell to load it again",
+        );
+        return Ok(());
+    }
+
+    let delay = (CRASH_BACKOFF_MIN * 2u32.saturating_pow(crashed.restarts)).min(CRASH_BACKOFF_MAX);
+    crashed.restarts += 1;
+    log::warn!(
+        "[wasm] [module:{name}] crashed, loading it again in {}",
+        humantime::format_duration(delay)
+    );
+
+    let request_tx = request_tx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+
+        let request = RuntimeRequest::new(Request::RestartModule { path });
+        if request_tx.send_async(request).await.is_err() {
+            log::debug!("[wasm] runtime stopped before a crashed module was loaded again");
+        }
+    });
+
+    return Ok(());
+}
+
+/// tells the app what went wrong in the modules since it was last told
+async fn send_module_errors(
+    host: &mut WasmHost,
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+) -> anyhow::Result<()> {
+    for module in &mut host.modules {
+        let errors = std::mem::take(&mut module.store.data_mut().errors);
+
+        for error in errors {
+            metrics::increment("runtime.wasm.module_errors");
+            chan.send(RuntimeEvent::Update(Event::ModuleError {
+                module_id: module.id,
+                error,
+            }))
+            .await?;
         }
     }
+
+    return Ok(());
+}
+
+/// tells the app about the modules whose stats changed since it was last
+/// told
+async fn send_module_stats(
+    host: &mut WasmHost,
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+) -> anyhow::Result<()> {
+    for module in &mut host.modules {
+        if module.stats == module.sent_stats {
+            continue;
+        }
+        module.sent_stats = module.stats;
+
+        chan.send(RuntimeEvent::Update(Event::ModuleStats {
+            module_id: module.id,
+            stats: module.stats,
+        }))
+        .await?;
+    }
+
+    return Ok(());
+}
+
+/// counts a trap in one of the module's functions and fails the request it
+/// was handling, the module is unloaded after `max_traps` in a row
+async fn module_trapped(
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    module: &mut WasmModule,
+    request: RequestId,
+    function: &'static str,
+    err: anyhow::Error,
+) -> anyhow::Result<bool> {
+    let message = module.trapped(function, &err);
+    module.fault(ModuleError::Trap { function, message });
+
+    let error = RequestError::Module {
+        module: RuntimeModuleId::Wasm(module.id),
+        message: format!("its {function} function trapped: {err}"),
+    };
+    request_failed(chan, request, error).await?;
+
+    return Ok(false);
+}
+
+/// the events that tell the app about a newly loaded module and register it
+/// to the services it asked for
+fn module_events(module: &WasmModule) -> Vec<Event> {
+    let mut events = vec![Event::ModuleLoaded {
+        module_id: module.id,
+        module_name: module.module_name.clone(),
+        file_path: module.file_path.clone(),
+    }];
+
+    for register in &module.registers {
+        events.push(Event::RegisterModuleToService {
+            module_id: module.id,
+            register: register.clone(),
+        });
+    }
+
+    return events;
+}
+
+/// keeps the modules listed in crash reports up to date
+fn record_crash_modules(host: &WasmHost) {
+    crash::clear_modules("wasm");
+    for module in &host.modules {
+        crash::record_module("wasm", &module.module_name, &module.file_path);
+    }
+}
+
+/// calls the module's `run_callback`, which from abi version 2 also takes the
+/// generation of the view the callback came from so the module can reject it
+///
+/// returns `None` if the module doesn't have a `run_callback` it can be
+/// called with, errors when it traps
+async fn run_callback(
+    module: &mut WasmModule,
+    surface_id: u32,
+    callback_id: u32,
+    data: u64,
+    kind: CallbackDataKind,
+    generation: u32,
+) -> anyhow::Result<Option<u64>> {
+    let result = if module.abi_version.has_tagged_callbacks() {
+        match module
+            .instance
+            .get_typed_func::<(u32, u32, u64, u32, u32), u64>(&mut module.store, "run_callback")
+        {
+            Ok(func) => Ok(func
+                .call_async(
+                    &mut module.store,
+                    (surface_id, callback_id, data, generation, kind as u32),
+                )
+                .await?),
+            Err(err) => Err(err),
+        }
+    } else if module.abi_version.has_generations() {
+        match module
+            .instance
+            .get_typed_func::<(u32, u32, u64, u32), u64>(&mut module.store, "run_callback")
+        {
+            Ok(func) => Ok(func
+                .call_async(
+                    &mut module.store,
+                    (surface_id, callback_id, data, generation),
+                )
+                .await?),
+            Err(err) => Err(err),
+        }
+    } else {
+        match module
+            .instance
+            .get_typed_func::<(u32, u32, u64), u64>(&mut module.store, "run_callback")
+        {
+            Ok(func) => Ok(func
+                .call_async(&mut module.store, (surface_id, callback_id, data))
+                .await?),
+            Err(err) => Err(err),
+        }
+    };
+
+    match result {
+        Ok(callback_data) => Ok(Some(callback_data)),
+        Err(err) => {
+            log::warn!(
+                "[wasm] [module:{}] run_callback function does not exist or is incorrect type: {}",
+                module.module_name,
+                err
+            );
+            module.fault(ModuleError::MissingExport {
+                name: "run_callback",
+                message: err.to_string(),
+            });
+            Ok(None)
+        }
+    }
+}
+
+/// stops rendering a module and running its callbacks, its surfaces are
+/// destroyed by the app
+async fn disable_module(
+    chan: &mut InstrumentedSender<mpsc::Sender<RuntimeEvent<WasmRuntime>>>,
+    module: &mut WasmModule,
+    reason: String,
+) -> anyhow::Result<()> {
+    log::error!(
+        "[wasm] [module:{}] disabled because {}",
+        module.module_name,
+        reason
+    );
+
+    module.disabled = true;
+    module.tree_bytes.clear();
+    metrics::increment("runtime.wasm.modules_disabled");
+
+    notify::send(
+        &format!("aurorashell disabled the module {}", module.module_name),
+        &format!("{reason}, restart the shell to load it again"),
+    );
+
+    chan.send(RuntimeEvent::Update(Event::ModuleDisabled {
+        module_id: module.id,
+        reason,
+    }))
+    .await?;
+
+    return Ok(());
 }
 
 /// callback data for certain widgets
 #[derive(Debug, Clone)]
 pub enum WasmCallbackData {
     Slider(u64),
+    /// the item is the address of the tray item that was clicked, only the
+    /// click is given to the module
+    Tray {
+        item: String,
+        click: TrayClick,
+    },
+    /// text, like the whole text of a text input after it changed
+    Text(String),
+    /// whether a toggler is on after it was flipped
+    Bool(bool),
+    /// the first item of a grid that can be seen after it was scrolled
+    Grid(u32),
+    /// how far a mouse area was scrolled, in lines
+    Scroll {
+        x: f32,
+        y: f32,
+    },
+}
+
+/// what the data given to `run_callback` is, so the module doesn't have to
+/// know from the widget alone
+///
+/// only passed to modules from abi version 12, older ones get the same data
+/// without it
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CallbackDataKind {
+    /// the widget has no data, `data` is 0
+    None = 0,
+    /// a number, or a few packed into the 64 bits
+    Bits = 1,
+    /// `data` is the length of the text, read with `read_callback_text`
+    Text = 2,
+    /// `data` is 0 or 1
+    Bool = 3,
 }
 
 /// stores state for the wasm runtime
@@ -327,6 +1783,76 @@ pub struct WasmHost {
     /// shared linker for all stores and engine
     linker: Linker<WasiContext>,
     modules: Vec<WasmModule>,
+    /// the last audio devices the app sent, given to modules as they load
+    audio: Option<Arc<AudioDevices>>,
+    /// counts the audio updates, see `AudioDevices::summary`
+    audio_revision: u32,
+    /// the last devices from kde connect, in the layout of
+    /// `KdeConnectState::serialize`, given to modules as they load
+    kdeconnect: Arc<Vec<u8>>,
+    /// the palette and font in the layout of `Base16Color::serialize`, given
+    /// to modules as they load
+    theme: Arc<Vec<u8>>,
+    /// modules unloaded for trapping too many times, by their file
+    crashed: HashMap<PathBuf, Crashed>,
+}
+
+/// a module that was unloaded for trapping too many times in a row
+#[derive(Debug)]
+struct Crashed {
+    /// kept so the module gets the same id when it's loaded again
+    id: u32,
+    /// how many times it was loaded again since it first crashed
+    restarts: u32,
+}
+
+impl WasmHost {
+    /// an engine and linker with the host's api, without any modules
+    fn new() -> anyhow::Result<Self> {
+        let engine = Engine::new(&target::engine_config())?;
+        limits::tick_epochs(&engine);
+
+        let mut linker: Linker<WasiContext> = Linker::new(&engine);
+        wasmtime_wasi::preview1::add_to_linker_async(&mut linker, |context| &mut context.wasip1)?;
+
+        get_api_functions(&mut linker)?;
+
+        return Ok(Self {
+            engine,
+            linker,
+            modules: vec![],
+            audio: None,
+            audio_revision: 0,
+            kdeconnect: Arc::default(),
+            theme: Arc::default(),
+            crashed: HashMap::new(),
+        });
+    }
+
+    fn module_mut(&mut self, id: u32) -> Option<&mut WasmModule> {
+        self.modules.iter_mut().find(|module| module.id == id)
+    }
+
+    /// an id no loaded module is using, nor a crashed one waiting to be
+    /// loaded again
+    fn next_module_id(&self) -> u32 {
+        self.modules
+            .iter()
+            .map(|module| module.id)
+            .chain(self.crashed.values().map(|crashed| crashed.id))
+            .map(|id| id + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// a surface a module opened or closed after its setup
+#[derive(Debug)]
+enum SurfaceRequest {
+    /// the id the module gave the surface and its settings
+    Open(u32, SctkLayerSurfaceSettings),
+    /// the id the module gave the surface
+    Close(u32),
 }
 
 /// wasi context for a wasm module
@@ -339,6 +1865,97 @@ struct WasiContext {
     pub surface_wasm_id: WasmId,
     /// the surface ids that the module has actually used
     pub used_surface_ids: RefCell<Vec<u32>>,
+    /// the last time the clock service gave the module, in the layout of
+    /// `ClockTime::serialize`, empty until the first one arrives
+    pub clock: Vec<u8>,
+    /// the audio devices modules read the details of on demand
+    pub audio: Option<Arc<AudioDevices>>,
+    /// in the layout of `AudioDevices::summary`, empty until the audio
+    /// service first updates
+    pub audio_summary: Vec<u8>,
+    /// the devices paired with kde connect, in the layout of
+    /// `KdeConnectState::serialize`, empty until the service first updates
+    pub kdeconnect: Arc<Vec<u8>>,
+    /// the palette and font in the layout of `Base16Color::serialize`,
+    /// empty until the app first sends it
+    pub theme: Arc<Vec<u8>>,
+    /// what the module asked of kde connect, sent to the app once the event
+    /// the module is handling is done
+    pub kdeconnect_requests: Vec<kdeconnect::Request>,
+    /// what the module asked of the audio service, sent to the app once the
+    /// event the module is handling is done
+    pub audio_requests: Vec<audio::Request>,
+    /// what the module asked of the inhibit service, sent to the app once
+    /// the event the module is handling is done
+    pub inhibit_requests: Vec<inhibit::Request>,
+    /// what the module asked of the session service, sent to the app once
+    /// the event the module is handling is done
+    pub session_requests: Vec<session::Request>,
+    /// the text the module last put on the clipboard, sent to the app once
+    /// the event the module is handling is done
+    pub clipboard: Option<String>,
+    /// the text given to the callback that's running, like a text input's
+    pub callback_text: String,
+    /// the module's last search of the unicode table, in the layout of
+    /// `unicode::serialize`
+    pub unicode_results: Vec<u8>,
+    /// the output the module asked for a region of, sent to the app once the
+    /// event the module is handling is done. `u32::MAX` is the one with focus
+    pub region_request: Option<u32>,
+    /// the last region picked for the module in the layout of
+    /// `region::serialize`, empty once the module has read it
+    pub region: Vec<u8>,
+    /// the output and part of it the module asked for a screenshot of, sent
+    /// to the app once the event the module is handling is done. `u32::MAX`
+    /// is every output
+    pub screenshot_request: Option<(u32, Option<Region>)>,
+    /// the outcome of the module's last screenshot in the layout of
+    /// `screenshot::serialize`, empty once the module has read it
+    pub screenshot: Vec<u8>,
+    /// whether the app is taking a screenshot for the module, it can't ask
+    /// for another until it's done
+    pub screenshot_pending: bool,
+    /// surfaces the module opened or closed after setup, sent to the app
+    /// once the event the module is handling is done
+    pub surface_requests: Vec<SurfaceRequest>,
+    /// the surfaces the module marked as changed by the event it's handling,
+    /// only these are rendered once it's done. every surface is when it's
+    /// empty
+    pub dirty_surfaces: HashSet<u32>,
+    /// the settings each of the module's surfaces was last opened with, by
+    /// the id the module gave it, so a closed one can be opened again
+    pub surfaces: HashMap<u32, SctkLayerSurfaceSettings>,
+    /// commands the module asked for by their ticket, started once the
+    /// event the module is handling is done
+    pub command_requests: Vec<(u64, CommandRequest)>,
+    /// the handle the module gave each of its commands that hasn't finished
+    /// yet, by ticket
+    pub pending_commands: HashMap<u64, u32>,
+    /// the output of the command whose `run_command` is running, in the
+    /// layout of `command::serialize`
+    pub command_output: Vec<u8>,
+    /// the event whose `service_event` is running, in the layout of its
+    /// service's `WasmSerializable`
+    pub service_event: Vec<u8>,
+    /// the module's name, empty until its `setup` has run
+    pub module_name: String,
+    /// the layout the module hands data over in, `V0` until it's asked
+    pub abi_version: AbiVersion,
+    /// the module's options from the config, in the layout of
+    /// `fs::serialize_settings`. empty when it has none
+    pub settings: Vec<u8>,
+    /// what went wrong in the module since the app was last told, see
+    /// `ModuleError`
+    pub errors: Vec<ModuleError>,
+}
+
+/// a view of one of a module's surfaces the app was sent
+#[derive(Debug, Clone, Copy)]
+struct SentView {
+    /// see `WasmUiNode::fingerprint`, views that come out the same aren't
+    /// sent again
+    fingerprint: u64,
+    generation: u32,
 }
 
 /// stores data related to a wasm module
@@ -349,7 +1966,7 @@ struct WasmModule {
     id: u32,
     /// the module's name, must be unique
     module_name: String,
-    /// file path in $HOME/.local/share/aurorashell/modules
+    /// file path in one of the modules directories, see `fs::modules_dirs`
     file_path: PathBuf,
     /// the registers the module has requested
     registers: Vec<SubscriptionData>,
@@ -359,4 +1976,75 @@ struct WasmModule {
     store: Store<WasiContext>,
     instance: Instance,
     memory: Memory,
+    /// the layout the module hands data over in
+    abi_version: AbiVersion,
+    /// the size of the last ui tree for each of the module's surfaces
+    tree_bytes: HashMap<u32, usize>,
+    /// the generation of the last view of each of the module's surfaces
+    generations: HashMap<u32, u32>,
+    /// the last view of each of the module's surfaces the app was sent,
+    /// callbacks from an older view are dropped
+    sent_views: HashMap<u32, SentView>,
+    /// events waiting to be run through the module, in the order they came
+    events: EventQueue,
+    /// set when the module went over a limit, see `limits`
+    disabled: bool,
+    /// how many times in a row the module's functions trapped, it's
+    /// unloaded at `max_traps` under `[modules]`
+    traps: u32,
+    /// whether the module exports `service_event`, the events of the
+    /// services it registered for are only given to it if it does
+    has_service_event: bool,
+    /// icons and pngs from the module's last views
+    images: ImageCache,
+    /// the callback waiting for the module's next view, see `latency`
+    callback_timing: Option<CallbackTiming>,
+    /// what the module has been doing, for the inspector
+    stats: ModuleStats,
+    /// the stats the app was last sent, they're only sent again once they
+    /// changed
+    sent_stats: ModuleStats,
+}
+
+impl WasmModule {
+    /// counts a trap in the module's `function`, returns what happened for
+    /// its `ModuleError`
+    fn trapped(&mut self, function: &str, err: &anyhow::Error) -> String {
+        self.traps += 1;
+        metrics::increment("runtime.wasm.traps");
+
+        if limits::timed_out(err) {
+            metrics::increment("runtime.wasm.timeouts");
+            log::warn!(
+                "[wasm] [module:{}] {function} function took longer than {}ms and was stopped \
+                 ({} traps in a row)",
+                self.module_name,
+                config::get().modules.call_timeout_ms,
+                self.traps
+            );
+            return format!(
+                "took longer than {}ms and was stopped",
+                config::get().modules.call_timeout_ms
+            );
+        }
+
+        log::warn!(
+            "[wasm] [module:{}] {function} function trapped ({} in a row): {err}",
+            self.module_name,
+            self.traps
+        );
+        return err.to_string();
+    }
+
+    /// keeps an error for the app, sent with the others once the runtime is
+    /// done with the module
+    fn fault(&mut self, error: ModuleError) {
+        self.store.data_mut().errors.push(error);
+    }
+
+    /// whether the module trapped too many times in a row and has to be
+    /// unloaded
+    fn has_crashed(&self) -> bool {
+        return self.traps >= config::get().modules.max_traps.max(1);
+    }
 }
@@ -0,0 +1,142 @@
+//! a module's events wait in a queue until the runtime gets to them, so they
+//! reach the module in the order they arrived no matter where they came from
+//!
+//! every event gets a sequence number from its module's queue, which is in
+//! the trace logs to check the order events were queued and handled in
+//!
+//! modules waiting to be rendered are queued too, with the surfaces that
+//! have to be rendered when the module marked them with
+//! `mark_surface_dirty`
+
+use super::WasmCallbackData;
+use super::latency::CallbackTiming;
+
+use crate::runtime::RequestId;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use iced::window::Id;
+
+/// how many events a module can have waiting, any more are dropped
+const MAX_QUEUED: usize = 256;
+
+/// something to run through a module
+#[derive(Debug, Clone)]
+pub enum ModuleEvent {
+    /// a widget's callback was triggered, see `Request::CallbackEvent`
+    Callback {
+        /// the request the callback came in, told about it if it fails
+        request: RequestId,
+        timing: CallbackTiming,
+        surface_id: Id,
+        callback_id: u32,
+        generation: u32,
+        data: Option<WasmCallbackData>,
+    },
+    /// a command the module started finished, see `Request::CommandFinished`
+    Command {
+        request: RequestId,
+        timing: CallbackTiming,
+        /// what the module called the command
+        handle: u32,
+        /// in the layout of `command::serialize`
+        output: Vec<u8>,
+    },
+    /// one of the module's hotkeys was pressed, see `Request::HotkeyPressed`
+    Hotkey {
+        request: RequestId,
+        timing: CallbackTiming,
+        /// where the hotkey is in the module's register
+        index: u32,
+    },
+    /// a service the module registered for emitted an event, see
+    /// `WasmSerializable`
+    Service {
+        request: RequestId,
+        timing: CallbackTiming,
+        /// the id of the service
+        service: u16,
+        /// the event in the layout of the service's `WasmSerializable`
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct EventQueue {
+    events: VecDeque<(u64, ModuleEvent)>,
+    /// the sequence number of the next event
+    next_seq: u64,
+}
+
+impl EventQueue {
+    /// adds `event` to the back of the queue, returning its sequence number
+    ///
+    /// returns `None` if the queue is full, the sequence number is still
+    /// used up so the dropped event shows as a gap
+    pub fn push(&mut self, event: ModuleEvent) -> Option<u64> {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        if self.events.len() >= MAX_QUEUED {
+            return None;
+        }
+
+        self.events.push_back((seq, event));
+        return Some(seq);
+    }
+
+    /// takes the oldest event
+    pub fn pop(&mut self) -> Option<(u64, ModuleEvent)> {
+        self.events.pop_front()
+    }
+}
+
+/// the modules waiting to be rendered, in the order they were queued
+#[derive(Debug, Default)]
+pub struct RenderQueue {
+    modules: VecDeque<u32>,
+    /// the surfaces each queued module marked dirty, every surface is
+    /// rendered for modules that aren't in here
+    surfaces: HashMap<u32, HashSet<u32>>,
+}
+
+impl RenderQueue {
+    /// queues every surface of the module
+    pub fn push_back(&mut self, module_id: u32) {
+        self.surfaces.remove(&module_id);
+        if !self.modules.contains(&module_id) {
+            self.modules.push_back(module_id);
+        }
+    }
+
+    /// queues only `surfaces` of the module, unless every surface of it is
+    /// already queued
+    pub fn push_surfaces(&mut self, module_id: u32, surfaces: HashSet<u32>) {
+        if self.modules.contains(&module_id) {
+            if let Some(queued) = self.surfaces.get_mut(&module_id) {
+                queued.extend(surfaces);
+            }
+            return;
+        }
+
+        self.modules.push_back(module_id);
+        self.surfaces.insert(module_id, surfaces);
+    }
+
+    /// takes the module queued first, with the surfaces to render or `None`
+    /// for all of them
+    pub fn pop_front(&mut self) -> Option<(u32, Option<HashSet<u32>>)> {
+        let module_id = self.modules.pop_front()?;
+        return Some((module_id, self.surfaces.remove(&module_id)));
+    }
+}
+
+impl FromIterator<u32> for RenderQueue {
+    fn from_iter<T: IntoIterator<Item = u32>>(iter: T) -> Self {
+        let mut queue = Self::default();
+        for module_id in iter {
+            queue.push_back(module_id);
+        }
+        return queue;
+    }
+}
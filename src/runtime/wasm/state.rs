@@ -1,27 +1,95 @@
-use super::{Event, WasmRuntime, WasmUiNode};
+use super::{Event, ModuleError, ModuleStats, SurfaceView, WasmRuntime, WasmUiNode};
 
 use crate::app::AppMessage;
+use crate::compositor::{create_layer_surface, destroy_layer_surface};
+use crate::instrumented::InstrumentedSender;
 use crate::runtime::{RuntimeRequest, RuntimeService, RuntimeState};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use iced::Task;
-use iced::platform_specific::shell::commands::layer_surface::{
-    destroy_layer_surface, get_layer_surface,
-};
 use iced::window::Id;
 
 #[derive(Debug, Clone)]
 pub struct WasmState {
     /// used to send requests to the `WasmService`
-    pub(super) channel: flume::Sender<RuntimeRequest<WasmRuntime>>,
+    pub(super) channel: InstrumentedSender<flume::Sender<RuntimeRequest<WasmRuntime>>>,
 
-    /// maps module ids to a map of surface ids to ui trees
-    pub module_ui_trees: HashMap<u32, HashMap<Id, Box<WasmUiNode>>>,
+    /// maps module ids to the trees of their last render, replaced as a
+    /// whole by each render so `App::view` never sees half of one
+    pub module_ui_trees: HashMap<u32, Arc<ModuleSnapshot>>,
     /// maps surface ids to module ids
     ///
     /// used as a lookup table for `Self::module_ui_trees`
     pub surface_module_ids: HashMap<Id, u32>,
+    /// info about each loaded module, keyed by module id
+    pub modules: HashMap<u32, LoadedModule>,
+    /// what went wrong in each module, keyed by module id. kept when the
+    /// module is unloaded, so the errors of one that crashed or couldn't be
+    /// loaded can still be read
+    pub module_errors: HashMap<u32, ModuleErrors>,
+    /// what each loaded module has been doing, keyed by module id. a module
+    /// isn't in here until it's been rendered or given an event
+    pub module_stats: HashMap<u32, ModuleStats>,
+}
+
+/// the most recent errors kept for each module
+const MAX_RECENT_ERRORS: usize = 10;
+
+/// what went wrong in a module since the runtime started
+#[derive(Debug, Clone, Default)]
+pub struct ModuleErrors {
+    /// every error, including the ones no longer in `recent`
+    pub count: u64,
+    /// the last `MAX_RECENT_ERRORS` errors, oldest first
+    pub recent: VecDeque<ModuleError>,
+}
+
+/// the ui trees of a module's surfaces as of one render
+#[derive(Debug, Clone, Default)]
+pub struct ModuleSnapshot {
+    pub trees: HashMap<Id, SurfaceTree>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SurfaceTree {
+    /// the generation of the tree, see `Event::ModViewData`
+    pub generation: u32,
+    pub tree: Arc<WasmUiNode>,
+}
+
+impl ModuleSnapshot {
+    /// the snapshot with the trees of a new render, surfaces the render
+    /// didn't have a tree for (like one whose view trapped) keep their last
+    /// tree
+    fn with_views(&self, views: Vec<SurfaceView>) -> Self {
+        let mut trees = self.trees.clone();
+        for view in views {
+            trees.insert(
+                view.surface_id,
+                SurfaceTree {
+                    generation: view.generation,
+                    tree: view.tree,
+                },
+            );
+        }
+
+        return Self { trees };
+    }
+}
+
+/// what the app knows about a loaded module
+#[derive(Debug, Clone)]
+pub struct LoadedModule {
+    pub module_name: String,
+    pub file_path: PathBuf,
+    /// why the module was disabled, if it was
+    pub disabled: Option<String>,
+    /// the services the module registered for, see
+    /// `SubscriptionData::service_name`
+    pub services: Vec<&'static str>,
 }
 
 impl RuntimeState<WasmRuntime> for WasmState {
@@ -29,22 +97,104 @@ impl RuntimeState<WasmRuntime> for WasmState {
         match event {
             Event::ModViewData {
                 module_id,
-                surface_id,
-                tree,
+                views,
+                timing,
+            } => {
+                for view in &views {
+                    self.surface_module_ids.insert(view.surface_id, module_id);
+                }
+
+                let snapshot = match self.module_ui_trees.get(&module_id) {
+                    Some(snapshot) => snapshot.with_views(views),
+                    None => ModuleSnapshot::default().with_views(views),
+                };
+                self.module_ui_trees.insert(module_id, Arc::new(snapshot));
+
+                if let Some(timing) = timing {
+                    let module_name = self
+                        .modules
+                        .get(&module_id)
+                        .map_or("unknown", |module| module.module_name.as_str());
+                    timing.finish(module_name);
+                }
+            }
+            Event::ModuleLoaded {
+                module_id,
+                module_name,
+                file_path,
+            } => {
+                self.modules.insert(
+                    module_id,
+                    LoadedModule {
+                        module_name,
+                        file_path,
+                        disabled: None,
+                        services: vec![],
+                    },
+                );
+            }
+            Event::RegisterModuleToService {
+                module_id,
+                register,
             } => {
-                self.surface_module_ids.insert(surface_id, module_id);
-                if let Some(map) = self.module_ui_trees.get_mut(&module_id) {
-                    map.insert(surface_id, tree);
-                } else {
-                    let mut map = HashMap::new();
-                    map.insert(surface_id, tree);
-                    self.module_ui_trees.insert(module_id, map);
+                if let Some(module) = self.modules.get_mut(&module_id) {
+                    let service = register.service_name();
+                    if !module.services.contains(&service) {
+                        module.services.push(service);
+                    }
                 }
             }
+            Event::ModuleStats { module_id, stats } => {
+                self.module_stats.insert(module_id, stats);
+            }
+            Event::ModuleError { module_id, error } => {
+                let errors = self.module_errors.entry(module_id).or_default();
+                errors.count += 1;
+                if errors.recent.len() == MAX_RECENT_ERRORS {
+                    errors.recent.pop_front();
+                }
+                errors.recent.push_back(error);
+            }
+            Event::ModuleUnloaded { module_id } => {
+                self.modules.remove(&module_id);
+                self.module_ui_trees.remove(&module_id);
+                self.module_stats.remove(&module_id);
+                self.surface_module_ids.retain(|_, id| *id != module_id);
+            }
+            Event::ModuleDisabled { module_id, reason } => {
+                if let Some(module) = self.modules.get_mut(&module_id) {
+                    module.disabled = Some(reason);
+                }
+
+                self.module_ui_trees.remove(&module_id);
+
+                let surfaces = self
+                    .surface_module_ids
+                    .iter()
+                    .filter(|(_, id)| **id == module_id)
+                    .map(|(surface_id, _)| *surface_id)
+                    .collect::<Vec<Id>>();
+
+                let mut tasks = vec![];
+                for surface_id in surfaces {
+                    self.surface_module_ids.remove(&surface_id);
+                    tasks.push(destroy_layer_surface(surface_id));
+                }
+
+                return Task::batch(tasks);
+            }
             Event::CreateLayerSurface(layer) => {
-                return get_layer_surface(layer);
+                return create_layer_surface(layer);
             }
             Event::DestroyLayerSurface(layer) => {
+                // modules can close their surfaces without being unloaded,
+                // so their trees have to go too
+                if let Some(module_id) = self.surface_module_ids.remove(&layer) {
+                    if let Some(snapshot) = self.module_ui_trees.get_mut(&module_id) {
+                        Arc::make_mut(snapshot).trees.remove(&layer);
+                    }
+                }
+
                 return destroy_layer_surface(layer);
             }
             _ => {}
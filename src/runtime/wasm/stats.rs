@@ -0,0 +1,30 @@
+//! counters for what each module has been doing, kept by the runtime and
+//! sent to the app for the inspector, see `builtin::inspector`
+//!
+//! the counters only go up, the inspector works out how many a second there
+//! were from the difference between two of them
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModuleStats {
+    /// how many times the module was rendered, once for all of its surfaces
+    pub renders: u64,
+    /// how long the last render took, from the first `view` to the last
+    pub render_time: Duration,
+    /// when the last render finished, `None` until the first one
+    pub rendered_at: Option<Instant>,
+    /// events queued for the module, like callbacks and service events
+    pub events: u64,
+    /// events dropped because the module had too many waiting
+    pub dropped_events: u64,
+}
+
+impl ModuleStats {
+    pub(super) fn rendered(&mut self, started: Instant) {
+        let now = Instant::now();
+        self.renders += 1;
+        self.render_time = now - started;
+        self.rendered_at = Some(now);
+    }
+}
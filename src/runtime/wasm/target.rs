@@ -0,0 +1,140 @@
+//! checks a module was built for the target the shell runs, `wasm32-wasip1`,
+//! so building for the wrong one gives an error saying how to fix it rather
+//! than a confusing failure later on
+//!
+//! modules can also be precompiled by `aurorashell install` into `.cwasm`
+//! files, which skip compiling when the shell starts. those are tied to the
+//! machine and the version of wasmtime that made them
+
+use std::path::Path;
+
+use anyhow::anyhow;
+use wasmtime::{Config, Engine, Module, Precompiled};
+
+/// the start of every wasm binary
+const WASM_MAGIC: &[u8; 4] = b"\0asm";
+/// the version after the magic for a core module, which is what wasip1 gives
+const CORE_MODULE_VERSION: [u8; 4] = [0x01, 0x00, 0x00, 0x00];
+/// the version after the magic for a component, which is what wasip2 gives
+const COMPONENT_VERSION: [u8; 4] = [0x0D, 0x00, 0x01, 0x00];
+
+/// the configuration modules are compiled with, precompiled modules only
+/// load in an engine with the same one
+///
+/// modules are compiled with checks for the engine's epoch so calls into
+/// them can be given a deadline, see `limits::set_deadlines`
+pub fn engine_config() -> Config {
+    let mut config = Config::new();
+    config.async_support(true);
+    config.epoch_interruption(true);
+    return config;
+}
+
+/// compiles the module at `path`, or loads it if it's a `.cwasm`
+pub fn compile(engine: &Engine, path: &Path) -> anyhow::Result<Module> {
+    let bytes = std::fs::read(path)?;
+
+    if path.extension().is_some_and(|ext| ext == "cwasm") {
+        return deserialize(engine, &bytes);
+    }
+
+    check_binary(engine, &bytes)?;
+    let module = Module::new(engine, &bytes)?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    check_imports(&module, &file_name)?;
+
+    return Ok(module);
+}
+
+/// loads a module precompiled by `aurorashell install`
+fn deserialize(engine: &Engine, bytes: &[u8]) -> anyhow::Result<Module> {
+    match engine.detect_precompiled(bytes) {
+        Some(Precompiled::Module) => {}
+        Some(_) => {
+            return Err(anyhow!(
+                "this is a precompiled component, modules are built for `wasm32-wasip1`"
+            ));
+        }
+        None => {
+            return Err(anyhow!(
+                "this is not a precompiled module, `.cwasm` files are made by `aurorashell \
+                 install`"
+            ));
+        }
+    }
+
+    // safety: the bytes were checked to be a module precompiled by wasmtime,
+    // and wasmtime checks the version and target that made it
+    match unsafe { Module::deserialize(engine, bytes) } {
+        Ok(module) => return Ok(module),
+        Err(err) => {
+            return Err(anyhow!(
+                "it was precompiled by another version of aurorashell or for another machine, run \
+                 `aurorashell install` with its `.wasm` again: {err}"
+            ));
+        }
+    }
+}
+
+/// checks the header of a `.wasm`, so components and precompiled modules
+/// are caught before wasmtime tries to compile them
+pub fn check_binary(engine: &Engine, bytes: &[u8]) -> anyhow::Result<()> {
+    if bytes.get(0..4) != Some(WASM_MAGIC) {
+        if engine.detect_precompiled(bytes).is_some() {
+            return Err(anyhow!(
+                "this is a precompiled module, rename it to end in `.cwasm`"
+            ));
+        }
+
+        return Err(anyhow!("this is not a wasm binary"));
+    }
+
+    match bytes.get(4..8) {
+        Some(version) if version == CORE_MODULE_VERSION => return Ok(()),
+        Some(version) if version == COMPONENT_VERSION => {
+            return Err(anyhow!(
+                "this is a component, it was probably built for `wasm32-wasip2`, build it with \
+                 `--target wasm32-wasip1`"
+            ));
+        }
+        Some(version) => {
+            return Err(anyhow!("unknown wasm binary version {version:02X?}"));
+        }
+        None => return Err(anyhow!("the wasm binary is cut short")),
+    }
+}
+
+/// checks what the module imports for signs of the wrong target
+pub fn check_imports(module: &Module, file_name: &str) -> anyhow::Result<()> {
+    let mut wasi = false;
+
+    for import in module.imports() {
+        match import.module() {
+            "wasi_snapshot_preview1" => wasi = true,
+            "wasi_unstable" => {
+                return Err(anyhow!(
+                    "this imports `wasi_unstable`, an old version of wasi, build it with \
+                     `--target wasm32-wasip1`"
+                ));
+            }
+            name if name.starts_with("__wbindgen") => {
+                return Err(anyhow!(
+                    "this was built with wasm-bindgen, probably for `wasm32-unknown-unknown`, \
+                     build it with `--target wasm32-wasip1`"
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    // a module that never prints or reads the environment may not need wasi,
+    // so this doesn't stop it loading
+    if !wasi {
+        log::warn!(
+            "[wasm] [module:{file_name}] doesn't import wasi, if it was built for \
+             `wasm32-unknown-unknown` it should be built with `--target wasm32-wasip1`"
+        );
+    }
+
+    return Ok(());
+}
@@ -1,12 +1,32 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::ops::RangeInclusive;
+use std::path::PathBuf;
 use std::str;
+use std::sync::Arc;
 
 use anyhow::anyhow;
-use iced::Color;
-use iced::core::widget::text;
+use iced::widget::image;
 use wasmtime::{Memory, Store};
 
 use super::WasiContext;
+use super::abi::AbiVersion;
+use super::images::ImageCache;
+use super::memory::{self, FromBytes};
+
+use crate::config;
+
+/// set in `RawElement::flags` when the module marked the element and its
+/// children as static
+const STATIC_FLAG: u8 = 1 << 0;
+/// set in `RawElement::flags` on text, sliders and progress bars that run top
+/// to bottom,
+/// for bars on the left or right edge
+const VERTICAL_FLAG: u8 = 1 << 1;
+
+/// set in `RawTextStyle::font_flags` for bold text
+const TEXT_BOLD_FLAG: u8 = 1 << 0;
+/// set in `RawTextStyle::font_flags` for italic text
+const TEXT_ITALIC_FLAG: u8 = 1 << 1;
 
 /// gets the tree of RawElement from the guest,
 /// turning it into a tree of UiNode to send to the main thread
@@ -15,149 +35,452 @@ use super::WasiContext;
 ///           as we use the wasi p1
 /// `memory` - the wasmtime `Memory` struct
 /// `offset` - points to head of the tree in wasm linear memory
+/// `version` - the layout of the `ViewFuncData` at `offset`
+/// `images` - the module's images from its last views
 pub fn get_element_tree(
     module_name: &str,
     store: &Store<WasiContext>,
     memory: Memory,
     offset: u32,
-) -> anyhow::Result<WasmUiNode> {
+    version: AbiVersion,
+    images: &mut ImageCache,
+) -> anyhow::Result<ElementTree> {
     let memory_bytes: &[u8] = memory.data(store);
 
-    let data = unsafe {
-        let offset = offset as usize;
-        let bytes = &memory_bytes[offset..offset + std::mem::size_of::<ViewFuncData>()];
-        std::ptr::read_unaligned(bytes.as_ptr() as *const ViewFuncData)
-    };
+    let data = ViewFuncData::read(module_name, memory_bytes, offset as usize, version)?;
 
     let head_element = get_raw_element(memory_bytes, &data, data.head_index)?;
 
-    return build_tree(module_name, memory_bytes, &data, &head_element);
+    let root = build_tree(module_name, memory_bytes, &data, head_element, images)?;
+
+    return Ok(ElementTree {
+        root,
+        generation: data.generation,
+    });
 }
 
+/// a tree read out of a module's view
+#[derive(Debug)]
+pub struct ElementTree {
+    pub root: WasmUiNode,
+    /// the generation the module gave the view, modules before abi version 2
+    /// don't have one
+    pub generation: Option<u32>,
+}
+
+/// builds the tree with an explicit stack instead of recursing, as the
+/// children are read from the module's memory and could be nested deep
+/// enough to overflow the stack, or even point back at their parents
+///
+/// fails once the tree goes over `max_tree_depth` or `max_tree_nodes`
 fn build_tree(
     module_name: &str,
     memory: &[u8],
     data: &ViewFuncData,
-    element: &RawElement,
+    head: RawElement,
+    images: &mut ImageCache,
 ) -> anyhow::Result<WasmUiNode> {
-    let element = match element.tag {
-        1 => {
-            let children = get_element_children(memory, &data, &element)?
-                .iter()
-                .map(|child| build_tree(module_name, memory, data, child))
-                .collect::<anyhow::Result<Vec<WasmUiNode>>>()?;
+    /// an element whose children are still being built
+    struct Frame {
+        element: RawElement,
+        /// children left to build, in reverse so the next is at the end
+        pending: Vec<RawElement>,
+        built: Vec<WasmUiNode>,
+    }
 
-            WasmUiNode::Row { children }
-        }
-        2 => {
-            let children = get_element_children(memory, &data, &element)?
-                .iter()
-                .map(|child| build_tree(module_name, memory, data, child))
-                .collect::<anyhow::Result<Vec<WasmUiNode>>>()?;
+    let limits = &config::get().modules;
+    let mut nodes = 0;
+    let mut stack: Vec<Frame> = vec![];
+    let mut next = Some(head);
 
-            WasmUiNode::Column { children }
-        }
-        3 => {
-            let text_content = {
-                // indexes to the RawTextData struct
-                // assuming its an array, using `element.data_index` to offset
-                // the ptr to the element
-                let offset = data.raw_text_data_ptr as usize
-                    + std::mem::size_of::<RawTextData>() * element.data_index as usize;
-                let end = offset + std::mem::size_of::<RawTextData>();
-
-                // note: consider turning this into a function where it returns
-                // the bytes if successful, otherwise, it doesn't
-                if offset >= memory.len() || end >= memory.len() {
-                    return Err(anyhow::anyhow!(
-                        "[wasm] [module:{}] RawTextData offsets out of bounds: {}-{}, memory \
-                         size: {}",
+    loop {
+        let node = match next.take() {
+            Some(element) => {
+                nodes += 1;
+                if nodes > limits.max_tree_nodes {
+                    return Err(anyhow!(
+                        "[wasm] [module:{}] ui tree has more than {} nodes",
                         module_name,
-                        offset,
-                        end,
-                        memory.len()
+                        limits.max_tree_nodes
+                    ));
+                }
+                // the head is at a depth of 1
+                if stack.len() + 1 > limits.max_tree_depth {
+                    return Err(anyhow!(
+                        "[wasm] [module:{}] ui tree is nested deeper than {} levels",
+                        module_name,
+                        limits.max_tree_depth
                     ));
                 }
 
-                let bytes = &memory[offset..end];
+                if has_children(element.tag) {
+                    let mut pending = get_element_children(memory, data, &element)?;
+                    pending.reverse();
 
-                let raw_text_data: RawTextData =
-                    unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const RawTextData) };
+                    stack.push(Frame {
+                        element,
+                        pending,
+                        built: vec![],
+                    });
+                    None
+                } else {
+                    Some(with_flags(
+                        &element,
+                        leaf_node(module_name, memory, data, &element, images)?,
+                    ))
+                }
+            }
+            None => match stack.last_mut() {
+                Some(frame) => match frame.pending.pop() {
+                    Some(child) => {
+                        next = Some(child);
+                        continue;
+                    }
+                    None => {
+                        let frame = stack.pop().expect("the stack has a last frame");
+                        let node =
+                            parent_node(module_name, memory, data, &frame.element, frame.built)?;
+                        Some(with_flags(&frame.element, node))
+                    }
+                },
+                None => {
+                    return Err(anyhow!(
+                        "[wasm] [module:{}] ui tree ended without a head element",
+                        module_name
+                    ));
+                }
+            },
+        };
 
-                let offset = raw_text_data.content_ptr as usize;
-                let len = raw_text_data.content_len as usize;
+        if let Some(node) = node {
+            match stack.last_mut() {
+                Some(parent) => parent.built.push(node),
+                None => return Ok(node),
+            }
+        }
+    }
+}
 
-                let bytes = &memory[offset..offset + len];
+/// true if elements with the tag can have children
+fn has_children(tag: u8) -> bool {
+    matches!(tag, 1 | 2 | 4 | 6 | 8 | 9 | 13 | 14 | 15)
+}
 
-                match str::from_utf8(bytes).ok() {
-                    Some(s) => s,
-                    None => {
+/// builds an element that can have children from its built children
+fn parent_node(
+    module_name: &str,
+    memory: &[u8],
+    data: &ViewFuncData,
+    element: &RawElement,
+    mut children: Vec<WasmUiNode>,
+) -> anyhow::Result<WasmUiNode> {
+    let node = match element.tag {
+        1 => WasmUiNode::Row {
+            children,
+            layout: read_layout(module_name, memory, data, element)?,
+        },
+        2 => WasmUiNode::Column {
+            children,
+            layout: read_layout(module_name, memory, data, element)?,
+        },
+        4 => {
+            if children.is_empty() {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] button has no inner element",
+                    module_name
+                ));
+            }
+
+            WasmUiNode::Button {
+                inner: Box::new(children.swap_remove(0)),
+                callback_id: element.callback_id,
+            }
+        }
+        6 => WasmUiNode::Stack { children },
+        8 => {
+            if children.is_empty() {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] long press has no inner element",
+                    module_name
+                ));
+            }
+
+            WasmUiNode::LongPress {
+                inner: Box::new(children.swap_remove(0)),
+                callback_id: element.callback_id,
+            }
+        }
+        9 => {
+            if children.is_empty() {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] container has no inner element",
+                    module_name
+                ));
+            }
+
+            let Some(container_data_ptr) = data.container_data_ptr else {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] containers need abi version 3",
+                    module_name
+                ));
+            };
+
+            let raw: RawContainerData =
+                match memory::guest_read_at(memory, container_data_ptr, element.data_index) {
+                    Ok(raw) => raw,
+                    Err(err) => {
+                        return Err(anyhow!(
+                            "[wasm] [module:{}] RawContainerData {}",
+                            module_name,
+                            err
+                        ));
+                    }
+                };
+
+            WasmUiNode::Container {
+                inner: Box::new(children.swap_remove(0)),
+                style: ContainerStyle::from_raw(raw),
+            }
+        }
+        13 => {
+            let Some(grid_data_ptr) = data.grid_data_ptr else {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] grids need abi version 6",
+                    module_name
+                ));
+            };
+
+            let raw: RawGridData =
+                match memory::guest_read_at(memory, grid_data_ptr, element.data_index) {
+                    Ok(raw) => raw,
+                    Err(err) => {
                         return Err(anyhow!(
-                            "failed to convert string from bytes:\nbytes = {:?}\nlossy string = \
-                             {:?}",
-                            bytes,
-                            String::from_utf8_lossy(bytes)
+                            "[wasm] [module:{}] RawGridData {}",
+                            module_name,
+                            err
                         ));
                     }
+                };
+
+            WasmUiNode::Grid {
+                children,
+                layout: GridLayout::from_raw(raw),
+                callback_id: element.callback_id,
+            }
+        }
+        14 => {
+            if children.is_empty() {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] tooltip has no inner element",
+                    module_name
+                ));
+            }
+
+            let Some(tooltip_data_ptr) = data.tooltip_data_ptr else {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] tooltips need abi version 8",
+                    module_name
+                ));
+            };
+
+            let raw: RawTooltipData =
+                match memory::guest_read_at(memory, tooltip_data_ptr, element.data_index) {
+                    Ok(raw) => raw,
+                    Err(err) => {
+                        return Err(anyhow!(
+                            "[wasm] [module:{}] RawTooltipData {}",
+                            module_name,
+                            err
+                        ));
+                    }
+                };
+
+            let start = raw.text_ptr as usize;
+            let Some(bytes) = memory.get(start..start + raw.text_len as usize) else {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] tooltip text out of bounds: {}-{}, memory size: {}",
+                    module_name,
+                    start,
+                    start + raw.text_len as usize,
+                    memory.len()
+                ));
+            };
+
+            let text = match str::from_utf8(bytes) {
+                Ok(text) => text.to_string(),
+                Err(err) => {
+                    return Err(anyhow!(
+                        "[wasm] [module:{}] tooltip text is not utf-8: {}",
+                        module_name,
+                        err
+                    ));
                 }
-                .to_string()
             };
 
-            let raw_style: RawTextStyle = {
+            WasmUiNode::Tooltip {
+                inner: Box::new(children.swap_remove(0)),
+                text,
+                position: TooltipPosition::from_raw(raw.position),
+            }
+        }
+        15 => {
+            if children.is_empty() {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] mouse area has no inner element",
+                    module_name
+                ));
+            }
+
+            let Some(mouse_area_data_ptr) = data.mouse_area_data_ptr else {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] mouse areas need abi version 9",
+                    module_name
+                ));
+            };
+
+            let raw: RawMouseAreaData =
+                match memory::guest_read_at(memory, mouse_area_data_ptr, element.data_index) {
+                    Ok(raw) => raw,
+                    Err(err) => {
+                        return Err(anyhow!(
+                            "[wasm] [module:{}] RawMouseAreaData {}",
+                            module_name,
+                            err
+                        ));
+                    }
+                };
+
+            WasmUiNode::MouseArea {
+                inner: Box::new(children.swap_remove(0)),
+                callbacks: MouseAreaCallbacks {
+                    on_enter: raw.on_enter,
+                    on_exit: raw.on_exit,
+                    on_right_press: raw.on_right_press,
+                    on_scroll: raw.on_scroll,
+                },
+            }
+        }
+        id => {
+            return Err(anyhow!(
+                "[wasm] [module:{}] tag unsupported: {}",
+                module_name,
+                id
+            ));
+        }
+    };
+
+    return Ok(node);
+}
+
+/// the layout of a row or column, modules before abi version 7 can't set
+/// one and get the default
+fn read_layout(
+    module_name: &str,
+    memory: &[u8],
+    data: &ViewFuncData,
+    element: &RawElement,
+) -> anyhow::Result<Layout> {
+    let Some(layout_data_ptr) = data.layout_data_ptr else {
+        return Ok(Layout::default());
+    };
+
+    let raw: RawLayoutData =
+        match memory::guest_read_at(memory, layout_data_ptr, element.data_index) {
+            Ok(raw) => raw,
+            Err(err) => {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] RawLayoutData {}",
+                    module_name,
+                    err
+                ));
+            }
+        };
+
+    return Ok(Layout::from_raw(raw));
+}
+
+/// builds an element that can't have children
+fn leaf_node(
+    module_name: &str,
+    memory: &[u8],
+    data: &ViewFuncData,
+    element: &RawElement,
+    images: &mut ImageCache,
+) -> anyhow::Result<WasmUiNode> {
+    let element = match element.tag {
+        3 => {
+            let text_content = {
                 // indexes to the RawTextData struct
                 // assuming its an array, using `element.data_index` to offset
                 // the ptr to the element
-                let offset = data.text_style_ptr as usize + std::mem::size_of::<RawTextStyle>();
-                let end = offset + std::mem::size_of::<RawTextStyle>();
+                let raw_text_data: RawTextData =
+                    match memory::guest_read_at(memory, data.raw_text_data_ptr, element.data_index)
+                    {
+                        Ok(raw) => raw,
+                        Err(err) => {
+                            return Err(anyhow!(
+                                "[wasm] [module:{}] RawTextData {}",
+                                module_name,
+                                err
+                            ));
+                        }
+                    };
 
-                if offset >= memory.len() || end >= memory.len() {
-                    return Err(anyhow::anyhow!(
-                        "[wasm] [module:{}] RawTextData offsets out of bounds: {}-{}, memory \
-                         size: {}",
-                        module_name,
-                        offset,
-                        end,
-                        memory.len()
-                    ));
+                match memory::guest_str(
+                    memory,
+                    raw_text_data.content_ptr as usize,
+                    raw_text_data.content_len as usize,
+                ) {
+                    Ok(content) => content.to_string(),
+                    Err(err) => {
+                        return Err(anyhow!("[wasm] [module:{}] text {}", module_name, err));
+                    }
                 }
+            };
 
-                let bytes = &[offset..end];
-
-                unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const RawTextStyle) }
+            // `style_index` starts at 1 for text, 0 is unstyled. modules
+            // before abi version 3 wrote styles the host never read
+            let index = match data.version >= AbiVersion::V3 {
+                true => element.style_index.checked_sub(1),
+                false => None,
             };
+            let style = match index {
+                Some(index) => {
+                    let raw_style: RawTextStyle =
+                        match memory::guest_read_at(memory, data.text_style_ptr, index) {
+                            Ok(raw) => raw,
+                            Err(err) => {
+                                return Err(anyhow!(
+                                    "[wasm] [module:{}] RawTextStyle {}",
+                                    module_name,
+                                    err
+                                ));
+                            }
+                        };
 
-            let style = text::Style {
-                color: Some(Color::from_rgb(1.0, 1.0, 1.0)),
+                    TextStyle::from_raw(raw_style)
+                }
+                None => TextStyle::default(),
             };
 
             WasmUiNode::Text {
                 content: text_content,
                 style,
-            }
-        }
-        4 => {
-            let inner_element = get_element_children(memory, &data, &element)?
-                .iter()
-                .map(|child| build_tree(module_name, memory, data, child))
-                .collect::<anyhow::Result<Vec<WasmUiNode>>>()?[0]
-                .clone();
-
-            WasmUiNode::Button {
-                inner: Box::new(inner_element),
-                callback_id: element.callback_id,
+                vertical: element.flags & VERTICAL_FLAG != 0,
             }
         }
         5 => {
-            let slider_data = {
-                // indexes into the start of a RawSliderData element
-                let offset = data.raw_slider_data_ptr as usize
-                    + std::mem::size_of::<RawSliderData>() * element.data_index as usize;
-                let end = offset + std::mem::size_of::<RawSliderData>();
-
-                let bytes = &memory[offset..end];
-
-                unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const RawSliderData) }
-            };
+            let slider_data: RawSliderData =
+                match memory::guest_read_at(memory, data.raw_slider_data_ptr, element.data_index) {
+                    Ok(raw) => raw,
+                    Err(err) => {
+                        return Err(anyhow!(
+                            "[wasm] [module:{}] RawSliderData {}",
+                            module_name,
+                            err
+                        ));
+                    }
+                };
 
             let number_type = match slider_data.number_type {
                 0 => SliderNumberType::I32,
@@ -177,15 +500,175 @@ fn build_tree(
                 range: slider_data.range_min..=slider_data.range_max,
                 value: slider_data.value,
                 callback_id: element.callback_id,
+                vertical: element.flags & VERTICAL_FLAG != 0,
+            }
+        }
+        // the slot is all a tray icon has, so it's kept in `data_index`
+        7 => WasmUiNode::TrayImage {
+            slot: element.data_index,
+            callback_id: element.callback_id,
+        },
+        // same for whether a toggler is on
+        16 => WasmUiNode::Toggler {
+            is_toggled: element.data_index != 0,
+            callback_id: element.callback_id,
+        },
+        10 => {
+            let Some(image_data_ptr) = data.image_data_ptr else {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] images need abi version 4",
+                    module_name
+                ));
+            };
+
+            let raw: RawImageData =
+                match memory::guest_read_at(memory, image_data_ptr, element.data_index) {
+                    Ok(raw) => raw,
+                    Err(err) => {
+                        return Err(anyhow!(
+                            "[wasm] [module:{}] RawImageData {}",
+                            module_name,
+                            err
+                        ));
+                    }
+                };
+
+            let start = raw.source_ptr as usize;
+            let source = match memory.get(start..start + raw.source_len as usize) {
+                Some(source) => source,
+                None => {
+                    return Err(anyhow!(
+                        "[wasm] [module:{}] image source out of bounds: {}-{}, memory size: {}",
+                        module_name,
+                        start,
+                        start + raw.source_len as usize,
+                        memory.len()
+                    ));
+                }
+            };
+
+            // 0 is the image's own size
+            let size = |value: f32| (value.is_finite() && value > 0.0).then_some(value);
+            let width = size(raw.width);
+            let height = size(raw.height);
+
+            let image = match raw.kind {
+                0 => match str::from_utf8(source) {
+                    Ok(name) => images.icon(name, width.or(height)),
+                    Err(err) => {
+                        return Err(anyhow!(
+                            "[wasm] [module:{}] icon name is not utf-8: {}",
+                            module_name,
+                            err
+                        ));
+                    }
+                },
+                1 => images.png(source),
+                2 => match str::from_utf8(source) {
+                    Ok(path) => images.screenshot(path),
+                    Err(err) => {
+                        return Err(anyhow!(
+                            "[wasm] [module:{}] screenshot path is not utf-8: {}",
+                            module_name,
+                            err
+                        ));
+                    }
+                },
+                kind => {
+                    return Err(anyhow!(
+                        "[wasm] [module:{}] image kind unsupported: {}",
+                        module_name,
+                        kind
+                    ));
+                }
+            };
+
+            WasmUiNode::Image {
+                image,
+                width,
+                height,
+            }
+        }
+        12 => {
+            let Some(text_input_data_ptr) = data.text_input_data_ptr else {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] text inputs need abi version 6",
+                    module_name
+                ));
+            };
+
+            let raw: RawTextInputData =
+                match memory::guest_read_at(memory, text_input_data_ptr, element.data_index) {
+                    Ok(raw) => raw,
+                    Err(err) => {
+                        return Err(anyhow!(
+                            "[wasm] [module:{}] RawTextInputData {}",
+                            module_name,
+                            err
+                        ));
+                    }
+                };
+
+            let string = |ptr: u32, len: u32, what: &str| -> anyhow::Result<String> {
+                let start = ptr as usize;
+                let Some(bytes) = memory.get(start..start + len as usize) else {
+                    return Err(anyhow!(
+                        "[wasm] [module:{}] text input {} out of bounds: {}-{}, memory size: {}",
+                        module_name,
+                        what,
+                        start,
+                        start + len as usize,
+                        memory.len()
+                    ));
+                };
+
+                match str::from_utf8(bytes) {
+                    Ok(string) => Ok(string.to_string()),
+                    Err(err) => Err(anyhow!(
+                        "[wasm] [module:{}] text input {} is not utf-8: {}",
+                        module_name,
+                        what,
+                        err
+                    )),
+                }
+            };
+
+            // 0 is unset for both
+            let size = |value: f32| (value.is_finite() && value > 0.0).then_some(value);
+
+            WasmUiNode::TextInput {
+                value: string(raw.value_ptr, raw.value_len, "value")?,
+                placeholder: string(raw.placeholder_ptr, raw.placeholder_len, "placeholder")?,
+                width: size(raw.width),
+                size: size(raw.size),
+                callback_id: element.callback_id,
             }
         }
-        6 => {
-            let children = get_element_children(memory, &data, &element)?
-                .iter()
-                .map(|child| build_tree(module_name, memory, data, child))
-                .collect::<anyhow::Result<Vec<WasmUiNode>>>()?;
+        11 => {
+            let Some(progress_bar_data_ptr) = data.progress_bar_data_ptr else {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] progress bars need abi version 5",
+                    module_name
+                ));
+            };
+
+            let raw: RawProgressBarData =
+                match memory::guest_read_at(memory, progress_bar_data_ptr, element.data_index) {
+                    Ok(raw) => raw,
+                    Err(err) => {
+                        return Err(anyhow!(
+                            "[wasm] [module:{}] RawProgressBarData {}",
+                            module_name,
+                            err
+                        ));
+                    }
+                };
 
-            WasmUiNode::Stack { children }
+            WasmUiNode::ProgressBar {
+                filled: ProgressBarStyle::filled(&raw),
+                style: ProgressBarStyle::from_raw(raw),
+                vertical: element.flags & VERTICAL_FLAG != 0,
+            }
         }
         id => {
             return Err(anyhow!(
@@ -199,28 +682,26 @@ fn build_tree(
     return Ok(element);
 }
 
+/// applies `RawElement::flags` to a built node
+fn with_flags(element: &RawElement, node: WasmUiNode) -> WasmUiNode {
+    if element.flags & STATIC_FLAG != 0 {
+        return WasmUiNode::Static {
+            key: node.fingerprint(),
+            child: Arc::new(node),
+        };
+    }
+
+    return node;
+}
+
 /// gets the raw element from the wasm module's memory
 ///
 /// will error if the offset provides ends up out of bounds
 fn get_raw_element(memory: &[u8], data: &ViewFuncData, index: u32) -> anyhow::Result<RawElement> {
-    let offset = data.elements_ptr as usize + std::mem::size_of::<RawElement>() * index as usize;
-    let end = offset + std::mem::size_of::<RawElement>();
-
-    if offset >= memory.len() || end >= memory.len() {
-        return Err(anyhow::anyhow!(
-            "[wasm] get_raw_element: offsets out of bounds: {}-{}, memory size: {}",
-            offset,
-            end,
-            memory.len()
-        ));
-    }
-
-    let bytes = &memory[offset..end];
-
-    let element: RawElement =
-        unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const RawElement) };
-
-    return Ok(element);
+    return match memory::guest_read_at(memory, data.elements_ptr, index) {
+        Ok(element) => Ok(element),
+        Err(err) => Err(anyhow!("[wasm] get_raw_element: {}", err)),
+    };
 }
 
 /// gets an element's children from the wasm module's memory
@@ -239,49 +720,24 @@ fn get_element_children(
         // i think i'll forget all of this so:
         // this part gets the 4 bytes that make up the offset in wasm memory
         // to the actual children vector of the element that we want
-        let offset = (data.children_ptr
-            + std::mem::size_of::<u32>() as u32 * element.children_index)
-            as usize;
-        let end = offset + std::mem::size_of::<u32>();
-
-        if offset >= memory.len() || end >= memory.len() {
-            return Err(anyhow::anyhow!(
-                "[wasm] get_element_children: offsets out of bounds: {}-{}, memory size: {}",
-                offset,
-                end,
-                memory.len()
-            ));
-        }
-
-        let bytes = &memory[offset..end];
+        let ptr = match memory::guest_read_at::<[u8; 4]>(
+            memory,
+            data.children_ptr,
+            element.children_index,
+        ) {
+            Ok(bytes) => u32::from_le_bytes(bytes),
+            Err(err) => return Err(anyhow!("[wasm] get_element_children: {}", err)),
+        };
 
-        // and this offset is the offset in wasm memory to that children vector
-        let offset =
-            u32::from_le_bytes(bytes.try_into().expect("no clue how its not 4 bytes :3")) as usize;
-        let len = element.child_count as usize;
-        // need to use u32 as usize can be 64 bit on the wasm host
-        let end = offset + (std::mem::size_of::<u32>() * len);
-
-        if offset >= memory.len() || end >= memory.len() {
-            return Err(anyhow::anyhow!(
-                "[wasm] get_element_children: offsets out of bounds: {}-{}, memory size: {}",
-                offset,
-                end,
-                memory.len()
-            ));
+        // and this is the children vector itself, the indexes of the
+        // elements in it
+        match memory::guest_read_slice::<[u8; 4]>(memory, ptr, element.child_count as u32) {
+            Ok(indexes) => indexes
+                .into_iter()
+                .map(u32::from_le_bytes)
+                .collect::<Vec<u32>>(),
+            Err(err) => return Err(anyhow!("[wasm] get_element_children: {}", err)),
         }
-        let bytes = &memory[offset..end];
-
-        bytes
-            .chunks(4)
-            .map(|bytes| {
-                u32::from_le_bytes(
-                    bytes
-                        .try_into()
-                        .expect("it was supposed to be exactly 4 bytes :p"),
-                )
-            })
-            .collect::<Vec<u32>>()
     };
 
     return indexes
@@ -294,13 +750,17 @@ fn get_element_children(
 pub enum WasmUiNode {
     Row {
         children: Vec<WasmUiNode>,
+        layout: Layout,
     },
     Column {
         children: Vec<WasmUiNode>,
+        layout: Layout,
     },
     Text {
         content: String,
-        style: text::Style,
+        style: TextStyle,
+        /// the letters are stacked top to bottom
+        vertical: bool,
     },
     Button {
         inner: Box<WasmUiNode>,
@@ -311,10 +771,592 @@ pub enum WasmUiNode {
         range: RangeInclusive<u64>,
         value: u64,
         callback_id: u32,
+        /// slides up and down instead of left and right
+        vertical: bool,
     },
     Stack {
         children: Vec<WasmUiNode>,
     },
+    /// calls back when a finger is held on `inner`, a tap or drag still
+    /// goes to `inner`
+    LongPress {
+        inner: Box<WasmUiNode>,
+        callback_id: u32,
+    },
+    /// draws a background and border around `inner`
+    Container {
+        inner: Box<WasmUiNode>,
+        style: ContainerStyle,
+    },
+    /// an icon or png, drawn at its own size unless the module gave one
+    Image {
+        image: ModuleImage,
+        width: Option<f32>,
+        height: Option<f32>,
+    },
+    /// a bar filled up to how far its value is through its range
+    ProgressBar {
+        /// 0 to 1
+        filled: f32,
+        style: ProgressBarStyle,
+        /// fills from the bottom up instead of left to right
+        vertical: bool,
+    },
+    /// a box to type into, calls back with the whole text on every change
+    TextInput {
+        value: String,
+        placeholder: String,
+        /// in logical pixels, filling the space it's given when `None`
+        width: Option<f32>,
+        /// the size of the text in logical pixels
+        size: Option<f32>,
+        callback_id: u32,
+    },
+    /// cells laid out in rows of `layout.columns` inside a scrollable, the
+    /// module only sends the cells from `layout.first` that can be seen and
+    /// the host leaves room for the rest. calls back with the first item
+    /// that can be seen when it's scrolled
+    Grid {
+        children: Vec<WasmUiNode>,
+        layout: GridLayout,
+        callback_id: u32,
+    },
+    /// shows `text` next to `inner` while the pointer is over it
+    Tooltip {
+        inner: Box<WasmUiNode>,
+        text: String,
+        position: TooltipPosition,
+    },
+    /// calls back when the pointer goes over or leaves `inner`, when it's
+    /// right clicked and when it's scrolled over, left clicks still go to
+    /// `inner`
+    MouseArea {
+        inner: Box<WasmUiNode>,
+        callbacks: MouseAreaCallbacks,
+    },
+    /// the icon of the item in the tray at `slot`, drawn as nothing when
+    /// there aren't that many items
+    TrayImage {
+        slot: u32,
+        callback_id: u32,
+    },
+    /// a switch that's on or off, calls back with whether it's on after it
+    /// was flipped
+    Toggler {
+        is_toggled: bool,
+        callback_id: u32,
+    },
+    /// a subtree the module marked as static, the host reuses the widgets
+    /// built for it while `key` stays the same
+    Static {
+        /// a hash of `child`, changes whenever anything in it does
+        key: u64,
+        child: Arc<WasmUiNode>,
+    },
+}
+
+impl WasmUiNode {
+    /// roughly how many bytes the tree takes up on the host
+    pub fn size_bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + self.heap_bytes()
+    }
+
+    /// bytes allocated by the node outside of its own `size_of`
+    fn heap_bytes(&self) -> usize {
+        match self {
+            WasmUiNode::Row { children, .. }
+            | WasmUiNode::Column { children, .. }
+            | WasmUiNode::Stack { children }
+            | WasmUiNode::Grid { children, .. } => {
+                children.capacity() * std::mem::size_of::<Self>()
+                    + children.iter().map(Self::heap_bytes).sum::<usize>()
+            }
+            WasmUiNode::Text { content, .. } => content.capacity(),
+            WasmUiNode::TextInput {
+                value, placeholder, ..
+            } => value.capacity() + placeholder.capacity(),
+            WasmUiNode::Button { inner, .. }
+            | WasmUiNode::LongPress { inner, .. }
+            | WasmUiNode::Container { inner, .. } => inner.size_bytes(),
+            WasmUiNode::Tooltip { inner, text, .. } => inner.size_bytes() + text.capacity(),
+            WasmUiNode::MouseArea { inner, .. } => inner.size_bytes(),
+            WasmUiNode::Image { image, .. } => match image {
+                ModuleImage::File(path) => path.capacity(),
+                // the cache keeps one copy of the bytes, shared with the
+                // handle, which the module is still made to pay for
+                ModuleImage::Png { size, .. } => *size,
+                ModuleImage::Missing => 0,
+            },
+            WasmUiNode::Slider { .. }
+            | WasmUiNode::ProgressBar { .. }
+            | WasmUiNode::TrayImage { .. }
+            | WasmUiNode::Toggler { .. } => 0,
+            // the arc's two reference counts are stored with the child
+            WasmUiNode::Static { child, .. } => {
+                2 * std::mem::size_of::<usize>() + child.size_bytes()
+            }
+        }
+    }
+
+    /// a hash of the whole tree, used to tell if a static subtree changed
+    /// between views
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash_into(&mut hasher);
+        return hasher.finish();
+    }
+
+    fn hash_into(&self, hasher: &mut DefaultHasher) {
+        std::mem::discriminant(self).hash(hasher);
+
+        match self {
+            WasmUiNode::Row { children, layout } | WasmUiNode::Column { children, layout } => {
+                layout.hash_into(hasher);
+                children.len().hash(hasher);
+                for child in children {
+                    child.hash_into(hasher);
+                }
+            }
+            WasmUiNode::Stack { children } => {
+                children.len().hash(hasher);
+                for child in children {
+                    child.hash_into(hasher);
+                }
+            }
+            WasmUiNode::Text {
+                content,
+                style,
+                vertical,
+            } => {
+                content.hash(hasher);
+                vertical.hash(hasher);
+                style.color.hash(hasher);
+                // floats can't be hashed directly
+                style.size.map(f32::to_bits).hash(hasher);
+                style.font.hash(hasher);
+            }
+            WasmUiNode::Button { inner, callback_id }
+            | WasmUiNode::LongPress { inner, callback_id } => {
+                callback_id.hash(hasher);
+                inner.hash_into(hasher);
+            }
+            WasmUiNode::Container { inner, style } => {
+                // floats can't be hashed directly
+                style.padding.map(f32::to_bits).hash(hasher);
+                style.background.hash(hasher);
+                style.border_width.to_bits().hash(hasher);
+                style.border_radius.to_bits().hash(hasher);
+                style.border_color.hash(hasher);
+                inner.hash_into(hasher);
+            }
+            WasmUiNode::Slider {
+                number_type,
+                range,
+                value,
+                callback_id,
+                vertical,
+            } => {
+                std::mem::discriminant(number_type).hash(hasher);
+                range.hash(hasher);
+                value.hash(hasher);
+                callback_id.hash(hasher);
+                vertical.hash(hasher);
+            }
+            WasmUiNode::Image {
+                image,
+                width,
+                height,
+            } => {
+                std::mem::discriminant(image).hash(hasher);
+                match image {
+                    ModuleImage::File(path) => path.hash(hasher),
+                    // the same bytes get the same handle from the cache
+                    ModuleImage::Png { handle, .. } => handle.id().hash(hasher),
+                    ModuleImage::Missing => {}
+                }
+                width.map(f32::to_bits).hash(hasher);
+                height.map(f32::to_bits).hash(hasher);
+            }
+            WasmUiNode::TextInput {
+                value,
+                placeholder,
+                width,
+                size,
+                callback_id,
+            } => {
+                value.hash(hasher);
+                placeholder.hash(hasher);
+                // floats can't be hashed directly
+                width.map(f32::to_bits).hash(hasher);
+                size.map(f32::to_bits).hash(hasher);
+                callback_id.hash(hasher);
+            }
+            WasmUiNode::Grid {
+                children,
+                layout,
+                callback_id,
+            } => {
+                layout.columns.hash(hasher);
+                layout.total.hash(hasher);
+                layout.first.hash(hasher);
+                // floats can't be hashed directly
+                layout.cell_width.to_bits().hash(hasher);
+                layout.cell_height.to_bits().hash(hasher);
+                layout.height.map(f32::to_bits).hash(hasher);
+                callback_id.hash(hasher);
+                children.len().hash(hasher);
+                for child in children {
+                    child.hash_into(hasher);
+                }
+            }
+            WasmUiNode::ProgressBar {
+                filled,
+                style,
+                vertical,
+            } => {
+                // floats can't be hashed directly
+                filled.to_bits().hash(hasher);
+                style.length.map(f32::to_bits).hash(hasher);
+                style.thickness.to_bits().hash(hasher);
+                style.bar.hash(hasher);
+                style.background.hash(hasher);
+                vertical.hash(hasher);
+            }
+            WasmUiNode::Tooltip {
+                inner,
+                text,
+                position,
+            } => {
+                text.hash(hasher);
+                position.hash(hasher);
+                inner.hash_into(hasher);
+            }
+            WasmUiNode::MouseArea { inner, callbacks } => {
+                callbacks.hash(hasher);
+                inner.hash_into(hasher);
+            }
+            WasmUiNode::TrayImage { slot, callback_id } => {
+                slot.hash(hasher);
+                callback_id.hash(hasher);
+            }
+            WasmUiNode::Toggler {
+                is_toggled,
+                callback_id,
+            } => {
+                is_toggled.hash(hasher);
+                callback_id.hash(hasher);
+            }
+            // the child was hashed when the key was made
+            WasmUiNode::Static { key, .. } => key.hash(hasher),
+        }
+    }
+}
+
+/// how a module asked for its text to look, anything it didn't set is left
+/// to the shell
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextStyle {
+    pub color: Option<ModuleColor>,
+    /// in logical pixels
+    pub size: Option<f32>,
+    pub font: TextFont,
+}
+
+/// a color from the theme, so module text follows `colors.toml`, or one the
+/// module picked itself
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ModuleColor {
+    /// `color00` to `color15`
+    Base16(u8),
+    Foreground,
+    Background,
+    Rgba([u8; 4]),
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
+pub struct TextFont {
+    pub family: TextFontFamily,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
+pub enum TextFontFamily {
+    /// the font the shell's own widgets use
+    #[default]
+    Shell,
+    SansSerif,
+    Serif,
+    Monospace,
+}
+
+impl ModuleColor {
+    /// the guest's `Color` as a u8, with `custom` used for `Color::Custom`
+    fn from_raw(color: u8, custom: u32) -> Option<Self> {
+        match color {
+            // the module's `Color01` is `color00`
+            index @ 1..=16 => Some(Self::Base16(index - 1)),
+            17 => Some(Self::Foreground),
+            18 => Some(Self::Background),
+            19 => Some(Self::Rgba(custom.to_be_bytes())),
+            _ => None,
+        }
+    }
+}
+
+/// an image a module drew, found or decoded through its `ImageCache`
+#[derive(Debug, Clone)]
+pub enum ModuleImage {
+    /// an icon file found from the name the module gave
+    File(PathBuf),
+    /// a png the module sent, `size` is how many bytes it is
+    Png { handle: image::Handle, size: usize },
+    /// there's no icon with the name the module gave
+    Missing,
+}
+
+/// how a module asked for a container to look
+#[derive(Debug, Clone, Copy)]
+pub struct ContainerStyle {
+    /// top, right, bottom and left in logical pixels
+    pub padding: [f32; 4],
+    pub background: Option<ModuleColor>,
+    pub border_width: f32,
+    pub border_radius: f32,
+    /// the theme's foreground when `None`
+    pub border_color: Option<ModuleColor>,
+}
+
+impl ContainerStyle {
+    fn from_raw(raw: RawContainerData) -> Self {
+        // what the module sent is only trusted to be a float
+        let positive = |value: f32| match value.is_finite() {
+            true => value.max(0.0),
+            false => 0.0,
+        };
+
+        Self {
+            padding: raw.padding.map(positive),
+            background: ModuleColor::from_raw(raw.background, raw.background_custom),
+            border_width: positive(raw.border_width),
+            border_radius: positive(raw.border_radius),
+            border_color: ModuleColor::from_raw(raw.border_color, raw.border_custom),
+        }
+    }
+}
+
+/// how a module asked for a progress bar to look
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressBarStyle {
+    /// in logical pixels, filling the space it's given when `None`
+    pub length: Option<f32>,
+    /// in logical pixels
+    pub thickness: f32,
+    /// the theme's foreground when `None`
+    pub bar: Option<ModuleColor>,
+    /// `color02` from the theme when `None`
+    pub background: Option<ModuleColor>,
+}
+
+impl ProgressBarStyle {
+    /// how thick a bar is when the module didn't say
+    const DEFAULT_THICKNESS: f32 = 6.0;
+
+    fn from_raw(raw: RawProgressBarData) -> Self {
+        // 0 is unset, and what the module sent is only trusted to be a float
+        let size = |value: f32| (value.is_finite() && value > 0.0).then_some(value);
+
+        Self {
+            length: size(raw.length),
+            thickness: size(raw.thickness).unwrap_or(Self::DEFAULT_THICKNESS),
+            bar: ModuleColor::from_raw(raw.bar, raw.bar_custom),
+            background: ModuleColor::from_raw(raw.background, raw.background_custom),
+        }
+    }
+
+    /// how far the value is through the range, an empty or backwards range
+    /// is never filled
+    fn filled(raw: &RawProgressBarData) -> f32 {
+        let filled = (raw.value - raw.min) / (raw.max - raw.min);
+        match filled.is_finite() && raw.max > raw.min {
+            true => filled.clamp(0.0, 1.0),
+            false => 0.0,
+        }
+    }
+}
+
+/// where a grid's cells go
+#[derive(Debug, Clone, Copy)]
+pub struct GridLayout {
+    /// at least 1
+    pub columns: u32,
+    /// how many items there are, not just the ones the module sent
+    pub total: u32,
+    /// the item the first cell the module sent is for, always at the start
+    /// of a row
+    pub first: u32,
+    /// in logical pixels
+    pub cell_width: f32,
+    pub cell_height: f32,
+    /// in logical pixels, filling the space it's given when `None`
+    pub height: Option<f32>,
+}
+
+impl GridLayout {
+    /// how big cells are when the module didn't say
+    const DEFAULT_CELL_SIZE: f32 = 32.0;
+
+    fn from_raw(raw: RawGridData) -> Self {
+        // 0 is unset, and what the module sent is only trusted to be a float
+        let size = |value: f32| (value.is_finite() && value > 0.0).then_some(value);
+        let columns = raw.columns.max(1);
+
+        Self {
+            columns,
+            total: raw.total,
+            first: raw.first.min(raw.total) / columns * columns,
+            cell_width: size(raw.cell_width).unwrap_or(Self::DEFAULT_CELL_SIZE),
+            cell_height: size(raw.cell_height).unwrap_or(Self::DEFAULT_CELL_SIZE),
+            height: size(raw.height),
+        }
+    }
+}
+
+/// how a row or column lays out its children, the default is what rows and
+/// columns looked like before modules could set it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Layout {
+    /// top, right, bottom and left in logical pixels
+    pub padding: [f32; 4],
+    /// the space between children in logical pixels
+    pub spacing: f32,
+    pub width: ModuleLength,
+    pub height: ModuleLength,
+    /// where children go across the row or column, up and down in a row
+    /// and left and right in a column
+    pub align: ModuleAlignment,
+}
+
+/// how much space a row or column takes up
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ModuleLength {
+    /// only as much as its children need
+    #[default]
+    Shrink,
+    Fill,
+    /// a share of the space left, next to others filling it
+    FillPortion(u16),
+    /// in logical pixels
+    Fixed(f32),
+}
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum ModuleAlignment {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+/// the callback ids of a mouse area, 0 is no callback
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct MouseAreaCallbacks {
+    pub on_enter: u32,
+    pub on_exit: u32,
+    pub on_right_press: u32,
+    /// called with `WasmCallbackData::Scroll`
+    pub on_scroll: u32,
+}
+
+/// where a tooltip shows up next to the element it's on
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum TooltipPosition {
+    Top,
+    #[default]
+    Bottom,
+    Left,
+    Right,
+    /// next to the pointer, moving with it
+    FollowCursor,
+}
+
+impl TooltipPosition {
+    fn from_raw(position: u8) -> Self {
+        match position {
+            0 => Self::Top,
+            2 => Self::Left,
+            3 => Self::Right,
+            4 => Self::FollowCursor,
+            _ => Self::Bottom,
+        }
+    }
+}
+
+impl Layout {
+    fn from_raw(raw: RawLayoutData) -> Self {
+        // what the module sent is only trusted to be a float
+        let positive = |value: f32| match value.is_finite() {
+            true => value.max(0.0),
+            false => 0.0,
+        };
+
+        let length = |kind: u8, value: f32| match kind {
+            1 => ModuleLength::Fill,
+            2 => ModuleLength::FillPortion(positive(value).clamp(1.0, u16::MAX as f32) as u16),
+            3 => ModuleLength::Fixed(positive(value)),
+            _ => ModuleLength::Shrink,
+        };
+
+        Self {
+            padding: raw.padding.map(positive),
+            spacing: positive(raw.spacing),
+            width: length(raw.width_kind, raw.width),
+            height: length(raw.height_kind, raw.height),
+            align: match raw.align {
+                1 => ModuleAlignment::Center,
+                2 => ModuleAlignment::End,
+                _ => ModuleAlignment::Start,
+            },
+        }
+    }
+
+    fn hash_into(&self, hasher: &mut DefaultHasher) {
+        // floats can't be hashed directly
+        self.padding.map(f32::to_bits).hash(hasher);
+        self.spacing.to_bits().hash(hasher);
+        for length in [self.width, self.height] {
+            std::mem::discriminant(&length).hash(hasher);
+            match length {
+                ModuleLength::FillPortion(portion) => portion.hash(hasher),
+                ModuleLength::Fixed(pixels) => pixels.to_bits().hash(hasher),
+                ModuleLength::Shrink | ModuleLength::Fill => {}
+            }
+        }
+        self.align.hash(hasher);
+    }
+}
+
+impl TextStyle {
+    fn from_raw(raw: RawTextStyle) -> Self {
+        let color = ModuleColor::from_raw(raw.text_color, raw.custom_color);
+
+        let family = match raw.font_family {
+            1 => TextFontFamily::SansSerif,
+            2 => TextFontFamily::Serif,
+            3 => TextFontFamily::Monospace,
+            _ => TextFontFamily::Shell,
+        };
+
+        Self {
+            color,
+            size: (raw.size.is_finite() && raw.size > 0.0).then_some(raw.size),
+            font: TextFont {
+                family,
+                bold: raw.font_flags & TEXT_BOLD_FLAG != 0,
+                italic: raw.font_flags & TEXT_ITALIC_FLAG != 0,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -325,7 +1367,9 @@ pub enum SliderNumberType {
 }
 
 /// data that a module's `view()` function is expected to return
-#[repr(C)]
+///
+/// from abi version 1 it's prefixed with the version it was written for,
+/// each field is a little endian u32
 #[derive(Debug, Clone, Copy)]
 struct ViewFuncData {
     pub head_index: u32,
@@ -334,6 +1378,112 @@ struct ViewFuncData {
     pub raw_text_data_ptr: u32,
     pub text_style_ptr: u32,
     pub raw_slider_data_ptr: u32,
+    /// only sent from abi version 2
+    pub generation: Option<u32>,
+    /// only sent from abi version 3
+    pub container_data_ptr: Option<u32>,
+    /// only sent from abi version 4
+    pub image_data_ptr: Option<u32>,
+    /// only sent from abi version 5
+    pub progress_bar_data_ptr: Option<u32>,
+    /// only sent from abi version 6
+    pub text_input_data_ptr: Option<u32>,
+    /// only sent from abi version 6
+    pub grid_data_ptr: Option<u32>,
+    /// only sent from abi version 7
+    pub layout_data_ptr: Option<u32>,
+    /// only sent from abi version 8
+    pub tooltip_data_ptr: Option<u32>,
+    /// only sent from abi version 9
+    pub mouse_area_data_ptr: Option<u32>,
+    /// the version the module wrote the data in
+    pub version: AbiVersion,
+}
+
+impl ViewFuncData {
+    /// the amount of u32 fields after the header
+    const FIELDS: usize = 6;
+
+    fn read(
+        module_name: &str,
+        memory: &[u8],
+        offset: usize,
+        version: AbiVersion,
+    ) -> anyhow::Result<Self> {
+        // the amount of u32s before the fields
+        let header = match version {
+            AbiVersion::V0 => 0,
+            AbiVersion::V1
+            | AbiVersion::V2
+            | AbiVersion::V3
+            | AbiVersion::V4
+            | AbiVersion::V5
+            | AbiVersion::V6
+            | AbiVersion::V7
+            | AbiVersion::V8
+            | AbiVersion::V9
+            | AbiVersion::V10
+            | AbiVersion::V11
+            | AbiVersion::V12 => 1,
+        };
+        let count = Self::FIELDS
+            + version.has_generations() as usize
+            + version.has_containers() as usize
+            + version.has_images() as usize
+            + version.has_progress_bars() as usize
+            + 2 * version.has_inputs_and_grids() as usize
+            + version.has_layouts() as usize
+            + version.has_tooltips() as usize
+            + version.has_mouse_areas() as usize;
+        let end = offset + (header + count) * std::mem::size_of::<u32>();
+
+        if end > memory.len() {
+            return Err(anyhow!(
+                "[wasm] [module:{}] ViewFuncData offsets out of bounds: {}-{}, memory size: {}",
+                module_name,
+                offset,
+                end,
+                memory.len()
+            ));
+        }
+
+        let mut fields = memory[offset..end]
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        let mut next = || fields.next().unwrap_or_default();
+
+        if header != 0 {
+            let written_for = next();
+            if AbiVersion::try_from(written_for).ok() != Some(version) {
+                return Err(anyhow!(
+                    "[wasm] [module:{}] module said it uses abi version {} but its view data is \
+                     version {}",
+                    module_name,
+                    version,
+                    written_for
+                ));
+            }
+        }
+
+        Ok(Self {
+            head_index: next(),
+            elements_ptr: next(),
+            children_ptr: next(),
+            raw_text_data_ptr: next(),
+            text_style_ptr: next(),
+            raw_slider_data_ptr: next(),
+            generation: version.has_generations().then(&mut next),
+            container_data_ptr: version.has_containers().then(&mut next),
+            image_data_ptr: version.has_images().then(&mut next),
+            progress_bar_data_ptr: version.has_progress_bars().then(&mut next),
+            text_input_data_ptr: version.has_inputs_and_grids().then(&mut next),
+            grid_data_ptr: version.has_inputs_and_grids().then(&mut next),
+            layout_data_ptr: version.has_layouts().then(&mut next),
+            tooltip_data_ptr: version.has_tooltips().then(&mut next),
+            mouse_area_data_ptr: version.has_mouse_areas().then(next),
+            version,
+        })
+    }
 }
 
 #[repr(C)]
@@ -348,6 +1498,10 @@ struct RawElement {
     ///
     /// if its greater than 0 on elements that aren't, thats a bug
     pub child_count: u8,
+    /// bitflags about the element, see `STATIC_FLAG`
+    ///
+    /// sits in what was padding, so modules built before it existed send 0
+    pub flags: u8,
     /// the index into the memory arena of the module
     ///
     /// 0 is a valid index and doesn't mean none
@@ -384,7 +1538,129 @@ struct RawTextData {
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 struct RawTextStyle {
+    /// 0 is unset, 1 to 16 are `color00` to `color15`, 17 the foreground,
+    /// 18 the background and 19 is `custom_color`
     pub text_color: u8,
+    /// 0 is the shell's font, then sans serif, serif and monospace
+    pub font_family: u8,
+    /// `TEXT_BOLD_FLAG` and `TEXT_ITALIC_FLAG`
+    pub font_flags: u8,
+    /// rgba, one byte each, used when `text_color` is 19
+    pub custom_color: u32,
+    /// in logical pixels, 0 is unset
+    pub size: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawImageData {
+    /// the icon name or screenshot path as utf-8, or the png's bytes
+    pub source_ptr: u32,
+    pub source_len: u32,
+    /// 0 for the image's own size
+    pub width: f32,
+    pub height: f32,
+    /// 0 for an icon name, 1 for a png and 2 for a screenshot
+    pub kind: u8,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawTextInputData {
+    pub value_ptr: u32,
+    pub value_len: u32,
+    pub placeholder_ptr: u32,
+    pub placeholder_len: u32,
+    /// 0 fills the space the input is given
+    pub width: f32,
+    /// 0 for the shell's text size
+    pub size: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawGridData {
+    pub columns: u32,
+    pub total: u32,
+    pub first: u32,
+    /// 0 for the shell's default
+    pub cell_width: f32,
+    pub cell_height: f32,
+    /// 0 fills the space the grid is given
+    pub height: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawLayoutData {
+    /// top, right, bottom and left
+    pub padding: [f32; 4],
+    pub spacing: f32,
+    /// the portion or pixels, depending on `width_kind`
+    pub width: f32,
+    pub height: f32,
+    /// 0 shrinks, 1 fills, 2 fills a portion and 3 is a fixed size
+    pub width_kind: u8,
+    pub height_kind: u8,
+    /// 0 is the start, 1 the center and 2 the end
+    pub align: u8,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawTooltipData {
+    pub text_ptr: u32,
+    pub text_len: u32,
+    /// 0 is the top, 1 the bottom, 2 the left, 3 the right and 4 follows
+    /// the pointer
+    pub position: u8,
+}
+
+/// the ids of the callbacks, 0 is no callback
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawMouseAreaData {
+    pub on_enter: u32,
+    pub on_exit: u32,
+    pub on_right_press: u32,
+    pub on_scroll: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawProgressBarData {
+    pub min: f32,
+    pub max: f32,
+    pub value: f32,
+    /// 0 fills the space the bar is given
+    pub length: f32,
+    /// 0 for the shell's default
+    pub thickness: f32,
+    /// rgba, one byte each, used when `bar` is 19
+    pub bar_custom: u32,
+    /// rgba, one byte each, used when `background` is 19
+    pub background_custom: u32,
+    /// like `RawTextStyle::text_color`, 0 is the theme's foreground
+    pub bar: u8,
+    /// like `RawTextStyle::text_color`, 0 is `color02` from the theme
+    pub background: u8,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawContainerData {
+    /// top, right, bottom and left
+    pub padding: [f32; 4],
+    pub border_width: f32,
+    pub border_radius: f32,
+    /// rgba, one byte each, used when `background` is 19
+    pub background_custom: u32,
+    /// rgba, one byte each, used when `border_color` is 19
+    pub border_custom: u32,
+    /// like `RawTextStyle::text_color`, 0 is no background
+    pub background: u8,
+    /// like `RawTextStyle::text_color`, 0 is the theme's foreground
+    pub border_color: u8,
 }
 
 #[repr(C)]
@@ -405,3 +1681,16 @@ struct RawSliderData {
     /// actual type is determined from `number_type`
     pub value: u64,
 }
+
+unsafe impl FromBytes for RawElement {}
+unsafe impl FromBytes for RawTextData {}
+unsafe impl FromBytes for RawTextStyle {}
+unsafe impl FromBytes for RawImageData {}
+unsafe impl FromBytes for RawTextInputData {}
+unsafe impl FromBytes for RawGridData {}
+unsafe impl FromBytes for RawLayoutData {}
+unsafe impl FromBytes for RawTooltipData {}
+unsafe impl FromBytes for RawMouseAreaData {}
+unsafe impl FromBytes for RawProgressBarData {}
+unsafe impl FromBytes for RawContainerData {}
+unsafe impl FromBytes for RawSliderData {}
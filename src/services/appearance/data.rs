@@ -0,0 +1,46 @@
+/// messages emitted from the appearance service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when the service starts and each time the user switches
+    /// between light and dark
+    SchemeChanged { scheme: ColorScheme },
+
+    /// event emitted when the desktop portal can't be reached or doesn't
+    /// have the appearance settings
+    ServiceUnavailable { reason: String },
+    /// event emitted when the service read the color scheme from the portal
+    ServiceAvailable,
+}
+
+/// requests for the appearance service
+#[derive(Debug, Clone)]
+pub enum Request {}
+
+/// whether the user would like light or dark colors
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ColorScheme {
+    /// the user didn't pick, which the shell treats like dark
+    #[default]
+    NoPreference = 0,
+    Dark = 1,
+    Light = 2,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum AppearanceEventType {
+    SchemeChanged,
+}
+
+impl Event {
+    /// the kind of event, `None` for the events about the service itself
+    pub fn event_type(&self) -> Option<AppearanceEventType> {
+        match self {
+            Self::SchemeChanged { .. } => Some(AppearanceEventType::SchemeChanged),
+            Self::ServiceUnavailable { .. } | Self::ServiceAvailable => None,
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! follows the user's light or dark preference through the desktop portal's
+//! `org.freedesktop.appearance` `color-scheme` setting
+//!
+//! the shell switches to `colors-light.toml` or `colors-dark.toml` when the
+//! preference changes, and modules with the `Theme` register get the new
+//! palette. modules don't register with the service itself
+
+mod data;
+mod portal;
+mod state;
+
+pub use data::{ColorScheme, Event, Request};
+pub use state::AppearanceState;
+
+use data::AppearanceEventType;
+use portal::SettingsProxy;
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// the first wait before looking for the portal again after it couldn't be
+/// reached, doubles each failed attempt
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(5);
+/// the longest wait between attempts
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct AppearanceService;
+
+/// returned from `AppearanceService::run` when the portal couldn't be
+/// reached or has no color scheme, so the service waits longer before trying
+/// again
+#[derive(Debug)]
+struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "appearance unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+impl Service for AppearanceService {
+    type Event = Event;
+    type EventType = AppearanceEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = AppearanceState;
+    type SubscriptionData = ();
+
+    const ID: u16 = 16;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.appearance.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut backoff = RETRY_BACKOFF_MIN;
+
+                loop {
+                    let mut state = AppearanceState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.appearance.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:appearance] could not send init event: {}", err);
+                        log::error!("[service:appearance] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:appearance] restarting");
+                        backoff = RETRY_BACKOFF_MIN;
+                        continue;
+                    }
+
+                    let (reason, delay) = match err.downcast_ref::<Unavailable>() {
+                        Some(Unavailable(reason)) => {
+                            log::warn!(
+                                "[service:appearance] {err}, retrying in {}",
+                                humantime::format_duration(backoff)
+                            );
+
+                            let delay = backoff;
+                            backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                            (reason.clone(), delay)
+                        }
+                        None => {
+                            log::error!("[service:appearance] error: {err}");
+                            log::error!("[service:appearance] restarting in 5 seconds...");
+
+                            backoff = RETRY_BACKOFF_MIN;
+                            (err.to_string(), Duration::from_secs(5))
+                        }
+                    };
+
+                    emit(
+                        &mut state,
+                        &mut chan,
+                        vec![Event::ServiceUnavailable { reason }],
+                    )
+                    .await;
+
+                    restart_delay::<Self>(delay).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut AppearanceState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let conn = match zbus::Connection::session().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                return Unavailable(format!("could not connect to the session bus: {err}")).into();
+            }
+        };
+        let settings = match SettingsProxy::new(&conn).await {
+            Ok(settings) => settings,
+            Err(err) => return Unavailable(format!("could not create proxy: {err}")).into(),
+        };
+
+        // listening first so a change right after reading isn't missed
+        let mut changed = match settings.receive_setting_changed().await {
+            Ok(changed) => changed,
+            Err(err) => {
+                return anyhow!("[service:appearance] could not listen for settings: {err}");
+            }
+        };
+
+        // fails when there's no portal or its backend has no appearance
+        // settings
+        let scheme = match portal::color_scheme(&settings).await {
+            Ok(scheme) => scheme,
+            Err(err) => return Unavailable(err.to_string()).into(),
+        };
+
+        log::info!("[service:appearance] service started");
+        startup::ready("appearance");
+
+        emit(
+            state,
+            chan,
+            vec![Event::ServiceAvailable, Event::SchemeChanged { scheme }],
+        )
+        .await;
+
+        loop {
+            tokio::select! {
+                signal = changed.next() => {
+                    let Some(signal) = signal else {
+                        return anyhow!("[service:appearance] signal stream ended");
+                    };
+                    let args = match signal.args() {
+                        Ok(args) => args,
+                        Err(err) => {
+                            log::warn!("[service:appearance] could not read a setting: {err}");
+                            continue;
+                        }
+                    };
+
+                    if args.namespace != portal::NAMESPACE || args.key != portal::COLOR_SCHEME {
+                        continue;
+                    }
+
+                    let scheme = ColorScheme::from_value(&args.value);
+                    log::debug!("[service:appearance] color scheme changed to {scheme:?}");
+                    emit(state, chan, vec![Event::SchemeChanged { scheme }]).await;
+                }
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => match request {},
+                        Ok(ServiceRequest::SubscribeModule { id, data: () }) => {
+                            let events = vec![AppearanceEventType::SchemeChanged];
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:appearance] {id:?} registered, {diff}");
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            module_ids.unregister_module(id);
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:appearance] error receiving request: {err}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! the part of the desktop portal's dbus api the service uses
+//!
+//! see https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.Settings.html
+
+use super::ColorScheme;
+
+use zbus::proxy;
+use zbus::zvariant::{OwnedValue, Value};
+
+/// where the portal keeps the appearance settings
+pub const NAMESPACE: &str = "org.freedesktop.appearance";
+/// the user's light or dark preference
+pub const COLOR_SCHEME: &str = "color-scheme";
+
+#[proxy(
+    interface = "org.freedesktop.portal.Settings",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+pub trait Settings {
+    /// added in version 2 of the interface
+    fn read_one(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+    /// deprecated for `read_one`, it wraps the value in another variant
+    fn read(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+    #[zbus(signal)]
+    fn setting_changed(&self, namespace: &str, key: &str, value: Value<'_>) -> zbus::Result<()>;
+}
+
+/// reads the color scheme, with `read` for portals that don't have
+/// `read_one` yet
+pub async fn color_scheme(settings: &SettingsProxy<'_>) -> zbus::Result<ColorScheme> {
+    let value = match settings.read_one(NAMESPACE, COLOR_SCHEME).await {
+        Ok(value) => value,
+        Err(_) => settings.read(NAMESPACE, COLOR_SCHEME).await?,
+    };
+
+    return Ok(ColorScheme::from_value(&value));
+}
+
+impl ColorScheme {
+    /// the portal's `0` for no preference, `1` for dark and `2` for light,
+    /// anything else is taken as no preference
+    pub fn from_value(value: &Value<'_>) -> Self {
+        match value {
+            Value::U32(1) => Self::Dark,
+            Value::U32(2) => Self::Light,
+            Value::Value(inner) => Self::from_value(inner),
+            _ => Self::NoPreference,
+        }
+    }
+}
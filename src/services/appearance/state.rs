@@ -0,0 +1,49 @@
+use super::data::{AppearanceEventType, ColorScheme};
+use super::{AppearanceService, Event};
+
+use crate::services::{Dedup, ServiceState};
+
+#[derive(Debug)]
+pub struct AppearanceState {
+    pub scheme: ColorScheme,
+
+    /// why the color scheme can't be read, `None` when it can
+    pub unavailable: Option<String>,
+
+    /// some portals send the setting again when anything in the namespace
+    /// changes, which would reload the theme for nothing
+    dedup: Dedup<AppearanceEventType>,
+}
+
+impl ServiceState<AppearanceService> for AppearanceState {
+    fn init() -> Self {
+        Self {
+            scheme: ColorScheme::NoPreference,
+            unavailable: None,
+            dedup: Dedup::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        if let Some(kind) = event.event_type()
+            && !self.dedup.is_new(kind, &event)
+        {
+            return vec![];
+        }
+
+        match event.clone() {
+            Event::SchemeChanged { scheme } => {
+                self.scheme = scheme;
+            }
+            Event::ServiceUnavailable { reason } => {
+                *self = Self::init();
+                self.unavailable = Some(reason);
+            }
+            Event::ServiceAvailable => {
+                self.unavailable = None;
+            }
+        };
+
+        return vec![event];
+    }
+}
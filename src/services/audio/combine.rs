@@ -0,0 +1,104 @@
+//! a sink that plays to several outputs at once, made by loading
+//! `module-combine-sink` into the sound server (pipewire-pulse has one too)
+//!
+//! the sink shows up in `SinksChanged` like any other and can be made the
+//! default sink. there's only one, creating it again replaces it
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::bail;
+use pulse::context::Context;
+use pulse::mainloop::standard::{IterateResult, Mainloop};
+use pulse::operation::{Operation, State};
+
+/// the name of the combined sink, see `Sink.name`
+pub const COMBINED_SINK_NAME: &str = "aurorashell_combined";
+
+/// what the sound server answers `load_module` with when it failed
+const INVALID_INDEX: u32 = u32::MAX;
+
+/// replaces the combined sink with one playing to the sinks named in
+/// `members`, or to every sink (including ones plugged in later) when it's
+/// empty
+pub fn create(
+    mainloop: &mut Mainloop,
+    context: &Context,
+    members: &[String],
+) -> anyhow::Result<()> {
+    remove(mainloop, context)?;
+
+    let mut argument = format!(
+        "sink_name={COMBINED_SINK_NAME} sink_properties='device.description=\"Combined output\"'"
+    );
+    if !members.is_empty() {
+        argument.push_str(&format!(" slaves={}", members.join(",")));
+    }
+
+    let index = Arc::new(Mutex::new(INVALID_INDEX));
+    let index_ref = Arc::clone(&index);
+    let operation =
+        context
+            .introspect()
+            .load_module("module-combine-sink", &argument, move |index| {
+                *index_ref.lock().unwrap() = index
+            });
+    wait(mainloop, &operation)?;
+
+    if *index.lock().unwrap() == INVALID_INDEX {
+        bail!("the sound server couldn't load module-combine-sink with `{argument}`");
+    }
+
+    return Ok(());
+}
+
+/// removes the combined sink, nothing happens when there isn't one
+pub fn remove(mainloop: &mut Mainloop, context: &Context) -> anyhow::Result<()> {
+    let owner = Arc::new(Mutex::new(None::<u32>));
+    let owner_ref = Arc::clone(&owner);
+    let operation =
+        context
+            .introspect()
+            .get_sink_info_by_name(COMBINED_SINK_NAME, move |sink_info| {
+                if let pulse::callbacks::ListResult::Item(sink) = sink_info {
+                    *owner_ref.lock().unwrap() = sink.owner_module;
+                }
+            });
+    wait(mainloop, &operation)?;
+
+    let Some(module) = *owner.lock().unwrap() else {
+        return Ok(());
+    };
+
+    let unloaded = Arc::new(Mutex::new(false));
+    let unloaded_ref = Arc::clone(&unloaded);
+    let operation = context.introspect().unload_module(module, move |success| {
+        *unloaded_ref.lock().unwrap() = success
+    });
+    wait(mainloop, &operation)?;
+
+    if !*unloaded.lock().unwrap() {
+        bail!("the sound server couldn't unload module {module}");
+    }
+
+    return Ok(());
+}
+
+/// runs the mainloop until the sound server answered `operation`
+pub(super) fn wait<F: ?Sized>(
+    mainloop: &mut Mainloop,
+    operation: &Operation<F>,
+) -> anyhow::Result<()> {
+    loop {
+        match operation.get_state() {
+            State::Done => return Ok(()),
+            State::Cancelled => bail!("the operation was cancelled"),
+            State::Running => {}
+        }
+
+        match mainloop.iterate(true) {
+            IterateResult::Quit(q) => bail!("mainloop quit: {q:?}"),
+            IterateResult::Err(err) => bail!("mainloop error: {err}"),
+            IterateResult::Success(_) => {}
+        }
+    }
+}
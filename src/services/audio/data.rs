@@ -1,14 +1,22 @@
+use super::eq::Equalizer;
+
+use crate::instrumented::InstrumentedSender;
+use crate::runtime::RuntimeModuleId;
+
 use std::borrow::Cow;
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
 
 use pulse::callbacks::ListResult;
-use pulse::context::introspect::Introspector;
+use pulse::context::introspect::{CardProfileInfo, Introspector};
+use pulse::def::PortAvailable;
+use pulse::proplist::{Proplist, properties};
 use pulse::volume::ChannelVolumes;
 
 /// messages emitted from the audio service when an event happens
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Hash)]
 pub enum Event {
     /// event emitted when any property of any sink (output) changes
     ///
@@ -47,6 +55,85 @@ pub enum Event {
     /// emitted as a secondary event as a side effect of processing a main
     /// event from the pulseaudio mainloop (see `AudioState::update()`)
     SourceProfileChanged { profile_name: Option<String> },
+
+    /// ports of the default sink and source, like speakers or headphones
+    ///
+    /// event emitted when the default sink or source switches to another
+    /// port, or the default changes
+    ///
+    /// emitted as a secondary event as a side effect of processing a main
+    /// event from the pulseaudio mainloop (see `AudioState::update()`)
+    ActivePortChanged {
+        sink_port: Option<String>,
+        source_port: Option<String>,
+    },
+
+    /// event emitted when an application starts or stops playing, or one of
+    /// its streams changes, like its volume or the sink it plays to
+    ///
+    /// emitted as a main event from the pulseaudio mainloop
+    SinkInputsChanged { inputs: Vec<Stream> },
+    /// event emitted when an application starts or stops recording, or one
+    /// of its streams changes
+    ///
+    /// emitted as a main event from the pulseaudio mainloop
+    SourceOutputsChanged { outputs: Vec<Stream> },
+
+    /// event emitted when something starts or stops recording from a source
+    /// (not a monitor of a sink), like a call or a voice recorder
+    ///
+    /// emitted as a main event from the pulseaudio mainloop
+    RecordingChanged { recording: bool },
+
+    /// event emitted when a noise suppression filter (like pipewire's
+    /// `filter-chain` with rnnoise) appears or goes away, or it's made or
+    /// stops being the default source
+    ///
+    /// emitted as a secondary event as a side effect of processing a main
+    /// event from the pulseaudio mainloop (see `AudioState::update()`)
+    NoiseSuppressionChanged { available: bool, enabled: bool },
+
+    /// event emitted when an equalizer (a pipewire `filter-chain` with
+    /// bands, or easyeffects) appears or goes away, or its bands change
+    ///
+    /// emitted from a thread looking for one when the sinks change, see
+    /// `eq::probe`
+    EqualizerChanged { equalizer: Option<Equalizer> },
+
+    /// event emitted with the smoothed peaks of the default sink and source
+    /// while a module has an `AudioMeter` register
+    ///
+    /// emitted from the level meter's thread, see `meter::Meters`. it's not
+    /// part of the state and is only given to modules with the register
+    LevelsChanged { levels: Levels },
+
+    /// event emitted after every batch of events from the pulseaudio
+    /// mainloop, with everything the batch could have changed
+    ///
+    /// the mainloop's callbacks can finish in any order (pipewire's pulse
+    /// server is known to answer out of order), so consumers can take this
+    /// as the truth instead of stitching the partial events together.
+    /// `generation` is `AudioState::generation` when it was made
+    ///
+    /// emitted by the service itself once the batch was handled, it's never
+    /// given to modules
+    Snapshot {
+        generation: u64,
+        sinks: Vec<Sink>,
+        sources: Vec<Source>,
+        cards: Vec<Card>,
+        defaults: Defaults,
+        profiles: Profiles,
+    },
+
+    /// event emitted when there is no sound server to connect to, or the
+    /// connection to it was lost
+    ///
+    /// all devices are cleared, modules should show a disabled state until
+    /// `Event::ServiceAvailable` is emitted
+    ServiceUnavailable { reason: String },
+    /// event emitted when the service connected to the sound server
+    ServiceAvailable,
 }
 
 /// requests the pulseaudio thread to set properties on the pulseaudio server
@@ -57,12 +144,8 @@ pub enum Request {
     SetDefaultSink {
         name: String,
     },
-    /// sets the default sink's volume
-    //
-    // note: could make this take just a f32 and automatically grab
-    // the default sink and `ChannelVolumes`
-    // as it could take away from the setup thats required elsewhere to make
-    // this request
+    /// sets the default sink's volume, `AdjustSinkVolume` does it without
+    /// needing the sink's name and `ChannelVolumes`
     SetSinkVolume {
         name: String,
         volume: ChannelVolumes,
@@ -71,6 +154,20 @@ pub enum Request {
         name: String,
         state: bool,
     },
+    /// raises or lowers the default sink's volume by `delta_percent`, for
+    /// volume keys. the service turns this into a `SetSinkVolume`
+    AdjustSinkVolume {
+        delta_percent: f32,
+    },
+    /// mutes the default sink or unmutes it, the service turns this into a
+    /// `SetSinkMute`
+    ToggleSinkMute,
+    /// switches a sink to one of its ports by name (see `Port.name`), like
+    /// from speakers to headphones
+    SetSinkPort {
+        name: String,
+        port: String,
+    },
 
     /// sets the default source by source name (see `Source.name`)
     SetDefaultSource {
@@ -81,12 +178,79 @@ pub enum Request {
         name: String,
         volume: ChannelVolumes,
     },
+    /// switches a source to one of its ports by name, like from the
+    /// microphone to the line-in
+    SetSourcePort {
+        name: String,
+        port: String,
+    },
+
+    /// sets the volume of an application's stream by its index (see
+    /// `Stream.index`)
+    SetSinkInputVolume {
+        index: u32,
+        volume: ChannelVolumes,
+    },
+    SetSinkInputMute {
+        index: u32,
+        state: bool,
+    },
+    /// moves an application's stream to another sink by sink name (see
+    /// `Sink.name`)
+    MoveSinkInput {
+        index: u32,
+        sink: String,
+    },
 
     /// sets the profile of an audio card
     SetCardProfile {
         card_name: String,
         profile_name: String,
     },
+
+    /// makes a sink named `combine::COMBINED_SINK_NAME` that plays to the sinks in
+    /// `members` (by `Sink.name`), or to all of them when it's empty. an
+    /// older one is removed first
+    CreateCombinedSink {
+        members: Vec<String>,
+    },
+    /// removes the sink made by `CreateCombinedSink`
+    RemoveCombinedSink,
+
+    /// plays the default source through the default sink `latency_msec`
+    /// later, for testing a microphone. a test that's already running is
+    /// replaced
+    StartMicTest {
+        latency_msec: u32,
+    },
+    /// removes the loopback made by `StartMicTest`
+    StopMicTest,
+
+    /// makes the noise suppression filter the default source, or goes back
+    /// to the source that was the default before it. the service turns this
+    /// into a `SetDefaultSource`, nothing happens without a filter
+    SetNoiseSuppression {
+        enabled: bool,
+    },
+
+    /// sets the gain in dB of the equalizer's band at `band`, from the lowest
+    /// band up. only a `filter-chain`'s bands can be set
+    SetEqBand {
+        band: usize,
+        gain: f32,
+    },
+    /// loads one of the equalizer's presets by name
+    LoadEqPreset {
+        name: String,
+    },
+
+    /// sends `Event::LevelsChanged` about `rate` times a second for a
+    /// module's `AudioMeter` register, until the module unsubscribes. the
+    /// fastest rate asked for is used for every module
+    WatchLevels {
+        module: RuntimeModuleId,
+        rate: u32,
+    },
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -101,6 +265,40 @@ pub enum AudioEventType {
     CardsChanged,
     SinkProfileChanged,
     SourceProfileChanged,
+    ActivePortChanged,
+    SinkInputsChanged,
+    SourceOutputsChanged,
+    RecordingChanged,
+    NoiseSuppressionChanged,
+    EqualizerChanged,
+}
+
+impl Event {
+    /// the kind of event modules register for, `None` for the events about
+    /// the service itself
+    pub fn event_type(&self) -> Option<AudioEventType> {
+        match self {
+            Self::SinksChanged { .. } => Some(AudioEventType::SinksChanged),
+            Self::DefaultSinkChanged { .. } => Some(AudioEventType::DefaultSinkChanged),
+            Self::SourcesChanged { .. } => Some(AudioEventType::SourcesChanged),
+            Self::DefaultSourceChanged { .. } => Some(AudioEventType::DefaultSourceChanged),
+            Self::CardsChanged { .. } => Some(AudioEventType::CardsChanged),
+            Self::SinkProfileChanged { .. } => Some(AudioEventType::SinkProfileChanged),
+            Self::SourceProfileChanged { .. } => Some(AudioEventType::SourceProfileChanged),
+            Self::ActivePortChanged { .. } => Some(AudioEventType::ActivePortChanged),
+            Self::SinkInputsChanged { .. } => Some(AudioEventType::SinkInputsChanged),
+            Self::SourceOutputsChanged { .. } => Some(AudioEventType::SourceOutputsChanged),
+            Self::RecordingChanged { .. } => Some(AudioEventType::RecordingChanged),
+            Self::NoiseSuppressionChanged { .. } => Some(AudioEventType::NoiseSuppressionChanged),
+            Self::EqualizerChanged { .. } => Some(AudioEventType::EqualizerChanged),
+            // a snapshot is about everything at once
+            // levels go to the modules with an `AudioMeter` register instead
+            Self::LevelsChanged { .. } => None,
+            Self::Snapshot { .. } | Self::ServiceUnavailable { .. } | Self::ServiceAvailable => {
+                None
+            }
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -126,6 +324,8 @@ impl AudioSubscriptionData {
     pub const SOURCES_CHANGED: Self = Self(0b_0000_0100);
     /// subscribes to default sink's current profile changing
     pub const SOURCE_PROFILE_CHANGED: Self = Self(0b_0100_0000);
+    /// subscribes to applications' streams changing
+    pub const STREAMS_CHANGED: Self = Self(0b_1000_0000);
 
     pub fn is_set(&self, case: AudioSubscriptionData) -> bool {
         return *self & case != AudioSubscriptionData(0);
@@ -138,7 +338,7 @@ impl AudioSubscriptionData {
     }
 
     pub fn all() -> Self {
-        Self(0b0111_1111)
+        Self(0b1111_1111)
     }
 }
 
@@ -167,35 +367,87 @@ impl BitAnd for AudioSubscriptionData {
 ////////////////////////////////////////////////////////////////////////////////
 // types used for events
 
+/// the names of the default sink and source, see `Event::Snapshot`
+#[derive(Debug, Clone, Default, PartialEq, Hash)]
+pub struct Defaults {
+    pub sink: Option<String>,
+    pub source: Option<String>,
+}
+
+/// the descriptions of the default sink's and source's profiles, see
+/// `Event::Snapshot`
+#[derive(Debug, Clone, Default, PartialEq, Hash)]
+pub struct Profiles {
+    pub sink: Option<String>,
+    pub source: Option<String>,
+}
+
+/// the peaks of the default sink and source between 0.0 - 1.0, see
+/// `Event::LevelsChanged`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Levels {
+    pub sink: f32,
+    pub source: f32,
+}
+
+// hashes the same fields as `PartialEq`
+impl Hash for Levels {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sink.to_bits().hash(state);
+        self.source.to_bits().hash(state);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Sink {
+    /// pulseaudio's index of the sink, an application's `Stream.device`
+    pub index: u32,
     pub name: String,
     pub description: String,
     pub volume: ChannelVolumes,
     pub mute: bool,
     pub card_index: Option<u32>,
+    pub ports: Vec<Port>,
+    /// the name of the port in use
+    pub active_port: Option<String>,
 }
 
 impl PartialEq for Sink {
     fn eq(&self, other: &Self) -> bool {
-        return self.name == other.name
+        return self.index == other.index
+            && self.name == other.name
             && self.description == other.description
             && self.volume.get() == other.volume.get()
             && self.mute == other.mute
-            && self.card_index == other.card_index;
+            && self.card_index == other.card_index
+            && self.ports == other.ports
+            && self.active_port == other.active_port;
     }
 }
 
-pub fn get_sinks(introspector: &Introspector, chan: flume::Sender<Event>) {
-    let sinks = Arc::new(Mutex::new(Vec::<Sink>::new()));
-    let sinks_ref = Arc::clone(&sinks);
+// hashes the same fields as `PartialEq`
+impl Hash for Sink {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.name.hash(state);
+        self.description.hash(state);
+        hash_volume(&self.volume, state);
+        self.mute.hash(state);
+        self.card_index.hash(state);
+        self.ports.hash(state);
+        self.active_port.hash(state);
+    }
+}
 
-    // used so the thread can signal if it failed to start
-    let (tx, rx) = flume::bounded::<bool>(1);
+pub fn get_sinks(introspector: &Introspector, chan: InstrumentedSender<flume::Sender<Event>>) {
+    let mut sinks = Vec::<Sink>::new();
 
+    // the list is sent once pulseaudio says it's complete, however long it
+    // takes to get there
     introspector.get_sink_info_list(move |sink_info| match sink_info {
         ListResult::Item(sink) => {
-            let sink = Sink {
+            sinks.push(Sink {
+                index: sink.index,
                 name: sink.name.clone().unwrap().to_string(),
                 description: sink
                     .description
@@ -205,86 +457,158 @@ pub fn get_sinks(introspector: &Introspector, chan: flume::Sender<Event>) {
                 volume: sink.volume,
                 mute: sink.mute,
                 card_index: sink.card,
-            };
-
-            sinks_ref.lock().unwrap().push(sink);
+                ports: sink
+                    .ports
+                    .iter()
+                    .map(|port| {
+                        Port::new(
+                            port.name.as_deref(),
+                            port.description.as_deref(),
+                            port.available,
+                            port.priority,
+                        )
+                    })
+                    .collect(),
+                active_port: sink
+                    .active_port
+                    .as_ref()
+                    .and_then(|port| port.name.as_ref())
+                    .map(|name| name.to_string()),
+            });
         }
         ListResult::End => {
-            if let Err(err) = tx.send(true) {
-                log::error!(
-                    "error while sending success for introspector.get_sink_info_list: {}",
-                    err
-                );
+            let sinks = std::mem::take(&mut sinks);
+            if let Err(err) = chan.send(Event::SinksChanged { sinks }) {
+                log::error!("[audio] error while sending Event::SinksChanged: {err}");
             }
         }
         ListResult::Error => {
-            log::warn!("could not process introspector.get_sink_info_list");
-            if let Err(err) = tx.send(false) {
-                log::error!(
-                    "error while sending failure for introspector.get_sink_info_list: {}",
-                    err
-                );
-            }
+            log::warn!("[audio] could not process introspector.get_sink_info_list");
         }
     });
-
-    thread::spawn(move || {
-        match rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(success) => match success {
-                true => {
-                    let data = {
-                        let guard = sinks.lock().unwrap();
-                        guard.to_vec()
-                    };
-
-                    if let Err(err) = chan.send(Event::SinksChanged { sinks: data }) {
-                        log::error!("error while sending Event::SinksChanged: {err}");
-                    }
-                }
-                false => {
-                    log::warn!("could not get sinks")
-                }
-            },
-            Err(err) => {
-                log::error!(
-                    "error while waiting for introspector.get_sink_info_list: {}",
-                    err
-                );
-            }
-        };
-    });
 }
 
 #[derive(Debug, Clone)]
 pub struct Source {
+    /// pulseaudio's index of the source, an application's `Stream.device`
+    pub index: u32,
     pub name: String,
     pub description: String,
     pub volume: ChannelVolumes,
     pub mute: bool,
     pub card_index: Option<u32>,
+    pub ports: Vec<Port>,
+    /// the name of the port in use
+    pub active_port: Option<String>,
 }
 
 impl PartialEq for Source {
     fn eq(&self, other: &Self) -> bool {
-        return self.name == other.name
+        return self.index == other.index
+            && self.name == other.name
             && self.description == other.description
-            && self.volume.get() == other.volume.get();
+            && self.volume.get() == other.volume.get()
+            && self.mute == other.mute
+            && self.card_index == other.card_index
+            && self.ports == other.ports
+            && self.active_port == other.active_port;
     }
 }
 
-pub fn get_sources(introspector: &Introspector, chan: flume::Sender<Event>) {
-    let sources = Arc::new(Mutex::new(Vec::<Source>::new()));
-    let sources_ref = Arc::clone(&sources);
+// hashes the same fields as `PartialEq`
+impl Hash for Source {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.name.hash(state);
+        self.description.hash(state);
+        hash_volume(&self.volume, state);
+        self.mute.hash(state);
+        self.card_index.hash(state);
+        self.ports.hash(state);
+        self.active_port.hash(state);
+    }
+}
 
-    // used so the thread can signal if it failed to start
-    let (tx, rx) = flume::bounded::<bool>(1);
+/// an application playing to a sink (a sink input) or recording from a
+/// source (a source output)
+#[derive(Debug, Clone)]
+pub struct Stream {
+    /// pulseaudio's index of the stream, requests for it use this
+    pub index: u32,
+    /// the application's name, or the stream's when it doesn't say
+    pub app_name: String,
+    /// the application's icon name from the icon theme
+    pub icon: Option<String>,
+    pub volume: ChannelVolumes,
+    pub mute: bool,
+    /// pulseaudio's index of the sink or source it's on
+    pub device: u32,
+}
+
+impl PartialEq for Stream {
+    fn eq(&self, other: &Self) -> bool {
+        return self.index == other.index
+            && self.app_name == other.app_name
+            && self.icon == other.icon
+            && self.volume.get() == other.volume.get()
+            && self.mute == other.mute
+            && self.device == other.device;
+    }
+}
+
+// hashes the same fields as `PartialEq`
+impl Hash for Stream {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.app_name.hash(state);
+        self.icon.hash(state);
+        hash_volume(&self.volume, state);
+        self.mute.hash(state);
+        self.device.hash(state);
+    }
+}
+
+/// what the names and descriptions of noise suppression filters contain,
+/// pipewire's rnnoise `filter-chain` example makes a "Noise Canceling source"
+/// named `rnnoise_source`
+const NOISE_FILTER_HINTS: [&str; 5] = [
+    "rnnoise",
+    "noisetorch",
+    "deepfilter",
+    "noise cancel",
+    "noise suppress",
+];
+
+impl Source {
+    /// whether the source is a filter that takes the noise out of a
+    /// microphone
+    pub fn is_noise_filter(&self) -> bool {
+        let name = self.name.to_lowercase().replace(['_', '-'], " ");
+        let description = self.description.to_lowercase();
+
+        return NOISE_FILTER_HINTS
+            .iter()
+            .any(|hint| name.contains(hint) || description.contains(hint));
+    }
+}
+
+/// `ChannelVolumes` isn't `Hash`, so the volume of each channel is hashed
+fn hash_volume<H: Hasher>(volume: &ChannelVolumes, state: &mut H) {
+    for channel in volume.get() {
+        channel.0.hash(state);
+    }
+}
+
+pub fn get_sources(introspector: &Introspector, chan: InstrumentedSender<flume::Sender<Event>>) {
+    let mut sources = Vec::<Source>::new();
 
     introspector.get_source_info_list(move |source_info| match source_info {
         ListResult::Item(source) => {
             // don't get monitors
             // todo?: maybe allow user to select monitors
             if let None = source.monitor_of_sink {
-                let source = Source {
+                sources.push(Source {
+                    index: source.index,
                     name: source.name.clone().unwrap().to_string(),
                     description: source
                         .description
@@ -294,221 +618,312 @@ pub fn get_sources(introspector: &Introspector, chan: flume::Sender<Event>) {
                     volume: source.volume,
                     mute: source.mute,
                     card_index: source.card,
-                };
-
-                sources_ref.lock().unwrap().push(source);
+                    ports: source
+                        .ports
+                        .iter()
+                        .map(|port| {
+                            Port::new(
+                                port.name.as_deref(),
+                                port.description.as_deref(),
+                                port.available,
+                                port.priority,
+                            )
+                        })
+                        .collect(),
+                    active_port: source
+                        .active_port
+                        .as_ref()
+                        .and_then(|port| port.name.as_ref())
+                        .map(|name| name.to_string()),
+                });
             }
         }
         ListResult::End => {
-            if let Err(err) = tx.send(true) {
-                log::error!(
-                    "error while sending success for introspector.get_source_info_list: {}",
-                    err
-                );
+            let sources = std::mem::take(&mut sources);
+            if let Err(err) = chan.send(Event::SourcesChanged { sources }) {
+                log::error!("[audio] error while sending Event::SourcesChanged: {err}");
             }
         }
         ListResult::Error => {
-            log::warn!("could not process introspector.get_source_info_list");
-            if let Err(err) = tx.send(false) {
-                log::error!(
-                    "error while sending failure for introspector.get_source_info_list: {}",
-                    err
-                );
-            }
+            log::warn!("[audio] could not process introspector.get_source_info_list");
         }
     });
-
-    thread::spawn(move || {
-        match rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(success) => match success {
-                true => {
-                    let data = {
-                        let guard = sources.lock().unwrap();
-                        guard.to_vec()
-                    };
-                    if let Err(err) = chan.send(Event::SourcesChanged { sources: data }) {
-                        log::error!("error while sending Message::SourcesChanged: {err}");
-                    }
-                }
-                false => {
-                    log::warn!("could not get sources")
-                }
-            },
-            Err(err) => {
-                log::error!(
-                    "error while waiting for introspector.get_source_info_list: {}",
-                    err
-                );
-            }
-        };
-    });
 }
 
-pub fn get_default_devices(introspector: &Introspector, chan: flume::Sender<Event>) {
-    let default_sink: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-    let default_source: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-    let default_sink_ref = Arc::clone(&default_sink);
-    let default_source_ref = Arc::clone(&default_source);
-
-    // used so the thread can signal if it failed to start
-    let (tx, rx) = flume::bounded::<bool>(1);
-
+pub fn get_default_devices(
+    introspector: &Introspector,
+    chan: InstrumentedSender<flume::Sender<Event>>,
+) {
     introspector.get_server_info(move |server_info| {
-        let mut sink = default_sink_ref
-            .lock()
-            .expect("default sink rwlock poisioned");
-        *sink = match &server_info.default_sink_name {
-            Some(sink) => Some(sink.to_string()),
-            None => None,
-        };
-
-        let mut source = default_source_ref
-            .lock()
-            .expect("default source rwlock poisioned");
-        *source = match &server_info.default_source_name {
-            Some(source) => Some(source.to_string()),
-            None => None,
-        };
-
-        match tx.send(true) {
-            Ok(_) => {}
-            Err(err) => {
-                log::error!(
-                    "error while sending success for introspector.get_server_info: {}",
-                    err
-                );
-            }
-        };
-    });
+        let name = server_info
+            .default_sink_name
+            .as_ref()
+            .map(|sink| sink.to_string());
+        if let Err(err) = chan.send(Event::DefaultSinkChanged { name }) {
+            log::error!("[audio] error while sending Event::DefaultSinkChanged: {err}");
+        }
 
-    thread::spawn(move || {
-        match rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(success) => match success {
-                true => {
-                    let data = {
-                        let guard = default_sink.lock().unwrap();
-                        guard.clone()
-                    };
-
-                    if let Err(err) = chan.send(Event::DefaultSinkChanged { name: data }) {
-                        log::error!("error while sending Message::DefaultSinkChanged: {err}");
-                    }
-
-                    let data = {
-                        let guard = default_source.lock().unwrap();
-                        guard.clone()
-                    };
-
-                    if let Err(err) = chan.send(Event::DefaultSourceChanged { name: data }) {
-                        log::error!("error while sending Message::DefaultSinkChanged: {err}");
-                    }
-                }
-                false => {
-                    log::warn!("could not get the default sink and source")
-                }
-            },
-            Err(err) => {
-                log::error!(
-                    "error while waiting for introspector.get_server_info: {}",
-                    err
-                );
-            }
-        };
+        let name = server_info
+            .default_source_name
+            .as_ref()
+            .map(|source| source.to_string());
+        if let Err(err) = chan.send(Event::DefaultSourceChanged { name }) {
+            log::error!("[audio] error while sending Event::DefaultSourceChanged: {err}");
+        }
     });
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct Card {
     pub name: String,
     pub index: u32,
     pub profiles: Vec<Profile>,
     pub selected_profile: Option<Profile>,
+    /// every port of the card's sinks and sources
+    pub ports: Vec<Port>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Profile {
+/// where a sink plays to or a source records from, like speakers and
+/// headphones or a microphone and a line-in
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Port {
     pub name: String,
     pub description: String,
+    /// false when nothing is plugged into it, like headphones that aren't
+    /// plugged in. ports that can't tell are taken as available
+    pub available: bool,
+    /// higher is better, the sound server switches to the highest available
+    /// one by itself
+    pub priority: u32,
 }
 
-pub fn get_cards(introspector: &Introspector, chan: flume::Sender<Event>) {
-    let cards = Arc::new(Mutex::new(Vec::<Card>::new()));
-    let cards_ref = Arc::clone(&cards);
+impl Port {
+    fn new(
+        name: Option<&str>,
+        description: Option<&str>,
+        available: PortAvailable,
+        priority: u32,
+    ) -> Self {
+        Self {
+            name: name.unwrap_or_default().to_string(),
+            description: description.unwrap_or("Unknown").to_string(),
+            available: available != PortAvailable::No,
+            priority,
+        }
+    }
+}
 
-    // used so the thread can signal if it failed to start
-    let (tx, rx) = flume::bounded::<bool>(1);
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Profile {
+    pub name: String,
+    pub description: String,
+    /// false when the profile can't be used right now, like a headset
+    /// profile of a card whose headset isn't plugged in
+    pub available: bool,
+    /// higher is better, the sound server picks the highest available one
+    /// by itself
+    pub priority: u32,
+}
+
+pub fn get_cards(introspector: &Introspector, chan: InstrumentedSender<flume::Sender<Event>>) {
+    let mut cards = Vec::<Card>::new();
 
     introspector.get_card_info_list(move |card_info| match card_info {
         ListResult::Item(card) => {
-            let card_x3 = Card {
+            cards.push(Card {
                 name: card.name.clone().unwrap().to_string(),
                 index: card.index,
-                profiles: card
-                    .profiles
+                profiles: card.profiles.iter().map(profile).collect::<Vec<Profile>>(),
+                selected_profile: card.active_profile.as_deref().map(profile),
+                ports: card
+                    .ports
                     .iter()
-                    .map(|profile| Profile {
-                        name: profile.name.clone().unwrap().to_string(),
-                        description: profile
-                            .description
-                            .clone()
-                            .unwrap_or(Cow::Borrowed("Unknown"))
-                            .to_string(),
+                    .map(|port| {
+                        Port::new(
+                            port.name.as_deref(),
+                            port.description.as_deref(),
+                            port.available,
+                            port.priority,
+                        )
                     })
-                    .collect::<Vec<Profile>>(),
-                selected_profile: card.active_profile.as_ref().map(|profile| Profile {
-                    name: profile.name.clone().unwrap().to_string(),
-                    description: profile
-                        .description
-                        .clone()
-                        .unwrap_or(Cow::Borrowed("Unknown"))
-                        .to_string(),
-                }),
-            };
+                    .collect(),
+            });
+        }
+        ListResult::End => {
+            let cards = std::mem::take(&mut cards);
+            if let Err(err) = chan.send(Event::CardsChanged { cards }) {
+                log::error!("[audio] error while sending Event::CardsChanged: {err}");
+            }
+        }
+        ListResult::Error => {
+            log::warn!("[audio] could not process introspector.get_card_info_list");
+        }
+    });
+}
+
+/// the applications playing to a sink, streams from the shell itself (like
+/// the microphone test) are left out
+pub fn get_sink_inputs(
+    introspector: &Introspector,
+    chan: InstrumentedSender<flume::Sender<Event>>,
+) {
+    let mut inputs = Vec::<Stream>::new();
+    let pid = std::process::id().to_string();
+
+    introspector.get_sink_input_info_list(move |input_info| match input_info {
+        ListResult::Item(input) => {
+            if is_ours(&input.proplist, &pid) {
+                return;
+            }
 
-            cards_ref.lock().unwrap().push(card_x3);
+            inputs.push(Stream {
+                index: input.index,
+                app_name: app_name(&input.proplist, input.name.as_deref()),
+                icon: input.proplist.get_str(properties::APPLICATION_ICON_NAME),
+                volume: input.volume,
+                mute: input.mute,
+                device: input.sink,
+            });
         }
         ListResult::End => {
-            if let Err(err) = tx.send(true) {
-                log::error!(
-                    "error while sending success for introspector.get_card_info_list: {}",
-                    err
-                );
+            let inputs = std::mem::take(&mut inputs);
+            if let Err(err) = chan.send(Event::SinkInputsChanged { inputs }) {
+                log::error!("[audio] error while sending Event::SinkInputsChanged: {err}");
             }
         }
         ListResult::Error => {
-            log::warn!("could not process introspector.get_card_info_list");
-            if let Err(err) = tx.send(false) {
-                log::error!(
-                    "error while sending failure for introspector.get_card_info_list: {}",
-                    err
-                );
+            log::warn!("[audio] could not process introspector.get_sink_input_info_list");
+        }
+    });
+}
+
+/// the applications recording from a source, like `get_sink_inputs`
+pub fn get_source_outputs(
+    introspector: &Introspector,
+    chan: InstrumentedSender<flume::Sender<Event>>,
+) {
+    let mut outputs = Vec::<Stream>::new();
+    let pid = std::process::id().to_string();
+
+    introspector.get_source_output_info_list(move |output_info| match output_info {
+        ListResult::Item(output) => {
+            if is_ours(&output.proplist, &pid) {
+                return;
+            }
+
+            outputs.push(Stream {
+                index: output.index,
+                app_name: app_name(&output.proplist, output.name.as_deref()),
+                icon: output.proplist.get_str(properties::APPLICATION_ICON_NAME),
+                volume: output.volume,
+                mute: output.mute,
+                device: output.source,
+            });
+        }
+        ListResult::End => {
+            let outputs = std::mem::take(&mut outputs);
+            if let Err(err) = chan.send(Event::SourceOutputsChanged { outputs }) {
+                log::error!("[audio] error while sending Event::SourceOutputsChanged: {err}");
+            }
+        }
+        ListResult::Error => {
+            log::warn!("[audio] could not process introspector.get_source_output_info_list");
+        }
+    });
+}
+
+/// whether the stream is from the shell itself
+fn is_ours(proplist: &Proplist, pid: &str) -> bool {
+    return proplist
+        .get_str(properties::APPLICATION_PROCESS_ID)
+        .is_some_and(|id| id == pid);
+}
+
+fn app_name(proplist: &Proplist, name: Option<&str>) -> String {
+    return proplist
+        .get_str(properties::APPLICATION_NAME)
+        .or_else(|| name.map(str::to_string))
+        .unwrap_or_else(|| "Unknown".to_string());
+}
+
+fn profile(profile: &CardProfileInfo) -> Profile {
+    Profile {
+        name: profile.name.clone().unwrap().to_string(),
+        description: profile
+            .description
+            .clone()
+            .unwrap_or(Cow::Borrowed("Unknown"))
+            .to_string(),
+        available: profile.available,
+        priority: profile.priority,
+    }
+}
+
+/// finds out whether anything records from a source, streams from the shell
+/// itself and from monitors of sinks (like level meters) don't count
+pub fn get_recording(introspector: &Introspector, chan: InstrumentedSender<flume::Sender<Event>>) {
+    // both lists are answered on the mainloop's thread, whichever finishes
+    // last sends the event
+    let recording = Rc::new(RefCell::new(Recording::default()));
+    let sources_recording = Rc::clone(&recording);
+    let sources_chan = chan.clone();
+
+    // the stream's source is an index, so which sources are monitors is
+    // looked up too
+    let mut monitors = HashSet::<u32>::new();
+    introspector.get_source_info_list(move |source_info| match source_info {
+        ListResult::Item(source) => {
+            if source.monitor_of_sink.is_some() {
+                monitors.insert(source.index);
             }
         }
+        ListResult::End => {
+            let mut recording = sources_recording.borrow_mut();
+            recording.monitors = Some(std::mem::take(&mut monitors));
+            recording.send(&sources_chan);
+        }
+        ListResult::Error => {
+            log::warn!("[audio] could not process introspector.get_source_info_list");
+        }
     });
 
-    thread::spawn(move || {
-        match rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(success) => match success {
-                true => {
-                    let data = {
-                        let guard = cards.lock().unwrap();
-                        guard.clone()
-                    };
-
-                    if let Err(err) = chan.send(Event::CardsChanged { cards: data }) {
-                        log::error!("[audio] error while sending Message::CardsChanged: {err}");
-                    }
-                }
-                false => {
-                    log::warn!("[audio] could not get cards");
-                }
-            },
-            Err(err) => {
-                log::error!(
-                    "[audio] error while waiting for introspector.get_card_info_list: {}",
-                    err
-                );
+    let pid = std::process::id().to_string();
+    let mut streams = Vec::<u32>::new();
+    introspector.get_source_output_info_list(move |output_info| match output_info {
+        ListResult::Item(output) => {
+            if !output.corked && !is_ours(&output.proplist, &pid) {
+                streams.push(output.source);
             }
-        };
+        }
+        ListResult::End => {
+            let mut recording = recording.borrow_mut();
+            recording.streams = Some(std::mem::take(&mut streams));
+            recording.send(&chan);
+        }
+        ListResult::Error => {
+            log::warn!("[audio] could not process introspector.get_source_output_info_list");
+        }
     });
 }
+
+/// the two lists `get_recording` needs, `None` until pulseaudio finished
+/// giving it
+#[derive(Default)]
+struct Recording {
+    monitors: Option<HashSet<u32>>,
+    streams: Option<Vec<u32>>,
+}
+
+impl Recording {
+    /// sends `Event::RecordingChanged` once both lists are here
+    fn send(&self, chan: &InstrumentedSender<flume::Sender<Event>>) {
+        let (Some(monitors), Some(streams)) = (&self.monitors, &self.streams) else {
+            return;
+        };
+
+        let recording = streams.iter().any(|source| !monitors.contains(source));
+        if let Err(err) = chan.send(Event::RecordingChanged { recording }) {
+            log::error!("[audio] error while sending Event::RecordingChanged: {err}");
+        }
+    }
+}
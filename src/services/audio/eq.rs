@@ -0,0 +1,300 @@
+//! equalizers set up outside of the shell, found through pipewire
+//!
+//! a pipewire `filter-chain` with biquad filters has a `<band>:Freq` and
+//! `<band>:Gain` control for each band, which are read with `pw-dump` and set
+//! with `pw-cli`. easyeffects keeps its bands to itself, so only its output
+//! presets can be listed and loaded
+//!
+//! presets for a `filter-chain` are the gains of its bands, set under
+//! `[services.audio.eq_presets]`
+
+use super::Event;
+
+use crate::config;
+use crate::instrumented::InstrumentedSender;
+
+use std::hash::{Hash, Hasher};
+use std::process::Command;
+use std::thread;
+
+use anyhow::{anyhow, bail};
+use serde_json::Value;
+
+/// the gains a band can be set to, in dB
+const GAIN_RANGE: (f32, f32) = (-24.0, 24.0);
+
+/// the name of the sink easyeffects plays through
+const EASYEFFECTS_SINK: &str = "easyeffects_sink";
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Equalizer {
+    /// the description of the node, like "Equalizer Sink"
+    pub name: String,
+    pub kind: EqualizerKind,
+    pub bands: Vec<EqBand>,
+    /// the names presets can be loaded by
+    pub presets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EqualizerKind {
+    /// a `filter-chain`, with the id of its pipewire node
+    FilterChain {
+        node: u32,
+    },
+    EasyEffects,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EqBand {
+    /// the band's name in the filter chain, like `eq_band_1`
+    pub name: String,
+    /// the center frequency in Hz, `None` if the filter doesn't have one
+    pub frequency: Option<f32>,
+    /// in dB
+    pub gain: f32,
+}
+
+// hashes the same fields as `PartialEq`
+impl Hash for EqBand {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.frequency.map(f32::to_bits).hash(state);
+        self.gain.to_bits().hash(state);
+    }
+}
+
+/// looks for an equalizer in a thread and sends `EqualizerChanged` with what
+/// was found
+pub fn probe(chan: InstrumentedSender<flume::Sender<Event>>) {
+    thread::spawn(move || {
+        let equalizer = match find() {
+            Ok(equalizer) => equalizer,
+            Err(err) => {
+                log::debug!("[audio] could not look for an equalizer: {err}");
+                None
+            }
+        };
+
+        if let Err(err) = chan.send(Event::EqualizerChanged { equalizer }) {
+            log::error!("[audio] error while sending Event::EqualizerChanged: {err}");
+        }
+    });
+}
+
+/// sets the gain of the band at `band`, returns the equalizer as it will be
+/// once the band is set
+pub fn set_band(equalizer: &Equalizer, band: usize, gain: f32) -> anyhow::Result<Equalizer> {
+    let EqualizerKind::FilterChain { node } = equalizer.kind else {
+        bail!("easyeffects' bands can't be set from the shell");
+    };
+    let Some(name) = equalizer.bands.get(band).map(|band| band.name.clone()) else {
+        bail!("{} has no band {band}", equalizer.name);
+    };
+
+    let gain = gain.clamp(GAIN_RANGE.0, GAIN_RANGE.1);
+    set_gains(node, vec![(name, gain)]);
+
+    let mut equalizer = equalizer.clone();
+    equalizer.bands[band].gain = gain;
+
+    return Ok(equalizer);
+}
+
+/// loads the preset called `name`, returns the equalizer as it will be once
+/// it's loaded
+pub fn load_preset(equalizer: &Equalizer, name: &str) -> anyhow::Result<Equalizer> {
+    let mut equalizer = equalizer.clone();
+
+    match equalizer.kind {
+        EqualizerKind::FilterChain { node } => {
+            let Some(gains) = config::get().services.audio.eq_presets.get(name) else {
+                bail!("there's no preset called {name}");
+            };
+
+            let gains = equalizer
+                .bands
+                .iter_mut()
+                .zip(gains)
+                .map(|(band, gain)| {
+                    band.gain = gain.clamp(GAIN_RANGE.0, GAIN_RANGE.1);
+                    (band.name.clone(), band.gain)
+                })
+                .collect::<Vec<(String, f32)>>();
+
+            set_gains(node, gains);
+        }
+        EqualizerKind::EasyEffects => {
+            if !equalizer.presets.iter().any(|preset| preset == name) {
+                bail!("easyeffects has no preset called {name}");
+            }
+
+            let mut command = Command::new("easyeffects");
+            command.args(["-l", name]);
+            run_in_thread(command, "loading the easyeffects preset");
+        }
+    }
+
+    return Ok(equalizer);
+}
+
+/// sets the gains of the bands with `pw-cli` in a thread, it can take a while
+/// to start
+fn set_gains(node: u32, gains: Vec<(String, f32)>) {
+    let params = gains
+        .iter()
+        .map(|(name, gain)| format!("\"{name}:Gain\" {gain}"))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    let mut command = Command::new("pw-cli");
+    command
+        .arg("set-param")
+        .arg(node.to_string())
+        .arg("Props")
+        .arg(format!("{{ params = [ {params} ] }}"));
+    run_in_thread(command, "setting the equalizer's bands");
+}
+
+fn run_in_thread(mut command: Command, what: &'static str) {
+    thread::spawn(move || match command.output() {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => log::warn!(
+            "[audio] {what} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(err) => log::warn!("[audio] {what} failed: {err}"),
+    });
+}
+
+/// the first `filter-chain` with equalizer bands, or easyeffects when it's
+/// running
+fn find() -> anyhow::Result<Option<Equalizer>> {
+    let output = Command::new("pw-dump").output()?;
+    if !output.status.success() {
+        bail!("pw-dump exited with {}", output.status);
+    }
+
+    let objects: Vec<Value> = serde_json::from_slice(&output.stdout)?;
+    let nodes = objects
+        .iter()
+        .filter(|object| object["type"] == "PipeWire:Interface:Node");
+
+    let mut easyeffects = false;
+
+    for node in nodes {
+        let props = &node["info"]["props"];
+        let name = props["node.description"]
+            .as_str()
+            .or(props["node.name"].as_str())
+            .unwrap_or("Equalizer");
+
+        if props["node.name"] == EASYEFFECTS_SINK {
+            easyeffects = true;
+            continue;
+        }
+
+        let bands = bands(node);
+        if bands.is_empty() {
+            continue;
+        }
+
+        let Some(id) = node["id"].as_u64() else {
+            continue;
+        };
+
+        return Ok(Some(Equalizer {
+            name: name.to_string(),
+            kind: EqualizerKind::FilterChain { node: id as u32 },
+            bands,
+            presets: config::get()
+                .services
+                .audio
+                .eq_presets
+                .keys()
+                .cloned()
+                .collect(),
+        }));
+    }
+
+    if !easyeffects {
+        return Ok(None);
+    }
+
+    return Ok(Some(Equalizer {
+        name: "EasyEffects".to_string(),
+        kind: EqualizerKind::EasyEffects,
+        bands: vec![],
+        presets: easyeffects_presets()?,
+    }));
+}
+
+/// the bands of a `filter-chain` node, in the order the chain has them
+///
+/// the node's `Props` have a list of controls and their values one after
+/// the other, like `["eq_band_1:Freq", 100.0, "eq_band_1:Gain", 0.0, ...]`
+fn bands(node: &Value) -> Vec<EqBand> {
+    let mut bands: Vec<EqBand> = vec![];
+
+    let Some(props) = node["info"]["params"]["Props"].as_array() else {
+        return bands;
+    };
+
+    for prop in props {
+        let Some(params) = prop["params"].as_array() else {
+            continue;
+        };
+
+        for pair in params.chunks_exact(2) {
+            let (Some(control), Some(value)) = (pair[0].as_str(), pair[1].as_f64()) else {
+                continue;
+            };
+            let Some((band, control)) = control.split_once(':') else {
+                continue;
+            };
+
+            let index = match bands.iter().position(|known| known.name == band) {
+                Some(index) => index,
+                None => {
+                    bands.push(EqBand {
+                        name: band.to_string(),
+                        frequency: None,
+                        gain: f32::NAN,
+                    });
+                    bands.len() - 1
+                }
+            };
+
+            match control {
+                "Freq" => bands[index].frequency = Some(value as f32),
+                "Gain" => bands[index].gain = value as f32,
+                _ => {}
+            }
+        }
+    }
+
+    // filters without a gain, like a high pass, aren't bands that can be
+    // moved
+    bands.retain(|band| !band.gain.is_nan());
+
+    return bands;
+}
+
+/// the output presets easyeffects has, `easyeffects -p` prints them like
+/// `Output Presets: Bass Boost,Loudness,`
+fn easyeffects_presets() -> anyhow::Result<Vec<String>> {
+    let output = Command::new("easyeffects").arg("-p").output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let line = stdout
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Output Presets:"))
+        .ok_or_else(|| anyhow!("easyeffects didn't list its output presets"))?;
+
+    return Ok(line
+        .split(',')
+        .map(str::trim)
+        .filter(|preset| !preset.is_empty())
+        .map(str::to_string)
+        .collect());
+}
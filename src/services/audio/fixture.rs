@@ -0,0 +1,101 @@
+use super::data::{Card, Event, Profile, Sink, Source};
+use super::{AudioService, PULSE_MAX_VOLUME};
+
+use crate::services::ipc::protocol::{AudioDevice, AudioInfo, AudioProfile};
+
+use pulse::volume::{ChannelVolumes, Volume};
+
+impl AudioService {
+    /// turns a dumped state back into the events that would have created it
+    ///
+    /// used by `--load-state-fixture` in place of a real pulseaudio server
+    pub fn fixture_events(info: &AudioInfo) -> Vec<Event> {
+        if let Some(reason) = &info.unavailable {
+            return vec![Event::ServiceUnavailable {
+                reason: reason.clone(),
+            }];
+        }
+
+        let volume = |device: &AudioDevice| {
+            let mut volume = ChannelVolumes::default();
+            volume.set(
+                2,
+                Volume((device.volume / 100.0 * PULSE_MAX_VOLUME as f32).round() as u32),
+            );
+            volume
+        };
+        let profile = |profile: &AudioProfile| Profile {
+            name: profile.name.clone(),
+            description: profile.description.clone(),
+            // not dumped, the policy for bluetooth headsets doesn't run
+            // with a fixture
+            available: true,
+            priority: 0,
+        };
+
+        // cards go first so the profiles can be found when the defaults
+        // are set
+        return vec![
+            Event::CardsChanged {
+                cards: info
+                    .cards
+                    .iter()
+                    .map(|card| Card {
+                        name: card.name.clone(),
+                        index: card.index,
+                        profiles: card.profiles.iter().map(profile).collect(),
+                        selected_profile: card.selected_profile.as_ref().map(profile),
+                        ports: vec![],
+                    })
+                    .collect(),
+            },
+            Event::SinksChanged {
+                sinks: info
+                    .sinks
+                    .iter()
+                    .zip(0..)
+                    .map(|(sink, index)| Sink {
+                        index,
+                        name: sink.name.clone(),
+                        description: sink.description.clone(),
+                        volume: volume(sink),
+                        mute: sink.mute,
+                        card_index: sink.card_index,
+                        // not dumped
+                        ports: vec![],
+                        active_port: None,
+                    })
+                    .collect(),
+            },
+            Event::SourcesChanged {
+                sources: info
+                    .sources
+                    .iter()
+                    .zip(0..)
+                    .map(|(source, index)| Source {
+                        index,
+                        name: source.name.clone(),
+                        description: source.description.clone(),
+                        volume: volume(source),
+                        mute: source.mute,
+                        card_index: source.card_index,
+                        ports: vec![],
+                        active_port: None,
+                    })
+                    .collect(),
+            },
+            Event::DefaultSinkChanged {
+                name: info.default_sink.clone(),
+            },
+            Event::DefaultSourceChanged {
+                name: info.default_source.clone(),
+            },
+            Event::SinkProfileChanged {
+                profile_name: info.sink_default_profile.clone(),
+            },
+            Event::SourceProfileChanged {
+                profile_name: info.source_default_profile.clone(),
+            },
+        ];
+    }
+}
@@ -0,0 +1,95 @@
+//! a microphone test, made by loading `module-loopback` into the sound server
+//! so the default source plays back through the default sink
+//!
+//! the loopback is found again by the name of its streams, so one left over
+//! from an earlier run (the shell crashing or the service restarting) is
+//! removed when the service starts
+
+use super::combine::wait;
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::bail;
+use pulse::context::Context;
+use pulse::mainloop::standard::Mainloop;
+
+/// the `media.name` of the loopback's streams
+pub const MIC_TEST_NAME: &str = "aurorashell_mic_test";
+
+/// used when a module doesn't ask for a latency
+pub const DEFAULT_LATENCY_MSEC: u32 = 200;
+/// more than this and it's hard to tell what you hear is what you said
+pub const MAX_LATENCY_MSEC: u32 = 2000;
+
+/// what the sound server answers `load_module` with when it failed
+const INVALID_INDEX: u32 = u32::MAX;
+
+/// plays the default source through the default sink `latency_msec` later,
+/// replacing a test that's already running
+pub fn start(mainloop: &mut Mainloop, context: &Context, latency_msec: u32) -> anyhow::Result<()> {
+    stop(mainloop, context)?;
+
+    let latency_msec = latency_msec.clamp(1, MAX_LATENCY_MSEC);
+    let argument = format!(
+        "latency_msec={latency_msec} source_dont_move=true sink_dont_move=true \
+         sink_input_properties=media.name={MIC_TEST_NAME} \
+         source_output_properties=media.name={MIC_TEST_NAME}"
+    );
+
+    let index = Arc::new(Mutex::new(INVALID_INDEX));
+    let index_ref = Arc::clone(&index);
+    let operation = context
+        .introspect()
+        .load_module("module-loopback", &argument, move |index| {
+            *index_ref.lock().unwrap() = index
+        });
+    wait(mainloop, &operation)?;
+
+    if *index.lock().unwrap() == INVALID_INDEX {
+        bail!("the sound server couldn't load module-loopback with `{argument}`");
+    }
+
+    log::info!("[audio] started the microphone test with {latency_msec}ms of latency");
+
+    return Ok(());
+}
+
+/// removes every loopback made by `start`, nothing happens when there isn't
+/// one
+pub fn stop(mainloop: &mut Mainloop, context: &Context) -> anyhow::Result<()> {
+    let modules = Arc::new(Mutex::new(Vec::<u32>::new()));
+    let modules_ref = Arc::clone(&modules);
+    let operation = context
+        .introspect()
+        .get_module_info_list(move |module_info| {
+            if let pulse::callbacks::ListResult::Item(module) = module_info
+                && module.name.as_deref() == Some("module-loopback")
+                && module
+                    .argument
+                    .as_deref()
+                    .is_some_and(|argument| argument.contains(MIC_TEST_NAME))
+            {
+                modules_ref.lock().unwrap().push(module.index);
+            }
+        });
+    wait(mainloop, &operation)?;
+
+    let modules = std::mem::take(&mut *modules.lock().unwrap());
+
+    for module in modules {
+        let unloaded = Arc::new(Mutex::new(false));
+        let unloaded_ref = Arc::clone(&unloaded);
+        let operation = context.introspect().unload_module(module, move |success| {
+            *unloaded_ref.lock().unwrap() = success
+        });
+        wait(mainloop, &operation)?;
+
+        if !*unloaded.lock().unwrap() {
+            bail!("the sound server couldn't unload module {module}");
+        }
+
+        log::info!("[audio] stopped the microphone test");
+    }
+
+    return Ok(());
+}
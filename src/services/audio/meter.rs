@@ -0,0 +1,267 @@
+//! live levels of the default sink and source for modules with an
+//! `AudioMeter` register
+//!
+//! the levels come from two peak detecting record streams in a thread with
+//! its own connection to the sound server, one on the default sink's monitor
+//! and one on the default source. the sound server sends a stream's peak
+//! `rate` times a second, so the thread only runs while a module wants levels
+
+use super::{AudioService, Event, Levels};
+
+use crate::instrumented::InstrumentedSender;
+use crate::runtime::RuntimeModuleId;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use pulse::context::Context;
+use pulse::context::subscribe::{Facility, InterestMaskSet};
+use pulse::def::BufferAttr;
+use pulse::mainloop::standard::IterateResult;
+use pulse::sample::{Format, Spec};
+use pulse::stream::{self, PeekResult, Stream};
+
+/// the fewest levels a second a module can ask for
+const MIN_RATE: u32 = 1;
+/// the most levels a second a module can ask for, more can't be seen anyway
+const MAX_RATE: u32 = 60;
+
+/// how much of a level is left after a second of quiet, so the meter falls
+/// back smoothly instead of jumping with every peak
+const FALLOFF: f32 = 0.05;
+
+/// the levels a second the modules asked for, kept across restarts of the
+/// service like `ModuleIds`
+#[derive(Debug, Default)]
+pub struct Meters {
+    rates: HashMap<RuntimeModuleId, u32>,
+    /// the thread measuring, `None` while no module wants levels
+    running: Option<Meter>,
+}
+
+/// stops its thread when dropped
+#[derive(Debug)]
+struct Meter {
+    rate: u32,
+    _stop: flume::Sender<()>,
+}
+
+impl Meters {
+    pub fn watch(
+        &mut self,
+        id: RuntimeModuleId,
+        rate: u32,
+        events: &InstrumentedSender<flume::Sender<Event>>,
+    ) {
+        let rate = rate.clamp(MIN_RATE, MAX_RATE);
+        log::debug!("[service:audio] {id:?} wants levels {rate} times a second");
+
+        self.rates.insert(id, rate);
+        self.update(events);
+    }
+
+    pub fn unwatch(
+        &mut self,
+        id: &RuntimeModuleId,
+        events: &InstrumentedSender<flume::Sender<Event>>,
+    ) {
+        if self.rates.remove(id).is_some() {
+            self.update(events);
+        }
+    }
+
+    /// starts the thread again, the one from before the service restarted
+    /// sends its levels to a channel that's gone
+    pub fn restart(&mut self, events: &InstrumentedSender<flume::Sender<Event>>) {
+        self.running = None;
+        self.update(events);
+    }
+
+    /// starts, restarts or stops the thread so it measures at the fastest
+    /// rate a module asked for
+    fn update(&mut self, events: &InstrumentedSender<flume::Sender<Event>>) {
+        let rate = self.rates.values().max().copied();
+
+        if self.running.as_ref().map(|meter| meter.rate) == rate {
+            return;
+        }
+
+        self.running = rate.map(|rate| Meter::spawn(rate, events.clone()));
+    }
+}
+
+impl Meter {
+    fn spawn(rate: u32, events: InstrumentedSender<flume::Sender<Event>>) -> Self {
+        let (stop_tx, stop_rx) = flume::bounded::<()>(0);
+
+        thread::spawn(move || {
+            if let Err(err) = measure(rate, &events, &stop_rx) {
+                log::error!("[audio] level meter stopped: {err}");
+            }
+        });
+
+        return Self {
+            rate,
+            _stop: stop_tx,
+        };
+    }
+}
+
+/// the streams being read, disconnected when dropped
+struct Streams {
+    sink: Option<Stream>,
+    source: Option<Stream>,
+}
+
+impl Streams {
+    fn connect(context: &mut Context, rate: u32) -> Self {
+        Self {
+            sink: connect(context, "@DEFAULT_MONITOR@", rate),
+            source: connect(context, "@DEFAULT_SOURCE@", rate),
+        }
+    }
+}
+
+impl Drop for Streams {
+    fn drop(&mut self) {
+        for stream in [&mut self.sink, &mut self.source].into_iter().flatten() {
+            let _ = stream.disconnect();
+        }
+    }
+}
+
+/// runs until the `Meter` is dropped or the service stops taking levels
+fn measure(
+    rate: u32,
+    events: &InstrumentedSender<flume::Sender<Event>>,
+    stop: &flume::Receiver<()>,
+) -> anyhow::Result<()> {
+    let (mut mainloop, mut context) = AudioService::init_mainloop()?;
+
+    // the default sink or source changing is a change to the server, the
+    // streams are connected again to follow it
+    let moved = Rc::new(Cell::new(false));
+    {
+        let moved = moved.clone();
+        context.set_subscribe_callback(Some(Box::new(move |facility, _, _| {
+            if matches!(facility, Some(Facility::Server)) {
+                moved.set(true);
+            }
+        })));
+    }
+    context.subscribe(InterestMaskSet::SERVER, |success| {
+        log::debug!("[audio] level meter subscribe success: {success}");
+    });
+
+    let mut streams = Streams::connect(&mut context, rate);
+    let mut levels = Levels::default();
+    let decay = FALLOFF.powf(1.0 / rate as f32);
+    let period = Duration::from_secs(1) / rate;
+    let mut sent = Instant::now();
+
+    log::info!("[audio] level meter started, {rate} levels a second");
+
+    loop {
+        // the streams wake the mainloop `rate` times a second, so this is
+        // noticed soon after the `Meter` is dropped
+        if let Err(flume::TryRecvError::Disconnected) = stop.try_recv() {
+            log::info!("[audio] level meter stopped, no module wants levels");
+            return Ok(());
+        }
+
+        match mainloop.iterate(true) {
+            IterateResult::Quit(_) | IterateResult::Err(_) => {
+                return Err(anyhow!("the mainloop stopped"));
+            }
+            IterateResult::Success(_) => {}
+        }
+
+        if moved.replace(false) {
+            streams = Streams::connect(&mut context, rate);
+        }
+
+        levels.sink = smooth(levels.sink, streams.sink.as_mut().and_then(peak), decay);
+        levels.source = smooth(levels.source, streams.source.as_mut().and_then(peak), decay);
+
+        if sent.elapsed() < period {
+            continue;
+        }
+        sent = Instant::now();
+
+        if events.send(Event::LevelsChanged { levels }).is_err() {
+            // the service stopped and `Meters::restart` starts another
+            return Ok(());
+        }
+    }
+}
+
+fn connect(context: &mut Context, device: &str, rate: u32) -> Option<Stream> {
+    let spec = Spec {
+        format: Format::FLOAT32NE,
+        channels: 1,
+        rate,
+    };
+
+    let Some(mut stream) = Stream::new(context, "aurorashell level meter", &spec, None) else {
+        log::warn!("[audio] could not create a stream to meter {device}");
+        return None;
+    };
+
+    // a fragment of one sample has the sound server send every peak as soon
+    // as it has it
+    let attr = BufferAttr {
+        maxlength: u32::MAX,
+        tlength: u32::MAX,
+        prebuf: u32::MAX,
+        minreq: u32::MAX,
+        fragsize: size_of::<f32>() as u32,
+    };
+    let flags =
+        stream::FlagSet::PEAK_DETECT | stream::FlagSet::ADJUST_LATENCY | stream::FlagSet::DONT_MOVE;
+
+    if let Err(err) = stream.connect_record(Some(device), Some(&attr), flags) {
+        log::warn!("[audio] could not meter {device}: {err}");
+        return None;
+    }
+
+    return Some(stream);
+}
+
+/// the loudest peak that arrived since the last read, `None` when none did
+fn peak(stream: &mut Stream) -> Option<f32> {
+    if stream.get_state() != stream::State::Ready {
+        return None;
+    }
+
+    let mut loudest: Option<f32> = None;
+
+    loop {
+        match stream.peek() {
+            Ok(PeekResult::Data(data)) => {
+                for sample in data.chunks_exact(size_of::<f32>()) {
+                    let sample = f32::from_ne_bytes([sample[0], sample[1], sample[2], sample[3]]);
+                    loudest = Some(loudest.map_or(sample, |loudest| loudest.max(sample)));
+                }
+            }
+            Ok(PeekResult::Hole(_)) => {}
+            Ok(PeekResult::Empty) | Err(_) => return loudest,
+        }
+
+        if stream.discard().is_err() {
+            return loudest;
+        }
+    }
+}
+
+/// jumps up to a louder peak and falls back by `decay` otherwise
+fn smooth(level: f32, peak: Option<f32>, decay: f32) -> f32 {
+    let Some(peak) = peak else {
+        return level;
+    };
+
+    return peak.clamp(0.0, 1.0).max(level * decay);
+}
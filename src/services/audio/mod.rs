@@ -1,23 +1,42 @@
+mod combine;
 mod data;
+mod eq;
+mod fixture;
+mod loopback;
+mod meter;
+mod persist;
+mod policy;
 mod se;
 mod state;
 
-pub use data::AudioSubscriptionData;
+pub use data::{AudioSubscriptionData, Event, Levels, Request, Sink, Source, Stream};
+pub use se::{AudioDetail, AudioDevices};
 
 use data::{
-    AudioEventType, Event, Request, get_cards, get_default_devices, get_sinks, get_sources,
+    AudioEventType, get_cards, get_default_devices, get_recording, get_sink_inputs, get_sinks,
+    get_source_outputs, get_sources,
 };
+use eq::Equalizer;
+use meter::Meters;
+use persist::Persist;
+use policy::Policy;
 use state::AudioRequestThreadState;
 
-use crate::services::{ModuleIds, Service, ServiceEvent, ServiceRequest, ServiceState};
+use crate::config;
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    Debounce, ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState,
+    restart_delay, supervise,
+};
+use crate::startup;
+use crate::watchdog::Heartbeat;
 
 use std::any::TypeId;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
 use iced::Subscription;
-use iced::futures::SinkExt;
 use iced::futures::channel::mpsc;
 use iced::stream::channel;
 use pulse::context::subscribe::{
@@ -26,18 +45,27 @@ use pulse::context::subscribe::{
 use pulse::context::{Context, FlagSet};
 use pulse::mainloop::standard::{IterateResult, Mainloop};
 use pulse::proplist::{Proplist, properties};
-use state::AudioState;
+pub use state::AudioState;
 
 ////////////////////////////////////////////////////////////////////////////////
 // service parameters
 
-/// configures the capacity for all channels in this service
-const CHANNEL_CAPACITY: usize = 64;
+/// configures the capacity for all channels in this service, see
+/// `channel_capacity` under `[services.audio]`
+fn channel_capacity() -> usize {
+    config::get().services.audio.channel_capacity.max(1)
+}
 
 /// the time between requesting pulseaudio to update a value that can
 /// be frequently updated (like volume) to stop us from spamming the server
-/// which lags pulseaudio
-const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+/// which lags pulseaudio, see `update_interval_ms` under `[services.audio]`
+fn update_interval() -> Duration {
+    Duration::from_millis(config::get().services.audio.update_interval_ms)
+}
+
+/// how long events from pulseaudio are held to gather a burst of them into
+/// one, a change to a device sends an event for every property that changed
+const DEBOUNCE_WINDOW: Duration = Duration::from_millis(30);
 
 /// 65536 represents 100% in pulseaudio
 ///
@@ -47,33 +75,64 @@ const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
 /// found as the Volume Control app allows up to 153% volume
 pub const PULSE_MAX_VOLUME: u32 = 65536;
 
+/// the first wait before trying to connect to the sound server again after
+/// it couldn't be found, doubles each failed attempt
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(5);
+/// the longest wait between attempts to connect to the sound server
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
 ////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug, Clone)]
 pub struct AudioService;
 
+/// returned from `AudioService::run` when the sound server couldn't be
+/// connected to, so the service waits longer before trying again
+#[derive(Debug)]
+struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sound server unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
 impl Service for AudioService {
     type Event = Event;
     type EventType = AudioEventType;
     type Request = Request;
-    type RuntimeData = (AudioRequestThreadState,);
+    type RuntimeData = (AudioRequestThreadState, Heartbeat, Meters);
     type State = AudioState;
     type SubscriptionData = AudioSubscriptionData;
 
+    const ID: u16 = 1;
+    const VERSION: u32 = 1;
+
     fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
         let id = TypeId::of::<Self>();
 
         Subscription::run_with_id(
             id,
-            channel(CHANNEL_CAPACITY, async |mut chan| {
+            channel(channel_capacity(), async |chan| {
+                let mut chan = InstrumentedSender::new("service.audio.events", chan);
                 let mut module_ids = ModuleIds::new();
+                let heartbeat = Heartbeat::spawn("service:audio");
+                let mut backoff = RETRY_BACKOFF_MIN;
+                // outlives the restarts with `module_ids`, the modules don't
+                // register again
+                let mut meters = Meters::default();
 
                 loop {
                     let mut state = AudioState::init();
 
                     // setup channel for modules to be able to talk to this
                     // service :3
-                    let (tx, rx) = flume::bounded::<ServiceRequest<Self>>(CHANNEL_CAPACITY);
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.audio.requests",
+                        channel_capacity(),
+                    );
 
                     if let Err(err) = chan
                         .send(ServiceEvent::Init {
@@ -87,17 +146,60 @@ impl Service for AudioService {
                         continue;
                     }
 
-                    let mut runtime_data = (AudioRequestThreadState::init(tx),);
-
-                    let err = Self::run(
-                        &mut state,
-                        &mut module_ids,
-                        &mut runtime_data,
-                        &mut chan,
-                        rx,
-                    )
-                    .await;
-                    log::error!("[service:audio] mainloop error: {err}");
+                    let mut runtime_data = (
+                        AudioRequestThreadState::init(tx),
+                        heartbeat.clone(),
+                        std::mem::take(&mut meters),
+                    );
+
+                    let err = tokio::select! {
+                        err = supervise::<Self>(Self::run(
+                            &mut state,
+                            &mut module_ids,
+                            &mut runtime_data,
+                            &mut chan,
+                            rx,
+                        )) => err,
+                        _ = heartbeat.tripped() => anyhow!("[service:audio] watchdog tripped"),
+                    };
+                    meters = runtime_data.2;
+
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:audio] restarting");
+                        backoff = RETRY_BACKOFF_MIN;
+                        continue;
+                    }
+
+                    match err.downcast_ref::<Unavailable>() {
+                        Some(Unavailable(reason)) => {
+                            log::warn!(
+                                "[service:audio] {err}, retrying in {}",
+                                humantime::format_duration(backoff)
+                            );
+
+                            for event in state.update(Event::ServiceUnavailable {
+                                reason: reason.clone(),
+                            }) {
+                                if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                                    log::error!(
+                                        "[service:audio] error sending service event update: {err}"
+                                    );
+                                }
+                            }
+
+                            restart_delay::<Self>(backoff).await;
+                            backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                        }
+                        None => {
+                            log::error!("[service:audio] mainloop error: {err}");
+                            log::error!("[service:audio] restarting in 5 seconds...");
+
+                            // the service was connected so the next failure
+                            // starts from the shortest wait again
+                            backoff = RETRY_BACKOFF_MIN;
+                            restart_delay::<Self>(Duration::from_secs(5)).await;
+                        }
+                    }
                 }
             }),
         )
@@ -106,53 +208,159 @@ impl Service for AudioService {
     async fn run(
         state: &mut AudioState,
         module_ids: &mut ModuleIds<Self>,
-        runtime_data: &mut (AudioRequestThreadState,),
-        chan: &mut mpsc::Sender<ServiceEvent<Self>>,
+        runtime_data: &mut (AudioRequestThreadState, Heartbeat, Meters),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
         request_rx: flume::Receiver<ServiceRequest<Self>>,
     ) -> anyhow::Error {
         log::info!("[service:audio] service started!");
+        startup::ready("audio");
 
         // used for communicating with the pulseaudio mainloop
         // as i haven't found a way to use the async channels that are already
         // provided by the subscription in the mainloop part
-        let (internal_event_tx, internal_event_rx) = flume::bounded::<Event>(CHANNEL_CAPACITY);
+        let (internal_event_tx, internal_event_rx) =
+            instrumented::bounded::<Event>("service.audio.internal_events", channel_capacity());
         let (internal_request_tx, internal_request_rx) =
-            flume::bounded::<ServiceRequest<Self>>(CHANNEL_CAPACITY);
+            instrumented::bounded::<ServiceRequest<Self>>(
+                "service.audio.internal_requests",
+                channel_capacity(),
+            );
+
+        let (request_state, heartbeat, meters) = runtime_data;
+
+        // equalizers are looked for and changed outside of the mainloop, on
+        // their own channel so the mainloop stopping is still noticed
+        let (eq_event_tx, eq_event_rx) =
+            instrumented::bounded::<Event>("service.audio.eq_events", channel_capacity());
 
-        let (request_state,) = runtime_data;
+        // the level meter runs in its own thread too, see `meter`
+        let (level_tx, level_rx) =
+            instrumented::bounded::<Event>("service.audio.levels", channel_capacity());
+
+        // the mainloop thread sends whether it connected to the sound server
+        let (ready_tx, ready_rx) = flume::bounded::<Result<(), String>>(1);
 
         Self::mainloop(
             internal_event_tx,
             internal_request_rx,
             request_state.clone(),
+            ready_tx,
         );
 
+        match ready_rx.recv_async().await {
+            Ok(Ok(())) => {
+                for event in state.update(Event::ServiceAvailable) {
+                    if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                        log::error!("[service:audio] error sending service event update: {err}");
+                    }
+                }
+            }
+            Ok(Err(reason)) => return Unavailable(reason).into(),
+            Err(_) => return Unavailable("mainloop thread exited".to_string()).into(),
+        };
+
+        meters.restart(&level_tx);
+
+        let mut debounce = Debounce::new(DEBOUNCE_WINDOW);
+        let mut persist = Persist::new();
+        let mut policy = Policy::new();
+        // the sinks the last look for an equalizer was made with
+        let mut eq_sinks: Option<Vec<String>> = None;
+        // the generation of the last `Event::Snapshot`
+        let mut snapshot_generation: Option<u64> = None;
+
         loop {
+            let deadline = debounce.deadline();
+
             tokio::select! {
                 event = internal_event_rx.recv_async() => {
                     match event {
-                        Ok(event) => {
-                            let events = state.update(event.clone());
-                            log::debug!("{:?}", events); // note: prob remove this, not needed
-
-                            for event in events {
-                                if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
-                                    log::error!(
-                                        "[service:audio] error sending service event update: {err}"
-                                    );
-                                    continue;
-                                }
+                        Ok(event) => match event.event_type() {
+                            Some(kind) => debounce.push(kind, event),
+                            None => {
+                                // keeps the events in order
+                                let mut events = debounce.flush();
+                                events.push(event);
+                                Self::emit(state, chan, heartbeat, events).await;
+                                Self::snapshot(state, chan, &mut snapshot_generation).await;
+                                Self::persist(&mut persist, state, &internal_request_tx);
+                                Self::policy(&mut policy, state, &internal_request_tx);
+                                Self::probe_equalizer(&mut eq_sinks, state, &eq_event_tx);
                             }
-                        }
+                        },
                         Err(err) => {
                             return anyhow!("[service:audio] error receiving message from mainloop: {err}");
                         }
                     }
                 }
+                Ok(event) = eq_event_rx.recv_async() => {
+                    Self::emit(state, chan, heartbeat, vec![event]).await;
+                }
+                // not part of the state, so they skip it
+                Ok(event) = level_rx.recv_async() => {
+                    if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                        log::error!("[service:audio] error sending service event update: {err}");
+                    }
+                }
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now).into()), if deadline.is_some() => {
+                    Self::emit(state, chan, heartbeat, debounce.flush()).await;
+                    Self::snapshot(state, chan, &mut snapshot_generation).await;
+                    Self::persist(&mut persist, state, &internal_request_tx);
+                    Self::policy(&mut policy, state, &internal_request_tx);
+                    Self::probe_equalizer(&mut eq_sinks, state, &eq_event_tx);
+                }
                 request = request_rx.recv_async() => {
                     match request {
                         Ok(request) => {
+                            let _busy = heartbeat.busy(|| format!("request {request:?}"));
+
                             match request {
+                                ServiceRequest::Request { request: Request::SetNoiseSuppression { enabled } } => {
+                                    // the filter is a source like any other, so
+                                    // this is only a matter of which is the default
+                                    let Some(request) = state.noise_suppression_request(enabled) else {
+                                        log::debug!("[service:audio] noise suppression is already {enabled} or there's no filter");
+                                        continue;
+                                    };
+
+                                    if let Err(err) = internal_request_tx.send(ServiceRequest::Request { request }) {
+                                        log::error!("[service:audio] error relaying service request: {err}");
+                                        continue;
+                                    };
+                                }
+                                ServiceRequest::Request { request: Request::AdjustSinkVolume { delta_percent } } => {
+                                    // the pulseaudio thread doesn't know the
+                                    // default sink, so it's resolved here
+                                    let Some(request) = state.adjust_sink_volume_request(delta_percent) else {
+                                        log::debug!("[service:audio] no default sink or its volume is already at the limit");
+                                        continue;
+                                    };
+
+                                    if let Err(err) = internal_request_tx.send(ServiceRequest::Request { request }) {
+                                        log::error!("[service:audio] error relaying service request: {err}");
+                                        continue;
+                                    };
+                                }
+                                ServiceRequest::Request { request: Request::ToggleSinkMute } => {
+                                    let Some(request) = state.toggle_sink_mute_request() else {
+                                        log::debug!("[service:audio] no default sink to toggle mute on");
+                                        continue;
+                                    };
+
+                                    if let Err(err) = internal_request_tx.send(ServiceRequest::Request { request }) {
+                                        log::error!("[service:audio] error relaying service request: {err}");
+                                        continue;
+                                    };
+                                }
+                                ServiceRequest::Request { request: Request::WatchLevels { module, rate } } => {
+                                    meters.watch(module, rate, &level_tx);
+                                }
+                                ServiceRequest::Request { request: Request::SetEqBand { band, gain } } => {
+                                    Self::equalize(state, &eq_event_tx, |equalizer| eq::set_band(equalizer, band, gain));
+                                }
+                                ServiceRequest::Request { request: Request::LoadEqPreset { name } } => {
+                                    Self::equalize(state, &eq_event_tx, |equalizer| eq::load_preset(equalizer, &name));
+                                }
                                 ServiceRequest::Request { request } => {
                                     // pulseaudio mainloop processes this instead
                                     if let Err(err) = internal_request_tx.send(ServiceRequest::Request { request: request.clone() }) {
@@ -184,12 +392,22 @@ impl Service for AudioService {
                                     if data.is_set(AudioSubscriptionData::SOURCE_PROFILE_CHANGED) {
                                         events.push(AudioEventType::SourceProfileChanged);
                                     }
+                                    if data.is_set(AudioSubscriptionData::DEFAULT_SINK_CHANGED)
+                                        || data.is_set(AudioSubscriptionData::DEFAULT_SOURCE_CHANGED)
+                                    {
+                                        events.push(AudioEventType::ActivePortChanged);
+                                    }
+                                    if data.is_set(AudioSubscriptionData::STREAMS_CHANGED) {
+                                        events.push(AudioEventType::SinkInputsChanged);
+                                        events.push(AudioEventType::SourceOutputsChanged);
+                                    }
 
-                                    module_ids.register_module(id, events);
-
-                                    // remove this bc its silly and not needed
-                                    // - aurora :3
-                                    log::debug!("[service:audio] module ids = {:?}", module_ids);
+                                    let diff = module_ids.register_module(id.clone(), events);
+                                    log::debug!("[service:audio] {id:?} registered, {diff}");
+                                }
+                                ServiceRequest::UnsubscribeModule { id } => {
+                                    meters.unwatch(&id, &level_tx);
+                                    module_ids.unregister_module(id);
                                 }
                             }
                         }
@@ -204,25 +422,161 @@ impl Service for AudioService {
 }
 
 impl AudioService {
+    /// updates the state with events from the mainloop and sends out the
+    /// ones that changed something
+    async fn emit(
+        state: &mut AudioState,
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        heartbeat: &Heartbeat,
+        events: Vec<Event>,
+    ) {
+        for event in events {
+            let _busy = heartbeat.busy(|| format!("event {event:?}"));
+
+            let events = state.update(event);
+            log::debug!("{:?}", events); // note: prob remove this, not needed
+
+            for event in events {
+                if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                    log::error!("[service:audio] error sending service event update: {err}");
+                    continue;
+                }
+            }
+        }
+    }
+
+    /// sends an `Event::Snapshot` after a batch of events from the mainloop,
+    /// when the batch changed something since the last one
+    async fn snapshot(
+        state: &AudioState,
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        last: &mut Option<u64>,
+    ) {
+        if *last == Some(state.generation) {
+            return;
+        }
+        *last = Some(state.generation);
+
+        let event = state.snapshot();
+        if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+            log::error!("[service:audio] error sending snapshot: {err}");
+        }
+    }
+
+    /// restores the saved devices or saves them after the state changed,
+    /// see `persist`
+    fn persist(
+        persist: &mut Option<Persist>,
+        state: &AudioState,
+        request_tx: &InstrumentedSender<flume::Sender<ServiceRequest<Self>>>,
+    ) {
+        let Some(persist) = persist else {
+            return;
+        };
+
+        for request in persist.update(state) {
+            if let Err(err) = request_tx.send(ServiceRequest::Request { request }) {
+                log::error!("[service:audio] error sending restore request: {err}");
+            }
+        }
+    }
+
+    /// switches bluetooth headsets' profiles after the state changed, see
+    /// `policy`
+    fn policy(
+        policy: &mut Option<Policy>,
+        state: &AudioState,
+        request_tx: &InstrumentedSender<flume::Sender<ServiceRequest<Self>>>,
+    ) {
+        let Some(policy) = policy else {
+            return;
+        };
+
+        for request in policy.update(state) {
+            if let Err(err) = request_tx.send(ServiceRequest::Request { request }) {
+                log::error!("[service:audio] error sending profile request: {err}");
+            }
+        }
+    }
+
+    /// looks for an equalizer again when sinks were added or removed, a
+    /// `filter-chain` or easyeffects shows up as one
+    fn probe_equalizer(
+        eq_sinks: &mut Option<Vec<String>>,
+        state: &AudioState,
+        event_tx: &InstrumentedSender<flume::Sender<Event>>,
+    ) {
+        let sinks = state
+            .sinks
+            .iter()
+            .map(|sink| sink.name.clone())
+            .collect::<Vec<String>>();
+
+        if eq_sinks.as_ref() == Some(&sinks) {
+            return;
+        }
+
+        *eq_sinks = Some(sinks);
+        eq::probe(event_tx.clone());
+    }
+
+    /// changes the equalizer with `change`, which returns it as it will be
+    /// once the change is made
+    fn equalize(
+        state: &AudioState,
+        event_tx: &InstrumentedSender<flume::Sender<Event>>,
+        change: impl FnOnce(&Equalizer) -> anyhow::Result<Equalizer>,
+    ) {
+        let Some(equalizer) = &state.equalizer else {
+            log::debug!("[service:audio] there's no equalizer to change");
+            return;
+        };
+
+        match change(equalizer) {
+            Ok(equalizer) => {
+                let event = Event::EqualizerChanged {
+                    equalizer: Some(equalizer),
+                };
+                if let Err(err) = event_tx.send(event) {
+                    log::error!("[service:audio] error sending the changed equalizer: {err}");
+                }
+            }
+            Err(err) => log::warn!("[service:audio] could not change the equalizer: {err}"),
+        }
+    }
+
     /// initialize mainloop for later setup
     ///
     /// returns the mainloop and context
     ///
-    /// todo: return custom error or anyhow to explain error
+    /// errors when there's no sound server to connect to
     pub fn init_mainloop() -> anyhow::Result<(Mainloop, Context)> {
-        let mut proplist = Proplist::new().unwrap();
-        proplist
+        let mut proplist = match Proplist::new() {
+            Some(proplist) => proplist,
+            None => return Err(anyhow!("could not create proplist")),
+        };
+        if proplist
             .set_str(properties::APPLICATION_NAME, "aurorashell")
-            .unwrap();
+            .is_err()
+        {
+            return Err(anyhow!("could not set application name in proplist"));
+        }
 
-        let mut mainloop = Mainloop::new().expect("error creating pulseaudio mainloop :3");
+        let mut mainloop = match Mainloop::new() {
+            Some(mainloop) => mainloop,
+            None => return Err(anyhow!("could not create pulseaudio mainloop")),
+        };
 
-        let mut context = Context::new_with_proplist(&mainloop, "aurorashell", &proplist)
-            .expect("Failed to create context");
+        let mut context = match Context::new_with_proplist(&mainloop, "aurorashell", &proplist) {
+            Some(context) => context,
+            None => return Err(anyhow!("could not create pulseaudio context")),
+        };
 
-        context
-            .connect(None, FlagSet::NOFLAGS, None)
-            .expect("Failed to connect to PulseAudio");
+        // fails when there's no server running, with pipewire this means
+        // pipewire-pulse isn't running
+        if let Err(err) = context.connect(None, FlagSet::NOFLAGS, None) {
+            return Err(anyhow!("could not connect to a sound server: {err}"));
+        }
 
         // wait for context to be ready
         loop {
@@ -254,17 +608,23 @@ impl AudioService {
     ///
     /// this code can't be part of `Self::run` as the pulseaudio mainloop
     /// doesn't like async
+    ///
+    /// `ready_tx` is sent whether the sound server could be connected to
     fn mainloop(
-        event_tx: flume::Sender<Event>,
+        event_tx: InstrumentedSender<flume::Sender<Event>>,
         request_rx: flume::Receiver<ServiceRequest<Self>>,
         mut request_state: AudioRequestThreadState,
+        ready_tx: flume::Sender<Result<(), String>>,
     ) {
         // thread to handle events to modules
         thread::spawn(move || {
             let (mut mainloop, mut context) = match Self::init_mainloop() {
-                Ok(res) => res,
+                Ok(res) => {
+                    let _ = ready_tx.send(Ok(()));
+                    res
+                }
                 Err(err) => {
-                    log::error!("[audio] error while creating mainloop: {err}");
+                    let _ = ready_tx.send(Err(err.to_string()));
                     return;
                 }
             };
@@ -275,11 +635,16 @@ impl AudioService {
             get_sources(&introspector, event_tx.clone());
             get_default_devices(&introspector, event_tx.clone());
             get_cards(&introspector, event_tx.clone());
+            get_recording(&introspector, event_tx.clone());
+            get_sink_inputs(&introspector, event_tx.clone());
+            get_source_outputs(&introspector, event_tx.clone());
 
             let interest_mask = InterestMaskSet::SERVER
                 | InterestMaskSet::CLIENT
                 | InterestMaskSet::SOURCE
                 | InterestMaskSet::SINK
+                | InterestMaskSet::SINK_INPUT
+                | InterestMaskSet::SOURCE_OUTPUT
                 | InterestMaskSet::CARD;
 
             context.subscribe(interest_mask, |success| {
@@ -301,6 +666,13 @@ impl AudioService {
                             subscribe::Facility::Source => {
                                 get_sources(&introspector, event_tx.clone());
                             }
+                            subscribe::Facility::SinkInput => {
+                                get_sink_inputs(&introspector, event_tx.clone());
+                            }
+                            subscribe::Facility::SourceOutput => {
+                                get_recording(&introspector, event_tx.clone());
+                                get_source_outputs(&introspector, event_tx.clone());
+                            }
                             _ => (),
                         };
                     }
@@ -312,6 +684,13 @@ impl AudioService {
                             subscribe::Facility::Source => {
                                 get_sources(&introspector, event_tx.clone());
                             }
+                            subscribe::Facility::SinkInput => {
+                                get_sink_inputs(&introspector, event_tx.clone());
+                            }
+                            subscribe::Facility::SourceOutput => {
+                                get_recording(&introspector, event_tx.clone());
+                                get_source_outputs(&introspector, event_tx.clone());
+                            }
                             _ => (),
                         };
                     }
@@ -329,6 +708,13 @@ impl AudioService {
                             subscribe::Facility::Source => {
                                 get_sources(&introspector, event_tx.clone());
                             }
+                            subscribe::Facility::SinkInput => {
+                                get_sink_inputs(&introspector, event_tx.clone());
+                            }
+                            subscribe::Facility::SourceOutput => {
+                                get_recording(&introspector, event_tx.clone());
+                                get_source_outputs(&introspector, event_tx.clone());
+                            }
                             _ => (),
                         };
                     }
@@ -339,10 +725,9 @@ impl AudioService {
                 let result = mainloop.iterate(true);
                 match result {
                     IterateResult::Quit(q) => {
-                        // note: shouldn't panic here but idrc for now :3
-                        // gracefully attempt a service restart
-                        log::error!("[audio] [pulseaudio thread 1] [PANIC] mainloop quit: {q:?}");
-                        panic!();
+                        // dropping `event_tx` makes the service restart
+                        log::error!("[audio] [pulseaudio thread 1] mainloop quit: {q:?}");
+                        return;
                     }
                     IterateResult::Err(e) => {
                         // note: need to only allow errors a few times then
@@ -368,19 +753,22 @@ impl AudioService {
                 }
             };
 
+            // a test left running when the shell or service stopped would
+            // keep playing the microphone back
+            if let Err(err) = loopback::stop(&mut mainloop, &context) {
+                log::warn!("[audio] could not remove a leftover microphone test: {err}");
+            }
+
             loop {
                 let result = match request_rx.recv() {
                     Ok(res) => res,
                     Err(err) => {
-                        log::warn!(
+                        // the service stopped so this thread isn't needed anymore
+                        log::debug!(
                             "[service:audio] [pulseaudio thread 2] could not receive request for \
-                             mainloop, error: {err}"
+                             mainloop, stopping: {err}"
                         );
-                        log::warn!(
-                            "[service:audio] [pulseaudio thread 2] retrying in 5 seconds..."
-                        );
-                        thread::sleep(Duration::from_secs(5));
-                        continue;
+                        return;
                     }
                 };
 
@@ -403,9 +791,23 @@ impl AudioService {
                                 .introspect()
                                 .set_sink_mute_by_name(name.as_str(), state, None);
                         }
+                        Request::SetSinkPort { name, port } => {
+                            context.introspect().set_sink_port_by_name(
+                                name.as_str(),
+                                port.as_str(),
+                                None,
+                            );
+                        }
                         Request::SetDefaultSource { name } => {
                             context.set_default_source(name.as_str(), |_| {});
                         }
+                        Request::SetSourcePort { name, port } => {
+                            context.introspect().set_source_port_by_name(
+                                name.as_str(),
+                                port.as_str(),
+                                None,
+                            );
+                        }
                         Request::SetSourceVolume { name, volume } => {
                             context.introspect().set_source_volume_by_name(
                                 name.as_str(),
@@ -413,6 +815,21 @@ impl AudioService {
                                 None,
                             );
                         }
+                        Request::SetSinkInputVolume { index, volume } => {
+                            context
+                                .introspect()
+                                .set_sink_input_volume(index, &volume, None);
+                        }
+                        Request::SetSinkInputMute { index, state } => {
+                            context.introspect().set_sink_input_mute(index, state, None);
+                        }
+                        Request::MoveSinkInput { index, sink } => {
+                            context.introspect().move_sink_input_by_name(
+                                index,
+                                sink.as_str(),
+                                None,
+                            );
+                        }
                         Request::SetCardProfile {
                             card_name,
                             profile_name,
@@ -423,6 +840,40 @@ impl AudioService {
                                 None,
                             );
                         }
+                        // these wait for their answers themselves, iterating
+                        // again below would block until the next one
+                        Request::CreateCombinedSink { members } => {
+                            if let Err(err) = combine::create(&mut mainloop, &context, &members) {
+                                log::error!("[audio] could not create the combined sink: {err}");
+                            }
+                            continue;
+                        }
+                        Request::RemoveCombinedSink => {
+                            if let Err(err) = combine::remove(&mut mainloop, &context) {
+                                log::error!("[audio] could not remove the combined sink: {err}");
+                            }
+                            continue;
+                        }
+                        Request::StartMicTest { latency_msec } => {
+                            if let Err(err) = loopback::start(&mut mainloop, &context, latency_msec)
+                            {
+                                log::error!("[audio] could not start the microphone test: {err}");
+                            }
+                            continue;
+                        }
+                        Request::StopMicTest => {
+                            if let Err(err) = loopback::stop(&mut mainloop, &context) {
+                                log::error!("[audio] could not stop the microphone test: {err}");
+                            }
+                            continue;
+                        }
+                        // the service handles these as they need the state
+                        Request::SetNoiseSuppression { .. }
+                        | Request::AdjustSinkVolume { .. }
+                        | Request::ToggleSinkMute
+                        | Request::SetEqBand { .. }
+                        | Request::LoadEqPreset { .. }
+                        | Request::WatchLevels { .. } => continue,
                     },
                     _ => {}
                 };
@@ -430,11 +881,12 @@ impl AudioService {
                 let result = mainloop.iterate(true);
                 match result {
                     IterateResult::Quit(q) => {
-                        // probably shouldn't panic here but idrc for now :3
-                        panic!("[audio] [pulseaudio thread 2] mainloop quit: {q:?}");
+                        log::error!("[audio] [pulseaudio thread 2] mainloop quit: {q:?}");
+                        return;
                     }
                     IterateResult::Err(e) => {
-                        panic!("[audio] [pulseaudio thread 2] mainloop error: {e}");
+                        log::error!("[audio] [pulseaudio thread 2] mainloop error: {e}");
+                        return;
                     }
                     _ => {}
                 };
@@ -0,0 +1,204 @@
+//! remembers the default sink and source and their volumes in
+//! `~/.local/state/aurorashell/audio.json` when `[services.audio] restore`
+//! is set, for sound servers that forget them across reboots
+//!
+//! they're set again once per start of the shell, the first time the
+//! service connects and the saved devices show up. after that the file is
+//! written whenever they change
+
+use super::data::Request;
+use super::state::AudioState;
+
+use crate::config;
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// set once the saved devices were restored (or there was nothing to
+/// restore), so reconnecting to the sound server doesn't undo changes made
+/// since
+static RESTORED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedAudio {
+    pub default_sink: Option<SavedDevice>,
+    pub default_source: Option<SavedDevice>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedDevice {
+    /// see `Sink.name`
+    pub name: String,
+    /// the average volume of all channels from 0.0 - 100.0
+    pub volume: f32,
+}
+
+impl SavedAudio {
+    fn path() -> anyhow::Result<PathBuf> {
+        return Ok(config::state_dir()?.join("audio.json"));
+    }
+
+    /// reads the saved devices, nothing is saved if the file doesn't exist
+    fn load() -> anyhow::Result<Self> {
+        let source = match fs::read_to_string(Self::path()?) {
+            Ok(source) => source,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        return Ok(serde_json::from_str(&source)?);
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // written next to the file and moved over it so a crash while
+        // writing doesn't leave half a file
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp, &path)?;
+
+        return Ok(());
+    }
+
+    fn from_state(state: &AudioState) -> Self {
+        Self {
+            default_sink: state.get_default_sink().map(|sink| SavedDevice {
+                name: sink.name,
+                volume: AudioState::volume_percent(sink.volume),
+            }),
+            default_source: state.get_default_source().map(|source| SavedDevice {
+                name: source.name,
+                volume: AudioState::volume_percent(source.volume),
+            }),
+        }
+    }
+}
+
+/// restores the saved devices then keeps the file up to date, made for
+/// each run of the service
+#[derive(Debug)]
+pub struct Persist {
+    /// what's left to restore, taken once the sinks or sources are known
+    pending: SavedAudio,
+    /// what the file holds, so it's only written when something changed
+    saved: Option<SavedAudio>,
+}
+
+impl Persist {
+    /// `None` when restoring is turned off in the config
+    pub fn new() -> Option<Self> {
+        if !config::get().services.audio.restore {
+            return None;
+        }
+
+        if RESTORED.load(Ordering::Acquire) {
+            return Some(Self {
+                pending: SavedAudio::default(),
+                saved: None,
+            });
+        }
+
+        let saved = match SavedAudio::load() {
+            Ok(saved) => saved,
+            Err(err) => {
+                log::error!("[service:audio] could not read the saved devices: {err}");
+                SavedAudio::default()
+            }
+        };
+
+        return Some(Self {
+            pending: saved.clone(),
+            saved: Some(saved),
+        });
+    }
+
+    /// called after the state changed, returns the requests that restore
+    /// the saved devices
+    pub fn update(&mut self, state: &AudioState) -> Vec<Request> {
+        let mut requests = vec![];
+
+        if !state.sinks.is_empty()
+            && let Some(saved) = self.pending.default_sink.take()
+        {
+            match state.sinks.iter().find(|sink| sink.name == saved.name) {
+                Some(sink) => {
+                    log::info!("[service:audio] restoring the default sink {}", saved.name);
+
+                    if state.default_sink.as_ref() != Some(&saved.name) {
+                        requests.push(Request::SetDefaultSink {
+                            name: saved.name.clone(),
+                        });
+                    }
+                    requests.push(Request::SetSinkVolume {
+                        name: saved.name,
+                        volume: AudioState::set_channel_volume(sink.volume, saved.volume),
+                    });
+                }
+                None => log::info!(
+                    "[service:audio] the saved default sink {} isn't there, not restoring it",
+                    saved.name
+                ),
+            }
+        }
+
+        if !state.sources.is_empty()
+            && let Some(saved) = self.pending.default_source.take()
+        {
+            match state
+                .sources
+                .iter()
+                .find(|source| source.name == saved.name)
+            {
+                Some(source) => {
+                    log::info!(
+                        "[service:audio] restoring the default source {}",
+                        saved.name
+                    );
+
+                    if state.default_source.as_ref() != Some(&saved.name) {
+                        requests.push(Request::SetDefaultSource {
+                            name: saved.name.clone(),
+                        });
+                    }
+                    requests.push(Request::SetSourceVolume {
+                        name: saved.name,
+                        volume: AudioState::set_channel_volume(source.volume, saved.volume),
+                    });
+                }
+                None => log::info!(
+                    "[service:audio] the saved default source {} isn't there, not restoring it",
+                    saved.name
+                ),
+            }
+        }
+
+        // the devices from before the restore would be saved over the file,
+        // the restored ones are saved once they come back as events
+        if self.pending != SavedAudio::default() || !requests.is_empty() {
+            return requests;
+        }
+        RESTORED.store(true, Ordering::Release);
+
+        // nothing is known while disconnected
+        if state.sinks.is_empty() && state.sources.is_empty() {
+            return requests;
+        }
+
+        let current = SavedAudio::from_state(state);
+        if self.saved.as_ref() != Some(&current) {
+            if let Err(err) = current.save() {
+                log::error!("[service:audio] could not save the default devices: {err}");
+            }
+            self.saved = Some(current);
+        }
+
+        return requests;
+    }
+}
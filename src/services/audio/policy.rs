@@ -0,0 +1,177 @@
+//! switches bluetooth headsets to `bluetooth_profile` under
+//! `[services.audio]` when they connect, and with `bluetooth_autoswitch` over
+//! to their headset profile while something records so the microphone can
+//! be used, then back once nothing does
+//!
+//! the profile changes come back from the sound server as `CardsChanged`
+//! events like any other, so widgets showing the profile follow along
+
+use super::data::{Card, Profile, Request};
+use super::state::AudioState;
+
+use crate::config::{self, BluetoothProfile};
+
+use std::collections::{HashMap, HashSet};
+
+/// what the sound server starts the names of bluetooth cards with, for both
+/// pulseaudio and pipewire
+const BLUETOOTH_CARD_PREFIX: &str = "bluez_card.";
+
+/// made for each run of the service
+#[derive(Debug)]
+pub struct Policy {
+    /// the bluetooth cards that were there at the last update, `None` before
+    /// the first list of cards. the cards connected before the service
+    /// started are left how they are
+    known: Option<HashSet<String>>,
+    /// the cards switched to their headset profile for recording, with the
+    /// profile to go back to
+    switched: HashMap<String, String>,
+}
+
+impl Policy {
+    /// `None` when neither option is set in the config
+    pub fn new() -> Option<Self> {
+        let config = &config::get().services.audio;
+        if config.bluetooth_profile.is_none() && !config.bluetooth_autoswitch {
+            return None;
+        }
+
+        return Some(Self {
+            known: None,
+            switched: HashMap::new(),
+        });
+    }
+
+    /// called after the state changed, returns the profiles to switch to
+    pub fn update(&mut self, state: &AudioState) -> Vec<Request> {
+        let config = &config::get().services.audio;
+        let mut requests = vec![];
+
+        if self.known.is_none() && state.cards.is_empty() {
+            return requests;
+        }
+
+        let cards = state
+            .cards
+            .iter()
+            .filter(|card| card.name.starts_with(BLUETOOTH_CARD_PREFIX))
+            .collect::<Vec<&Card>>();
+
+        let first = self.known.is_none();
+        let known = self.known.get_or_insert_with(HashSet::new);
+
+        for card in &cards {
+            if !known.insert(card.name.clone()) || first {
+                continue;
+            }
+
+            let Some(preferred) = config.bluetooth_profile else {
+                continue;
+            };
+
+            if let Some(profile) = best_profile(card, preferred)
+                && card
+                    .selected_profile
+                    .as_ref()
+                    .map(|selected| &selected.name)
+                    != Some(&profile.name)
+            {
+                log::info!(
+                    "[service:audio] {} connected, switching it to {}",
+                    card.name,
+                    profile.name
+                );
+
+                requests.push(Request::SetCardProfile {
+                    card_name: card.name.clone(),
+                    profile_name: profile.name.clone(),
+                });
+            }
+        }
+
+        // a headset that reconnects counts as connecting again
+        known.retain(|name| cards.iter().any(|card| &card.name == name));
+        self.switched
+            .retain(|name, _| cards.iter().any(|card| &card.name == name));
+
+        if !config.bluetooth_autoswitch {
+            return requests;
+        }
+
+        // only the headset that's being played to is switched, others could
+        // be connected without being used
+        let Some(card_index) = state.get_default_sink().and_then(|sink| sink.card_index) else {
+            return requests;
+        };
+        let Some(card) = cards.iter().find(|card| card.index == card_index) else {
+            return requests;
+        };
+        let selected = card
+            .selected_profile
+            .as_ref()
+            .and_then(|profile| Some((profile, profile_kind(&profile.name)?)));
+
+        match (state.recording, selected) {
+            // the card keeps its old profile until the sound server's event
+            // for the switch comes back
+            (true, Some((profile, BluetoothProfile::A2dp)))
+                if !self.switched.contains_key(&card.name) =>
+            {
+                if let Some(headset) = best_profile(card, BluetoothProfile::Hfp) {
+                    log::info!(
+                        "[service:audio] something is recording, switching {} to {}",
+                        card.name,
+                        headset.name
+                    );
+
+                    self.switched
+                        .insert(card.name.clone(), profile.name.clone());
+                    requests.push(Request::SetCardProfile {
+                        card_name: card.name.clone(),
+                        profile_name: headset.name.clone(),
+                    });
+                }
+            }
+            (false, Some((_, BluetoothProfile::Hfp))) => {
+                if let Some(previous) = self.switched.remove(&card.name) {
+                    log::info!(
+                        "[service:audio] nothing is recording, switching {} back to {previous}",
+                        card.name
+                    );
+
+                    requests.push(Request::SetCardProfile {
+                        card_name: card.name.clone(),
+                        profile_name: previous,
+                    });
+                }
+            }
+            _ => {}
+        }
+
+        return requests;
+    }
+}
+
+/// which kind of profile a bluetooth card's profile is by its name, like
+/// `a2dp_sink` or `headset_head_unit` with pulseaudio and `a2dp-sink-aac` or
+/// `headset-head-unit-msbc` with pipewire
+fn profile_kind(name: &str) -> Option<BluetoothProfile> {
+    if name.starts_with("a2dp") {
+        return Some(BluetoothProfile::A2dp);
+    }
+    if name.contains("head_unit") || name.contains("head-unit") {
+        return Some(BluetoothProfile::Hfp);
+    }
+
+    return None;
+}
+
+/// the available profile of `kind` the sound server ranks highest, with
+/// pipewire there's one for each codec
+fn best_profile(card: &Card, kind: BluetoothProfile) -> Option<&Profile> {
+    card.profiles
+        .iter()
+        .filter(|profile| profile.available && profile_kind(&profile.name) == Some(kind))
+        .max_by_key(|profile| profile.priority)
+}
@@ -1,9 +1,602 @@
-use super::Event;
+//! what modules read about audio
+//!
+//! every module registered for `PulseAudio` gets a small summary when
+//! anything changes. the lists of sinks, sources and cards can be long (a
+//! card has a profile for every combination of its ports) so they're only
+//! serialized one device at a time, when a module asks for it
+
+use super::data::{AudioEventType, Card, Port};
+use super::eq::{Equalizer, EqualizerKind};
+use super::loopback;
+use super::{
+    AudioService, AudioState, AudioSubscriptionData, Event, Request, Sink, Source, Stream,
+};
 
 use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
 
+/// the layout modules get audio events in through `service_event`, a u8 for
+/// the kind of event then what changed
+///
+/// the lists of devices are only counted (a u16), the devices themselves are
+/// read with `audio_detail` like they are from the summary. names are a u16
+/// length then the bytes, empty when there's no device
+///
+/// - 0 sinks changed, 1 the default sink's name, 2 sources changed, 3 the
+///   default source's name, 4 cards changed, 5 the default sink's profile
+///   name, 6 the default source's profile name
+/// - 7 a u8 for something recording, 8 a u8 for a noise filter being there
+///   and a u8 for it being the default source, 9 a u8 for an equalizer
+///   being there
+/// - 10 the service is unavailable with the reason, 11 it's available again
+/// - 12 applications' streams playing to sinks changed, 13 streams recording
+///   from sources changed
+/// - 14 the names of the default sink's and source's active ports
+/// - 15 an f32 for the default sink's level and one for the default
+///   source's, only for modules with an `AudioMeter` register
 impl WasmSerializable for Event {
-    fn serialise(self) -> &'static [u8] {
-        &[]
+    fn service_id(&self) -> u16 {
+        AudioService::ID
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        let mut bytes = vec![];
+
+        match self {
+            Event::SinksChanged { sinks } => {
+                bytes.push(0);
+                bytes.extend(count(sinks.len()).to_le_bytes());
+            }
+            Event::DefaultSinkChanged { name } => {
+                bytes.push(1);
+                string(&mut bytes, name.as_deref().unwrap_or_default());
+            }
+            Event::SourcesChanged { sources } => {
+                bytes.push(2);
+                bytes.extend(count(sources.len()).to_le_bytes());
+            }
+            Event::DefaultSourceChanged { name } => {
+                bytes.push(3);
+                string(&mut bytes, name.as_deref().unwrap_or_default());
+            }
+            Event::CardsChanged { cards } => {
+                bytes.push(4);
+                bytes.extend(count(cards.len()).to_le_bytes());
+            }
+            Event::SinkProfileChanged { profile_name } => {
+                bytes.push(5);
+                string(&mut bytes, profile_name.as_deref().unwrap_or_default());
+            }
+            Event::SourceProfileChanged { profile_name } => {
+                bytes.push(6);
+                string(&mut bytes, profile_name.as_deref().unwrap_or_default());
+            }
+            Event::ActivePortChanged {
+                sink_port,
+                source_port,
+            } => {
+                bytes.push(14);
+                string(&mut bytes, sink_port.as_deref().unwrap_or_default());
+                string(&mut bytes, source_port.as_deref().unwrap_or_default());
+            }
+            Event::SinkInputsChanged { inputs } => {
+                bytes.push(12);
+                bytes.extend(count(inputs.len()).to_le_bytes());
+            }
+            Event::SourceOutputsChanged { outputs } => {
+                bytes.push(13);
+                bytes.extend(count(outputs.len()).to_le_bytes());
+            }
+            Event::RecordingChanged { recording } => {
+                bytes.push(7);
+                bytes.push(*recording as u8);
+            }
+            Event::NoiseSuppressionChanged { available, enabled } => {
+                bytes.push(8);
+                bytes.push(*available as u8);
+                bytes.push(*enabled as u8);
+            }
+            Event::EqualizerChanged { equalizer } => {
+                bytes.push(9);
+                bytes.push(equalizer.is_some() as u8);
+            }
+            Event::ServiceUnavailable { reason } => {
+                bytes.push(10);
+                string(&mut bytes, reason);
+            }
+            Event::ServiceAvailable => bytes.push(11),
+            Event::LevelsChanged { levels } => {
+                bytes.push(15);
+                bytes.extend(levels.sink.to_le_bytes());
+                bytes.extend(levels.source.to_le_bytes());
+            }
+            // modules read the devices from the summary instead
+            Event::Snapshot { .. } => return None,
+        }
+
+        return Some(bytes);
+    }
+
+    fn is_for(&self, register: &SubscriptionData) -> bool {
+        let levels = matches!(self, Event::LevelsChanged { .. });
+
+        return match register {
+            SubscriptionData::PulseAudio { data } => {
+                !levels && self.subscription().is_none_or(|bit| data.is_set(bit))
+            }
+            SubscriptionData::AudioMeter { .. } => levels,
+            _ => false,
+        };
+    }
+}
+
+/// written instead of an index when there's no device
+const NO_INDEX: u16 = u16::MAX;
+
+/// a copy of the audio state for the wasm runtime, shared between the
+/// modules registered for audio
+#[derive(Debug, Clone)]
+pub struct AudioDevices {
+    pub sinks: Vec<Sink>,
+    pub default_sink: Option<String>,
+    pub sources: Vec<Source>,
+    pub default_source: Option<String>,
+    pub cards: Vec<Card>,
+    pub sink_inputs: Vec<Stream>,
+    pub source_outputs: Vec<Stream>,
+    pub recording: bool,
+    /// whether the noise filter is the default source, `None` without one
+    pub noise_suppression: Option<bool>,
+    pub equalizer: Option<Equalizer>,
+    pub available: bool,
+}
+
+/// which list `AudioDevices::detail` reads from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioDetail {
+    Sink,
+    Source,
+    Card,
+    Equalizer,
+    SinkInput,
+    SourceOutput,
+}
+
+impl TryFrom<u32> for AudioDetail {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Sink),
+            1 => Ok(Self::Source),
+            2 => Ok(Self::Card),
+            3 => Ok(Self::Equalizer),
+            4 => Ok(Self::SinkInput),
+            5 => Ok(Self::SourceOutput),
+            value => Err(value),
+        }
+    }
+}
+
+impl AudioState {
+    pub fn devices(&self) -> AudioDevices {
+        AudioDevices {
+            sinks: self.sinks.clone(),
+            default_sink: self.default_sink.clone(),
+            sources: self.sources.clone(),
+            default_source: self.default_source.clone(),
+            cards: self.cards.clone(),
+            sink_inputs: self.sink_inputs.clone(),
+            source_outputs: self.source_outputs.clone(),
+            recording: self.recording,
+            noise_suppression: self
+                .noise_filter
+                .is_some()
+                .then(|| self.noise_suppression()),
+            equalizer: self.equalizer.clone(),
+            available: self.unavailable.is_none(),
+        }
+    }
+}
+
+impl Event {
+    /// the register bit a module needs to hear about this event, `None` for
+    /// the events about the service itself which every module hears about
+    pub fn subscription(&self) -> Option<AudioSubscriptionData> {
+        match self.event_type()? {
+            AudioEventType::SinksChanged => Some(AudioSubscriptionData::SINKS_CHANGED),
+            AudioEventType::DefaultSinkChanged => Some(AudioSubscriptionData::DEFAULT_SINK_CHANGED),
+            AudioEventType::SourcesChanged => Some(AudioSubscriptionData::SOURCES_CHANGED),
+            AudioEventType::DefaultSourceChanged => {
+                Some(AudioSubscriptionData::DEFAULT_SOURCE_CHANGED)
+            }
+            AudioEventType::CardsChanged => Some(AudioSubscriptionData::CARDS_CHANGED),
+            AudioEventType::SinkProfileChanged => Some(AudioSubscriptionData::SINK_PROFILE_CHANGED),
+            AudioEventType::SourceProfileChanged => {
+                Some(AudioSubscriptionData::SOURCE_PROFILE_CHANGED)
+            }
+            // shown next to the sources, like a microphone being in use
+            // about the default devices, it's the same for either
+            AudioEventType::ActivePortChanged => Some(
+                AudioSubscriptionData::DEFAULT_SINK_CHANGED
+                    | AudioSubscriptionData::DEFAULT_SOURCE_CHANGED,
+            ),
+            AudioEventType::SinkInputsChanged | AudioEventType::SourceOutputsChanged => {
+                Some(AudioSubscriptionData::STREAMS_CHANGED)
+            }
+            AudioEventType::RecordingChanged => Some(AudioSubscriptionData::SOURCES_CHANGED),
+            AudioEventType::NoiseSuppressionChanged => Some(AudioSubscriptionData::SOURCES_CHANGED),
+            // the equalizer is a sink, or plays to one
+            AudioEventType::EqualizerChanged => Some(AudioSubscriptionData::SINKS_CHANGED),
+        }
+    }
+}
+
+impl Request {
+    /// the request a module asked for through `audio_request`, `None` for an
+    /// unknown action or a sink name the sound server couldn't be given
+    ///
+    /// 0 creates the combined sink with the sink names in `text` on a line
+    /// each (all sinks when it's empty) and 1 removes it. 2 starts the
+    /// microphone test with the latency in milliseconds in `text` (the
+    /// default when it's empty) and 3 stops it. 4 turns noise suppression
+    /// on and 5 turns it off. 6 sets an equalizer band with its index and
+    /// gain on a line each in `text`, and 7 loads the preset named `text`
+    ///
+    /// 8 sets the volume of an application's stream, 9 mutes it (`1`) or
+    /// unmutes it (`0`) and 10 moves it to the sink with the name given.
+    /// `text` is the stream's index then that on a line each. the volume is
+    /// in percent and is set on every channel of the stream in `devices`
+    ///
+    /// 11 switches a sink to one of its ports and 12 a source, `text` is the
+    /// device's name then the port's name on a line each
+    ///
+    /// 13 raises the default sink's volume by the percent in `text`, or
+    /// lowers it when it's negative, and 14 mutes or unmutes it
+    pub fn from_module(action: u32, text: &str, devices: Option<&AudioDevices>) -> Option<Self> {
+        match action {
+            0 => {
+                let members = text
+                    .lines()
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<String>>();
+
+                // they're passed to the sound server in a list of arguments
+                let invalid = |c: char| c.is_whitespace() || matches!(c, ',' | '"' | '\'');
+                if members.iter().any(|name| name.contains(invalid)) {
+                    return None;
+                }
+
+                Some(Self::CreateCombinedSink { members })
+            }
+            1 => Some(Self::RemoveCombinedSink),
+            2 => {
+                let latency_msec = match text.trim() {
+                    "" => loopback::DEFAULT_LATENCY_MSEC,
+                    latency => latency.parse::<u32>().ok()?,
+                };
+
+                Some(Self::StartMicTest { latency_msec })
+            }
+            3 => Some(Self::StopMicTest),
+            4 => Some(Self::SetNoiseSuppression { enabled: true }),
+            5 => Some(Self::SetNoiseSuppression { enabled: false }),
+            6 => {
+                let (band, gain) = text.split_once('\n')?;
+                let gain = gain
+                    .trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|gain| gain.is_finite())?;
+
+                Some(Self::SetEqBand {
+                    band: band.trim().parse().ok()?,
+                    gain,
+                })
+            }
+            7 => Some(Self::LoadEqPreset {
+                name: text.to_string(),
+            }),
+            8..=10 => {
+                let (index, value) = text.split_once('\n')?;
+                let index = index.trim().parse::<u32>().ok()?;
+                let value = value.trim();
+
+                match action {
+                    8 => {
+                        let percent = value
+                            .parse::<f32>()
+                            .ok()
+                            .filter(|percent| percent.is_finite())?;
+                        let stream = devices?
+                            .sink_inputs
+                            .iter()
+                            .find(|stream| stream.index == index)?;
+
+                        Some(Self::SetSinkInputVolume {
+                            index,
+                            volume: AudioState::set_channel_volume(
+                                stream.volume,
+                                percent.clamp(0.0, 100.0),
+                            ),
+                        })
+                    }
+                    9 => Some(Self::SetSinkInputMute {
+                        index,
+                        state: match value {
+                            "1" => true,
+                            "0" => false,
+                            _ => return None,
+                        },
+                    }),
+                    _ if value.is_empty() => None,
+                    _ => Some(Self::MoveSinkInput {
+                        index,
+                        sink: value.to_string(),
+                    }),
+                }
+            }
+            11 | 12 => {
+                let (name, port) = text.split_once('\n')?;
+                let (name, port) = (name.trim().to_string(), port.trim().to_string());
+                if name.is_empty() || port.is_empty() {
+                    return None;
+                }
+
+                match action {
+                    11 => Some(Self::SetSinkPort { name, port }),
+                    _ => Some(Self::SetSourcePort { name, port }),
+                }
+            }
+            13 => Some(Self::AdjustSinkVolume {
+                delta_percent: text
+                    .trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|delta| delta.is_finite())?,
+            }),
+            14 => Some(Self::ToggleSinkMute),
+            _ => None,
+        }
+    }
+}
+
+impl AudioDevices {
+    /// the layout modules read the summary in, little endian, 0x1C bytes
+    ///
+    /// - 0x00: u16 amount of sinks, sources and cards
+    /// - 0x06: u16 index of the default sink and source, `0xFFFF` for none
+    /// - 0x0A: u8 flags, bit 0 is the sound server being available, bit 1 the
+    ///   default sink being muted, bit 2 the default source, bit 3
+    ///   something recording, bit 4 there being a noise suppression filter,
+    ///   bit 5 it being the default source and bit 6 there being an
+    ///   equalizer
+    /// - 0x0C: f32 volume percent of the default sink and source
+    /// - 0x14: u32 revision, changes with every update so a module knows to
+    ///   read the details it kept again
+    /// - 0x18: u16 amount of applications' streams playing to sinks and
+    ///   recording from sources
+    pub fn summary(&self, revision: u32) -> Vec<u8> {
+        let default_sink = self
+            .default_sink
+            .as_ref()
+            .and_then(|name| self.sinks.iter().position(|sink| &sink.name == name));
+        let default_source = self
+            .default_source
+            .as_ref()
+            .and_then(|name| self.sources.iter().position(|source| &source.name == name));
+
+        let mut flags = self.available as u8;
+        if default_sink.is_some_and(|index| self.sinks[index].mute) {
+            flags |= 1 << 1;
+        }
+        if default_source.is_some_and(|index| self.sources[index].mute) {
+            flags |= 1 << 2;
+        }
+        if self.recording {
+            flags |= 1 << 3;
+        }
+        if let Some(enabled) = self.noise_suppression {
+            flags |= 1 << 4;
+            if enabled {
+                flags |= 1 << 5;
+            }
+        }
+        if self.equalizer.is_some() {
+            flags |= 1 << 6;
+        }
+
+        let sink_volume = default_sink.map_or(0.0, |index| {
+            AudioState::volume_percent(self.sinks[index].volume)
+        });
+        let source_volume = default_source.map_or(0.0, |index| {
+            AudioState::volume_percent(self.sources[index].volume)
+        });
+
+        let mut bytes = Vec::with_capacity(0x1C);
+        bytes.extend(count(self.sinks.len()).to_le_bytes());
+        bytes.extend(count(self.sources.len()).to_le_bytes());
+        bytes.extend(count(self.cards.len()).to_le_bytes());
+        bytes.extend(default_sink.map_or(NO_INDEX, count).to_le_bytes());
+        bytes.extend(default_source.map_or(NO_INDEX, count).to_le_bytes());
+        bytes.extend([flags, 0]);
+        bytes.extend(sink_volume.to_le_bytes());
+        bytes.extend(source_volume.to_le_bytes());
+        bytes.extend(revision.to_le_bytes());
+        bytes.extend(count(self.sink_inputs.len()).to_le_bytes());
+        bytes.extend(count(self.source_outputs.len()).to_le_bytes());
+
+        return bytes;
+    }
+
+    /// one sink, source or card in the layout modules read it in, `None`
+    /// when there's no device at `index`
+    ///
+    /// strings are a u16 length followed by that many bytes of utf-8
+    ///
+    /// sinks and sources are the name and description, the volume percent as
+    /// an f32, a u8 for being muted, the u32 index of their card in the
+    /// list of cards (`0xFFFFFFFF` for none), then their ports
+    ///
+    /// cards are the name, a u16 amount of profiles, the u16 index of the
+    /// selected one (`0xFFFF` for none), the name and description of each
+    /// profile, then the ports of all the card's sinks and sources with
+    /// `0xFFFF` as the active one
+    ///
+    /// ports are a u16 amount of them, the u16 index of the active one
+    /// (`0xFFFF` for none), then the name, description and a u8 for being
+    /// plugged in of each port
+    ///
+    /// applications' streams are the stream's u32 index, the application's
+    /// name and icon name (empty for none), the volume percent as an f32, a
+    /// u8 for being muted and the u32 index of their sink or source in its
+    /// list, `0xFFFFFFFF` for none
+    ///
+    /// the equalizer (`index` is ignored) is its name, a u8 for the bands
+    /// being settable, a u16 amount of bands and a u16 amount of presets,
+    /// then the name, f32 frequency (0.0 for none) and f32 gain of each band
+    /// and the name of each preset
+    pub fn detail(&self, kind: AudioDetail, index: usize) -> Option<Vec<u8>> {
+        let mut bytes = vec![];
+
+        match kind {
+            AudioDetail::Sink => {
+                let sink = self.sinks.get(index)?;
+                device(
+                    &mut bytes,
+                    &sink.name,
+                    &sink.description,
+                    AudioState::volume_percent(sink.volume),
+                    sink.mute,
+                    self.card_position(sink.card_index),
+                );
+                ports(&mut bytes, &sink.ports, sink.active_port.as_deref());
+            }
+            AudioDetail::Source => {
+                let source = self.sources.get(index)?;
+                device(
+                    &mut bytes,
+                    &source.name,
+                    &source.description,
+                    AudioState::volume_percent(source.volume),
+                    source.mute,
+                    self.card_position(source.card_index),
+                );
+                ports(&mut bytes, &source.ports, source.active_port.as_deref());
+            }
+            AudioDetail::Card => {
+                let card = self.cards.get(index)?;
+                let selected = card.selected_profile.as_ref().and_then(|selected| {
+                    card.profiles
+                        .iter()
+                        .position(|profile| profile.name == selected.name)
+                });
+
+                string(&mut bytes, &card.name);
+                bytes.extend(count(card.profiles.len()).to_le_bytes());
+                bytes.extend(selected.map_or(NO_INDEX, count).to_le_bytes());
+                for profile in &card.profiles {
+                    string(&mut bytes, &profile.name);
+                    string(&mut bytes, &profile.description);
+                }
+                ports(&mut bytes, &card.ports, None);
+            }
+            AudioDetail::SinkInput => {
+                let stream = self.sink_inputs.get(index)?;
+                let sink = self
+                    .sinks
+                    .iter()
+                    .position(|sink| sink.index == stream.device);
+                app_stream(&mut bytes, stream, sink);
+            }
+            AudioDetail::SourceOutput => {
+                let stream = self.source_outputs.get(index)?;
+                let source = self
+                    .sources
+                    .iter()
+                    .position(|source| source.index == stream.device);
+                app_stream(&mut bytes, stream, source);
+            }
+            AudioDetail::Equalizer => {
+                let equalizer = self.equalizer.as_ref()?;
+                let settable = matches!(equalizer.kind, EqualizerKind::FilterChain { .. });
+
+                string(&mut bytes, &equalizer.name);
+                bytes.push(settable as u8);
+                bytes.extend(count(equalizer.bands.len()).to_le_bytes());
+                bytes.extend(count(equalizer.presets.len()).to_le_bytes());
+                for band in &equalizer.bands {
+                    string(&mut bytes, &band.name);
+                    bytes.extend(band.frequency.unwrap_or(0.0).to_le_bytes());
+                    bytes.extend(band.gain.to_le_bytes());
+                }
+                for preset in &equalizer.presets {
+                    string(&mut bytes, preset);
+                }
+            }
+        }
+
+        return Some(bytes);
+    }
+
+    /// where the card with pulseaudio's `index` is in `cards`, which is the
+    /// index modules know cards by
+    fn card_position(&self, index: Option<u32>) -> Option<u32> {
+        let index = index?;
+        let position = self.cards.iter().position(|card| card.index == index)?;
+        Some(position as u32)
     }
 }
+
+fn device(
+    bytes: &mut Vec<u8>,
+    name: &str,
+    description: &str,
+    volume: f32,
+    mute: bool,
+    card: Option<u32>,
+) {
+    string(bytes, name);
+    string(bytes, description);
+    bytes.extend(volume.to_le_bytes());
+    bytes.push(mute as u8);
+    bytes.extend(card.unwrap_or(u32::MAX).to_le_bytes());
+}
+
+fn ports(bytes: &mut Vec<u8>, ports: &[Port], active: Option<&str>) {
+    let active = active.and_then(|active| ports.iter().position(|port| port.name == active));
+
+    bytes.extend(count(ports.len()).to_le_bytes());
+    bytes.extend(active.map_or(NO_INDEX, count).to_le_bytes());
+    for port in ports {
+        string(bytes, &port.name);
+        string(bytes, &port.description);
+        bytes.push(port.available as u8);
+    }
+}
+
+/// `device` is where the stream's sink or source is in its list
+fn app_stream(bytes: &mut Vec<u8>, stream: &Stream, device: Option<usize>) {
+    bytes.extend(stream.index.to_le_bytes());
+    string(bytes, &stream.app_name);
+    string(bytes, stream.icon.as_deref().unwrap_or_default());
+    bytes.extend(AudioState::volume_percent(stream.volume).to_le_bytes());
+    bytes.push(stream.mute as u8);
+    bytes.extend(
+        device
+            .map_or(u32::MAX, |device| device as u32)
+            .to_le_bytes(),
+    );
+}
+
+/// `len` as a u16, there won't be more devices than that
+fn count(len: usize) -> u16 {
+    len.min(NO_INDEX as usize - 1) as u16
+}
+
+fn string(bytes: &mut Vec<u8>, string: &str) {
+    let string = &string.as_bytes()[..string.len().min(u16::MAX as usize)];
+    bytes.extend((string.len() as u16).to_le_bytes());
+    bytes.extend(string);
+}
@@ -1,7 +1,9 @@
-use super::data::{Card, Request, Sink, Source};
-use super::{AudioService, Event, PULSE_MAX_VOLUME, UPDATE_INTERVAL};
+use super::data::{AudioEventType, Card, Defaults, Profiles, Request, Sink, Source, Stream};
+use super::eq::Equalizer;
+use super::{AudioService, Event, PULSE_MAX_VOLUME, update_interval};
 
-use crate::services::{ServiceRequest, ServiceState};
+use crate::instrumented::InstrumentedSender;
+use crate::services::{Dedup, ServiceRequest, ServiceState};
 
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -37,6 +39,37 @@ pub struct AudioState {
 
     /// audio cards, sinks and sources map to these
     pub cards: Vec<Card>,
+
+    /// the applications playing to a sink, in the order pulseaudio lists
+    /// them
+    pub sink_inputs: Vec<Stream>,
+    /// the applications recording from a source
+    pub source_outputs: Vec<Stream>,
+
+    /// whether anything records from a source
+    pub recording: bool,
+
+    /// the name of the source that suppresses noise, see
+    /// `Source::is_noise_filter`
+    pub noise_filter: Option<String>,
+    /// the default source before the noise filter was made the default,
+    /// turning noise suppression off goes back to it
+    plain_source: Option<String>,
+
+    /// the equalizer found through pipewire, see `eq`
+    pub equalizer: Option<Equalizer>,
+
+    /// why the sound server can't be used, `None` when connected
+    pub unavailable: Option<String>,
+
+    /// raised by every event that changes something, it keeps going up
+    /// when the service restarts. a copy of the state takes it from each
+    /// `Event::Snapshot`
+    pub generation: u64,
+
+    /// pulseaudio sends the same lists again for changes we don't show, like
+    /// a stream starting, so those are dropped
+    dedup: Dedup<AudioEventType>,
 }
 
 impl ServiceState<AudioService> for AudioState {
@@ -51,10 +84,32 @@ impl ServiceState<AudioService> for AudioState {
             source_profiles: vec![],
             source_default_profile: None,
             cards: vec![],
+            sink_inputs: vec![],
+            source_outputs: vec![],
+            recording: false,
+            noise_filter: None,
+            plain_source: None,
+            equalizer: None,
+            unavailable: None,
+            generation: 0,
+            dedup: Dedup::new(),
         }
     }
 
     fn update(&mut self, event: Event) -> Vec<Event> {
+        // levels come too often to keep and nothing else depends on them
+        if matches!(event, Event::LevelsChanged { .. }) {
+            return vec![];
+        }
+
+        if !self.is_new(&event) {
+            return vec![];
+        }
+
+        if !matches!(event, Event::Snapshot { .. }) {
+            self.generation = self.generation.wrapping_add(1);
+        }
+
         let mut _events = match event.clone() {
             Event::SinksChanged { sinks } => {
                 self.sinks = sinks;
@@ -69,12 +124,18 @@ impl ServiceState<AudioService> for AudioState {
             Event::SourcesChanged { sources } => {
                 self.sources = sources;
 
-                vec![]
+                self.update_noise_suppression()
             }
             Event::DefaultSourceChanged { name } => {
                 self.default_source = name;
 
-                self.update_source_profile()
+                [
+                    self.update_source_profile(),
+                    self.update_noise_suppression(),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<Event>>()
             }
             Event::CardsChanged { cards } => {
                 self.cards = cards;
@@ -84,11 +145,109 @@ impl ServiceState<AudioService> for AudioState {
                     .flatten()
                     .collect::<Vec<Event>>()
             }
+            Event::SinkInputsChanged { inputs } => {
+                self.sink_inputs = inputs;
+
+                vec![]
+            }
+            Event::SourceOutputsChanged { outputs } => {
+                self.source_outputs = outputs;
+
+                vec![]
+            }
+            Event::RecordingChanged { recording } => {
+                self.recording = recording;
+
+                vec![]
+            }
+            Event::EqualizerChanged { equalizer } => {
+                self.equalizer = equalizer;
+
+                vec![]
+            }
+            Event::Snapshot {
+                generation,
+                sinks,
+                sources,
+                cards,
+                defaults,
+                profiles,
+            } => {
+                self.generation = generation;
+
+                // usually the events before it already got here, then
+                // there's nothing to tell anyone
+                let defaults_now = Defaults {
+                    sink: self.default_sink.clone(),
+                    source: self.default_source.clone(),
+                };
+                if self.sinks == sinks
+                    && self.sources == sources
+                    && self.cards == cards
+                    && defaults_now == defaults
+                    && self.profiles() == profiles
+                {
+                    return vec![];
+                }
+
+                log::debug!("[audio] resynced to the snapshot of generation {generation}");
+
+                self.sinks = sinks;
+                self.sources = sources;
+                self.cards = cards;
+                self.default_sink = defaults.sink;
+                self.default_source = defaults.source;
+                // kept when the default device has no card to find them in
+                self.sink_default_profile = profiles.sink;
+                self.source_default_profile = profiles.source;
+
+                [
+                    self.update_sink_profile(),
+                    self.update_source_profile(),
+                    self.update_noise_suppression(),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<Event>>()
+            }
+            Event::ServiceUnavailable { reason } => {
+                let generation = self.generation;
+                *self = Self::init();
+                self.unavailable = Some(reason);
+                self.generation = generation;
+
+                vec![]
+            }
+            Event::ServiceAvailable => {
+                self.unavailable = None;
+
+                vec![]
+            }
             _ => {
                 vec![]
             }
         };
 
+        // the ports are part of the devices, so any change to them or to
+        // which one is the default can switch the active port
+        if matches!(
+            event,
+            Event::SinksChanged { .. }
+                | Event::DefaultSinkChanged { .. }
+                | Event::SourcesChanged { .. }
+                | Event::DefaultSourceChanged { .. }
+                | Event::Snapshot { .. }
+        ) {
+            _events.push(Event::ActivePortChanged {
+                sink_port: self.get_default_sink().and_then(|sink| sink.active_port),
+                source_port: self
+                    .get_default_source()
+                    .and_then(|source| source.active_port),
+            });
+        }
+
+        _events.retain(|event| self.is_new(event));
+
         let mut events = vec![event];
         events.append(&mut _events);
         return events;
@@ -96,6 +255,36 @@ impl ServiceState<AudioService> for AudioState {
 }
 
 impl AudioState {
+    /// whether `event` changes anything since the last event of its kind
+    fn is_new(&mut self, event: &Event) -> bool {
+        match event.event_type() {
+            Some(kind) => self.dedup.is_new(kind, event),
+            None => true,
+        }
+    }
+
+    /// everything the mainloop's callbacks fill in, see `Event::Snapshot`
+    pub fn snapshot(&self) -> Event {
+        Event::Snapshot {
+            generation: self.generation,
+            sinks: self.sinks.clone(),
+            sources: self.sources.clone(),
+            cards: self.cards.clone(),
+            defaults: Defaults {
+                sink: self.default_sink.clone(),
+                source: self.default_source.clone(),
+            },
+            profiles: self.profiles(),
+        }
+    }
+
+    fn profiles(&self) -> Profiles {
+        Profiles {
+            sink: self.sink_default_profile.clone(),
+            source: self.source_default_profile.clone(),
+        }
+    }
+
     pub fn get_default_sink(&self) -> Option<Sink> {
         if let Some(sink) = &self.default_sink {
             for s in &self.sinks {
@@ -118,6 +307,79 @@ impl AudioState {
         return None;
     }
 
+    /// whether the noise filter is the default source
+    pub fn noise_suppression(&self) -> bool {
+        return self.noise_filter.is_some() && self.noise_filter == self.default_source;
+    }
+
+    /// the `SetDefaultSource` a `Request::SetNoiseSuppression` stands for,
+    /// `None` when there's no filter or nothing to change
+    pub fn noise_suppression_request(&self, enabled: bool) -> Option<Request> {
+        let filter = self.noise_filter.as_ref()?;
+
+        if enabled == self.noise_suppression() {
+            return None;
+        }
+
+        if enabled {
+            return Some(Request::SetDefaultSource {
+                name: filter.clone(),
+            });
+        }
+
+        // the source from before can be gone, like a headset that was
+        // unplugged
+        let name = self
+            .plain_source
+            .as_ref()
+            .filter(|name| self.sources.iter().any(|source| &source.name == *name))
+            .cloned()
+            .or_else(|| {
+                self.sources
+                    .iter()
+                    .find(|source| !source.is_noise_filter())
+                    .map(|source| source.name.clone())
+            })?;
+
+        return Some(Request::SetDefaultSource { name });
+    }
+
+    /// the `SetSinkVolume` a `Request::AdjustSinkVolume` stands for, `None`
+    /// when there's no default sink or nothing to change
+    pub fn adjust_sink_volume_request(&self, delta_percent: f32) -> Option<Request> {
+        let sink = self.get_default_sink()?;
+
+        let current = Self::volume_percent(sink.volume);
+        // a sink that's over amplified can't be turned up any more, but
+        // turning it up shouldn't bring it down to 100% either
+        let volume = (current + delta_percent).clamp(0.0, current.max(100.0));
+        if !volume.is_finite() || volume == current {
+            return None;
+        }
+
+        return Some(Request::SetSinkVolume {
+            name: sink.name,
+            volume: Self::scale_channel_volume(sink.volume, volume),
+        });
+    }
+
+    /// the `SetSinkMute` a `Request::ToggleSinkMute` stands for, `None` when
+    /// there's no default sink
+    pub fn toggle_sink_mute_request(&self) -> Option<Request> {
+        let sink = self.get_default_sink()?;
+
+        return Some(Request::SetSinkMute {
+            name: sink.name,
+            state: !sink.mute,
+        });
+    }
+
+    /// the average volume of the channels, between 0.0 - 100.0 unless the
+    /// device is over amplified
+    pub fn volume_percent(channel: ChannelVolumes) -> f32 {
+        return (channel.avg().0 as f32 / PULSE_MAX_VOLUME as f32 * 100.0).round();
+    }
+
     /// `volume` must be between 0.0 - 100.0
     pub fn set_channel_volume(channel: ChannelVolumes, volume: f32) -> ChannelVolumes {
         let vol =
@@ -129,8 +391,28 @@ impl AudioState {
         return channel;
     }
 
+    /// like `set_channel_volume` but keeps the balance between the channels,
+    /// each one is scaled so their average comes out as `volume`. a channel
+    /// isn't turned up past 100% unless it already was
+    pub fn scale_channel_volume(channel: ChannelVolumes, volume: f32) -> ChannelVolumes {
+        let average = channel.avg().0;
+        // muted down to nothing, there's no balance left to keep
+        if average == 0 {
+            return Self::set_channel_volume(channel, volume.min(100.0));
+        }
+
+        let scale = volume / 100.0 * PULSE_MAX_VOLUME as f32 / average as f32;
+
+        let mut channel = channel.clone();
+        for channel_volume in channel.get_mut() {
+            let scaled = (channel_volume.0 as f32 * scale).round() as u32;
+            *channel_volume = Volume(scaled.min(channel_volume.0.max(PULSE_MAX_VOLUME)));
+        }
+        return channel;
+    }
+
     fn set_sink_volume(
-        channel: &flume::Sender<ServiceRequest<AudioService>>,
+        channel: &InstrumentedSender<flume::Sender<ServiceRequest<AudioService>>>,
         volume_data: &(String, ChannelVolumes),
     ) {
         if let Err(err) = channel.send(ServiceRequest::Request {
@@ -147,7 +429,7 @@ impl AudioState {
     }
 
     fn set_source_volume(
-        channel: &flume::Sender<ServiceRequest<AudioService>>,
+        channel: &InstrumentedSender<flume::Sender<ServiceRequest<AudioService>>>,
         volume_data: &(String, ChannelVolumes),
     ) {
         if let Err(err) = channel.send(ServiceRequest::Request {
@@ -193,6 +475,23 @@ impl AudioState {
         return vec![];
     }
 
+    fn update_noise_suppression(&mut self) -> Vec<Event> {
+        self.noise_filter = self
+            .sources
+            .iter()
+            .find(|source| source.is_noise_filter())
+            .map(|source| source.name.clone());
+
+        if !self.noise_suppression() && self.default_source.is_some() {
+            self.plain_source = self.default_source.clone();
+        }
+
+        return vec![Event::NoiseSuppressionChanged {
+            available: self.noise_filter.is_some(),
+            enabled: self.noise_suppression(),
+        }];
+    }
+
     fn update_source_profile(&mut self) -> Vec<Event> {
         let source: Source = match self.get_default_source() {
             Some(source) => source,
@@ -231,7 +530,7 @@ impl AudioState {
 pub struct AudioRequestThreadState {
     /// channel for communicating with the service as we use threads here
     /// to slow down rates to the pulseaudio server
-    chan: flume::Sender<ServiceRequest<AudioService>>,
+    chan: InstrumentedSender<flume::Sender<ServiceRequest<AudioService>>>,
 
     /// the last time we updated certain values (like volume) on the
     /// pulseaudio server for the sink
@@ -259,7 +558,7 @@ pub struct AudioRequestThreadState {
 }
 
 impl AudioRequestThreadState {
-    pub fn init(chan: flume::Sender<ServiceRequest<AudioService>>) -> Self {
+    pub fn init(chan: InstrumentedSender<flume::Sender<ServiceRequest<AudioService>>>) -> Self {
         Self {
             chan,
             sink_last_update_time: Instant::now(),
@@ -283,12 +582,13 @@ impl AudioRequestThreadState {
 
         let now = Instant::now();
         let delta = now - self.sink_last_update_time;
+        let interval = update_interval();
 
-        if delta <= UPDATE_INTERVAL {
+        if delta <= interval {
             if !self.sink_thread_scheduled {
                 // if this somehow errors, its prob the os and not my code because i don't
                 // get why time would move backwards >:3
-                let wait_time = UPDATE_INTERVAL - delta;
+                let wait_time = interval - delta;
 
                 let chan = self.chan.clone();
                 let volume_data = Arc::clone(&self.sink_volume_data);
@@ -317,7 +617,7 @@ impl AudioRequestThreadState {
                 });
                 self.sink_thread_scheduled = true;
             } else {
-                // have we waited more than the UPDATE_INTERVAL and is a thread
+                // have we waited more than the update interval and is a thread
                 // already scheduled to set the volume of the sink in the future?
                 return false;
             }
@@ -341,12 +641,13 @@ impl AudioRequestThreadState {
 
         let now = Instant::now();
         let delta = now - self.source_last_update_time;
+        let interval = update_interval();
 
-        if delta <= UPDATE_INTERVAL {
+        if delta <= interval {
             if !self.source_thread_scheduled {
                 // if this somehow errors, its prob the os and not my code because i don't
                 // get why time would move backwards >:3
-                let wait_time = UPDATE_INTERVAL - delta;
+                let wait_time = interval - delta;
 
                 let chan = self.chan.clone();
                 let volume_data = Arc::clone(&self.source_volume_data);
@@ -375,7 +676,7 @@ impl AudioRequestThreadState {
                 });
                 self.source_thread_scheduled = true;
             } else {
-                // have we waited more than the UPDATE_INTERVAL and is a thread
+                // have we waited more than the update interval and is a thread
                 // already scheduled to set the volume of the source in the future?
                 return false;
             }
@@ -0,0 +1,114 @@
+/// messages emitted from the brightness service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when the brightness of a backlight changes, or one is
+    /// added or removed, with every backlight sorted by name
+    BrightnessChanged { backlights: Vec<Backlight> },
+
+    /// event emitted when there's no backlight to control (like on most
+    /// desktops) or logind can't be reached
+    ServiceUnavailable { reason: String },
+    /// event emitted when the service found a backlight and connected to
+    /// logind
+    ServiceAvailable,
+}
+
+/// requests for the brightness service
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// sets the brightness of a backlight in percent, through logind so the
+    /// shell doesn't need to be root
+    ///
+    /// `device` is the backlight's name (see `Backlight.name`), `None` for
+    /// the first one
+    SetBrightness { device: Option<String>, percent: u8 },
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum BrightnessEventType {
+    BrightnessChanged,
+}
+
+impl Event {
+    /// the kind of event modules register for, `None` for the events about
+    /// the service itself
+    pub fn event_type(&self) -> Option<BrightnessEventType> {
+        match self {
+            Self::BrightnessChanged { .. } => Some(BrightnessEventType::BrightnessChanged),
+            Self::ServiceUnavailable { .. } | Self::ServiceAvailable => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrightnessSubscriptionData(pub u8);
+
+impl BrightnessSubscriptionData {
+    /// subscribes to the brightness of a backlight changing
+    pub const BRIGHTNESS_CHANGED: Self = Self(0b_0000_0001);
+
+    pub fn is_set(&self, case: BrightnessSubscriptionData) -> bool {
+        return *self & case != BrightnessSubscriptionData(0);
+    }
+}
+
+impl BitOr for BrightnessSubscriptionData {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for BrightnessSubscriptionData {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for BrightnessSubscriptionData {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// types used for events
+
+/// a device in `/sys/class/backlight`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Backlight {
+    /// the name of the device, like `intel_backlight`
+    pub name: String,
+    /// from 0 to `max_brightness`, in the device's own steps
+    pub brightness: u32,
+    pub max_brightness: u32,
+}
+
+impl Backlight {
+    /// the brightness from 0 to 100
+    pub fn percent(&self) -> u8 {
+        if self.max_brightness == 0 {
+            return 0;
+        }
+
+        return (self.brightness as u64 * 100 / self.max_brightness as u64).min(100) as u8;
+    }
+
+    /// the brightness in the device's own steps for `percent`, rounded to
+    /// the nearest step
+    pub fn brightness_for(&self, percent: u8) -> u32 {
+        let percent = percent.min(100) as u64;
+        return ((percent * self.max_brightness as u64 + 50) / 100) as u32;
+    }
+}
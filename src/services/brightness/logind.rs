@@ -0,0 +1,16 @@
+//! the part of logind's dbus api the service uses
+//!
+//! see https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+pub trait Session {
+    /// sets the brightness of a device in `/sys/class/<subsystem>/<name>`,
+    /// logind only lets the session that's in the foreground do this
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+}
@@ -0,0 +1,275 @@
+//! shows and sets the brightness of the screen's backlight, read from
+//! `/sys/class/backlight` and set through logind so the shell doesn't need
+//! to be root
+//!
+//! sysfs doesn't tell anyone when the brightness changes (like from the
+//! brightness keys), so the backlights are polled
+
+mod data;
+mod logind;
+mod se;
+mod state;
+mod sysfs;
+
+pub use data::{Backlight, BrightnessSubscriptionData, Event, Request};
+pub use state::BrightnessState;
+
+use data::BrightnessEventType;
+use logind::SessionProxy;
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::time::MissedTickBehavior;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// how often the backlights are read
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// the first wait before looking for a backlight or logind again after
+/// neither could be found, doubles each failed attempt
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(5);
+/// the longest wait between attempts, machines without a backlight won't
+/// grow one so there's no point trying often
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct BrightnessService;
+
+/// returned from `BrightnessService::run` when there's no backlight or
+/// logind couldn't be reached, so the service waits longer before trying
+/// again
+#[derive(Debug)]
+struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "brightness unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+impl Service for BrightnessService {
+    type Event = Event;
+    type EventType = BrightnessEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = BrightnessState;
+    type SubscriptionData = BrightnessSubscriptionData;
+
+    const ID: u16 = 11;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.brightness.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut backoff = RETRY_BACKOFF_MIN;
+
+                loop {
+                    let mut state = BrightnessState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.brightness.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:brightness] could not send init event: {}", err);
+                        log::error!("[service:brightness] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:brightness] restarting");
+                        backoff = RETRY_BACKOFF_MIN;
+                        continue;
+                    }
+
+                    let (reason, delay) = match err.downcast_ref::<Unavailable>() {
+                        Some(Unavailable(reason)) => {
+                            log::warn!(
+                                "[service:brightness] {err}, retrying in {}",
+                                humantime::format_duration(backoff)
+                            );
+
+                            let delay = backoff;
+                            backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                            (reason.clone(), delay)
+                        }
+                        None => {
+                            log::error!("[service:brightness] error: {err}");
+                            log::error!("[service:brightness] restarting in 5 seconds...");
+
+                            backoff = RETRY_BACKOFF_MIN;
+                            (err.to_string(), Duration::from_secs(5))
+                        }
+                    };
+
+                    emit(
+                        &mut state,
+                        &mut chan,
+                        vec![Event::ServiceUnavailable { reason }],
+                    )
+                    .await;
+
+                    restart_delay::<Self>(delay).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut BrightnessState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let backlights = match sysfs::read_backlights().await {
+            Ok(backlights) if backlights.is_empty() => {
+                return Unavailable("there is no backlight".to_string()).into();
+            }
+            Ok(backlights) => backlights,
+            Err(err) => return Unavailable(format!("could not read backlights: {err}")).into(),
+        };
+
+        let conn = match zbus::Connection::system().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                return Unavailable(format!("could not connect to the system bus: {err}")).into();
+            }
+        };
+        let session = match SessionProxy::new(&conn).await {
+            Ok(session) => session,
+            Err(err) => return Unavailable(format!("could not create proxy: {err}")).into(),
+        };
+
+        log::info!("[service:brightness] service started");
+        startup::ready("brightness");
+
+        emit(
+            state,
+            chan,
+            vec![
+                Event::ServiceAvailable,
+                Event::BrightnessChanged { backlights },
+            ],
+        )
+        .await;
+
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                request = request_rx.recv_async() => {
+                    match request {
+                        // the backlights are read again below, so the new
+                        // brightness shows right away instead of at the next
+                        // poll
+                        Ok(ServiceRequest::Request { request }) => {
+                            let result = Self::request(&session, state, request.clone()).await;
+                            if let Err(err) = result {
+                                log::warn!(
+                                    "[service:brightness] could not handle {request:?}: {err}"
+                                );
+                            }
+                        }
+                        Ok(ServiceRequest::SubscribeModule { id, data }) => {
+                            let mut events = vec![];
+
+                            if data.is_set(BrightnessSubscriptionData::BRIGHTNESS_CHANGED) {
+                                events.push(BrightnessEventType::BrightnessChanged);
+                            }
+
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:brightness] {id:?} registered, {diff}");
+                            continue;
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            module_ids.unregister_module(id);
+                            continue;
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:brightness] error receiving request: {err}");
+                        }
+                    }
+                }
+            };
+
+            match sysfs::read_backlights().await {
+                Ok(backlights) => {
+                    emit(state, chan, vec![Event::BrightnessChanged { backlights }]).await;
+                }
+                Err(err) => log::warn!("[service:brightness] could not read backlights: {err}"),
+            }
+        }
+    }
+}
+
+impl BrightnessService {
+    async fn request(
+        session: &SessionProxy<'_>,
+        state: &BrightnessState,
+        request: Request,
+    ) -> anyhow::Result<()> {
+        match request {
+            Request::SetBrightness { device, percent } => {
+                let backlight = match &device {
+                    Some(device) => state
+                        .backlights
+                        .iter()
+                        .find(|backlight| backlight.name == *device),
+                    None => state.backlights.first(),
+                };
+                let Some(backlight) = backlight else {
+                    return Err(anyhow!("there is no backlight called {device:?}"));
+                };
+
+                session
+                    .set_brightness(
+                        "backlight",
+                        &backlight.name,
+                        backlight.brightness_for(percent),
+                    )
+                    .await?;
+            }
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,108 @@
+use super::{Backlight, BrightnessService, BrightnessSubscriptionData, Event};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+/// the layout modules get brightness events in through `service_event`, a
+/// u8 for the kind of event then what changed
+///
+/// - 0 the brightness changed, a u16 for the amount of backlights then for
+///   each one the name as a u16 length then the bytes, and the brightness
+///   and max brightness as u32s
+/// - 1 the service is unavailable with the reason as a u16 length then the
+///   bytes, 2 it's available again
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        BrightnessService::ID
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        let mut bytes = vec![];
+
+        match self {
+            Event::BrightnessChanged { backlights } => {
+                bytes.push(0);
+                let backlights = &backlights[..backlights.len().min(u16::MAX as usize)];
+                bytes.extend((backlights.len() as u16).to_le_bytes());
+                for Backlight {
+                    name,
+                    brightness,
+                    max_brightness,
+                } in backlights
+                {
+                    push_str(&mut bytes, name);
+                    bytes.extend(brightness.to_le_bytes());
+                    bytes.extend(max_brightness.to_le_bytes());
+                }
+            }
+            Event::ServiceUnavailable { reason } => {
+                bytes.push(1);
+                push_str(&mut bytes, reason);
+            }
+            Event::ServiceAvailable => bytes.push(2),
+        }
+
+        return Some(bytes);
+    }
+
+    fn is_for(&self, register: &SubscriptionData) -> bool {
+        let SubscriptionData::Brightness { data } = register else {
+            return false;
+        };
+
+        return self
+            .event_type()
+            .is_none_or(|_| data.is_set(BrightnessSubscriptionData::BRIGHTNESS_CHANGED));
+    }
+}
+
+fn push_str(bytes: &mut Vec<u8>, string: &str) {
+    let string = &string.as_bytes()[..string.len().min(u16::MAX as usize)];
+    bytes.extend((string.len() as u16).to_le_bytes());
+    bytes.extend(string);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_module_gets_the_event() {
+        let event = Event::BrightnessChanged {
+            backlights: vec![Backlight {
+                name: "bl".to_string(),
+                brightness: 300,
+                max_brightness: 1000,
+            }],
+        };
+
+        assert!(event.is_for(&SubscriptionData::Brightness {
+            data: BrightnessSubscriptionData::BRIGHTNESS_CHANGED,
+        }));
+        assert!(!event.is_for(&SubscriptionData::Brightness {
+            data: BrightnessSubscriptionData(0),
+        }));
+        assert!(!event.is_for(&SubscriptionData::Weather));
+
+        assert_eq!(
+            event.serialize().unwrap(),
+            [
+                &[0, 1, 0, 2, 0][..],
+                b"bl",
+                &300u32.to_le_bytes(),
+                &1000u32.to_le_bytes(),
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn service_events_go_to_every_registered_module() {
+        let event = Event::ServiceAvailable;
+
+        assert!(event.is_for(&SubscriptionData::Brightness {
+            data: BrightnessSubscriptionData(0),
+        }));
+        assert_eq!(event.serialize().unwrap(), [2]);
+    }
+}
@@ -0,0 +1,50 @@
+use super::data::{Backlight, BrightnessEventType};
+use super::{BrightnessService, Event};
+
+use crate::services::{Dedup, ServiceState};
+
+#[derive(Debug)]
+pub struct BrightnessState {
+    /// every backlight, sorted by name
+    pub backlights: Vec<Backlight>,
+
+    /// why the brightness can't be controlled, `None` when it can
+    pub unavailable: Option<String>,
+
+    /// the backlights are polled, so most reads are the same as before and
+    /// are dropped
+    dedup: Dedup<BrightnessEventType>,
+}
+
+impl ServiceState<BrightnessService> for BrightnessState {
+    fn init() -> Self {
+        Self {
+            backlights: vec![],
+            unavailable: None,
+            dedup: Dedup::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        if let Some(kind) = event.event_type()
+            && !self.dedup.is_new(kind, &event)
+        {
+            return vec![];
+        }
+
+        match event.clone() {
+            Event::BrightnessChanged { backlights } => {
+                self.backlights = backlights;
+            }
+            Event::ServiceUnavailable { reason } => {
+                *self = Self::init();
+                self.unavailable = Some(reason);
+            }
+            Event::ServiceAvailable => {
+                self.unavailable = None;
+            }
+        };
+
+        return vec![event];
+    }
+}
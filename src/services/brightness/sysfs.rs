@@ -0,0 +1,49 @@
+//! reads the backlights from `/sys/class/backlight`
+
+use super::data::Backlight;
+
+use std::path::Path;
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+/// every backlight, sorted by name so the first one stays the same
+pub async fn read_backlights() -> anyhow::Result<Vec<Backlight>> {
+    let mut entries = tokio::fs::read_dir(BACKLIGHT_DIR).await?;
+
+    let mut backlights = vec![];
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        let max_brightness = match read_u32(&path.join("max_brightness")).await {
+            Some(max_brightness) => max_brightness,
+            None => {
+                log::debug!("[service:brightness] could not read the max brightness of {name}");
+                continue;
+            }
+        };
+
+        // `actual_brightness` is what the hardware reports, some drivers
+        // don't have it so what was last set is used instead
+        let brightness = match read_u32(&path.join("actual_brightness")).await {
+            Some(brightness) => brightness,
+            None => read_u32(&path.join("brightness")).await.unwrap_or(0),
+        };
+
+        backlights.push(Backlight {
+            name,
+            brightness,
+            max_brightness,
+        });
+    }
+    backlights.sort_by(|a, b| a.name.cmp(&b.name));
+
+    return Ok(backlights);
+}
+
+async fn read_u32(path: &Path) -> Option<u32> {
+    tokio::fs::read_to_string(path)
+        .await
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+}
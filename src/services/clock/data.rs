@@ -0,0 +1,107 @@
+use crate::runtime::RuntimeModuleId;
+
+/// messages emitted from the clock service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when the text a module's format gives changes, like
+    /// once a minute for `%H:%M`
+    TimeChanged {
+        module: RuntimeModuleId,
+        time: ClockTime,
+    },
+}
+
+/// requests for the clock service
+#[derive(Debug, Clone)]
+pub enum Request {}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum ClockEventType {
+    TimeChanged,
+}
+
+impl Event {
+    /// the kind of event modules register for
+    pub fn event_type(&self) -> ClockEventType {
+        match self {
+            Self::TimeChanged { .. } => ClockEventType::TimeChanged,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+/// how a module wants the time, read from the register's extra data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockSubscriptionData {
+    /// strftime style, see
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    pub format: String,
+    /// an iana name like `Europe/Berlin`, `None` for the local timezone
+    pub timezone: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// types used for events
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClockTime {
+    /// the time in the module's format
+    pub text: String,
+    pub calendar: Calendar,
+}
+
+/// the date in the module's timezone, enough to draw a month's calendar
+/// without doing date maths in the module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Calendar {
+    pub year: i32,
+    /// 1 - 12
+    pub month: u8,
+    /// 1 - 31
+    pub day: u8,
+    /// 0 for monday to 6 for sunday
+    pub weekday: u8,
+    /// the day of the year, 1 - 366
+    pub ordinal: u16,
+    /// the iso 8601 week number, 1 - 53
+    pub iso_week: u8,
+    pub days_in_month: u8,
+    /// the weekday of the 1st of the month, like `weekday`
+    pub first_weekday: u8,
+    /// the timezone's offset from utc
+    pub utc_offset_seconds: i32,
+}
+
+impl ClockTime {
+    /// the layout modules read it in, little endian
+    ///
+    /// the year and utc offset as i32s, the month, day and weekday as u8s,
+    /// the ordinal as a u16, the iso week, days in month and first weekday as
+    /// u8s, then the text as a u16 length followed by that many bytes of
+    /// utf-8
+    pub fn serialize(&self) -> Vec<u8> {
+        let calendar = &self.calendar;
+        let mut bytes = vec![];
+
+        bytes.extend(calendar.year.to_le_bytes());
+        bytes.extend(calendar.utc_offset_seconds.to_le_bytes());
+        bytes.extend([calendar.month, calendar.day, calendar.weekday]);
+        bytes.extend(calendar.ordinal.to_le_bytes());
+        bytes.extend([
+            calendar.iso_week,
+            calendar.days_in_month,
+            calendar.first_weekday,
+        ]);
+
+        let text = &self.text.as_bytes()[..self.text.len().min(u16::MAX as usize)];
+        bytes.extend((text.len() as u16).to_le_bytes());
+        bytes.extend(text);
+
+        return bytes;
+    }
+}
@@ -0,0 +1,251 @@
+//! formats the time for modules, so they don't each need an `Interval` and
+//! their own date maths
+//!
+//! every module gives a strftime format and optionally a timezone. the time
+//! is formatted for each of them on the second, and a module only hears
+//! about it when its text changed, so a `%H:%M` clock is rendered once a
+//! minute
+
+mod data;
+mod se;
+mod state;
+
+pub use data::{Calendar, ClockSubscriptionData, ClockTime, Event, Request};
+pub use state::ClockState;
+
+use data::ClockEventType;
+
+use crate::builtin::clock::locale;
+use crate::config;
+use crate::instrumented::{self, InstrumentedSender};
+use crate::runtime::RuntimeModuleId;
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Local, Locale, Months, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::time::{Instant, MissedTickBehavior};
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// used when a module's format can't be read
+const DEFAULT_FORMAT: &str = "%H:%M";
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct ClockService;
+
+/// a module's format and timezone, checked when it registered
+#[derive(Debug)]
+struct ModuleClock {
+    format: String,
+    /// `None` for the local timezone
+    timezone: Option<Tz>,
+}
+
+impl Service for ClockService {
+    type Event = Event;
+    type EventType = ClockEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = ClockState;
+    type SubscriptionData = ClockSubscriptionData;
+
+    const ID: u16 = 12;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.clock.events", chan);
+                let mut module_ids = ModuleIds::new();
+
+                loop {
+                    let mut state = ClockState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.clock.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:clock] could not send init event: {}", err);
+                        log::error!("[service:clock] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:clock] restarting");
+                        continue;
+                    }
+                    log::error!("[service:clock] error: {err}");
+                    restart_delay::<Self>(Duration::from_secs(5)).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut ClockState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let mut clocks: HashMap<RuntimeModuleId, ModuleClock> = HashMap::new();
+        // the names of days and months follow the clock widget's locale
+        let locale = locale(config::get().widgets.clock.locale.as_deref());
+
+        // ticks on the second, so a clock showing seconds doesn't lag behind
+        let until_next_second = Duration::from_nanos(
+            1_000_000_000 - Utc::now().timestamp_subsec_nanos().min(999_999_999) as u64,
+        );
+        let mut ticker =
+            tokio::time::interval_at(Instant::now() + until_next_second, Duration::from_secs(1));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        log::info!("[service:clock] service started");
+        startup::ready("clock");
+
+        loop {
+            let modules: Vec<RuntimeModuleId> = tokio::select! {
+                _ = ticker.tick() => clocks.keys().cloned().collect(),
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => match request {},
+                        Ok(ServiceRequest::SubscribeModule { id, data }) => {
+                            clocks.insert(id.clone(), ModuleClock::new(&id, data));
+                            let events = vec![ClockEventType::TimeChanged];
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:clock] {id:?} registered, {diff}");
+
+                            // the module gets its first time right away, even
+                            // if it's the same as before a reload
+                            state.times.remove(&id);
+                            vec![id]
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            clocks.remove(&id);
+                            state.times.remove(&id);
+                            module_ids.unregister_module(id);
+                            vec![]
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:clock] error receiving request: {err}");
+                        }
+                    }
+                }
+            };
+
+            let mut events = vec![];
+            for module in modules {
+                let Some(clock) = clocks.get(&module) else {
+                    continue;
+                };
+
+                let time = clock.now(locale);
+                if state.times.get(&module) != Some(&time) {
+                    events.push(Event::TimeChanged { module, time });
+                }
+            }
+
+            emit(state, chan, events).await;
+        }
+    }
+}
+
+impl ModuleClock {
+    /// checks the module's format and timezone, falling back to
+    /// `DEFAULT_FORMAT` and the local timezone
+    fn new(id: &RuntimeModuleId, data: ClockSubscriptionData) -> Self {
+        // chrono can't format invalid specifiers, so check them up front
+        let format = if StrftimeItems::new(&data.format).any(|item| item == Item::Error) {
+            log::warn!(
+                "[service:clock] {id:?} asked for `{}` which is not a valid format, using \
+                 `{DEFAULT_FORMAT}`",
+                data.format
+            );
+            DEFAULT_FORMAT.to_string()
+        } else {
+            data.format
+        };
+
+        let timezone = match data.timezone {
+            Some(name) => match name.parse::<Tz>() {
+                Ok(timezone) => Some(timezone),
+                Err(err) => {
+                    log::warn!(
+                        "[service:clock] {id:?} asked for the timezone `{name}`, using the local \
+                         timezone: {err}"
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+
+        Self { format, timezone }
+    }
+
+    fn now(&self, locale: Locale) -> ClockTime {
+        match self.timezone {
+            Some(timezone) => time_at(Utc::now().with_timezone(&timezone), &self.format, locale),
+            None => time_at(Local::now(), &self.format, locale),
+        }
+    }
+}
+
+fn time_at<Z: TimeZone>(now: DateTime<Z>, format: &str, locale: Locale) -> ClockTime
+where
+    Z::Offset: std::fmt::Display,
+{
+    let date = now.date_naive();
+    let first = date.with_day(1).unwrap_or(date);
+    let days_in_month = first
+        .checked_add_months(Months::new(1))
+        .map_or(31, |next| (next - first).num_days());
+
+    ClockTime {
+        text: now.format_localized(format, locale).to_string(),
+        calendar: Calendar {
+            year: date.year(),
+            month: date.month() as u8,
+            day: date.day() as u8,
+            weekday: date.weekday().num_days_from_monday() as u8,
+            ordinal: date.ordinal() as u16,
+            iso_week: date.iso_week().week() as u8,
+            days_in_month: days_in_month as u8,
+            first_weekday: first.weekday().num_days_from_monday() as u8,
+            utc_offset_seconds: now.offset().fix().local_minus_utc(),
+        },
+    }
+}
@@ -0,0 +1,20 @@
+use super::{ClockService, Event};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        ClockService::ID
+    }
+
+    // the time is formatted for each module and given through
+    // `Request::ClockChanged`
+    fn serialize(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn is_for(&self, _register: &SubscriptionData) -> bool {
+        false
+    }
+}
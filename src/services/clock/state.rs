@@ -0,0 +1,33 @@
+use super::data::ClockTime;
+use super::{ClockService, Event};
+
+use crate::runtime::RuntimeModuleId;
+use crate::services::ServiceState;
+
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct ClockState {
+    /// the last time sent to each module
+    pub times: HashMap<RuntimeModuleId, ClockTime>,
+}
+
+impl ServiceState<ClockService> for ClockState {
+    fn init() -> Self {
+        Self {
+            times: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match event.clone() {
+            Event::TimeChanged { module, time } => {
+                // the service only sends times that changed, so there's
+                // nothing to drop
+                self.times.insert(module, time);
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+/// messages emitted from the custom service when a sensor runs
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// event emitted when a sensor's command finished with new output
+    SensorUpdated { name: String, value: SensorValue },
+    /// event emitted when a sensor's command failed to run, timed out,
+    /// exited with an error or its output couldn't be parsed
+    SensorFailed { name: String, error: String },
+}
+
+/// the output of a sensor, depends on the sensor's parse mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "parse", content = "value", rename_all = "lowercase")]
+pub enum SensorValue {
+    /// trimmed stdout of the command
+    Plain(String),
+    /// stdout of the command parsed as json
+    Json(serde_json::Value),
+}
+
+/// requests for the custom service
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// runs a sensor's command right away instead of waiting for its
+    /// interval
+    Refresh { name: String },
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum CustomEventType {
+    /// events for a sensor, keyed by the sensor's name
+    Sensor(String),
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomSubscriptionData {
+    /// the names of the sensors the module wants the output of
+    pub sensors: Vec<String>,
+}
@@ -0,0 +1,24 @@
+use super::{CustomService, Event};
+
+use crate::services::ipc::protocol::CustomInfo;
+
+impl CustomService {
+    /// turns a dumped state back into the events that would have created it
+    ///
+    /// used by `--load-state-fixture` in place of running the sensors
+    pub fn fixture_events(info: &CustomInfo) -> Vec<Event> {
+        let values = info
+            .values
+            .iter()
+            .map(|(name, value)| Event::SensorUpdated {
+                name: name.clone(),
+                value: value.clone(),
+            });
+        let errors = info.errors.iter().map(|(name, error)| Event::SensorFailed {
+            name: name.clone(),
+            error: error.clone(),
+        });
+
+        return values.chain(errors).collect();
+    }
+}
@@ -0,0 +1,358 @@
+//! runs user defined scripts ("sensors") from the config and emits their
+//! output, so simple widgets can be made with shell scripts
+
+mod data;
+mod fixture;
+mod se;
+mod state;
+
+pub use data::{CustomSubscriptionData, Event, Request, SensorValue};
+pub use state::CustomState;
+
+use data::CustomEventType;
+
+use crate::config::{self, SensorConfig, SensorParseMode};
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 64;
+
+/// sensors can't run more often than this
+const MIN_INTERVAL: Duration = Duration::from_millis(250);
+
+/// stdout past this many bytes is ignored
+const MAX_OUTPUT_BYTES: u64 = 64 * 1024;
+
+/// environment variables that are passed through to sensor commands
+///
+/// everything else is cleared so commands don't see the daemon's environment
+const PASSTHROUGH_ENV: [&str; 5] = ["PATH", "HOME", "LANG", "USER", "XDG_RUNTIME_DIR"];
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct CustomService;
+
+impl Service for CustomService {
+    type Event = Event;
+    type EventType = CustomEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = CustomState;
+    type SubscriptionData = CustomSubscriptionData;
+
+    const ID: u16 = 4;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.custom.events", chan);
+                let mut module_ids = ModuleIds::new();
+
+                loop {
+                    let mut state = CustomState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.custom.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:custom] could not send init event: {}", err);
+                        log::error!("[service:custom] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:custom] restarting");
+                        continue;
+                    }
+                    log::error!("[service:custom] error: {err}");
+                    restart_delay::<Self>(Duration::from_secs(5)).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut CustomState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let sensors = config::get().services.custom.sensors.clone();
+
+        log::info!(
+            "[service:custom] service started with {} sensor(s)",
+            sensors.len()
+        );
+        startup::ready("custom");
+
+        // the sender is kept alive here so receiving doesn't error when there
+        // are no sensors configured
+        let (internal_event_tx, internal_event_rx) =
+            instrumented::bounded::<Event>("service.custom.internal_events", CHANNEL_CAPACITY);
+
+        // used to ask a sensor's worker to run its command early
+        let mut refresh_txs: HashMap<String, flume::Sender<()>> = HashMap::new();
+        let mut workers = Workers(vec![]);
+
+        for sensor in sensors {
+            if refresh_txs.contains_key(&sensor.name) {
+                log::warn!(
+                    "[service:custom] duplicate sensor name `{}` (skipped)",
+                    sensor.name
+                );
+                continue;
+            }
+
+            let (refresh_tx, refresh_rx) = flume::bounded::<()>(1);
+            refresh_txs.insert(sensor.name.clone(), refresh_tx);
+
+            workers.0.push(tokio::spawn(Self::worker(
+                sensor,
+                internal_event_tx.clone(),
+                refresh_rx,
+            )));
+        }
+
+        loop {
+            tokio::select! {
+                event = internal_event_rx.recv_async() => {
+                    match event {
+                        Ok(event) => {
+                            for event in state.update(event) {
+                                if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                                    log::error!(
+                                        "[service:custom] error sending service event update: {err}"
+                                    );
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:custom] error receiving message from sensors: {err}");
+                        }
+                    }
+                }
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => match request {
+                            Request::Refresh { name } => match refresh_txs.get(&name) {
+                                // a full channel means a refresh is already pending
+                                Some(tx) => { let _ = tx.try_send(()); }
+                                None => {
+                                    log::warn!("[service:custom] no sensor named `{name}` to refresh");
+                                }
+                            },
+                        },
+                        Ok(ServiceRequest::SubscribeModule { id, data }) => {
+                            let events = data
+                                .sensors
+                                .into_iter()
+                                .map(CustomEventType::Sensor)
+                                .collect();
+
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:custom] {id:?} registered, {diff}");
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            module_ids.unregister_module(id);
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:custom] error receiving request: {err}");
+                        }
+                    }
+                }
+            };
+        }
+    }
+}
+
+impl CustomService {
+    /// runs a sensor's command on its interval until the service stops
+    async fn worker(
+        sensor: SensorConfig,
+        event_tx: InstrumentedSender<flume::Sender<Event>>,
+        refresh_rx: flume::Receiver<()>,
+    ) {
+        let period = Duration::from_millis(sensor.interval_ms).max(MIN_INTERVAL);
+
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                refresh = refresh_rx.recv_async() => {
+                    if refresh.is_err() {
+                        return;
+                    }
+                    interval.reset();
+                }
+            };
+
+            let event = match Self::run_sensor(&sensor).await {
+                Ok(value) => Event::SensorUpdated {
+                    name: sensor.name.clone(),
+                    value,
+                },
+                Err(err) => {
+                    log::debug!("[service:custom] [sensor:{}] failed: {err}", sensor.name);
+                    Event::SensorFailed {
+                        name: sensor.name.clone(),
+                        error: err.to_string(),
+                    }
+                }
+            };
+
+            if let Err(err) = event_tx.send_async(event).await {
+                log::error!(
+                    "[service:custom] [sensor:{}] could not send event: {err}",
+                    sensor.name
+                );
+                return;
+            }
+        }
+    }
+
+    /// runs the command once in a sandboxed child process
+    ///
+    /// the child gets a cleared environment, no stdin, its own process group,
+    /// and is killed when it runs past its timeout
+    async fn run_sensor(sensor: &SensorConfig) -> anyhow::Result<SensorValue> {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(&sensor.command)
+            .env_clear()
+            .envs(
+                PASSTHROUGH_ENV
+                    .iter()
+                    .filter_map(|key| std::env::var_os(key).map(|value| (key.to_string(), value))),
+            )
+            .current_dir(std::env::var_os("HOME").unwrap_or("/".into()))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .process_group(0)
+            .kill_on_drop(true);
+
+        let mut child = command.spawn()?;
+        let mut stdout = match child.stdout.take() {
+            Some(stdout) => stdout,
+            None => return Err(anyhow!("could not get stdout of the command")),
+        };
+
+        let timeout = Duration::from_millis(sensor.timeout_ms);
+        let result = tokio::time::timeout(timeout, async {
+            let mut bytes = vec![];
+            (&mut stdout)
+                .take(MAX_OUTPUT_BYTES)
+                .read_to_end(&mut bytes)
+                .await?;
+            // the rest is thrown away so the command isn't stuck on a full
+            // pipe until it times out
+            tokio::io::copy(&mut stdout, &mut tokio::io::sink()).await?;
+            let status = child.wait().await?;
+
+            anyhow::Ok((status, bytes))
+        })
+        .await;
+
+        // the child is killed on drop if it timed out
+        let (status, bytes) = match result {
+            Ok(res) => res?,
+            Err(_) => return Err(anyhow!("command timed out after {:?}", timeout)),
+        };
+
+        if !status.success() {
+            return Err(anyhow!("command exited with {status}"));
+        }
+
+        return Ok(match sensor.parse {
+            SensorParseMode::Plain => {
+                SensorValue::Plain(String::from_utf8_lossy(&bytes).trim().to_string())
+            }
+            SensorParseMode::Json => SensorValue::Json(serde_json::from_slice(&bytes)?),
+        });
+    }
+}
+
+/// aborts the sensor workers when the service stops running
+struct Workers(Vec<JoinHandle<()>>);
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        for worker in &self.0 {
+            worker.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(command: &str) -> SensorConfig {
+        return SensorConfig {
+            name: "test".to_string(),
+            command: command.to_string(),
+            interval_ms: 1000,
+            timeout_ms: 2000,
+            parse: SensorParseMode::Plain,
+        };
+    }
+
+    #[tokio::test]
+    async fn output_past_the_limit_is_dropped() {
+        let value = CustomService::run_sensor(&sensor("head -c 1000000 /dev/zero | tr '\\0' a"))
+            .await
+            .unwrap();
+
+        let SensorValue::Plain(output) = value else {
+            panic!("expected plain output, got {value:?}");
+        };
+        assert_eq!(output.len(), MAX_OUTPUT_BYTES as usize);
+    }
+
+    #[tokio::test]
+    async fn failing_command_is_an_error() {
+        assert!(CustomService::run_sensor(&sensor("exit 3")).await.is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use super::{CustomService, Event, SensorValue};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+/// the layout modules get sensor events in through `service_event`, a u8
+/// for the kind of event then the sensor's name as a u16 length then the
+/// bytes
+///
+/// - 0 the sensor ran, a u8 for how its output was parsed (0 plain and 1
+///   json) then the output as a u32 length then the bytes, json is sent as
+///   its text
+/// - 1 it failed with the error as a u16 length then the bytes
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        CustomService::ID
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        let mut bytes = vec![];
+
+        match self {
+            Event::SensorUpdated { name, value } => {
+                bytes.push(0);
+                push_str(&mut bytes, name);
+                let (parse, output) = match value {
+                    SensorValue::Plain(output) => (0, output.clone()),
+                    SensorValue::Json(value) => (1, value.to_string()),
+                };
+                bytes.push(parse);
+                let output = &output.as_bytes()[..output.len().min(u32::MAX as usize)];
+                bytes.extend((output.len() as u32).to_le_bytes());
+                bytes.extend(output);
+            }
+            Event::SensorFailed { name, error } => {
+                bytes.push(1);
+                push_str(&mut bytes, name);
+                push_str(&mut bytes, error);
+            }
+        }
+
+        return Some(bytes);
+    }
+
+    fn is_for(&self, register: &SubscriptionData) -> bool {
+        let SubscriptionData::Custom { data } = register else {
+            return false;
+        };
+
+        let (Event::SensorUpdated { name, .. } | Event::SensorFailed { name, .. }) = self;
+        return data.sensors.contains(name);
+    }
+}
+
+fn push_str(bytes: &mut Vec<u8>, string: &str) {
+    let string = &string.as_bytes()[..string.len().min(u16::MAX as usize)];
+    bytes.extend((string.len() as u16).to_le_bytes());
+    bytes.extend(string);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::custom::CustomSubscriptionData;
+
+    fn registered(sensors: &[&str]) -> SubscriptionData {
+        return SubscriptionData::Custom {
+            data: CustomSubscriptionData {
+                sensors: sensors.iter().map(|name| name.to_string()).collect(),
+            },
+        };
+    }
+
+    #[test]
+    fn registered_module_gets_the_event() {
+        let event = Event::SensorUpdated {
+            name: "temp".to_string(),
+            value: SensorValue::Plain("41".to_string()),
+        };
+
+        assert!(event.is_for(&registered(&["load", "temp"])));
+        assert!(!event.is_for(&registered(&["load"])));
+        assert!(!event.is_for(&SubscriptionData::Weather));
+
+        assert_eq!(
+            event.serialize().unwrap(),
+            [&[0, 4, 0][..], b"temp", &[0, 2, 0, 0, 0], b"41"].concat()
+        );
+    }
+
+    #[test]
+    fn serializes_json_as_text() {
+        let event = Event::SensorUpdated {
+            name: "j".to_string(),
+            value: SensorValue::Json(serde_json::json!([1])),
+        };
+
+        assert_eq!(
+            event.serialize().unwrap(),
+            [&[0, 1, 0][..], b"j", &[1, 3, 0, 0, 0], b"[1]"].concat()
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use super::data::SensorValue;
+use super::{CustomService, Event};
+
+use crate::services::ServiceState;
+
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct CustomState {
+    /// the last output of each sensor, keyed by the sensor's name
+    pub values: HashMap<String, SensorValue>,
+    /// the last error of each sensor, keyed by the sensor's name
+    ///
+    /// cleared when the sensor runs successfully again
+    pub errors: HashMap<String, String>,
+}
+
+impl ServiceState<CustomService> for CustomState {
+    fn init() -> Self {
+        Self {
+            values: HashMap::new(),
+            errors: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match event.clone() {
+            Event::SensorUpdated { name, value } => {
+                self.errors.remove(&name);
+                self.values.insert(name, value);
+            }
+            Event::SensorFailed { name, error } => {
+                self.errors.insert(name, error);
+            }
+        };
+
+        return vec![event];
+    }
+}
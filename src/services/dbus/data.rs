@@ -0,0 +1,86 @@
+use crate::runtime::RuntimeModuleId;
+
+/// messages emitted from the dbus service when an event happens
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// something the module watches sent a signal or changed its properties
+    Signal {
+        module: RuntimeModuleId,
+        signal: Signal,
+    },
+}
+
+/// requests for the dbus service
+#[derive(Debug, Clone)]
+pub enum Request {}
+
+/// what one of a module's `Dbus` registers saw, with the register's bus
+/// name, path and interface so the module can tell its registers apart
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signal {
+    pub name: String,
+    pub path: String,
+    pub interface: String,
+    pub kind: SignalKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignalKind {
+    /// the interface's properties changed, also sent with all of them when
+    /// the module registers or the service appears on the bus
+    PropertiesChanged {
+        changed: Vec<(String, DbusValue)>,
+        /// properties that changed without their new value being sent
+        invalidated: Vec<String>,
+    },
+    /// any other signal of the interface
+    Signal {
+        member: String,
+        args: Vec<DbusValue>,
+    },
+}
+
+/// a dbus value with the types modules don't need to tell apart merged,
+/// every integer is an `Int` or `Uint`, object paths and signatures are
+/// strings and variants are unwrapped
+#[derive(Debug, Clone, PartialEq)]
+pub enum DbusValue {
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    Double(f64),
+    String(String),
+    Array(Vec<DbusValue>),
+    Dict(Vec<(DbusValue, DbusValue)>),
+    Struct(Vec<DbusValue>),
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum DbusEventType {
+    Signal,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    Session,
+    System,
+}
+
+/// one of a module's `Dbus` registers, a module can have more than one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbusSubscriptionData {
+    /// the module's file name without the extension, checked against
+    /// `[modules.dbus]`. filled in by the runtime once the module is loaded
+    pub module: String,
+    pub bus: Bus,
+    /// a well-known bus name like `org.freedesktop.UPower`
+    pub name: String,
+    pub path: String,
+    pub interface: String,
+}
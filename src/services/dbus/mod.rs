@@ -0,0 +1,455 @@
+//! relays what other programs send on dbus to modules, so a module can show
+//! something the shell has no service for
+//!
+//! a module names what it wants with `Dbus` registers, each a bus, a bus
+//! name, an object path and an interface. it's sent the interface's
+//! properties when it registers, whenever they change and whenever the name
+//! gets a new owner, and the interface's other signals with their arguments.
+//! only the bus names listed for the module under `[modules.dbus]` can be
+//! watched, the system bus especially has a lot on it a module shouldn't
+//! read
+
+mod data;
+mod se;
+mod state;
+
+pub use data::{Bus, DbusSubscriptionData, Event, Request, Signal};
+pub use se::serialize_signal;
+pub use state::DbusState;
+
+use data::{DbusEventType, DbusValue, SignalKind};
+
+use crate::config;
+use crate::instrumented::{self, InstrumentedSender};
+use crate::runtime::RuntimeModuleId;
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::task::JoinSet;
+use zbus::Message;
+use zbus::zvariant::{OwnedValue, Structure, Value};
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// the most registers a module can have, the rest are dropped
+const MAX_REGISTERS: usize = 16;
+
+/// signals bigger than this once serialized aren't sent, so a service with
+/// huge properties can't fill a module's memory
+const MAX_SIGNAL_SIZE: usize = 1024 * 1024;
+
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct DbusService;
+
+/// the session and system bus, connected to when a module first watches
+/// something on them
+#[derive(Debug, Default)]
+struct Connections {
+    session: Option<zbus::Connection>,
+    system: Option<zbus::Connection>,
+}
+
+impl Service for DbusService {
+    type Event = Event;
+    type EventType = DbusEventType;
+    type Request = Request;
+    /// each module's registers, kept across restarts of the service like
+    /// `ModuleIds` so they're watched again
+    type RuntimeData = HashMap<RuntimeModuleId, Vec<DbusSubscriptionData>>;
+    type State = DbusState;
+    type SubscriptionData = DbusSubscriptionData;
+
+    const ID: u16 = 22;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.dbus.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut registers = HashMap::new();
+
+                loop {
+                    let mut state = DbusState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.dbus.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:dbus] could not send init event: {}", err);
+                        log::error!("[service:dbus] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut registers,
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:dbus] restarting");
+                        continue;
+                    }
+                    log::error!("[service:dbus] error: {err}");
+                    restart_delay::<Self>(Duration::from_secs(5)).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut DbusState,
+        module_ids: &mut ModuleIds<Self>,
+        registers: &mut HashMap<RuntimeModuleId, Vec<DbusSubscriptionData>>,
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let (signal_tx, signal_rx) = flume::unbounded::<(RuntimeModuleId, Signal)>();
+        let mut connections = Connections::default();
+
+        // a task for each register, dropping a module's set stops them. the
+        // registers from before a restart are watched again here
+        let mut watches: HashMap<RuntimeModuleId, JoinSet<()>> = HashMap::new();
+        for (module, module_registers) in registers.iter() {
+            for data in module_registers {
+                watch(&mut connections, &mut watches, module, data, &signal_tx).await;
+            }
+        }
+
+        log::info!("[service:dbus] service started");
+        startup::ready("dbus");
+
+        loop {
+            tokio::select! {
+                signal = signal_rx.recv_async() => {
+                    // the service holds a sender, so this never ends
+                    let Ok((module, signal)) = signal else {
+                        continue;
+                    };
+
+                    let size = serialize_signal(&signal).len();
+                    if size > MAX_SIGNAL_SIZE {
+                        log::warn!(
+                            "[service:dbus] dropped a {size} byte signal from {} for {module:?}",
+                            signal.name
+                        );
+                        continue;
+                    }
+
+                    for event in state.update(Event::Signal { module, signal }) {
+                        if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                            log::error!("[service:dbus] error sending service event update: {err}");
+                        }
+                    }
+                }
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => match request {},
+                        Ok(ServiceRequest::SubscribeModule { id, data }) => {
+                            let module_registers = registers.entry(id.clone()).or_default();
+
+                            if !is_allowed(&data) {
+                                log::warn!(
+                                    "[service:dbus] {} asked to watch {}, which isn't listed for \
+                                     it under `[modules.dbus]`",
+                                    data.module,
+                                    data.name
+                                );
+                            } else if module_registers.len() >= MAX_REGISTERS {
+                                log::warn!(
+                                    "[service:dbus] {} has more than {MAX_REGISTERS} `Dbus` \
+                                     registers, not watching {} {} {}",
+                                    data.module,
+                                    data.name,
+                                    data.path,
+                                    data.interface
+                                );
+                            } else if !module_registers.contains(&data) {
+                                watch(&mut connections, &mut watches, &id, &data, &signal_tx).await;
+                                module_registers.push(data);
+                            }
+
+                            let events = vec![DbusEventType::Signal];
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:dbus] {id:?} registered, {diff}");
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            registers.remove(&id);
+                            watches.remove(&id);
+                            state.signals.remove(&id);
+                            module_ids.unregister_module(id);
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:dbus] error receiving request: {err}");
+                        }
+                    }
+                }
+            };
+        }
+    }
+}
+
+impl Connections {
+    /// the connection to `bus`, connecting to it the first time
+    async fn get(&mut self, bus: Bus) -> zbus::Result<zbus::Connection> {
+        let conn = match bus {
+            Bus::Session => &mut self.session,
+            Bus::System => &mut self.system,
+        };
+
+        if let Some(conn) = conn {
+            return Ok(conn.clone());
+        }
+
+        let connected = match bus {
+            Bus::Session => zbus::Connection::session().await?,
+            Bus::System => zbus::Connection::system().await?,
+        };
+        *conn = Some(connected.clone());
+
+        return Ok(connected);
+    }
+}
+
+/// whether the module's bus name is listed for it under `[modules.dbus]`
+fn is_allowed(data: &DbusSubscriptionData) -> bool {
+    return config::get()
+        .modules
+        .dbus
+        .get(&data.module)
+        .is_some_and(|names| names.contains(&data.name));
+}
+
+/// starts relaying what a register watches to the module
+async fn watch(
+    connections: &mut Connections,
+    watches: &mut HashMap<RuntimeModuleId, JoinSet<()>>,
+    module: &RuntimeModuleId,
+    data: &DbusSubscriptionData,
+    signal_tx: &flume::Sender<(RuntimeModuleId, Signal)>,
+) {
+    let conn = match connections.get(data.bus).await {
+        Ok(conn) => conn,
+        Err(err) => {
+            log::warn!(
+                "[service:dbus] could not connect to the {:?} bus: {err}",
+                data.bus
+            );
+            return;
+        }
+    };
+
+    let module = module.clone();
+    let data = data.clone();
+    let signal_tx = signal_tx.clone();
+
+    watches
+        .entry(module.clone())
+        .or_default()
+        .spawn(async move {
+            if let Err(err) = relay(&conn, &module, &data, &signal_tx).await {
+                log::warn!(
+                    "[service:dbus] stopped watching {} {} {} for {}: {err}",
+                    data.name,
+                    data.path,
+                    data.interface,
+                    data.module
+                );
+            }
+        });
+}
+
+/// sends the register's properties and signals to the module until the
+/// task is dropped or the module's channel closes
+async fn relay(
+    conn: &zbus::Connection,
+    module: &RuntimeModuleId,
+    data: &DbusSubscriptionData,
+    signal_tx: &flume::Sender<(RuntimeModuleId, Signal)>,
+) -> zbus::Result<()> {
+    let object = zbus::Proxy::new(
+        conn,
+        data.name.as_str(),
+        data.path.as_str(),
+        data.interface.as_str(),
+    )
+    .await?;
+    let properties = zbus::Proxy::new(
+        conn,
+        data.name.as_str(),
+        data.path.as_str(),
+        PROPERTIES_INTERFACE,
+    )
+    .await?;
+
+    let mut signals = object.receive_all_signals().await?;
+    let mut changes = properties.receive_signal("PropertiesChanged").await?;
+    let mut owners = object.receive_owner_changed().await?;
+
+    let send = |kind: SignalKind| {
+        let signal = Signal {
+            name: data.name.clone(),
+            path: data.path.clone(),
+            interface: data.interface.clone(),
+            kind,
+        };
+        return signal_tx.send((module.clone(), signal)).is_ok();
+    };
+
+    // the properties as they are now, the name might not have an owner yet
+    if let Some(kind) = get_all(&properties, &data.interface).await
+        && !send(kind)
+    {
+        return Ok(());
+    }
+
+    loop {
+        let kind = tokio::select! {
+            message = signals.next() => match message {
+                Some(message) => Some(signal(&message)),
+                None => return Ok(()),
+            },
+            message = changes.next() => match message {
+                Some(message) => properties_changed(&message, &data.interface),
+                None => return Ok(()),
+            },
+            owner = owners.next() => match owner {
+                // a new owner has its own properties
+                Some(Some(_)) => get_all(&properties, &data.interface).await,
+                Some(None) => None,
+                None => return Ok(()),
+            },
+        };
+
+        if let Some(kind) = kind
+            && !send(kind)
+        {
+            return Ok(());
+        }
+    }
+}
+
+/// all of the interface's properties, `None` when they can't be read
+async fn get_all(properties: &zbus::Proxy<'_>, interface: &str) -> Option<SignalKind> {
+    let all: HashMap<String, OwnedValue> = match properties.call("GetAll", &(interface,)).await {
+        Ok(all) => all,
+        Err(err) => {
+            log::debug!("[service:dbus] could not read the properties of {interface}: {err}");
+            return None;
+        }
+    };
+
+    let mut changed: Vec<(String, DbusValue)> = all
+        .into_iter()
+        .map(|(name, value)| (name, convert(&value)))
+        .collect();
+    changed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    return Some(SignalKind::PropertiesChanged {
+        changed,
+        invalidated: vec![],
+    });
+}
+
+/// `None` when the properties changed on another interface of the object
+fn properties_changed(message: &Message, interface: &str) -> Option<SignalKind> {
+    let body = message.body();
+    let (changed_interface, changed, invalidated) =
+        match body.deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>() {
+            Ok(body) => body,
+            Err(err) => {
+                log::debug!("[service:dbus] could not read a PropertiesChanged signal: {err}");
+                return None;
+            }
+        };
+    if changed_interface != interface {
+        return None;
+    }
+
+    let mut changed: Vec<(String, DbusValue)> = changed
+        .into_iter()
+        .map(|(name, value)| (name, convert(&value)))
+        .collect();
+    changed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    return Some(SignalKind::PropertiesChanged {
+        changed,
+        invalidated,
+    });
+}
+
+/// any other signal, its arguments are left out if they can't be read
+fn signal(message: &Message) -> SignalKind {
+    let header = message.header();
+    let member = header
+        .member()
+        .map(|member| member.to_string())
+        .unwrap_or_default();
+
+    let body = message.body();
+    let args = match body.deserialize::<Structure>() {
+        Ok(args) => args.fields().iter().map(convert).collect(),
+        Err(_) => vec![],
+    };
+
+    return SignalKind::Signal { member, args };
+}
+
+/// merges the types modules don't need to tell apart, see `DbusValue`
+fn convert(value: &Value) -> DbusValue {
+    match value {
+        Value::Bool(value) => DbusValue::Bool(*value),
+        Value::I16(value) => DbusValue::Int(*value as i64),
+        Value::I32(value) => DbusValue::Int(*value as i64),
+        Value::I64(value) => DbusValue::Int(*value),
+        Value::U8(value) => DbusValue::Uint(*value as u64),
+        Value::U16(value) => DbusValue::Uint(*value as u64),
+        Value::U32(value) => DbusValue::Uint(*value as u64),
+        Value::U64(value) => DbusValue::Uint(*value),
+        Value::F64(value) => DbusValue::Double(*value),
+        Value::Str(value) => DbusValue::String(value.to_string()),
+        Value::ObjectPath(value) => DbusValue::String(value.to_string()),
+        Value::Signature(value) => DbusValue::String(value.to_string()),
+        Value::Value(value) => convert(value),
+        Value::Array(array) => DbusValue::Array(array.iter().map(convert).collect()),
+        Value::Dict(dict) => DbusValue::Dict(
+            dict.iter()
+                .map(|(key, value)| (convert(key), convert(value)))
+                .collect(),
+        ),
+        Value::Structure(structure) => {
+            DbusValue::Struct(structure.fields().iter().map(convert).collect())
+        }
+        // a file descriptor means nothing inside a module
+        Value::Fd(_) => DbusValue::Int(-1),
+    }
+}
@@ -0,0 +1,136 @@
+use super::data::{DbusValue, Signal, SignalKind};
+use super::{DbusService, Event};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        DbusService::ID
+    }
+
+    // a signal is only for the module watching it, the runtime gives it
+    // through `Request::DbusSignal`
+    fn serialize(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn is_for(&self, _register: &SubscriptionData) -> bool {
+        false
+    }
+}
+
+/// the layout modules get a signal in through `service_event`, everything
+/// is little endian
+///
+/// - a u8 for the kind of signal, 0 for properties changing and 1 for any
+///   other signal
+/// - the bus name, path and interface of the register, each a u16 length
+///   then the bytes
+/// - for 0 a u16 for the amount of changed properties, each a name then a
+///   value, then a u16 for the amount of invalidated properties, each a name
+/// - for 1 the signal's name then a u16 for the amount of arguments, each a
+///   value
+///
+/// it's made by the runtime rather than `serialize`, the signal is only for
+/// the one module that watches it
+pub fn serialize_signal(signal: &Signal) -> Vec<u8> {
+    let mut bytes = vec![];
+
+    bytes.push(match signal.kind {
+        SignalKind::PropertiesChanged { .. } => 0,
+        SignalKind::Signal { .. } => 1,
+    });
+    push_str(&mut bytes, &signal.name);
+    push_str(&mut bytes, &signal.path);
+    push_str(&mut bytes, &signal.interface);
+
+    match &signal.kind {
+        SignalKind::PropertiesChanged {
+            changed,
+            invalidated,
+        } => {
+            let changed = &changed[..changed.len().min(u16::MAX as usize)];
+            bytes.extend((changed.len() as u16).to_le_bytes());
+            for (name, value) in changed {
+                push_str(&mut bytes, name);
+                push_value(&mut bytes, value);
+            }
+
+            let invalidated = &invalidated[..invalidated.len().min(u16::MAX as usize)];
+            bytes.extend((invalidated.len() as u16).to_le_bytes());
+            for name in invalidated {
+                push_str(&mut bytes, name);
+            }
+        }
+        SignalKind::Signal { member, args } => {
+            push_str(&mut bytes, member);
+
+            let args = &args[..args.len().min(u16::MAX as usize)];
+            bytes.extend((args.len() as u16).to_le_bytes());
+            for arg in args {
+                push_value(&mut bytes, arg);
+            }
+        }
+    }
+
+    return bytes;
+}
+
+/// a u8 for the type then the value
+///
+/// - 0 a bool as a u8
+/// - 1 an i64, 2 a u64 and 3 an f64
+/// - 4 a string as a u32 length then the bytes
+/// - 5 an array and 7 a struct, a u32 for the amount of values then each
+///   value
+/// - 6 a dict, a u32 for the amount of entries then each key and value
+fn push_value(bytes: &mut Vec<u8>, value: &DbusValue) {
+    match value {
+        DbusValue::Bool(value) => {
+            bytes.push(0);
+            bytes.push(*value as u8);
+        }
+        DbusValue::Int(value) => {
+            bytes.push(1);
+            bytes.extend(value.to_le_bytes());
+        }
+        DbusValue::Uint(value) => {
+            bytes.push(2);
+            bytes.extend(value.to_le_bytes());
+        }
+        DbusValue::Double(value) => {
+            bytes.push(3);
+            bytes.extend(value.to_le_bytes());
+        }
+        DbusValue::String(value) => {
+            bytes.push(4);
+            bytes.extend((value.len() as u32).to_le_bytes());
+            bytes.extend(value.as_bytes());
+        }
+        DbusValue::Array(values) | DbusValue::Struct(values) => {
+            bytes.push(match value {
+                DbusValue::Array(_) => 5,
+                _ => 7,
+            });
+            bytes.extend((values.len() as u32).to_le_bytes());
+            for value in values {
+                push_value(bytes, value);
+            }
+        }
+        DbusValue::Dict(entries) => {
+            bytes.push(6);
+            bytes.extend((entries.len() as u32).to_le_bytes());
+            for (key, value) in entries {
+                push_value(bytes, key);
+                push_value(bytes, value);
+            }
+        }
+    }
+}
+
+fn push_str(bytes: &mut Vec<u8>, string: &str) {
+    let string = &string.as_bytes()[..string.len().min(u16::MAX as usize)];
+    bytes.extend((string.len() as u16).to_le_bytes());
+    bytes.extend(string);
+}
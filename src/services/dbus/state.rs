@@ -0,0 +1,30 @@
+use super::{DbusService, Event};
+
+use crate::runtime::RuntimeModuleId;
+use crate::services::ServiceState;
+
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct DbusState {
+    /// how many signals each module was given since it registered
+    pub signals: HashMap<RuntimeModuleId, u64>,
+}
+
+impl ServiceState<DbusService> for DbusState {
+    fn init() -> Self {
+        Self {
+            signals: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match event.clone() {
+            Event::Signal { module, .. } => {
+                *self.signals.entry(module).or_default() += 1;
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -0,0 +1,62 @@
+use crate::runtime::RuntimeModuleId;
+
+/// messages emitted from the hotkeys service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when one of a module's hotkeys was pressed, `index` is
+    /// where it is in the module's register
+    KeyPressed { module: RuntimeModuleId, index: u32 },
+
+    /// event emitted when the desktop portal has no global shortcuts
+    ServiceUnavailable { reason: String },
+    /// event emitted when the service connected to the desktop portal
+    ServiceAvailable,
+}
+
+/// requests for the hotkeys service
+#[derive(Debug, Clone)]
+pub enum Request {}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum HotkeysEventType {
+    KeyPressed,
+}
+
+impl Event {
+    /// the kind of event modules register for, `None` for the events about
+    /// the service itself
+    pub fn event_type(&self) -> Option<HotkeysEventType> {
+        match self {
+            Self::KeyPressed { .. } => Some(HotkeysEventType::KeyPressed),
+            Self::ServiceUnavailable { .. } | Self::ServiceAvailable => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+/// the hotkeys a module wants, read from the register's extra data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotkeysSubscriptionData {
+    /// the name of the module, filled in by the runtime as the register
+    /// doesn't have it. the portal keeps the keys the user picked by the
+    /// hotkey's id, so it's made from this rather than the module's id which
+    /// can change between runs
+    pub module: String,
+    pub hotkeys: Vec<Hotkey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hotkey {
+    /// unique within the module, like `toggle-popup`
+    pub id: String,
+    /// shown to the user when the desktop asks them to bind it
+    pub description: String,
+    /// the keys the module would like, like `LOGO+a` or `CTRL+ALT+t`. only a
+    /// suggestion, the desktop or the user has the final say. empty for none
+    pub trigger: String,
+}
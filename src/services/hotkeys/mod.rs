@@ -0,0 +1,396 @@
+//! keybinds that work anywhere on the desktop for modules, through the
+//! desktop portal's global shortcuts, like one to open a module's popup
+//!
+//! the portal is implemented by kde, gnome and hyprland's portal backends,
+//! which may ask the user to confirm or change the keys the first time.
+//! shortcuts can't be taken back out of a portal session, so a new session
+//! is made whenever modules register or go away, once they stop for a bit
+
+mod data;
+mod portal;
+mod se;
+mod state;
+
+pub use data::{Event, Hotkey, HotkeysSubscriptionData, Request};
+pub use state::HotkeysState;
+
+use data::HotkeysEventType;
+use portal::{GlobalShortcutsProxy, RegistryProxy, SessionProxy};
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::runtime::RuntimeModuleId;
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::time::Instant;
+use zbus::zvariant::{OwnedObjectPath, Value};
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// modules register one after another on startup, so the shortcuts are
+/// bound once they stop for this long rather than for each module
+const REBIND_DELAY: Duration = Duration::from_millis(500);
+
+/// what the shell calls itself to the portal
+const APP_ID: &str = "aurorashell";
+
+/// the first wait before trying to reach the portal again after it
+/// couldn't be found, doubles each failed attempt
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(5);
+/// the longest wait between attempts to reach the portal
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct HotkeysService;
+
+/// kept across restarts of the service, so the hotkeys are bound again
+/// without the modules registering again
+#[derive(Debug, Default)]
+pub struct HotkeysData {
+    /// what each module registered
+    bindings: HashMap<RuntimeModuleId, HotkeysSubscriptionData>,
+}
+
+/// returned from `HotkeysService::run` when the portal has no global
+/// shortcuts, so the service waits longer before trying again
+#[derive(Debug)]
+struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "global shortcuts unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+impl Service for HotkeysService {
+    type Event = Event;
+    type EventType = HotkeysEventType;
+    type Request = Request;
+    type RuntimeData = HotkeysData;
+    type State = HotkeysState;
+    type SubscriptionData = HotkeysSubscriptionData;
+
+    const ID: u16 = 14;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.hotkeys.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut data = HotkeysData::default();
+                let mut backoff = RETRY_BACKOFF_MIN;
+
+                loop {
+                    let mut state = HotkeysState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.hotkeys.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:hotkeys] could not send init event: {}", err);
+                        log::error!("[service:hotkeys] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut data,
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:hotkeys] restarting");
+                        backoff = RETRY_BACKOFF_MIN;
+                        continue;
+                    }
+
+                    let (reason, delay) = match err.downcast_ref::<Unavailable>() {
+                        Some(Unavailable(reason)) => {
+                            log::warn!(
+                                "[service:hotkeys] {err}, retrying in {}",
+                                humantime::format_duration(backoff)
+                            );
+
+                            let delay = backoff;
+                            backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                            (reason.clone(), delay)
+                        }
+                        None => {
+                            log::error!("[service:hotkeys] error: {err}");
+                            log::error!("[service:hotkeys] restarting in 5 seconds...");
+
+                            backoff = RETRY_BACKOFF_MIN;
+                            (err.to_string(), Duration::from_secs(5))
+                        }
+                    };
+
+                    emit(
+                        &mut state,
+                        &mut chan,
+                        vec![Event::ServiceUnavailable { reason }],
+                    )
+                    .await;
+
+                    restart_delay::<Self>(delay).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut HotkeysState,
+        module_ids: &mut ModuleIds<Self>,
+        data: &mut HotkeysData,
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let conn = match zbus::Connection::session().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                return Unavailable(format!("could not connect to the session bus: {err}")).into();
+            }
+        };
+
+        // older portals don't have the registry and take the app from the
+        // connection instead
+        if let Ok(registry) = RegistryProxy::new(&conn).await
+            && let Err(err) = registry.register(APP_ID, HashMap::new()).await
+        {
+            log::debug!("[service:hotkeys] could not register with the portal: {err}");
+        }
+
+        let shortcuts = match GlobalShortcutsProxy::new(&conn).await {
+            Ok(shortcuts) => shortcuts,
+            Err(err) => return Unavailable(format!("could not create proxy: {err}")).into(),
+        };
+
+        // fails when there's no portal or its backend has no global shortcuts
+        if let Err(err) = shortcuts.version().await {
+            return Unavailable(err.to_string()).into();
+        }
+
+        let mut activated = match shortcuts.receive_activated().await {
+            Ok(activated) => activated,
+            Err(err) => {
+                return anyhow!("[service:hotkeys] could not listen for shortcuts: {err}");
+            }
+        };
+
+        log::info!("[service:hotkeys] service started");
+        startup::ready("hotkeys");
+        emit(state, chan, vec![Event::ServiceAvailable]).await;
+
+        let mut session: Option<OwnedObjectPath> = None;
+        // the modules from before a restart are bound again right away
+        let mut rebind_at = (!data.bindings.is_empty()).then(Instant::now);
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(rebind_at.unwrap_or_else(Instant::now)), if rebind_at.is_some() => {
+                    rebind_at = None;
+
+                    // the user can turn the shortcuts down, which isn't
+                    // worth restarting over
+                    if let Err(err) = Self::bind(&conn, &shortcuts, &mut session, data).await {
+                        log::warn!("[service:hotkeys] could not bind the hotkeys: {err}");
+                    }
+                }
+                signal = activated.next() => {
+                    let Some(signal) = signal else {
+                        return anyhow!("[service:hotkeys] signal stream ended");
+                    };
+                    let args = match signal.args() {
+                        Ok(args) => args,
+                        Err(err) => {
+                            log::warn!("[service:hotkeys] could not read a shortcut: {err}");
+                            continue;
+                        }
+                    };
+
+                    // a session that was just replaced can still send one
+                    if session
+                        .as_ref()
+                        .is_none_or(|session| session.as_str() != args.session_handle.as_str())
+                    {
+                        continue;
+                    }
+
+                    match data.find(args.shortcut_id) {
+                        Some((module, index)) => {
+                            log::debug!(
+                                "[service:hotkeys] {} was pressed for {module:?}",
+                                args.shortcut_id
+                            );
+                            emit(state, chan, vec![Event::KeyPressed { module, index }])
+                                .await;
+                        }
+                        None => log::debug!(
+                            "[service:hotkeys] {} was pressed, which no module has",
+                            args.shortcut_id
+                        ),
+                    }
+                }
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => match request {},
+                        Ok(ServiceRequest::SubscribeModule { id, data: subscription }) => {
+                            let events = vec![HotkeysEventType::KeyPressed];
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!(
+                                "[service:hotkeys] {id:?} registered {} hotkeys, {diff}",
+                                subscription.hotkeys.len()
+                            );
+
+                            data.bindings.insert(id, subscription);
+                            rebind_at = Some(Instant::now() + REBIND_DELAY);
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            module_ids.unregister_module(id.clone());
+                            if data.bindings.remove(&id).is_some() {
+                                rebind_at = Some(Instant::now() + REBIND_DELAY);
+                            }
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:hotkeys] error receiving request: {err}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl HotkeysService {
+    /// closes the old session and binds every module's hotkeys in a new
+    /// one, none is made when no module has hotkeys
+    async fn bind(
+        conn: &zbus::Connection,
+        shortcuts: &GlobalShortcutsProxy<'_>,
+        session: &mut Option<OwnedObjectPath>,
+        data: &HotkeysData,
+    ) -> anyhow::Result<()> {
+        if let Some(old) = session.take() {
+            let closed = match SessionProxy::builder(conn).path(old)?.build().await {
+                Ok(proxy) => proxy.close().await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = closed {
+                log::debug!("[service:hotkeys] could not close the old session: {err}");
+            }
+        }
+
+        let bound = data.shortcuts();
+        if bound.is_empty() {
+            return Ok(());
+        }
+
+        let session_token = portal::token();
+        let handle_token = portal::token();
+        let options = HashMap::from([
+            ("handle_token", Value::from(handle_token.as_str())),
+            ("session_handle_token", Value::from(session_token.as_str())),
+        ]);
+        portal::call(conn, &handle_token, shortcuts.create_session(options)).await?;
+
+        let path =
+            OwnedObjectPath::try_from(portal::handle_path(conn, "session", &session_token)?)?;
+        *session = Some(path.clone());
+
+        let list: Vec<(&str, HashMap<&str, Value<'_>>)> = bound
+            .iter()
+            .map(|(id, hotkey)| {
+                let mut options =
+                    HashMap::from([("description", Value::from(hotkey.description.as_str()))]);
+                if !hotkey.trigger.is_empty() {
+                    options.insert("preferred_trigger", Value::from(hotkey.trigger.as_str()));
+                }
+                (id.as_str(), options)
+            })
+            .collect();
+
+        let handle_token = portal::token();
+        let options = HashMap::from([("handle_token", Value::from(handle_token.as_str()))]);
+        portal::call(
+            conn,
+            &handle_token,
+            shortcuts.bind_shortcuts(&path, &list, "", options),
+        )
+        .await?;
+
+        log::info!("[service:hotkeys] bound {} hotkeys", list.len());
+        return Ok(());
+    }
+}
+
+impl HotkeysData {
+    /// every module's hotkeys by the id they're bound with in the portal,
+    /// like `launcher:toggle`. a hotkey with the same id as one before it is
+    /// left out
+    fn shortcuts(&self) -> Vec<(String, &Hotkey)> {
+        let mut seen = HashSet::new();
+        let mut shortcuts = vec![];
+
+        for binding in self.bindings.values() {
+            for hotkey in &binding.hotkeys {
+                let id = shortcut_id(&binding.module, &hotkey.id);
+                if !seen.insert(id.clone()) {
+                    log::warn!("[service:hotkeys] {id} is registered more than once");
+                    continue;
+                }
+                shortcuts.push((id, hotkey));
+            }
+        }
+
+        return shortcuts;
+    }
+
+    /// the module with the shortcut and the index of the hotkey in its
+    /// register
+    fn find(&self, shortcut: &str) -> Option<(RuntimeModuleId, u32)> {
+        for (module, binding) in &self.bindings {
+            let index = binding
+                .hotkeys
+                .iter()
+                .position(|hotkey| shortcut_id(&binding.module, &hotkey.id) == shortcut);
+
+            if let Some(index) = index {
+                return Some((module.clone(), index as u32));
+            }
+        }
+
+        return None;
+    }
+}
+
+fn shortcut_id(module: &str, hotkey: &str) -> String {
+    format!("{module}:{hotkey}")
+}
@@ -0,0 +1,124 @@
+//! the parts of the desktop portal's dbus api the service uses
+//!
+//! see https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.GlobalShortcuts.html
+//!
+//! methods on the portal answer through a `Request` object with a
+//! `Response` signal rather than returning, so `call` listens on it first
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use anyhow::{anyhow, bail};
+use iced::futures::StreamExt;
+use zbus::proxy;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+/// makes the tokens the request and session paths are made from unique
+static NEXT_TOKEN: AtomicU32 = AtomicU32::new(0);
+
+#[proxy(
+    interface = "org.freedesktop.portal.GlobalShortcuts",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+pub trait GlobalShortcuts {
+    fn create_session(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+
+    /// `shortcuts` is each shortcut's id with its `description` and
+    /// `preferred_trigger`, the trigger is only a suggestion
+    fn bind_shortcuts(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        shortcuts: &[(&str, HashMap<&str, Value<'_>>)],
+        parent_window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(signal)]
+    fn activated(
+        &self,
+        session_handle: ObjectPath<'_>,
+        shortcut_id: &str,
+        timestamp: u64,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    #[zbus(property, name = "version")]
+    fn version(&self) -> zbus::Result<u32>;
+}
+
+/// tells the portal which app a program that isn't sandboxed is, backends
+/// like kde's keep shortcuts by app. older portals don't have it
+#[proxy(
+    interface = "org.freedesktop.host.portal.Registry",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+pub trait Registry {
+    /// has to come before any other call to the portal on the connection
+    fn register(&self, app_id: &str, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.portal.Request",
+    default_service = "org.freedesktop.portal.Desktop"
+)]
+pub trait Request {
+    /// 0 when it went through, 1 when the user cancelled it and 2 when it
+    /// failed some other way
+    #[zbus(signal)]
+    fn response(&self, response: u32, results: HashMap<&str, OwnedValue>) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.portal.Session",
+    default_service = "org.freedesktop.portal.Desktop"
+)]
+pub trait Session {
+    fn close(&self) -> zbus::Result<()>;
+}
+
+/// a token for `handle_token` or `session_handle_token`
+pub fn token() -> String {
+    format!("aurorashell{}", NEXT_TOKEN.fetch_add(1, Ordering::Relaxed))
+}
+
+/// the path the portal puts the object for `token` under, `kind` is
+/// `request` or `session`
+pub fn handle_path(conn: &zbus::Connection, kind: &str, token: &str) -> anyhow::Result<String> {
+    let sender = conn
+        .unique_name()
+        .ok_or_else(|| anyhow!("the connection has no name"))?;
+    let sender = sender.trim_start_matches(':').replace('.', "_");
+
+    return Ok(format!(
+        "/org/freedesktop/portal/desktop/{kind}/{sender}/{token}"
+    ));
+}
+
+/// calls a portal method made with `handle_token` set to `token`, then waits
+/// for its response
+pub async fn call<F>(conn: &zbus::Connection, token: &str, method: F) -> anyhow::Result<()>
+where
+    F: Future<Output = zbus::Result<OwnedObjectPath>>,
+{
+    // the response could come before a listener made after the call
+    let request = RequestProxy::builder(conn)
+        .path(handle_path(conn, "request", token)?)?
+        .build()
+        .await?;
+    let mut responses = request.receive_response().await?;
+
+    method.await?;
+
+    let Some(response) = responses.next().await else {
+        bail!("the portal stopped before answering");
+    };
+
+    match response.args()?.response {
+        0 => Ok(()),
+        1 => bail!("it was cancelled"),
+        code => bail!("the portal answered with {code}"),
+    }
+}
@@ -0,0 +1,20 @@
+use super::{Event, HotkeysService};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        HotkeysService::ID
+    }
+
+    // presses go to the module that registered the hotkey through
+    // `Request::HotkeyPressed`
+    fn serialize(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn is_for(&self, _register: &SubscriptionData) -> bool {
+        false
+    }
+}
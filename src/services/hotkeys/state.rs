@@ -0,0 +1,30 @@
+use super::{Event, HotkeysService};
+
+use crate::services::ServiceState;
+
+#[derive(Debug)]
+pub struct HotkeysState {
+    /// why the portal's global shortcuts can't be used, `None` when they can
+    pub unavailable: Option<String>,
+}
+
+impl ServiceState<HotkeysService> for HotkeysState {
+    fn init() -> Self {
+        Self { unavailable: None }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match event.clone() {
+            // every press goes out, even the same key twice
+            Event::KeyPressed { .. } => {}
+            Event::ServiceUnavailable { reason } => {
+                self.unavailable = Some(reason);
+            }
+            Event::ServiceAvailable => {
+                self.unavailable = None;
+            }
+        };
+
+        return vec![event];
+    }
+}
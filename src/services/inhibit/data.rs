@@ -0,0 +1,78 @@
+/// messages emitted from the inhibit service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when the shell starts or stops keeping the session from
+    /// going idle
+    InhibitChanged { inhibited: bool },
+
+    /// event emitted when logind can't be reached
+    ServiceUnavailable { reason: String },
+    /// event emitted when the service connected to logind
+    ServiceAvailable,
+}
+
+/// requests for the inhibit service
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// takes logind's idle inhibitor lock, so the screen doesn't blank or
+    /// lock, or lets go of it
+    SetInhibit { inhibit: bool },
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum InhibitEventType {
+    InhibitChanged,
+}
+
+impl Event {
+    /// the kind of event modules register for, `None` for the events about
+    /// the service itself
+    pub fn event_type(&self) -> Option<InhibitEventType> {
+        match self {
+            Self::InhibitChanged { .. } => Some(InhibitEventType::InhibitChanged),
+            Self::ServiceUnavailable { .. } | Self::ServiceAvailable => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InhibitSubscriptionData(pub u8);
+
+impl InhibitSubscriptionData {
+    /// subscribes to the idle inhibitor being taken or let go of
+    pub const INHIBIT_CHANGED: Self = Self(0b_0000_0001);
+
+    pub fn is_set(&self, case: InhibitSubscriptionData) -> bool {
+        return *self & case != InhibitSubscriptionData(0);
+    }
+}
+
+impl BitOr for InhibitSubscriptionData {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for InhibitSubscriptionData {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for InhibitSubscriptionData {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
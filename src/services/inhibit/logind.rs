@@ -0,0 +1,18 @@
+//! the part of logind's dbus api the service uses
+//!
+//! see https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+
+use zbus::proxy;
+use zbus::zvariant::OwnedFd;
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+pub trait Manager {
+    /// takes an inhibitor lock, which is held until the returned fd is
+    /// closed. `what` is a colon separated list like `idle` or
+    /// `sleep:shutdown` and `mode` is `block` or `delay`
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+}
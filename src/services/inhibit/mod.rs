@@ -0,0 +1,267 @@
+//! keeps the session from going idle, for a "caffeine" toggle on the bar
+//!
+//! this takes logind's `idle` inhibitor lock, which the idle daemons and
+//! desktops that follow logind's inhibitors (like hypridle or kde's) check
+//! before blanking or locking the screen. the lock is let go of when the
+//! service stops, so a crashed shell doesn't keep the screen on
+
+mod data;
+mod logind;
+mod se;
+mod state;
+
+pub use data::{Event, InhibitSubscriptionData, Request};
+pub use state::InhibitState;
+
+use data::InhibitEventType;
+use logind::ManagerProxy;
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use zbus::zvariant::OwnedFd;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// the first wait before looking for logind again after it couldn't be
+/// reached, doubles each failed attempt
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(5);
+/// the longest wait between attempts
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
+/// what logind shows as holding the lock, in `systemd-inhibit --list`
+const WHO: &str = "aurorashell";
+const WHY: &str = "a module asked to keep the screen on";
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// whether the session is kept from going idle, 0 for no, 1 for yes and 2
+/// when the service isn't running
+///
+/// kept outside the app like `services::set_available`, so the wasm runtime
+/// can answer modules asking without a round trip
+static INHIBITED: AtomicU8 = AtomicU8::new(2);
+
+/// whether the session is kept from going idle, `None` when the service
+/// isn't running
+pub fn inhibited() -> Option<bool> {
+    match INHIBITED.load(Ordering::Relaxed) {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InhibitService;
+
+/// returned from `InhibitService::run` when logind couldn't be reached, so
+/// the service waits longer before trying again
+#[derive(Debug)]
+struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "idle inhibitor unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+impl Service for InhibitService {
+    type Event = Event;
+    type EventType = InhibitEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = InhibitState;
+    type SubscriptionData = InhibitSubscriptionData;
+
+    const ID: u16 = 18;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.inhibit.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut backoff = RETRY_BACKOFF_MIN;
+
+                loop {
+                    let mut state = InhibitState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.inhibit.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:inhibit] could not send init event: {}", err);
+                        log::error!("[service:inhibit] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:inhibit] restarting");
+                        backoff = RETRY_BACKOFF_MIN;
+                        continue;
+                    }
+
+                    let (reason, delay) = match err.downcast_ref::<Unavailable>() {
+                        Some(Unavailable(reason)) => {
+                            log::warn!(
+                                "[service:inhibit] {err}, retrying in {}",
+                                humantime::format_duration(backoff)
+                            );
+
+                            let delay = backoff;
+                            backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                            (reason.clone(), delay)
+                        }
+                        None => {
+                            log::error!("[service:inhibit] error: {err}");
+                            log::error!("[service:inhibit] restarting in 5 seconds...");
+
+                            backoff = RETRY_BACKOFF_MIN;
+                            (err.to_string(), Duration::from_secs(5))
+                        }
+                    };
+
+                    Self::emit(
+                        &mut state,
+                        &mut chan,
+                        vec![Event::ServiceUnavailable { reason }],
+                    )
+                    .await;
+
+                    restart_delay::<Self>(delay).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut InhibitState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let conn = match zbus::Connection::system().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                return Unavailable(format!("could not connect to the system bus: {err}")).into();
+            }
+        };
+        let manager = match ManagerProxy::new(&conn).await {
+            Ok(manager) => manager,
+            Err(err) => return Unavailable(format!("could not create proxy: {err}")).into(),
+        };
+
+        // closed when this returns, which lets go of the lock
+        let mut lock: Option<OwnedFd> = None;
+
+        log::info!("[service:inhibit] service started");
+        startup::ready("inhibit");
+
+        Self::emit(
+            state,
+            chan,
+            vec![
+                Event::ServiceAvailable,
+                Event::InhibitChanged { inhibited: false },
+            ],
+        )
+        .await;
+
+        loop {
+            match request_rx.recv_async().await {
+                Ok(ServiceRequest::Request { request }) => match request {
+                    Request::SetInhibit { inhibit: true } if lock.is_none() => {
+                        match manager.inhibit("idle", WHO, WHY, "block").await {
+                            Ok(fd) => {
+                                log::info!("[service:inhibit] keeping the session from going idle");
+                                lock = Some(fd);
+                            }
+                            Err(err) => {
+                                log::warn!("[service:inhibit] could not take the lock: {err}");
+                                continue;
+                            }
+                        }
+                    }
+                    Request::SetInhibit { inhibit: false } if lock.is_some() => {
+                        log::info!("[service:inhibit] letting the session go idle");
+                        lock = None;
+                    }
+                    // already how it was asked to be
+                    Request::SetInhibit { .. } => continue,
+                },
+                Ok(ServiceRequest::SubscribeModule { id, data }) => {
+                    let mut events = vec![];
+
+                    if data.is_set(InhibitSubscriptionData::INHIBIT_CHANGED) {
+                        events.push(InhibitEventType::InhibitChanged);
+                    }
+
+                    let diff = module_ids.register_module(id.clone(), events);
+                    log::debug!("[service:inhibit] {id:?} registered, {diff}");
+                    continue;
+                }
+                Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                    module_ids.unregister_module(id);
+                    continue;
+                }
+                Err(err) => {
+                    return anyhow!("[service:inhibit] error receiving request: {err}");
+                }
+            }
+
+            let inhibited = lock.is_some();
+            Self::emit(state, chan, vec![Event::InhibitChanged { inhibited }]).await;
+        }
+    }
+}
+
+impl InhibitService {
+    /// `services::emit`, keeping `INHIBITED` up to date for modules
+    async fn emit(
+        state: &mut InhibitState,
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        events: Vec<Event>,
+    ) {
+        emit(state, chan, events).await;
+
+        let inhibited = match (&state.unavailable, state.inhibited) {
+            (Some(_), _) => 2,
+            (None, inhibited) => inhibited as u8,
+        };
+        INHIBITED.store(inhibited, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,60 @@
+use super::{Event, InhibitService, InhibitSubscriptionData, Request};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+/// the layout modules get inhibit events in through `service_event`, a u8
+/// for the kind of event then what changed
+///
+/// - 0 a u8 for the session being kept from going idle
+/// - 1 the service is unavailable with the reason as a u16 length then the
+///   bytes, 2 it's available again
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        InhibitService::ID
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        let mut bytes = vec![];
+
+        match self {
+            Event::InhibitChanged { inhibited } => {
+                bytes.push(0);
+                bytes.push(*inhibited as u8);
+            }
+            Event::ServiceUnavailable { reason } => {
+                bytes.push(1);
+                let reason = &reason.as_bytes()[..reason.len().min(u16::MAX as usize)];
+                bytes.extend((reason.len() as u16).to_le_bytes());
+                bytes.extend(reason);
+            }
+            Event::ServiceAvailable => bytes.push(2),
+        }
+
+        return Some(bytes);
+    }
+
+    fn is_for(&self, register: &SubscriptionData) -> bool {
+        let SubscriptionData::Inhibit { data } = register else {
+            return false;
+        };
+
+        return self
+            .event_type()
+            .is_none_or(|_| data.is_set(InhibitSubscriptionData::INHIBIT_CHANGED));
+    }
+}
+
+impl Request {
+    /// the request for an action a module asked for through
+    /// `inhibit_request`, `None` for an unknown action
+    ///
+    /// 0 lets the session go idle again and 1 keeps it from going idle
+    pub fn from_module(action: u32) -> Option<Self> {
+        match action {
+            0 => Some(Self::SetInhibit { inhibit: false }),
+            1 => Some(Self::SetInhibit { inhibit: true }),
+            _ => None,
+        }
+    }
+}
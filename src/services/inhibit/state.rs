@@ -0,0 +1,48 @@
+use super::data::InhibitEventType;
+use super::{Event, InhibitService};
+
+use crate::services::{Dedup, ServiceState};
+
+#[derive(Debug)]
+pub struct InhibitState {
+    pub inhibited: bool,
+
+    /// why the session can't be kept from going idle, `None` when it can
+    pub unavailable: Option<String>,
+
+    /// a module asking to inhibit twice doesn't change anything
+    dedup: Dedup<InhibitEventType>,
+}
+
+impl ServiceState<InhibitService> for InhibitState {
+    fn init() -> Self {
+        Self {
+            inhibited: false,
+            unavailable: None,
+            dedup: Dedup::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        if let Some(kind) = event.event_type()
+            && !self.dedup.is_new(kind, &event)
+        {
+            return vec![];
+        }
+
+        match event.clone() {
+            Event::InhibitChanged { inhibited } => {
+                self.inhibited = inhibited;
+            }
+            Event::ServiceUnavailable { reason } => {
+                *self = Self::init();
+                self.unavailable = Some(reason);
+            }
+            Event::ServiceAvailable => {
+                self.unavailable = None;
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -0,0 +1,40 @@
+use crate::runtime::RuntimeModuleId;
+
+/// messages emitted from the interval service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// one or more of the module's intervals came around
+    Elapsed { module: RuntimeModuleId },
+}
+
+/// requests for the interval service
+#[derive(Debug, Clone)]
+pub enum Request {}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum IntervalEventType {
+    Elapsed,
+}
+
+impl Event {
+    /// the kind of event modules register for
+    pub fn event_type(&self) -> IntervalEventType {
+        match self {
+            Self::Elapsed { .. } => IntervalEventType::Elapsed,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+/// one of a module's `Interval` registers, a module can have more than one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntervalSubscriptionData {
+    pub milliseconds: u64,
+    /// shifts when the interval comes around forward, in milliseconds
+    pub offset: u32,
+}
@@ -0,0 +1,238 @@
+//! renders modules again on the `Interval`s they registered
+//!
+//! intervals line up with the wall clock instead of when the module loaded,
+//! so an interval of a minute comes around on the minute and two modules
+//! with the same interval are rendered together. the offset shifts that
+//! forward, so two 5 second intervals with one offset by 2 seconds come
+//! around 2 seconds apart
+
+mod data;
+mod se;
+mod state;
+
+pub use data::{Event, IntervalSubscriptionData, Request};
+pub use state::IntervalState;
+
+use data::IntervalEventType;
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::runtime::RuntimeModuleId;
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::time::Instant;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// the shortest interval a module can ask for, shorter ones are raised to it
+/// so a typo can't render a module in a loop
+const MIN_PERIOD: Duration = Duration::from_millis(100);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct IntervalService;
+
+/// one of a module's intervals
+#[derive(Debug)]
+struct Timer {
+    period: Duration,
+    offset: Duration,
+    /// when it comes around next
+    next: Instant,
+}
+
+impl Service for IntervalService {
+    type Event = Event;
+    type EventType = IntervalEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = IntervalState;
+    type SubscriptionData = IntervalSubscriptionData;
+
+    const ID: u16 = 3;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.interval.events", chan);
+                let mut module_ids = ModuleIds::new();
+
+                loop {
+                    let mut state = IntervalState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.interval.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:interval] could not send init event: {}", err);
+                        log::error!("[service:interval] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:interval] restarting");
+                        continue;
+                    }
+                    log::error!("[service:interval] error: {err}");
+                    restart_delay::<Self>(Duration::from_secs(5)).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut IntervalState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        // a module registers each of its intervals on its own, they're all
+        // dropped together when it's unloaded
+        let mut timers: HashMap<RuntimeModuleId, Vec<Timer>> = HashMap::new();
+
+        log::info!("[service:interval] service started");
+        startup::ready("interval");
+
+        loop {
+            let next = timers.values().flatten().map(|timer| timer.next).min();
+
+            let modules: HashSet<RuntimeModuleId> = tokio::select! {
+                _ = async {
+                    match next {
+                        Some(next) => tokio::time::sleep_until(next).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    let now = Instant::now();
+                    let mut modules = HashSet::new();
+
+                    for (module, timers) in timers.iter_mut() {
+                        for timer in timers.iter_mut().filter(|timer| timer.next <= now) {
+                            timer.advance(now);
+                            modules.insert(module.clone());
+                        }
+                    }
+
+                    modules
+                }
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => match request {},
+                        Ok(ServiceRequest::SubscribeModule { id, data }) => {
+                            if let Some(timer) = Timer::new(&id, data) {
+                                timers.entry(id.clone()).or_default().push(timer);
+                            }
+
+                            let events = vec![IntervalEventType::Elapsed];
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:interval] {id:?} registered, {diff}");
+                            HashSet::new()
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            timers.remove(&id);
+                            state.elapsed.remove(&id);
+                            module_ids.unregister_module(id);
+                            HashSet::new()
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:interval] error receiving request: {err}");
+                        }
+                    }
+                }
+            };
+
+            for module in modules {
+                for event in state.update(Event::Elapsed { module }) {
+                    if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                        log::error!("[service:interval] error sending service event update: {err}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Timer {
+    /// `None` for an interval of 0, which would never come around
+    fn new(id: &RuntimeModuleId, data: IntervalSubscriptionData) -> Option<Self> {
+        if data.milliseconds == 0 {
+            log::warn!("[service:interval] {id:?} asked for an interval of 0ms, ignoring it");
+            return None;
+        }
+
+        let mut period = Duration::from_millis(data.milliseconds);
+        if period < MIN_PERIOD {
+            log::warn!(
+                "[service:interval] {id:?} asked for an interval of {}ms, using {}ms",
+                data.milliseconds,
+                MIN_PERIOD.as_millis()
+            );
+            period = MIN_PERIOD;
+        }
+
+        let offset = Duration::from_millis(data.offset as u64);
+
+        return Some(Self {
+            period,
+            offset,
+            next: Instant::now() + until_next(period, offset),
+        });
+    }
+
+    /// moves `next` to when the interval comes around after `now`
+    ///
+    /// it's lined up with the wall clock again each time, so it doesn't
+    /// drift and after a suspend it comes around once instead of for every
+    /// time it missed
+    fn advance(&mut self, now: Instant) {
+        let aligned = now + until_next(self.period, self.offset);
+        // the wall clock can be a little behind, which would have it come
+        // around twice right after each other
+        self.next = aligned.max(self.next + self.period);
+    }
+}
+
+/// how long until the wall clock is at a multiple of `period` past `offset`
+fn until_next(period: Duration, offset: Duration) -> Duration {
+    let period = period.as_millis();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    let since = (now % period + period - offset.as_millis() % period) % period;
+
+    return Duration::from_millis((period - since) as u64);
+}
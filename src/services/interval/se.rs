@@ -0,0 +1,19 @@
+use super::{Event, IntervalService};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        IntervalService::ID
+    }
+
+    // modules are rendered again through `Request::IntervalElapsed`
+    fn serialize(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn is_for(&self, _register: &SubscriptionData) -> bool {
+        false
+    }
+}
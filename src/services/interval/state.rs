@@ -0,0 +1,31 @@
+use super::{Event, IntervalService};
+
+use crate::runtime::RuntimeModuleId;
+use crate::services::ServiceState;
+
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct IntervalState {
+    /// how many times each module's intervals came around since it
+    /// registered
+    pub elapsed: HashMap<RuntimeModuleId, u64>,
+}
+
+impl ServiceState<IntervalService> for IntervalState {
+    fn init() -> Self {
+        Self {
+            elapsed: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match event.clone() {
+            Event::Elapsed { module } => {
+                *self.elapsed.entry(module).or_default() += 1;
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -0,0 +1,39 @@
+//! used by the cli to talk to a running shell
+
+use super::protocol::{Query, Response};
+use super::socket_path;
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+/// how long to wait for the shell to reply
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// sends a query to the running shell and waits for its response
+pub fn query(query: Query) -> anyhow::Result<Response> {
+    let path = socket_path()?;
+
+    let mut stream = match UnixStream::connect(&path) {
+        Ok(stream) => stream,
+        Err(err) => {
+            return Err(anyhow!(
+                "could not connect to {:?}, is aurorashell running? ({err})",
+                path
+            ));
+        }
+    };
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut line = serde_json::to_string(&query)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+
+    return Ok(serde_json::from_str::<Response>(&reply)?);
+}
@@ -0,0 +1,24 @@
+use super::protocol::{Query, Response};
+
+/// messages emitted from the ipc service
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// a client asked for something only the app knows about, the app is
+    /// expected to send a `Response` back through `reply_tx`
+    Query {
+        query: Query,
+        reply_tx: flume::Sender<Response>,
+    },
+}
+
+/// requests for the ipc service
+#[derive(Debug, Clone)]
+pub enum Request {}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+/// modules can't subscribe to the ipc service so there's nothing to key
+/// events by
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum IpcEventType {}
@@ -0,0 +1,465 @@
+//! lets other programs (like the cli) talk to the running shell over a unix
+//! socket at `$XDG_RUNTIME_DIR/aurorashell.sock`
+//!
+//! see `protocol` for the messages sent over the socket
+//!
+//! the protocol can optionally be exposed over tcp, see `remote`
+
+pub mod client;
+mod data;
+pub mod protocol;
+mod remote;
+mod state;
+
+pub use data::Event;
+pub use state::IpcState;
+
+use data::{IpcEventType, Request};
+use protocol::{Auth, Query, Response};
+use remote::{RemoteAuth, RemoteListener};
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState,
+    restart_delay, supervise,
+};
+use crate::{config, metrics, startup};
+
+use std::any::TypeId;
+use std::env;
+use std::net::SocketAddr;
+use std::os::unix::net::UnixStream as StdUnixStream;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::{TcpStream, UnixListener};
+use tokio::sync::Semaphore;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 64;
+
+/// how long a client waits for the app to answer a query
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// clients sending a query longer than this are disconnected, nothing past
+/// it is read
+const MAX_QUERY_LEN: usize = 4096;
+
+/// how many remote clients can be connected without having authenticated
+/// yet, any more are disconnected straight away
+const MAX_UNAUTHENTICATED: usize = 16;
+
+/// remote clients must finish the tls handshake and send their token
+/// within this time
+const REMOTE_AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// how long to wait before rejecting a remote client that sent the wrong
+/// token, slows down guessing
+const REMOTE_AUTH_FAIL_DELAY: Duration = Duration::from_secs(1);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct IpcService;
+
+impl Service for IpcService {
+    type Event = Event;
+    type EventType = IpcEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = IpcState;
+    type SubscriptionData = ();
+
+    const ID: u16 = 7;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.ipc.events", chan);
+                let mut module_ids = ModuleIds::new();
+
+                loop {
+                    let mut state = IpcState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.ipc.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:ipc] could not send init event: {}", err);
+                        log::error!("[service:ipc] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:ipc] restarting");
+                        continue;
+                    }
+                    log::error!("[service:ipc] error: {err}");
+                    restart_delay::<Self>(Duration::from_secs(5)).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut IpcState,
+        _module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let listener = match Self::bind() {
+            Ok(listener) => listener,
+            Err(err) => return err,
+        };
+
+        // remote control is optional so the local socket still works when
+        // it's misconfigured
+        let remote = match RemoteListener::bind(&config::get().ipc.remote).await {
+            Ok(remote) => remote,
+            Err(err) => {
+                log::error!("[service:ipc] could not start remote listener: {err}");
+                None
+            }
+        };
+
+        // connections send their queries here so they can be passed on to
+        // the app
+        let (internal_event_tx, internal_event_rx) =
+            instrumented::bounded::<Event>("service.ipc.internal_events", CHANNEL_CAPACITY);
+
+        let unauthenticated = Arc::new(Semaphore::new(MAX_UNAUTHENTICATED));
+
+        loop {
+            tokio::select! {
+                connection = listener.accept() => {
+                    match connection {
+                        Ok((stream, _)) => {
+                            tokio::spawn(Self::handle_connection(stream, internal_event_tx.clone(), None));
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:ipc] error accepting connection: {err}");
+                        }
+                    }
+                }
+                connection = Self::accept_remote(&remote) => {
+                    match connection {
+                        Ok((stream, addr)) => {
+                            let Ok(permit) = unauthenticated.clone().try_acquire_owned() else {
+                                log::debug!(
+                                    "[service:ipc] [remote] too many unauthenticated clients, \
+                                     dropping {addr}"
+                                );
+                                metrics::increment("ipc.remote.rejected");
+                                continue;
+                            };
+
+                            if let Some(remote) = &remote {
+                                tokio::spawn(Self::handle_remote_connection(
+                                    stream,
+                                    addr,
+                                    remote.acceptor.clone(),
+                                    RemoteAuth {
+                                        token: remote.token.clone(),
+                                        permit,
+                                    },
+                                    internal_event_tx.clone(),
+                                ));
+                            }
+                        }
+                        Err(err) => {
+                            log::error!("[service:ipc] [remote] error accepting connection: {err}");
+                        }
+                    }
+                }
+                event = internal_event_rx.recv_async() => {
+                    match event {
+                        Ok(event) => {
+                            for event in state.update(event) {
+                                if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                                    log::error!(
+                                        "[service:ipc] error sending service event update: {err}"
+                                    );
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:ipc] error receiving message from connections: {err}");
+                        }
+                    }
+                }
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => match request {},
+                        Ok(ServiceRequest::SubscribeModule { .. }) => {
+                            log::warn!("[service:ipc] modules can't subscribe to the ipc service");
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { .. }) => {}
+                        Err(err) => {
+                            return anyhow!("[service:ipc] error receiving request: {err}");
+                        }
+                    }
+                }
+            };
+        }
+    }
+}
+
+impl IpcService {
+    /// binds to the socket, removing the socket file left by a shell that
+    /// didn't exit cleanly
+    fn bind() -> anyhow::Result<UnixListener> {
+        let path = socket_path()?;
+
+        if path.exists() {
+            // something answering means another shell is running
+            if StdUnixStream::connect(&path).is_ok() {
+                return Err(anyhow!(
+                    "[service:ipc] {:?} is in use, is another aurorashell running?",
+                    path
+                ));
+            }
+
+            std::fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        log::info!("[service:ipc] listening on {:?}", path);
+        startup::ready("ipc");
+
+        return Ok(listener);
+    }
+
+    /// never resolves when remote control is disabled
+    async fn accept_remote(
+        remote: &Option<RemoteListener>,
+    ) -> std::io::Result<(TcpStream, SocketAddr)> {
+        match remote {
+            Some(remote) => remote.accept().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// does the tls handshake then handles the client like a local one,
+    /// except it has to send the token first
+    async fn handle_remote_connection(
+        stream: TcpStream,
+        addr: SocketAddr,
+        acceptor: tokio_rustls::TlsAcceptor,
+        auth: RemoteAuth,
+        event_tx: InstrumentedSender<flume::Sender<Event>>,
+    ) {
+        let stream = match tokio::time::timeout(REMOTE_AUTH_TIMEOUT, acceptor.accept(stream)).await
+        {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(err)) => {
+                log::debug!("[service:ipc] [remote] tls handshake with {addr} failed: {err}");
+                return;
+            }
+            Err(_) => {
+                log::debug!("[service:ipc] [remote] tls handshake with {addr} timed out");
+                return;
+            }
+        };
+
+        log::info!("[service:ipc] [remote] connection from {addr}");
+
+        Self::handle_connection(stream, event_tx, Some(auth)).await;
+    }
+
+    /// reads queries line by line from a client until it disconnects
+    ///
+    /// when `auth` is set the first line must be an `Auth` with its token
+    async fn handle_connection<S>(
+        stream: S,
+        event_tx: InstrumentedSender<flume::Sender<Event>>,
+        auth: Option<RemoteAuth>,
+    ) where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+
+        if let Some(RemoteAuth { token, permit }) = auth {
+            let authenticated =
+                match tokio::time::timeout(REMOTE_AUTH_TIMEOUT, read_line(&mut reader)).await {
+                    Ok(Ok(Some(line))) => match serde_json::from_str::<Auth>(&line) {
+                        Ok(auth) => remote::token_matches(&token, &auth.token),
+                        Err(_) => false,
+                    },
+                    _ => false,
+                };
+
+            let response = if authenticated {
+                Response::Authenticated
+            } else {
+                metrics::increment("ipc.remote.auth_failures");
+                tokio::time::sleep(REMOTE_AUTH_FAIL_DELAY).await;
+
+                Response::Error {
+                    message: "authentication failed".to_string(),
+                }
+            };
+
+            if !Self::write_response(&mut writer, &response).await || !authenticated {
+                return;
+            }
+
+            // lets the next remote client in
+            drop(permit);
+        }
+
+        loop {
+            let line = match read_line(&mut reader).await {
+                Ok(Some(line)) => line,
+                Ok(None) => return,
+                Err(err) => {
+                    log::debug!("[service:ipc] error reading from client: {err}");
+
+                    if err.kind() == std::io::ErrorKind::InvalidData {
+                        let response = Response::Error {
+                            message: err.to_string(),
+                        };
+                        Self::write_response(&mut writer, &response).await;
+                    }
+                    return;
+                }
+            };
+
+            metrics::increment("ipc.queries");
+
+            let response = match serde_json::from_str::<Query>(&line) {
+                Ok(query) => Self::ask_app(query, &event_tx).await,
+                Err(err) => Response::Error {
+                    message: format!("invalid query: {err}"),
+                },
+            };
+
+            if !Self::write_response(&mut writer, &response).await {
+                return;
+            }
+        }
+    }
+
+    /// returns false if the client can't be written to anymore
+    async fn write_response<W>(writer: &mut W, response: &Response) -> bool
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut reply = match serde_json::to_string(response) {
+            Ok(reply) => reply,
+            Err(err) => {
+                log::error!("[service:ipc] could not serialize response: {err}");
+                return false;
+            }
+        };
+        reply.push('\n');
+
+        if let Err(err) = writer.write_all(reply.as_bytes()).await {
+            log::debug!("[service:ipc] error writing to client: {err}");
+            return false;
+        }
+
+        return true;
+    }
+
+    /// passes a query on to the app and waits for its answer
+    async fn ask_app(
+        query: Query,
+        event_tx: &InstrumentedSender<flume::Sender<Event>>,
+    ) -> Response {
+        let (reply_tx, reply_rx) = flume::bounded::<Response>(1);
+
+        if let Err(err) = event_tx.send_async(Event::Query { query, reply_tx }).await {
+            return Response::Error {
+                message: format!("could not send query to the shell: {err}"),
+            };
+        }
+
+        return match tokio::time::timeout(REPLY_TIMEOUT, reply_rx.recv_async()).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(err)) => Response::Error {
+                message: format!("shell dropped the query: {err}"),
+            },
+            Err(_) => Response::Error {
+                message: "shell took too long to reply".to_string(),
+            },
+        };
+    }
+}
+
+/// reads one line without its newline, `None` once the client disconnected
+///
+/// never reads more than `MAX_QUERY_LEN` bytes, a longer line is an
+/// `InvalidData` error so the client can be dropped
+async fn read_line<R>(reader: &mut R) -> std::io::Result<Option<String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = Vec::new();
+    let read = reader
+        .take(MAX_QUERY_LEN as u64 + 1)
+        .read_until(b'\n', &mut line)
+        .await?;
+
+    if read == 0 {
+        return Ok(None);
+    }
+
+    if line.last() == Some(&b'\n') {
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+    }
+
+    if line.len() > MAX_QUERY_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "query too long",
+        ));
+    }
+
+    return String::from_utf8(line)
+        .map(Some)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err));
+}
+
+/// `$XDG_RUNTIME_DIR/aurorashell.sock`
+pub fn socket_path() -> anyhow::Result<PathBuf> {
+    let runtime_dir = match env::var("XDG_RUNTIME_DIR") {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("no environment variable `XDG_RUNTIME_DIR` or it could not be interpreted");
+            return Err(e.into());
+        }
+    };
+
+    return Ok(PathBuf::from(runtime_dir).join("aurorashell.sock"));
+}
@@ -0,0 +1,562 @@
+//! the messages sent over the ipc socket
+//!
+//! each message is a single line of json, the client sends a `Query` and
+//! the shell replies with a `Response`
+
+use crate::compositor::{Capabilities, Protocol};
+use crate::config::BarWidget;
+use crate::runtime::wasm::{ModuleError, WasmUiNode};
+use crate::services::Service;
+use crate::services::appearance::AppearanceService;
+use crate::services::audio::{AudioService, AudioState};
+use crate::services::brightness::BrightnessService;
+use crate::services::clock::ClockService;
+use crate::services::custom::{CustomService, CustomState, SensorValue};
+use crate::services::dbus::DbusService;
+use crate::services::hotkeys::HotkeysService;
+use crate::services::inhibit::InhibitService;
+use crate::services::interval::IntervalService;
+use crate::services::ipc::IpcService;
+use crate::services::kdeconnect::KdeConnectService;
+use crate::services::network::NetworkService;
+use crate::services::screenshot::ScreenshotService;
+use crate::services::session::SessionService;
+use crate::services::sysinfo::SysinfoService;
+use crate::services::tray::TrayService;
+use crate::services::watch::WatchService;
+use crate::services::weather::WeatherService;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// what a client can ask the shell for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "query", rename_all = "snake_case")]
+pub enum Query {
+    /// the loaded modules
+    Modules,
+    /// whether each service is running
+    Services,
+    /// audio devices and their volumes
+    Audio,
+    /// counters from `crate::metrics`
+    Metrics,
+    /// the wayland protocols the compositor supports, see `crate::compositor`
+    Compositor,
+    /// everything the app knows, see `StateSnapshot`
+    DumpState,
+    /// opens a built-in widget's popup, or closes it if it's open
+    TogglePopup { widget: BarWidget },
+    /// opens the panel showing what each module is doing, or closes it if
+    /// it's open
+    ToggleInspector,
+    /// re-reads the theme and the bar's config
+    Reload,
+    /// tells the shell do not disturb was turned on or off outside of it, so
+    /// notifications from phones aren't forwarded while it's on
+    DoNotDisturb { enabled: bool },
+    /// stops a service and starts it again, leaving the other services and
+    /// modules alone
+    RestartService { service: ServiceName },
+    /// loads a module again from its file, or every module when `module` is
+    /// `None`
+    ReloadModules { module: Option<String> },
+    /// closes one of a module's surfaces, or opens it again if it's closed.
+    /// `surface` is the id the module gave it
+    ToggleSurface { module: String, surface: u32 },
+}
+
+/// the services that can be restarted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceName {
+    Appearance,
+    Audio,
+    Brightness,
+    Clock,
+    Custom,
+    Dbus,
+    Hotkeys,
+    Inhibit,
+    Interval,
+    Ipc,
+    #[serde(rename = "kdeconnect")]
+    KdeConnect,
+    Network,
+    Screenshot,
+    Session,
+    Sysinfo,
+    Tray,
+    Watch,
+    Weather,
+}
+
+/// the first message a remote client sends, before any `Query`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Auth {
+    pub token: String,
+}
+
+/// the shell's reply to a `Query`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Modules {
+        modules: Vec<ModuleInfo>,
+    },
+    Services {
+        services: Vec<ServiceInfo>,
+    },
+    Audio(AudioInfo),
+    Metrics {
+        metrics: BTreeMap<String, u64>,
+    },
+    Compositor(Capabilities),
+    State(StateSnapshot),
+    /// the query was an action and it was done
+    Done,
+    /// the remote client sent the right token
+    Authenticated,
+    /// the query couldn't be answered
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub id: u32,
+    pub name: String,
+    pub runtime: String,
+    pub file_path: String,
+    /// the amount of surfaces the module has rendered a ui to
+    pub surfaces: usize,
+    /// why the module was disabled, if it was
+    #[serde(default)]
+    pub disabled: Option<String>,
+    /// how many errors the module has had since the runtime started
+    #[serde(default)]
+    pub error_count: u64,
+    /// the module's last few errors, oldest first
+    #[serde(default)]
+    pub errors: Vec<ModuleErrorInfo>,
+}
+
+/// one of a module's errors, see `runtime::wasm::ModuleError`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleErrorInfo {
+    /// like `view_trap` or `bad_pointer`
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceInfo {
+    pub name: String,
+    /// true once the service has sent its init event
+    pub running: bool,
+    /// the version of the data the service gives modules
+    #[serde(default)]
+    pub version: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AudioInfo {
+    pub sinks: Vec<AudioDevice>,
+    pub sources: Vec<AudioDevice>,
+    pub default_sink: Option<String>,
+    pub default_source: Option<String>,
+    pub cards: Vec<AudioCard>,
+    /// profile descriptions of the default sink's card
+    pub sink_profiles: Vec<String>,
+    pub sink_default_profile: Option<String>,
+    /// profile descriptions of the default source's card
+    pub source_profiles: Vec<String>,
+    pub source_default_profile: Option<String>,
+    /// why there's no sound server, `None` when connected
+    pub unavailable: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioDevice {
+    pub name: String,
+    pub description: String,
+    /// the average volume of all channels from 0.0 - 100.0+
+    pub volume: f32,
+    pub mute: bool,
+    pub card_index: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioCard {
+    pub name: String,
+    pub index: u32,
+    pub profiles: Vec<AudioProfile>,
+    pub selected_profile: Option<AudioProfile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioProfile {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CustomInfo {
+    /// the last output of each sensor
+    pub values: BTreeMap<String, SensorValue>,
+    /// the last error of each sensor
+    pub errors: BTreeMap<String, String>,
+}
+
+/// a dump of the app's state, used for debugging
+///
+/// can be loaded back with `--load-state-fixture` to render modules with the
+/// same service data without the services running
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    /// bumped when the snapshot changes in a way older dumps can't be read
+    pub version: u32,
+    /// rfc3339 time of when the snapshot was taken
+    pub taken_at: String,
+    pub services: Vec<ServiceInfo>,
+    pub audio: AudioInfo,
+    pub custom: CustomInfo,
+    pub modules: Vec<ModuleInfo>,
+    pub surfaces: Vec<SurfaceInfo>,
+}
+
+impl StateSnapshot {
+    pub const VERSION: u32 = 1;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SurfaceInfo {
+    /// debug representation of the iced window id
+    pub surface_id: String,
+    pub module_id: u32,
+    /// info about the last ui tree rendered to this surface
+    pub tree: Option<TreeInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeInfo {
+    /// the root element's kind (row, column, text...)
+    pub root: String,
+    pub nodes: usize,
+    pub depth: usize,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// conversions
+
+impl ServiceName {
+    /// the service's `Service::ID`
+    pub fn id(self) -> u16 {
+        match self {
+            ServiceName::Appearance => AppearanceService::ID,
+            ServiceName::Audio => AudioService::ID,
+            ServiceName::Brightness => BrightnessService::ID,
+            ServiceName::Clock => ClockService::ID,
+            ServiceName::Custom => CustomService::ID,
+            ServiceName::Dbus => DbusService::ID,
+            ServiceName::Hotkeys => HotkeysService::ID,
+            ServiceName::Inhibit => InhibitService::ID,
+            ServiceName::Interval => IntervalService::ID,
+            ServiceName::Ipc => IpcService::ID,
+            ServiceName::KdeConnect => KdeConnectService::ID,
+            ServiceName::Network => NetworkService::ID,
+            ServiceName::Screenshot => ScreenshotService::ID,
+            ServiceName::Session => SessionService::ID,
+            ServiceName::Sysinfo => SysinfoService::ID,
+            ServiceName::Tray => TrayService::ID,
+            ServiceName::Watch => WatchService::ID,
+            ServiceName::Weather => WeatherService::ID,
+        }
+    }
+
+    /// the same names as `ServiceInfo::name`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ServiceName::Appearance => "appearance",
+            ServiceName::Audio => "audio",
+            ServiceName::Brightness => "brightness",
+            ServiceName::Clock => "clock",
+            ServiceName::Custom => "custom",
+            ServiceName::Dbus => "dbus",
+            ServiceName::Hotkeys => "hotkeys",
+            ServiceName::Inhibit => "inhibit",
+            ServiceName::Interval => "interval",
+            ServiceName::Ipc => "ipc",
+            ServiceName::KdeConnect => "kdeconnect",
+            ServiceName::Network => "network",
+            ServiceName::Screenshot => "screenshot",
+            ServiceName::Session => "session",
+            ServiceName::Sysinfo => "sysinfo",
+            ServiceName::Tray => "tray",
+            ServiceName::Watch => "watch",
+            ServiceName::Weather => "weather",
+        }
+    }
+}
+
+impl AudioInfo {
+    pub fn from_state(state: &AudioState) -> Self {
+        Self {
+            sinks: state
+                .sinks
+                .iter()
+                .map(|sink| AudioDevice {
+                    name: sink.name.clone(),
+                    description: sink.description.clone(),
+                    volume: AudioState::volume_percent(sink.volume),
+                    mute: sink.mute,
+                    card_index: sink.card_index,
+                })
+                .collect(),
+            sources: state
+                .sources
+                .iter()
+                .map(|source| AudioDevice {
+                    name: source.name.clone(),
+                    description: source.description.clone(),
+                    volume: AudioState::volume_percent(source.volume),
+                    mute: source.mute,
+                    card_index: source.card_index,
+                })
+                .collect(),
+            default_sink: state.default_sink.clone(),
+            default_source: state.default_source.clone(),
+            cards: state
+                .cards
+                .iter()
+                .map(|card| AudioCard {
+                    name: card.name.clone(),
+                    index: card.index,
+                    profiles: card
+                        .profiles
+                        .iter()
+                        .map(|profile| AudioProfile {
+                            name: profile.name.clone(),
+                            description: profile.description.clone(),
+                        })
+                        .collect(),
+                    selected_profile: card.selected_profile.as_ref().map(|profile| AudioProfile {
+                        name: profile.name.clone(),
+                        description: profile.description.clone(),
+                    }),
+                })
+                .collect(),
+            sink_profiles: state.sink_profiles.clone(),
+            sink_default_profile: state.sink_default_profile.clone(),
+            source_profiles: state.source_profiles.clone(),
+            source_default_profile: state.source_default_profile.clone(),
+            unavailable: state.unavailable.clone(),
+        }
+    }
+}
+
+impl CustomInfo {
+    pub fn from_state(state: &CustomState) -> Self {
+        Self {
+            values: state
+                .values
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            errors: state
+                .errors
+                .iter()
+                .map(|(name, error)| (name.clone(), error.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl ModuleErrorInfo {
+    pub fn from_error(error: &ModuleError) -> Self {
+        Self {
+            kind: error.kind().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl TreeInfo {
+    pub fn from_tree(tree: &WasmUiNode) -> Self {
+        let (nodes, depth) = Self::count(tree);
+
+        Self {
+            root: Self::kind(tree).to_string(),
+            nodes,
+            depth,
+        }
+    }
+
+    fn kind(node: &WasmUiNode) -> &'static str {
+        match node {
+            WasmUiNode::Row { .. } => "row",
+            WasmUiNode::Column { .. } => "column",
+            WasmUiNode::Text { .. } => "text",
+            WasmUiNode::Button { .. } => "button",
+            WasmUiNode::Slider { .. } => "slider",
+            WasmUiNode::Stack { .. } => "stack",
+            WasmUiNode::LongPress { .. } => "long_press",
+            WasmUiNode::Container { .. } => "container",
+            WasmUiNode::Image { .. } => "image",
+            WasmUiNode::ProgressBar { .. } => "progress_bar",
+            WasmUiNode::TextInput { .. } => "text_input",
+            WasmUiNode::Grid { .. } => "grid",
+            WasmUiNode::Tooltip { .. } => "tooltip",
+            WasmUiNode::MouseArea { .. } => "mouse_area",
+            WasmUiNode::TrayImage { .. } => "tray_image",
+            WasmUiNode::Toggler { .. } => "toggler",
+            WasmUiNode::Static { .. } => "static",
+        }
+    }
+
+    /// returns the amount of nodes and the depth of the tree
+    fn count(node: &WasmUiNode) -> (usize, usize) {
+        let children: Vec<&WasmUiNode> = match node {
+            WasmUiNode::Row { children, .. }
+            | WasmUiNode::Column { children, .. }
+            | WasmUiNode::Stack { children }
+            | WasmUiNode::Grid { children, .. } => children.iter().collect(),
+            WasmUiNode::Button { inner, .. }
+            | WasmUiNode::LongPress { inner, .. }
+            | WasmUiNode::Container { inner, .. }
+            | WasmUiNode::Tooltip { inner, .. }
+            | WasmUiNode::MouseArea { inner, .. } => vec![inner.as_ref()],
+            WasmUiNode::Static { child, .. } => vec![child.as_ref()],
+            WasmUiNode::Text { .. }
+            | WasmUiNode::Slider { .. }
+            | WasmUiNode::Image { .. }
+            | WasmUiNode::ProgressBar { .. }
+            | WasmUiNode::TextInput { .. }
+            | WasmUiNode::TrayImage { .. }
+            | WasmUiNode::Toggler { .. } => {
+                vec![]
+            }
+        };
+
+        return children.into_iter().map(Self::count).fold(
+            (1, 1),
+            |(nodes, depth), (child_nodes, child_depth)| {
+                (nodes + child_nodes, depth.max(child_depth + 1))
+            },
+        );
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// human readable output
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Response::Modules { modules } => {
+                if modules.is_empty() {
+                    return writeln!(f, "no modules loaded");
+                }
+
+                for module in modules {
+                    writeln!(
+                        f,
+                        "{:>3}  {} ({}) - {} surface(s)\n     {}",
+                        module.id, module.name, module.runtime, module.surfaces, module.file_path
+                    )?;
+
+                    if let Some(reason) = &module.disabled {
+                        writeln!(f, "     disabled: {reason}")?;
+                    }
+
+                    if let Some(error) = module.errors.last() {
+                        writeln!(
+                            f,
+                            "     {} error(s), last: [{}] {}",
+                            module.error_count, error.kind, error.message
+                        )?;
+                    }
+                }
+            }
+            Response::Services { services } => {
+                for service in services {
+                    let status = if service.running {
+                        "running"
+                    } else {
+                        "stopped"
+                    };
+                    writeln!(f, "{:<8} {:<8} v{}", service.name, status, service.version)?;
+                }
+            }
+            Response::Audio(audio) => {
+                if let Some(reason) = &audio.unavailable {
+                    return writeln!(f, "audio unavailable: {reason}");
+                }
+
+                let device = |f: &mut fmt::Formatter<'_>,
+                              device: &AudioDevice,
+                              default: &Option<String>|
+                 -> fmt::Result {
+                    let marker = if default.as_ref() == Some(&device.name) {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    let mute = if device.mute { " (muted)" } else { "" };
+                    writeln!(
+                        f,
+                        " {} {:>4}%{}  {}",
+                        marker, device.volume, mute, device.description
+                    )
+                };
+
+                writeln!(f, "sinks:")?;
+                for sink in &audio.sinks {
+                    device(f, sink, &audio.default_sink)?;
+                }
+                writeln!(f, "sources:")?;
+                for source in &audio.sources {
+                    device(f, source, &audio.default_source)?;
+                }
+            }
+            Response::Metrics { metrics } => {
+                for (name, value) in metrics {
+                    writeln!(f, "{name} = {value}")?;
+                }
+            }
+            Response::Compositor(capabilities) => {
+                if !capabilities.probed {
+                    return writeln!(f, "the compositor couldn't be probed");
+                }
+
+                for protocol in Protocol::ALL {
+                    match capabilities.version(protocol) {
+                        Some(version) => writeln!(f, "{:<36} v{version}", protocol.interface())?,
+                        None => writeln!(f, "{:<36} unsupported", protocol.interface())?,
+                    }
+                }
+            }
+            Response::State(snapshot) => {
+                writeln!(
+                    f,
+                    "snapshot taken at {} - {} module(s), {} surface(s)",
+                    snapshot.taken_at,
+                    snapshot.modules.len(),
+                    snapshot.surfaces.len()
+                )?;
+            }
+            Response::Done => {}
+            Response::Authenticated => {
+                writeln!(f, "authenticated")?;
+            }
+            Response::Error { message } => {
+                writeln!(f, "error: {message}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+//! exposes the ipc protocol over tcp + tls, see `RemoteIpcConfig`
+
+use crate::config::RemoteIpcConfig;
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::OwnedSemaphorePermit;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::ServerConfig;
+
+pub(super) struct RemoteListener {
+    listener: TcpListener,
+    pub acceptor: TlsAcceptor,
+    /// the token clients must send before they can query anything
+    pub token: Arc<str>,
+}
+
+/// what a remote client has to get through before it can send queries
+pub(super) struct RemoteAuth {
+    pub token: Arc<str>,
+    /// held until the client has authenticated, see `MAX_UNAUTHENTICATED`
+    pub permit: OwnedSemaphorePermit,
+}
+
+impl RemoteListener {
+    /// returns `None` when remote control is disabled
+    pub async fn bind(config: &RemoteIpcConfig) -> anyhow::Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        if config.token.is_empty() {
+            return Err(anyhow!(
+                "[service:ipc] [remote] `ipc.remote.token` must be set to enable remote control"
+            ));
+        }
+
+        let (cert, key) = match (&config.tls_cert, &config.tls_key) {
+            (Some(cert), Some(key)) => (cert, key),
+            _ => {
+                return Err(anyhow!(
+                    "[service:ipc] [remote] `ipc.remote.tls_cert` and `ipc.remote.tls_key` must \
+                     be set to enable remote control"
+                ));
+            }
+        };
+
+        let acceptor = TlsAcceptor::from(Arc::new(load_tls_config(cert, key)?));
+        let listener = TcpListener::bind(&config.address).await?;
+
+        log::info!("[service:ipc] [remote] listening on {}", config.address);
+
+        return Ok(Some(Self {
+            listener,
+            acceptor,
+            token: Arc::from(config.token.as_str()),
+        }));
+    }
+
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        self.listener.accept().await
+    }
+}
+
+fn load_tls_config(cert: &Path, key: &Path) -> anyhow::Result<ServerConfig> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert)?))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let key = match rustls_pemfile::private_key(&mut BufReader::new(File::open(key)?))? {
+        Some(key) => key,
+        None => {
+            return Err(anyhow!(
+                "[service:ipc] [remote] no private key in {:?}",
+                key
+            ));
+        }
+    };
+
+    return Ok(ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?);
+}
+
+/// compares tokens in constant time so the token can't be guessed by timing
+/// how long it takes to be rejected
+pub(super) fn token_matches(expected: &str, given: &str) -> bool {
+    let (expected, given) = (expected.as_bytes(), given.as_bytes());
+
+    if expected.len() != given.len() {
+        return false;
+    }
+
+    return expected
+        .iter()
+        .zip(given)
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0;
+}
@@ -0,0 +1,25 @@
+use super::{Event, IpcService};
+
+use crate::services::ServiceState;
+
+#[derive(Debug)]
+pub struct IpcState {
+    /// the amount of queries received since the service started
+    pub queries: u64,
+}
+
+impl ServiceState<IpcService> for IpcState {
+    fn init() -> Self {
+        Self { queries: 0 }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match &event {
+            Event::Query { .. } => {
+                self.queries += 1;
+            }
+        }
+
+        return vec![event];
+    }
+}
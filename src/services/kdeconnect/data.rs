@@ -0,0 +1,167 @@
+/// messages emitted from the kde connect service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when a device connects, disconnects or its battery or
+    /// media changes, with every paired device sorted by name
+    DevicesChanged { devices: Vec<Device> },
+    /// event emitted when a device posts a notification, or updates one
+    NotificationPosted {
+        device: String,
+        notification: PhoneNotification,
+    },
+    /// event emitted when a notification is dismissed on the device
+    NotificationRemoved { device: String, id: String },
+
+    /// event emitted when kde connect isn't running
+    ServiceUnavailable { reason: String },
+    /// event emitted when the service connected to kde connect
+    ServiceAvailable,
+}
+
+/// requests for the kde connect service
+///
+/// `device` is the device's id (see `Device.id`), `None` for the first
+/// connected one
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// makes the device ring, to find it
+    Ring { device: Option<String> },
+    /// sends a ping notification to the device
+    Ping { device: Option<String> },
+    /// controls what's playing on the device
+    Media {
+        device: Option<String>,
+        action: MediaAction,
+    },
+    /// notifications aren't forwarded to the desktop while do not disturb
+    /// is on, see `forward_notifications` under `[services.kdeconnect]`
+    SetDoNotDisturb { enabled: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaAction {
+    PlayPause,
+    Next,
+    Previous,
+}
+
+impl MediaAction {
+    /// the action's name in kde connect's `sendAction`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PlayPause => "PlayPause",
+            Self::Next => "Next",
+            Self::Previous => "Previous",
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum KdeConnectEventType {
+    DevicesChanged,
+    Notifications,
+}
+
+impl Event {
+    /// the kind of event modules register for, `None` for the events about
+    /// the service itself
+    pub fn event_type(&self) -> Option<KdeConnectEventType> {
+        match self {
+            Self::DevicesChanged { .. } => Some(KdeConnectEventType::DevicesChanged),
+            Self::NotificationPosted { .. } | Self::NotificationRemoved { .. } => {
+                Some(KdeConnectEventType::Notifications)
+            }
+            Self::ServiceUnavailable { .. } | Self::ServiceAvailable => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdeConnectSubscriptionData(pub u8);
+
+impl KdeConnectSubscriptionData {
+    /// subscribes to devices connecting or disconnecting, and their battery
+    /// and media changing
+    pub const DEVICES_CHANGED: Self = Self(0b_0000_0001);
+    /// subscribes to notifications being posted or dismissed on a device
+    pub const NOTIFICATIONS: Self = Self(0b_0000_0010);
+
+    pub fn is_set(&self, case: KdeConnectSubscriptionData) -> bool {
+        return *self & case != KdeConnectSubscriptionData(0);
+    }
+}
+
+impl BitOr for KdeConnectSubscriptionData {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for KdeConnectSubscriptionData {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for KdeConnectSubscriptionData {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// types used for events
+
+/// a paired device, like a phone
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Device {
+    /// kde connect's id for the device
+    pub id: String,
+    pub name: String,
+    /// like `phone`, `tablet` or `desktop`
+    pub kind: String,
+    /// whether the device is connected right now
+    pub reachable: bool,
+    /// `None` when the device doesn't share its battery or isn't connected
+    pub battery: Option<DeviceBattery>,
+    /// `None` when nothing is playing or the device doesn't share it
+    pub media: Option<DeviceMedia>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeviceBattery {
+    /// 0 - 100
+    pub charge: u8,
+    pub charging: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DeviceMedia {
+    /// the app that's playing, like `Spotify`
+    pub player: String,
+    pub title: String,
+    pub artist: String,
+    pub playing: bool,
+}
+
+/// a notification shown on a device
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhoneNotification {
+    /// kde connect's id for the notification, unique on its device
+    pub id: String,
+    pub app_name: String,
+    pub title: String,
+    pub text: String,
+}
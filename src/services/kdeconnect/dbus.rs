@@ -0,0 +1,125 @@
+//! the parts of kde connect's dbus api the service uses
+//!
+//! everything is under `/modules/kdeconnect`, each device at
+//! `devices/<id>` with its plugins below it like `devices/<id>/battery`.
+//! kde connect names its methods and properties in camel case
+
+use zbus::proxy;
+
+/// where every object of kde connect is under
+pub const ROOT: &str = "/modules/kdeconnect";
+
+#[proxy(
+    interface = "org.kde.kdeconnect.daemon",
+    default_service = "org.kde.kdeconnect",
+    default_path = "/modules/kdeconnect"
+)]
+pub trait Daemon {
+    /// the ids of the known devices
+    #[zbus(name = "devices")]
+    fn devices(&self, only_reachable: bool, only_paired: bool) -> zbus::Result<Vec<String>>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device",
+    default_service = "org.kde.kdeconnect"
+)]
+pub trait Device {
+    #[zbus(property, name = "name")]
+    fn name(&self) -> zbus::Result<String>;
+    /// like `phone`, `tablet` or `desktop`
+    #[zbus(property, name = "type")]
+    fn kind(&self) -> zbus::Result<String>;
+    #[zbus(property, name = "isReachable")]
+    fn is_reachable(&self) -> zbus::Result<bool>;
+    /// the plugins the device has turned on, like `kdeconnect_battery`
+    #[zbus(name = "loadedPlugins")]
+    fn loaded_plugins(&self) -> zbus::Result<Vec<String>>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.battery",
+    default_service = "org.kde.kdeconnect"
+)]
+pub trait Battery {
+    /// 0 - 100, -1 before the device first sent it
+    #[zbus(property, name = "charge")]
+    fn charge(&self) -> zbus::Result<i32>;
+    #[zbus(property, name = "isCharging")]
+    fn is_charging(&self) -> zbus::Result<bool>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.mprisremote",
+    default_service = "org.kde.kdeconnect"
+)]
+pub trait MprisRemote {
+    /// `Play`, `Pause`, `PlayPause`, `Next`, `Previous` or `Stop`
+    #[zbus(name = "sendAction")]
+    fn send_action(&self, action: &str) -> zbus::Result<()>;
+
+    /// the player the other properties are about, empty if there's none
+    #[zbus(property, name = "player")]
+    fn player(&self) -> zbus::Result<String>;
+    #[zbus(property, name = "title")]
+    fn title(&self) -> zbus::Result<String>;
+    #[zbus(property, name = "artist")]
+    fn artist(&self) -> zbus::Result<String>;
+    #[zbus(property, name = "isPlaying")]
+    fn is_playing(&self) -> zbus::Result<bool>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.findmyphone",
+    default_service = "org.kde.kdeconnect"
+)]
+pub trait FindMyPhone {
+    #[zbus(name = "ring")]
+    fn ring(&self) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.ping",
+    default_service = "org.kde.kdeconnect"
+)]
+pub trait Ping {
+    #[zbus(name = "sendPing")]
+    fn send_ping(&self) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.notifications",
+    default_service = "org.kde.kdeconnect"
+)]
+pub trait Notifications {
+    /// the ids of the notifications shown on the device
+    #[zbus(name = "activeNotifications")]
+    fn active_notifications(&self) -> zbus::Result<Vec<String>>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.notifications.notification",
+    default_service = "org.kde.kdeconnect"
+)]
+pub trait Notification {
+    #[zbus(property, name = "appName")]
+    fn app_name(&self) -> zbus::Result<String>;
+    #[zbus(property, name = "title")]
+    fn title(&self) -> zbus::Result<String>;
+    #[zbus(property, name = "text")]
+    fn text(&self) -> zbus::Result<String>;
+}
+
+/// the path of a device, or one of its plugins when `plugin` isn't empty
+pub fn device_path(id: &str, plugin: &str) -> String {
+    match plugin {
+        "" => format!("{ROOT}/devices/{id}"),
+        plugin => format!("{ROOT}/devices/{id}/{plugin}"),
+    }
+}
+
+/// the id of the device a path is under, `None` for paths that aren't
+pub fn device_id(path: &str) -> Option<&str> {
+    let rest = path.strip_prefix(ROOT)?.strip_prefix("/devices/")?;
+    rest.split('/').next().filter(|id| !id.is_empty())
+}
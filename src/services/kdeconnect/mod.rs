@@ -0,0 +1,567 @@
+//! devices paired with kde connect, like phones, over dbus
+//!
+//! modules get each device's battery, whether it's connected and what's
+//! playing on it, and the notifications shown on it. notifications can also
+//! be forwarded to the desktop with `forward_notifications` under
+//! `[services.kdeconnect]`, except while do not disturb is on
+//!
+//! kde connect emits its own signals for most changes rather than
+//! `PropertiesChanged`, so any of them has the devices read again. they're
+//! also read on a timer in case a signal was missed
+
+mod data;
+mod dbus;
+mod se;
+mod state;
+
+pub use data::{
+    Device, DeviceBattery, DeviceMedia, Event, KdeConnectSubscriptionData, MediaAction,
+    PhoneNotification, Request,
+};
+pub use state::KdeConnectState;
+
+use data::KdeConnectEventType;
+use dbus::{
+    BatteryProxy, DaemonProxy, DeviceProxy, FindMyPhoneProxy, MprisRemoteProxy, NotificationProxy,
+    NotificationsProxy, PingProxy, device_id, device_path,
+};
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::{config, notify, startup};
+
+use std::any::TypeId;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::time::{Instant, MissedTickBehavior};
+use zbus::proxy::CacheProperties;
+use zbus::{MatchRule, Message, MessageStream};
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// how often the devices are read when there are no signals
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// a device connecting sends a signal for each of its plugins, so the
+/// devices are read once the signals stop for this long
+const REFRESH_DELAY: Duration = Duration::from_millis(250);
+
+/// the first wait before trying to reach kde connect again after it
+/// couldn't be found, doubles each failed attempt
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(5);
+/// the longest wait between attempts to reach kde connect
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct KdeConnectService;
+
+/// kept across restarts of the service
+#[derive(Debug, Default)]
+pub struct KdeConnectData {
+    /// set by `Request::SetDoNotDisturb`
+    dnd: bool,
+}
+
+/// returned from `KdeConnectService::run` when kde connect couldn't be
+/// reached, so the service waits longer before trying again
+#[derive(Debug)]
+struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "kde connect unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+impl Service for KdeConnectService {
+    type Event = Event;
+    type EventType = KdeConnectEventType;
+    type Request = Request;
+    type RuntimeData = KdeConnectData;
+    type State = KdeConnectState;
+    type SubscriptionData = KdeConnectSubscriptionData;
+
+    const ID: u16 = 13;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.kdeconnect.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut data = KdeConnectData::default();
+                let mut backoff = RETRY_BACKOFF_MIN;
+
+                loop {
+                    let mut state = KdeConnectState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.kdeconnect.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:kdeconnect] could not send init event: {}", err);
+                        log::error!("[service:kdeconnect] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut data,
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:kdeconnect] restarting");
+                        backoff = RETRY_BACKOFF_MIN;
+                        continue;
+                    }
+
+                    let (reason, delay) = match err.downcast_ref::<Unavailable>() {
+                        Some(Unavailable(reason)) => {
+                            log::warn!(
+                                "[service:kdeconnect] {err}, retrying in {}",
+                                humantime::format_duration(backoff)
+                            );
+
+                            let delay = backoff;
+                            backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                            (reason.clone(), delay)
+                        }
+                        None => {
+                            log::error!("[service:kdeconnect] error: {err}");
+                            log::error!("[service:kdeconnect] restarting in 5 seconds...");
+
+                            backoff = RETRY_BACKOFF_MIN;
+                            (err.to_string(), Duration::from_secs(5))
+                        }
+                    };
+
+                    emit(
+                        &mut state,
+                        &mut chan,
+                        vec![Event::ServiceUnavailable { reason }],
+                    )
+                    .await;
+
+                    restart_delay::<Self>(delay).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut KdeConnectState,
+        module_ids: &mut ModuleIds<Self>,
+        data: &mut KdeConnectData,
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let conn = match zbus::Connection::session().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                return Unavailable(format!("could not connect to the session bus: {err}")).into();
+            }
+        };
+        let daemon = match DaemonProxy::new(&conn).await {
+            Ok(daemon) => daemon,
+            Err(err) => return Unavailable(format!("could not create proxy: {err}")).into(),
+        };
+
+        // the first call fails when kde connect isn't running
+        let devices = match read_devices(&conn, &daemon).await {
+            Ok(devices) => devices,
+            Err(err) => return Unavailable(err.to_string()).into(),
+        };
+
+        let rule = match MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .path_namespace(dbus::ROOT)
+        {
+            Ok(rule) => rule.build(),
+            Err(err) => return anyhow!("[service:kdeconnect] could not build match rule: {err}"),
+        };
+        let mut signals = match MessageStream::for_match_rule(rule, &conn, None).await {
+            Ok(signals) => signals,
+            Err(err) => {
+                return anyhow!("[service:kdeconnect] could not listen for signals: {err}");
+            }
+        };
+
+        log::info!("[service:kdeconnect] service started");
+        startup::ready("kdeconnect");
+
+        // the notifications already on the devices are only shown to
+        // modules, they were forwarded when they came in if at all
+        let reachable: Vec<String> = devices
+            .iter()
+            .filter(|device| device.reachable)
+            .map(|device| device.id.clone())
+            .collect();
+
+        let mut events = vec![Event::ServiceAvailable, Event::DevicesChanged { devices }];
+        for device in reachable {
+            events.extend(read_notifications(&conn, &device).await);
+        }
+        emit(state, chan, events).await;
+
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // the first tick is right away and the devices were just read
+        ticker.tick().await;
+
+        let mut refresh_at: Option<Instant> = None;
+
+        loop {
+            let refresh = tokio::select! {
+                _ = ticker.tick() => true,
+                _ = tokio::time::sleep_until(refresh_at.unwrap_or_else(Instant::now)), if refresh_at.is_some() => {
+                    refresh_at = None;
+                    true
+                }
+                signal = signals.next() => {
+                    let signal = match signal {
+                        Some(Ok(signal)) => signal,
+                        Some(Err(err)) => {
+                            log::warn!("[service:kdeconnect] could not read a signal: {err}");
+                            continue;
+                        }
+                        None => return anyhow!("[service:kdeconnect] signal stream ended"),
+                    };
+
+                    match Self::notification_signal(&conn, state, data, &signal).await {
+                        Some(events) => emit(state, chan, events).await,
+                        None => {
+                            refresh_at = Some(Instant::now() + REFRESH_DELAY);
+                        }
+                    }
+                    false
+                }
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => {
+                            let result = Self::request(&conn, state, data, request.clone()).await;
+                            if let Err(err) = result {
+                                log::warn!(
+                                    "[service:kdeconnect] could not handle {request:?}: {err}"
+                                );
+                            }
+                        }
+                        Ok(ServiceRequest::SubscribeModule { id, data }) => {
+                            let mut events = vec![];
+
+                            if data.is_set(KdeConnectSubscriptionData::DEVICES_CHANGED) {
+                                events.push(KdeConnectEventType::DevicesChanged);
+                            }
+                            if data.is_set(KdeConnectSubscriptionData::NOTIFICATIONS) {
+                                events.push(KdeConnectEventType::Notifications);
+                            }
+
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:kdeconnect] {id:?} registered, {diff}");
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            module_ids.unregister_module(id);
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:kdeconnect] error receiving request: {err}");
+                        }
+                    }
+                    false
+                }
+            };
+
+            if !refresh {
+                continue;
+            }
+
+            // failing to read the devices means kde connect went away
+            match read_devices(&conn, &daemon).await {
+                Ok(devices) => {
+                    emit(state, chan, vec![Event::DevicesChanged { devices }]).await;
+                }
+                Err(err) => return Unavailable(err.to_string()).into(),
+            }
+        }
+    }
+}
+
+impl KdeConnectService {
+    /// the events for a signal from a device's notifications plugin,
+    /// `None` for any other signal, after which the devices are read again
+    async fn notification_signal(
+        conn: &zbus::Connection,
+        state: &KdeConnectState,
+        data: &KdeConnectData,
+        signal: &Message,
+    ) -> Option<Vec<Event>> {
+        let header = signal.header();
+        let member = header.member()?.as_str();
+        let device = device_id(header.path()?.as_str())?.to_string();
+
+        match member {
+            "notificationPosted" | "notificationUpdated" => {
+                let id: String = signal.body().deserialize().ok()?;
+                let notification = match read_notification(conn, &device, id).await {
+                    Ok(notification) => notification,
+                    Err(err) => {
+                        log::warn!("[service:kdeconnect] could not read a notification: {err}");
+                        return Some(vec![]);
+                    }
+                };
+
+                if member == "notificationPosted" {
+                    Self::forward(state, data, &device, &notification);
+                }
+
+                Some(vec![Event::NotificationPosted {
+                    device,
+                    notification,
+                }])
+            }
+            "notificationRemoved" => {
+                let id: String = signal.body().deserialize().ok()?;
+                Some(vec![Event::NotificationRemoved { device, id }])
+            }
+            "allNotificationsRemoved" => Some(
+                state
+                    .notifications
+                    .iter()
+                    .filter(|(owner, _)| *owner == device)
+                    .map(|(_, notification)| Event::NotificationRemoved {
+                        device: device.clone(),
+                        id: notification.id.clone(),
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// shows a notification from a device on the desktop, if the config
+    /// asks for it and do not disturb is off
+    fn forward(
+        state: &KdeConnectState,
+        data: &KdeConnectData,
+        device: &str,
+        notification: &PhoneNotification,
+    ) {
+        if !config::get().services.kdeconnect.forward_notifications || data.dnd {
+            return;
+        }
+
+        let device_name = state
+            .devices
+            .iter()
+            .find(|known| known.id == device)
+            .map_or(device, |known| known.name.as_str());
+
+        notify::send(
+            &format!("{} on {device_name}", notification.app_name),
+            &format!("{}\n{}", notification.title, notification.text),
+        );
+    }
+
+    async fn request(
+        conn: &zbus::Connection,
+        state: &KdeConnectState,
+        data: &mut KdeConnectData,
+        request: Request,
+    ) -> anyhow::Result<()> {
+        match request {
+            Request::Ring { device } => {
+                let path = device_path(&Self::target(state, device)?, "findmyphone");
+                let proxy = FindMyPhoneProxy::builder(conn).path(path)?.build().await?;
+                proxy.ring().await?;
+            }
+            Request::Ping { device } => {
+                let path = device_path(&Self::target(state, device)?, "ping");
+                let proxy = PingProxy::builder(conn).path(path)?.build().await?;
+                proxy.send_ping().await?;
+            }
+            Request::Media { device, action } => {
+                let path = device_path(&Self::target(state, device)?, "mprisremote");
+                let proxy = MprisRemoteProxy::builder(conn).path(path)?.build().await?;
+                proxy.send_action(action.as_str()).await?;
+            }
+            Request::SetDoNotDisturb { enabled } => {
+                data.dnd = enabled;
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// the id of the device a request is for, the first connected one if
+    /// it didn't say
+    fn target(state: &KdeConnectState, device: Option<String>) -> anyhow::Result<String> {
+        let found = match &device {
+            Some(id) => state.devices.iter().find(|known| known.id == *id),
+            None => state.devices.iter().find(|known| known.reachable),
+        };
+
+        match found {
+            Some(found) if found.reachable => Ok(found.id.clone()),
+            Some(found) => Err(anyhow!("{} is not connected", found.name)),
+            None => Err(anyhow!("there is no connected device called {device:?}")),
+        }
+    }
+}
+
+/// every paired device, sorted by name
+async fn read_devices(
+    conn: &zbus::Connection,
+    daemon: &DaemonProxy<'_>,
+) -> zbus::Result<Vec<Device>> {
+    let mut devices = vec![];
+    for id in daemon.devices(false, true).await? {
+        devices.push(read_device(conn, id).await?);
+    }
+
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    return Ok(devices);
+}
+
+async fn read_device(conn: &zbus::Connection, id: String) -> zbus::Result<Device> {
+    // kde connect doesn't always emit `PropertiesChanged`, so nothing is
+    // cached
+    let device = DeviceProxy::builder(conn)
+        .path(device_path(&id, ""))?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let reachable = device.is_reachable().await?;
+    // plugins are only loaded while the device is connected
+    let plugins = match reachable {
+        true => device.loaded_plugins().await.unwrap_or_default(),
+        false => vec![],
+    };
+    let has = |plugin: &str| plugins.iter().any(|loaded| loaded == plugin);
+
+    // a plugin can be unloaded while it's being read, the device is then
+    // read again on its signal
+    let battery = match has("kdeconnect_battery") {
+        true => read_battery(conn, &id).await.ok().flatten(),
+        false => None,
+    };
+    let media = match has("kdeconnect_mprisremote") {
+        true => read_media(conn, &id).await.ok().flatten(),
+        false => None,
+    };
+
+    return Ok(Device {
+        name: device.name().await?,
+        kind: device.kind().await?,
+        id,
+        reachable,
+        battery,
+        media,
+    });
+}
+
+async fn read_battery(conn: &zbus::Connection, id: &str) -> zbus::Result<Option<DeviceBattery>> {
+    let battery = BatteryProxy::builder(conn)
+        .path(device_path(id, "battery"))?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    // -1 until the device first sends it
+    let charge = battery.charge().await?;
+    if charge < 0 {
+        return Ok(None);
+    }
+
+    return Ok(Some(DeviceBattery {
+        charge: charge.min(100) as u8,
+        charging: battery.is_charging().await?,
+    }));
+}
+
+async fn read_media(conn: &zbus::Connection, id: &str) -> zbus::Result<Option<DeviceMedia>> {
+    let remote = MprisRemoteProxy::builder(conn)
+        .path(device_path(id, "mprisremote"))?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let player = remote.player().await?;
+    if player.is_empty() {
+        return Ok(None);
+    }
+
+    return Ok(Some(DeviceMedia {
+        player,
+        title: remote.title().await?,
+        artist: remote.artist().await?,
+        playing: remote.is_playing().await?,
+    }));
+}
+
+/// the notifications shown on a device, as events
+async fn read_notifications(conn: &zbus::Connection, device: &str) -> Vec<Event> {
+    let ids = match NotificationsProxy::builder(conn).path(device_path(device, "notifications")) {
+        Ok(builder) => match builder.build().await {
+            Ok(proxy) => proxy.active_notifications().await.unwrap_or_default(),
+            Err(_) => vec![],
+        },
+        Err(_) => vec![],
+    };
+
+    let mut events = vec![];
+    for id in ids {
+        match read_notification(conn, device, id).await {
+            Ok(notification) => events.push(Event::NotificationPosted {
+                device: device.to_string(),
+                notification,
+            }),
+            Err(err) => log::warn!("[service:kdeconnect] could not read a notification: {err}"),
+        }
+    }
+
+    return events;
+}
+
+async fn read_notification(
+    conn: &zbus::Connection,
+    device: &str,
+    id: String,
+) -> zbus::Result<PhoneNotification> {
+    let path = format!("{}/{id}", device_path(device, "notifications"));
+    let notification = NotificationProxy::builder(conn)
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    return Ok(PhoneNotification {
+        app_name: notification.app_name().await?,
+        title: notification.title().await?,
+        text: notification.text().await?,
+        id,
+    });
+}
@@ -0,0 +1,127 @@
+use super::data::{KdeConnectEventType, KdeConnectSubscriptionData};
+use super::{Event, KdeConnectService, KdeConnectState, MediaAction, Request};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        KdeConnectService::ID
+    }
+
+    // the whole state is given to modules through
+    // `Request::KdeConnectChanged`
+    fn serialize(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn is_for(&self, _register: &SubscriptionData) -> bool {
+        false
+    }
+}
+
+impl Event {
+    /// the register bit a module needs to hear about this event, `None` for
+    /// the events about the service itself which every module hears about
+    pub fn subscription(&self) -> Option<KdeConnectSubscriptionData> {
+        match self.event_type()? {
+            KdeConnectEventType::DevicesChanged => {
+                Some(KdeConnectSubscriptionData::DEVICES_CHANGED)
+            }
+            KdeConnectEventType::Notifications => Some(KdeConnectSubscriptionData::NOTIFICATIONS),
+        }
+    }
+}
+
+impl Request {
+    /// the request for an action a module asked for through
+    /// `kdeconnect_request`, `None` for an unknown action
+    ///
+    /// 0 rings the device, 1 pings it, 2 plays or pauses what's playing on
+    /// it, 3 skips to the next song and 4 to the previous one
+    pub fn from_module(action: u32, device: Option<String>) -> Option<Self> {
+        let action = match action {
+            0 => return Some(Self::Ring { device }),
+            1 => return Some(Self::Ping { device }),
+            2 => MediaAction::PlayPause,
+            3 => MediaAction::Next,
+            4 => MediaAction::Previous,
+            _ => return None,
+        };
+
+        return Some(Self::Media { device, action });
+    }
+}
+
+impl KdeConnectState {
+    /// the devices and notifications in the layout modules read them in,
+    /// little endian
+    ///
+    /// strings are a u16 length followed by that many bytes of utf-8
+    ///
+    /// - u16 amount of devices, then u16 amount of notifications
+    /// - each device is its id, name and kind, a u8 of flags (bit 0 being
+    ///   connected, bit 1 having a battery, bit 2 charging, bit 3 having
+    ///   media and bit 4 playing) and the u8 battery charge. devices with
+    ///   media then have the player, title and artist
+    /// - each notification is the u16 index of its device, then its id, app
+    ///   name, title and text
+    pub fn serialize(&self) -> Vec<u8> {
+        let devices = &self.devices[..count(self.devices.len()) as usize];
+        let notifications = &self.notifications[..count(self.notifications.len()) as usize];
+
+        let mut bytes = vec![];
+        bytes.extend((devices.len() as u16).to_le_bytes());
+        bytes.extend((notifications.len() as u16).to_le_bytes());
+
+        for device in devices {
+            string(&mut bytes, &device.id);
+            string(&mut bytes, &device.name);
+            string(&mut bytes, &device.kind);
+
+            let mut flags = device.reachable as u8;
+            if let Some(battery) = &device.battery {
+                flags |= 1 << 1;
+                flags |= (battery.charging as u8) << 2;
+            }
+            if let Some(media) = &device.media {
+                flags |= 1 << 3;
+                flags |= (media.playing as u8) << 4;
+            }
+            bytes.push(flags);
+            bytes.push(device.battery.map_or(0, |battery| battery.charge));
+
+            if let Some(media) = &device.media {
+                string(&mut bytes, &media.player);
+                string(&mut bytes, &media.title);
+                string(&mut bytes, &media.artist);
+            }
+        }
+
+        for (device, notification) in notifications {
+            let index = devices
+                .iter()
+                .position(|known| known.id == *device)
+                .map_or(u16::MAX, count);
+            bytes.extend(index.to_le_bytes());
+
+            string(&mut bytes, &notification.id);
+            string(&mut bytes, &notification.app_name);
+            string(&mut bytes, &notification.title);
+            string(&mut bytes, &notification.text);
+        }
+
+        return bytes;
+    }
+}
+
+/// `len` as a u16, there won't be more devices or notifications than that
+fn count(len: usize) -> u16 {
+    len.min(u16::MAX as usize - 1) as u16
+}
+
+fn string(bytes: &mut Vec<u8>, string: &str) {
+    let string = &string.as_bytes()[..string.len().min(u16::MAX as usize)];
+    bytes.extend((string.len() as u16).to_le_bytes());
+    bytes.extend(string);
+}
@@ -0,0 +1,84 @@
+use super::data::{Device, KdeConnectEventType, PhoneNotification};
+use super::{Event, KdeConnectService};
+
+use crate::services::{Dedup, ServiceState};
+
+/// how many notifications are kept across all devices, the oldest are
+/// dropped first
+const MAX_NOTIFICATIONS: usize = 64;
+
+#[derive(Debug)]
+pub struct KdeConnectState {
+    /// every paired device, sorted by name
+    pub devices: Vec<Device>,
+    /// the notifications shown on the devices with the id of their device,
+    /// oldest first
+    pub notifications: Vec<(String, PhoneNotification)>,
+
+    /// why kde connect can't be reached, `None` when it can
+    pub unavailable: Option<String>,
+
+    /// the devices are read again on most signals from kde connect, which
+    /// usually doesn't change anything
+    dedup: Dedup<KdeConnectEventType>,
+}
+
+impl ServiceState<KdeConnectService> for KdeConnectState {
+    fn init() -> Self {
+        Self {
+            devices: vec![],
+            notifications: vec![],
+            unavailable: None,
+            dedup: Dedup::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match event.clone() {
+            Event::DevicesChanged { devices } => {
+                if !self
+                    .dedup
+                    .is_new(KdeConnectEventType::DevicesChanged, &devices)
+                {
+                    return vec![];
+                }
+
+                // a device that was unpaired takes its notifications with it
+                self.notifications
+                    .retain(|(id, _)| devices.iter().any(|device| device.id == *id));
+                self.devices = devices;
+            }
+            Event::NotificationPosted {
+                device,
+                notification,
+            } => {
+                self.notifications
+                    .retain(|(id, old)| !(*id == device && old.id == notification.id));
+                self.notifications.push((device, notification));
+
+                if self.notifications.len() > MAX_NOTIFICATIONS {
+                    self.notifications.remove(0);
+                }
+            }
+            Event::NotificationRemoved { device, id } => {
+                let len = self.notifications.len();
+                self.notifications.retain(|(device_id, notification)| {
+                    !(*device_id == device && notification.id == id)
+                });
+
+                if self.notifications.len() == len {
+                    return vec![];
+                }
+            }
+            Event::ServiceUnavailable { reason } => {
+                *self = Self::init();
+                self.unavailable = Some(reason);
+            }
+            Event::ServiceAvailable => {
+                self.unavailable = None;
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -4,18 +4,48 @@
 //! all services store their state internally but gives the main thread a
 //! struct to interact with the service
 
+pub mod appearance;
 pub mod audio;
-//pub mod interval;
+pub mod brightness;
+pub mod clock;
+pub mod custom;
+pub mod dbus;
+pub mod hotkeys;
+pub mod inhibit;
+pub mod interval;
+pub mod ipc;
+pub mod kdeconnect;
+pub mod network;
+pub mod screenshot;
+pub mod session;
+pub mod sysinfo;
+pub mod tray;
+pub mod watch;
+pub mod weather;
 
+use crate::instrumented::InstrumentedSender;
 use crate::runtime::RuntimeModuleId;
 use crate::services::audio::AudioSubscriptionData;
+use crate::services::brightness::BrightnessSubscriptionData;
+use crate::services::clock::ClockSubscriptionData;
+use crate::services::custom::CustomSubscriptionData;
+use crate::services::dbus::DbusSubscriptionData;
+use crate::services::hotkeys::HotkeysSubscriptionData;
+use crate::services::inhibit::InhibitSubscriptionData;
+use crate::services::kdeconnect::KdeConnectSubscriptionData;
+use crate::services::network::NetworkSubscriptionData;
+use crate::services::sysinfo::SysinfoSubscriptionData;
+use crate::services::watch::WatchSubscriptionData;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
-use std::hash::Hash;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
 use iced::Subscription;
 use iced::futures::channel::mpsc;
+use tokio::sync::Notify;
 
 /// a service that provides data to modules
 pub trait Service: Debug + Clone + Sized {
@@ -38,6 +68,13 @@ pub trait Service: Debug + Clone + Sized {
     /// send an `Self::Event` to
     type EventType: Debug + Clone + Hash + Eq + PartialEq;
 
+    /// what modules call the service when asking if it's available, the same
+    /// as the id of its register if it has one
+    const ID: u16;
+    /// the version of the data the service gives modules, raised when a
+    /// module written for the old data would break
+    const VERSION: u32;
+
     /// allows the iced to subscribe to this service
     ///
     /// the subscription will emit `ServiceEvent::Init(Self)` on either:
@@ -52,7 +89,10 @@ pub trait Service: Debug + Clone + Sized {
     ///
     ///     Subscription::run_with_id(
     ///         id,
-    ///         channel(64, async |mut chan| {
+    ///         channel(64, async |chan| {
+    ///             // counts what goes through the channel in the metrics
+    ///             let mut chan = InstrumentedSender::new("service.example.events", chan);
+    ///
     ///             // services need to be aware of modules even after a service restart so we put
     ///             // it outside the loop to make it persistent
     ///             let mut module_ids = ModuleIds::new();
@@ -63,7 +103,10 @@ pub trait Service: Debug + Clone + Sized {
     ///                 let mut state = ServiceState::init();
     ///
     ///                 // setup channel for modules to talk to this service
-    ///                 let (tx, rx) = flume::bounded::<ServiceRequest<Self>>(64);
+    ///                 let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+    ///                     "service.example.requests",
+    ///                     64,
+    ///                 );
     ///
     ///                 // send channel to iced thread
     ///                 if let Err(err) = chan
@@ -76,8 +119,12 @@ pub trait Service: Debug + Clone + Sized {
     ///                     // this would mean the service could not initalize properly
     ///                 }
     ///
-    ///                 // start the service
-    ///                 let err = Self::run(&mut state, &mut chan, rx, &mut ()).await;
+    ///                 // start the service, `supervise` stops it when a restart is asked
+    ///                 // for with `aurorashell service restart`
+    ///                 let err = supervise::<Self>(Self::run(&mut state, &mut chan, rx, &mut ())).await;
+    ///                 if err.is::<RestartRequested>() {
+    ///                     continue;
+    ///                 }
     ///
     ///                 // handle error or just log it
     ///             }
@@ -97,7 +144,7 @@ pub trait Service: Debug + Clone + Sized {
         state: &mut Self::State,
         module_ids: &mut ModuleIds<Self>,
         runtime_data: &mut Self::RuntimeData,
-        chan: &mut mpsc::Sender<ServiceEvent<Self>>,
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
         request_rx: flume::Receiver<ServiceRequest<Self>>,
     ) -> anyhow::Error;
 }
@@ -138,7 +185,7 @@ pub enum ServiceEvent<S: Service> {
     /// to send a channel for requests to the service
     Init {
         /// the channel used to communicate with the service
-        request_tx: flume::Sender<ServiceRequest<S>>,
+        request_tx: InstrumentedSender<flume::Sender<ServiceRequest<S>>>,
     },
     /// all events must specify the runtime they're for, id, and event
     Update { event: S::Event },
@@ -156,6 +203,9 @@ pub enum ServiceRequest<S: Service> {
         /// see `Service::SubscriptionData`
         data: S::SubscriptionData,
     },
+    /// the module was unloaded, or is being reloaded and will subscribe
+    /// again
+    UnsubscribeModule { id: RuntimeModuleId },
 }
 
 /// data structure for storing the relationship between module ids and
@@ -177,18 +227,36 @@ impl<S: Service> ModuleIds<S> {
         }
     }
 
-    /// registers a module with the service
-    pub fn register_module(&mut self, id: RuntimeModuleId, events: Vec<S::EventType>) {
-        for event in &events {
-            if let Some(ids) = &mut self.events_to_ids.get_mut(event) {
-                ids.insert(id.clone());
-            } else {
-                let mut ids = HashSet::new();
-                ids.insert(id.clone());
-                self.events_to_ids.insert(event.clone(), ids);
-            }
+    /// registers a module with the service, replacing whatever it registered
+    /// for before so registering again (like after a reload) never leaves
+    /// the module in the table twice
+    ///
+    /// returns the events that were added and removed for the module
+    pub fn register_module(
+        &mut self,
+        id: RuntimeModuleId,
+        events: Vec<S::EventType>,
+    ) -> RegisterDiff<S::EventType> {
+        let events: HashSet<S::EventType> = HashSet::from_iter(events);
+        let old = self.ids_to_events.remove(&id).unwrap_or_default();
+
+        let diff = RegisterDiff {
+            added: events.difference(&old).cloned().collect(),
+            removed: old.difference(&events).cloned().collect(),
+        };
+
+        for event in &diff.removed {
+            self.remove_from_event(event, &id);
+        }
+        for event in &diff.added {
+            self.events_to_ids
+                .entry(event.clone())
+                .or_default()
+                .insert(id.clone());
         }
-        self.ids_to_events.insert(id, HashSet::from_iter(events));
+
+        self.ids_to_events.insert(id, events);
+        return diff;
     }
 
     /// unregisters a module from the service
@@ -199,15 +267,330 @@ impl<S: Service> ModuleIds<S> {
         };
 
         for event in &events {
-            if let Some(ids) = self.events_to_ids.get_mut(event) {
-                ids.remove(&id);
+            self.remove_from_event(event, &id);
+        }
+    }
+
+    fn remove_from_event(&mut self, event: &S::EventType, id: &RuntimeModuleId) {
+        if let Some(ids) = self.events_to_ids.get_mut(event) {
+            ids.remove(id);
+            if ids.is_empty() {
+                self.events_to_ids.remove(event);
+            }
+        }
+    }
+}
+
+/// what registering a module again changed, see `ModuleIds::register_module`
+#[derive(Debug)]
+pub struct RegisterDiff<E> {
+    /// events the module wasn't registered for before
+    pub added: Vec<E>,
+    /// events the module was registered for and no longer is
+    pub removed: Vec<E>,
+}
+
+impl<E: Debug> std::fmt::Display for RegisterDiff<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.added.is_empty() && self.removed.is_empty() {
+            return write!(f, "no changes");
+        }
+
+        write!(f, "added {:?}, removed {:?}", self.added, self.removed)
+    }
+}
+
+/// updates the service's state with each event and sends the events that
+/// come out of it to the app
+pub async fn emit<S: Service>(
+    state: &mut S::State,
+    chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<S>>>,
+    events: Vec<S::Event>,
+) {
+    for event in events {
+        for event in state.update(event) {
+            if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                log::error!(
+                    "[services] [service:{}] error sending service event update: {err}",
+                    S::ID
+                );
             }
         }
     }
 }
 
+////////////////////////////////////////////////////////////////////////////////
+// deduplication
+
+/// remembers the last event of each kind by a hash of its contents, so an
+/// event that doesn't change anything can be dropped
+#[derive(Debug)]
+pub struct Dedup<K> {
+    last: HashMap<K, u64>,
+}
+
+impl<K: Hash + Eq> Dedup<K> {
+    pub fn new() -> Self {
+        Self {
+            last: HashMap::new(),
+        }
+    }
+
+    /// whether `event` differs from the last event of its `kind`, which it
+    /// then becomes
+    pub fn is_new(&mut self, kind: K, event: &impl Hash) -> bool {
+        let mut hasher = DefaultHasher::new();
+        event.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        return self.last.insert(kind, hash) != Some(hash);
+    }
+}
+
+/// holds events back for a short window, keeping only the newest of each
+/// kind, so a burst of events for one change comes out as one event
+#[derive(Debug)]
+pub struct Debounce<K, E> {
+    window: Duration,
+    /// the newest event of each kind, in the order the kinds first came in
+    pending: Vec<(K, E)>,
+    /// when the pending events are let out, set by the first of a burst so
+    /// a steady stream of events can't hold them back forever
+    deadline: Option<Instant>,
+}
+
+impl<K: Eq, E> Debounce<K, E> {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: vec![],
+            deadline: None,
+        }
+    }
+
+    /// holds `event`, replacing a pending event of the same kind
+    pub fn push(&mut self, kind: K, event: E) {
+        match self
+            .pending
+            .iter_mut()
+            .find(|(pending, _)| *pending == kind)
+        {
+            Some((_, pending)) => *pending = event,
+            None => self.pending.push((kind, event)),
+        }
+
+        if self.deadline.is_none() {
+            self.deadline = Some(Instant::now() + self.window);
+        }
+    }
+
+    /// when `Self::flush` should be called, `None` if nothing is pending
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// takes the pending events
+    pub fn flush(&mut self) -> Vec<E> {
+        self.deadline = None;
+        return self.pending.drain(..).map(|(_, event)| event).collect();
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum SubscriptionData {
-    Interval { milliseconds: u64, offset: u32 },
-    PulseAudio { data: AudioSubscriptionData },
+    Interval {
+        milliseconds: u64,
+        offset: u32,
+    },
+    PulseAudio {
+        data: AudioSubscriptionData,
+    },
+    /// handled by the audio service, `rate` is how many times a second the
+    /// module wants the levels
+    AudioMeter {
+        rate: u32,
+    },
+    Custom {
+        data: CustomSubscriptionData,
+    },
+    Network {
+        data: NetworkSubscriptionData,
+    },
+    Sysinfo {
+        data: SysinfoSubscriptionData,
+    },
+    Brightness {
+        data: BrightnessSubscriptionData,
+    },
+    Clock {
+        data: ClockSubscriptionData,
+    },
+    KdeConnect {
+        data: KdeConnectSubscriptionData,
+    },
+    Hotkeys {
+        data: HotkeysSubscriptionData,
+    },
+    Inhibit {
+        data: InhibitSubscriptionData,
+    },
+    /// modules with this get every weather update
+    Weather,
+    Watch {
+        data: WatchSubscriptionData,
+    },
+    Dbus {
+        data: DbusSubscriptionData,
+    },
+    /// the monitors are tracked by the app rather than a service, modules
+    /// with this are rendered again when one is plugged in, unplugged or
+    /// changes
+    Outputs,
+    /// the palette is read by the app rather than a service, modules with
+    /// this are rendered again when `colors.toml` is reloaded
+    Theme,
+}
+
+impl SubscriptionData {
+    /// the name of the service that handles the register, like in
+    /// `aurorashell query services`. `Outputs` and `Theme` are handled by
+    /// the app and go by their own name
+    pub fn service_name(&self) -> &'static str {
+        match self {
+            Self::Interval { .. } => "interval",
+            Self::PulseAudio { .. } | Self::AudioMeter { .. } => "audio",
+            Self::Custom { .. } => "custom",
+            Self::Network { .. } => "network",
+            Self::Sysinfo { .. } => "sysinfo",
+            Self::Brightness { .. } => "brightness",
+            Self::Clock { .. } => "clock",
+            Self::KdeConnect { .. } => "kdeconnect",
+            Self::Hotkeys { .. } => "hotkeys",
+            Self::Inhibit { .. } => "inhibit",
+            Self::Weather => "weather",
+            Self::Watch { .. } => "watch",
+            Self::Dbus { .. } => "dbus",
+            Self::Outputs => "outputs",
+            Self::Theme => "theme",
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// discovery
+
+/// the services that are running, mapped from their id to their version
+///
+/// kept outside the app so the wasm runtime can answer modules asking about
+/// services without a round trip
+static AVAILABLE: LazyLock<Mutex<BTreeMap<u16, u32>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// marks a service as running or not, returns whether that changed anything
+pub fn set_available<S: Service>(available: bool) -> bool {
+    let mut services = match AVAILABLE.lock() {
+        Ok(services) => services,
+        Err(err) => {
+            log::error!("[services] could not lock available services: {err}");
+            return false;
+        }
+    };
+
+    if available {
+        return services.insert(S::ID, S::VERSION) != Some(S::VERSION);
+    }
+
+    return services.remove(&S::ID).is_some();
+}
+
+/// the version of the service with the id, if it's running
+pub fn version(id: u16) -> Option<u32> {
+    match AVAILABLE.lock() {
+        Ok(services) => services.get(&id).copied(),
+        Err(err) => {
+            log::error!("[services] could not lock available services: {err}");
+            None
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// restarting
+
+/// what `supervise` stops a service with when `restart` was called for it
+#[derive(Debug)]
+pub struct RestartRequested;
+
+impl std::fmt::Display for RestartRequested {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "restart requested")
+    }
+}
+
+impl std::error::Error for RestartRequested {}
+
+/// wakes the supervisor of the service with the id, added the first time
+/// the service is supervised
+static RESTARTS: LazyLock<Mutex<HashMap<u16, Arc<Notify>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn restart_notify<S: Service>() -> Option<Arc<Notify>> {
+    match RESTARTS.lock() {
+        Ok(mut restarts) => Some(restarts.entry(S::ID).or_default().clone()),
+        Err(err) => {
+            log::error!("[services] could not lock service restarts: {err}");
+            None
+        }
+    }
+}
+
+/// runs a service until it stops on its own or `restart` is called for it,
+/// which drops it and returns `RestartRequested`
+///
+/// the supervisor should start the service again straight away for that
+/// error, keeping its `ModuleIds` so modules stay subscribed
+pub async fn supervise<S: Service>(run: impl Future<Output = anyhow::Error>) -> anyhow::Error {
+    let Some(notify) = restart_notify::<S>() else {
+        return run.await;
+    };
+
+    tokio::select! {
+        err = run => err,
+        _ = notify.notified() => anyhow::Error::new(RestartRequested),
+    }
+}
+
+/// waits before starting a service again after an error, cut short when
+/// `restart` is called for it
+pub async fn restart_delay<S: Service>(delay: Duration) {
+    let Some(notify) = restart_notify::<S>() else {
+        return tokio::time::sleep(delay).await;
+    };
+
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => {}
+        _ = notify.notified() => {}
+    }
+}
+
+/// asks the service with the id to restart, returns false if it was never
+/// started (like the weather service without a location)
+///
+/// only that service is restarted, other services and modules keep running
+pub fn restart(id: u16) -> bool {
+    let restarts = match RESTARTS.lock() {
+        Ok(restarts) => restarts,
+        Err(err) => {
+            log::error!("[services] could not lock service restarts: {err}");
+            return false;
+        }
+    };
+
+    let Some(notify) = restarts.get(&id) else {
+        return false;
+    };
+
+    // stores a permit if the supervisor is between runs so it isn't missed
+    notify.notify_one();
+    return true;
 }
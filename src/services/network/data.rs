@@ -0,0 +1,144 @@
+/// messages emitted from the network service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when a saved connection is added or removed
+    ConnectionsChanged { connections: Vec<Connection> },
+    /// event emitted when the connection the default route goes through
+    /// changes, `None` when offline
+    ActiveConnectionChanged { active: Option<ActiveConnection> },
+    /// event emitted when the signal strength (0 - 100) of the access point
+    /// the active connection uses changes, `None` when it isn't wifi
+    WirelessStrengthChanged { strength: Option<u8> },
+    /// event emitted when wifi is turned on or off
+    WifiEnabledChanged { enabled: bool },
+
+    /// event emitted when NetworkManager can't be reached, or the connection
+    /// to it was lost
+    ///
+    /// modules should show a disabled state until `Event::ServiceAvailable`
+    /// is emitted
+    ServiceUnavailable { reason: String },
+    /// event emitted when the service connected to NetworkManager
+    ServiceAvailable,
+}
+
+/// requests for the network service
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// turns wifi on or off
+    SetWifiEnabled { enabled: bool },
+    /// activates a saved connection by its name or uuid (see
+    /// `Connection.id` and `Connection.uuid`)
+    ConnectToNetwork { connection: String },
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum NetworkEventType {
+    ConnectionsChanged,
+    ActiveConnectionChanged,
+    WirelessStrengthChanged,
+    WifiEnabledChanged,
+}
+
+impl Event {
+    /// the kind of event modules register for, `None` for the events about
+    /// the service itself
+    pub fn event_type(&self) -> Option<NetworkEventType> {
+        match self {
+            Self::ConnectionsChanged { .. } => Some(NetworkEventType::ConnectionsChanged),
+            Self::ActiveConnectionChanged { .. } => Some(NetworkEventType::ActiveConnectionChanged),
+            Self::WirelessStrengthChanged { .. } => Some(NetworkEventType::WirelessStrengthChanged),
+            Self::WifiEnabledChanged { .. } => Some(NetworkEventType::WifiEnabledChanged),
+            Self::ServiceUnavailable { .. } | Self::ServiceAvailable => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkSubscriptionData(pub u8);
+
+impl NetworkSubscriptionData {
+    /// subscribes to the active connection changing
+    pub const ACTIVE_CONNECTION_CHANGED: Self = Self(0b_0000_0010);
+    /// subscribes to the saved connections changing
+    pub const CONNECTIONS_CHANGED: Self = Self(0b_0000_0001);
+    /// subscribes to wifi being turned on or off
+    pub const WIFI_ENABLED_CHANGED: Self = Self(0b_0000_1000);
+    /// subscribes to the wifi signal strength changing
+    pub const WIRELESS_STRENGTH_CHANGED: Self = Self(0b_0000_0100);
+
+    pub fn is_set(&self, case: NetworkSubscriptionData) -> bool {
+        return *self & case != NetworkSubscriptionData(0);
+    }
+}
+
+impl BitOr for NetworkSubscriptionData {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for NetworkSubscriptionData {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for NetworkSubscriptionData {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// types used for events
+
+/// a saved connection
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Connection {
+    /// the name of the connection, what `nmcli` shows as NAME
+    pub id: String,
+    pub uuid: String,
+    pub kind: ConnectionKind,
+}
+
+/// the connection the default route goes through
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActiveConnection {
+    pub id: String,
+    pub uuid: String,
+    pub kind: ConnectionKind,
+    /// the name of the wifi network, `None` if it isn't wifi
+    pub ssid: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ConnectionKind {
+    Wifi,
+    Ethernet,
+    /// any other type, like a vpn or bridge, with NetworkManager's name for it
+    Other(String),
+}
+
+impl ConnectionKind {
+    /// from NetworkManager's name for the type, like `802-11-wireless`
+    pub fn from_nm(kind: &str) -> Self {
+        match kind {
+            "802-11-wireless" => Self::Wifi,
+            "802-3-ethernet" => Self::Ethernet,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,480 @@
+//! shows the state of the network from NetworkManager over dbus, like the
+//! wifi network that's connected and its signal strength
+
+mod data;
+mod nm;
+mod se;
+mod state;
+
+pub use data::{
+    ActiveConnection, Connection, ConnectionKind, Event, NetworkSubscriptionData, Request,
+};
+pub use state::NetworkState;
+
+use data::NetworkEventType;
+use nm::{
+    AccessPointProxy, ActiveProxy, NetworkManagerProxy, SettingsConnectionProxy, SettingsProxy,
+    connection_setting,
+};
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use zbus::proxy::{PropertyChanged, PropertyStream};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// the first wait before trying to reach NetworkManager again after it
+/// couldn't be found, doubles each failed attempt
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(5);
+/// the longest wait between attempts to reach NetworkManager
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct NetworkService;
+
+/// returned from `NetworkService::run` when NetworkManager couldn't be
+/// reached, so the service waits longer before trying again
+#[derive(Debug)]
+struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NetworkManager unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+impl Service for NetworkService {
+    type Event = Event;
+    type EventType = NetworkEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = NetworkState;
+    type SubscriptionData = NetworkSubscriptionData;
+
+    const ID: u16 = 5;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.network.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut backoff = RETRY_BACKOFF_MIN;
+
+                loop {
+                    let mut state = NetworkState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.network.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:network] could not send init event: {}", err);
+                        log::error!("[service:network] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:network] restarting");
+                        backoff = RETRY_BACKOFF_MIN;
+                        continue;
+                    }
+
+                    let (reason, delay) = match err.downcast_ref::<Unavailable>() {
+                        Some(Unavailable(reason)) => {
+                            log::warn!(
+                                "[service:network] {err}, retrying in {}",
+                                humantime::format_duration(backoff)
+                            );
+
+                            let delay = backoff;
+                            backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                            (reason.clone(), delay)
+                        }
+                        None => {
+                            log::error!("[service:network] error: {err}");
+                            log::error!("[service:network] restarting in 5 seconds...");
+
+                            // the service was connected so the next failure
+                            // starts from the shortest wait again
+                            backoff = RETRY_BACKOFF_MIN;
+                            (err.to_string(), Duration::from_secs(5))
+                        }
+                    };
+
+                    emit(
+                        &mut state,
+                        &mut chan,
+                        vec![Event::ServiceUnavailable { reason }],
+                    )
+                    .await;
+
+                    restart_delay::<Self>(delay).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut NetworkState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let conn = match zbus::Connection::system().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                return Unavailable(format!("could not connect to the system bus: {err}")).into();
+            }
+        };
+
+        let (nm, settings) =
+            match tokio::try_join!(NetworkManagerProxy::new(&conn), SettingsProxy::new(&conn)) {
+                Ok(proxies) => proxies,
+                Err(err) => return Unavailable(format!("could not create proxies: {err}")).into(),
+            };
+
+        // the first call fails when NetworkManager isn't running
+        let wifi_enabled = match nm.wireless_enabled().await {
+            Ok(enabled) => enabled,
+            Err(err) => return Unavailable(err.to_string()).into(),
+        };
+
+        log::info!("[service:network] service started");
+        startup::ready("network");
+
+        let mut primary_changed = nm.receive_primary_connection_changed().await;
+        let mut wifi_changed = nm.receive_wireless_enabled_changed().await;
+        let (mut connection_added, mut connection_removed) = match tokio::try_join!(
+            settings.receive_new_connection(),
+            settings.receive_connection_removed()
+        ) {
+            Ok(streams) => streams,
+            Err(err) => {
+                return anyhow!("[service:network] could not listen for connection changes: {err}");
+            }
+        };
+
+        let connections = match read_connections(&conn, &settings).await {
+            Ok(connections) => connections,
+            Err(err) => return anyhow!("[service:network] could not read connections: {err}"),
+        };
+        let (active, mut access_point) = match read_active(&conn, &nm).await {
+            Ok(active) => active,
+            Err(err) => {
+                return anyhow!("[service:network] could not read the active connection: {err}");
+            }
+        };
+        let mut strength_changed = watch_strength(&access_point).await;
+
+        emit(
+            state,
+            chan,
+            vec![
+                Event::ServiceAvailable,
+                Event::WifiEnabledChanged {
+                    enabled: wifi_enabled,
+                },
+                Event::ConnectionsChanged { connections },
+                Event::ActiveConnectionChanged { active },
+                Event::WirelessStrengthChanged {
+                    strength: read_strength(&access_point).await,
+                },
+            ],
+        )
+        .await;
+
+        loop {
+            let events = tokio::select! {
+                Some(_) = primary_changed.next() => {
+                    match read_active(&conn, &nm).await {
+                        Ok((active, new_access_point)) => {
+                            access_point = new_access_point;
+                            strength_changed = watch_strength(&access_point).await;
+
+                            vec![
+                                Event::ActiveConnectionChanged { active },
+                                Event::WirelessStrengthChanged {
+                                    strength: read_strength(&access_point).await,
+                                },
+                            ]
+                        }
+                        Err(err) => {
+                            // the connection can go away while it's being read,
+                            // the next change reads it again
+                            log::warn!(
+                                "[service:network] could not read the active connection: {err}"
+                            );
+                            vec![]
+                        }
+                    }
+                }
+                Some(changed) = wifi_changed.next() => match changed.get().await {
+                    Ok(enabled) => vec![Event::WifiEnabledChanged { enabled }],
+                    Err(err) => {
+                        log::warn!(
+                            "[service:network] could not read whether wifi is enabled: {err}"
+                        );
+                        vec![]
+                    }
+                },
+                Some(changed) = next_strength(&mut strength_changed) => match changed.get().await {
+                    Ok(strength) => vec![Event::WirelessStrengthChanged {
+                        strength: Some(strength),
+                    }],
+                    Err(err) => {
+                        log::warn!("[service:network] could not read the signal strength: {err}");
+                        vec![]
+                    }
+                },
+                Some(_) = connection_added.next() => {
+                    Self::connections_changed(&conn, &settings).await
+                }
+                Some(_) = connection_removed.next() => {
+                    Self::connections_changed(&conn, &settings).await
+                }
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => {
+                            let result =
+                                Self::request(&conn, &nm, &settings, request.clone()).await;
+                            if let Err(err) = result {
+                                log::warn!("[service:network] could not handle {request:?}: {err}");
+                            }
+                        }
+                        Ok(ServiceRequest::SubscribeModule { id, data }) => {
+                            let mut events = vec![];
+
+                            if data.is_set(NetworkSubscriptionData::CONNECTIONS_CHANGED) {
+                                events.push(NetworkEventType::ConnectionsChanged);
+                            }
+                            if data.is_set(NetworkSubscriptionData::ACTIVE_CONNECTION_CHANGED) {
+                                events.push(NetworkEventType::ActiveConnectionChanged);
+                            }
+                            if data.is_set(NetworkSubscriptionData::WIRELESS_STRENGTH_CHANGED) {
+                                events.push(NetworkEventType::WirelessStrengthChanged);
+                            }
+                            if data.is_set(NetworkSubscriptionData::WIFI_ENABLED_CHANGED) {
+                                events.push(NetworkEventType::WifiEnabledChanged);
+                            }
+
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:network] {id:?} registered, {diff}");
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            module_ids.unregister_module(id);
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:network] error receiving request: {err}");
+                        }
+                    }
+
+                    vec![]
+                }
+            };
+
+            emit(state, chan, events).await;
+        }
+    }
+}
+
+impl NetworkService {
+    async fn connections_changed(
+        conn: &zbus::Connection,
+        settings: &SettingsProxy<'_>,
+    ) -> Vec<Event> {
+        match read_connections(conn, settings).await {
+            Ok(connections) => vec![Event::ConnectionsChanged { connections }],
+            Err(err) => {
+                log::warn!("[service:network] could not read connections: {err}");
+                vec![]
+            }
+        }
+    }
+
+    async fn request(
+        conn: &zbus::Connection,
+        nm: &NetworkManagerProxy<'_>,
+        settings: &SettingsProxy<'_>,
+        request: Request,
+    ) -> anyhow::Result<()> {
+        match request {
+            Request::SetWifiEnabled { enabled } => {
+                nm.set_wireless_enabled(enabled).await?;
+            }
+            Request::ConnectToNetwork { connection } => {
+                let path = match find_connection(conn, settings, &connection).await? {
+                    Some(path) => path,
+                    None => {
+                        return Err(anyhow!("there is no saved connection called {connection}"));
+                    }
+                };
+
+                // lets NetworkManager pick the device and access point
+                let any = ObjectPath::from_static_str_unchecked("/");
+                nm.activate_connection(&path, &any, &any).await?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// reads the saved connections, skipping any without a name, uuid or type
+async fn read_connections(
+    conn: &zbus::Connection,
+    settings: &SettingsProxy<'_>,
+) -> zbus::Result<Vec<Connection>> {
+    let mut connections = vec![];
+
+    for path in settings.list_connections().await? {
+        let connection = SettingsConnectionProxy::builder(conn)
+            .path(path)?
+            .build()
+            .await?
+            .get_settings()
+            .await?;
+
+        if let Some(id) = connection_setting(&connection, "id")
+            && let Some(uuid) = connection_setting(&connection, "uuid")
+            && let Some(kind) = connection_setting(&connection, "type")
+        {
+            connections.push(Connection {
+                id,
+                uuid,
+                kind: ConnectionKind::from_nm(&kind),
+            });
+        }
+    }
+
+    return Ok(connections);
+}
+
+/// the path of the saved connection with `name` as its name or uuid
+async fn find_connection(
+    conn: &zbus::Connection,
+    settings: &SettingsProxy<'_>,
+    name: &str,
+) -> zbus::Result<Option<OwnedObjectPath>> {
+    for path in settings.list_connections().await? {
+        let connection = SettingsConnectionProxy::builder(conn)
+            .path(path.clone())?
+            .build()
+            .await?
+            .get_settings()
+            .await?;
+
+        if connection_setting(&connection, "id").as_deref() == Some(name)
+            || connection_setting(&connection, "uuid").as_deref() == Some(name)
+        {
+            return Ok(Some(path));
+        }
+    }
+
+    return Ok(None);
+}
+
+/// reads the primary connection, along with the access point it uses if it's
+/// wifi
+async fn read_active(
+    conn: &zbus::Connection,
+    nm: &NetworkManagerProxy<'_>,
+) -> zbus::Result<(Option<ActiveConnection>, Option<AccessPointProxy<'static>>)> {
+    let path = nm.primary_connection().await?;
+    if path.as_str() == "/" {
+        return Ok((None, None));
+    }
+
+    let active = ActiveProxy::builder(conn).path(path)?.build().await?;
+    let kind = ConnectionKind::from_nm(&active.kind().await?);
+
+    let access_point = match kind {
+        ConnectionKind::Wifi => {
+            let path = active.specific_object().await?;
+            match path.as_str() {
+                "/" => None,
+                _ => Some(AccessPointProxy::builder(conn).path(path)?.build().await?),
+            }
+        }
+        _ => None,
+    };
+
+    let ssid = match &access_point {
+        Some(access_point) => {
+            Some(String::from_utf8_lossy(&access_point.ssid().await?).into_owned())
+        }
+        None => None,
+    };
+
+    return Ok((
+        Some(ActiveConnection {
+            id: active.id().await?,
+            uuid: active.uuid().await?,
+            kind,
+            ssid,
+        }),
+        access_point,
+    ));
+}
+
+async fn read_strength(access_point: &Option<AccessPointProxy<'static>>) -> Option<u8> {
+    match access_point {
+        Some(access_point) => access_point.strength().await.ok(),
+        None => None,
+    }
+}
+
+async fn watch_strength(
+    access_point: &Option<AccessPointProxy<'static>>,
+) -> Option<PropertyStream<'static, u8>> {
+    match access_point {
+        Some(access_point) => Some(access_point.receive_strength_changed().await),
+        None => None,
+    }
+}
+
+/// waits forever when there's no access point to watch
+async fn next_strength(
+    stream: &mut Option<PropertyStream<'static, u8>>,
+) -> Option<PropertyChanged<'static, u8>> {
+    match stream {
+        Some(stream) => stream.next().await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,95 @@
+//! the parts of NetworkManager's dbus api the service uses
+//!
+//! see https://networkmanager.dev/docs/api/latest/spec.html
+
+use std::collections::HashMap;
+
+use zbus::proxy;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+
+/// the settings of a saved connection, grouped by setting name like
+/// `connection` or `802-11-wireless`
+pub type ConnectionSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+pub trait NetworkManager {
+    /// `device` and `specific_object` can be `/` to let NetworkManager pick
+    fn activate_connection(
+        &self,
+        connection: &ObjectPath<'_>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    /// the active connection the default route goes through, `/` if there
+    /// isn't one
+    #[zbus(property)]
+    fn primary_connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn wireless_enabled(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn set_wireless_enabled(&self, enabled: bool) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+pub trait Settings {
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    #[zbus(signal)]
+    fn new_connection(&self, connection: ObjectPath<'_>) -> zbus::Result<()>;
+    #[zbus(signal)]
+    fn connection_removed(&self, connection: ObjectPath<'_>) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait SettingsConnection {
+    fn get_settings(&self) -> zbus::Result<ConnectionSettings>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait Active {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn uuid(&self) -> zbus::Result<String>;
+    #[zbus(property, name = "Type")]
+    fn kind(&self) -> zbus::Result<String>;
+    /// the access point for wifi connections
+    #[zbus(property)]
+    fn specific_object(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait AccessPoint {
+    /// not always utf-8
+    #[zbus(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
+    /// 0 - 100
+    #[zbus(property)]
+    fn strength(&self) -> zbus::Result<u8>;
+}
+
+/// a string from the `connection` group of a connection's settings, like
+/// `id`, `uuid` or `type`
+pub fn connection_setting(settings: &ConnectionSettings, key: &str) -> Option<String> {
+    let value = settings.get("connection")?.get(key)?;
+    return value.downcast_ref::<&str>().ok().map(str::to_string);
+}
@@ -0,0 +1,194 @@
+use super::{
+    ActiveConnection, Connection, ConnectionKind, Event, NetworkEventType, NetworkService,
+    NetworkSubscriptionData,
+};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+/// the layout modules get network events in through `service_event`, a u8
+/// for the kind of event then what changed. strings are a u16 length then
+/// the bytes
+///
+/// - 0 the saved connections changed, a u16 for the amount of connections
+///   then each one in the layout of `push_connection`
+/// - 1 the active connection changed, a u8 for whether there is one then it
+///   in the layout of `push_connection` followed by the ssid, 0 long when it
+///   isn't wifi
+/// - 2 the signal strength changed, a u8 from 0 to 100 or 255 when the
+///   active connection isn't wifi
+/// - 3 wifi was turned on or off, a u8 for whether it's on
+/// - 4 the service is unavailable with the reason, 5 it's available again
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        NetworkService::ID
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        let mut bytes = vec![];
+
+        match self {
+            Event::ConnectionsChanged { connections } => {
+                bytes.push(0);
+                let connections = &connections[..connections.len().min(u16::MAX as usize)];
+                bytes.extend((connections.len() as u16).to_le_bytes());
+                for Connection { id, uuid, kind } in connections {
+                    push_connection(&mut bytes, id, uuid, kind);
+                }
+            }
+            Event::ActiveConnectionChanged { active } => {
+                bytes.push(1);
+                bytes.push(active.is_some() as u8);
+                if let Some(ActiveConnection {
+                    id,
+                    uuid,
+                    kind,
+                    ssid,
+                }) = active
+                {
+                    push_connection(&mut bytes, id, uuid, kind);
+                    push_str(&mut bytes, ssid.as_deref().unwrap_or_default());
+                }
+            }
+            Event::WirelessStrengthChanged { strength } => {
+                bytes.push(2);
+                bytes.push(strength.map_or(u8::MAX, |strength| strength.min(100)));
+            }
+            Event::WifiEnabledChanged { enabled } => {
+                bytes.push(3);
+                bytes.push(*enabled as u8);
+            }
+            Event::ServiceUnavailable { reason } => {
+                bytes.push(4);
+                push_str(&mut bytes, reason);
+            }
+            Event::ServiceAvailable => bytes.push(5),
+        }
+
+        return Some(bytes);
+    }
+
+    fn is_for(&self, register: &SubscriptionData) -> bool {
+        let SubscriptionData::Network { data } = register else {
+            return false;
+        };
+
+        return self.event_type().is_none_or(|event_type| {
+            data.is_set(match event_type {
+                NetworkEventType::ConnectionsChanged => {
+                    NetworkSubscriptionData::CONNECTIONS_CHANGED
+                }
+                NetworkEventType::ActiveConnectionChanged => {
+                    NetworkSubscriptionData::ACTIVE_CONNECTION_CHANGED
+                }
+                NetworkEventType::WirelessStrengthChanged => {
+                    NetworkSubscriptionData::WIRELESS_STRENGTH_CHANGED
+                }
+                NetworkEventType::WifiEnabledChanged => {
+                    NetworkSubscriptionData::WIFI_ENABLED_CHANGED
+                }
+            })
+        });
+    }
+}
+
+/// a connection as modules read it, the name and uuid then a u8 for the
+/// kind, 0 wifi, 1 ethernet and 2 anything else followed by NetworkManager's
+/// name for it
+fn push_connection(bytes: &mut Vec<u8>, id: &str, uuid: &str, kind: &ConnectionKind) {
+    push_str(bytes, id);
+    push_str(bytes, uuid);
+    match kind {
+        ConnectionKind::Wifi => bytes.push(0),
+        ConnectionKind::Ethernet => bytes.push(1),
+        ConnectionKind::Other(name) => {
+            bytes.push(2);
+            push_str(bytes, name);
+        }
+    }
+}
+
+fn push_str(bytes: &mut Vec<u8>, string: &str) {
+    let string = &string.as_bytes()[..string.len().min(u16::MAX as usize)];
+    bytes.extend((string.len() as u16).to_le_bytes());
+    bytes.extend(string);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registered(data: NetworkSubscriptionData) -> SubscriptionData {
+        return SubscriptionData::Network { data };
+    }
+
+    #[test]
+    fn registered_module_gets_the_event() {
+        let event = Event::ActiveConnectionChanged {
+            active: Some(ActiveConnection {
+                id: "home".to_string(),
+                uuid: "u".to_string(),
+                kind: ConnectionKind::Wifi,
+                ssid: Some("net".to_string()),
+            }),
+        };
+
+        assert!(event.is_for(&registered(
+            NetworkSubscriptionData::ACTIVE_CONNECTION_CHANGED
+                | NetworkSubscriptionData::WIFI_ENABLED_CHANGED
+        )));
+        assert!(!event.is_for(&registered(NetworkSubscriptionData::WIFI_ENABLED_CHANGED)));
+        assert!(!event.is_for(&SubscriptionData::Weather));
+
+        assert_eq!(
+            event.serialize().unwrap(),
+            [
+                &[1, 1, 4, 0][..],
+                b"home",
+                &[1, 0],
+                b"u",
+                &[0, 3, 0],
+                b"net",
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn service_events_go_to_every_registered_module() {
+        let event = Event::ServiceAvailable;
+
+        assert!(event.is_for(&registered(NetworkSubscriptionData(0))));
+        assert_eq!(event.serialize().unwrap(), [5]);
+    }
+
+    #[test]
+    fn serializes_connections() {
+        let event = Event::ConnectionsChanged {
+            connections: vec![Connection {
+                id: "vpn".to_string(),
+                uuid: "v".to_string(),
+                kind: ConnectionKind::Other("wireguard".to_string()),
+            }],
+        };
+
+        assert_eq!(
+            event.serialize().unwrap(),
+            [
+                &[0, 1, 0, 3, 0][..],
+                b"vpn",
+                &[1, 0],
+                b"v",
+                &[2, 9, 0],
+                b"wireguard",
+            ]
+            .concat()
+        );
+        assert_eq!(
+            Event::WirelessStrengthChanged { strength: None }
+                .serialize()
+                .unwrap(),
+            [2, 255]
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use super::data::{ActiveConnection, Connection, NetworkEventType};
+use super::{Event, NetworkService};
+
+use crate::services::{Dedup, ServiceState};
+
+#[derive(Debug)]
+pub struct NetworkState {
+    /// the saved connections
+    pub connections: Vec<Connection>,
+    /// the connection the default route goes through
+    pub active: Option<ActiveConnection>,
+    /// the signal strength of the active wifi connection, 0 - 100
+    pub strength: Option<u8>,
+    pub wifi_enabled: bool,
+
+    /// why NetworkManager can't be used, `None` when connected
+    pub unavailable: Option<String>,
+
+    /// everything is read again when anything changes, so most reads are
+    /// the same as before and are dropped
+    dedup: Dedup<NetworkEventType>,
+}
+
+impl ServiceState<NetworkService> for NetworkState {
+    fn init() -> Self {
+        Self {
+            connections: vec![],
+            active: None,
+            strength: None,
+            wifi_enabled: false,
+            unavailable: None,
+            dedup: Dedup::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        if let Some(kind) = event.event_type()
+            && !self.dedup.is_new(kind, &event)
+        {
+            return vec![];
+        }
+
+        match event.clone() {
+            Event::ConnectionsChanged { connections } => {
+                self.connections = connections;
+            }
+            Event::ActiveConnectionChanged { active } => {
+                self.active = active;
+            }
+            Event::WirelessStrengthChanged { strength } => {
+                self.strength = strength;
+            }
+            Event::WifiEnabledChanged { enabled } => {
+                self.wifi_enabled = enabled;
+            }
+            Event::ServiceUnavailable { reason } => {
+                *self = Self::init();
+                self.unavailable = Some(reason);
+            }
+            Event::ServiceAvailable => {
+                self.unavailable = None;
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -0,0 +1,89 @@
+use crate::runtime::RuntimeModuleId;
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// messages emitted from the screenshot service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when a screenshot a module asked for was saved, it's
+    /// only for that module
+    Captured {
+        module: RuntimeModuleId,
+        path: PathBuf,
+    },
+    /// event emitted when a screenshot a module asked for couldn't be taken
+    /// or saved
+    CaptureFailed {
+        module: RuntimeModuleId,
+        reason: String,
+    },
+
+    /// event emitted when `grim` isn't installed
+    ServiceUnavailable { reason: String },
+    /// event emitted when the service found `grim`
+    ServiceAvailable,
+}
+
+/// requests for the screenshot service
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// saves what's on the output with the name, or on every output when
+    /// it's `None`
+    CaptureOutput {
+        module: RuntimeModuleId,
+        output: Option<String>,
+    },
+    /// saves a rectangle of the screen, like one picked with the region
+    /// overlay
+    CaptureRegion {
+        module: RuntimeModuleId,
+        region: Geometry,
+    },
+}
+
+/// a rectangle in the compositor's layout in logical pixels, so it can span
+/// outputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// how `grim -g` takes it
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{} {}x{}", self.x, self.y, self.width, self.height)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum ScreenshotEventType {
+    Captured,
+}
+
+impl Event {
+    /// the kind of event, `None` for the events about the service itself
+    pub fn event_type(&self) -> Option<ScreenshotEventType> {
+        match self {
+            Self::Captured { .. } | Self::CaptureFailed { .. } => {
+                Some(ScreenshotEventType::Captured)
+            }
+            Self::ServiceUnavailable { .. } | Self::ServiceAvailable => None,
+        }
+    }
+}
+
+impl Request {
+    /// the module that asked for the screenshot
+    pub fn module(&self) -> &RuntimeModuleId {
+        match self {
+            Self::CaptureOutput { module, .. } | Self::CaptureRegion { module, .. } => module,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! takes screenshots with `grim`, which uses the compositor's
+//! wlr-screencopy protocol
+//!
+//! the desktop portal's screenshot can't be told an output or a region, so
+//! it isn't used
+
+use super::Request;
+
+use std::path::Path;
+use std::process::Stdio;
+
+use anyhow::anyhow;
+use tokio::process::Command;
+
+/// whether `grim` can be run, the error says why when it can't
+pub async fn check() -> anyhow::Result<()> {
+    let result = Command::new("grim")
+        .arg("-h")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await;
+
+    return match result {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            Err(anyhow!("`grim` isn't installed"))
+        }
+        Err(err) => Err(anyhow!("could not run `grim`: {err}")),
+    };
+}
+
+/// saves a png of what `request` asked for to `path`
+pub async fn capture(request: &Request, path: &Path) -> anyhow::Result<()> {
+    let mut command = Command::new("grim");
+    command.stdout(Stdio::null()).stderr(Stdio::piped());
+
+    match request {
+        Request::CaptureOutput {
+            output: Some(output),
+            ..
+        } => {
+            command.arg("-o").arg(output);
+        }
+        Request::CaptureOutput { output: None, .. } => {}
+        Request::CaptureRegion { region, .. } => {
+            command.arg("-g").arg(region.to_string());
+        }
+    }
+
+    let output = command.arg(path).output().await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("`grim` failed: {}", stderr.trim()));
+    }
+
+    return Ok(());
+}
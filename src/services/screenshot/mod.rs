@@ -0,0 +1,317 @@
+//! saves screenshots of an output or a region of the screen for modules,
+//! like a bar button that takes one and shows a thumbnail of it
+//!
+//! the screenshots are taken with `grim` and saved as pngs to `directory`
+//! under `[services.screenshot]`, or `$XDG_PICTURES_DIR/Screenshots`. the
+//! module that asked is given the path or why it failed, other modules
+//! don't hear about it
+
+mod data;
+mod grim;
+mod state;
+
+pub use data::{Event, Geometry, Request};
+pub use state::ScreenshotState;
+
+use data::ScreenshotEventType;
+
+use crate::config;
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::collections::VecDeque;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::task::JoinSet;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// the first wait before looking for `grim` again after it couldn't be
+/// run, doubles each failed attempt
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(5);
+/// the longest wait between attempts
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
+/// how many of the last screenshots modules can draw, see `saved`
+const MAX_SAVED: usize = 32;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// the screenshots the service saved last, oldest first
+///
+/// kept outside the app like `services::set_available`, so the wasm runtime
+/// can check a module only draws screenshots and not any file it likes
+static SAVED: LazyLock<Mutex<VecDeque<PathBuf>>> = LazyLock::new(|| Mutex::new(VecDeque::new()));
+
+/// whether `path` is one of the last screenshots the service saved
+pub fn saved(path: &Path) -> bool {
+    match SAVED.lock() {
+        Ok(saved) => saved.iter().any(|saved| saved == path),
+        Err(err) => {
+            log::error!("[service:screenshot] could not lock saved screenshots: {err}");
+            false
+        }
+    }
+}
+
+/// the outcome of a screenshot in the layout modules read it in, a u8 that's
+/// 1 when it was saved then the path, or the reason it failed, as a u16
+/// length followed by that many bytes of utf-8
+pub fn serialize(result: &Result<PathBuf, String>) -> Vec<u8> {
+    let (saved, text) = match result {
+        Ok(path) => (true, path.to_string_lossy()),
+        Err(reason) => (false, reason.as_str().into()),
+    };
+
+    let mut bytes = vec![saved as u8];
+    let text = &text.as_bytes()[..text.len().min(u16::MAX as usize)];
+    bytes.extend((text.len() as u16).to_le_bytes());
+    bytes.extend(text);
+
+    return bytes;
+}
+
+#[derive(Debug, Clone)]
+pub struct ScreenshotService;
+
+/// returned from `ScreenshotService::run` when `grim` couldn't be run, so
+/// the service waits longer before trying again
+#[derive(Debug)]
+struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "screenshots unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+impl Service for ScreenshotService {
+    type Event = Event;
+    type EventType = ScreenshotEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = ScreenshotState;
+    type SubscriptionData = ();
+
+    const ID: u16 = 19;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.screenshot.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut backoff = RETRY_BACKOFF_MIN;
+
+                loop {
+                    let mut state = ScreenshotState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.screenshot.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:screenshot] could not send init event: {}", err);
+                        log::error!("[service:screenshot] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:screenshot] restarting");
+                        backoff = RETRY_BACKOFF_MIN;
+                        continue;
+                    }
+
+                    let (reason, delay) = match err.downcast_ref::<Unavailable>() {
+                        Some(Unavailable(reason)) => {
+                            log::warn!(
+                                "[service:screenshot] {err}, retrying in {}",
+                                humantime::format_duration(backoff)
+                            );
+
+                            let delay = backoff;
+                            backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                            (reason.clone(), delay)
+                        }
+                        None => {
+                            log::error!("[service:screenshot] error: {err}");
+                            log::error!("[service:screenshot] restarting in 5 seconds...");
+
+                            backoff = RETRY_BACKOFF_MIN;
+                            (err.to_string(), Duration::from_secs(5))
+                        }
+                    };
+
+                    emit(
+                        &mut state,
+                        &mut chan,
+                        vec![Event::ServiceUnavailable { reason }],
+                    )
+                    .await;
+
+                    restart_delay::<Self>(delay).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut ScreenshotState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        if let Err(err) = grim::check().await {
+            return Unavailable(err.to_string()).into();
+        }
+
+        // the screenshots being taken, each gives back the event for the
+        // module that asked
+        let mut captures: JoinSet<Event> = JoinSet::new();
+
+        log::info!("[service:screenshot] service started");
+        startup::ready("screenshot");
+
+        emit(state, chan, vec![Event::ServiceAvailable]).await;
+
+        loop {
+            let event = tokio::select! {
+                Some(event) = captures.join_next() => match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        log::error!("[service:screenshot] a screenshot panicked: {err}");
+                        continue;
+                    }
+                },
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => {
+                            log::debug!("[service:screenshot] {request:?}");
+                            captures.spawn(capture(request));
+                            continue;
+                        }
+                        // modules are given the screenshots they asked for
+                        // without registering
+                        Ok(ServiceRequest::SubscribeModule { id, data: () }) => {
+                            let events = vec![ScreenshotEventType::Captured];
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:screenshot] {id:?} registered, {diff}");
+                            continue;
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            module_ids.unregister_module(id);
+                            continue;
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:screenshot] error receiving request: {err}");
+                        }
+                    }
+                }
+            };
+
+            emit(state, chan, vec![event]).await;
+        }
+    }
+}
+
+/// takes the screenshot `request` asked for and saves it to a new file in
+/// the screenshot directory
+async fn capture(request: Request) -> Event {
+    let module = request.module().clone();
+
+    let path = match new_path() {
+        Ok(path) => path,
+        Err(err) => {
+            log::error!("[service:screenshot] could not make a path to save to: {err}");
+            return Event::CaptureFailed {
+                module,
+                reason: err.to_string(),
+            };
+        }
+    };
+
+    if let Err(err) = grim::capture(&request, &path).await {
+        log::warn!(
+            "[service:screenshot] could not save {}: {err}",
+            path.display()
+        );
+        return Event::CaptureFailed {
+            module,
+            reason: err.to_string(),
+        };
+    }
+
+    log::info!("[service:screenshot] saved {}", path.display());
+
+    match SAVED.lock() {
+        Ok(mut saved) => {
+            if saved.len() >= MAX_SAVED {
+                saved.pop_front();
+            }
+            saved.push_back(path.clone());
+        }
+        Err(err) => log::error!("[service:screenshot] could not lock saved screenshots: {err}"),
+    }
+
+    return Event::Captured { module, path };
+}
+
+/// a path in the screenshot directory named after the time, the directory
+/// is made if it doesn't exist
+fn new_path() -> anyhow::Result<PathBuf> {
+    let dir = directory()?;
+    std::fs::create_dir_all(&dir)?;
+
+    let name = chrono::Local::now().format("screenshot-%Y-%m-%d-%H%M%S%.3f.png");
+    return Ok(dir.join(name.to_string()));
+}
+
+/// `directory` under `[services.screenshot]`, or `Screenshots` in
+/// `$XDG_PICTURES_DIR` or `$HOME/Pictures`
+fn directory() -> anyhow::Result<PathBuf> {
+    let home = env::var("HOME").map(PathBuf::from);
+
+    if let Some(dir) = &config::get().services.screenshot.directory {
+        return match dir.strip_prefix("~") {
+            Ok(rest) => Ok(home?.join(rest)),
+            Err(_) => Ok(dir.clone()),
+        };
+    }
+
+    return match env::var("XDG_PICTURES_DIR") {
+        Ok(pictures) if !pictures.is_empty() => Ok(PathBuf::from(pictures).join("Screenshots")),
+        _ => Ok(home?.join("Pictures/Screenshots")),
+    };
+}
@@ -0,0 +1,41 @@
+use super::{Event, ScreenshotService};
+
+use crate::services::ServiceState;
+
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub struct ScreenshotState {
+    /// the last screenshot saved
+    pub last: Option<PathBuf>,
+
+    /// why screenshots can't be taken, `None` when they can
+    pub unavailable: Option<String>,
+}
+
+impl ServiceState<ScreenshotService> for ScreenshotState {
+    fn init() -> Self {
+        Self {
+            last: None,
+            unavailable: None,
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match event.clone() {
+            Event::Captured { path, .. } => {
+                self.last = Some(path);
+            }
+            Event::CaptureFailed { .. } => {}
+            Event::ServiceUnavailable { reason } => {
+                *self = Self::init();
+                self.unavailable = Some(reason);
+            }
+            Event::ServiceAvailable => {
+                self.unavailable = None;
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+
+/// messages emitted from the session service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when the service starts with what logind says the
+    /// system can do
+    CapabilitiesChanged { capabilities: Capabilities },
+
+    /// event emitted when logind can't be reached
+    ServiceUnavailable { reason: String },
+    /// event emitted when the service connected to logind
+    ServiceAvailable,
+}
+
+/// requests for the session service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Request {
+    Suspend,
+    Hibernate,
+    Reboot,
+    PowerOff,
+    /// asks the screen locker to lock the session, which only does something
+    /// when one is listening to logind, like `swayidle` with a `lock` command
+    LockSession,
+}
+
+impl Request {
+    /// the request for an action a module asked for through
+    /// `session_request`, `None` for an unknown action
+    ///
+    /// 0 suspends, 1 hibernates, 2 reboots, 3 powers off and 4 locks the
+    /// session
+    pub fn from_module(action: u32) -> Option<Self> {
+        match action {
+            0 => Some(Self::Suspend),
+            1 => Some(Self::Hibernate),
+            2 => Some(Self::Reboot),
+            3 => Some(Self::PowerOff),
+            4 => Some(Self::LockSession),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            Self::Suspend => "suspend",
+            Self::Hibernate => "hibernate",
+            Self::Reboot => "reboot",
+            Self::PowerOff => "power off",
+            Self::LockSession => "lock the session",
+        };
+
+        write!(f, "{action}")
+    }
+}
+
+/// what logind says the system can do, locking always can
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct Capabilities {
+    pub suspend: bool,
+    pub hibernate: bool,
+    pub reboot: bool,
+    pub power_off: bool,
+}
+
+impl Capabilities {
+    /// one bit for each action in the order of `Request::from_module`, the
+    /// layout modules read them in
+    pub fn bits(&self) -> u8 {
+        return self.suspend as u8
+            | (self.hibernate as u8) << 1
+            | (self.reboot as u8) << 2
+            | (self.power_off as u8) << 3
+            | 1 << 4;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum SessionEventType {
+    CapabilitiesChanged,
+}
+
+impl Event {
+    /// the kind of event, `None` for the events about the service itself
+    pub fn event_type(&self) -> Option<SessionEventType> {
+        match self {
+            Self::CapabilitiesChanged { .. } => Some(SessionEventType::CapabilitiesChanged),
+            Self::ServiceUnavailable { .. } | Self::ServiceAvailable => None,
+        }
+    }
+}
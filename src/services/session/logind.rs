@@ -0,0 +1,35 @@
+//! the part of logind's dbus api the service uses
+//!
+//! see https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+pub trait Manager {
+    /// `interactive` lets polkit ask for a password when it wants one
+    fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+    fn hibernate(&self, interactive: bool) -> zbus::Result<()>;
+    fn reboot(&self, interactive: bool) -> zbus::Result<()>;
+    fn power_off(&self, interactive: bool) -> zbus::Result<()>;
+
+    /// `yes`, `no`, `challenge` when polkit would ask for a password or `na`
+    /// when the system can't
+    fn can_suspend(&self) -> zbus::Result<String>;
+    fn can_hibernate(&self) -> zbus::Result<String>;
+    fn can_reboot(&self) -> zbus::Result<String>;
+    fn can_power_off(&self) -> zbus::Result<String>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+pub trait Session {
+    /// asks the screen locker listening to logind to lock the session
+    fn lock(&self) -> zbus::Result<()>;
+}
@@ -0,0 +1,266 @@
+//! suspends, hibernates, reboots or powers off the system and locks the
+//! session through logind, for a power menu
+//!
+//! only the modules listed in `session` under `[modules]` can ask for these,
+//! a module that's only meant to show the weather shouldn't be able to turn
+//! the computer off. logind decides whether the user is allowed to, polkit
+//! asks for a password when it wants one
+
+mod data;
+mod logind;
+mod state;
+
+pub use data::{Capabilities, Event, Request};
+pub use state::SessionState;
+
+use data::SessionEventType;
+use logind::{ManagerProxy, SessionProxy};
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// the first wait before looking for logind again after it couldn't be
+/// reached, doubles each failed attempt
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(5);
+/// the longest wait between attempts
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// what the system can do in the layout of `Capabilities::bits`, 0 when the
+/// service isn't running
+///
+/// kept outside the app like `services::set_available`, so the wasm runtime
+/// can answer modules asking without a round trip
+static CAPABILITIES: AtomicU8 = AtomicU8::new(0);
+
+/// what the system can do in the layout of `Capabilities::bits`, 0 when the
+/// service isn't running
+pub fn capabilities() -> u8 {
+    return CAPABILITIES.load(Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionService;
+
+/// returned from `SessionService::run` when logind couldn't be reached, so
+/// the service waits longer before trying again
+#[derive(Debug)]
+struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "session unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+impl Service for SessionService {
+    type Event = Event;
+    type EventType = SessionEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = SessionState;
+    type SubscriptionData = ();
+
+    const ID: u16 = 20;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.session.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut backoff = RETRY_BACKOFF_MIN;
+
+                loop {
+                    let mut state = SessionState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.session.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:session] could not send init event: {}", err);
+                        log::error!("[service:session] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:session] restarting");
+                        backoff = RETRY_BACKOFF_MIN;
+                        continue;
+                    }
+
+                    let (reason, delay) = match err.downcast_ref::<Unavailable>() {
+                        Some(Unavailable(reason)) => {
+                            log::warn!(
+                                "[service:session] {err}, retrying in {}",
+                                humantime::format_duration(backoff)
+                            );
+
+                            let delay = backoff;
+                            backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                            (reason.clone(), delay)
+                        }
+                        None => {
+                            log::error!("[service:session] error: {err}");
+                            log::error!("[service:session] restarting in 5 seconds...");
+
+                            backoff = RETRY_BACKOFF_MIN;
+                            (err.to_string(), Duration::from_secs(5))
+                        }
+                    };
+
+                    Self::emit(
+                        &mut state,
+                        &mut chan,
+                        vec![Event::ServiceUnavailable { reason }],
+                    )
+                    .await;
+
+                    restart_delay::<Self>(delay).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut SessionState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let conn = match zbus::Connection::system().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                return Unavailable(format!("could not connect to the system bus: {err}")).into();
+            }
+        };
+        let manager = match ManagerProxy::new(&conn).await {
+            Ok(manager) => manager,
+            Err(err) => return Unavailable(format!("could not create proxy: {err}")).into(),
+        };
+        let session = match SessionProxy::new(&conn).await {
+            Ok(session) => session,
+            Err(err) => return Unavailable(format!("could not create proxy: {err}")).into(),
+        };
+
+        // fails when there's no logind, like on systems without systemd or
+        // elogind
+        let capabilities = match read_capabilities(&manager).await {
+            Ok(capabilities) => capabilities,
+            Err(err) => return Unavailable(err.to_string()).into(),
+        };
+
+        log::info!("[service:session] service started");
+        startup::ready("session");
+
+        Self::emit(
+            state,
+            chan,
+            vec![
+                Event::ServiceAvailable,
+                Event::CapabilitiesChanged { capabilities },
+            ],
+        )
+        .await;
+
+        loop {
+            match request_rx.recv_async().await {
+                Ok(ServiceRequest::Request { request }) => {
+                    log::info!("[service:session] asking logind to {request}");
+
+                    let result = match request {
+                        Request::Suspend => manager.suspend(true).await,
+                        Request::Hibernate => manager.hibernate(true).await,
+                        Request::Reboot => manager.reboot(true).await,
+                        Request::PowerOff => manager.power_off(true).await,
+                        Request::LockSession => session.lock().await,
+                    };
+
+                    // logind refusing, like when polkit said no, is up to
+                    // the user and not the service
+                    if let Err(err) = result {
+                        log::warn!("[service:session] could not {request}: {err}");
+                    }
+                }
+                Ok(ServiceRequest::SubscribeModule { id, data: () }) => {
+                    let events = vec![SessionEventType::CapabilitiesChanged];
+                    let diff = module_ids.register_module(id.clone(), events);
+                    log::debug!("[service:session] {id:?} registered, {diff}");
+                }
+                Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                    module_ids.unregister_module(id);
+                }
+                Err(err) => {
+                    return anyhow!("[service:session] error receiving request: {err}");
+                }
+            }
+        }
+    }
+}
+
+impl SessionService {
+    /// `services::emit`, keeping `CAPABILITIES` up to date for modules
+    async fn emit(
+        state: &mut SessionState,
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        events: Vec<Event>,
+    ) {
+        emit(state, chan, events).await;
+
+        let capabilities = match state.unavailable {
+            Some(_) => 0,
+            None => state.capabilities.bits(),
+        };
+        CAPABILITIES.store(capabilities, Ordering::Relaxed);
+    }
+}
+
+/// asks logind what the system can do, `challenge` is counted as can since
+/// polkit can ask for a password
+async fn read_capabilities(manager: &ManagerProxy<'_>) -> zbus::Result<Capabilities> {
+    let can = |answer: String| matches!(answer.as_str(), "yes" | "challenge");
+
+    return Ok(Capabilities {
+        suspend: can(manager.can_suspend().await?),
+        hibernate: can(manager.can_hibernate().await?),
+        reboot: can(manager.can_reboot().await?),
+        power_off: can(manager.can_power_off().await?),
+    });
+}
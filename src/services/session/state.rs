@@ -0,0 +1,38 @@
+use super::data::Capabilities;
+use super::{Event, SessionService};
+
+use crate::services::ServiceState;
+
+#[derive(Debug)]
+pub struct SessionState {
+    pub capabilities: Capabilities,
+
+    /// why logind can't be reached, `None` when it can
+    pub unavailable: Option<String>,
+}
+
+impl ServiceState<SessionService> for SessionState {
+    fn init() -> Self {
+        Self {
+            capabilities: Capabilities::default(),
+            unavailable: None,
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match event.clone() {
+            Event::CapabilitiesChanged { capabilities } => {
+                self.capabilities = capabilities;
+            }
+            Event::ServiceUnavailable { reason } => {
+                *self = Self::init();
+                self.unavailable = Some(reason);
+            }
+            Event::ServiceAvailable => {
+                self.unavailable = None;
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+/// messages emitted from the sysinfo service each time it samples the system
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when the cpu usage since the last sample changed
+    CpuUsageChanged { usage: CpuUsage },
+    /// event emitted when the memory or swap in use changed
+    MemoryChanged { memory: Memory },
+    /// event emitted when the space used on a mounted disk changed
+    DiskChanged { disks: Vec<Disk> },
+    /// event emitted when a temperature sensor's reading changed
+    TemperatureChanged { sensors: Vec<Temperature> },
+}
+
+/// requests for the sysinfo service
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// samples the system right away instead of waiting for the interval
+    Refresh,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum SysinfoEventType {
+    CpuUsageChanged,
+    MemoryChanged,
+    DiskChanged,
+    TemperatureChanged,
+}
+
+impl Event {
+    /// the kind of event modules register for
+    pub fn event_type(&self) -> SysinfoEventType {
+        match self {
+            Self::CpuUsageChanged { .. } => SysinfoEventType::CpuUsageChanged,
+            Self::MemoryChanged { .. } => SysinfoEventType::MemoryChanged,
+            Self::DiskChanged { .. } => SysinfoEventType::DiskChanged,
+            Self::TemperatureChanged { .. } => SysinfoEventType::TemperatureChanged,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SysinfoSubscriptionData {
+    /// bitflags of the events the module wants
+    pub events: u8,
+    /// how often the module wants the system sampled, 0 for the default
+    pub interval_ms: u64,
+}
+
+impl SysinfoSubscriptionData {
+    /// subscribes to the cpu usage changing
+    pub const CPU_USAGE_CHANGED: u8 = 0b_0000_0001;
+    /// subscribes to the space used on disks changing
+    pub const DISK_CHANGED: u8 = 0b_0000_0100;
+    /// subscribes to the memory in use changing
+    pub const MEMORY_CHANGED: u8 = 0b_0000_0010;
+    /// subscribes to temperatures changing
+    pub const TEMPERATURE_CHANGED: u8 = 0b_0000_1000;
+
+    pub fn is_set(&self, event: u8) -> bool {
+        return self.events & event != 0;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// types used for events
+
+/// how busy the cpu was between two samples, in percent
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CpuUsage {
+    /// across all cores
+    pub total: u8,
+    /// each core, in the order the kernel numbers them
+    pub cores: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Memory {
+    pub total_bytes: u64,
+    /// what can be used without swapping, which counts caches that can be
+    /// dropped
+    pub available_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_free_bytes: u64,
+}
+
+/// a mounted block device
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Disk {
+    /// where it's mounted, the first mount if it's mounted more than once
+    pub mount: PathBuf,
+    pub total_bytes: u64,
+    /// what can be used by the user, which leaves out the space reserved
+    /// for root
+    pub available_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Temperature {
+    /// the chip and the sensor's label, like `coretemp Package id 0`
+    pub name: String,
+    pub millidegrees: i32,
+}
@@ -0,0 +1,231 @@
+//! samples the cpu, memory, disks and temperatures from `/proc` and `/sys`,
+//! so bar modules can show system monitors without reading files themselves
+//!
+//! the system is sampled as often as the module that asked for the shortest
+//! interval wants
+
+mod data;
+mod proc;
+mod se;
+mod state;
+
+pub use data::{CpuUsage, Disk, Event, Memory, Request, SysinfoSubscriptionData, Temperature};
+pub use state::SysinfoState;
+
+use data::SysinfoEventType;
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::runtime::RuntimeModuleId;
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::time::{Interval, MissedTickBehavior};
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// how often the system is sampled when no module asked for an interval
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+/// modules can't have the system sampled more often than this
+const MIN_INTERVAL: Duration = Duration::from_millis(500);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct SysinfoService;
+
+impl Service for SysinfoService {
+    type Event = Event;
+    type EventType = SysinfoEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = SysinfoState;
+    type SubscriptionData = SysinfoSubscriptionData;
+
+    const ID: u16 = 10;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.sysinfo.events", chan);
+                let mut module_ids = ModuleIds::new();
+
+                loop {
+                    let mut state = SysinfoState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.sysinfo.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:sysinfo] could not send init event: {}", err);
+                        log::error!("[service:sysinfo] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:sysinfo] restarting");
+                        continue;
+                    }
+                    log::error!("[service:sysinfo] error: {err}");
+                    restart_delay::<Self>(Duration::from_secs(5)).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut SysinfoState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        // the interval each module asked for, the shortest one is used
+        let mut intervals: HashMap<RuntimeModuleId, Duration> = HashMap::new();
+        let mut period = DEFAULT_INTERVAL;
+        let mut ticker = sample_interval(period);
+
+        // cpu usage is worked out from the difference to the last sample
+        let mut cpu_times = match proc::read_cpu_times().await {
+            Ok(times) => times,
+            Err(err) => return anyhow!("[service:sysinfo] could not read cpu times: {err}"),
+        };
+
+        log::info!("[service:sysinfo] service started");
+        startup::ready("sysinfo");
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => match request {
+                            Request::Refresh => ticker.reset_immediately(),
+                        },
+                        Ok(ServiceRequest::SubscribeModule { id, data }) => {
+                            let mut events = vec![];
+
+                            if data.is_set(SysinfoSubscriptionData::CPU_USAGE_CHANGED) {
+                                events.push(SysinfoEventType::CpuUsageChanged);
+                            }
+                            if data.is_set(SysinfoSubscriptionData::MEMORY_CHANGED) {
+                                events.push(SysinfoEventType::MemoryChanged);
+                            }
+                            if data.is_set(SysinfoSubscriptionData::DISK_CHANGED) {
+                                events.push(SysinfoEventType::DiskChanged);
+                            }
+                            if data.is_set(SysinfoSubscriptionData::TEMPERATURE_CHANGED) {
+                                events.push(SysinfoEventType::TemperatureChanged);
+                            }
+
+                            // registering again replaces the module's interval,
+                            // so a module that stopped asking for one loses it
+                            match data.interval_ms {
+                                0 => intervals.remove(&id),
+                                ms => intervals.insert(id.clone(), Duration::from_millis(ms)),
+                            };
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:sysinfo] {id:?} registered, {diff}");
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            intervals.remove(&id);
+                            module_ids.unregister_module(id);
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:sysinfo] error receiving request: {err}");
+                        }
+                    }
+
+                    let shortest = intervals
+                        .values()
+                        .min()
+                        .map_or(DEFAULT_INTERVAL, |shortest| (*shortest).max(MIN_INTERVAL));
+                    if shortest != period {
+                        log::debug!(
+                            "[service:sysinfo] sampling every {}",
+                            humantime::format_duration(shortest)
+                        );
+                        period = shortest;
+                        ticker = sample_interval(period);
+                    }
+
+                    continue;
+                }
+            };
+
+            let events = Self::sample(&mut cpu_times).await;
+            emit(state, chan, events).await;
+        }
+    }
+}
+
+impl SysinfoService {
+    /// reads everything once, what couldn't be read is logged and left out
+    async fn sample(cpu_times: &mut Vec<proc::CpuTimes>) -> Vec<Event> {
+        let mut events = vec![];
+
+        match proc::read_cpu_times().await {
+            Ok(times) => {
+                events.push(Event::CpuUsageChanged {
+                    usage: proc::cpu_usage(cpu_times, &times),
+                });
+                *cpu_times = times;
+            }
+            Err(err) => log::warn!("[service:sysinfo] could not read cpu times: {err}"),
+        }
+
+        match proc::read_memory().await {
+            Ok(memory) => events.push(Event::MemoryChanged { memory }),
+            Err(err) => log::warn!("[service:sysinfo] could not read memory: {err}"),
+        }
+
+        match proc::read_disks().await {
+            Ok(disks) => events.push(Event::DiskChanged { disks }),
+            Err(err) => log::warn!("[service:sysinfo] could not read disks: {err}"),
+        }
+
+        // machines without sensors (like most vms) have no hwmon chips
+        match proc::read_temperatures().await {
+            Ok(sensors) => events.push(Event::TemperatureChanged { sensors }),
+            Err(err) => log::debug!("[service:sysinfo] could not read temperatures: {err}"),
+        }
+
+        return events;
+    }
+}
+
+/// ticks every `period`, starting right away
+fn sample_interval(period: Duration) -> Interval {
+    let mut ticker = tokio::time::interval(period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    return ticker;
+}
@@ -0,0 +1,238 @@
+//! reads the system's state from `/proc` and `/sys`
+
+use super::data::{CpuUsage, Disk, Memory, Temperature};
+
+use std::collections::HashSet;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+/// the time a cpu has spent busy and in total, in clock ticks since boot
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+/// the times for all cores together, then for each core
+pub async fn read_cpu_times() -> anyhow::Result<Vec<CpuTimes>> {
+    let stat = tokio::fs::read_to_string("/proc/stat").await?;
+
+    let times = stat
+        .lines()
+        .filter(|line| line.starts_with("cpu"))
+        .map(|line| {
+            // user nice system idle iowait irq softirq steal, the guest times
+            // after them are already counted in user and nice
+            let fields = line
+                .split_whitespace()
+                .skip(1)
+                .take(8)
+                .map(|field| field.parse::<u64>().unwrap_or(0))
+                .collect::<Vec<u64>>();
+
+            let total = fields.iter().sum::<u64>();
+            let idle = fields.get(3).unwrap_or(&0) + fields.get(4).unwrap_or(&0);
+
+            CpuTimes {
+                busy: total.saturating_sub(idle),
+                total,
+            }
+        })
+        .collect::<Vec<CpuTimes>>();
+
+    if times.is_empty() {
+        return Err(anyhow!("/proc/stat has no cpu lines"));
+    }
+
+    return Ok(times);
+}
+
+/// the usage between two reads of `read_cpu_times`
+pub fn cpu_usage(last: &[CpuTimes], now: &[CpuTimes]) -> CpuUsage {
+    let percent = |last: &CpuTimes, now: &CpuTimes| {
+        let total = now.total.saturating_sub(last.total);
+        if total == 0 {
+            return 0;
+        }
+
+        let busy = now.busy.saturating_sub(last.busy);
+        return (busy * 100 / total).min(100) as u8;
+    };
+
+    let mut usage = now
+        .iter()
+        .zip(last.iter())
+        .map(|(now, last)| percent(last, now));
+
+    CpuUsage {
+        total: usage.next().unwrap_or(0),
+        cores: usage.collect(),
+    }
+}
+
+pub async fn read_memory() -> anyhow::Result<Memory> {
+    let meminfo = tokio::fs::read_to_string("/proc/meminfo").await?;
+
+    let mut memory = Memory::default();
+    for line in meminfo.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(key), Some(value)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        // the values are in kibibytes
+        let bytes = value.parse::<u64>().unwrap_or(0) * 1024;
+
+        match key {
+            "MemTotal:" => memory.total_bytes = bytes,
+            "MemAvailable:" => memory.available_bytes = bytes,
+            "SwapTotal:" => memory.swap_total_bytes = bytes,
+            "SwapFree:" => memory.swap_free_bytes = bytes,
+            _ => {}
+        }
+    }
+
+    return Ok(memory);
+}
+
+/// the mounted block devices, leaving out pseudo filesystems like `tmpfs`
+pub async fn read_disks() -> anyhow::Result<Vec<Disk>> {
+    let mounts = tokio::fs::read_to_string("/proc/self/mounts").await?;
+
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut disks = vec![];
+
+    for line in mounts.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(device), Some(mount)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        // a device mounted more than once (like btrfs subvolumes) is only
+        // counted at its first mount
+        if !device.starts_with("/dev/") || !seen.insert(device) {
+            continue;
+        }
+
+        let mount = PathBuf::from(unescape_mount(mount));
+        match statvfs(&mount) {
+            Ok((total_bytes, available_bytes)) => disks.push(Disk {
+                mount,
+                total_bytes,
+                available_bytes,
+            }),
+            Err(err) => {
+                log::debug!(
+                    "[service:sysinfo] could not stat {}: {err}",
+                    mount.display()
+                );
+            }
+        }
+    }
+
+    return Ok(disks);
+}
+
+/// the total and available bytes of the filesystem at `path`
+fn statvfs(path: &Path) -> anyhow::Result<(u64, u64)> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let block_size = stat.f_frsize as u64;
+    return Ok((
+        stat.f_blocks as u64 * block_size,
+        stat.f_bavail as u64 * block_size,
+    ));
+}
+
+/// spaces and other whitespace in mount points are written as octal escapes,
+/// like `\040`
+fn unescape_mount(mount: &str) -> String {
+    let mut unescaped = String::with_capacity(mount.len());
+    let mut chars = mount.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
+
+        let code = chars.by_ref().take(3).collect::<String>();
+        match u8::from_str_radix(&code, 8) {
+            Ok(byte) => unescaped.push(byte as char),
+            Err(_) => {
+                unescaped.push('\\');
+                unescaped.push_str(&code);
+            }
+        }
+    }
+
+    return unescaped;
+}
+
+/// every `temp*_input` of every hwmon chip
+pub async fn read_temperatures() -> anyhow::Result<Vec<Temperature>> {
+    let mut entries = tokio::fs::read_dir("/sys/class/hwmon").await?;
+    let mut chips = vec![];
+    while let Some(chip) = entries.next_entry().await? {
+        chips.push(chip.path());
+    }
+    // directories are listed in any order, but readings are compared with
+    // the last sample's so the order has to stay the same
+    chips.sort();
+
+    let mut temperatures = vec![];
+    for chip in chips {
+        let chip_name = read_trimmed(&chip.join("name")).await.unwrap_or_default();
+
+        let mut files = tokio::fs::read_dir(&chip).await?;
+        let mut inputs = vec![];
+        while let Some(file) = files.next_entry().await? {
+            let file_name = file.file_name().to_string_lossy().to_string();
+            if let Some(sensor) = file_name.strip_suffix("_input")
+                && sensor.starts_with("temp")
+            {
+                inputs.push(sensor.to_string());
+            }
+        }
+        // `temp10` sorts before `temp2`, but like the chips it only has to
+        // stay the same
+        inputs.sort();
+
+        for sensor in inputs {
+            let millidegrees = match read_trimmed(&chip.join(format!("{sensor}_input"))).await {
+                Some(value) => match value.parse::<i32>() {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                },
+                // sensors that are powered off can't be read
+                None => continue,
+            };
+
+            let label = read_trimmed(&chip.join(format!("{sensor}_label")))
+                .await
+                .unwrap_or(sensor);
+
+            temperatures.push(Temperature {
+                name: format!("{chip_name} {label}"),
+                millidegrees,
+            });
+        }
+    }
+
+    return Ok(temperatures);
+}
+
+async fn read_trimmed(path: &Path) -> Option<String> {
+    tokio::fs::read_to_string(path)
+        .await
+        .ok()
+        .map(|value| value.trim().to_string())
+}
@@ -0,0 +1,150 @@
+use super::{
+    CpuUsage, Disk, Event, Memory, SysinfoEventType, SysinfoService, SysinfoSubscriptionData,
+    Temperature,
+};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+/// the layout modules get sysinfo events in through `service_event`, a u8
+/// for the kind of event then the new reading, everything is little endian
+/// and lists start with a u16 for their length
+///
+/// - 0 the cpu usage, a u8 for the total in percent then the list of each
+///   core's as u8s
+/// - 1 the memory, the total, available, swap total and free swap bytes as
+///   u64s
+/// - 2 the disks, each is where it's mounted as a u16 length then the bytes,
+///   then the total and available bytes as u64s
+/// - 3 the temperatures, each is the name as a u16 length then the bytes,
+///   then the millidegrees celsius as an i32
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        SysinfoService::ID
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        let mut bytes = vec![];
+
+        match self {
+            Event::CpuUsageChanged {
+                usage: CpuUsage { total, cores },
+            } => {
+                bytes.push(0);
+                bytes.push(*total);
+                let cores = &cores[..cores.len().min(u16::MAX as usize)];
+                bytes.extend((cores.len() as u16).to_le_bytes());
+                bytes.extend(cores);
+            }
+            Event::MemoryChanged { memory } => {
+                let Memory {
+                    total_bytes,
+                    available_bytes,
+                    swap_total_bytes,
+                    swap_free_bytes,
+                } = memory;
+                bytes.push(1);
+                for number in [
+                    total_bytes,
+                    available_bytes,
+                    swap_total_bytes,
+                    swap_free_bytes,
+                ] {
+                    bytes.extend(number.to_le_bytes());
+                }
+            }
+            Event::DiskChanged { disks } => {
+                bytes.push(2);
+                let disks = &disks[..disks.len().min(u16::MAX as usize)];
+                bytes.extend((disks.len() as u16).to_le_bytes());
+                for Disk {
+                    mount,
+                    total_bytes,
+                    available_bytes,
+                } in disks
+                {
+                    push_str(&mut bytes, &mount.to_string_lossy());
+                    bytes.extend(total_bytes.to_le_bytes());
+                    bytes.extend(available_bytes.to_le_bytes());
+                }
+            }
+            Event::TemperatureChanged { sensors } => {
+                bytes.push(3);
+                let sensors = &sensors[..sensors.len().min(u16::MAX as usize)];
+                bytes.extend((sensors.len() as u16).to_le_bytes());
+                for Temperature { name, millidegrees } in sensors {
+                    push_str(&mut bytes, name);
+                    bytes.extend(millidegrees.to_le_bytes());
+                }
+            }
+        }
+
+        return Some(bytes);
+    }
+
+    fn is_for(&self, register: &SubscriptionData) -> bool {
+        let SubscriptionData::Sysinfo { data } = register else {
+            return false;
+        };
+
+        return data.is_set(match self.event_type() {
+            SysinfoEventType::CpuUsageChanged => SysinfoSubscriptionData::CPU_USAGE_CHANGED,
+            SysinfoEventType::MemoryChanged => SysinfoSubscriptionData::MEMORY_CHANGED,
+            SysinfoEventType::DiskChanged => SysinfoSubscriptionData::DISK_CHANGED,
+            SysinfoEventType::TemperatureChanged => SysinfoSubscriptionData::TEMPERATURE_CHANGED,
+        });
+    }
+}
+
+fn push_str(bytes: &mut Vec<u8>, string: &str) {
+    let string = &string.as_bytes()[..string.len().min(u16::MAX as usize)];
+    bytes.extend((string.len() as u16).to_le_bytes());
+    bytes.extend(string);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registered(events: u8) -> SubscriptionData {
+        return SubscriptionData::Sysinfo {
+            data: SysinfoSubscriptionData {
+                events,
+                interval_ms: 0,
+            },
+        };
+    }
+
+    #[test]
+    fn registered_module_gets_the_event() {
+        let event = Event::CpuUsageChanged {
+            usage: CpuUsage {
+                total: 42,
+                cores: vec![40, 44],
+            },
+        };
+
+        assert!(event.is_for(&registered(
+            SysinfoSubscriptionData::CPU_USAGE_CHANGED | SysinfoSubscriptionData::MEMORY_CHANGED
+        )));
+        assert!(!event.is_for(&registered(SysinfoSubscriptionData::MEMORY_CHANGED)));
+        assert!(!event.is_for(&SubscriptionData::Weather));
+
+        assert_eq!(event.serialize().unwrap(), [0, 42, 2, 0, 40, 44]);
+    }
+
+    #[test]
+    fn serializes_temperatures() {
+        let event = Event::TemperatureChanged {
+            sensors: vec![Temperature {
+                name: "cpu".to_string(),
+                millidegrees: -1500,
+            }],
+        };
+
+        assert_eq!(
+            event.serialize().unwrap(),
+            [&[3, 1, 0, 3, 0][..], b"cpu", &(-1500i32).to_le_bytes()].concat()
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use super::data::{CpuUsage, Disk, Memory, SysinfoEventType, Temperature};
+use super::{Event, SysinfoService};
+
+use crate::services::{Dedup, ServiceState};
+
+#[derive(Debug)]
+pub struct SysinfoState {
+    pub cpu: CpuUsage,
+    pub memory: Memory,
+    pub disks: Vec<Disk>,
+    pub temperatures: Vec<Temperature>,
+
+    /// everything is sampled on each tick, so readings that didn't change
+    /// are dropped
+    dedup: Dedup<SysinfoEventType>,
+}
+
+impl ServiceState<SysinfoService> for SysinfoState {
+    fn init() -> Self {
+        Self {
+            cpu: CpuUsage::default(),
+            memory: Memory::default(),
+            disks: vec![],
+            temperatures: vec![],
+            dedup: Dedup::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        if !self.dedup.is_new(event.event_type(), &event) {
+            return vec![];
+        }
+
+        match event.clone() {
+            Event::CpuUsageChanged { usage } => {
+                self.cpu = usage;
+            }
+            Event::MemoryChanged { memory } => {
+                self.memory = memory;
+            }
+            Event::DiskChanged { disks } => {
+                self.disks = disks;
+            }
+            Event::TemperatureChanged { sensors } => {
+                self.temperatures = sensors;
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -0,0 +1,122 @@
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use iced::widget::image;
+
+/// messages emitted from the tray service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// event emitted when an item is added, removed or changes its icon,
+    /// title or status, with every item in the order they were added
+    ItemsChanged { items: Vec<TrayItem> },
+
+    /// event emitted when the session bus can't be reached, or the
+    /// connection to it was lost
+    ServiceUnavailable { reason: String },
+    /// event emitted when the service connected to the session bus
+    ServiceAvailable,
+}
+
+/// requests for the tray service
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// passes a click on an item's icon on to the app that owns it
+    Click { item: String, click: TrayClick },
+}
+
+/// an icon in the tray, see the `StatusNotifierItem` spec
+#[derive(Debug, Clone, Hash)]
+pub struct TrayItem {
+    /// where the item is on the bus, like `:1.42/StatusNotifierItem`
+    pub address: String,
+    /// the name of the app the item is for
+    pub id: String,
+    pub title: String,
+    pub status: TrayStatus,
+    /// `None` when the item has no icon or it couldn't be found
+    pub icon: Option<TrayIcon>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrayStatus {
+    /// the app isn't doing anything worth showing, the item is hidden
+    Passive,
+    Active,
+    /// the app wants the user to look at it
+    NeedsAttention,
+}
+
+impl TrayStatus {
+    pub fn from_sni(status: &str) -> Self {
+        match status {
+            "Passive" => Self::Passive,
+            "NeedsAttention" => Self::NeedsAttention,
+            _ => Self::Active,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TrayIcon {
+    /// an icon file found from the icon name the item gave
+    File(PathBuf),
+    /// pixels the item sent itself, already turned into rgba
+    Pixmap(image::Handle),
+}
+
+impl Hash for TrayIcon {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            Self::File(path) => path.hash(state),
+            Self::Pixmap(handle) => handle.id().hash(state),
+        }
+    }
+}
+
+/// how an item's icon was clicked, sent to the module as the callback's data
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrayClick {
+    /// a left click, usually opens the app's window
+    Activate = 0,
+    /// a right click, the app shows its menu
+    ContextMenu = 1,
+    /// a middle click
+    SecondaryActivate = 2,
+}
+
+/// the items that are shown, shared with the ui without copying them
+#[derive(Debug, Clone, Default)]
+pub struct TrayItems {
+    /// changes whenever `items` does, so cached widgets know to rebuild
+    pub revision: u64,
+    pub items: Arc<Vec<TrayItem>>,
+}
+
+impl TrayItems {
+    /// the item in a module's tray slot
+    pub fn get(&self, slot: u32) -> Option<&TrayItem> {
+        self.items.get(slot as usize)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum TrayEventType {
+    ItemsChanged,
+}
+
+impl Event {
+    /// the kind of event, `None` for the events about the service itself
+    pub fn event_type(&self) -> Option<TrayEventType> {
+        match self {
+            Self::ItemsChanged { .. } => Some(TrayEventType::ItemsChanged),
+            Self::ServiceUnavailable { .. } | Self::ServiceAvailable => None,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! finds the icon to show for an item, from its icon name or its pixmaps
+//!
+//! icon names are looked up in the theme path the item gave, then in the
+//! icon themes through `crate::icons`
+
+use super::data::TrayIcon;
+
+use crate::icons;
+
+use std::path::PathBuf;
+
+use iced::widget::image;
+
+/// the size pixmaps are picked for, the closest one at or above it is used
+const PIXMAP_SIZE: i32 = 22;
+
+/// the `hicolor` sizes that are looked in under an item's theme path, best
+/// first
+const SIZES: [&str; 9] = [
+    "scalable", "22x22", "24x24", "32x32", "48x48", "16x16", "64x64", "128x128", "256x256",
+];
+
+/// the icon for an item, from its name if it can be found and its pixmaps
+/// otherwise
+pub fn load(name: &str, theme_path: &str, pixmaps: Vec<(i32, i32, Vec<u8>)>) -> Option<TrayIcon> {
+    if !name.is_empty()
+        && let Some(path) = find(name, theme_path)
+    {
+        return Some(TrayIcon::File(path));
+    }
+
+    return pixmap(pixmaps).map(TrayIcon::Pixmap);
+}
+
+/// looks for the icon file called `name` in the item's theme path, then in
+/// the icon themes
+fn find(name: &str, theme_path: &str) -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = vec![];
+
+    if !theme_path.is_empty() {
+        let theme_path = PathBuf::from(theme_path);
+        dirs.push(theme_path.clone());
+        dirs.extend(
+            SIZES
+                .iter()
+                .map(|size| theme_path.join("hicolor").join(size).join("apps")),
+        );
+    }
+
+    return icons::find(name, PIXMAP_SIZE as u32, &dirs);
+}
+
+/// picks the pixmap closest to `PIXMAP_SIZE` and turns its argb pixels into
+/// rgba
+fn pixmap(pixmaps: Vec<(i32, i32, Vec<u8>)>) -> Option<image::Handle> {
+    let (width, height, argb) = pixmaps
+        .into_iter()
+        .filter(|(width, height, pixels)| {
+            *width > 0 && *height > 0 && pixels.len() == (*width * *height * 4) as usize
+        })
+        .min_by_key(|(width, _, _)| match *width >= PIXMAP_SIZE {
+            true => (0, *width - PIXMAP_SIZE),
+            false => (1, PIXMAP_SIZE - *width),
+        })?;
+
+    let rgba = argb
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[1], pixel[2], pixel[3], pixel[0]])
+        .collect::<Vec<u8>>();
+
+    return Some(image::Handle::from_rgba(width as u32, height as u32, rgba));
+}
@@ -0,0 +1,489 @@
+//! shows the icons apps put in the system tray, over the `StatusNotifierItem`
+//! dbus protocol
+//!
+//! the shell serves the `StatusNotifierWatcher` that items register with,
+//! unless another program on the session already does, in which case the
+//! items are listed from that one instead
+
+mod data;
+mod icon;
+mod sni;
+mod state;
+
+pub use data::{Event, Request, TrayClick, TrayIcon, TrayItem, TrayItems, TrayStatus};
+pub use state::TrayState;
+
+use data::TrayEventType;
+use sni::{
+    StatusNotifierItemProxy, StatusNotifierWatcherProxy, WATCHER_NAME, WATCHER_PATH, Watcher,
+    split_address,
+};
+
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState, emit,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use tokio::task::JoinHandle;
+use zbus::fdo::{DBusProxy, RequestNameFlags};
+use zbus::proxy::CacheProperties;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// the first wait before trying to reach the session bus again after it
+/// couldn't be found, doubles each failed attempt
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(5);
+/// the longest wait between attempts to reach the session bus
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct TrayService;
+
+/// returned from `TrayService::run` when the session bus or a watcher
+/// couldn't be reached, so the service waits longer before trying again
+#[derive(Debug)]
+struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tray unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+/// an item was read, or is gone when it's `None`
+type ItemUpdate = (String, Option<TrayItem>);
+
+/// the tasks watching each item, stopped when the service stops
+#[derive(Debug, Default)]
+struct ItemTasks(HashMap<String, JoinHandle<()>>);
+
+impl Drop for ItemTasks {
+    fn drop(&mut self) {
+        for task in self.0.values() {
+            task.abort();
+        }
+    }
+}
+
+impl Service for TrayService {
+    type Event = Event;
+    type EventType = TrayEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = TrayState;
+    /// the tray is drawn by the host, so modules don't register for it
+    type SubscriptionData = ();
+
+    const ID: u16 = 8;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.tray.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut backoff = RETRY_BACKOFF_MIN;
+
+                loop {
+                    let mut state = TrayState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.tray.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:tray] could not send init event: {}", err);
+                        log::error!("[service:tray] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:tray] restarting");
+                        backoff = RETRY_BACKOFF_MIN;
+                        continue;
+                    }
+
+                    let (reason, delay) = match err.downcast_ref::<Unavailable>() {
+                        Some(Unavailable(reason)) => {
+                            log::warn!(
+                                "[service:tray] {err}, retrying in {}",
+                                humantime::format_duration(backoff)
+                            );
+
+                            let delay = backoff;
+                            backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                            (reason.clone(), delay)
+                        }
+                        None => {
+                            log::error!("[service:tray] error: {err}");
+                            log::error!("[service:tray] restarting in 5 seconds...");
+
+                            backoff = RETRY_BACKOFF_MIN;
+                            (err.to_string(), Duration::from_secs(5))
+                        }
+                    };
+
+                    emit(
+                        &mut state,
+                        &mut chan,
+                        vec![Event::ServiceUnavailable { reason }],
+                    )
+                    .await;
+
+                    restart_delay::<Self>(delay).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut TrayState,
+        _module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let builder = zbus::connection::Builder::session()
+            .and_then(|builder| builder.serve_at(WATCHER_PATH, Watcher::default()));
+        let conn = match builder {
+            Ok(builder) => match builder.build().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    return Unavailable(format!("could not connect to the session bus: {err}"))
+                        .into();
+                }
+            },
+            Err(err) => {
+                return Unavailable(format!("could not connect to the session bus: {err}")).into();
+            }
+        };
+
+        // another bar or desktop could already be the watcher
+        let serving = match conn
+            .request_name_with_flags(WATCHER_NAME, RequestNameFlags::DoNotQueue.into())
+            .await
+        {
+            Ok(_) => true,
+            Err(err) => {
+                log::info!("[service:tray] using the running tray watcher ({err})");
+                false
+            }
+        };
+
+        let host = format!("org.kde.StatusNotifierHost-{}-1", std::process::id());
+        if let Err(err) = conn.request_name(host.as_str()).await {
+            return anyhow!("[service:tray] could not take the name {host}: {err}");
+        }
+
+        let (watcher, dbus) = match tokio::try_join!(
+            StatusNotifierWatcherProxy::new(&conn),
+            DBusProxy::new(&conn)
+        ) {
+            Ok(proxies) => proxies,
+            Err(err) => return Unavailable(format!("could not create proxies: {err}")).into(),
+        };
+
+        // fails when nothing is serving the watcher
+        if let Err(err) = watcher.register_status_notifier_host(&host).await {
+            return Unavailable(format!("could not register with the tray watcher: {err}")).into();
+        }
+
+        let (mut registered, mut unregistered, mut owner_changed) = match tokio::try_join!(
+            watcher.receive_status_notifier_item_registered(),
+            watcher.receive_status_notifier_item_unregistered(),
+            dbus.receive_name_owner_changed(),
+        ) {
+            Ok(streams) => streams,
+            Err(err) => return anyhow!("[service:tray] could not listen for items: {err}"),
+        };
+
+        let addresses = match watcher.registered_status_notifier_items().await {
+            Ok(addresses) => addresses,
+            Err(err) => return anyhow!("[service:tray] could not list the items: {err}"),
+        };
+
+        log::info!("[service:tray] service started");
+        startup::ready("tray");
+
+        let (update_tx, update_rx) =
+            instrumented::bounded::<ItemUpdate>("service.tray.internal_events", CHANNEL_CAPACITY);
+
+        // items are kept in the order they registered
+        let mut order: Vec<String> = vec![];
+        let mut items: HashMap<String, TrayItem> = HashMap::new();
+        let mut tasks = ItemTasks::default();
+
+        for address in addresses {
+            Self::add_item(&conn, &update_tx, &mut tasks, &mut order, address);
+        }
+
+        emit(state, chan, vec![Event::ServiceAvailable]).await;
+
+        loop {
+            let changed = tokio::select! {
+                Some(signal) = registered.next() => match signal.args() {
+                    Ok(args) => {
+                        let address = args.service().clone();
+                        Self::add_item(&conn, &update_tx, &mut tasks, &mut order, address);
+                        false
+                    }
+                    Err(err) => {
+                        log::warn!("[service:tray] could not read a registered item: {err}");
+                        false
+                    }
+                },
+                Some(signal) = unregistered.next() => match signal.args() {
+                    Ok(args) => {
+                        Self::remove_item(&mut tasks, &mut order, &mut items, args.service())
+                    }
+                    Err(err) => {
+                        log::warn!("[service:tray] could not read an unregistered item: {err}");
+                        false
+                    }
+                },
+                Some(signal) = owner_changed.next(), if serving => {
+                    if let Ok(args) = signal.args()
+                        && args.new_owner().is_none()
+                        && let Err(err) = Self::owner_left(&conn, args.name().as_str()).await
+                    {
+                        log::warn!("[service:tray] could not remove the items of a program: {err}");
+                    }
+                    false
+                }
+                update = update_rx.recv_async() => match update {
+                    Ok((address, Some(item))) => {
+                        // the item could have unregistered while it was read
+                        if order.contains(&address) {
+                            items.insert(address, item);
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                    Ok((address, None)) => {
+                        Self::remove_item(&mut tasks, &mut order, &mut items, &address)
+                    }
+                    Err(err) => {
+                        return anyhow!("[service:tray] error receiving item updates: {err}");
+                    }
+                },
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => {
+                            if let Err(err) = Self::request(&conn, request.clone()).await {
+                                log::warn!("[service:tray] could not handle {request:?}: {err}");
+                            }
+                        }
+                        // the tray is drawn by the host, there's nothing to register for
+                        Ok(ServiceRequest::SubscribeModule { .. })
+                        | Ok(ServiceRequest::UnsubscribeModule { .. }) => {}
+                        Err(err) => {
+                            return anyhow!("[service:tray] error receiving request: {err}");
+                        }
+                    }
+                    false
+                }
+            };
+
+            if changed {
+                let items = order
+                    .iter()
+                    .filter_map(|address| items.get(address).cloned())
+                    .collect();
+
+                emit(state, chan, vec![Event::ItemsChanged { items }]).await;
+            }
+        }
+    }
+}
+
+impl TrayService {
+    /// starts watching an item, it's shown once it has been read
+    fn add_item(
+        conn: &zbus::Connection,
+        update_tx: &InstrumentedSender<flume::Sender<ItemUpdate>>,
+        tasks: &mut ItemTasks,
+        order: &mut Vec<String>,
+        address: String,
+    ) {
+        if tasks.0.contains_key(&address) {
+            return;
+        }
+
+        log::debug!("[service:tray] watching item {address}");
+
+        let task = tokio::spawn(watch_item(conn.clone(), address.clone(), update_tx.clone()));
+        tasks.0.insert(address.clone(), task);
+        order.push(address);
+    }
+
+    /// stops watching an item, returns whether it was being shown
+    fn remove_item(
+        tasks: &mut ItemTasks,
+        order: &mut Vec<String>,
+        items: &mut HashMap<String, TrayItem>,
+        address: &str,
+    ) -> bool {
+        if let Some(task) = tasks.0.remove(address) {
+            task.abort();
+        }
+        order.retain(|other| other != address);
+
+        return items.remove(address).is_some();
+    }
+
+    /// unregisters the items of a program that left the bus from the
+    /// shell's watcher, which tells the service through the unregistered
+    /// signal
+    async fn owner_left(conn: &zbus::Connection, name: &str) -> zbus::Result<()> {
+        let watcher = conn
+            .object_server()
+            .interface::<_, Watcher>(WATCHER_PATH)
+            .await?;
+
+        let removed = watcher.get_mut().await.remove_owner(name);
+        for address in removed {
+            Watcher::status_notifier_item_unregistered(watcher.signal_emitter(), &address).await?;
+        }
+
+        return Ok(());
+    }
+
+    async fn request(conn: &zbus::Connection, request: Request) -> anyhow::Result<()> {
+        match request {
+            Request::Click { item, click } => {
+                let proxy = item_proxy(conn, &item).await?;
+
+                // the item picks where to show anything, the position of
+                // the click on screen isn't known
+                match click {
+                    TrayClick::Activate => proxy.activate(0, 0).await?,
+                    TrayClick::ContextMenu => proxy.context_menu(0, 0).await?,
+                    TrayClick::SecondaryActivate => proxy.secondary_activate(0, 0).await?,
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// items
+
+async fn item_proxy(
+    conn: &zbus::Connection,
+    address: &str,
+) -> zbus::Result<StatusNotifierItemProxy<'static>> {
+    let (destination, path) = split_address(address);
+
+    // the properties are read again on the item's own signals, which aren't
+    // the property changes the cache listens for
+    StatusNotifierItemProxy::builder(conn)
+        .destination(destination.to_string())?
+        .path(path.to_string())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+/// reads an item, and again whenever it says it changed, until it's gone
+async fn watch_item(
+    conn: zbus::Connection,
+    address: String,
+    update_tx: InstrumentedSender<flume::Sender<ItemUpdate>>,
+) {
+    let proxy = match item_proxy(&conn, &address).await {
+        Ok(proxy) => proxy,
+        Err(err) => {
+            log::warn!("[service:tray] could not reach item {address}: {err}");
+            let _ = update_tx.send_async((address, None)).await;
+            return;
+        }
+    };
+
+    let (mut new_icon, mut new_title, mut new_status) = match tokio::try_join!(
+        proxy.receive_new_icon(),
+        proxy.receive_new_title(),
+        proxy.receive_new_status(),
+    ) {
+        Ok(streams) => streams,
+        Err(err) => {
+            log::warn!("[service:tray] could not listen to item {address}: {err}");
+            let _ = update_tx.send_async((address, None)).await;
+            return;
+        }
+    };
+
+    loop {
+        let item = match read_item(&proxy, &address).await {
+            Ok(item) => Some(item),
+            Err(err) => {
+                log::debug!("[service:tray] item {address} is gone: {err}");
+                None
+            }
+        };
+
+        let gone = item.is_none();
+        if update_tx.send_async((address.clone(), item)).await.is_err() || gone {
+            return;
+        }
+
+        tokio::select! {
+            Some(_) = new_icon.next() => {}
+            Some(_) = new_title.next() => {}
+            Some(_) = new_status.next() => {}
+            else => return,
+        }
+    }
+}
+
+async fn read_item(proxy: &StatusNotifierItemProxy<'_>, address: &str) -> zbus::Result<TrayItem> {
+    // items often leave out properties, only a missing status means the item
+    // can't be read
+    let status = proxy.status().await?;
+
+    let icon_name = proxy.icon_name().await.unwrap_or_default();
+    let theme_path = proxy.icon_theme_path().await.unwrap_or_default();
+    let pixmaps = proxy.icon_pixmap().await.unwrap_or_default();
+
+    Ok(TrayItem {
+        address: address.to_string(),
+        id: proxy.id().await.unwrap_or_default(),
+        title: proxy.title().await.unwrap_or_default(),
+        status: TrayStatus::from_sni(&status),
+        icon: icon::load(&icon_name, &theme_path, pixmaps),
+    })
+}
@@ -0,0 +1,170 @@
+//! the `StatusNotifierWatcher` and `StatusNotifierItem` dbus interfaces
+//!
+//! see https://www.freedesktop.org/wiki/Specifications/StatusNotifierItem/
+
+use zbus::message::Header;
+use zbus::object_server::SignalEmitter;
+use zbus::{fdo, interface, proxy};
+
+/// where the watcher is served, for both ours and any other
+pub const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+pub const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+/// where an item is when it registers with only its bus name
+pub const DEFAULT_ITEM_PATH: &str = "/StatusNotifierItem";
+
+/// the watcher the shell serves when nothing else on the session does,
+/// items register with it and hosts (like the shell) list them from it
+#[derive(Debug, Default)]
+pub struct Watcher {
+    /// the addresses of the registered items, in the order they registered
+    items: Vec<String>,
+    hosts: Vec<String>,
+}
+
+impl Watcher {
+    /// forgets the items owned by a bus name that left the bus, returning
+    /// their addresses
+    pub fn remove_owner(&mut self, name: &str) -> Vec<String> {
+        let (removed, kept) = self
+            .items
+            .drain(..)
+            .partition(|address| split_address(address).0 == name);
+        self.items = kept;
+        self.hosts.retain(|host| host != name);
+
+        return removed;
+    }
+}
+
+#[interface(name = "org.kde.StatusNotifierWatcher")]
+impl Watcher {
+    /// `service` is either a bus name or the path of the item on the
+    /// sender's connection
+    async fn register_status_notifier_item(
+        &mut self,
+        service: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let address = match service.starts_with('/') {
+            true => match header.sender() {
+                Some(sender) => format!("{sender}{service}"),
+                None => {
+                    return Err(fdo::Error::InvalidArgs(
+                        "the item has no sender".to_string(),
+                    ));
+                }
+            },
+            false => format!("{service}{DEFAULT_ITEM_PATH}"),
+        };
+
+        if self.items.contains(&address) {
+            return Ok(());
+        }
+
+        log::debug!("[service:tray] item registered: {address}");
+        self.items.push(address.clone());
+        Self::status_notifier_item_registered(&emitter, &address).await?;
+
+        return Ok(());
+    }
+
+    async fn register_status_notifier_host(
+        &mut self,
+        service: &str,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        if !self.hosts.iter().any(|host| host == service) {
+            self.hosts.push(service.to_string());
+        }
+        Self::status_notifier_host_registered(&emitter).await?;
+
+        return Ok(());
+    }
+
+    #[zbus(property)]
+    fn registered_status_notifier_items(&self) -> Vec<String> {
+        self.items.clone()
+    }
+
+    #[zbus(property)]
+    fn is_status_notifier_host_registered(&self) -> bool {
+        !self.hosts.is_empty()
+    }
+
+    #[zbus(property)]
+    fn protocol_version(&self) -> i32 {
+        0
+    }
+
+    #[zbus(signal)]
+    pub async fn status_notifier_item_registered(
+        emitter: &SignalEmitter<'_>,
+        service: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    pub async fn status_notifier_item_unregistered(
+        emitter: &SignalEmitter<'_>,
+        service: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn status_notifier_host_registered(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+/// the watcher, whether it's the shell's or another program's
+#[proxy(
+    interface = "org.kde.StatusNotifierWatcher",
+    default_service = "org.kde.StatusNotifierWatcher",
+    default_path = "/StatusNotifierWatcher"
+)]
+pub trait StatusNotifierWatcher {
+    fn register_status_notifier_host(&self, service: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn registered_status_notifier_items(&self) -> zbus::Result<Vec<String>>;
+
+    #[zbus(signal)]
+    fn status_notifier_item_registered(&self, service: String) -> zbus::Result<()>;
+    #[zbus(signal)]
+    fn status_notifier_item_unregistered(&self, service: String) -> zbus::Result<()>;
+}
+
+#[proxy(interface = "org.kde.StatusNotifierItem")]
+pub trait StatusNotifierItem {
+    fn activate(&self, x: i32, y: i32) -> zbus::Result<()>;
+    fn context_menu(&self, x: i32, y: i32) -> zbus::Result<()>;
+    fn secondary_activate(&self, x: i32, y: i32) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn title(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn status(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn icon_name(&self) -> zbus::Result<String>;
+    /// `(width, height, argb32 pixels in network byte order)` for each size
+    #[zbus(property)]
+    fn icon_pixmap(&self) -> zbus::Result<Vec<(i32, i32, Vec<u8>)>>;
+    /// an extra directory to look for `icon_name` in, not part of the spec
+    /// but set by most items
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> zbus::Result<String>;
+
+    #[zbus(signal)]
+    fn new_icon(&self) -> zbus::Result<()>;
+    #[zbus(signal)]
+    fn new_title(&self) -> zbus::Result<()>;
+    #[zbus(signal)]
+    fn new_status(&self, status: String) -> zbus::Result<()>;
+}
+
+/// splits `:1.42/StatusNotifierItem` into its bus name and object path
+pub fn split_address(address: &str) -> (&str, &str) {
+    match address.find('/') {
+        Some(index) => address.split_at(index),
+        None => (address, DEFAULT_ITEM_PATH),
+    }
+}
@@ -0,0 +1,61 @@
+use super::data::{TrayEventType, TrayItems, TrayStatus};
+use super::{Event, TrayService};
+
+use crate::services::{Dedup, ServiceState};
+
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct TrayState {
+    /// the items that aren't passive, which are the ones modules show
+    pub items: TrayItems,
+
+    /// why the tray can't be used, `None` when connected
+    pub unavailable: Option<String>,
+
+    /// items are read again on every signal, even when nothing changed
+    dedup: Dedup<TrayEventType>,
+}
+
+impl ServiceState<TrayService> for TrayState {
+    fn init() -> Self {
+        Self {
+            items: TrayItems::default(),
+            unavailable: None,
+            dedup: Dedup::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        if let Some(kind) = event.event_type()
+            && !self.dedup.is_new(kind, &event)
+        {
+            return vec![];
+        }
+
+        match event.clone() {
+            Event::ItemsChanged { items } => {
+                let items = items
+                    .into_iter()
+                    .filter(|item| item.status != TrayStatus::Passive)
+                    .collect();
+
+                self.items = TrayItems {
+                    revision: self.items.revision.wrapping_add(1),
+                    items: Arc::new(items),
+                };
+            }
+            Event::ServiceUnavailable { reason } => {
+                let revision = self.items.revision.wrapping_add(1);
+                *self = Self::init();
+                self.items.revision = revision;
+                self.unavailable = Some(reason);
+            }
+            Event::ServiceAvailable => {
+                self.unavailable = None;
+            }
+        };
+
+        return vec![event];
+    }
+}
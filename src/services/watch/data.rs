@@ -0,0 +1,39 @@
+use crate::runtime::RuntimeModuleId;
+
+use std::path::PathBuf;
+
+/// messages emitted from the watch service when an event happens
+#[derive(Debug, Clone, Hash)]
+pub enum Event {
+    /// a file matching one of the module's globs was created, written to or
+    /// removed
+    FileChanged {
+        module: RuntimeModuleId,
+        path: PathBuf,
+    },
+}
+
+/// requests for the watch service
+#[derive(Debug, Clone)]
+pub enum Request {}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum WatchEventType {
+    FileChanged,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// subscription data
+
+/// a module's `Watch` register
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchSubscriptionData {
+    /// the module's file name without the extension, checked against
+    /// `[modules.watch]`. filled in by the runtime once the module is loaded
+    pub module: String,
+    /// like `~/notes/*.md`, `~` is the home directory
+    pub globs: Vec<String>,
+}
@@ -0,0 +1,413 @@
+//! tells modules when the files they watch change
+//!
+//! a module names the files in its `Watch` register with globs like
+//! `~/notes/*.md`. it can only watch files inside the directories listed for
+//! it under `[modules.watch]`, globs outside of them are dropped with a
+//! warning. the directories the globs start in are watched with `notify`,
+//! and each module is sent the changed files matching its globs
+//!
+//! changes are gathered for a moment before they're sent, an editor saving a
+//! file often writes, renames and changes its permissions, which is one
+//! change to the module
+
+mod data;
+mod se;
+mod state;
+
+pub use data::{Event, Request, WatchSubscriptionData};
+pub use se::serialize_changed;
+pub use state::WatchState;
+
+use data::WatchEventType;
+
+use crate::config;
+use crate::instrumented::{self, InstrumentedSender};
+use crate::runtime::RuntimeModuleId;
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::time::Instant;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// how long changes are gathered before they're sent
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// the most globs a module can watch, the rest are dropped
+const MAX_GLOBS: usize = 64;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct WatchService;
+
+/// what a module watches, made from its `Watch` register
+#[derive(Debug)]
+pub struct Watched {
+    globs: GlobSet,
+    /// the directories the globs start in and whether the globs reach into
+    /// their subdirectories
+    roots: Vec<(PathBuf, RecursiveMode)>,
+}
+
+impl Service for WatchService {
+    type Event = Event;
+    type EventType = WatchEventType;
+    type Request = Request;
+    /// what each module watches, kept across restarts of the service like
+    /// `ModuleIds` so the files are watched again
+    type RuntimeData = HashMap<RuntimeModuleId, Watched>;
+    type State = WatchState;
+    type SubscriptionData = WatchSubscriptionData;
+
+    const ID: u16 = 21;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.watch.events", chan);
+                let mut module_ids = ModuleIds::new();
+                let mut watched = HashMap::new();
+
+                loop {
+                    let mut state = WatchState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.watch.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:watch] could not send init event: {}", err);
+                        log::error!("[service:watch] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut watched,
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:watch] restarting");
+                        continue;
+                    }
+                    log::error!("[service:watch] error: {err}");
+                    restart_delay::<Self>(Duration::from_secs(5)).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut WatchState,
+        module_ids: &mut ModuleIds<Self>,
+        watched: &mut HashMap<RuntimeModuleId, Watched>,
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        // notify calls this from its own thread
+        let (event_tx, event_rx) = flume::unbounded::<notify::Result<notify::Event>>();
+        let mut watcher = match notify::recommended_watcher(move |event| {
+            let _ = event_tx.send(event);
+        }) {
+            Ok(watcher) => watcher,
+            Err(err) => return anyhow!("[service:watch] could not start watching: {err}"),
+        };
+
+        // the directories being watched, the ones from before a restart are
+        // watched again here
+        let mut roots: HashMap<PathBuf, RecursiveMode> = HashMap::new();
+        update_roots(&mut watcher, &mut roots, watched);
+
+        log::info!("[service:watch] service started");
+        startup::ready("watch");
+
+        // the changes waiting to be sent and when they're sent
+        let mut pending: HashSet<(RuntimeModuleId, PathBuf)> = HashSet::new();
+        let mut deadline: Option<Instant> = None;
+
+        loop {
+            tokio::select! {
+                _ = async {
+                    match deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    deadline = None;
+
+                    for (module, path) in pending.drain() {
+                        log::debug!("[service:watch] {} changed for {module:?}", path.display());
+
+                        for event in state.update(Event::FileChanged { module, path }) {
+                            if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                                log::error!(
+                                    "[service:watch] error sending service event update: {err}"
+                                );
+                            }
+                        }
+                    }
+                }
+                event = event_rx.recv_async() => {
+                    let event = match event {
+                        Ok(Ok(event)) => event,
+                        Ok(Err(err)) => {
+                            log::warn!("[service:watch] error while watching: {err}");
+                            continue;
+                        }
+                        Err(err) => return anyhow!("[service:watch] the watcher stopped: {err}"),
+                    };
+
+                    // reading a file isn't a change
+                    if matches!(event.kind, EventKind::Access(_)) {
+                        continue;
+                    }
+
+                    for path in &event.paths {
+                        for (module, module_watched) in watched.iter() {
+                            if module_watched.globs.is_match(path) {
+                                pending.insert((module.clone(), path.clone()));
+                            }
+                        }
+                    }
+
+                    if !pending.is_empty() && deadline.is_none() {
+                        deadline = Some(Instant::now() + DEBOUNCE);
+                    }
+                }
+                request = request_rx.recv_async() => {
+                    match request {
+                        Ok(ServiceRequest::Request { request }) => match request {},
+                        Ok(ServiceRequest::SubscribeModule { id, data }) => {
+                            match Watched::new(&data) {
+                                Some(module_watched) => {
+                                    watched.insert(id.clone(), module_watched);
+                                }
+                                None => {
+                                    watched.remove(&id);
+                                }
+                            }
+                            update_roots(&mut watcher, &mut roots, watched);
+
+                            let events = vec![WatchEventType::FileChanged];
+                            let diff = module_ids.register_module(id.clone(), events);
+                            log::debug!("[service:watch] {id:?} registered, {diff}");
+                        }
+                        Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                            watched.remove(&id);
+                            pending.retain(|(module, _)| module != &id);
+                            state.changes.remove(&id);
+                            update_roots(&mut watcher, &mut roots, watched);
+                            module_ids.unregister_module(id);
+                        }
+                        Err(err) => {
+                            return anyhow!("[service:watch] error receiving request: {err}");
+                        }
+                    }
+                }
+            };
+        }
+    }
+}
+
+impl Watched {
+    /// `None` when none of the globs can be watched
+    fn new(data: &WatchSubscriptionData) -> Option<Self> {
+        let module = &data.module;
+        let home = env::var("HOME").map(PathBuf::from).ok();
+
+        let allowed: Vec<PathBuf> = match config::get().modules.watch.get(module) {
+            Some(dirs) => dirs
+                .iter()
+                .filter_map(|dir| expand_home(dir, home.as_deref()))
+                .collect(),
+            None => vec![],
+        };
+        if allowed.is_empty() {
+            log::warn!(
+                "[service:watch] {module} tried to watch files without any directories listed \
+                 for it under `[modules.watch]`"
+            );
+            return None;
+        }
+
+        if data.globs.len() > MAX_GLOBS {
+            log::warn!(
+                "[service:watch] {module} asked to watch {} globs, only watching the first \
+                 {MAX_GLOBS}",
+                data.globs.len()
+            );
+        }
+
+        let mut globs = GlobSetBuilder::new();
+        let mut roots = vec![];
+
+        for glob in data.globs.iter().take(MAX_GLOBS) {
+            let Some(pattern) = expand_home(Path::new(glob), home.as_deref()) else {
+                log::warn!(
+                    "[service:watch] {module} asked to watch `{glob}`, which isn't absolute"
+                );
+                continue;
+            };
+            let Some((root, mode)) = root(&pattern) else {
+                log::warn!("[service:watch] {module} asked to watch `{glob}`, which has a `..`");
+                continue;
+            };
+            if !allowed.iter().any(|dir| root.starts_with(dir)) {
+                log::warn!(
+                    "[service:watch] {module} asked to watch `{glob}`, which isn't in a directory \
+                     listed for it under `[modules.watch]`"
+                );
+                continue;
+            }
+
+            // `*` stays inside a directory, `**` is for going into them
+            match GlobBuilder::new(&pattern.to_string_lossy())
+                .literal_separator(true)
+                .build()
+            {
+                Ok(compiled) => {
+                    globs.add(compiled);
+                    roots.push((root, mode));
+                }
+                Err(err) => {
+                    log::warn!("[service:watch] {module} asked to watch `{glob}`: {err}");
+                }
+            }
+        }
+
+        if roots.is_empty() {
+            return None;
+        }
+
+        return match globs.build() {
+            Ok(globs) => Some(Self { globs, roots }),
+            Err(err) => {
+                log::warn!("[service:watch] could not build {module}'s globs: {err}");
+                None
+            }
+        };
+    }
+}
+
+/// `~` at the start of `path` as the home directory, `None` when the path
+/// isn't absolute after that
+fn expand_home(path: &Path, home: Option<&Path>) -> Option<PathBuf> {
+    let path = match (path.strip_prefix("~"), home) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        (Ok(_), None) => return None,
+        (Err(_), _) => path.to_path_buf(),
+    };
+
+    return path.is_absolute().then_some(path);
+}
+
+/// the directory a glob starts in, the part before its first wildcard, and
+/// whether the wildcards reach into subdirectories of it. a glob without
+/// wildcards starts in the file's directory, editors often save by moving a
+/// new file over the old one which would end a watch on the file itself
+///
+/// `None` for a glob with a `..`, which could leave the allowed directories
+fn root(pattern: &Path) -> Option<(PathBuf, RecursiveMode)> {
+    if pattern
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        return None;
+    }
+
+    let is_wild = |component: &Component| {
+        component
+            .as_os_str()
+            .to_string_lossy()
+            .contains(['*', '?', '[', '{'])
+    };
+
+    let components: Vec<Component> = pattern.components().collect();
+    let literal = components
+        .iter()
+        .position(is_wild)
+        .unwrap_or(components.len() - 1);
+
+    let root: PathBuf = components[..literal].iter().collect();
+    let mode = match components.len() - literal {
+        1 if !pattern.to_string_lossy().contains("**") => RecursiveMode::NonRecursive,
+        _ => RecursiveMode::Recursive,
+    };
+
+    return Some((root, mode));
+}
+
+/// watches the directories the modules' globs start in and stops watching
+/// the ones no module needs anymore
+fn update_roots(
+    watcher: &mut RecommendedWatcher,
+    roots: &mut HashMap<PathBuf, RecursiveMode>,
+    watched: &HashMap<RuntimeModuleId, Watched>,
+) {
+    let mut wanted: HashMap<PathBuf, RecursiveMode> = HashMap::new();
+    for (root, mode) in watched.values().flat_map(|watched| &watched.roots) {
+        let entry = wanted.entry(root.clone()).or_insert(*mode);
+        if *mode == RecursiveMode::Recursive {
+            *entry = RecursiveMode::Recursive;
+        }
+    }
+
+    roots.retain(|root, mode| {
+        if wanted.get(root) == Some(mode) {
+            return true;
+        }
+
+        if let Err(err) = watcher.unwatch(root) {
+            log::debug!(
+                "[service:watch] could not stop watching {}: {err}",
+                root.display()
+            );
+        }
+        return false;
+    });
+
+    for (root, mode) in wanted {
+        if roots.contains_key(&root) {
+            continue;
+        }
+
+        // a directory that doesn't exist yet isn't watched until a module
+        // registers again or the service restarts
+        match watcher.watch(&root, mode) {
+            Ok(()) => {
+                log::debug!("[service:watch] watching {} ({mode:?})", root.display());
+                roots.insert(root, mode);
+            }
+            Err(err) => log::warn!("[service:watch] could not watch {}: {err}", root.display()),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use super::{Event, WatchService};
+
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+use std::path::Path;
+
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        WatchService::ID
+    }
+
+    // a changed file is only for the module watching it, the runtime gives it
+    // through `Request::FileChanged`
+    fn serialize(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn is_for(&self, _register: &SubscriptionData) -> bool {
+        false
+    }
+}
+
+/// the layout modules get a changed file in through `service_event`, a u8
+/// for the kind of event (0 for a file that changed) then the path as a u16
+/// length followed by the bytes
+///
+/// it's made by the runtime rather than `serialize`, the event is only for
+/// the one module that watches the file
+pub fn serialize_changed(path: &Path) -> Vec<u8> {
+    let path = path.to_string_lossy();
+    let path = &path.as_bytes()[..path.len().min(u16::MAX as usize)];
+
+    let mut bytes = vec![0];
+    bytes.extend((path.len() as u16).to_le_bytes());
+    bytes.extend(path);
+
+    return bytes;
+}
@@ -0,0 +1,30 @@
+use super::{Event, WatchService};
+
+use crate::runtime::RuntimeModuleId;
+use crate::services::ServiceState;
+
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct WatchState {
+    /// how many changes each module was told about since it registered
+    pub changes: HashMap<RuntimeModuleId, u64>,
+}
+
+impl ServiceState<WatchService> for WatchState {
+    fn init() -> Self {
+        Self {
+            changes: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match event.clone() {
+            Event::FileChanged { module, .. } => {
+                *self.changes.entry(module).or_default() += 1;
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -0,0 +1,104 @@
+//! keeps the last weather in `~/.local/state/aurorashell/weather.json`, so
+//! it's shown right away after a restart instead of once the next fetch
+//! comes back, and a restart doesn't fetch again before the interval is up
+//!
+//! the cache is thrown away when the weather config it was fetched with
+//! changed, another location or units would show the wrong weather
+
+use super::data::Weather;
+
+use crate::config::{self, WeatherServiceConfig};
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Cached {
+    /// see `key`
+    key: String,
+    /// unix timestamp in seconds
+    fetched: i64,
+    weather: Weather,
+}
+
+fn path() -> anyhow::Result<PathBuf> {
+    return Ok(config::state_dir()?.join("weather.json"));
+}
+
+/// everything in the config that changes what's fetched
+fn key(config: &WeatherServiceConfig) -> String {
+    return format!(
+        "{:?}|{:?}|{:?}|{:?}|{:?}|{}",
+        config.provider,
+        config.location,
+        config.latitude,
+        config.longitude,
+        config.units,
+        config.forecast_days
+    );
+}
+
+/// the cached weather and how long ago it was fetched, `None` when there's
+/// nothing cached for this config
+pub fn load(config: &WeatherServiceConfig) -> Option<(Weather, Duration)> {
+    let source = match path().and_then(|path| Ok(fs::read_to_string(path)?)) {
+        Ok(source) => source,
+        Err(err) => {
+            log::debug!("[service:weather] no cached weather: {err}");
+            return None;
+        }
+    };
+
+    let cached: Cached = match serde_json::from_str(&source) {
+        Ok(cached) => cached,
+        Err(err) => {
+            log::warn!("[service:weather] could not read the cached weather: {err}");
+            return None;
+        }
+    };
+
+    if cached.key != key(config) {
+        log::debug!("[service:weather] the cached weather is for another config");
+        return None;
+    }
+
+    // a clock set back makes it look fetched in the future, it's fetched
+    // again right away then
+    let age = match u64::try_from(Utc::now().timestamp() - cached.fetched) {
+        Ok(age) => Duration::from_secs(age),
+        Err(_) => Duration::MAX,
+    };
+
+    return Some((cached.weather, age));
+}
+
+pub fn save(config: &WeatherServiceConfig, weather: &Weather) {
+    let cached = Cached {
+        key: key(config),
+        fetched: Utc::now().timestamp(),
+        weather: weather.clone(),
+    };
+
+    let result = (|| -> anyhow::Result<()> {
+        let path = path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // written next to the file and moved over it so a crash while
+        // writing doesn't leave half a file
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string(&cached)?)?;
+        fs::rename(&temp, &path)?;
+
+        return Ok(());
+    })();
+
+    if let Err(err) = result {
+        log::error!("[service:weather] could not cache the weather: {err}");
+    }
+}
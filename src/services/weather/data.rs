@@ -0,0 +1,119 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// messages emitted from the weather service
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// event emitted when the weather was fetched
+    WeatherUpdated { weather: Weather },
+    /// event emitted when the weather couldn't be fetched, the last weather
+    /// is kept
+    WeatherFailed { error: String },
+}
+
+/// requests for the weather service
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// fetches the weather right away instead of waiting for the interval
+    Refresh,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Weather {
+    /// the name of the location, if it was looked up by name
+    pub location: Option<String>,
+    pub current: Conditions,
+    /// starts with today
+    pub daily: Vec<DailyForecast>,
+}
+
+/// the weather right now, in the configured units
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conditions {
+    pub temperature: f32,
+    /// what the temperature feels like
+    pub apparent_temperature: f32,
+    pub wind_speed: f32,
+    pub kind: WeatherKind,
+    pub is_day: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyForecast {
+    pub date: NaiveDate,
+    pub temperature_min: f32,
+    pub temperature_max: f32,
+    /// chance of precipitation in percent
+    pub precipitation_probability: Option<f32>,
+    pub kind: WeatherKind,
+}
+
+/// a simplified wmo weather code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherKind {
+    Clear,
+    PartlyCloudy,
+    Cloudy,
+    Fog,
+    Drizzle,
+    Rain,
+    Snow,
+    Thunderstorm,
+    Unknown,
+}
+
+impl WeatherKind {
+    /// see the `weather_code` section of <https://open-meteo.com/en/docs>
+    pub fn from_wmo_code(code: u8) -> Self {
+        match code {
+            0 => Self::Clear,
+            1 | 2 => Self::PartlyCloudy,
+            3 => Self::Cloudy,
+            45 | 48 => Self::Fog,
+            51..=57 => Self::Drizzle,
+            61..=67 | 80..=82 => Self::Rain,
+            71..=77 | 85 | 86 => Self::Snow,
+            95..=99 => Self::Thunderstorm,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// the codes wttr.in gives, from worldweatheronline, see
+    /// <https://www.worldweatheronline.com/weather-api/api/docs/weather-icons.aspx>
+    pub fn from_wwo_code(code: u16) -> Self {
+        match code {
+            113 => Self::Clear,
+            116 => Self::PartlyCloudy,
+            119 | 122 => Self::Cloudy,
+            143 | 248 | 260 => Self::Fog,
+            185 | 263 | 266 | 281 | 284 => Self::Drizzle,
+            176 | 293..=314 | 353..=359 => Self::Rain,
+            179 | 182 | 227 | 230 | 317..=350 | 362..=377 => Self::Snow,
+            200 | 386..=395 => Self::Thunderstorm,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Clear => "clear",
+            Self::PartlyCloudy => "partly cloudy",
+            Self::Cloudy => "cloudy",
+            Self::Fog => "fog",
+            Self::Drizzle => "drizzle",
+            Self::Rain => "rain",
+            Self::Snow => "snow",
+            Self::Thunderstorm => "thunderstorm",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// event type
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum WeatherEventType {
+    Weather,
+}
@@ -0,0 +1,272 @@
+//! fetches the weather for the configured location from open-meteo or
+//! wttr.in
+//!
+//! the last weather is cached on disk (see `cache`), and modules with a
+//! `Weather` register are given every update so they don't each need to
+//! make their own requests
+
+mod cache;
+mod data;
+mod open_meteo;
+mod se;
+mod state;
+mod wttr;
+
+pub use data::{Event, Request, Weather, WeatherKind};
+pub use state::WeatherState;
+
+use data::WeatherEventType;
+
+use crate::config::{self, WeatherProvider, WeatherServiceConfig};
+use crate::instrumented::{self, InstrumentedSender};
+use crate::services::{
+    ModuleIds, RestartRequested, Service, ServiceEvent, ServiceRequest, ServiceState,
+    restart_delay, supervise,
+};
+use crate::startup;
+
+use std::any::TypeId;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::stream::channel;
+
+////////////////////////////////////////////////////////////////////////////////
+// service parameters
+
+/// configures the capacity for all channels in this service
+const CHANNEL_CAPACITY: usize = 16;
+
+/// the weather isn't fetched more often than this
+const MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// requests to the provider are cancelled after this
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// how long to wait before trying again when fetching fails
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// the last weather, for modules reading it with `read_weather`
+static CURRENT: Mutex<Option<Weather>> = Mutex::new(None);
+
+/// the last weather in the layout of `se::serialize_weather`, empty before
+/// the first fetch
+pub fn serialized() -> Vec<u8> {
+    let current = match CURRENT.lock() {
+        Ok(current) => current,
+        Err(err) => {
+            log::error!("[service:weather] could not lock the current weather: {err}");
+            return vec![];
+        }
+    };
+
+    return current
+        .as_ref()
+        .map(se::serialize_weather)
+        .unwrap_or_default();
+}
+
+#[derive(Debug, Clone)]
+pub struct WeatherService;
+
+impl Service for WeatherService {
+    type Event = Event;
+    type EventType = WeatherEventType;
+    type Request = Request;
+    type RuntimeData = ();
+    type State = WeatherState;
+    type SubscriptionData = ();
+
+    const ID: u16 = 6;
+    const VERSION: u32 = 1;
+
+    fn subscribe() -> iced::Subscription<ServiceEvent<Self>> {
+        let id = TypeId::of::<Self>();
+
+        Subscription::run_with_id(
+            id,
+            channel(CHANNEL_CAPACITY, async |chan| {
+                let mut chan = InstrumentedSender::new("service.weather.events", chan);
+                let mut module_ids = ModuleIds::new();
+
+                // there's no point looking up the weather before the network
+                // is up
+                startup::wait_for_dependencies("weather").await;
+
+                loop {
+                    let mut state = WeatherState::init();
+
+                    let (tx, rx) = instrumented::bounded::<ServiceRequest<Self>>(
+                        "service.weather.requests",
+                        CHANNEL_CAPACITY,
+                    );
+
+                    if let Err(err) = chan.send(ServiceEvent::Init { request_tx: tx }).await {
+                        log::error!("[service:weather] could not send init event: {}", err);
+                        log::error!("[service:weather] retrying in 5 seconds...");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+
+                    let err = supervise::<Self>(Self::run(
+                        &mut state,
+                        &mut module_ids,
+                        &mut (),
+                        &mut chan,
+                        rx,
+                    ))
+                    .await;
+                    if err.is::<RestartRequested>() {
+                        log::info!("[service:weather] restarting");
+                        continue;
+                    }
+                    log::error!("[service:weather] error: {err}");
+                    restart_delay::<Self>(Duration::from_secs(5)).await;
+                }
+            }),
+        )
+    }
+
+    async fn run(
+        state: &mut WeatherState,
+        module_ids: &mut ModuleIds<Self>,
+        _runtime_data: &mut (),
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        request_rx: flume::Receiver<ServiceRequest<Self>>,
+    ) -> anyhow::Error {
+        let weather_config = &config::get().services.weather;
+        let interval = Duration::from_secs(weather_config.interval_minutes * 60).max(MIN_INTERVAL);
+
+        let client = match reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("aurorashell/", env!("CARGO_PKG_VERSION")))
+            .build()
+        {
+            Ok(client) => client,
+            Err(err) => return anyhow!("[service:weather] could not create http client: {err}"),
+        };
+
+        log::info!("[service:weather] service started");
+        startup::ready("weather");
+
+        // looked up once, then kept for the rest of the run
+        let mut place = None;
+
+        // the weather from the last run is shown right away, it's only
+        // fetched again once it's as old as the interval
+        let mut delay = match cache::load(weather_config) {
+            Some((weather, age)) => {
+                log::debug!(
+                    "[service:weather] using the weather cached {}s ago",
+                    age.as_secs()
+                );
+                Self::emit(state, chan, Event::WeatherUpdated { weather }).await;
+                interval.saturating_sub(age)
+            }
+            None => Duration::ZERO,
+        };
+
+        loop {
+            // wait for the next fetch, handling requests in the meantime
+            let sleep = tokio::time::sleep(delay);
+            tokio::pin!(sleep);
+
+            loop {
+                tokio::select! {
+                    _ = &mut sleep => break,
+                    request = request_rx.recv_async() => {
+                        match request {
+                            Ok(ServiceRequest::Request { request }) => match request {
+                                Request::Refresh => break,
+                            },
+                            Ok(ServiceRequest::SubscribeModule { id, data: () }) => {
+                                let events = vec![WeatherEventType::Weather];
+                                let diff = module_ids.register_module(id.clone(), events);
+                                log::debug!("[service:weather] {id:?} registered, {diff}");
+                            }
+                            Ok(ServiceRequest::UnsubscribeModule { id }) => {
+                                module_ids.unregister_module(id);
+                            }
+                            Err(err) => {
+                                return anyhow!("[service:weather] error receiving request: {err}");
+                            }
+                        }
+                    }
+                };
+            }
+
+            let event = match Self::fetch(&client, weather_config, &mut place).await {
+                Ok(weather) => {
+                    cache::save(weather_config, &weather);
+                    Event::WeatherUpdated { weather }
+                }
+                Err(err) => Event::WeatherFailed {
+                    error: err.to_string(),
+                },
+            };
+
+            delay = match &event {
+                Event::WeatherUpdated { .. } => interval,
+                Event::WeatherFailed { error } => {
+                    log::warn!("[service:weather] could not fetch the weather: {error}");
+                    RETRY_DELAY.min(interval)
+                }
+            };
+
+            Self::emit(state, chan, event).await;
+        }
+    }
+}
+
+impl WeatherService {
+    /// fetches the weather from the configured provider
+    async fn fetch(
+        client: &reqwest::Client,
+        config: &WeatherServiceConfig,
+        place: &mut Option<open_meteo::Place>,
+    ) -> anyhow::Result<Weather> {
+        if config.provider == WeatherProvider::Wttr {
+            return wttr::forecast(client, config).await;
+        }
+
+        let place = match place {
+            Some(place) => place,
+            None => match open_meteo::resolve_place(client, config).await {
+                Ok(resolved) => {
+                    log::debug!("[service:weather] using location {resolved:?}");
+                    place.insert(resolved)
+                }
+                Err(err) => return Err(anyhow!("could not find the location: {err}")),
+            },
+        };
+
+        return open_meteo::forecast(client, place, config).await;
+    }
+
+    /// updates the state and sends out the event
+    async fn emit(
+        state: &mut WeatherState,
+        chan: &mut InstrumentedSender<mpsc::Sender<ServiceEvent<Self>>>,
+        event: Event,
+    ) {
+        for event in state.update(event) {
+            if let Event::WeatherUpdated { weather } = &event {
+                match CURRENT.lock() {
+                    Ok(mut current) => *current = Some(weather.clone()),
+                    Err(err) => {
+                        log::error!("[service:weather] could not lock the current weather: {err}")
+                    }
+                }
+            }
+
+            if let Err(err) = chan.send(ServiceEvent::Update { event }).await {
+                log::error!("[service:weather] error sending service event update: {err}");
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! requests to <https://open-meteo.com>, which needs no api key
+
+use super::data::{Conditions, DailyForecast, Weather, WeatherKind};
+
+use crate::config::{WeatherServiceConfig, WeatherUnits};
+
+use anyhow::anyhow;
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+const FORECAST_URL: &str = "https://api.open-meteo.com/v1/forecast";
+const GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com/v1/search";
+
+/// a place that was looked up by name
+#[derive(Debug, Clone)]
+pub struct Place {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// finds the coordinates of the configured location
+///
+/// coordinates in the config are used as is, otherwise the location's name
+/// is looked up
+pub async fn resolve_place(
+    client: &reqwest::Client,
+    config: &WeatherServiceConfig,
+) -> anyhow::Result<Place> {
+    if let (Some(latitude), Some(longitude)) = (config.latitude, config.longitude) {
+        return Ok(Place {
+            name: config.location.clone().unwrap_or_default(),
+            latitude,
+            longitude,
+        });
+    }
+
+    let name = match &config.location {
+        Some(name) => name,
+        None => return Err(anyhow!("no location or coordinates configured")),
+    };
+
+    let response: GeocodingResponse = client
+        .get(GEOCODING_URL)
+        .query(&[("name", name.as_str()), ("count", "1")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let result = match response.results.into_iter().next() {
+        Some(result) => result,
+        None => return Err(anyhow!("could not find a place named `{name}`")),
+    };
+
+    return Ok(Place {
+        name: match result.country {
+            Some(country) => format!("{}, {country}", result.name),
+            None => result.name,
+        },
+        latitude: result.latitude,
+        longitude: result.longitude,
+    });
+}
+
+pub async fn forecast(
+    client: &reqwest::Client,
+    place: &Place,
+    config: &WeatherServiceConfig,
+) -> anyhow::Result<Weather> {
+    let (temperature_unit, wind_speed_unit) = match config.units {
+        WeatherUnits::Metric => ("celsius", "kmh"),
+        WeatherUnits::Imperial => ("fahrenheit", "mph"),
+    };
+
+    let response: ForecastResponse = client
+        .get(FORECAST_URL)
+        .query(&[
+            ("latitude", place.latitude.to_string()),
+            ("longitude", place.longitude.to_string()),
+            (
+                "current",
+                "temperature_2m,apparent_temperature,weather_code,is_day,wind_speed_10m"
+                    .to_string(),
+            ),
+            (
+                "daily",
+                "weather_code,temperature_2m_max,temperature_2m_min,precipitation_probability_max"
+                    .to_string(),
+            ),
+            ("forecast_days", config.forecast_days.to_string()),
+            ("temperature_unit", temperature_unit.to_string()),
+            ("wind_speed_unit", wind_speed_unit.to_string()),
+            ("timezone", "auto".to_string()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let daily = &response.daily;
+    let daily = (0..daily.time.len())
+        .filter_map(|i| {
+            Some(DailyForecast {
+                date: daily.time[i],
+                temperature_min: *daily.temperature_2m_min.get(i)?,
+                temperature_max: *daily.temperature_2m_max.get(i)?,
+                precipitation_probability: daily
+                    .precipitation_probability_max
+                    .get(i)
+                    .copied()
+                    .flatten(),
+                kind: WeatherKind::from_wmo_code(*daily.weather_code.get(i)?),
+            })
+        })
+        .collect();
+
+    return Ok(Weather {
+        location: (!place.name.is_empty()).then(|| place.name.clone()),
+        current: Conditions {
+            temperature: response.current.temperature_2m,
+            apparent_temperature: response.current.apparent_temperature,
+            wind_speed: response.current.wind_speed_10m,
+            kind: WeatherKind::from_wmo_code(response.current.weather_code),
+            is_day: response.current.is_day != 0,
+        },
+        daily,
+    });
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// responses
+
+#[derive(Debug, Deserialize)]
+struct GeocodingResponse {
+    /// missing when nothing was found
+    #[serde(default)]
+    results: Vec<GeocodingResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeocodingResult {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    country: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastResponse {
+    current: CurrentResponse,
+    daily: DailyResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentResponse {
+    temperature_2m: f32,
+    apparent_temperature: f32,
+    weather_code: u8,
+    is_day: u8,
+    wind_speed_10m: f32,
+}
+
+/// each field has one value per day
+#[derive(Debug, Deserialize)]
+struct DailyResponse {
+    time: Vec<NaiveDate>,
+    weather_code: Vec<u8>,
+    temperature_2m_max: Vec<f32>,
+    temperature_2m_min: Vec<f32>,
+    precipitation_probability_max: Vec<Option<f32>>,
+}
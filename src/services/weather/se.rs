@@ -0,0 +1,109 @@
+use super::data::{Weather, WeatherKind};
+use super::{Event, WeatherService};
+
+use crate::config::{self, WeatherUnits};
+use crate::runtime::wasm::WasmSerializable;
+use crate::services::{Service, SubscriptionData};
+
+use chrono::Datelike;
+
+/// the layout modules get weather events in through `service_event`, a u8
+/// for the kind of event then what changed
+///
+/// - 0 the weather was fetched, in the layout of `serialize_weather`
+/// - 1 it couldn't be fetched with the error as a u16 length then the bytes,
+///   the last weather is still the one to show
+impl WasmSerializable for Event {
+    fn service_id(&self) -> u16 {
+        WeatherService::ID
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        let mut bytes = vec![];
+
+        match self {
+            Event::WeatherUpdated { weather } => {
+                bytes.push(0);
+                bytes.extend(serialize_weather(weather));
+            }
+            Event::WeatherFailed { error } => {
+                bytes.push(1);
+                push_str(&mut bytes, error);
+            }
+        }
+
+        return Some(bytes);
+    }
+
+    fn is_for(&self, register: &SubscriptionData) -> bool {
+        return matches!(register, SubscriptionData::Weather);
+    }
+}
+
+/// the weather as modules read it, everything is little endian
+///
+/// - a u8 for the units, 0 metric (celsius and km/h) and 1 imperial
+///   (fahrenheit and mph)
+/// - the location as a u16 length then the bytes, 0 long when it isn't known
+/// - the current temperature, apparent temperature and wind speed as f32s,
+///   then a u8 for the kind of weather (see `kind_id`) and a u8 for whether
+///   it's day
+/// - a u8 for the amount of days in the forecast, starting with today. each
+///   is a u16 year, u8 month and u8 day, the lowest and highest temperature
+///   as f32s, the chance of precipitation in percent as an f32 (nan when it
+///   isn't known) and a u8 for the kind of weather
+pub fn serialize_weather(weather: &Weather) -> Vec<u8> {
+    let mut bytes = vec![];
+
+    bytes.push(match config::get().services.weather.units {
+        WeatherUnits::Metric => 0,
+        WeatherUnits::Imperial => 1,
+    });
+    push_str(&mut bytes, weather.location.as_deref().unwrap_or_default());
+
+    let current = &weather.current;
+    bytes.extend(current.temperature.to_le_bytes());
+    bytes.extend(current.apparent_temperature.to_le_bytes());
+    bytes.extend(current.wind_speed.to_le_bytes());
+    bytes.push(kind_id(current.kind));
+    bytes.push(current.is_day as u8);
+
+    let days = &weather.daily[..weather.daily.len().min(u8::MAX as usize)];
+    bytes.push(days.len() as u8);
+    for day in days {
+        bytes.extend((day.date.year().clamp(0, u16::MAX as i32) as u16).to_le_bytes());
+        bytes.push(day.date.month() as u8);
+        bytes.push(day.date.day() as u8);
+        bytes.extend(day.temperature_min.to_le_bytes());
+        bytes.extend(day.temperature_max.to_le_bytes());
+        bytes.extend(
+            day.precipitation_probability
+                .unwrap_or(f32::NAN)
+                .to_le_bytes(),
+        );
+        bytes.push(kind_id(day.kind));
+    }
+
+    return bytes;
+}
+
+/// the order of the guest's `weather::Kind`
+fn kind_id(kind: WeatherKind) -> u8 {
+    match kind {
+        WeatherKind::Clear => 0,
+        WeatherKind::PartlyCloudy => 1,
+        WeatherKind::Cloudy => 2,
+        WeatherKind::Fog => 3,
+        WeatherKind::Drizzle => 4,
+        WeatherKind::Rain => 5,
+        WeatherKind::Snow => 6,
+        WeatherKind::Thunderstorm => 7,
+        WeatherKind::Unknown => 255,
+    }
+}
+
+fn push_str(bytes: &mut Vec<u8>, string: &str) {
+    let string = &string.as_bytes()[..string.len().min(u16::MAX as usize)];
+    bytes.extend((string.len() as u16).to_le_bytes());
+    bytes.extend(string);
+}
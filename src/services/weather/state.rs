@@ -0,0 +1,35 @@
+use super::data::Weather;
+use super::{Event, WeatherService};
+
+use crate::services::ServiceState;
+
+#[derive(Debug)]
+pub struct WeatherState {
+    /// the last weather that was fetched
+    pub weather: Option<Weather>,
+    /// the last error, cleared when the weather is fetched again
+    pub error: Option<String>,
+}
+
+impl ServiceState<WeatherService> for WeatherState {
+    fn init() -> Self {
+        Self {
+            weather: None,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, event: Event) -> Vec<Event> {
+        match event.clone() {
+            Event::WeatherUpdated { weather } => {
+                self.error = None;
+                self.weather = Some(weather);
+            }
+            Event::WeatherFailed { error } => {
+                self.error = Some(error);
+            }
+        };
+
+        return vec![event];
+    }
+}
@@ -0,0 +1,214 @@
+//! requests to <https://wttr.in>, which needs no api key either and takes
+//! the location's name as is
+//!
+//! every number in its json is a string, and it only forecasts today and
+//! the next 2 days
+
+use super::data::{Conditions, DailyForecast, Weather, WeatherKind};
+
+use crate::config::{WeatherServiceConfig, WeatherUnits};
+
+use anyhow::anyhow;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Deserialize;
+
+const BASE_URL: &str = "https://wttr.in";
+
+pub async fn forecast(
+    client: &reqwest::Client,
+    config: &WeatherServiceConfig,
+) -> anyhow::Result<Weather> {
+    let query = match (config.latitude, config.longitude, &config.location) {
+        (Some(latitude), Some(longitude), _) => format!("{latitude},{longitude}"),
+        (_, _, Some(location)) => location.clone(),
+        _ => return Err(anyhow!("no location or coordinates configured")),
+    };
+
+    let mut url = reqwest::Url::parse(BASE_URL)?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("{BASE_URL} can't have a path"))?
+        .push(&query);
+
+    let response: Response = client
+        .get(url)
+        .query(&[("format", "j1")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let current = match response.current_condition.first() {
+        Some(current) => current,
+        None => return Err(anyhow!("no current conditions for `{query}`")),
+    };
+
+    let metric = config.units == WeatherUnits::Metric;
+    let pick = |metric_value: &str, imperial_value: &str| -> f32 {
+        let value = if metric { metric_value } else { imperial_value };
+        value.trim().parse().unwrap_or(0.0)
+    };
+
+    let daily = response
+        .weather
+        .iter()
+        .take(config.forecast_days as usize)
+        .filter_map(|day| {
+            Some(DailyForecast {
+                date: day.date,
+                temperature_min: pick(&day.mintemp_c, &day.mintemp_f),
+                temperature_max: pick(&day.maxtemp_c, &day.maxtemp_f),
+                precipitation_probability: day
+                    .hourly
+                    .iter()
+                    .filter_map(|hour| {
+                        let rain: f32 = hour.chanceofrain.parse().ok()?;
+                        let snow: f32 = hour.chanceofsnow.parse().unwrap_or(0.0);
+                        Some(rain.max(snow))
+                    })
+                    .reduce(f32::max),
+                kind: WeatherKind::from_wwo_code(day.midday()?.weather_code.parse().ok()?),
+            })
+        })
+        .collect();
+
+    let location = match (&config.location, response.nearest_area.first()) {
+        (Some(location), _) => Some(location.clone()),
+        (None, Some(area)) => area.name(),
+        (None, None) => None,
+    };
+
+    return Ok(Weather {
+        location,
+        current: Conditions {
+            temperature: pick(&current.temp_c, &current.temp_f),
+            apparent_temperature: pick(&current.feels_like_c, &current.feels_like_f),
+            wind_speed: pick(&current.windspeed_kmph, &current.windspeed_miles),
+            kind: current
+                .weather_code
+                .parse()
+                .map_or(WeatherKind::Unknown, WeatherKind::from_wwo_code),
+            is_day: is_day(current, response.weather.first()),
+        },
+        daily,
+    });
+}
+
+/// whether the sun was up when the conditions were observed, it's taken to
+/// be day when there's no sunrise or sunset (like near the poles)
+fn is_day(current: &CurrentCondition, today: Option<&Day>) -> bool {
+    let observed = NaiveDateTime::parse_from_str(&current.local_obs_date_time, "%Y-%m-%d %I:%M %p");
+    let Some(astronomy) = today.and_then(|today| today.astronomy.first()) else {
+        return true;
+    };
+    let sunrise = NaiveTime::parse_from_str(&astronomy.sunrise, "%I:%M %p");
+    let sunset = NaiveTime::parse_from_str(&astronomy.sunset, "%I:%M %p");
+
+    return match (observed, sunrise, sunset) {
+        (Ok(observed), Ok(sunrise), Ok(sunset)) => (sunrise..sunset).contains(&observed.time()),
+        _ => true,
+    };
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// responses
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    current_condition: Vec<CurrentCondition>,
+    #[serde(default)]
+    nearest_area: Vec<Area>,
+    weather: Vec<Day>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentCondition {
+    #[serde(rename = "temp_C")]
+    temp_c: String,
+    #[serde(rename = "temp_F")]
+    temp_f: String,
+    #[serde(rename = "FeelsLikeC")]
+    feels_like_c: String,
+    #[serde(rename = "FeelsLikeF")]
+    feels_like_f: String,
+    #[serde(rename = "windspeedKmph")]
+    windspeed_kmph: String,
+    #[serde(rename = "windspeedMiles")]
+    windspeed_miles: String,
+    #[serde(rename = "weatherCode")]
+    weather_code: String,
+    /// like `2024-01-05 10:21 AM` in the location's time zone
+    #[serde(rename = "localObsDateTime", default)]
+    local_obs_date_time: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Area {
+    #[serde(rename = "areaName", default)]
+    area_name: Vec<Value>,
+    #[serde(default)]
+    country: Vec<Value>,
+}
+
+impl Area {
+    fn name(&self) -> Option<String> {
+        let name = &self.area_name.first()?.value;
+
+        return Some(match self.country.first() {
+            Some(country) => format!("{name}, {}", country.value),
+            None => name.clone(),
+        });
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Value {
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Day {
+    date: NaiveDate,
+    #[serde(rename = "mintempC")]
+    mintemp_c: String,
+    #[serde(rename = "mintempF")]
+    mintemp_f: String,
+    #[serde(rename = "maxtempC")]
+    maxtemp_c: String,
+    #[serde(rename = "maxtempF")]
+    maxtemp_f: String,
+    #[serde(default)]
+    astronomy: Vec<Astronomy>,
+    #[serde(default)]
+    hourly: Vec<Hour>,
+}
+
+impl Day {
+    /// the hours are 3 hours apart, the one at noon stands for the whole day
+    fn midday(&self) -> Option<&Hour> {
+        return self
+            .hourly
+            .iter()
+            .find(|hour| hour.time == "1200")
+            .or_else(|| self.hourly.get(self.hourly.len() / 2));
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Astronomy {
+    /// like `08:15 AM`
+    sunrise: String,
+    sunset: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Hour {
+    /// the hour times 100, `1200` is noon
+    time: String,
+    #[serde(rename = "weatherCode")]
+    weather_code: String,
+    #[serde(default)]
+    chanceofrain: String,
+    #[serde(default)]
+    chanceofsnow: String,
+}
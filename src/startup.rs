@@ -0,0 +1,104 @@
+//! the order the parts of the shell start in
+//!
+//! services are iced subscriptions, so they all start at once. the few that
+//! need another part up first, like the weather needing the network, wait
+//! for it with `wait_for_dependencies` before they run. they only wait up to
+//! `DEPENDENCY_TIMEOUT`, so a part that never comes up (like the network
+//! service without networkmanager) doesn't hold the others back forever
+//!
+//! each part tells when it's up with `ready`, which logs how long it took
+//! since the shell started and sets `startup.<name>_ms` in the metrics. the
+//! whole timeline is logged once the wasm runtime loaded its modules
+
+use crate::metrics;
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// what has to be up before a part starts, by the name it gives `ready`
+const DEPENDENCIES: &[(&str, &[&str])] =
+    &[("weather", &["config", "network"]), ("wasm", &["config"])];
+
+/// how long a part waits for its dependencies before starting without them
+const DEPENDENCY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// the parts that are up, with how long after the shell started they were
+static READY: LazyLock<watch::Sender<BTreeMap<&'static str, Duration>>> =
+    LazyLock::new(|| watch::Sender::new(BTreeMap::new()));
+
+/// waits until what `name` depends on is up, or `DEPENDENCY_TIMEOUT` passed
+pub async fn wait_for_dependencies(name: &'static str) {
+    let Some((_, dependencies)) = DEPENDENCIES.iter().find(|(part, _)| *part == name) else {
+        return;
+    };
+
+    let mut ready = READY.subscribe();
+    let waiting = ready.wait_for(|ready| {
+        dependencies
+            .iter()
+            .all(|dependency| ready.contains_key(dependency))
+    });
+
+    match tokio::time::timeout(DEPENDENCY_TIMEOUT, waiting).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(err)) => {
+            log::error!("[startup] could not wait for the dependencies of {name}: {err}");
+        }
+        Err(_) => {
+            let missing = dependencies
+                .iter()
+                .filter(|dependency| !READY.borrow().contains_key(*dependency))
+                .copied()
+                .collect::<Vec<&str>>();
+            log::warn!(
+                "[startup] {name} is starting without {missing:?}, they weren't up after {}s",
+                DEPENDENCY_TIMEOUT.as_secs()
+            );
+        }
+    }
+}
+
+/// marks `name` as up, only the first time counts so a service restarting
+/// later doesn't change its startup time
+pub fn ready(name: &'static str) {
+    let elapsed = metrics::uptime();
+
+    let first = READY.send_if_modified(|ready| {
+        if ready.contains_key(name) {
+            return false;
+        }
+        ready.insert(name, elapsed);
+        true
+    });
+    if !first {
+        return;
+    }
+
+    metrics::set(format!("startup.{name}_ms"), elapsed.as_millis() as u64);
+    log::debug!("[startup] {name} is up after {}ms", elapsed.as_millis());
+
+    if name == "wasm" {
+        log_timeline();
+    }
+}
+
+/// logs when each part that's up so far came up, in order
+fn log_timeline() {
+    let mut ready = READY
+        .borrow()
+        .iter()
+        .map(|(name, elapsed)| (*name, *elapsed))
+        .collect::<Vec<(&str, Duration)>>();
+    ready.sort_by_key(|(_, elapsed)| *elapsed);
+
+    let timeline = ready
+        .iter()
+        .map(|(name, elapsed)| format!("{name} {}ms", elapsed.as_millis()))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    log::info!("[startup] timeline: {timeline}");
+}
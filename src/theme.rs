@@ -1,12 +1,16 @@
-use std::collections::HashMap;
-use std::env;
-use std::path::PathBuf;
+use crate::config;
+use crate::diagnostics::{self, Diagnostic};
+use crate::services::appearance::ColorScheme;
+
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
 
-use config::Config;
 use iced::core::widget::text;
 use iced::overlay::menu;
-use iced::widget::{button, pick_list, slider};
+use iced::widget::{button, container, pick_list, slider};
 use iced::{Background, Color, Radius, Theme, border, color};
+use toml_edit::ImDocument;
 
 // todo: maybe create a default color palette?
 #[allow(dead_code)]
@@ -33,95 +37,251 @@ pub struct Base16Color {
 }
 
 impl Base16Color {
-    pub fn from_config() -> anyhow::Result<Self> {
-        let home = match env::var("HOME") {
-            Ok(v) => v,
-            Err(e) => {
-                log::error!("no environment variable `HOME` or it could not be interpreted");
-                return Err(e.into());
-            }
+    /// the keys of `colors.toml`, in the order of the fields
+    const KEYS: [&'static str; 18] = [
+        "color00",
+        "color01",
+        "color02",
+        "color03",
+        "color04",
+        "color05",
+        "color06",
+        "color07",
+        "color08",
+        "color09",
+        "color10",
+        "color11",
+        "color12",
+        "color13",
+        "color14",
+        "color15",
+        "background",
+        "foreground",
+    ];
+
+    /// `color00` to `color15` by number, the foreground past that
+    pub fn base16(&self, index: u8) -> Color {
+        match index {
+            0 => self.color00,
+            1 => self.color01,
+            2 => self.color02,
+            3 => self.color03,
+            4 => self.color04,
+            5 => self.color05,
+            6 => self.color06,
+            7 => self.color07,
+            8 => self.color08,
+            9 => self.color09,
+            10 => self.color10,
+            11 => self.color11,
+            12 => self.color12,
+            13 => self.color13,
+            14 => self.color14,
+            15 => self.color15,
+            _ => self.foreground,
+        }
+    }
+
+    /// the colors file for `scheme`, `colors-light.toml` or
+    /// `colors-dark.toml` when there is one and `colors.toml` otherwise
+    pub fn path(scheme: ColorScheme) -> anyhow::Result<PathBuf> {
+        let dir = config::config_dir()?;
+
+        let name = match scheme {
+            ColorScheme::Light => "colors-light.toml",
+            ColorScheme::Dark => "colors-dark.toml",
+            ColorScheme::NoPreference => return Ok(dir.join("colors.toml")),
         };
 
-        let mut colors_path = PathBuf::from(home);
-        colors_path.push(".config/aurorashell/colors.toml");
-        let colors_path = match colors_path.to_str() {
-            Some(v) => v,
-            None => {
-                return Err(anyhow::format_err!(
-                    "could not convert {:?} to &str",
-                    colors_path
-                ));
-            }
+        return match dir.join(name) {
+            path if path.exists() => Ok(path),
+            _ => Ok(dir.join("colors.toml")),
         };
+    }
+
+    /// reads the colors file for `scheme`, see `Base16Color::path`
+    ///
+    /// problems in the file are added to the startup report and colors that
+    /// are missing or invalid are left as the default
+    pub fn from_config(scheme: ColorScheme) -> anyhow::Result<Self> {
+        let colors_path = Self::path(scheme)?;
 
-        let colors = match Config::builder()
-            .add_source(config::File::with_name(colors_path))
-            .build()
-        {
+        let source = match fs::read_to_string(&colors_path) {
             Ok(v) => v,
             Err(e) => {
-                log::error!("could not get colors.toml");
+                log::error!("could not get {}", colors_path.display());
                 return Err(e.into());
             }
         };
 
-        let colors = match colors.try_deserialize::<HashMap<String, String>>() {
-            Ok(v) => v,
-            Err(e) => {
-                log::error!("could not parse colors.toml");
-                return Err(e.into());
+        return Ok(Self::from_source(&colors_path, &source));
+    }
+
+    fn from_source(path: &Path, source: &str) -> Self {
+        let mut theme = Self::default();
+
+        let document = match ImDocument::parse(source) {
+            Ok(document) => document,
+            Err(err) => {
+                diagnostics::push(
+                    Diagnostic::new(path, err.message().trim_end()).with_span(source, err.span()),
+                );
+                return theme;
             }
         };
 
-        let get_key = |key: &str| -> anyhow::Result<Color> {
-            let hex_str = match colors.get(key) {
+        for (key, item) in document.as_table().iter() {
+            let span = diagnostics::key_span(&document, &[key]);
+
+            let field = match theme.field_mut(key) {
+                Some(field) => field,
+                None => {
+                    let help = diagnostics::did_you_mean(key, &Self::KEYS)
+                        .map_or("this key is ignored".to_string(), |known| {
+                            format!("did you mean `{known}`?")
+                        });
+
+                    diagnostics::push(
+                        Diagnostic::new(path, format!("unknown color `{key}`"))
+                            .with_span(source, span)
+                            .with_help(Some(help)),
+                    );
+                    continue;
+                }
+            };
+
+            let hex_str = match item.as_str() {
                 Some(v) => v,
-                None => return Err(anyhow::format_err!("could not get color: {}", key)),
+                None => {
+                    diagnostics::push(
+                        Diagnostic::new(
+                            path,
+                            format!(
+                                "expected `{key}` to be a string, found {}",
+                                item.type_name()
+                            ),
+                        )
+                        .with_span(source, item.span())
+                        .with_help(Some(format!(
+                            "write it as a string, like `{key} = \"1d2021\"`"
+                        ))),
+                    );
+                    continue;
+                }
             };
 
-            if hex_str.len() != 6 {
-                return Err(anyhow::format_err!(
-                    "hex color does not have 6 digits: {}",
-                    hex_str
-                ));
-            }
+            match parse_hex(hex_str) {
+                Some(color) => *field = color,
+                None => {
+                    let help = match hex_str.strip_prefix('#') {
+                        Some(stripped) if parse_hex(stripped).is_some() => {
+                            format!("remove the `#`, like `{key} = \"{stripped}\"`")
+                        }
+                        _ => "colors are 6 hex digits, like \"1d2021\"".to_string(),
+                    };
 
-            let hex_color = match u32::from_str_radix(hex_str, 16) {
-                Ok(v) => v,
-                Err(err) => {
-                    return Err(anyhow::format_err!(
-                        "couldn't convert hex string to number: {}",
-                        err
-                    ));
+                    diagnostics::push(
+                        Diagnostic::new(path, format!("`{hex_str}` is not a hex color"))
+                            .with_span(source, item.span())
+                            .with_help(Some(help)),
+                    );
                 }
-            };
+            }
+        }
+
+        let missing = Self::KEYS
+            .iter()
+            .filter(|&&key| !document.as_table().contains_key(key))
+            .map(|key| format!("`{key}`"))
+            .collect::<Vec<String>>();
+        if !missing.is_empty() {
+            diagnostics::push(
+                Diagnostic::new(path, format!("missing colors: {}", missing.join(", "))).with_help(
+                    Some("every color of the base16 palette is needed".to_string()),
+                ),
+            );
+        }
+
+        return theme;
+    }
+
+    /// the palette in the layout modules read it in, little endian
+    ///
+    /// `color00` to `color15`, the background and the foreground as 4 bytes
+    /// each (red, green, blue, alpha), then the size of module text as an f32
+    /// and the shell's font as a u16 length followed by that many bytes of
+    /// utf-8, then the color scheme as a byte (see `ColorScheme`)
+    pub fn serialize(&self, font: &str, text_size: f32, scheme: ColorScheme) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::KEYS.len() * 4 + 7 + font.len());
+        for color in self.colors() {
+            bytes.extend(color.into_rgba8());
+        }
+        bytes.extend(text_size.to_le_bytes());
+
+        let font = &font.as_bytes()[..font.len().min(u16::MAX as usize)];
+        bytes.extend((font.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(font);
+        bytes.push(scheme as u8);
+
+        return bytes;
+    }
+
+    /// changes whenever one of the colors does, so widgets cached with
+    /// `lazy` know to rebuild when the theme is reloaded
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for color in self.colors() {
+            color.into_rgba8().hash(&mut hasher);
+        }
 
-            Ok(color!(hex_color))
+        return hasher.finish();
+    }
+
+    /// every color in the order of `KEYS`
+    fn colors(&self) -> impl Iterator<Item = Color> {
+        return (0..16)
+            .map(|index| self.base16(index))
+            .chain([self.background, self.foreground]);
+    }
+
+    fn field_mut(&mut self, key: &str) -> Option<&mut Color> {
+        let field = match key {
+            "color00" => &mut self.color00,
+            "color01" => &mut self.color01,
+            "color02" => &mut self.color02,
+            "color03" => &mut self.color03,
+            "color04" => &mut self.color04,
+            "color05" => &mut self.color05,
+            "color06" => &mut self.color06,
+            "color07" => &mut self.color07,
+            "color08" => &mut self.color08,
+            "color09" => &mut self.color09,
+            "color10" => &mut self.color10,
+            "color11" => &mut self.color11,
+            "color12" => &mut self.color12,
+            "color13" => &mut self.color13,
+            "color14" => &mut self.color14,
+            "color15" => &mut self.color15,
+            "background" => &mut self.background,
+            "foreground" => &mut self.foreground,
+            _ => return None,
         };
 
-        return Ok(Self {
-            background: get_key("background")?,
-            foreground: get_key("foreground")?,
-            color00: get_key("color00")?,
-            color01: get_key("color01")?,
-            color02: get_key("color02")?,
-            color03: get_key("color03")?,
-            color04: get_key("color04")?,
-            color05: get_key("color05")?,
-            color06: get_key("color06")?,
-            color07: get_key("color07")?,
-            color08: get_key("color08")?,
-            color09: get_key("color09")?,
-            color10: get_key("color10")?,
-            color11: get_key("color11")?,
-            color12: get_key("color12")?,
-            color13: get_key("color13")?,
-            color14: get_key("color14")?,
-            color15: get_key("color15")?,
-        });
+        return Some(field);
     }
 }
 
+/// parses 6 hex digits, like `1d2021`
+fn parse_hex(hex_str: &str) -> Option<Color> {
+    if hex_str.len() != 6 || !hex_str.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let hex_color = u32::from_str_radix(hex_str, 16).ok()?;
+
+    return Some(color!(hex_color));
+}
+
 pub fn text_style(theme: &Base16Color) -> text::StyleFn<'_, Theme> {
     return Box::new(|_: &Theme| text::Style {
         color: Some(theme.foreground),
@@ -178,3 +338,117 @@ pub fn volume_button_style(theme: &Base16Color) -> button::StyleFn<'_, Theme> {
         ..button::Style::default()
     });
 }
+
+pub fn bar_style(theme: &Base16Color) -> container::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| container::Style {
+        background: Some(Background::Color(theme.background)),
+        text_color: Some(theme.foreground),
+        ..container::Style::default()
+    });
+}
+
+pub fn popup_style(theme: &Base16Color) -> container::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| container::Style {
+        background: Some(Background::Color(theme.background)),
+        text_color: Some(theme.foreground),
+        border: border::width(1).rounded(8).color(theme.color01),
+        ..container::Style::default()
+    });
+}
+
+/// the background of a group of widgets in the bar
+pub fn pill_style(theme: &Base16Color) -> container::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| container::Style {
+        background: Some(Background::Color(theme.color01)),
+        text_color: Some(theme.foreground),
+        border: border::rounded(128),
+        ..container::Style::default()
+    });
+}
+
+pub fn separator_style(theme: &Base16Color) -> text::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| text::Style {
+        color: Some(theme.color03),
+    });
+}
+
+pub fn bar_button_style(theme: &Base16Color) -> button::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme, status: button::Status| button::Style {
+        background: match status {
+            button::Status::Hovered | button::Status::Pressed => {
+                Some(Background::Color(theme.color01))
+            }
+            _ => None,
+        },
+        text_color: theme.foreground,
+        border_radius: Radius::new(4),
+        ..button::Style::default()
+    });
+}
+
+pub fn selected_button_style(theme: &Base16Color) -> button::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme, _status: button::Status| button::Style {
+        background: Some(Background::Color(theme.color13)),
+        text_color: theme.background,
+        border_radius: Radius::new(4),
+        ..button::Style::default()
+    });
+}
+
+/// the background behind a `slider_style` slider
+pub fn slider_rail_style(theme: &Base16Color) -> container::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| container::Style {
+        background: Some(Background::Color(theme.color01)),
+        border: border::width(0).rounded(128),
+        ..container::Style::default()
+    });
+}
+
+pub fn calendar_day_style(theme: &Base16Color) -> container::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| container::Style {
+        text_color: Some(theme.foreground),
+        ..container::Style::default()
+    });
+}
+
+pub fn calendar_other_month_style(theme: &Base16Color) -> container::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| container::Style {
+        text_color: Some(theme.color03),
+        ..container::Style::default()
+    });
+}
+
+pub fn calendar_week_style(theme: &Base16Color) -> container::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| container::Style {
+        text_color: Some(theme.color13),
+        ..container::Style::default()
+    });
+}
+
+pub fn calendar_today_style(theme: &Base16Color) -> container::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| container::Style {
+        background: Some(Background::Color(theme.color13)),
+        text_color: Some(theme.background),
+        border: border::rounded(4),
+        ..container::Style::default()
+    });
+}
+
+/// the dimmed screen around a region being selected
+pub fn region_dim_style(theme: &Base16Color) -> container::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| container::Style {
+        background: Some(Background::Color(Color {
+            a: 0.5,
+            ..theme.background
+        })),
+        ..container::Style::default()
+    });
+}
+
+/// the outline of the region being selected, the inside is left clear
+pub fn region_selection_style(theme: &Base16Color) -> container::StyleFn<'_, Theme> {
+    return Box::new(|_: &Theme| container::Style {
+        border: border::width(1).color(theme.color13),
+        ..container::Style::default()
+    });
+}
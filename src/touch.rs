@@ -0,0 +1,257 @@
+//! touch gestures iced's own widgets don't recognise
+//!
+//! taps and drags already work, buttons and mouse areas are pressed by a
+//! finger like they are by a click and sliders follow a finger that moves, but
+//! nothing tells a long press apart from a tap
+
+use std::time::{Duration, Instant};
+
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::widget::{Operation, Tree, tree};
+use iced::advanced::{Clipboard, Shell, Widget, overlay, renderer};
+use iced::event::{self, Event};
+use iced::window::RedrawRequest;
+use iced::{Element, Length, Point, Rectangle, Size, Vector, mouse, touch, window};
+
+/// how long a finger has to stay down for a press to be a long press
+const LONG_PRESS_DURATION: Duration = Duration::from_millis(500);
+/// how far a finger can move before the press is taken as a drag instead
+const LONG_PRESS_SLOP: f32 = 10.0;
+
+/// sends `on_long_press` when a finger is held on `content`
+///
+/// once a long press is sent, the content is told the finger was lost
+/// instead of lifted, so a button underneath doesn't also see a tap
+pub struct LongPress<'a, Message, Theme, Renderer> {
+    content: Element<'a, Message, Theme, Renderer>,
+    on_long_press: Option<Message>,
+}
+
+impl<'a, Message, Theme, Renderer> LongPress<'a, Message, Theme, Renderer> {
+    pub fn new(content: impl Into<Element<'a, Message, Theme, Renderer>>) -> Self {
+        Self {
+            content: content.into(),
+            on_long_press: None,
+        }
+    }
+
+    pub fn on_long_press(mut self, message: Message) -> Self {
+        self.on_long_press = Some(message);
+        return self;
+    }
+}
+
+pub fn long_press<'a, Message, Theme, Renderer>(
+    content: impl Into<Element<'a, Message, Theme, Renderer>>,
+) -> LongPress<'a, Message, Theme, Renderer> {
+    LongPress::new(content)
+}
+
+#[derive(Debug, Default)]
+struct State {
+    press: Option<Press>,
+}
+
+#[derive(Debug)]
+struct Press {
+    finger: touch::Finger,
+    position: Point,
+    started: Instant,
+    /// the long press was already sent for this finger
+    sent: bool,
+}
+
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer>
+    for LongPress<'_, Message, Theme, Renderer>
+where
+    Message: Clone,
+    Renderer: renderer::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&mut self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_mut(&mut self.content));
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        let mut event = event;
+
+        match &event {
+            Event::Touch(touch::Event::FingerPressed { id, position })
+                if layout.bounds().contains(*position) =>
+            {
+                let started = Instant::now();
+                state.press = Some(Press {
+                    finger: *id,
+                    position: *position,
+                    started,
+                    sent: false,
+                });
+                shell.request_redraw(RedrawRequest::At(started + LONG_PRESS_DURATION));
+            }
+            Event::Touch(touch::Event::FingerMoved { id, position }) => {
+                // a finger that moves is dragging, like along a slider
+                if let Some(press) = &state.press
+                    && press.finger == *id
+                    && !press.sent
+                    && press.position.distance(*position) > LONG_PRESS_SLOP
+                {
+                    state.press = None;
+                }
+            }
+            Event::Touch(
+                touch::Event::FingerLifted { id, position }
+                | touch::Event::FingerLost { id, position },
+            ) => {
+                if let Some(press) = &state.press
+                    && press.finger == *id
+                {
+                    if press.sent {
+                        event = Event::Touch(touch::Event::FingerLost {
+                            id: *id,
+                            position: *position,
+                        });
+                    }
+                    state.press = None;
+                }
+            }
+            Event::Window(window::Event::RedrawRequested(now)) => {
+                if let Some(press) = &mut state.press
+                    && !press.sent
+                {
+                    if now.duration_since(press.started) >= LONG_PRESS_DURATION {
+                        press.sent = true;
+                        if let Some(message) = &self.on_long_press {
+                            shell.publish(message.clone());
+                        }
+                    } else {
+                        shell
+                            .request_redraw(RedrawRequest::At(press.started + LONG_PRESS_DURATION));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        return self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        );
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer, translation)
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<LongPress<'a, Message, Theme, Renderer>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Message: Clone + 'a,
+    Theme: 'a,
+    Renderer: renderer::Renderer + 'a,
+{
+    fn from(long_press: LongPress<'a, Message, Theme, Renderer>) -> Self {
+        Element::new(long_press)
+    }
+}
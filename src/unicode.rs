@@ -0,0 +1,170 @@
+//! a searchable table of emoji and named unicode characters, kept by the
+//! shell so picker modules don't each carry a copy of it in their memory
+//!
+//! the table is built the first time it's searched, emoji come first in the
+//! order of their groups, then every other character with a name
+
+use std::sync::LazyLock;
+
+/// the most results one search hands out, the rest are reached with the
+/// offset
+pub const MAX_RESULTS: usize = 1000;
+
+static TABLE: LazyLock<Vec<Entry>> = LazyLock::new(build);
+
+/// a character or emoji that can be searched for
+#[derive(Debug)]
+pub struct Entry {
+    /// the character itself, emoji can be more than one
+    pub text: String,
+    /// like `grinning face` or `LATIN SMALL LETTER A`
+    pub name: String,
+    /// 1 to 10 for emoji, see `group_index`, 0 for other characters
+    pub group: u8,
+    /// the name and any shortcodes, lowercase
+    keywords: String,
+}
+
+/// which part of the table to search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Emoji,
+    /// emoji and every other named character
+    All,
+}
+
+impl TryFrom<u32> for Kind {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Emoji),
+            1 => Ok(Self::All),
+            value => Err(value),
+        }
+    }
+}
+
+/// the entries where every word of `query` is in the name or a shortcode,
+/// skipping the first `offset` of them. an empty query matches everything
+///
+/// returns how many entries matched in total along with up to `limit` of
+/// them
+pub fn search(
+    query: &str,
+    kind: Kind,
+    offset: usize,
+    limit: usize,
+) -> (usize, Vec<&'static Entry>) {
+    let query = query.to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    let mut total = 0;
+    let mut found = vec![];
+
+    let entries = TABLE
+        .iter()
+        .take_while(|entry| kind == Kind::All || entry.group != 0)
+        .filter(|entry| words.iter().all(|word| entry.keywords.contains(word)));
+
+    for entry in entries {
+        if total >= offset && found.len() < limit.min(MAX_RESULTS) {
+            found.push(entry);
+        }
+        total += 1;
+    }
+
+    return (total, found);
+}
+
+/// the results of `search` for a module
+///
+/// strings are a u16 length followed by that many bytes of utf-8
+///
+/// - u32 amount of matches in total, then u16 amount of entries
+/// - each entry is its text and name, then the u8 group
+pub fn serialize(total: usize, entries: &[&Entry]) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.extend_from_slice(&(total as u32).to_le_bytes());
+    bytes.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+
+    for entry in entries {
+        push_string(&mut bytes, &entry.text);
+        push_string(&mut bytes, &entry.name);
+        bytes.push(entry.group);
+    }
+
+    return bytes;
+}
+
+fn push_string(bytes: &mut Vec<u8>, string: &str) {
+    let len = string.len().min(u16::MAX as usize);
+    bytes.extend_from_slice(&(len as u16).to_le_bytes());
+    bytes.extend_from_slice(&string.as_bytes()[..len]);
+}
+
+fn build() -> Vec<Entry> {
+    let mut table: Vec<Entry> = emojis::iter()
+        .map(|emoji| {
+            let mut keywords = emoji.name().to_lowercase();
+            for shortcode in emoji.shortcodes() {
+                keywords.push(' ');
+                keywords.push_str(shortcode);
+            }
+
+            Entry {
+                text: emoji.as_str().to_string(),
+                name: emoji.name().to_string(),
+                group: group_index(emoji.group()),
+                keywords,
+            }
+        })
+        .collect();
+    let emoji_count = table.len();
+
+    let characters = (0..=char::MAX as u32)
+        .filter_map(char::from_u32)
+        // control characters have no use in a picker
+        .filter(|character| !character.is_control())
+        .filter_map(|character| {
+            let name = unicode_names2::name(character)?.to_string();
+            let text = character.to_string();
+
+            // emoji with a single code point are already in the table
+            if emojis::get(&text).is_some() {
+                return None;
+            }
+
+            Some(Entry {
+                keywords: name.to_lowercase(),
+                text,
+                name,
+                group: 0,
+            })
+        });
+    table.extend(characters);
+
+    log::debug!(
+        "[unicode] built the table with {} emoji and {} characters",
+        emoji_count,
+        table.len() - emoji_count
+    );
+
+    return table;
+}
+
+/// the group as modules know it, in the order emoji pickers show them
+fn group_index(group: emojis::Group) -> u8 {
+    match group {
+        emojis::Group::SmileysAndEmotion => 1,
+        emojis::Group::PeopleAndBody => 2,
+        emojis::Group::Component => 3,
+        emojis::Group::AnimalsAndNature => 4,
+        emojis::Group::FoodAndDrink => 5,
+        emojis::Group::TravelAndPlaces => 6,
+        emojis::Group::Activities => 7,
+        emojis::Group::Objects => 8,
+        emojis::Group::Symbols => 9,
+        emojis::Group::Flags => 10,
+    }
+}
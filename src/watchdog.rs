@@ -0,0 +1,161 @@
+//! notices when a loop stops making progress while it's handling a message,
+//! like a module stuck in a call or a channel that deadlocked
+//!
+//! only the wasm runtime and the audio service have a heartbeat so far, the
+//! other services aren't watched. loops mark themselves busy with
+//! `Heartbeat::busy` while handling a message and their supervisor restarts
+//! them when `Heartbeat::tripped` resolves
+//!
+//! note: a module spinning inside a call never yields back to the async
+//! runtime, so its supervisor can't be polled to restart it. the trip is still
+//! logged from the monitor task in that case
+
+use crate::{config, metrics};
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    inner: Arc<HeartbeatInner>,
+}
+
+#[derive(Debug)]
+struct HeartbeatInner {
+    /// used in logs, like `wasm` or `service:audio`
+    name: &'static str,
+    /// how long the loop can be busy for before the watchdog trips
+    threshold: Duration,
+    /// when the heartbeat was created, beats are stored relative to this
+    start: Instant,
+    /// milliseconds since `start` of the last time the loop made progress
+    last_beat: AtomicU64,
+    /// true while the loop is handling a message
+    busy: AtomicBool,
+    /// true once the watchdog has tripped for the current busy period so it
+    /// only trips once
+    tripped: AtomicBool,
+    /// description of what the loop is handling
+    last_message: Mutex<String>,
+    notify: Notify,
+}
+
+impl Heartbeat {
+    /// creates a heartbeat and starts a task that monitors it
+    ///
+    /// the threshold is from the config, the task stops when every clone of
+    /// the heartbeat is dropped
+    pub fn spawn(name: &'static str) -> Self {
+        let watchdog_config = &config::get().watchdog;
+
+        let heartbeat = Self {
+            inner: Arc::new(HeartbeatInner {
+                name,
+                threshold: Duration::from_millis(watchdog_config.threshold_ms),
+                start: Instant::now(),
+                last_beat: AtomicU64::new(0),
+                busy: AtomicBool::new(false),
+                tripped: AtomicBool::new(false),
+                last_message: Mutex::new(String::new()),
+                notify: Notify::new(),
+            }),
+        };
+
+        if watchdog_config.enabled {
+            tokio::spawn(Self::monitor(Arc::downgrade(&heartbeat.inner)));
+        }
+
+        return heartbeat;
+    }
+
+    /// marks the loop as busy until the returned guard is dropped
+    ///
+    /// `message` should describe what's being handled so it can be logged
+    /// if the loop gets stuck
+    pub fn busy(&self, message: impl FnOnce() -> String) -> BusyGuard<'_> {
+        if let Ok(mut last_message) = self.inner.last_message.lock() {
+            *last_message = message();
+        }
+
+        self.beat();
+        self.inner.tripped.store(false, Ordering::Release);
+        self.inner.busy.store(true, Ordering::Release);
+
+        return BusyGuard { heartbeat: self };
+    }
+
+    /// records that the loop made progress
+    pub fn beat(&self) {
+        let now = self.inner.start.elapsed().as_millis() as u64;
+        self.inner.last_beat.store(now, Ordering::Release);
+    }
+
+    /// resolves when the watchdog trips, the loop should be restarted
+    pub async fn tripped(&self) {
+        self.inner.notify.notified().await;
+    }
+
+    async fn monitor(inner: Weak<HeartbeatInner>) {
+        let period = match inner.upgrade() {
+            Some(inner) => (inner.threshold / 4).max(Duration::from_millis(100)),
+            None => return,
+        };
+
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            interval.tick().await;
+
+            let inner = match inner.upgrade() {
+                Some(inner) => inner,
+                None => return,
+            };
+
+            if !inner.busy.load(Ordering::Acquire) || inner.tripped.load(Ordering::Acquire) {
+                continue;
+            }
+
+            let now = inner.start.elapsed().as_millis() as u64;
+            let stalled_for =
+                Duration::from_millis(now.saturating_sub(inner.last_beat.load(Ordering::Acquire)));
+
+            if stalled_for < inner.threshold {
+                continue;
+            }
+
+            inner.tripped.store(true, Ordering::Release);
+            metrics::increment("watchdog.trips");
+
+            let last_message = match inner.last_message.lock() {
+                Ok(message) => message.clone(),
+                Err(_) => "<unknown>".to_string(),
+            };
+
+            log::error!(
+                "[watchdog] [{}] no progress for {:?} while handling: {}",
+                inner.name,
+                stalled_for,
+                last_message
+            );
+            log::error!("[watchdog] [{}] restarting", inner.name);
+
+            // stores a permit if the supervisor isn't waiting yet
+            inner.notify.notify_one();
+        }
+    }
+}
+
+/// marks the loop as idle when dropped
+pub struct BusyGuard<'a> {
+    heartbeat: &'a Heartbeat,
+}
+
+impl Drop for BusyGuard<'_> {
+    fn drop(&mut self) {
+        self.heartbeat.beat();
+        self.heartbeat.inner.busy.store(false, Ordering::Release);
+    }
+}